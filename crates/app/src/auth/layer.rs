@@ -1,12 +1,17 @@
 use crate::auth::{AuthSession, AuthenticatedUser};
+use crate::model::user::UserRepo;
 use axum::{body::Body, http::Request, response::Response, Extension, RequestExt};
 use futures::future::BoxFuture;
+use sea_orm::DatabaseConnection;
 use std::task::{Context, Poll};
 use tower::{Layer, Service};
 use tower_sessions::Session;
+use tracing::{error, info};
 
 #[derive(Clone)]
-struct AuthState {}
+struct AuthState {
+    db: DatabaseConnection,
+}
 
 #[derive(Clone)]
 pub struct AuthLayer {
@@ -14,18 +19,12 @@ pub struct AuthLayer {
 }
 
 impl AuthLayer {
-    pub fn new() -> Self {
-        let state = AuthState {};
+    pub fn new(db: DatabaseConnection) -> Self {
+        let state = AuthState { db };
         Self { state }
     }
 }
 
-impl Default for AuthLayer {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl<S> Layer<S> for AuthLayer {
     type Service = AuthService<S>;
 
@@ -57,7 +56,7 @@ where
     }
 
     fn call(&mut self, mut request: Request<Body>) -> Self::Future {
-        let _state = self.state.clone();
+        let state = self.state.clone();
         let inner = self.inner.clone();
 
         let mut inner = std::mem::replace(&mut self.inner, inner);
@@ -71,6 +70,10 @@ where
                 .get::<AuthenticatedUser>("authenticated_user")
                 .await
                 .unwrap_or(None);
+            let user = match user {
+                Some(user) => active_user(&state.db, &session, user).await,
+                None => None,
+            };
 
             let auth_session = AuthSession::new(session, user.clone());
             request.extensions_mut().insert(auth_session);
@@ -78,3 +81,28 @@ where
         })
     }
 }
+
+/// Checks the user of a session against the database, so that deactivating or deleting a user
+/// ends their sessions. Ended sessions are removed from the store. Returns the user with its
+/// current administrator flag. When the database cannot be reached, the user of the session is
+/// kept as it is, so that a brief failure does not log out everyone.
+async fn active_user(
+    db: &DatabaseConnection,
+    session: &Session,
+    user: AuthenticatedUser,
+) -> Option<AuthenticatedUser> {
+    match UserRepo::get_active(db, user.id).await {
+        Ok(Some(active)) => Some(AuthenticatedUser::new(active)),
+        Ok(None) => {
+            info!("Ending session of inactive user {}", user.username);
+            if let Err(e) = session.flush().await {
+                error!("Failed to flush session: {:?}", e);
+            }
+            None
+        }
+        Err(e) => {
+            error!("Failed to check user of session: {:?}", e);
+            Some(user)
+        }
+    }
+}
@@ -10,6 +10,7 @@ use webauthn_rs_proto::{
 };
 
 use super::error::AuthError;
+use crate::prefix::prefixed;
 
 pub async fn login_passkey(username: String) -> Result<(), AuthError> {
     let req_challenge_resp = login_begin(username).await?;
@@ -23,7 +24,7 @@ async fn login_begin(username: String) -> Result<RequestChallengeResponse, AuthE
     opts.method("POST");
     opts.mode(RequestMode::SameOrigin);
 
-    let dest = format!("/auth/authenticate_start/{username}");
+    let dest = prefixed(&format!("/auth/authenticate_start/{username}"));
     let request = Request::new_with_str_and_init(&dest, &opts)?;
 
     request.headers().set("content-type", "application/json")?;
@@ -71,7 +72,7 @@ async fn login_complete(pub_key_cred: PublicKeyCredential) -> Result<(), AuthErr
     opts.mode(RequestMode::SameOrigin);
     opts.body(Some(&req_jsvalue));
 
-    let request = Request::new_with_str_and_init("/auth/authenticate_finish", &opts)?;
+    let request = Request::new_with_str_and_init(&prefixed("/auth/authenticate_finish"), &opts)?;
     request.headers().set("content-type", "application/json")?;
 
     let resp_value = JsFuture::from(window().fetch_with_request(&request)).await?;
@@ -100,7 +101,7 @@ async fn register_begin(username: String) -> Result<CreationChallengeResponse, A
     opts.method("POST");
     opts.mode(RequestMode::SameOrigin);
 
-    let dest = format!("/auth/register_start/{username}");
+    let dest = prefixed(&format!("/auth/register_start/{username}"));
     let request = Request::new_with_str_and_init(&dest, &opts)?;
 
     request.headers().set("content-type", "application/json")?;
@@ -149,7 +150,7 @@ async fn register_complete(reg_pub_key_cred: RegisterPublicKeyCredential) -> Res
     opts.mode(RequestMode::SameOrigin);
     opts.body(Some(&req_jsvalue));
 
-    let request = Request::new_with_str_and_init("/auth/register_finish", &opts)?;
+    let request = Request::new_with_str_and_init(&prefixed("/auth/register_finish"), &opts)?;
     request.headers().set("content-type", "application/json")?;
 
     let resp_value = JsFuture::from(window().fetch_with_request(&request)).await?;
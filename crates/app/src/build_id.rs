@@ -0,0 +1,115 @@
+//! Normalization of the identifiers used to match symbol files with the modules of a crash.
+//!
+//! Symbol files are stored and looked up by `(debug_file, debug_id)`, using the Breakpad
+//! conventions: the debug file is a plain file name and the debug ID is an uppercase hex GUID
+//! (or Mach-O UUID) followed by the age in hex, e.g. `3F2504E04F8911D39A0C0305E82C33011`.
+//! Clients and tools report the same IDs with dashes, braces, lowercase digits or padded ages,
+//! and ELF modules only have a raw build ID.
+
+/// Normalizes a debug ID given as a GUID/UUID, with or without dashes and braces, optionally
+/// followed by an age. A missing age is taken to be zero. Returns `None` if the value is not a
+/// debug ID.
+pub fn normalize_debug_id(id: &str) -> Option<String> {
+    let id = id.trim();
+    let id = id
+        .strip_prefix('{')
+        .map(|id| id.replacen('}', "", 1))
+        .unwrap_or_else(|| id.to_owned())
+        .replace('-', "");
+
+    if !(32..=40).contains(&id.len()) || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let (guid, age) = id.split_at(32);
+    let age = if age.is_empty() {
+        0
+    } else {
+        u32::from_str_radix(age, 16).ok()?
+    };
+    Some(format!("{}{:X}", guid.to_ascii_uppercase(), age))
+}
+
+/// Converts an ELF build ID (GNU build-id note, in hex) to the debug ID Breakpad uses for the
+/// module: the first 16 bytes interpreted as a little-endian GUID, with age zero.
+pub fn debug_id_from_elf_build_id(build_id: &str) -> Option<String> {
+    let build_id = build_id.trim();
+    if build_id.is_empty() || build_id.len() % 2 != 0 {
+        return None;
+    }
+    let mut bytes = (0..build_id.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(build_id.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    bytes.resize(16, 0);
+
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    let guid: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    Some(format!("{}0", guid))
+}
+
+/// Normalizes a debug file to its file name; crash reports may include the full path of the
+/// module while symbol files only list the name.
+pub fn normalize_debug_file(file: &str) -> String {
+    file.trim()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_debug_id() {
+        let expected = Some("3F2504E04F8911D39A0C0305E82C33011".to_owned());
+        assert_eq!(
+            normalize_debug_id("3F2504E04F8911D39A0C0305E82C33011"),
+            expected
+        );
+        assert_eq!(
+            normalize_debug_id("3f2504e0-4f89-11d3-9a0c-0305e82c3301-1"),
+            expected
+        );
+        assert_eq!(
+            normalize_debug_id("3F2504E04F8911D39A0C0305E82C330100000001"),
+            expected
+        );
+        assert_eq!(
+            normalize_debug_id("{3F2504E0-4F89-11D3-9A0C-0305E82C3301}"),
+            Some("3F2504E04F8911D39A0C0305E82C33010".to_owned())
+        );
+        assert_eq!(normalize_debug_id("not a debug id"), None);
+        assert_eq!(normalize_debug_id("3F2504E0"), None);
+    }
+
+    #[test]
+    fn test_debug_id_from_elf_build_id() {
+        assert_eq!(
+            debug_id_from_elf_build_id("b4b3dbd9d0f47a6c4c1ab8d8d2c2a4f9f6b0e1a2"),
+            Some("D9DBB3B4F4D06C7A4C1AB8D8D2C2A4F90".to_owned())
+        );
+        assert_eq!(
+            debug_id_from_elf_build_id("0102030405"),
+            Some("040302010005000000000000000000000".to_owned())
+        );
+        assert_eq!(debug_id_from_elf_build_id("xyz"), None);
+    }
+
+    #[test]
+    fn test_normalize_debug_file() {
+        assert_eq!(normalize_debug_file("workrave.pdb"), "workrave.pdb");
+        assert_eq!(
+            normalize_debug_file("C:\\build\\out\\workrave.pdb"),
+            "workrave.pdb"
+        );
+        assert_eq!(
+            normalize_debug_file("/usr/lib/libgtk-3.so.0"),
+            "libgtk-3.so.0"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use leptos::*;
+
+use crate::data_providers::branding::{branding_update, Branding};
+use crate::BrandingResource;
+
+#[allow(non_snake_case)]
+#[component]
+pub fn BrandingPage() -> impl IntoView {
+    let branding = expect_context::<BrandingResource>();
+    let title = create_rw_signal(String::new());
+    let logo_url = create_rw_signal(String::new());
+    let accent_color = create_rw_signal(String::new());
+
+    create_effect(move |_| {
+        if let Some(branding) = branding.get() {
+            title.set(branding.title);
+            logo_url.set(branding.logo_url.unwrap_or_default());
+            accent_color.set(branding.accent_color.unwrap_or_default());
+        }
+    });
+
+    let save = create_action(move |branding: &Branding| {
+        let branding = branding.clone();
+        async move {
+            branding_update(
+                branding.title,
+                branding.logo_url.unwrap_or_default(),
+                branding.accent_color.unwrap_or_default(),
+            )
+            .await
+        }
+    });
+    create_effect(move |_| {
+        if let Some(Ok(())) = save.value().get() {
+            branding.refetch();
+        }
+    });
+
+    let result = move || {
+        save.value().get().map(|result| match result {
+            Ok(()) => view! { <div class="alert alert-success rounded-btn my-2 p-3">"Saved"</div> },
+            Err(e) => {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            }
+        })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Branding"</h2>
+                <p class="text-sm">
+                    "The title, logo and accent color shown to all users of this server. "
+                    "Leave a field empty to use the default."
+                </p>
+                <div class="form-control w-full max-w-md">
+                    <label class="label">
+                        <span class="label-text">"Title"</span>
+                    </label>
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="Guardrail"
+                        prop:value=move || title.get()
+                        on:input=move |ev| title.set(event_target_value(&ev))
+                    />
+                    <label class="label">
+                        <span class="label-text">"Logo URL"</span>
+                    </label>
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="https://example.com/logo.svg"
+                        prop:value=move || logo_url.get()
+                        on:input=move |ev| logo_url.set(event_target_value(&ev))
+                    />
+                    <label class="label">
+                        <span class="label-text">"Accent color"</span>
+                    </label>
+                    <div class="flex items-center gap-2">
+                        <input
+                            type="text"
+                            class="input input-bordered input-sm flex-1"
+                            placeholder="#1e88e5"
+                            prop:value=move || accent_color.get()
+                            on:input=move |ev| accent_color.set(event_target_value(&ev))
+                        />
+                        <input
+                            type="color"
+                            class="h-8 w-8"
+                            prop:value=move || accent_color.get()
+                            on:input=move |ev| accent_color.set(event_target_value(&ev))
+                        />
+                    </div>
+                </div>
+                <div class="my-2">
+                    <button
+                        class="btn btn-sm"
+                        disabled=move || save.pending().get()
+                        on:click=move |_| {
+                            save.dispatch(Branding {
+                                title: title.get(),
+                                logo_url: Some(logo_url.get()),
+                                accent_color: Some(accent_color.get()),
+                            });
+                        }
+                    >
+                        "Save"
+                    </button>
+                </div>
+                {result}
+            </div>
+        </div>
+    }
+}
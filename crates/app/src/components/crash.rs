@@ -1,37 +1,721 @@
 use leptos::*;
 use leptos_router::*;
 
-use crate::components::datatable_form::Fields;
+use crate::authenticated_user_is_admin;
+use crate::data_providers::crash::{
+    crash_annotation_diff, crash_get, crash_links, crash_pdf_request, crash_raw_report,
+    crash_report_details, crash_report_unredacted, crash_set_legal_hold, crash_stack_text,
+    crash_title, AnnotationDiff, BuildInfo,
+};
+use crate::prefix::prefixed;
+use crate::report::DEFAULT_STACK_FRAMES;
+
+// The clipboard is reached through an inline handler, which avoids pulling in web-sys for a
+// single call.
+const COPY_STACK: &str =
+    "navigator.clipboard.writeText(document.getElementById('crash-stack').innerText)";
 
 #[allow(non_snake_case)]
 #[component]
 pub fn Crash() -> impl IntoView {
     let query_map = use_query_map();
 
-    let q = query_map.get_untracked();
-    let q = q.get("crash").unwrap();
-    let _uuid = uuid::Uuid::parse_str(q).unwrap();
+    let crash_id =
+        move || query_map.with(|q| q.get("crash").and_then(|id| uuid::Uuid::parse_str(id).ok()));
+
+    let links = create_resource(crash_id, |id| async move {
+        match id {
+            Some(id) => crash_links(id).await.unwrap_or_default(),
+            None => vec![],
+        }
+    });
+
+    let crash = create_resource(crash_id, |id| async move {
+        match id {
+            Some(id) => crash_get(id).await.ok(),
+            None => None,
+        }
+    });
+
+    let title = create_resource(crash_id, |id| async move {
+        match id {
+            Some(id) => crash_title(id).await.ok().flatten(),
+            None => None,
+        }
+    });
+
+    let diff = create_resource(crash_id, |id| async move {
+        match id {
+            Some(id) => crash_annotation_diff(id).await.unwrap_or_default(),
+            None => AnnotationDiff::default(),
+        }
+    });
+
+    let (with_modules, set_with_modules) = create_signal(false);
+    let stack = create_resource(
+        move || (crash_id(), with_modules.get()),
+        |(id, modules)| async move {
+            match id {
+                Some(id) => crash_stack_text(id, DEFAULT_STACK_FRAMES, modules)
+                    .await
+                    .unwrap_or_default(),
+                None => String::new(),
+            }
+        },
+    );
+
+    let request_pdf = create_action(|id: &uuid::Uuid| {
+        let id = *id;
+        async move { crash_pdf_request(id).await }
+    });
+
+    let report = move || {
+        crash_id().map(|id| {
+            let download = prefixed(&format!("/download/crash/{}/pdf", id));
+            let status = move || {
+                request_pdf.value().get().map(|result| match result {
+                    Ok(true) => "The report is ready".to_owned(),
+                    Ok(false) => {
+                        "The report is being generated, download it in a moment".to_owned()
+                    }
+                    Err(e) => e.to_string(),
+                })
+            };
+            view! {
+                <div class="card bg-base-200 rounded-lg my-2">
+                    <div class="card-body p-4">
+                        <h2 class="card-title">"Report"</h2>
+                        <div class="flex items-center gap-2">
+                            <button
+                                class="btn btn-primary btn-sm"
+                                disabled=move || request_pdf.pending().get()
+                                on:click=move |_| request_pdf.dispatch(id)
+                            >
+                                "Generate PDF"
+                            </button>
+                            <a class="btn btn-sm" href=download>
+                                "Download PDF"
+                            </a>
+                            <span class="text-sm">{status}</span>
+                        </div>
+                    </div>
+                </div>
+            }
+        })
+    };
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                title
+                    .get()
+                    .flatten()
+                    .map(|title| view! { <h1 class="text-xl font-bold my-2">{title}</h1> })
+            }}
+            {move || {
+                crash
+                    .get()
+                    .flatten()
+                    .filter(|crash| crash.processing_status != "done")
+                    .map(|crash| {
+                        let (class, message) = match crash.processing_status.as_str() {
+                            "failed" => ("alert-error", "Processing the minidump failed"),
+                            "processing" => ("alert-info", "The minidump is being processed"),
+                            _ => ("alert-warning", "The minidump has not been processed yet"),
+                        };
+                        view! {
+                            <div class=format!("alert {} rounded-btn my-2 p-3", class)>
+                                {message}
+                                {crash.processing_error.map(|error| format!(": {}", error))}
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+        {report}
+        <Transition fallback=move || view! { <p>"Loading..."</p> }>
+            {move || {
+                stack
+                    .get()
+                    .filter(|stack| !stack.is_empty())
+                    .map(|stack| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <div class="flex items-center gap-2">
+                                        <h2 class="card-title">"Stack"</h2>
+                                        <label class="label cursor-pointer gap-2 ml-auto">
+                                            <span class="label-text">"Modules"</span>
+                                            <input
+                                                type="checkbox"
+                                                class="checkbox checkbox-sm"
+                                                prop:checked=with_modules
+                                                on:change=move |ev| {
+                                                    set_with_modules.set(event_target_checked(&ev))
+                                                }
+                                            />
+                                        </label>
+                                        <button class="btn btn-sm" onclick=COPY_STACK>
+                                            "Copy"
+                                        </button>
+                                    </div>
+                                    <pre id="crash-stack" class="text-xs overflow-x-auto">
+                                        {stack}
+                                    </pre>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+            {move || {
+                crash
+                    .get()
+                    .flatten()
+                    .filter(|crash| {
+                        crash.user_agent.is_some()
+                            || crash.sdk.is_some()
+                            || crash.platform.is_some()
+                            || crash.environment.is_some()
+                            || !crash.authenticated
+                            || crash.verified_checksum.is_some()
+                            || crash.clock_skew.is_some()
+                    })
+                    .map(|crash| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Client"</h2>
+                                    <table class="table table-sm">
+                                        <tbody>
+                                            <tr>
+                                                <th>"Platform"</th>
+                                                <td>{crash.platform.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Environment"</th>
+                                                <td>{crash.environment.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Submission"</th>
+                                                <td>
+                                                    {if crash.authenticated {
+                                                        "Authenticated"
+                                                    } else {
+                                                        "Public (unauthenticated)"
+                                                    }}
+                                                </td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Checksum"</th>
+                                                <td class="font-mono">
+                                                    {crash
+                                                        .verified_checksum
+                                                        .map(|checksum| format!("SHA-256 {} (verified)", checksum))
+                                                        .unwrap_or_default()}
+                                                </td>
+                                            </tr>
+                                            <tr>
+                                                <th>"SDK"</th>
+                                                <td>{crash.sdk.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"User agent"</th>
+                                                <td>{crash.user_agent.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Clock skew"</th>
+                                                <td>
+                                                    {crash
+                                                        .clock_skew
+                                                        .map(|skew| format!("{:+} s", skew))
+                                                        .unwrap_or_default()}
+                                                </td>
+                                            </tr>
+                                        </tbody>
+                                    </table>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+            {move || {
+                diff.get()
+                    .filter(|diff| diff.crashes > 1 && !diff.annotations.is_empty())
+                    .map(|diff| view! { <AnnotationDiffCard diff/> })
+            }}
+            {move || {
+                links
+                    .get()
+                    .filter(|links| !links.is_empty())
+                    .map(|links| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Links"</h2>
+                                    <ul>
+                                        {links
+                                            .into_iter()
+                                            .map(|link| {
+                                                view! {
+                                                    <li>
+                                                        <a
+                                                            class="link link-primary"
+                                                            href=link.url
+                                                            target="_blank"
+                                                            rel="noopener noreferrer"
+                                                        >
+                                                            {link.name}
+                                                        </a>
+                                                        {link.build.map(|build| view! { <BuildDetails build/> })}
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+        {move || crash_id().map(|id| view! { <ReportDetailsCard id/> })}
+        {move || crash_id().map(|id| view! { <RawReportCard id/> })}
+        {move || crash_id().map(|id| view! { <LegalHoldCard id/> })}
+    }
+}
 
-    let _fields: RwSignal<Fields> = create_rw_signal(Fields::new());
+/// The processed report of a crash: what happened, the registers and frames of the crashing
+/// thread and the loaded modules.
+#[allow(non_snake_case)]
+#[component]
+fn ReportDetailsCard(id: uuid::Uuid) -> impl IntoView {
+    let details = create_resource(
+        move || id,
+        |id| async move { crash_report_details(id).await.ok() },
+    );
 
     view! {
-        // <Header
-        //     filter=filter
-        //     capabilities=capabilities
-        //     enabled=is_row_selected
-        //     related=related
-        //     on_edit_click=on_edit_click
-        //     on_add_click=on_add_click.into()
-        //     on_delete_click=on_delete_click
-        //     on_related_click=on_related_click
-        // />
-
-        // <ConfirmationModal
-        //     show=show_confirm_popup
-        //     custom_text=custom_text
-        //     on_yes_click=on_yes_click
-        //     on_no_click=on_no_click.into()
-        // />
+        <Transition fallback=|| ()>
+            {move || {
+                details
+                    .get()
+                    .flatten()
+                    .map(|info| {
+                        let crash_info = info.crash_info.unwrap_or_default();
+                        let system = info.system_info.unwrap_or_default();
+                        let thread = info.crashing_thread.unwrap_or_default();
+                        let exception = info.exception.unwrap_or_default();
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Crashing thread"</h2>
+                                    <table class="table table-sm">
+                                        <tbody>
+                                            <tr>
+                                                <th>"Type"</th>
+                                                <td>{crash_info.crash_type.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Address"</th>
+                                                <td>{crash_info.address.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Exception code"</th>
+                                                <td>{exception.code.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"Thread"</th>
+                                                <td>{thread.thread_name.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <th>"System"</th>
+                                                <td>
+                                                    {format!(
+                                                        "{} {}",
+                                                        system.os.unwrap_or_default(),
+                                                        system.cpu_arch.unwrap_or_default(),
+                                                    )}
+                                                </td>
+                                            </tr>
+                                        </tbody>
+                                    </table>
+                                    {(!exception.registers.is_empty())
+                                        .then(|| {
+                                            view! {
+                                                <h2 class="card-title">"Registers"</h2>
+                                                <div class="grid grid-cols-2 md:grid-cols-4 gap-x-4 font-mono text-xs">
+                                                    {exception
+                                                        .registers
+                                                        .into_iter()
+                                                        .map(|register| {
+                                                            view! {
+                                                                <div class="flex justify-between gap-2">
+                                                                    <span class="font-semibold">{register.name}</span>
+                                                                    <span>{register.value}</span>
+                                                                </div>
+                                                            }
+                                                        })
+                                                        .collect_view()}
+                                                </div>
+                                            }
+                                        })}
+                                    <div class="overflow-x-auto max-h-96">
+                                        <table class="table table-xs">
+                                            <thead>
+                                                <tr>
+                                                    <th>"#"</th>
+                                                    <th>"Function"</th>
+                                                    <th>"Module"</th>
+                                                    <th>"Source"</th>
+                                                </tr>
+                                            </thead>
+                                            <tbody>
+                                                {thread
+                                                    .frames
+                                                    .into_iter()
+                                                    .enumerate()
+                                                    .map(|(index, frame)| {
+                                                        let source = frame
+                                                            .file
+                                                            .map(|file| match frame.line {
+                                                                Some(line) => format!("{}:{}", file, line),
+                                                                None => file,
+                                                            });
+                                                        view! {
+                                                            <tr>
+                                                                <td>{index}</td>
+                                                                <td class="break-all">
+                                                                    {frame.function.unwrap_or_default()}
+                                                                </td>
+                                                                <td>{frame.module.unwrap_or_default()}</td>
+                                                                <td class="break-all">
+                                                                    {match frame.source_url {
+                                                                        Some(url) => view! {
+                                                                            <a class="link" href=url target="_blank">
+                                                                                {source.unwrap_or_default()}
+                                                                            </a>
+                                                                        }
+                                                                        .into_view(),
+                                                                        None => source.unwrap_or_default().into_view(),
+                                                                    }}
+                                                                </td>
+                                                            </tr>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </tbody>
+                                        </table>
+                                    </div>
+                                    <h2 class="card-title">"Modules"</h2>
+                                    <div class="overflow-x-auto max-h-96">
+                                        <table class="table table-xs">
+                                            <thead>
+                                                <tr>
+                                                    <th>"File"</th>
+                                                    <th>"Version"</th>
+                                                    <th>"Debug file"</th>
+                                                    <th>"Debug ID"</th>
+                                                </tr>
+                                            </thead>
+                                            <tbody>
+                                                {info
+                                                    .modules
+                                                    .into_iter()
+                                                    .map(|module| {
+                                                        view! {
+                                                            <tr>
+                                                                <td class="break-all">{module.filename}</td>
+                                                                <td>{module.version.unwrap_or_default()}</td>
+                                                                <td>{module.debug_file.unwrap_or_default()}</td>
+                                                                <td>{module.debug_id.unwrap_or_default()}</td>
+                                                            </tr>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </tbody>
+                                        </table>
+                                    </div>
+                                    {(!info.attachments.is_empty())
+                                        .then(|| view! { <h2 class="card-title">"Attachments"</h2> })}
+                                    {info
+                                        .attachments
+                                        .into_iter()
+                                        .map(|finding| {
+                                            view! {
+                                                <div class="my-1">
+                                                    <p class="font-semibold">
+                                                        {format!("{}: {}", finding.attachment, finding.summary)}
+                                                    </p>
+                                                    <pre class="text-xs overflow-x-auto max-h-64 bg-base-300 rounded p-2">
+                                                        {finding.lines.join("\n")}
+                                                    </pre>
+                                                </div>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
 
+/// The stored crash report as JSON, for administrators of the product debugging the processing.
+/// Only fetched once expanded, as reports can be large.
+#[allow(non_snake_case)]
+#[component]
+fn RawReportCard(id: uuid::Uuid) -> impl IntoView {
+    let unredacted = create_resource(
+        || (),
+        move |_| async move { crash_report_unredacted(id).await.unwrap_or(false) },
+    );
+    let (expanded, set_expanded) = create_signal(false);
+    let raw = create_resource(
+        move || expanded.get(),
+        move |expanded| async move {
+            match expanded {
+                true => crash_raw_report(id).await.unwrap_or_else(|e| e.to_string()),
+                false => String::new(),
+            }
+        },
+    );
+    let download = prefixed(&format!("/download/crash/{}/json", id));
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                unredacted
+                    .get()
+                    .filter(|unredacted| *unredacted)
+                    .map(|_| {
+                        let download = download.clone();
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <div class="flex items-center gap-2">
+                                        <h2 class="card-title">"Raw crash info"</h2>
+                                        <button
+                                            class="btn btn-sm ml-auto"
+                                            on:click=move |_| set_expanded.update(|e| *e = !*e)
+                                        >
+                                            {move || if expanded.get() { "Hide" } else { "Show" }}
+                                        </button>
+                                        <a class="btn btn-sm" href=download>
+                                            "Download JSON"
+                                        </a>
+                                    </div>
+                                    <Show when=move || expanded.get()>
+                                        <pre class="text-xs overflow-x-auto max-h-96">
+                                            {move || raw.get().unwrap_or_default()}
+                                        </pre>
+                                    </Show>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
+
+/// Places the crash on legal hold or releases it, for administrators. Crashes on hold and their
+/// attachments are never deleted.
+#[allow(non_snake_case)]
+#[component]
+fn LegalHoldCard(id: uuid::Uuid) -> impl IntoView {
+    let is_admin = create_resource(
+        || (),
+        |_| async move { authenticated_user_is_admin().await.unwrap_or(false) },
+    );
+    let set_hold = create_action(move |reason: &Option<String>| {
+        let reason = reason.clone();
+        async move { crash_set_legal_hold(id, reason).await }
+    });
+    let crash = create_resource(
+        move || set_hold.version().get(),
+        move |_| async move { crash_get(id).await.ok() },
+    );
+    let (reason, set_reason) = create_signal(String::new());
+    let error = move || {
+        set_hold
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|e| e.to_string())
+    };
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                is_admin
+                    .get()
+                    .filter(|is_admin| *is_admin)
+                    .and_then(|_| crash.get().flatten())
+                    .map(|crash| {
+                        let held = match crash.legal_hold_at {
+                            Some(at) => {
+                                view! {
+                                    <p>
+                                        {format!(
+                                            "On hold since {}: {}",
+                                            at.format("%Y-%m-%d %H:%M"),
+                                            crash.legal_hold_reason.unwrap_or_default(),
+                                        )}
+                                    </p>
+                                    <button
+                                        class="btn btn-sm"
+                                        disabled=move || set_hold.pending().get()
+                                        on:click=move |_| set_hold.dispatch(None)
+                                    >
+                                        "Release"
+                                    </button>
+                                }
+                                    .into_view()
+                            }
+                            None => {
+                                view! {
+                                    <input
+                                        type="text"
+                                        class="input input-bordered input-sm grow"
+                                        placeholder="Reason"
+                                        prop:value=reason
+                                        on:input=move |ev| set_reason.set(event_target_value(&ev))
+                                    />
+                                    <button
+                                        class="btn btn-sm btn-warning"
+                                        disabled=move || {
+                                            set_hold.pending().get()
+                                                || reason.with(|reason| reason.trim().is_empty())
+                                        }
+                                        on:click=move |_| set_hold.dispatch(Some(reason.get()))
+                                    >
+                                        "Place on hold"
+                                    </button>
+                                }
+                                    .into_view()
+                            }
+                        };
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Legal hold"</h2>
+                                    <div class="flex items-center gap-2">{held}</div>
+                                    <span class="text-sm text-error">{error}</span>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
+
+/// Annotations of the crashes with the same signature; the constant ones often point at the
+/// cause, e.g. a single GPU driver version.
+#[allow(non_snake_case)]
+#[component]
+fn AnnotationDiffCard(diff: AnnotationDiff) -> impl IntoView {
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Annotations"</h2>
+                <p class="text-sm">
+                    {format!(
+                        "Compared over the {} most recent crashes with signature {}",
+                        diff.crashes,
+                        diff.signature.unwrap_or_default(),
+                    )}
+                </p>
+                <table class="table table-sm">
+                    <tbody>
+                        {diff
+                            .annotations
+                            .into_iter()
+                            .map(|annotation| {
+                                let distinct = (annotation.distinct > annotation.values.len())
+                                    .then(|| {
+                                        format!(
+                                            "{} more",
+                                            annotation.distinct - annotation.values.len(),
+                                        )
+                                    });
+                                view! {
+                                    <tr>
+                                        <th>{annotation.key}</th>
+                                        <td>
+                                            {if annotation.constant {
+                                                view! { <span class="badge badge-primary">"constant"</span> }
+                                            } else {
+                                                view! {
+                                                    <span class="badge badge-ghost">
+                                                        {format!("{} crashes", annotation.crashes)}
+                                                    </span>
+                                                }
+                                            }}
+                                        </td>
+                                        <td>
+                                            <div class="flex flex-wrap gap-1">
+                                                {annotation
+                                                    .values
+                                                    .into_iter()
+                                                    .map(|value| {
+                                                        view! {
+                                                            <span class="badge badge-outline gap-1">
+                                                                {value.value}
+                                                                <span class="opacity-60">{value.count}</span>
+                                                            </span>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                                <span class="text-sm opacity-60">{distinct}</span>
+                                            </div>
+                                        </td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view()}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+/// Status and artifacts of the CI build a link refers to.
+#[allow(non_snake_case)]
+#[component]
+fn BuildDetails(build: BuildInfo) -> impl IntoView {
+    let badge = match build.status.as_deref().map(str::to_lowercase).as_deref() {
+        Some("success") => "badge badge-success",
+        Some("failure" | "failed" | "timed_out") => "badge badge-error",
+        Some(_) => "badge badge-ghost",
+        None => "badge badge-warning",
+    };
+    let status = build
+        .status
+        .or(build.error.map(|_| "unavailable".to_owned()))
+        .unwrap_or_else(|| "unknown".to_owned());
+    view! {
+        <span class="ml-2" title=format!("Fetched {}", build.fetched_at.format("%d/%m/%Y - %H:%M"))>
+            <span class=badge>{status}</span>
+            {build
+                .artifacts
+                .into_iter()
+                .map(|artifact| {
+                    view! {
+                        <a
+                            class="link ml-2 text-sm"
+                            href=artifact.url
+                            target="_blank"
+                            rel="noopener noreferrer"
+                        >
+                            {artifact.name}
+                        </a>
+                    }
+                })
+                .collect_view()}
+        </span>
     }
 }
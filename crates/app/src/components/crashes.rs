@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use enumflags2::BitFlags;
 use leptos::*;
+use leptos_router::*;
 use leptos_struct_table::*;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Range;
@@ -13,9 +14,12 @@ use crate::components::datatable::DataTable;
 use crate::components::datatable_form::Field;
 use crate::data::QueryParams;
 use crate::data_providers::crash::{
-    crash_add, crash_count, crash_get, crash_list, crash_list_names, crash_remove, crash_update,
-    Crash, CrashRow,
+    crash_add, crash_count, crash_facets, crash_get, crash_list, crash_list_names, crash_remove,
+    crash_sdk_distribution, crash_update, split_time_range, with_time_range, Crash, CrashRow,
+    SIGNATURE_FILTER, TIME_RANGES,
 };
+use crate::data_providers::product::product_list;
+use crate::data_providers::version::version_list;
 use crate::data_providers::ExtraTableDataProvider;
 use crate::table_data_provider_impl;
 
@@ -71,7 +75,7 @@ impl DataTableTrait for CrashTable {
     fn get_related() -> Vec<super::datatable::Related> {
         vec![super::datatable::Related {
             name: "Crash".to_string(),
-            url: "/admin/crash?crash=".to_string(),
+            url: "/crash?crash=".to_string(),
         }]
     }
 
@@ -142,10 +146,352 @@ impl DataTableTrait for CrashTable {
 
 table_data_provider_impl!(CrashTable);
 
+/// Maximum number of products and versions offered by the crash filters.
+const FILTER_OPTIONS: usize = 500;
+
+fn parse_id(id: String) -> Option<Uuid> {
+    Uuid::parse_str(&id).ok()
+}
+
+fn options_query() -> QueryParams {
+    QueryParams {
+        sorting: VecDeque::from([(1, ColumnSort::Ascending)]),
+        range: 0..FILTER_OPTIONS,
+        filter: String::new(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn CrashesPage() -> impl IntoView {
+    let query_map = use_query_map();
+    let query_id =
+        move |name: &str| query_map.with_untracked(|q| q.get(name).cloned().and_then(parse_id));
+
+    let filter = create_rw_signal(String::new());
+    let product = create_rw_signal(query_id("product"));
+    let version = create_rw_signal(query_id("version"));
+    let parents = Signal::derive(move || {
+        let mut parents = HashMap::new();
+        if let Some(product) = product.get() {
+            parents.insert("product_id".to_string(), product);
+        }
+        if let Some(version) = version.get() {
+            parents.insert("version_id".to_string(), version);
+        }
+        parents
+    });
+
+    // The table reads its parents once, so it is created again when they change.
+    let table = move || {
+        view! { <DataTable<CrashTable> filter=filter live=true parents=parents.get()/> }
+    };
+
+    view! {
+        <CrashFilters filter=filter product=product version=version/>
+        <TimeRangeFilters filter=filter/>
+        <CrashFacets filter=filter parents=parents/>
+        {table}
+    }
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn CrashPage() -> impl IntoView {
+    let sdks = create_resource(
+        || (),
+        |_| async move {
+            crash_sdk_distribution(HashMap::new())
+                .await
+                .unwrap_or_default()
+        },
+    );
+
+    view! {
+        <CrashesPage/>
+        <Transition fallback=move || view! { <p>"Loading..."</p> }>
+            {move || {
+                sdks.get()
+                    .filter(|sdks| !sdks.is_empty())
+                    .map(|sdks| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Crashes by SDK"</h2>
+                                    <table class="table table-sm">
+                                        <tbody>
+                                            {sdks
+                                                .into_iter()
+                                                .map(|sdk| {
+                                                    view! {
+                                                        <tr>
+                                                            <td>{sdk.sdk}</td>
+                                                            <td>{sdk.count}</td>
+                                                        </tr>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn CrashFilters(
+    filter: RwSignal<String>,
+    product: RwSignal<Option<Uuid>>,
+    version: RwSignal<Option<Uuid>>,
+) -> impl IntoView {
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list(options_query()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch products: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let versions = create_resource(
+        move || product.get(),
+        |product| async move {
+            let Some(product) = product else {
+                return vec![];
+            };
+            let parents = HashMap::from([("product_id".to_string(), product)]);
+            version_list(parents, options_query())
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch versions: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+
+    let signature = move || {
+        filter.with(|filter| {
+            split_time_range(filter)
+                .1
+                .strip_prefix(SIGNATURE_FILTER)
+                .unwrap_or_default()
+                .to_string()
+        })
+    };
+    let set_signature = move |signature: String| {
+        filter.update(|filter| {
+            let (range, _) = split_time_range(filter);
+            let signature = signature.trim();
+            let rest = match signature.is_empty() {
+                true => String::new(),
+                false => format!("{}{}", SIGNATURE_FILTER, signature),
+            };
+            *filter = with_time_range(range, &rest);
+        });
+    };
+
+    // A range of dates replaces the relative time ranges, see `time_range_bounds`.
+    let dates = move || {
+        filter.with(|filter| {
+            split_time_range(filter)
+                .0
+                .and_then(|range| range.split_once(".."))
+                .map(|(from, until)| (from.to_string(), until.to_string()))
+                .unwrap_or_default()
+        })
+    };
+    let set_dates = move |from: String, until: String| {
+        filter.update(|filter| {
+            let (_, rest) = split_time_range(filter);
+            let range =
+                (!from.is_empty() || !until.is_empty()).then(|| format!("{}..{}", from, until));
+            *filter = with_time_range(range.as_deref(), rest);
+        });
+    };
+
+    view! {
+        <Transition fallback=|| ()>
+            <div class="flex flex-wrap items-center gap-2 my-1">
+                <select
+                    class="select select-bordered select-sm"
+                    on:change=move |ev| {
+                        product.set(parse_id(event_target_value(&ev)));
+                        version.set(None);
+                    }
+                >
+                    <option value="">"All products"</option>
+                    {move || {
+                        products
+                            .get()
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|option| {
+                                let id = option.id;
+                                view! {
+                                    <option
+                                        value=id.to_string()
+                                        selected=move || product.get() == Some(id)
+                                    >
+                                        {option.name}
+                                    </option>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </select>
+                <select
+                    class="select select-bordered select-sm"
+                    disabled=move || product.get().is_none()
+                    on:change=move |ev| version.set(parse_id(event_target_value(&ev)))
+                >
+                    <option value="">"All versions"</option>
+                    {move || {
+                        versions
+                            .get()
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|option| {
+                                let id = option.id;
+                                view! {
+                                    <option
+                                        value=id.to_string()
+                                        selected=move || version.get() == Some(id)
+                                    >
+                                        {option.name}
+                                    </option>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </select>
+                <input
+                    type="text"
+                    class="input input-bordered input-sm w-72"
+                    placeholder="Signature contains"
+                    prop:value=signature
+                    on:change=move |ev| set_signature(event_target_value(&ev))
+                />
+                <span class="text-sm font-semibold">"From"</span>
+                <input
+                    type="date"
+                    class="input input-bordered input-sm"
+                    prop:value=move || dates().0
+                    on:change=move |ev| set_dates(event_target_value(&ev), dates().1)
+                />
+                <span class="text-sm font-semibold">"Until"</span>
+                <input
+                    type="date"
+                    class="input input-bordered input-sm"
+                    prop:value=move || dates().1
+                    on:change=move |ev| set_dates(dates().0, event_target_value(&ev))
+                />
+            </div>
+        </Transition>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn TimeRangeFilters(filter: RwSignal<String>) -> impl IntoView {
+    let selected = move || filter.with(|filter| split_time_range(filter).0.map(str::to_string));
+
+    view! {
+        <div class="flex flex-wrap items-center gap-1 my-1">
+            <span class="text-sm font-semibold mr-1">"Received"</span>
+            {TIME_RANGES
+                .into_iter()
+                .map(|(range, label)| {
+                    view! {
+                        <button
+                            class="badge badge-outline"
+                            class:badge-primary=move || selected().as_deref() == Some(range)
+                            on:click=move |_| {
+                                filter.update(|filter| {
+                                    let (current, rest) = split_time_range(filter);
+                                    let range = (current != Some(range)).then_some(range);
+                                    *filter = with_time_range(range, rest);
+                                });
+                            }
+                        >
+                            {label}
+                        </button>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn CrashFacets(filter: RwSignal<String>, parents: Signal<HashMap<String, Uuid>>) -> impl IntoView {
+    let facets = create_resource(
+        move || (parents.get(), filter.get()),
+        |(parents, filter)| async move {
+            crash_facets(parents, filter, vec![])
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch facets: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+
     view! {
-        <DataTable<CrashTable>/>
+        <Transition fallback=move || ()>
+            {move || {
+                facets
+                    .get()
+                    .map(|facets| {
+                        facets
+                            .into_iter()
+                            .map(|facet| {
+                                let key = facet.key.clone();
+                                view! {
+                                    <div class="flex flex-wrap items-center gap-1 my-1">
+                                        <span class="text-sm font-semibold mr-1">{facet.key}</span>
+                                        {facet
+                                            .values
+                                            .into_iter()
+                                            .map(|value| {
+                                                let selected = format!("{}:{}", key, value.value);
+                                                let active = selected.clone();
+                                                view! {
+                                                    <button
+                                                        class="badge badge-outline gap-1"
+                                                        class:badge-primary=move || {
+                                                            filter.with(|filter| split_time_range(filter).1 == active)
+                                                        }
+                                                        on:click=move |_| {
+                                                            let selected = selected.clone();
+                                                            filter.update(|filter| {
+                                                                let (range, rest) = split_time_range(filter);
+                                                                let rest = if rest == selected {
+                                                                    ""
+                                                                } else {
+                                                                    selected.as_str()
+                                                                };
+                                                                *filter = with_time_range(range, rest);
+                                                            });
+                                                        }
+                                                    >
+                                                        {value.value}
+                                                        <span class="opacity-60">{value.count}</span>
+                                                    </button>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    })
+            }}
+        </Transition>
     }
 }
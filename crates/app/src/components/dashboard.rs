@@ -0,0 +1,246 @@
+use leptos::*;
+use std::collections::VecDeque;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::data::QueryParams;
+use crate::data_providers::product::product_list;
+use crate::data_providers::setup::setup_required;
+use crate::data_providers::stats::{
+    crash_stats, CrashCount, DEFAULT_DAYS, GROUP_CHANNEL, GROUP_DAY, GROUP_VERSION,
+};
+use crate::prefix::prefixed;
+
+/// Periods the dashboard can show, in days.
+const PERIODS: [i64; 3] = [7, 30, 90];
+
+fn all_products() -> QueryParams {
+    QueryParams {
+        sorting: VecDeque::new(),
+        range: 0..1000,
+        filter: String::new(),
+    }
+}
+
+/// Bars of the crashes per day, scaled to the day with the most crashes.
+#[allow(non_snake_case)]
+#[component]
+fn CrashesPerDay(counts: Vec<CrashCount>) -> impl IntoView {
+    let max = counts
+        .iter()
+        .map(|count| count.count)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let bars = counts
+        .into_iter()
+        .filter_map(|count| {
+            let day = count.day?;
+            let height = format!("height: {}%", count.count * 100 / max);
+            let title = format!("{}: {} crashes", day.format("%d/%m/%Y"), count.count);
+            Some(view! {
+                <div class="flex-1 h-full flex items-end tooltip" data-tip=title>
+                    <div class="w-full bg-primary rounded-t-sm" style=height></div>
+                </div>
+            })
+        })
+        .collect_view();
+    view! { <div class="flex items-end gap-px h-32 w-full">{bars}</div> }
+}
+
+/// Overview of the crashes of the last days: crashes per day, per version and release channel,
+/// and the signatures with the most crashes.
+#[allow(non_snake_case)]
+#[component]
+pub fn Dashboard() -> impl IntoView {
+    let product = create_rw_signal(None::<Uuid>);
+    let days = create_rw_signal(DEFAULT_DAYS);
+
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list(all_products()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch products: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let setup = create_resource(
+        || (),
+        |_| async move { setup_required().await.unwrap_or(false) },
+    );
+    let per_day = create_resource(
+        move || (product.get(), days.get()),
+        |(product, days)| async move { crash_stats(product, GROUP_DAY.to_owned(), days).await },
+    );
+    let per_version = create_resource(
+        move || (product.get(), days.get()),
+        |(product, days)| async move {
+            crash_stats(
+                product,
+                format!("{},{}", GROUP_VERSION, GROUP_CHANNEL),
+                days,
+            )
+            .await
+            .map(|stats| stats.counts)
+            .unwrap_or_default()
+        },
+    );
+
+    view! {
+        <Transition fallback=|| ()>
+            <div class="flex flex-wrap items-center gap-2 my-1">
+                <select
+                    class="select select-bordered select-sm"
+                    on:change=move |ev| product.set(Uuid::parse_str(&event_target_value(&ev)).ok())
+                >
+                    <option value="">"All products"</option>
+                    {move || {
+                        products
+                            .get()
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|option| {
+                                view! { <option value=option.id.to_string()>{option.name}</option> }
+                            })
+                            .collect_view()
+                    }}
+                </select>
+                <select
+                    class="select select-bordered select-sm"
+                    on:change=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse::<i64>() {
+                            days.set(value);
+                        }
+                    }
+                >
+                    {PERIODS
+                        .into_iter()
+                        .map(|period| {
+                            view! {
+                                <option value=period.to_string() selected=move || days.get() == period>
+                                    {format!("Last {} days", period)}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </div>
+        </Transition>
+        <Transition fallback=move || view! { <span>"Loading..."</span> }>
+            {move || {
+                per_day
+                    .get()
+                    .map(|stats| match stats {
+                        Ok(stats) => {
+                            view! {
+                                <div class="card bg-base-200 rounded-lg my-2">
+                                    <div class="card-body p-4">
+                                        <h2 class="card-title">
+                                            {format!("{} crashes", stats.total)}
+                                        </h2>
+                                        <CrashesPerDay counts=stats.counts/>
+                                    </div>
+                                </div>
+                                <div class="card bg-base-200 rounded-lg my-2">
+                                    <div class="card-body p-4">
+                                        <h2 class="card-title">"Top signatures"</h2>
+                                        <table class="table table-sm w-full">
+                                            <tbody>
+                                                {stats
+                                                    .top_signatures
+                                                    .into_iter()
+                                                    .map(|signature| {
+                                                        let href = prefixed(
+                                                            &format!("/issue?issue={}", signature.issue_id),
+                                                        );
+                                                        view! {
+                                                            <tr>
+                                                                <td>
+                                                                    <a class="link" href=href title=signature.title>
+                                                                        {signature.signature}
+                                                                    </a>
+                                                                </td>
+                                                                <td class="text-right">{signature.count}</td>
+                                                            </tr>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </tbody>
+                                        </table>
+                                    </div>
+                                </div>
+                            }
+                                .into_view()
+                        }
+                        Err(_) => {
+                            view! {
+                                <h1>"Welcome to Guardrail!"</h1>
+                                <Show
+                                    when=move || setup.get().unwrap_or(false)
+                                    fallback=|| {
+                                        view! {
+                                            <p>
+                                                <a class="link" href=prefixed("/auth/login")>
+                                                    "Log in"
+                                                </a>
+                                                " to see the crashes of your products."
+                                            </p>
+                                        }
+                                    }
+                                >
+                                    <p>
+                                        "Nobody has registered yet. "
+                                        <a class="link" href=prefixed("/setup")>
+                                            "Set up this server"
+                                        </a>
+                                        " to create the administrator and the first product."
+                                    </p>
+                                </Show>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+        </Transition>
+        <Transition fallback=|| ()>
+            {move || {
+                per_version
+                    .get()
+                    .filter(|counts| !counts.is_empty())
+                    .map(|counts| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h2 class="card-title">"Crashes per version"</h2>
+                                    <table class="table table-sm w-full">
+                                        <thead>
+                                            <tr>
+                                                <th>"Version"</th>
+                                                <th>"Channel"</th>
+                                                <th class="text-right">"Crashes"</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>
+                                            {counts
+                                                .into_iter()
+                                                .map(|count| {
+                                                    view! {
+                                                        <tr>
+                                                            <td>{count.version.unwrap_or_default()}</td>
+                                                            <td>{count.channel.unwrap_or_default()}</td>
+                                                            <td class="text-right">{count.count}</td>
+                                                        </tr>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
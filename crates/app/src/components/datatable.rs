@@ -13,8 +13,10 @@ use uuid::Uuid;
 use crate::components::confirmation::ConfirmationModal;
 use crate::components::datatable_form::{DataTableModalForm, Fields};
 use crate::components::datatable_header::DataTableHeader;
+use crate::components::live::use_live_updates;
 use crate::data::QueryParams;
 use crate::data_providers::{ExtraRowTrait, ExtraTableDataProvider};
+use crate::read_only::is_read_only;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Related {
@@ -90,25 +92,39 @@ where
 
 #[allow(non_snake_case)]
 #[component]
-pub fn DataTable<T>(#[prop(optional)] _ty: PhantomData<T>) -> impl IntoView
+pub fn DataTable<T>(
+    #[prop(optional)] _ty: PhantomData<T>,
+    /// Filter shared with the page, e.g. to apply a filter from outside the table.
+    #[prop(optional)]
+    filter: Option<RwSignal<String>>,
+    /// Refreshes the table when the server reports changes, see `use_live_updates`.
+    #[prop(optional)]
+    live: bool,
+    /// Parents of the rows, e.g. chosen on the page, instead of those in the query of the URL.
+    #[prop(optional)]
+    parents: Option<HashMap<String, Uuid>>,
+) -> impl IntoView
 where
     T: DataTableTrait,
 {
     let query_map = use_query_map();
 
-    let mut query = HashMap::new();
-    for foreign in T::get_foreign() {
-        let q = query_map.get_untracked();
-        let q = q.get(foreign.query.as_str());
-        if let Some(q) = q {
-            info!("{}: {}", foreign.query, q);
-            let uuid = uuid::Uuid::parse_str(q);
-            if let Ok(uuid) = uuid {
-                info!("{}: {}", foreign.id_name, uuid);
-                query.insert(foreign.id_name, uuid);
+    let query = parents.unwrap_or_else(|| {
+        let mut query = HashMap::new();
+        for foreign in T::get_foreign() {
+            let q = query_map.get_untracked();
+            let q = q.get(foreign.query.as_str());
+            if let Some(q) = q {
+                info!("{}: {}", foreign.query, q);
+                let uuid = uuid::Uuid::parse_str(q);
+                if let Ok(uuid) = uuid {
+                    info!("{}: {}", foreign.id_name, uuid);
+                    query.insert(foreign.id_name, uuid);
+                }
             }
         }
-    }
+        query
+    });
 
     let fields: RwSignal<Fields> = create_rw_signal(Fields::default());
 
@@ -122,6 +138,10 @@ where
 
     let rows_clone2 = form.clone();
     spawn_local(async move {
+        // A read-only mirror only allows browsing.
+        if is_read_only() {
+            return;
+        }
         let c = rows_clone2.capabilities().await;
         capabilities.update(|caps| {
             *caps = c;
@@ -131,7 +151,22 @@ where
     let selected_index: RwSignal<Option<usize>> = create_rw_signal(None);
     let (selected_row, set_selected_row) = create_signal(None);
 
+    let shared_filter = filter;
     let filter = form.get_filter_signal();
+    if let Some(shared_filter) = shared_filter {
+        create_effect(move |_| {
+            let value = shared_filter.get();
+            if filter.get_untracked() != value {
+                filter.set(value);
+            }
+        });
+        create_effect(move |_| {
+            let value = filter.get();
+            if shared_filter.get_untracked() != value {
+                shared_filter.set(value);
+            }
+        });
+    }
     let (custom_text, set_custom_text) = create_signal("".to_string());
     let (show_confirm_popup, set_show_confirm_popup) = create_signal(false);
     let (show_form_popup, set_show_form_popup) = create_signal(false);
@@ -157,6 +192,17 @@ where
         }
     });
 
+    if live {
+        let changes = use_live_updates(query.get("product_id").copied());
+        let rows = form_clone.clone();
+        create_effect(move |_| {
+            // Leave the table alone while a row is being edited or removed.
+            if changes.get() > 0 && matches!(state.get_untracked(), State::Idle) {
+                untrack(|| rows.refresh_table());
+            }
+        });
+    }
+
     let on_delete_click = Callback::new(move |_evt: web_sys::MouseEvent| {
         let row = selected_row.get();
         if row.is_some() {
@@ -0,0 +1,113 @@
+use leptos::*;
+use leptos_router::*;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::data_providers::device_key::device_key_list;
+use crate::data_providers::product::product_get;
+
+const DATE_FORMAT: &str = "%d/%m/%Y - %H:%M";
+
+#[allow(non_snake_case)]
+#[component]
+pub fn DeviceKeysPage() -> impl IntoView {
+    let query_map = use_query_map();
+    let product_id =
+        move || query_map.with(|q| q.get("product").and_then(|id| Uuid::parse_str(id).ok()));
+
+    view! {
+        {move || match product_id() {
+            Some(product_id) => view! { <DeviceKeys product_id/> }.into_view(),
+            None => view! {
+                <div class="alert alert-info rounded-btn my-2 p-3">
+                    "Open the device keys of a product from the products page"
+                </div>
+            }
+            .into_view(),
+        }}
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn DeviceKeys(product_id: Uuid) -> impl IntoView {
+    let product = create_resource(
+        move || product_id,
+        |product_id| async move {
+            product_get(product_id)
+                .await
+                .map(|product| product.name)
+                .unwrap_or_default()
+        },
+    );
+    let keys = create_resource(
+        move || product_id,
+        |product_id| async move {
+            device_key_list(product_id).await.unwrap_or_else(|e| {
+                error!("Failed to fetch device keys: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">
+                    "Device keys"
+                    <Transition fallback=|| ()>{move || product.get()}</Transition>
+                </h2>
+                <p class="text-sm">
+                    "Submissions are counted per key and written every 30 seconds, so the latest ones may not be shown yet."
+                </p>
+                <table class="table table-sm w-full">
+                    <thead>
+                        <tr>
+                            <th>"Name"</th>
+                            <th>"Entitlements"</th>
+                            <th>"Created"</th>
+                            <th>"Submissions"</th>
+                            <th>"Last used"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                            {move || {
+                                keys
+                                    .get()
+                                    .map(|keys| {
+                                        if keys.is_empty() {
+                                            return view! {
+                                                <tr>
+                                                    <td colspan="5">"No device keys"</td>
+                                                </tr>
+                                            }
+                                            .into_view();
+                                        }
+                                        keys.into_iter()
+                                            .map(|key| {
+                                                view! {
+                                                    <tr>
+                                                        <td>{key.name}</td>
+                                                        <td>{key.entitlements.unwrap_or_default()}</td>
+                                                        <td>{key.created_at.format(DATE_FORMAT).to_string()}</td>
+                                                        <td>{key.submission_count}</td>
+                                                        <td>
+                                                            {key
+                                                                .last_used_at
+                                                                .map(|at| at.format(DATE_FORMAT).to_string())
+                                                                .unwrap_or_else(|| "never".to_string())}
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()
+                                    })
+                            }}
+                        </Transition>
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
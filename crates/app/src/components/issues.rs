@@ -0,0 +1,589 @@
+use chrono::NaiveDate;
+use leptos::*;
+use leptos_router::*;
+use leptos_struct_table::ColumnSort;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::error;
+use uuid::Uuid;
+
+use super::crashes::CrashTable;
+use crate::components::datatable::DataTable;
+use crate::components::live::use_live_updates;
+use crate::data::QueryParams;
+use crate::data_providers::issue::{
+    issue_assign, issue_filter, issue_get, issue_list, issue_set_auto_resolve, issue_set_status,
+    issue_snooze, issue_status_counts, issue_versions, with_status, Issue, ISSUE_OPEN,
+    ISSUE_RESOLVED, ISSUE_SNOOZED, ISSUE_STATUSES, MAX_AUTO_RESOLVE_DAYS, QUIET_STATUSES,
+};
+use crate::prefix::prefixed;
+use crate::read_only::is_read_only;
+
+/// Number of issues shown, the most recently seen first.
+const SHOWN_ISSUES: usize = 200;
+/// Columns of the product, crash count and last seen time, see `EntityInfo::index_to_column` of
+/// issues.
+const PRODUCT_COLUMN: usize = 1;
+const COUNT_COLUMN: usize = 2;
+const LAST_SEEN_COLUMN: usize = 4;
+/// Number of affected versions listed per issue; the others are only counted.
+const SHOWN_VERSIONS: usize = 3;
+
+/// Orders of the issues, with their label.
+const SORT_ORDERS: [(usize, &str); 2] = [
+    (LAST_SEEN_COLUMN, "Most recent"),
+    (COUNT_COLUMN, "Most crashes"),
+];
+
+const DATE_FORMAT: &str = "%d/%m/%Y - %H:%M";
+
+#[derive(Debug, Clone)]
+enum BulkAction {
+    SetStatus(&'static str),
+    Assign(bool),
+    Snooze(NaiveDate),
+    AutoResolve(Option<i32>),
+}
+
+fn status_label(status: &str) -> String {
+    ISSUE_STATUSES
+        .iter()
+        .find(|(name, _)| *name == status)
+        .map_or_else(|| status.to_string(), |(_, label)| label.to_string())
+}
+
+fn status_class(status: &str) -> &'static str {
+    match status {
+        ISSUE_OPEN => "badge badge-warning",
+        ISSUE_RESOLVED => "badge badge-success",
+        _ => "badge badge-ghost",
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn IssuesPage() -> impl IntoView {
+    let filter = create_rw_signal(String::new());
+    let status = create_rw_signal(Some(ISSUE_OPEN));
+    let sort_column = create_rw_signal(LAST_SEEN_COLUMN);
+    let grouped = create_rw_signal(false);
+    let selected = create_rw_signal(HashSet::<Uuid>::new());
+    let snooze_until = create_rw_signal(String::new());
+    let auto_resolve_days = create_rw_signal(String::new());
+    let changes = use_live_updates(None);
+
+    let update = create_action(|(ids, action): &(Vec<Uuid>, BulkAction)| {
+        let (ids, action) = (ids.clone(), action.clone());
+        async move {
+            match action {
+                BulkAction::SetStatus(status) => issue_set_status(ids, status.to_string()).await,
+                BulkAction::Assign(assign) => issue_assign(ids, assign).await,
+                BulkAction::Snooze(until) => issue_snooze(ids, until).await,
+                BulkAction::AutoResolve(days) => issue_set_auto_resolve(ids, days).await,
+            }
+        }
+    });
+
+    let issues = create_resource(
+        move || {
+            (
+                filter.get(),
+                status.get(),
+                sort_column.get(),
+                grouped.get(),
+                changes.get(),
+                update.version().get(),
+            )
+        },
+        |(filter, status, sort_column, grouped, _, _)| async move {
+            let mut sorting = VecDeque::from([(sort_column, ColumnSort::Descending)]);
+            if grouped {
+                sorting.push_front((PRODUCT_COLUMN, ColumnSort::Ascending));
+            }
+            let query_params = QueryParams {
+                sorting,
+                range: 0..SHOWN_ISSUES,
+                filter: with_status(status, filter.trim()),
+            };
+            issue_list(HashMap::new(), query_params)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch issues: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+    let counts = create_resource(
+        move || (filter.get(), changes.get(), update.version().get()),
+        |(filter, _, _)| async move {
+            issue_status_counts(HashMap::new(), filter.trim().to_string())
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch issue counts: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+    let versions = create_resource(
+        move || {
+            issues
+                .get()
+                .map(|issues| issues.iter().map(|issue| issue.id).collect::<Vec<_>>())
+                .unwrap_or_default()
+        },
+        |ids| async move {
+            if ids.is_empty() {
+                return HashMap::new();
+            }
+            issue_versions(ids).await.unwrap_or_else(|e| {
+                error!("Failed to fetch affected versions: {:?}", e);
+                HashMap::new()
+            })
+        },
+    );
+
+    // A selection only applies to the issues it was made on.
+    create_effect(move |_| {
+        filter.track();
+        status.track();
+        selected.set(HashSet::new());
+    });
+
+    // Like the list, all issues leave out the muted and snoozed ones.
+    let count = move |status: Option<&str>| {
+        counts
+            .get()
+            .unwrap_or_default()
+            .iter()
+            .filter(|count| match status {
+                Some(status) => count.status == status,
+                None => !QUIET_STATUSES.contains(&count.status.as_str()),
+            })
+            .map(|count| count.count)
+            .sum::<i64>()
+    };
+    let shown = move || {
+        issues
+            .get()
+            .map(|issues| issues.iter().map(|issue| issue.id).collect::<HashSet<_>>())
+            .unwrap_or_default()
+    };
+    let dispatch = move |action: BulkAction| {
+        let ids = selected.get().into_iter().collect();
+        selected.set(HashSet::new());
+        update.dispatch((ids, action));
+    };
+    let disabled =
+        move || is_read_only() || update.pending().get() || selected.with(HashSet::is_empty);
+    let snooze_date = move || NaiveDate::parse_from_str(&snooze_until.get(), "%Y-%m-%d").ok();
+    // An empty number of days turns auto-resolve off.
+    let auto_resolve = move || {
+        let days = auto_resolve_days.get();
+        match days.trim() {
+            "" => Some(None),
+            days => days
+                .parse()
+                .ok()
+                .filter(|days| (1..=MAX_AUTO_RESOLVE_DAYS).contains(days))
+                .map(Some),
+        }
+    };
+    let failed = move || {
+        update
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Issues"</h2>
+                <div role="tablist" class="tabs tabs-bordered w-fit">
+                    <a
+                        role="tab"
+                        class="tab"
+                        class:tab-active=move || status.get().is_none()
+                        on:click=move |_| status.set(None)
+                    >
+                        {move || format!("All ({})", count(None))}
+                    </a>
+                    {ISSUE_STATUSES
+                        .into_iter()
+                        .map(|(name, label)| {
+                            view! {
+                                <a
+                                    role="tab"
+                                    class="tab"
+                                    class:tab-active=move || status.get() == Some(name)
+                                    on:click=move |_| status.set(Some(name))
+                                >
+                                    {move || format!("{} ({})", label, count(Some(name)))}
+                                </a>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+                <div class="flex flex-wrap items-center gap-2">
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm w-96"
+                        placeholder="Filter on title"
+                        prop:value=move || filter.get()
+                        on:change=move |ev| filter.set(event_target_value(&ev))
+                    />
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| {
+                            if let Ok(column) = event_target_value(&ev).parse() {
+                                sort_column.set(column);
+                            }
+                        }
+                    >
+                        {SORT_ORDERS
+                            .into_iter()
+                            .map(|(column, label)| {
+                                view! {
+                                    <option
+                                        value=column.to_string()
+                                        selected=move || sort_column.get() == column
+                                    >
+                                        {label}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                    <label class="label cursor-pointer gap-2">
+                        <span class="label-text">"Group by product"</span>
+                        <input
+                            type="checkbox"
+                            class="checkbox checkbox-sm"
+                            prop:checked=move || grouped.get()
+                            on:change=move |ev| grouped.set(event_target_checked(&ev))
+                        />
+                    </label>
+                </div>
+                <div class="flex flex-wrap items-center gap-2">
+                    <span class="text-sm">
+                        {move || format!("{} selected", selected.with(HashSet::len))}
+                    </span>
+                    {ISSUE_STATUSES
+                        .into_iter()
+                        .filter(|(name, _)| *name != ISSUE_SNOOZED)
+                        .map(|(name, label)| {
+                            view! {
+                                <button
+                                    class="btn btn-sm"
+                                    disabled=disabled
+                                    on:click=move |_| dispatch(BulkAction::SetStatus(name))
+                                >
+                                    {format!("Mark {}", label.to_lowercase())}
+                                </button>
+                            }
+                        })
+                        .collect_view()}
+                    <button
+                        class="btn btn-sm"
+                        disabled=disabled
+                        on:click=move |_| dispatch(BulkAction::Assign(true))
+                    >
+                        "Assign to me"
+                    </button>
+                    <button
+                        class="btn btn-sm"
+                        disabled=disabled
+                        on:click=move |_| dispatch(BulkAction::Assign(false))
+                    >
+                        "Unassign"
+                    </button>
+                    <input
+                        type="date"
+                        class="input input-bordered input-sm"
+                        prop:value=move || snooze_until.get()
+                        on:change=move |ev| snooze_until.set(event_target_value(&ev))
+                    />
+                    <button
+                        class="btn btn-sm"
+                        disabled=move || disabled() || snooze_date().is_none()
+                        on:click=move |_| {
+                            if let Some(until) = snooze_date() {
+                                dispatch(BulkAction::Snooze(until));
+                            }
+                        }
+                    >
+                        "Snooze until"
+                    </button>
+                    <input
+                        type="number"
+                        class="input input-bordered input-sm w-24"
+                        min="1"
+                        max=MAX_AUTO_RESOLVE_DAYS
+                        placeholder="Days"
+                        prop:value=move || auto_resolve_days.get()
+                        on:change=move |ev| auto_resolve_days.set(event_target_value(&ev))
+                    />
+                    <button
+                        class="btn btn-sm"
+                        title="Resolve the issues after this many days without crashes; leave empty to turn off"
+                        disabled=move || disabled() || auto_resolve().is_none()
+                        on:click=move |_| {
+                            if let Some(days) = auto_resolve() {
+                                dispatch(BulkAction::AutoResolve(days));
+                            }
+                        }
+                    >
+                        "Auto-resolve"
+                    </button>
+                </div>
+                {failed}
+                <table class="table table-sm w-full">
+                    <thead>
+                        <tr>
+                            <th>
+                                <input
+                                    type="checkbox"
+                                    class="checkbox checkbox-sm"
+                                    prop:checked=move || {
+                                        let shown = shown();
+                                        !shown.is_empty()
+                                            && selected.with(|selected| shown.is_subset(selected))
+                                    }
+                                    on:change=move |ev| {
+                                        if event_target_checked(&ev) {
+                                            selected.set(shown());
+                                        } else {
+                                            selected.set(HashSet::new());
+                                        }
+                                    }
+                                />
+                            </th>
+                            <th>"Title"</th>
+                            <th>"Product"</th>
+                            <th>"Crashes"</th>
+                            <th>"Versions"</th>
+                            <th>"Status"</th>
+                            <th>"Assignee"</th>
+                            <th>"Last seen"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                            {move || {
+                                issues
+                                    .get()
+                                    .map(|issues| {
+                                        if issues.is_empty() {
+                                            return view! {
+                                                <tr>
+                                                    <td colspan="8">"No issues"</td>
+                                                </tr>
+                                            }
+                                            .into_view();
+                                        }
+                                        let mut product = None;
+                                        issues
+                                            .into_iter()
+                                            .map(|issue| {
+                                                let header = (grouped.get_untracked()
+                                                    && product.as_ref() != Some(&issue.product))
+                                                    .then(|| {
+                                                        product = Some(issue.product.clone());
+                                                        view! {
+                                                            <tr class="bg-base-300">
+                                                                <td colspan="8" class="font-semibold">
+                                                                    {issue.product.clone()}
+                                                                </td>
+                                                            </tr>
+                                                        }
+                                                    });
+                                                let id = issue.id;
+                                                let versions = Signal::derive(move || {
+                                                    versions
+                                                        .get()
+                                                        .and_then(|versions| versions.get(&id).cloned())
+                                                        .unwrap_or_default()
+                                                });
+                                                view! {
+                                                    {header}
+                                                    <IssueRow issue versions selected/>
+                                                }
+                                            })
+                                            .collect_view()
+                                    })
+                            }}
+                        </Transition>
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn IssueRow(
+    issue: Issue,
+    versions: Signal<Vec<String>>,
+    selected: RwSignal<HashSet<Uuid>>,
+) -> impl IntoView {
+    let id = issue.id;
+    let href = prefixed(&format!("/issue?issue={}", issue.id));
+    let affected = move || {
+        versions.with(|versions| {
+            let mut shown = versions
+                .iter()
+                .take(SHOWN_VERSIONS)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if versions.len() > SHOWN_VERSIONS {
+                shown.push_str(&format!(" +{}", versions.len() - SHOWN_VERSIONS));
+            }
+            shown
+        })
+    };
+    view! {
+        <tr>
+            <td>
+                <input
+                    type="checkbox"
+                    class="checkbox checkbox-sm"
+                    prop:checked=move || selected.with(|selected| selected.contains(&id))
+                    on:change=move |ev| {
+                        let checked = event_target_checked(&ev);
+                        selected
+                            .update(|selected| {
+                                if checked {
+                                    selected.insert(id);
+                                } else {
+                                    selected.remove(&id);
+                                }
+                            });
+                    }
+                />
+            </td>
+            <td>
+                <a class="link" href=href>{issue.title}</a>
+                <div class="text-xs opacity-60 break-all">{issue.signature}</div>
+            </td>
+            <td>{issue.product}</td>
+            <td>{issue.count}</td>
+            <td title=move || versions.get().join(", ")>{affected}</td>
+            <td>
+                <span class=status_class(&issue.status)>{status_label(&issue.status)}</span>
+            </td>
+            <td>{issue.assignee.unwrap_or_default()}</td>
+            <td>{issue.last_seen.format(DATE_FORMAT).to_string()}</td>
+        </tr>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn IssuePage() -> impl IntoView {
+    let query_map = use_query_map();
+    let issue_id =
+        move || query_map.with(|q| q.get("issue").and_then(|id| Uuid::parse_str(id).ok()));
+
+    view! {
+        {move || match issue_id() {
+            Some(issue_id) => view! { <IssueDetails issue_id/> }.into_view(),
+            None => view! {
+                <div class="alert alert-info rounded-btn my-2 p-3">
+                    "Open an issue from the issues page"
+                </div>
+            }
+            .into_view(),
+        }}
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn IssueDetails(issue_id: Uuid) -> impl IntoView {
+    let filter = create_rw_signal(issue_filter(issue_id));
+    let issue = create_resource(
+        move || issue_id,
+        |issue_id| async move { issue_get(issue_id).await.ok() },
+    );
+
+    view! {
+        <Transition fallback=move || view! { <p>"Loading..."</p> }>
+            {move || {
+                issue
+                    .get()
+                    .flatten()
+                    .map(|issue| {
+                        view! {
+                            <div class="card bg-base-200 rounded-lg my-2">
+                                <div class="card-body p-4">
+                                    <h1 class="card-title text-xl break-all">{issue.title}</h1>
+                                    <table class="table table-sm">
+                                        <tbody>
+                                            <tr>
+                                                <td>"Signature"</td>
+                                                <td class="break-all">{issue.signature}</td>
+                                            </tr>
+                                            <tr>
+                                                <td>"Product"</td>
+                                                <td>{issue.product}</td>
+                                            </tr>
+                                            <tr>
+                                                <td>"Crashes"</td>
+                                                <td>{issue.count}</td>
+                                            </tr>
+                                            <tr>
+                                                <td>"Status"</td>
+                                                <td>
+                                                    <span class=status_class(&issue.status)>
+                                                        {status_label(&issue.status)}
+                                                    </span>
+                                                </td>
+                                            </tr>
+                                            {issue
+                                                .snoozed_until
+                                                .map(|until| {
+                                                    view! {
+                                                        <tr>
+                                                            <td>"Snoozed until"</td>
+                                                            <td>{until.format(DATE_FORMAT).to_string()}</td>
+                                                        </tr>
+                                                    }
+                                                })}
+                                            <tr>
+                                                <td>"Auto-resolve"</td>
+                                                <td>
+                                                    {issue
+                                                        .auto_resolve_days
+                                                        .map_or_else(
+                                                            || "Off".to_string(),
+                                                            |days| format!("After {} days without crashes", days),
+                                                        )}
+                                                </td>
+                                            </tr>
+                                            <tr>
+                                                <td>"Assignee"</td>
+                                                <td>{issue.assignee.unwrap_or_default()}</td>
+                                            </tr>
+                                            <tr>
+                                                <td>"First seen"</td>
+                                                <td>{issue.first_seen.format(DATE_FORMAT).to_string()}</td>
+                                            </tr>
+                                            <tr>
+                                                <td>"Last seen"</td>
+                                                <td>{issue.last_seen.format(DATE_FORMAT).to_string()}</td>
+                                            </tr>
+                                        </tbody>
+                                    </table>
+                                </div>
+                            </div>
+                        }
+                    })
+            }}
+        </Transition>
+        <DataTable<CrashTable> filter=filter live=true/>
+    }
+}
@@ -0,0 +1,43 @@
+use leptos::*;
+use uuid::Uuid;
+use web_sys::wasm_bindgen::closure::Closure;
+use web_sys::wasm_bindgen::JsCast;
+
+use crate::prefix::prefixed;
+
+/// Name of the server-sent event that signals that crashes or issues changed.
+const CHANGED_EVENT: &str = "changed";
+
+/// Returns the path of the live update stream, limited to the product if given.
+fn live_path(product: Option<Uuid>) -> String {
+    match product {
+        Some(product) => prefixed(&format!("/live/events?product={}", product)),
+        None => prefixed("/live/events"),
+    }
+}
+
+/// Returns a counter that increases whenever crashes or issues of the product, or of any product
+/// the user can access, change. The server combines changes in quick succession, so pages can
+/// refresh on every increase. Effects only run in the browser, so the server renders the page
+/// without opening the stream.
+pub fn use_live_updates(product: Option<Uuid>) -> ReadSignal<u64> {
+    let (changes, set_changes) = create_signal(0u64);
+
+    create_effect(move |_| {
+        let Ok(source) = web_sys::EventSource::new(&live_path(product)) else {
+            return;
+        };
+        let on_changed = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |_| {
+            set_changes.update(|changes| *changes += 1);
+        });
+        let _ = source
+            .add_event_listener_with_callback(CHANGED_EVENT, on_changed.as_ref().unchecked_ref());
+
+        on_cleanup(move || {
+            source.close();
+            drop(on_changed);
+        });
+    });
+
+    changes
+}
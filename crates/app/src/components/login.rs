@@ -3,7 +3,9 @@ use leptos_router::*;
 use std::time::Duration;
 use web_sys::SubmitEvent;
 
-use crate::{auth::passkeys::login_passkey, components::passkey_logo::PasskeyLogo};
+use crate::{
+    auth::passkeys::login_passkey, components::passkey_logo::PasskeyLogo, prefix::prefixed,
+};
 
 #[allow(non_snake_case)]
 #[component]
@@ -58,7 +60,7 @@ pub fn LoginPage(trigger: RwSignal<i64>) -> impl IntoView {
         let result = web_sys::window()
             .expect("Failed to get window")
             .location()
-            .set_href("/crashes");
+            .set_href(&prefixed("/crashes"));
         if let Err(e) = result {
             logging::error!("failed to reload: {:?}", e);
         }
@@ -1,17 +1,27 @@
+pub mod branding;
 pub mod confirmation;
 pub mod crash;
 pub mod crashes;
+pub mod dashboard;
 pub mod datatable;
 pub mod datatable_form;
 pub mod datatable_header;
+pub mod device_keys;
 pub mod error_template;
+pub mod issues;
+pub mod live;
 pub mod login;
 pub mod logout;
 pub mod navbar;
+pub mod organizations;
 pub mod passkey_logo;
+pub mod playground;
 pub mod products;
 pub mod profile;
 pub mod register;
+pub mod setup;
 pub mod symbols;
+pub mod tokens;
 pub mod users;
 pub mod versions;
+pub mod webhooks;
@@ -1,14 +1,93 @@
 use leptos::*;
 
-use crate::{components::logout::LogoutButton, UserResource};
+use crate::data_providers::search::{global_search, SearchResult, MIN_SEARCH_LENGTH};
+use crate::{components::logout::LogoutButton, prefix::prefixed, BrandingResource, UserResource};
 
+/// Search box for crash ids, signatures and build ids, with the matches in a dropdown. Enter
+/// opens the first match.
 #[allow(non_snake_case)]
 #[component]
-pub fn Navbar(trigger: RwSignal<i64>, user: UserResource) -> impl IntoView {
+fn GlobalSearch() -> impl IntoView {
+    let query = create_rw_signal(String::new());
+    let results = create_resource(
+        move || query.get(),
+        |query| async move {
+            if query.trim().chars().count() < MIN_SEARCH_LENGTH {
+                return vec![];
+            }
+            global_search(query).await.unwrap_or_default()
+        },
+    );
+    let open_first = move || {
+        let first = results
+            .get_untracked()
+            .and_then(|results| results.into_iter().next());
+        if let Some(result) = first {
+            let _ = window().location().set_href(&prefixed(&result.url));
+        }
+    };
+    let result_view = |result: SearchResult| {
+        view! {
+            <li>
+                <a href=prefixed(&result.url) class="flex flex-col items-start gap-0">
+                    <span>
+                        <span class="badge badge-sm badge-outline mr-2">{result.kind}</span>
+                        {result.title}
+                    </span>
+                    <span class="text-xs opacity-60 truncate w-full">
+                        {result.product} " " {result.detail}
+                    </span>
+                </a>
+            </li>
+        }
+    };
+
+    view! {
+        <div class="dropdown dropdown-end">
+            <input
+                type="search"
+                placeholder="Crash id, signature or build id"
+                class="input input-sm input-bordered w-64"
+                prop:value=query
+                on:input=move |ev| query.set(event_target_value(&ev))
+                on:keydown=move |ev| {
+                    if ev.key() == "Enter" {
+                        open_first();
+                    }
+                }
+            />
+            <Transition fallback=|| ()>
+                {move || {
+                    results
+                        .get()
+                        .filter(|results| !results.is_empty())
+                        .map(|results| {
+                            view! {
+                                <ul class="menu dropdown-content z-[1] mt-1 p-1 shadow bg-base-100 rounded-box w-96">
+                                    {results.into_iter().map(result_view).collect_view()}
+                                </ul>
+                            }
+                        })
+                }}
+            </Transition>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn Navbar(
+    trigger: RwSignal<i64>,
+    user: UserResource,
+    branding: BrandingResource,
+) -> impl IntoView {
     let user_area = move || match user.get().and_then(|u| u) {
         Some(user) => view! {
             <li>
-                <a class="px-2" href="/auth/profile">
+                <GlobalSearch/>
+            </li>
+            <li>
+                <a class="px-2" href=prefixed("/auth/profile")>
                     {{ user.username }}
                 </a>
             </li>
@@ -18,12 +97,12 @@ pub fn Navbar(trigger: RwSignal<i64>, user: UserResource) -> impl IntoView {
         },
         None => view! {
             <li>
-                <a class="px-2" href="/auth/login">
+                <a class="px-2" href=prefixed("/auth/login")>
                     login
                 </a>
             </li>
             <li>
-                <a class="px-2" href="/auth/register">
+                <a class="px-2" href=prefixed("/auth/register")>
                     register
                 </a>
             </li>
@@ -42,7 +121,7 @@ pub fn Navbar(trigger: RwSignal<i64>, user: UserResource) -> impl IntoView {
 
         </script>
 
-        <div class="navbar bg-base-200 rounded-lg relative z-10 p-0">
+        <div class="navbar brand-navbar bg-base-200 rounded-lg relative z-10 p-0">
             <div class="navbar-start">
                 <div class="dropdown">
                     <div tabindex="0" role="button" class="btn btn-ghost lg:hidden">
@@ -66,51 +145,91 @@ pub fn Navbar(trigger: RwSignal<i64>, user: UserResource) -> impl IntoView {
                         class="menu menu-sm dropdown-content mt-3 z-[1] p-1 shadow bg-base-100 rounded-box w-52"
                     >
                         <li>
-                            <a href="/crashes">Crashes</a>
+                            <a href=prefixed("/issues")>Issues</a>
+                        </li>
+                        <li>
+                            <a href=prefixed("/crashes")>Crashes</a>
                         </li>
                         <li>
-                            <a href="/symbols">Symbols</a>
+                            <a href=prefixed("/symbols")>Symbols</a>
                         </li>
                         <li>
                             <details>
                                 <summary>Admin</summary>
                                 <ul class="p-2">
                                     <li>
-                                        <a href="/admin/products">Products</a>
+                                        <a href=prefixed("/admin/products")>Products</a>
                                     </li>
                                     <li>
-                                        <a href="/admin/versions">Versions</a>
+                                        <a href=prefixed("/admin/versions")>Versions</a>
                                     </li>
                                     <li>
-                                        <a href="/admin/users">Users</a>
+                                        <a href=prefixed("/admin/users")>Users</a>
+                                    </li>
+                                    <li>
+                                        <a href=prefixed("/admin/tokens")>Tokens</a>
+                                    </li>
+                                    <li>
+                                        <a href=prefixed("/admin/playground")>API playground</a>
+                                    </li>
+                                    <li>
+                                        <a href=prefixed("/admin/organizations")>Organizations</a>
+                                    </li>
+                                    <li>
+                                        <a href=prefixed("/admin/branding")>Branding</a>
                                     </li>
                                 </ul>
                             </details>
                         </li>
                     </ul>
                 </div>
-                <a class="btn btn-ghost text-l">Guardrail</a>
+                <a class="btn btn-ghost text-l brand-title">
+                    {move || {
+                        let branding = branding.get().unwrap_or_default();
+                        view! {
+                            {branding
+                                .logo_url
+                                .map(|url| view! { <img src=url alt="" class="h-6 max-w-32"/> })}
+                            {branding.title}
+                        }
+                    }}
+                </a>
             </div>
             <div class="navbar-center hidden lg:flex">
                 <ul class="menu menu-horizontal px-1">
                     <li>
-                        <a href="/crashes">Crashes</a>
+                        <a href=prefixed("/issues")>Issues</a>
                     </li>
                     <li>
-                        <a href="/symbols">Symbols</a>
+                        <a href=prefixed("/crashes")>Crashes</a>
+                    </li>
+                    <li>
+                        <a href=prefixed("/symbols")>Symbols</a>
                     </li>
                     <li>
                         <details class="dropdown">
                             <summary>Admin</summary>
                             <ul class="menu mt-0 dropdown-content z-[1] bg-base-200 rounded-box w-52">
                                 <li>
-                                    <a href="/admin/products">Products</a>
+                                    <a href=prefixed("/admin/products")>Products</a>
+                                </li>
+                                <li>
+                                    <a href=prefixed("/admin/versions")>Versions</a>
+                                </li>
+                                <li>
+                                    <a href=prefixed("/admin/users")>Users</a>
+                                </li>
+                                <li>
+                                    <a href=prefixed("/admin/tokens")>Tokens</a>
+                                </li>
+                                <li>
+                                    <a href=prefixed("/admin/playground")>API playground</a>
                                 </li>
                                 <li>
-                                    <a href="/admin/versions">Versions</a>
+                                    <a href=prefixed("/admin/organizations")>Organizations</a>
                                 </li>
                                 <li>
-                                    <a href="/admin/users">Users</a>
+                                    <a href=prefixed("/admin/branding")>Branding</a>
                                 </li>
                             </ul>
                         </details>
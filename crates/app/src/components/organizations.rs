@@ -0,0 +1,364 @@
+use leptos::*;
+use std::collections::VecDeque;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::data::QueryParams;
+use crate::data_providers::organization::{
+    organization_add, organization_add_member, organization_list, organization_members,
+    organization_remove, organization_remove_member, organization_set_product,
+};
+use crate::data_providers::product::product_list;
+use crate::data_providers::user::{user_list, ROLES, ROLE_VIEWER};
+
+fn parse_id(value: String) -> Option<Uuid> {
+    Uuid::parse_str(&value).ok()
+}
+
+fn all_items() -> QueryParams {
+    QueryParams {
+        sorting: VecDeque::new(),
+        range: 0..1000,
+        filter: String::new(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn OrganizationsPage() -> impl IntoView {
+    let selected = create_rw_signal(None::<Uuid>);
+    let name = create_rw_signal(String::new());
+    let product = create_rw_signal(None::<Uuid>);
+    let member = create_rw_signal(None::<Uuid>);
+    let role = create_rw_signal(ROLE_VIEWER.to_string());
+
+    let add = create_action(|name: &String| {
+        let name = name.clone();
+        async move { organization_add(name).await }
+    });
+    let remove = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { organization_remove(id).await }
+    });
+    let set_product = create_action(|(product_id, id): &(Uuid, Option<Uuid>)| {
+        let (product_id, id) = (*product_id, *id);
+        async move { organization_set_product(product_id, id).await }
+    });
+    let add_member = create_action(|(id, user_id, role): &(Uuid, Uuid, String)| {
+        let (id, user_id, role) = (*id, *user_id, role.clone());
+        async move { organization_add_member(id, user_id, role).await }
+    });
+    let remove_member = create_action(|(id, user_id): &(Uuid, Uuid)| {
+        let (id, user_id) = (*id, *user_id);
+        async move { organization_remove_member(id, user_id).await }
+    });
+
+    let organizations = create_resource(
+        move || {
+            (
+                add.version().get(),
+                remove.version().get(),
+                set_product.version().get(),
+            )
+        },
+        |_| async move {
+            organization_list().await.unwrap_or_else(|e| {
+                error!("Failed to fetch organizations: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let users = create_resource(
+        || (),
+        |_| async move {
+            user_list(all_items()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch users: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list(all_items()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch products: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let members = create_resource(
+        move || {
+            (
+                selected.get(),
+                add_member.version().get(),
+                remove_member.version().get(),
+            )
+        },
+        |(id, _, _)| async move {
+            match id {
+                Some(id) => organization_members(id).await.unwrap_or_else(|e| {
+                    error!("Failed to fetch members: {:?}", e);
+                    vec![]
+                }),
+                None => vec![],
+            }
+        },
+    );
+
+    let result = move || {
+        add.value()
+            .get()
+            .or_else(|| remove.value().get())
+            .or_else(|| set_product.value().get())
+            .or_else(|| add_member.value().get())
+            .or_else(|| remove_member.value().get())
+            .and_then(|result| result.err())
+            .map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Organizations"</h2>
+                <p class="text-sm">
+                    "Members of an organization get their role in the organization for all its products. "
+                    "Products of an organization are only visible to its members, and to users with a role for the product itself."
+                </p>
+                <div class="flex flex-wrap items-center gap-2 my-2">
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="Name"
+                        prop:value=move || name.get()
+                        on:input=move |ev| name.set(event_target_value(&ev))
+                    />
+                    <button
+                        class="btn btn-sm"
+                        disabled=move || add.pending().get() || name.get().trim().is_empty()
+                        on:click=move |_| {
+                            add.dispatch(name.get());
+                            name.set(String::new());
+                        }
+                    >
+                        "Add"
+                    </button>
+                </div>
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    <table class="table table-sm w-full">
+                        <thead>
+                            <tr>
+                                <th>"Name"</th>
+                                <th>"Products"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {move || {
+                                let organizations = organizations.get().unwrap_or_default();
+                                if organizations.is_empty() {
+                                    return view! {
+                                        <tr>
+                                            <td colspan="3">"No organizations"</td>
+                                        </tr>
+                                    }
+                                    .into_view();
+                                }
+                                organizations
+                                    .into_iter()
+                                    .map(|organization| {
+                                        let id = organization.id;
+                                        let products = organization
+                                            .products
+                                            .into_iter()
+                                            .map(|(product_id, name)| {
+                                                view! {
+                                                    <span class="badge badge-outline gap-1 mr-1">
+                                                        {name}
+                                                        <button
+                                                            class="btn btn-ghost btn-xs px-1"
+                                                            disabled=move || set_product.pending().get()
+                                                            on:click=move |_| set_product.dispatch((product_id, None))
+                                                        >
+                                                            "x"
+                                                        </button>
+                                                    </span>
+                                                }
+                                            })
+                                            .collect_view();
+                                        view! {
+                                            <tr class:bg-base-300=move || selected.get() == Some(id)>
+                                                <td>
+                                                    <a
+                                                        class="link"
+                                                        on:click=move |_| selected.set(Some(id))
+                                                    >
+                                                        {organization.name}
+                                                    </a>
+                                                </td>
+                                                <td>{products}</td>
+                                                <td>
+                                                    <button
+                                                        class="btn btn-error btn-xs"
+                                                        disabled=move || remove.pending().get()
+                                                        on:click=move |_| {
+                                                            if selected.get() == Some(id) {
+                                                                selected.set(None);
+                                                            }
+                                                            remove.dispatch(id);
+                                                        }
+                                                    >
+                                                        "Remove"
+                                                    </button>
+                                                </td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect_view()
+                            }}
+                        </tbody>
+                    </table>
+                </Transition>
+                {result}
+            </div>
+        </div>
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Members and products"</h2>
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    <Show
+                        when=move || selected.get().is_some()
+                        fallback=|| view! { <p class="text-sm">"Select an organization above"</p> }
+                    >
+                        <div class="flex flex-wrap items-center gap-2 my-2">
+                            <select
+                                class="select select-bordered select-sm"
+                                on:change=move |ev| product.set(parse_id(event_target_value(&ev)))
+                            >
+                                <option value="">"Select product"</option>
+                                {move || {
+                                    products
+                                        .get()
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|product| {
+                                            view! { <option value=product.id.to_string()>{product.name}</option> }
+                                        })
+                                        .collect_view()
+                                }}
+                            </select>
+                            <button
+                                class="btn btn-sm"
+                                disabled=move || set_product.pending().get() || product.get().is_none()
+                                on:click=move |_| {
+                                    if let Some(product_id) = product.get() {
+                                        set_product.dispatch((product_id, selected.get()));
+                                    }
+                                }
+                            >
+                                "Add product"
+                            </button>
+                        </div>
+                        <div class="flex flex-wrap items-center gap-2 my-2">
+                            <select
+                                class="select select-bordered select-sm"
+                                on:change=move |ev| member.set(parse_id(event_target_value(&ev)))
+                            >
+                                <option value="">"Select user"</option>
+                                {move || {
+                                    users
+                                        .get()
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|user| {
+                                            view! { <option value=user.id.to_string()>{user.username}</option> }
+                                        })
+                                        .collect_view()
+                                }}
+                            </select>
+                            <select
+                                class="select select-bordered select-sm"
+                                on:change=move |ev| role.set(event_target_value(&ev))
+                            >
+                                {ROLES
+                                    .iter()
+                                    .map(|(name, label)| {
+                                        view! {
+                                            <option value=*name selected={*name == ROLE_VIEWER}>
+                                                {*label}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                            <button
+                                class="btn btn-sm"
+                                disabled=move || add_member.pending().get() || member.get().is_none()
+                                on:click=move |_| {
+                                    if let (Some(id), Some(user_id)) = (selected.get(), member.get()) {
+                                        add_member.dispatch((id, user_id, role.get()));
+                                    }
+                                }
+                            >
+                                "Add member"
+                            </button>
+                        </div>
+                        <table class="table table-sm w-full">
+                            <thead>
+                                <tr>
+                                    <th>"User"</th>
+                                    <th>"Role"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {move || {
+                                    let members = members.get().unwrap_or_default();
+                                    if members.is_empty() {
+                                        return view! {
+                                            <tr>
+                                                <td colspan="3">"No members"</td>
+                                            </tr>
+                                        }
+                                        .into_view();
+                                    }
+                                    members
+                                        .into_iter()
+                                        .map(|organization_member| {
+                                            let user_id = organization_member.user_id;
+                                            let label = ROLES
+                                                .iter()
+                                                .find(|(name, _)| *name == organization_member.role)
+                                                .map(|(_, label)| label.to_string())
+                                                .unwrap_or(organization_member.role);
+                                            view! {
+                                                <tr>
+                                                    <td>{organization_member.username}</td>
+                                                    <td>{label}</td>
+                                                    <td>
+                                                        <button
+                                                            class="btn btn-error btn-xs"
+                                                            disabled=move || remove_member.pending().get()
+                                                            on:click=move |_| {
+                                                                if let Some(id) = selected.get() {
+                                                                    remove_member.dispatch((id, user_id));
+                                                                }
+                                                            }
+                                                        >
+                                                            "Remove"
+                                                        </button>
+                                                    </td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()
+                                }}
+                            </tbody>
+                        </table>
+                    </Show>
+                </Transition>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,286 @@
+use leptos::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    wasm_bindgen::{JsCast, JsValue},
+    Request, RequestInit, RequestMode, Response,
+};
+
+use crate::authenticated_user_is_admin;
+use crate::prefix::prefixed;
+
+// Lets administrators try the APIs of the server from the browser, with a personal API token.
+// The endpoints are taken from the OpenAPI document of the server. Requests are sent by the
+// browser to this server only, so the token never passes through another service, and it is
+// not stored outside the page.
+
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoint {
+    method: String,
+    path: String,
+    summary: String,
+}
+
+#[derive(Debug, Clone)]
+struct PlaygroundRequest {
+    method: String,
+    path: String,
+    token: String,
+    body: String,
+}
+
+#[derive(Debug, Clone)]
+struct PlaygroundResponse {
+    status: u16,
+    body: String,
+}
+
+/// Returns the operations of the `paths` of an OpenAPI document.
+fn endpoints(document: &serde_json::Value) -> Vec<Endpoint> {
+    let Some(paths) = document["paths"].as_object() else {
+        return vec![];
+    };
+    paths
+        .iter()
+        .flat_map(|(path, operations)| {
+            operations
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(move |(method, operation)| Endpoint {
+                    method: method.clone(),
+                    path: path.clone(),
+                    summary: operation["summary"].as_str().unwrap_or_default().to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// Sends the request to this server, relative to the path prefix, and returns the response.
+async fn fetch(request: PlaygroundRequest) -> Result<PlaygroundResponse, JsValue> {
+    let mut opts = RequestInit::new();
+    opts.method(&request.method.to_uppercase());
+    opts.mode(RequestMode::SameOrigin);
+    if !request.body.is_empty() {
+        opts.body(Some(&JsValue::from_str(&request.body)));
+    }
+
+    let dest = prefixed(&request.path);
+    let http_request = Request::new_with_str_and_init(&dest, &opts)?;
+    if !request.token.is_empty() {
+        http_request
+            .headers()
+            .set("authorization", &format!("Bearer {}", request.token))?;
+    }
+    if !request.body.is_empty() {
+        http_request
+            .headers()
+            .set("content-type", "application/json")?;
+    }
+
+    let resp_value = JsFuture::from(window().fetch_with_request(&http_request)).await?;
+    let resp: Response = resp_value.dyn_into()?;
+    let body = JsFuture::from(resp.text()?)
+        .await?
+        .as_string()
+        .unwrap_or_default();
+    Ok(PlaygroundResponse {
+        status: resp.status(),
+        body,
+    })
+}
+
+async fn fetch_endpoints() -> Result<Vec<Endpoint>, String> {
+    let response = fetch(PlaygroundRequest {
+        method: "get".to_owned(),
+        path: "/api/openapi.json".to_owned(),
+        token: String::new(),
+        body: String::new(),
+    })
+    .await
+    .map_err(|e| format!("{:?}", e))?;
+    let document = serde_json::from_str(&response.body).map_err(|e| e.to_string())?;
+    Ok(endpoints(&document))
+}
+
+/// Returns the body pretty printed if it is JSON.
+fn pretty(body: String) -> String {
+    serde_json::from_str::<serde_json::Value>(&body)
+        .and_then(|json| serde_json::to_string_pretty(&json))
+        .unwrap_or(body)
+}
+
+/// The API playground, for administrators.
+#[allow(non_snake_case)]
+#[component]
+pub fn PlaygroundPage() -> impl IntoView {
+    let is_admin = create_resource(
+        || (),
+        |_| async move { authenticated_user_is_admin().await.unwrap_or(false) },
+    );
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || {
+                is_admin
+                    .get()
+                    .map(|is_admin| match is_admin {
+                        true => view! { <Playground/> }.into_view(),
+                        false => view! {
+                            <div class="alert alert-warning rounded-btn my-2 p-3">
+                                "The API playground is only available to administrators"
+                            </div>
+                        }
+                        .into_view(),
+                    })
+            }}
+        </Transition>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn Playground() -> impl IntoView {
+    let token = create_rw_signal(String::new());
+    let method = create_rw_signal("get".to_owned());
+    let path = create_rw_signal(String::new());
+    let body = create_rw_signal(String::new());
+
+    let endpoints = create_local_resource(|| (), |_| fetch_endpoints());
+    let send = create_action(|request: &PlaygroundRequest| {
+        let request = request.clone();
+        async move { fetch(request).await.map_err(|e| format!("{:?}", e)) }
+    });
+
+    let select_endpoint = move |index: String| {
+        let endpoint = index.parse::<usize>().ok().and_then(|index| {
+            endpoints
+                .get()
+                .and_then(|endpoints| endpoints.ok())
+                .and_then(|endpoints| endpoints.get(index).cloned())
+        });
+        if let Some(endpoint) = endpoint {
+            method.set(endpoint.method);
+            path.set(endpoint.path);
+        }
+    };
+
+    let response = move || {
+        send.value().get().map(|result| match result {
+            Ok(response) => {
+                let class = match response.status {
+                    200..=299 => "badge badge-success",
+                    _ => "badge badge-error",
+                };
+                view! {
+                    <div class="flex items-center gap-2">
+                        <span class=class>{response.status}</span>
+                    </div>
+                    <pre class="text-xs overflow-x-auto max-h-96">{pretty(response.body)}</pre>
+                }
+                .into_view()
+            }
+            Err(e) => {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e}</div> }.into_view()
+            }
+        })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"API playground"</h2>
+                <p class="text-sm">
+                    "Requests are sent from your browser to this server only, with the API token "
+                    "below. The token is not stored; it is lost when you leave the page. "
+                    "Requests that change data, such as POST and DELETE, are not simulated."
+                </p>
+                <input
+                    type="password"
+                    class="input input-bordered input-sm"
+                    placeholder="personal API token"
+                    autocomplete="off"
+                    prop:value=move || token.get()
+                    on:input=move |ev| token.set(event_target_value(&ev))
+                />
+                <select
+                    class="select select-bordered select-sm"
+                    on:change=move |ev| select_endpoint(event_target_value(&ev))
+                >
+                    <option value="" selected>
+                        "Select an endpoint"
+                    </option>
+                    {move || match endpoints.get() {
+                        Some(Ok(endpoints)) => endpoints
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, endpoint)| {
+                                view! {
+                                    <option value=index.to_string()>
+                                        {format!(
+                                            "{} {} - {}",
+                                            endpoint.method.to_uppercase(),
+                                            endpoint.path,
+                                            endpoint.summary,
+                                        )}
+                                    </option>
+                                }
+                            })
+                            .collect_view(),
+                        Some(Err(e)) => view! {
+                            <option disabled>{format!("Failed to load endpoints: {}", e)}</option>
+                        }
+                        .into_view(),
+                        None => ().into_view(),
+                    }}
+                </select>
+                <div class="flex items-center gap-2">
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| method.set(event_target_value(&ev))
+                    >
+                        {METHODS
+                            .into_iter()
+                            .map(|name| {
+                                view! {
+                                    <option value=name selected=move || method.get() == name>
+                                        {name.to_uppercase()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm grow font-mono"
+                        placeholder="/read/product"
+                        prop:value=move || path.get()
+                        on:input=move |ev| path.set(event_target_value(&ev))
+                    />
+                    <button
+                        class="btn btn-primary btn-sm"
+                        disabled=move || path.get().is_empty() || send.pending().get()
+                        on:click=move |_| {
+                            send.dispatch(PlaygroundRequest {
+                                method: method.get(),
+                                path: path.get(),
+                                token: token.get(),
+                                body: body.get(),
+                            })
+                        }
+                    >
+                        "Send"
+                    </button>
+                </div>
+                <textarea
+                    class="textarea textarea-bordered textarea-sm font-mono"
+                    placeholder="JSON request body"
+                    prop:value=move || body.get()
+                    on:input=move |ev| body.set(event_target_value(&ev))
+                ></textarea>
+                {response}
+            </div>
+        </div>
+    }
+}
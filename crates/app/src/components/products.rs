@@ -7,7 +7,7 @@ use std::ops::Range;
 use uuid::Uuid;
 
 use super::datatable::{Capabilities, DataTableTrait};
-use super::datatable_form::{FieldString, Fields};
+use super::datatable_form::{FieldCheckbox, FieldString, Fields};
 use crate::components::datatable::DataTable;
 use crate::components::datatable_form::Field;
 use crate::data::QueryParams;
@@ -18,6 +18,42 @@ use crate::data_providers::product::{
 use crate::data_providers::ExtraTableDataProvider;
 use crate::{authenticated_user_is_admin, table_data_provider_impl};
 
+const MAX_BUILD_AGE: &str = "Max build age in days (empty for no limit)";
+const BUILD_AGE_OVERRIDES: &str = "Build age per channel (e.g. beta=30, nightly=0 for no limit)";
+const BUILD_AGE_ALLOW_LIST: &str = "Versions always accepted (comma separated)";
+const PROMOTED_ANNOTATIONS: &str = "Annotations promoted to filterable columns (comma separated)";
+const REDACTED_FIELDS: &str =
+    "Report fields hidden from non-admins (e.g. crash_info.address, modules.*.base_address)";
+const ALLOWED_CONTENT_TYPES: &str =
+    "Accepted upload content types (e.g. attachment=text/plain, attachment=image/*)";
+const ENVIRONMENTS: &str = "Environments (e.g. production, staging, dev; empty accepts any)";
+const ACCEPTING_CRASHES: &str = "Accepting crashes";
+const PUBLIC_SUBMISSIONS: &str = "Public submissions (accept crashes without a token)";
+const AUTO_CREATE_VERSIONS: &str = "Create unknown versions on upload (otherwise rejected)";
+const REPOSITORY_URL: &str = "Source repository (e.g. https://github.com/owner/repo)";
+const SOURCE_ROOTS: &str = "Build directories of the sources (comma separated, e.g. /build/src)";
+const ATTACHMENT_PROCESSORS: &str = "Attachment processors (comma separated, e.g. log)";
+const DEFAULT_ANNOTATIONS: &str =
+    "Annotations added to every crash unless sent by the client (e.g. team=desktop, tier=gold)";
+const INGESTION_WINDOWS: &str =
+    "Ingestion windows in UTC, queue or reject (e.g. queue 01:00..03:00; reject 2025-01-12T22:00..2025-01-13T02:00 Upgrade)";
+
+const MAX_MINIDUMP_SIZE: &str = "Max minidump size in MB (empty for no limit)";
+const MAX_ATTACHMENT_SIZE: &str = "Max attachment size in MB (empty for no limit)";
+const MAX_ATTACHMENTS: &str = "Max attachments per crash (empty for no limit)";
+const MAX_DAILY_UPLOADS: &str = "Max uploads per day (empty for no limit)";
+
+const MEGABYTE: i64 = 1024 * 1024;
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
 #[derive(Debug, Clone)]
 pub struct ProductTable {
     sort: VecDeque<(usize, ColumnSort)>,
@@ -72,6 +108,14 @@ impl DataTableTrait for ProductTable {
                 name: "Crashes".to_string(),
                 url: "/admin/crashes?product=".to_string(),
             },
+            super::datatable::Related {
+                name: "Webhooks".to_string(),
+                url: "/admin/webhooks?product=".to_string(),
+            },
+            super::datatable::Related {
+                name: "Device keys".to_string(),
+                url: "/admin/device_keys?product=".to_string(),
+            },
         ]
     }
 
@@ -84,6 +128,30 @@ impl DataTableTrait for ProductTable {
     ) {
         create_effect(move |_| {
             let product_name = product.name.clone();
+            let max_build_age = product
+                .max_build_age_days
+                .map(|days| days.to_string())
+                .unwrap_or_default();
+            let overrides = product.build_age_overrides.clone().unwrap_or_default();
+            let allow_list = product.build_age_allow_list.clone().unwrap_or_default();
+            let promoted = product.promoted_annotations.clone().unwrap_or_default();
+            let redacted = product.redacted_fields.clone().unwrap_or_default();
+            let content_types = product.allowed_content_types.clone().unwrap_or_default();
+            let environments = product.environments.clone().unwrap_or_default();
+            let repository_url = product.repository_url.clone().unwrap_or_default();
+            let source_roots = product.source_roots.clone().unwrap_or_default();
+            let processors = product.attachment_processors.clone().unwrap_or_default();
+            let default_annotations = product.default_annotations.clone().unwrap_or_default();
+            let ingestion_windows = product.ingestion_windows.clone().unwrap_or_default();
+            let max_minidump_size = optional(product.max_minidump_size.map(|size| size / MEGABYTE));
+            let max_attachment_size =
+                optional(product.max_attachment_size.map(|size| size / MEGABYTE));
+            let max_attachments = optional(product.max_attachments);
+            let max_daily_uploads = optional(product.max_daily_uploads);
+            // New products accept crashes unless unchecked.
+            let accepting_crashes = product.accepting_crashes || product.id.is_nil();
+            let public_submissions = product.public_submissions;
+            let auto_create_versions = product.auto_create_versions;
             spawn_local(async move {
                 match product_list_names().await {
                     Ok(fetched_names) => {
@@ -92,6 +160,82 @@ impl DataTableTrait for ProductTable {
                                 "Name".to_string(),
                                 Field::new(FieldString::new(product_name, fetched_names)),
                             );
+                            field.insert(
+                                MAX_BUILD_AGE.to_string(),
+                                Field::new(FieldString::new(max_build_age, HashSet::new())),
+                            );
+                            field.insert(
+                                BUILD_AGE_OVERRIDES.to_string(),
+                                Field::new(FieldString::new(overrides, HashSet::new())),
+                            );
+                            field.insert(
+                                BUILD_AGE_ALLOW_LIST.to_string(),
+                                Field::new(FieldString::new(allow_list, HashSet::new())),
+                            );
+                            field.insert(
+                                PROMOTED_ANNOTATIONS.to_string(),
+                                Field::new(FieldString::new(promoted, HashSet::new())),
+                            );
+                            field.insert(
+                                REDACTED_FIELDS.to_string(),
+                                Field::new(FieldString::new(redacted, HashSet::new())),
+                            );
+                            field.insert(
+                                ALLOWED_CONTENT_TYPES.to_string(),
+                                Field::new(FieldString::new(content_types, HashSet::new())),
+                            );
+                            field.insert(
+                                ENVIRONMENTS.to_string(),
+                                Field::new(FieldString::new(environments, HashSet::new())),
+                            );
+                            field.insert(
+                                REPOSITORY_URL.to_string(),
+                                Field::new(FieldString::new(repository_url, HashSet::new())),
+                            );
+                            field.insert(
+                                SOURCE_ROOTS.to_string(),
+                                Field::new(FieldString::new(source_roots, HashSet::new())),
+                            );
+                            field.insert(
+                                ATTACHMENT_PROCESSORS.to_string(),
+                                Field::new(FieldString::new(processors, HashSet::new())),
+                            );
+                            field.insert(
+                                DEFAULT_ANNOTATIONS.to_string(),
+                                Field::new(FieldString::new(default_annotations, HashSet::new())),
+                            );
+                            field.insert(
+                                INGESTION_WINDOWS.to_string(),
+                                Field::new(FieldString::new(ingestion_windows, HashSet::new())),
+                            );
+                            field.insert(
+                                MAX_MINIDUMP_SIZE.to_string(),
+                                Field::new(FieldString::new(max_minidump_size, HashSet::new())),
+                            );
+                            field.insert(
+                                MAX_ATTACHMENT_SIZE.to_string(),
+                                Field::new(FieldString::new(max_attachment_size, HashSet::new())),
+                            );
+                            field.insert(
+                                MAX_ATTACHMENTS.to_string(),
+                                Field::new(FieldString::new(max_attachments, HashSet::new())),
+                            );
+                            field.insert(
+                                MAX_DAILY_UPLOADS.to_string(),
+                                Field::new(FieldString::new(max_daily_uploads, HashSet::new())),
+                            );
+                            field.insert(
+                                ACCEPTING_CRASHES.to_string(),
+                                Field::new(FieldCheckbox::new(accepting_crashes)),
+                            );
+                            field.insert(
+                                PUBLIC_SUBMISSIONS.to_string(),
+                                Field::new(FieldCheckbox::new(public_submissions)),
+                            );
+                            field.insert(
+                                AUTO_CREATE_VERSIONS.to_string(),
+                                Field::new(FieldCheckbox::new(auto_create_versions)),
+                            );
                         });
                     }
                     Err(e) => {
@@ -108,8 +252,58 @@ impl DataTableTrait for ProductTable {
         _parents: &HashMap<String, Uuid>,
     ) {
         let name = fields.get().get::<FieldString>("Name");
+        let max_build_age = fields.get().get::<FieldString>(MAX_BUILD_AGE);
+        let overrides = fields.get().get::<FieldString>(BUILD_AGE_OVERRIDES);
+        let allow_list = fields.get().get::<FieldString>(BUILD_AGE_ALLOW_LIST);
+        let promoted = fields.get().get::<FieldString>(PROMOTED_ANNOTATIONS);
+        let redacted = fields.get().get::<FieldString>(REDACTED_FIELDS);
+        let content_types = fields.get().get::<FieldString>(ALLOWED_CONTENT_TYPES);
+        let environments = fields.get().get::<FieldString>(ENVIRONMENTS);
+        let repository_url = fields.get().get::<FieldString>(REPOSITORY_URL);
+        let source_roots = fields.get().get::<FieldString>(SOURCE_ROOTS);
+        let processors = fields.get().get::<FieldString>(ATTACHMENT_PROCESSORS);
+        let default_annotations = fields.get().get::<FieldString>(DEFAULT_ANNOTATIONS);
+        let ingestion_windows = fields.get().get::<FieldString>(INGESTION_WINDOWS);
+        let max_minidump_size = fields.get().get::<FieldString>(MAX_MINIDUMP_SIZE);
+        let max_attachment_size = fields.get().get::<FieldString>(MAX_ATTACHMENT_SIZE);
+        let max_attachments = fields.get().get::<FieldString>(MAX_ATTACHMENTS);
+        let max_daily_uploads = fields.get().get::<FieldString>(MAX_DAILY_UPLOADS);
+        let accepting_crashes = fields.get().get::<FieldCheckbox>(ACCEPTING_CRASHES);
+        let public_submissions = fields.get().get::<FieldCheckbox>(PUBLIC_SUBMISSIONS);
+        let auto_create_versions = fields.get().get::<FieldCheckbox>(AUTO_CREATE_VERSIONS);
 
         product.name = name.value.get();
+        product.max_build_age_days = max_build_age.value.get().trim().parse().ok();
+        product.build_age_overrides = non_empty(overrides.value.get());
+        product.build_age_allow_list = non_empty(allow_list.value.get());
+        product.promoted_annotations = non_empty(promoted.value.get());
+        product.redacted_fields = non_empty(redacted.value.get());
+        product.allowed_content_types = non_empty(content_types.value.get());
+        product.environments = non_empty(environments.value.get());
+        product.repository_url = non_empty(repository_url.value.get());
+        product.source_roots = non_empty(source_roots.value.get());
+        product.attachment_processors = non_empty(processors.value.get());
+        product.default_annotations = non_empty(default_annotations.value.get());
+        product.ingestion_windows = non_empty(ingestion_windows.value.get());
+        product.max_minidump_size = max_minidump_size
+            .value
+            .get()
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(|size| size * MEGABYTE);
+        product.max_attachment_size = max_attachment_size
+            .value
+            .get()
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(|size| size * MEGABYTE);
+        product.max_attachments = max_attachments.value.get().trim().parse().ok();
+        product.max_daily_uploads = max_daily_uploads.value.get().trim().parse().ok();
+        product.accepting_crashes = accepting_crashes.value.get();
+        product.public_submissions = public_submissions.value.get();
+        product.auto_create_versions = auto_create_versions.value.get();
         if product.id.is_nil() {
             product.id = Uuid::new_v4();
         }
@@ -1,7 +1,9 @@
 use leptos::*;
 
+use crate::components::tokens::ApiTokens;
+
 #[allow(non_snake_case)]
 #[component]
 pub fn ProfilePage() -> impl IntoView {
-    view! {}
+    view! { <ApiTokens all=false/> }
 }
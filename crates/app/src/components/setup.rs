@@ -0,0 +1,274 @@
+use leptos::*;
+use uuid::Uuid;
+
+use crate::auth::passkeys::register_passkey;
+use crate::authenticated_user;
+use crate::components::passkey_logo::PasskeyLogo;
+use crate::data_providers::device_key::device_key_create;
+use crate::data_providers::product::{product_add, Product};
+use crate::data_providers::setup::{setup_ingest_url, setup_required};
+use crate::prefix::prefixed;
+
+// A new installation has no users, so nobody can log in to create products and credentials. The
+// setup page walks the first user through registering, which makes them an administrator, and
+// creating a product with a device key, and ends with the configuration of the crash reporting
+// client.
+
+/// Name of the device key created during setup.
+const SETUP_KEY_NAME: &str = "default";
+
+/// Returns the client configuration for the device key.
+fn client_config(url: &str, product: &str, key_id: Uuid, key: &str) -> String {
+    format!(
+        "GUARDRAIL_URL={}\nGUARDRAIL_PRODUCT={}\nGUARDRAIL_KEY_ID={}\nGUARDRAIL_KEY={}\n",
+        url, product, key_id, key
+    )
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn SetupPage(trigger: RwSignal<i64>) -> impl IntoView {
+    let product = create_rw_signal(None::<(Uuid, String)>);
+    let device_key = create_rw_signal(None::<(Uuid, String)>);
+
+    let required = create_resource(
+        || (),
+        |_| async move { setup_required().await.unwrap_or(false) },
+    );
+    let user = create_resource(
+        move || trigger.get(),
+        |_| async move { authenticated_user().await.unwrap_or(None) },
+    );
+
+    // `required` is not fetched again after the administrator registered, so the remaining steps
+    // are only offered in the session that started the setup.
+    let step = move || {
+        let is_admin = user.get().flatten().is_some_and(|user| user.is_admin);
+        let required = required.get().unwrap_or(false);
+        match (product.get(), device_key.get()) {
+            (Some((_, product)), Some((key_id, key))) => {
+                view! { <ClientConfig product key_id key/> }.into_view()
+            }
+            (Some((product_id, product)), None) => {
+                view! { <DeviceKeyStep product_id product device_key/> }.into_view()
+            }
+            _ if required && is_admin => view! { <ProductStep product/> }.into_view(),
+            _ if required => view! { <AdminStep trigger/> }.into_view(),
+            _ => view! {
+                <p class="text-sm">
+                    "This server has been set up. "
+                    <a class="link" href=prefixed("/auth/login")>
+                        "Log in"
+                    </a> " to manage products and device keys."
+                </p>
+            }
+            .into_view(),
+        }
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2 max-w-2xl w-full mx-auto">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Set up Guardrail"</h2>
+                <Transition fallback=move || view! { <span class="loading loading-dots"></span> }>
+                    {step}
+                </Transition>
+            </div>
+        </div>
+    }
+}
+
+/// Registers the first user, who becomes an administrator and is logged in.
+#[allow(non_snake_case)]
+#[component]
+fn AdminStep(trigger: RwSignal<i64>) -> impl IntoView {
+    let username = create_rw_signal(String::new());
+    let register = create_action(|username: &String| {
+        let username = username.trim().to_owned();
+        async move { register_passkey(username).await }
+    });
+    create_effect(move |_| {
+        if let Some(Ok(())) = register.value().get() {
+            trigger.update(|n| *n += 1);
+        }
+    });
+
+    let result = move || {
+        register.value().get().and_then(Result::err).map(|e| {
+            view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            }
+        })
+    };
+
+    view! {
+        <p class="text-sm">
+            "1/3: Register the administrator of this server with a passkey."
+        </p>
+        <div class="form-control w-full max-w-md">
+            <label class="label">
+                <span class="label-text">"Username"</span>
+            </label>
+            <input
+                type="text"
+                class="input input-bordered input-sm"
+                autocapitalize="none"
+                placeholder="user name"
+                prop:value=move || username.get()
+                on:input=move |ev| username.set(event_target_value(&ev))
+            />
+        </div>
+        <div class="my-2">
+            <button
+                class="btn btn-sm btn-primary"
+                disabled=move || register.pending().get() || username.get().trim().is_empty()
+                on:click=move |_| register.dispatch(username.get())
+            >
+                <PasskeyLogo/>
+                "Register with Passkey"
+            </button>
+        </div>
+        {result}
+    }
+}
+
+/// Creates the first product, accepting crashes.
+#[allow(non_snake_case)]
+#[component]
+fn ProductStep(product: RwSignal<Option<(Uuid, String)>>) -> impl IntoView {
+    let name = create_rw_signal(String::new());
+    let create = create_action(|name: &String| {
+        let product = Product {
+            id: Uuid::new_v4(),
+            name: name.trim().to_owned(),
+            accepting_crashes: true,
+            ..Default::default()
+        };
+        async move {
+            let (id, name) = (product.id, product.name.clone());
+            product_add(product).await.map(|()| (id, name))
+        }
+    });
+    create_effect(move |_| {
+        if let Some(Ok(created)) = create.value().get() {
+            product.set(Some(created));
+        }
+    });
+
+    let result = move || {
+        create.value().get().and_then(Result::err).map(|e| {
+            view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            }
+        })
+    };
+
+    view! {
+        <p class="text-sm">
+            "2/3: Create the product that crashes are reported for. "
+            "Products can be configured further under Admin, Products."
+        </p>
+        <div class="form-control w-full max-w-md">
+            <label class="label">
+                <span class="label-text">"Product name"</span>
+            </label>
+            <input
+                type="text"
+                class="input input-bordered input-sm"
+                placeholder="Workrave"
+                prop:value=move || name.get()
+                on:input=move |ev| name.set(event_target_value(&ev))
+            />
+        </div>
+        <div class="my-2">
+            <button
+                class="btn btn-sm btn-primary"
+                disabled=move || create.pending().get() || name.get().trim().is_empty()
+                on:click=move |_| create.dispatch(name.get())
+            >
+                "Create product"
+            </button>
+        </div>
+        {result}
+    }
+}
+
+/// Creates the device key that clients sign their uploads with.
+#[allow(non_snake_case)]
+#[component]
+fn DeviceKeyStep(
+    product_id: Uuid,
+    product: String,
+    device_key: RwSignal<Option<(Uuid, String)>>,
+) -> impl IntoView {
+    let create = create_action(move |_: &()| async move {
+        device_key_create(product_id, SETUP_KEY_NAME.to_owned()).await
+    });
+    create_effect(move |_| {
+        if let Some(Ok(created)) = create.value().get() {
+            device_key.set(Some(created));
+        }
+    });
+
+    let result = move || {
+        create.value().get().and_then(Result::err).map(|e| {
+            view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            }
+        })
+    };
+
+    view! {
+        <p class="text-sm">
+            "3/3: Create a device key for " {product}
+            ". Crash reporting clients sign their uploads with the key."
+        </p>
+        <div class="my-2">
+            <button
+                class="btn btn-sm btn-primary"
+                disabled=move || create.pending().get()
+                on:click=move |_| create.dispatch(())
+            >
+                "Create device key"
+            </button>
+        </div>
+        {result}
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn ClientConfig(product: String, key_id: Uuid, key: String) -> impl IntoView {
+    let url = create_resource(
+        || (),
+        |_| async move { setup_ingest_url().await.unwrap_or_default() },
+    );
+
+    let config = move || {
+        url.get()
+            .map(|url| client_config(&url, &product, key_id, &key))
+    };
+    let endpoints = move || {
+        url.get().map(|url| {
+            format!(
+                "Clients sign uploads to {url}/minidump/upload, or exchange a signed request to \
+                 {url}/token for a short-lived upload token."
+            )
+        })
+    };
+
+    view! {
+        <div class="alert alert-success rounded-btn my-2 p-3 flex flex-col items-start">
+            <span class="font-semibold">
+                "Copy the configuration now, the key will not be shown again"
+            </span>
+            <pre class="select-all whitespace-pre-wrap break-all text-sm">{config}</pre>
+        </div>
+        <p class="text-sm">{endpoints}</p>
+        <div class="my-2">
+            <a class="btn btn-sm" href=prefixed("/")>
+                "Go to the dashboard"
+            </a>
+        </div>
+    }
+}
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use enumflags2::BitFlags;
 use leptos::*;
+use leptos_router::*;
 use leptos_struct_table::*;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Range;
@@ -13,10 +14,12 @@ use crate::components::datatable::DataTable;
 use crate::components::datatable_form::Field;
 use crate::data::QueryParams;
 use crate::data_providers::symbols::{
-    symbols_add, symbols_count, symbols_get, symbols_list, symbols_list_names, symbols_remove,
-    symbols_update, Symbols, SymbolsRow,
+    format_size, symbols_add, symbols_count, symbols_get, symbols_list, symbols_list_names,
+    symbols_list_orphaned, symbols_list_quarantined, symbols_missing, symbols_release,
+    symbols_remove, symbols_update, Symbols, SymbolsRow,
 };
 use crate::data_providers::ExtraTableDataProvider;
+use crate::prefix::prefixed;
 use crate::table_data_provider_impl;
 
 #[derive(Debug, Clone)]
@@ -171,10 +174,339 @@ impl DataTableTrait for SymbolsTable {
 
 table_data_provider_impl!(SymbolsTable);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolsTab {
+    All,
+    Orphaned,
+    Quarantined,
+    Missing,
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn SymbolsPage() -> impl IntoView {
+    let tab = create_rw_signal(SymbolsTab::All);
+
+    view! {
+        <div role="tablist" class="tabs tabs-bordered mb-2">
+            <a
+                role="tab"
+                class="tab"
+                class:tab-active=move || tab.get() == SymbolsTab::All
+                on:click=move |_| tab.set(SymbolsTab::All)
+            >
+                "All"
+            </a>
+            <a
+                role="tab"
+                class="tab"
+                class:tab-active=move || tab.get() == SymbolsTab::Orphaned
+                on:click=move |_| tab.set(SymbolsTab::Orphaned)
+            >
+                "Orphaned"
+            </a>
+            <a
+                role="tab"
+                class="tab"
+                class:tab-active=move || tab.get() == SymbolsTab::Quarantined
+                on:click=move |_| tab.set(SymbolsTab::Quarantined)
+            >
+                "Quarantined"
+            </a>
+            <a
+                role="tab"
+                class="tab"
+                class:tab-active=move || tab.get() == SymbolsTab::Missing
+                on:click=move |_| tab.set(SymbolsTab::Missing)
+            >
+                "Missing"
+            </a>
+        </div>
+        {move || match tab.get() {
+            SymbolsTab::All => view! { <DataTable<SymbolsTable>/> }.into_view(),
+            SymbolsTab::Orphaned => view! { <OrphanedSymbols/> }.into_view(),
+            SymbolsTab::Quarantined => view! { <QuarantinedSymbols/> }.into_view(),
+            SymbolsTab::Missing => view! { <MissingSymbols/> }.into_view(),
+        }}
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn OrphanedSymbols() -> impl IntoView {
+    let query_map = use_query_map();
+    let days = create_rw_signal(90i64);
+
+    let parents = move || {
+        let mut parents = HashMap::new();
+        if let Some(product_id) =
+            query_map.with(|q| q.get("product").and_then(|id| Uuid::parse_str(id).ok()))
+        {
+            parents.insert("product_id".to_string(), product_id);
+        }
+        parents
+    };
+
+    let orphaned = create_resource(
+        move || (parents(), days.get()),
+        |(parents, days)| async move {
+            symbols_list_orphaned(parents, days)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch orphaned symbols: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+
+    view! {
+        <div class="flex items-center gap-2 mb-2 text-sm">
+            <span>"Symbols not referenced by any crash in the last"</span>
+            <input
+                type="number"
+                min="1"
+                class="input input-bordered input-sm w-24"
+                prop:value=move || days.get().to_string()
+                on:change=move |ev| {
+                    if let Ok(value) = event_target_value(&ev).parse::<i64>() {
+                        days.set(value.max(1));
+                    }
+                }
+            />
+            <span>"days"</span>
+        </div>
+        <div class="overflow-auto grow min-h-0">
+            <table class="table table-sm w-full">
+                <thead>
+                    <tr>
+                        <th>"Product"</th>
+                        <th>"Version"</th>
+                        <th>"Module"</th>
+                        <th>"Build ID"</th>
+                        <th>"Size"</th>
+                        <th>"Uploaded"</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                        {move || {
+                            orphaned
+                                .get()
+                                .map(|orphaned| {
+                                    orphaned
+                                        .into_iter()
+                                        .map(|symbols| {
+                                            view! {
+                                                <tr>
+                                                    <td>{symbols.product}</td>
+                                                    <td>{symbols.version}</td>
+                                                    <td>{symbols.module_id}</td>
+                                                    <td>{symbols.build_id}</td>
+                                                    <td>
+                                                        {symbols.file_size.map(format_size).unwrap_or_default()}
+                                                    </td>
+                                                    <td>
+                                                        {symbols.created_at.format("%d/%m/%Y - %H:%M").to_string()}
+                                                    </td>
+                                                    <td>
+                                                        <a
+                                                            class="link link-primary"
+                                                            href=prefixed(&format!("/download/symbols/{}", symbols.id))
+                                                            download
+                                                        >
+                                                            "Download"
+                                                        </a>
+                                                    </td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()
+                                })
+                        }}
+                    </Transition>
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn QuarantinedSymbols() -> impl IntoView {
+    let query_map = use_query_map();
+
+    let parents = move || {
+        let mut parents = HashMap::new();
+        if let Some(product_id) =
+            query_map.with(|q| q.get("product").and_then(|id| Uuid::parse_str(id).ok()))
+        {
+            parents.insert("product_id".to_string(), product_id);
+        }
+        parents
+    };
+
+    let release = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { symbols_release(id).await }
+    });
+    let remove = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { symbols_remove(id).await }
+    });
+
+    let quarantined = create_resource(
+        move || (parents(), release.version().get(), remove.version().get()),
+        |(parents, _, _)| async move {
+            symbols_list_quarantined(parents).await.unwrap_or_else(|e| {
+                error!("Failed to fetch quarantined symbols: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    let failed = move || {
+        release
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .or_else(|| remove.value().get().and_then(|result| result.err()))
+            .map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+    };
+
+    view! {
+        <p class="text-sm mb-2">
+            "Symbol files that failed validation at upload. They are not used to process minidumps until they are released."
+        </p>
+        {failed}
+        <div class="overflow-auto grow min-h-0">
+            <table class="table table-sm w-full">
+                <thead>
+                    <tr>
+                        <th>"Product"</th>
+                        <th>"Version"</th>
+                        <th>"Module"</th>
+                        <th>"Build ID"</th>
+                        <th>"Reason"</th>
+                        <th>"Uploaded"</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                        {move || {
+                            quarantined
+                                .get()
+                                .map(|quarantined| {
+                                    quarantined
+                                        .into_iter()
+                                        .map(|symbols| {
+                                            let id = symbols.id;
+                                            view! {
+                                                <tr>
+                                                    <td>{symbols.product}</td>
+                                                    <td>{symbols.version}</td>
+                                                    <td>{symbols.module_id}</td>
+                                                    <td>{symbols.build_id}</td>
+                                                    <td>{symbols.quarantine_reason.unwrap_or_default()}</td>
+                                                    <td>
+                                                        {symbols.created_at.format("%d/%m/%Y - %H:%M").to_string()}
+                                                    </td>
+                                                    <td class="flex gap-1">
+                                                        <a
+                                                            class="btn btn-ghost btn-xs"
+                                                            href=prefixed(&format!("/download/symbols/{}", id))
+                                                            download
+                                                        >
+                                                            "Download"
+                                                        </a>
+                                                        <button
+                                                            class="btn btn-ghost btn-xs"
+                                                            disabled=move || release.pending().get()
+                                                            on:click=move |_| release.dispatch(id)
+                                                        >
+                                                            "Release"
+                                                        </button>
+                                                        <button
+                                                            class="btn btn-ghost btn-xs"
+                                                            disabled=move || remove.pending().get()
+                                                            on:click=move |_| remove.dispatch(id)
+                                                        >
+                                                            "Delete"
+                                                        </button>
+                                                    </td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()
+                                })
+                        }}
+                    </Transition>
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn MissingSymbols() -> impl IntoView {
+    let query_map = use_query_map();
+
+    let product =
+        move || query_map.with(|q| q.get("product").and_then(|id| Uuid::parse_str(id).ok()));
+
+    let missing = create_resource(product, |product| async move {
+        symbols_missing(product).await.unwrap_or_else(|e| {
+            error!("Failed to fetch missing symbols: {:?}", e);
+            vec![]
+        })
+    });
+
     view! {
-        <DataTable<SymbolsTable>/>
+        <div class="mb-2 text-sm">
+            "Modules for which crashes had no symbols. Uploading their symbols removes them from this list."
+        </div>
+        <div class="overflow-auto grow min-h-0">
+            <table class="table table-sm w-full">
+                <thead>
+                    <tr>
+                        <th>"Product"</th>
+                        <th>"Module"</th>
+                        <th>"Build ID"</th>
+                        <th>"Crashes"</th>
+                        <th>"Last seen"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                        {move || {
+                            missing
+                                .get()
+                                .map(|missing| {
+                                    missing
+                                        .into_iter()
+                                        .map(|symbols| {
+                                            view! {
+                                                <tr>
+                                                    <td>{symbols.product}</td>
+                                                    <td>{symbols.module_id}</td>
+                                                    <td>{symbols.build_id}</td>
+                                                    <td>{symbols.count}</td>
+                                                    <td>
+                                                        {symbols.last_seen.format("%d/%m/%Y - %H:%M").to_string()}
+                                                    </td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect_view()
+                                })
+                        }}
+                    </Transition>
+                </tbody>
+            </table>
+        </div>
     }
 }
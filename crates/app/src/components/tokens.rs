@@ -0,0 +1,261 @@
+use leptos::*;
+use std::collections::VecDeque;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::data::QueryParams;
+use crate::data_providers::api_token::{
+    api_token_create, api_token_list, api_token_list_all, api_token_remove, api_token_rotate,
+    ApiToken, TOKEN_ENTITLEMENTS,
+};
+use crate::data_providers::product::product_list;
+
+fn all_products() -> QueryParams {
+    QueryParams {
+        sorting: VecDeque::new(),
+        range: 0..1000,
+        filter: String::new(),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NewToken {
+    name: String,
+    entitlements: Vec<String>,
+    product_id: Option<Uuid>,
+}
+
+/// The API tokens of all users, for administrators.
+#[allow(non_snake_case)]
+#[component]
+pub fn TokensPage() -> impl IntoView {
+    view! { <ApiTokens all=true/> }
+}
+
+/// Lists, creates, rotates and revokes API tokens: the tokens of the user, or with `all` the
+/// tokens of all users.
+#[allow(non_snake_case)]
+#[component]
+pub fn ApiTokens(all: bool) -> impl IntoView {
+    let name = create_rw_signal(String::new());
+    let entitlements = create_rw_signal(Vec::<String>::new());
+    let product_id = create_rw_signal(None::<Uuid>);
+    let secret = create_rw_signal(None::<Result<String, ServerFnError>>);
+
+    let create_token = create_action(|token: &NewToken| {
+        let token = token.clone();
+        async move { api_token_create(token.name, token.entitlements, token.product_id).await }
+    });
+    let rotate_token = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { api_token_rotate(id).await }
+    });
+    let remove_token = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { api_token_remove(id).await }
+    });
+    create_effect(move |_| {
+        if let Some(result) = create_token.value().get() {
+            secret.set(Some(result));
+        }
+    });
+    create_effect(move |_| {
+        if let Some(result) = rotate_token.value().get() {
+            secret.set(Some(result));
+        }
+    });
+
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list(all_products()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch products: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let tokens = create_resource(
+        move || {
+            (
+                create_token.version().get(),
+                rotate_token.version().get(),
+                remove_token.version().get(),
+            )
+        },
+        move |_| async move {
+            let tokens = match all {
+                true => api_token_list_all().await,
+                false => api_token_list().await,
+            };
+            tokens.unwrap_or_else(|e| {
+                error!("Failed to fetch API tokens: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    let product_name = move |id: Option<Uuid>| match id {
+        None => "all".to_string(),
+        Some(id) => products
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|product| product.id == id)
+            .map_or_else(|| id.to_string(), |product| product.name),
+    };
+
+    let shown = move || {
+        secret.get().map(|result| match result {
+            Ok(token) => view! {
+                <div class="alert alert-success rounded-btn my-2 p-3 flex flex-col items-start">
+                    <span class="font-semibold">
+                        "Copy the token now, it will not be shown again"
+                    </span>
+                    <code class="break-all">{token}</code>
+                </div>
+            }
+            .into_view(),
+            Err(e) => view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            }
+            .into_view(),
+        })
+    };
+
+    let row = move |token: ApiToken| {
+        let id = token.id;
+        view! {
+            <tr>
+                <td>{token.name}</td>
+                {all.then(|| view! { <td>{token.owner.unwrap_or_default()}</td> })}
+                <td>{token.entitlements.unwrap_or_else(|| "all".to_string())}</td>
+                <td>{move || product_name(token.product_id)}</td>
+                <td>{token.created_at.format("%d/%m/%Y - %H:%M").to_string()}</td>
+                <td>
+                    {token
+                        .last_used_at
+                        .map(|at| at.format("%d/%m/%Y - %H:%M").to_string())
+                        .unwrap_or_else(|| "never".to_string())}
+                </td>
+                <td>
+                    <button class="btn btn-ghost btn-xs" on:click=move |_| rotate_token.dispatch(id)>
+                        "Rotate"
+                    </button>
+                    <button class="btn btn-ghost btn-xs" on:click=move |_| remove_token.dispatch(id)>
+                        "Revoke"
+                    </button>
+                </td>
+            </tr>
+        }
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"API tokens"</h2>
+                <p class="text-sm">
+                    "Personal tokens give scripts access to the products you have a role for. "
+                    "Limit a token to the APIs it needs and to a single product where possible. "
+                    "Rotating a token replaces it with a new one; the old token stops working."
+                </p>
+                <div class="flex flex-wrap items-center gap-2 my-2">
+                    <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="token name"
+                        prop:value=move || name.get()
+                        on:input=move |ev| name.set(event_target_value(&ev))
+                    />
+                    <select
+                        class="select select-bordered select-sm"
+                        on:change=move |ev| {
+                            product_id.set(Uuid::parse_str(&event_target_value(&ev)).ok())
+                        }
+                    >
+                        <option value="" selected=move || product_id.get().is_none()>
+                            "All products"
+                        </option>
+                        {move || {
+                            products
+                                .get()
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|product| {
+                                    view! {
+                                        <option value=product.id.to_string()>{product.name}</option>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </select>
+                    {TOKEN_ENTITLEMENTS
+                        .into_iter()
+                        .map(|entitlement| {
+                            view! {
+                                <label class="label cursor-pointer gap-1">
+                                    <input
+                                        type="checkbox"
+                                        class="checkbox checkbox-sm"
+                                        prop:checked=move || {
+                                            entitlements.get().iter().any(|e| e == entitlement)
+                                        }
+                                        on:change=move |ev| {
+                                            let checked = event_target_checked(&ev);
+                                            entitlements
+                                                .update(|entitlements| {
+                                                    entitlements.retain(|e| e != entitlement);
+                                                    if checked {
+                                                        entitlements.push(entitlement.to_string());
+                                                    }
+                                                });
+                                        }
+                                    />
+                                    <span class="label-text">{entitlement}</span>
+                                </label>
+                            }
+                        })
+                        .collect_view()}
+                    <button
+                        class="btn btn-primary btn-sm"
+                        disabled=move || create_token.pending().get()
+                        on:click=move |_| {
+                            create_token
+                                .dispatch(NewToken {
+                                    name: name.get(),
+                                    entitlements: entitlements.get(),
+                                    product_id: product_id.get(),
+                                });
+                            name.set(String::new());
+                        }
+                    >
+                        "Create"
+                    </button>
+                </div>
+                <p class="text-xs">"Without entitlements, a token can use all APIs."</p>
+                {shown}
+                <table class="table table-sm w-full">
+                    <thead>
+                        <tr>
+                            <th>"Name"</th>
+                            {all.then(|| view! { <th>"Owner"</th> })}
+                            <th>"Entitlements"</th>
+                            <th>"Product"</th>
+                            <th>"Created"</th>
+                            <th>"Last used"</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                            {move || {
+                                tokens
+                                    .get()
+                                    .map(|tokens| tokens.into_iter().map(row).collect_view())
+                            }}
+                        </Transition>
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
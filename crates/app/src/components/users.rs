@@ -4,6 +4,7 @@ use leptos::*;
 use leptos_struct_table::*;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Range;
+use tracing::error;
 use uuid::Uuid;
 
 use super::datatable::{Capabilities, DataTableTrait};
@@ -11,9 +12,11 @@ use super::datatable_form::{FieldCheckbox, FieldString, Fields};
 use crate::components::datatable::DataTable;
 use crate::components::datatable_form::Field;
 use crate::data::QueryParams;
+use crate::data_providers::product::product_list;
 use crate::data_providers::user::{
-    user_add, user_count, user_get, user_list, user_list_names, user_remove, user_update, User,
-    UserRow,
+    user_add, user_assign_role, user_count, user_delete, user_get, user_list, user_list_names,
+    user_remove, user_revoke_role, user_roles, user_set_active, user_update, User, UserRow, ROLES,
+    ROLE_VIEWER,
 };
 use crate::data_providers::ExtraTableDataProvider;
 use crate::table_data_provider_impl;
@@ -125,5 +128,338 @@ table_data_provider_impl!(UserTable);
 pub fn UsersPage() -> impl IntoView {
     view! {
         <DataTable<UserTable>/>
+        <UserRoles/>
+        <UserAccounts/>
+    }
+}
+
+fn parse_id(value: String) -> Option<Uuid> {
+    Uuid::parse_str(&value).ok()
+}
+
+fn all_items() -> QueryParams {
+    QueryParams {
+        sorting: VecDeque::new(),
+        range: 0..1000,
+        filter: String::new(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn UserRoles() -> impl IntoView {
+    let selected = create_rw_signal(None::<Uuid>);
+    let product = create_rw_signal(None::<Uuid>);
+    let role = create_rw_signal(ROLE_VIEWER.to_string());
+
+    let assign = create_action(|(id, product_id, role): &(Uuid, Uuid, String)| {
+        let (id, product_id, role) = (*id, *product_id, role.clone());
+        async move { user_assign_role(id, product_id, role).await }
+    });
+    let revoke = create_action(|(id, product_id): &(Uuid, Uuid)| {
+        let (id, product_id) = (*id, *product_id);
+        async move { user_revoke_role(id, product_id).await }
+    });
+
+    let users = create_resource(
+        || (),
+        |_| async move {
+            user_list(all_items()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch users: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list(all_items()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch products: {:?}", e);
+                vec![]
+            })
+        },
+    );
+    let roles = create_resource(
+        move || {
+            (
+                selected.get(),
+                assign.version().get(),
+                revoke.version().get(),
+            )
+        },
+        |(id, _, _)| async move {
+            match id {
+                Some(id) => user_roles(id).await.unwrap_or_else(|e| {
+                    error!("Failed to fetch roles: {:?}", e);
+                    vec![]
+                }),
+                None => vec![],
+            }
+        },
+    );
+
+    let result = move || {
+        assign
+            .value()
+            .get()
+            .or_else(|| revoke.value().get())
+            .and_then(|result| result.err())
+            .map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Product roles"</h2>
+                <p class="text-sm">
+                    "Viewers can see the crashes, issues, versions and symbols of a product. "
+                    "Maintainers can also change them, and admins can also change the settings, "
+                    "webhooks and device keys of the product. Administrators have access to all products."
+                </p>
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    <div class="flex flex-wrap items-center gap-2 my-2">
+                        <select
+                            class="select select-bordered select-sm"
+                            on:change=move |ev| selected.set(parse_id(event_target_value(&ev)))
+                        >
+                            <option value="">"Select user"</option>
+                            {move || {
+                                users
+                                    .get()
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|user| {
+                                        view! { <option value=user.id.to_string()>{user.username}</option> }
+                                    })
+                                    .collect_view()
+                            }}
+                        </select>
+                        <select
+                            class="select select-bordered select-sm"
+                            on:change=move |ev| product.set(parse_id(event_target_value(&ev)))
+                        >
+                            <option value="">"Select product"</option>
+                            {move || {
+                                products
+                                    .get()
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|product| {
+                                        view! { <option value=product.id.to_string()>{product.name}</option> }
+                                    })
+                                    .collect_view()
+                            }}
+                        </select>
+                        <select
+                            class="select select-bordered select-sm"
+                            on:change=move |ev| role.set(event_target_value(&ev))
+                        >
+                            {ROLES
+                                .iter()
+                                .map(|(name, label)| {
+                                    view! {
+                                        <option value=*name selected={*name == ROLE_VIEWER}>
+                                            {*label}
+                                        </option>
+                                    }
+                                })
+                                .collect_view()}
+                        </select>
+                        <button
+                            class="btn btn-sm"
+                            disabled=move || {
+                                assign.pending().get() || selected.get().is_none()
+                                    || product.get().is_none()
+                            }
+                            on:click=move |_| {
+                                if let (Some(id), Some(product_id)) = (selected.get(), product.get()) {
+                                    assign.dispatch((id, product_id, role.get()));
+                                }
+                            }
+                        >
+                            "Assign"
+                        </button>
+                    </div>
+                    <table class="table table-sm w-full">
+                        <thead>
+                            <tr>
+                                <th>"Product"</th>
+                                <th>"Role"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {move || {
+                                let Some(id) = selected.get() else {
+                                    return view! {
+                                        <tr>
+                                            <td colspan="3">"Select a user to see their roles"</td>
+                                        </tr>
+                                    }
+                                    .into_view();
+                                };
+                                let roles = roles.get().unwrap_or_default();
+                                if roles.is_empty() {
+                                    return view! {
+                                        <tr>
+                                            <td colspan="3">"No roles"</td>
+                                        </tr>
+                                    }
+                                    .into_view();
+                                }
+                                roles
+                                    .into_iter()
+                                    .map(|user_role| {
+                                        let product_id = user_role.product_id;
+                                        let label = ROLES
+                                            .iter()
+                                            .find(|(name, _)| *name == user_role.role)
+                                            .map(|(_, label)| label.to_string())
+                                            .unwrap_or(user_role.role);
+                                        view! {
+                                            <tr>
+                                                <td>{user_role.product}</td>
+                                                <td>{label}</td>
+                                                <td>
+                                                    <button
+                                                        class="btn btn-error btn-xs"
+                                                        disabled=move || revoke.pending().get()
+                                                        on:click=move |_| revoke.dispatch((id, product_id))
+                                                    >
+                                                        "Remove"
+                                                    </button>
+                                                </td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect_view()
+                            }}
+                        </tbody>
+                    </table>
+                </Transition>
+                {result}
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn UserAccounts() -> impl IntoView {
+    let selected = create_rw_signal(None::<Uuid>);
+    let reassign_to = create_rw_signal(None::<Uuid>);
+
+    let set_active = create_action(|(id, active): &(Uuid, bool)| {
+        let (id, active) = (*id, *active);
+        async move { user_set_active(id, active).await }
+    });
+    let delete_user = create_action(|(id, reassign_to): &(Uuid, Option<Uuid>)| {
+        let (id, reassign_to) = (*id, *reassign_to);
+        async move { user_delete(id, reassign_to).await }
+    });
+
+    let users = create_resource(
+        move || (set_active.version().get(), delete_user.version().get()),
+        |_| async move {
+            user_list(all_items()).await.unwrap_or_else(|e| {
+                error!("Failed to fetch users: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    let selected_user = move || {
+        let id = selected.get()?;
+        users
+            .get()
+            .and_then(|users| users.into_iter().find(|user| user.id == id))
+    };
+
+    let result = move || {
+        set_active
+            .value()
+            .get()
+            .or_else(|| delete_user.value().get())
+            .and_then(|result| result.err())
+            .map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+    };
+
+    let options = move |exclude: Option<Uuid>| {
+        users
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(move |user| Some(user.id) != exclude)
+            .map(|user| {
+                view! { <option value=user.id.to_string()>{user.username}</option> }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Deactivate or delete account"</h2>
+                <p class="text-sm">
+                    "Deactivated users are logged out and can no longer log in or use their API "
+                    "tokens. Deleting a user revokes their API tokens; their issues are assigned "
+                    "to another user, or left unassigned if no user is selected."
+                </p>
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    <div class="flex flex-wrap items-center gap-2 my-2">
+                        <select
+                            class="select select-bordered select-sm"
+                            on:change=move |ev| {
+                                selected.set(parse_id(event_target_value(&ev)));
+                                reassign_to.set(None);
+                            }
+                        >
+                            <option value="">"Select user"</option>
+                            {move || options(None)}
+                        </select>
+                        {move || {
+                            selected_user()
+                                .map(|user| {
+                                    let id = user.id;
+                                    let active = user.deactivated_at.is_none();
+                                    view! {
+                                        <button
+                                            class="btn btn-sm"
+                                            disabled=move || set_active.pending().get()
+                                            on:click=move |_| set_active.dispatch((id, !active))
+                                        >
+                                            {if active { "Deactivate" } else { "Reactivate" }}
+                                        </button>
+                                        <select
+                                            class="select select-bordered select-sm"
+                                            on:change=move |ev| {
+                                                reassign_to.set(parse_id(event_target_value(&ev)))
+                                            }
+                                        >
+                                            <option value="">"Leave issues unassigned"</option>
+                                            {move || options(Some(id))}
+                                        </select>
+                                        <button
+                                            class="btn btn-error btn-sm"
+                                            disabled=move || delete_user.pending().get()
+                                            on:click=move |_| {
+                                                delete_user.dispatch((id, reassign_to.get()));
+                                                selected.set(None);
+                                            }
+                                        >
+                                            "Delete"
+                                        </button>
+                                    }
+                                })
+                        }}
+                    </div>
+                </Transition>
+                {result}
+            </div>
+        </div>
     }
 }
@@ -14,8 +14,9 @@ use crate::components::datatable_form::{Field, FieldCombo, FieldString};
 use crate::data::QueryParams;
 use crate::data_providers::product::{product_get, product_get_by_name, product_list_names};
 use crate::data_providers::version::{
-    version_add, version_count, version_get, version_list, version_list_names, version_remove,
-    version_update, Version, VersionRow,
+    parse_release_date, parse_version_lines, version_add, version_count, version_get,
+    version_import, version_list, version_list_names, version_remove, version_update, Version,
+    VersionRow,
 };
 use crate::data_providers::ExtraTableDataProvider;
 use crate::table_data_provider_impl;
@@ -133,6 +134,27 @@ impl DataTableTrait for VersionTable {
                 Field::new(FieldString::new(version.hash, HashSet::new())),
             );
         });
+        fields.update(|field| {
+            field.insert(
+                "Channel".to_string(),
+                Field::new(FieldString::new(
+                    version.channel.unwrap_or_default(),
+                    HashSet::new(),
+                )),
+            );
+        });
+        fields.update(|field| {
+            field.insert(
+                "Release date".to_string(),
+                Field::new(FieldString::new(
+                    version
+                        .release_date
+                        .map(|date| date.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default(),
+                    HashSet::new(),
+                )),
+            );
+        });
 
         if version.product_id.is_nil() {
             if let Some(product_id) = parents.get("product_id") {
@@ -180,6 +202,10 @@ impl DataTableTrait for VersionTable {
         version.name = fields.get().get::<FieldString>("Name").value.get();
         version.tag = fields.get().get::<FieldString>("Tag").value.get();
         version.hash = fields.get().get::<FieldString>("Hash").value.get();
+        let channel = fields.get().get::<FieldString>("Channel").value.get();
+        version.channel = (!channel.trim().is_empty()).then(|| channel.trim().to_owned());
+        let release_date = fields.get().get::<FieldString>("Release date").value.get();
+        version.release_date = parse_release_date(&release_date);
         match product_id {
             None => error!("Product ID is missing"),
             Some(product_id) => {
@@ -219,10 +245,102 @@ impl DataTableTrait for VersionTable {
 
 table_data_provider_impl!(VersionTable);
 
+#[allow(non_snake_case)]
+#[component]
+fn VersionImport() -> impl IntoView {
+    let product = create_rw_signal(String::new());
+    let text = create_rw_signal(String::new());
+
+    let products = create_resource(
+        || (),
+        |_| async move {
+            product_list_names()
+                .await
+                .map(|names| itertools::sorted(names).collect::<Vec<_>>())
+                .unwrap_or_else(|e| {
+                    error!("Failed to fetch product names: {:?}", e);
+                    vec![]
+                })
+        },
+    );
+
+    let import = create_action(|(product, text): &(String, String)| {
+        let (product, text) = (product.clone(), text.clone());
+        async move {
+            let product = product_get_by_name(product).await?;
+            let versions = parse_version_lines(&text, product.id).map_err(ServerFnError::new)?;
+            version_import(versions).await
+        }
+    });
+
+    let result = move || {
+        import.value().get().map(|result| match result {
+            Ok(count) => view! {
+                <div class="alert alert-success rounded-btn my-2 p-3">
+                    {format!("Imported {} version(s)", count)}
+                </div>
+            },
+            Err(e) => view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            },
+        })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">"Import versions"</h2>
+                <p class="text-sm">
+                    "One version per line as "
+                    <code>"name,tag,hash,channel,release_date"</code>
+                    ". Existing versions with the same name are updated."
+                </p>
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    <select
+                        class="select select-bordered select-sm my-2"
+                        on:change=move |ev| product.set(event_target_value(&ev))
+                    >
+                        <option value="">"Select product"</option>
+                        {move || {
+                            products
+                                .get()
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|name| view! { <option value=name.clone()>{name}</option> })
+                                .collect_view()
+                        }}
+                    </select>
+                </Transition>
+                <textarea
+                    class="textarea textarea-bordered font-mono text-sm"
+                    rows="6"
+                    placeholder="1.11.0,v1.11.0,3f2a9c1,stable,2024-10-01"
+                    prop:value=move || text.get()
+                    on:input=move |ev| text.set(event_target_value(&ev))
+                ></textarea>
+                {result}
+                <div class="card-actions justify-end">
+                    <button
+                        class="btn btn-sm btn-primary"
+                        disabled=move || {
+                            import.pending().get() || product.get().is_empty()
+                                || text.get().trim().is_empty()
+                        }
+                        on:click=move |_| import.dispatch((product.get(), text.get()))
+                    >
+                        "Import"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn VersionsPage() -> impl IntoView {
     view! {
         <DataTable<VersionTable>/>
+        <VersionImport/>
     }
 }
@@ -0,0 +1,308 @@
+use leptos::*;
+use leptos_router::*;
+use std::collections::HashSet;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::data_providers::product::product_get;
+use crate::data_providers::webhook::{
+    webhook_add, webhook_deliveries, webhook_list, webhook_remove, webhook_test, Webhook,
+    WebhookDelivery, WEBHOOK_EVENTS,
+};
+
+fn event_label(event: &str) -> String {
+    WEBHOOK_EVENTS
+        .iter()
+        .find(|(name, _)| *name == event)
+        .map(|(_, label)| label.to_string())
+        .unwrap_or_else(|| event.to_string())
+}
+
+fn delivery_status(delivery: &WebhookDelivery) -> String {
+    match (&delivery.status_code, &delivery.error) {
+        (Some(code), None) => code.to_string(),
+        (Some(code), Some(error)) => format!("{} {}", code, error),
+        (None, Some(error)) => error.clone(),
+        (None, None) => "-".to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn WebhooksPage() -> impl IntoView {
+    let query_map = use_query_map();
+    let product_id =
+        move || query_map.with(|q| q.get("product").and_then(|id| Uuid::parse_str(id).ok()));
+
+    view! {
+        {move || match product_id() {
+            Some(product_id) => view! { <Webhooks product_id/> }.into_view(),
+            None => view! {
+                <div class="alert alert-info rounded-btn my-2 p-3">
+                    "Open the webhooks of a product from the products page"
+                </div>
+            }
+            .into_view(),
+        }}
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn Webhooks(product_id: Uuid) -> impl IntoView {
+    let url = create_rw_signal(String::new());
+    let secret = create_rw_signal(String::new());
+    let events = create_rw_signal(HashSet::<String>::new());
+
+    let product = create_resource(
+        move || product_id,
+        |product_id| async move {
+            product_get(product_id)
+                .await
+                .map(|product| product.name)
+                .unwrap_or_default()
+        },
+    );
+
+    let add_webhook = create_action(
+        move |(url, secret, events): &(String, String, HashSet<String>)| {
+            let (url, secret) = (url.clone(), secret.clone());
+            let events = itertools::sorted(events.iter().cloned()).collect();
+            async move { webhook_add(product_id, url, secret, events).await }
+        },
+    );
+    let remove_webhook = create_action(|id: &Uuid| {
+        let id = *id;
+        async move { webhook_remove(id).await }
+    });
+
+    let webhooks = create_resource(
+        move || (add_webhook.version().get(), remove_webhook.version().get()),
+        move |_| async move {
+            webhook_list(product_id).await.unwrap_or_else(|e| {
+                error!("Failed to fetch webhooks: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    let added = move || {
+        add_webhook.value().get().and_then(|result| {
+            result.err().map(|e| {
+                view! { <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div> }
+            })
+        })
+    };
+
+    view! {
+        <div class="card bg-base-200 rounded-lg my-2">
+            <div class="card-body p-4">
+                <h2 class="card-title">
+                    "Webhooks"
+                    <Transition fallback=|| ()>{move || product.get()}</Transition>
+                </h2>
+                <p class="text-sm">
+                    "Events are posted as JSON. With a secret, the body is signed with HMAC-SHA256 in the "
+                    <code>"X-Guardrail-Signature"</code>
+                    " header."
+                </p>
+                <div class="flex flex-wrap items-center gap-2 my-2">
+                    <input
+                        type="url"
+                        class="input input-bordered input-sm w-96"
+                        placeholder="https://example.com/hooks/guardrail"
+                        prop:value=move || url.get()
+                        on:input=move |ev| url.set(event_target_value(&ev))
+                    />
+                    <input
+                        type="password"
+                        class="input input-bordered input-sm"
+                        placeholder="secret (optional)"
+                        prop:value=move || secret.get()
+                        on:input=move |ev| secret.set(event_target_value(&ev))
+                    />
+                    {WEBHOOK_EVENTS
+                        .iter()
+                        .map(|(event, label)| {
+                            let event = event.to_string();
+                            let checked = {
+                                let event = event.clone();
+                                move || events.with(|events| events.contains(&event))
+                            };
+                            view! {
+                                <label class="label cursor-pointer gap-1">
+                                    <input
+                                        type="checkbox"
+                                        class="checkbox checkbox-sm"
+                                        prop:checked=checked
+                                        on:change=move |ev| {
+                                            let event = event.clone();
+                                            if event_target_checked(&ev) {
+                                                events.update(|events| {
+                                                    events.insert(event);
+                                                });
+                                            } else {
+                                                events.update(|events| {
+                                                    events.remove(&event);
+                                                });
+                                            }
+                                        }
+                                    />
+                                    <span class="label-text">{*label}</span>
+                                </label>
+                            }
+                        })
+                        .collect_view()}
+                    <button
+                        class="btn btn-primary btn-sm"
+                        disabled=move || {
+                            add_webhook.pending().get() || url.get().trim().is_empty()
+                                || events.with(|events| events.is_empty())
+                        }
+                        on:click=move |_| {
+                            add_webhook.dispatch((url.get(), secret.get(), events.get()));
+                            url.set(String::new());
+                            secret.set(String::new());
+                        }
+                    >
+                        "Add"
+                    </button>
+                </div>
+                {added}
+                <Transition fallback=move || view! { <span>"Loading..."</span> }>
+                    {move || {
+                        webhooks
+                            .get()
+                            .map(|webhooks| {
+                                webhooks
+                                    .into_iter()
+                                    .map(|webhook| view! { <WebhookCard webhook remove_webhook/> })
+                                    .collect_view()
+                            })
+                    }}
+                </Transition>
+            </div>
+        </div>
+    }
+}
+
+#[allow(non_snake_case)]
+#[component]
+fn WebhookCard(
+    webhook: Webhook,
+    remove_webhook: Action<Uuid, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let id = webhook.id;
+    let test_webhook = create_action(move |_: &()| async move { webhook_test(id).await });
+
+    let deliveries = create_resource(
+        move || test_webhook.version().get(),
+        move |_| async move {
+            webhook_deliveries(id).await.unwrap_or_else(|e| {
+                error!("Failed to fetch webhook deliveries: {:?}", e);
+                vec![]
+            })
+        },
+    );
+
+    let tested = move || {
+        test_webhook.value().get().map(|result| match result {
+            Ok(delivery) if delivery.succeeded() => view! {
+                <div class="alert alert-success rounded-btn my-2 p-3">
+                    {format!("Test event delivered: {}", delivery_status(&delivery))}
+                </div>
+            },
+            Ok(delivery) => view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">
+                    {format!("Test event failed: {}", delivery_status(&delivery))}
+                </div>
+            },
+            Err(e) => view! {
+                <div class="alert alert-error rounded-btn my-2 p-3">{e.to_string()}</div>
+            },
+        })
+    };
+
+    let events = webhook
+        .events
+        .iter()
+        .map(|event| event_label(event))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    view! {
+        <div class="card bg-base-100 rounded-lg my-2">
+            <div class="card-body p-4">
+                <div class="flex items-center gap-2">
+                    <code class="break-all flex-1">{webhook.url}</code>
+                    <span class="text-sm">{events}</span>
+                    <span class="badge badge-sm">
+                        {if webhook.has_secret { "signed" } else { "unsigned" }}
+                    </span>
+                    <button
+                        class="btn btn-ghost btn-xs"
+                        disabled=move || test_webhook.pending().get()
+                        on:click=move |_| test_webhook.dispatch(())
+                    >
+                        "Send test"
+                    </button>
+                    <button
+                        class="btn btn-ghost btn-xs"
+                        on:click=move |_| remove_webhook.dispatch(id)
+                    >
+                        "Remove"
+                    </button>
+                </div>
+                {tested}
+                <table class="table table-sm w-full">
+                    <thead>
+                        <tr>
+                            <th>"Time"</th>
+                            <th>"Event"</th>
+                            <th>"Status"</th>
+                            <th>"Duration"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <Transition fallback=move || view! { <tr><td>"Loading..."</td></tr> }>
+                            {move || {
+                                deliveries
+                                    .get()
+                                    .map(|deliveries| {
+                                        if deliveries.is_empty() {
+                                            return view! {
+                                                <tr>
+                                                    <td colspan="4">"No deliveries yet"</td>
+                                                </tr>
+                                            }
+                                            .into_view();
+                                        }
+                                        deliveries
+                                            .into_iter()
+                                            .map(|delivery| {
+                                                let class = if delivery.succeeded() {
+                                                    "text-success"
+                                                } else {
+                                                    "text-error"
+                                                };
+                                                view! {
+                                                    <tr>
+                                                        <td>
+                                                            {delivery.created_at.format("%d/%m/%Y - %H:%M:%S").to_string()}
+                                                        </td>
+                                                        <td>{event_label(&delivery.event)}</td>
+                                                        <td class=class>{delivery_status(&delivery)}</td>
+                                                        <td>{format!("{} ms", delivery.duration_ms)}</td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect_view()
+                                    })
+                            }}
+                        </Transition>
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
@@ -15,6 +15,10 @@ cfg_if! { if #[cfg(feature="ssr")] {
     use std::collections::{HashMap, HashSet};
     use uuid::Uuid;
     use crate::entity;
+    use crate::model::organization::OrganizationRepo;
+    use crate::model::role;
+    use sea_query::Query;
+    use crate::read_only::check_writable;
 }}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -35,6 +39,13 @@ where
 
     fn filter_column() -> Self::Column;
     fn index_to_column(index: usize) -> Option<Self::Column>;
+    fn extend_query_for_filter(
+        query: Select<Self>,
+        filter: String,
+        _backend: DbBackend,
+    ) -> Select<Self> {
+        query.filter(Self::filter_column().contains(filter))
+    }
     fn extend_query_for_view(query: Select<Self>) -> Select<Self> {
         query
     }
@@ -46,30 +57,38 @@ where
         None
     }
 
+    /// Limits the query to the products for which the user has one of `roles`, or any role when
+    /// `roles` is empty, either for the product itself or in the organization that owns it.
     fn extend_query_for_access(
         query: Select<Self>,
         user: AuthenticatedUser,
-        _roles: Vec<String>,
+        roles: Vec<String>,
     ) -> Select<Self> {
         if user.is_admin {
             return query;
         }
-        query
-            .join(
-                JoinType::InnerJoin,
-                entity::product::Entity::belongs_to(entity::role::Entity)
-                    .from(entity::product::Column::Id)
-                    .to(entity::role::Column::ProductId)
-                    .into(),
-            )
-            .join(
-                JoinType::InnerJoin,
-                entity::role::Entity::belongs_to(entity::user::Entity)
-                    .from(entity::role::Column::UserId)
-                    .to(entity::user::Column::Id)
-                    .into(),
-            )
-            .filter(Expr::col((entity::user::Entity, entity::user::Column::Id)).eq(user.id))
+        let mut roles_query = Query::select()
+            .column(entity::role::Column::ProductId)
+            .from(entity::role::Entity)
+            .and_where(entity::role::Column::UserId.eq(user.id))
+            .to_owned();
+        if !roles.is_empty() {
+            roles_query.and_where(entity::role::Column::Name.is_in(roles.clone()));
+        }
+        query.filter(
+            Condition::any()
+                .add(
+                    Expr::col((entity::product::Entity, entity::product::Column::Id))
+                        .in_subquery(roles_query),
+                )
+                .add(
+                    Expr::col((
+                        entity::product::Entity,
+                        entity::product::Column::OrganizationId,
+                    ))
+                    .in_subquery(OrganizationRepo::organization_ids(user.id, &roles)),
+                ),
+        )
     }
 
     fn id_to_column(_id_name: String) -> Option<Self::Column> {
@@ -140,7 +159,7 @@ where
             .map_err(|e| ServerFnError::new(format!("{e:?}")))?
             .ok_or(ServerFnError::new("no access".to_string()))?;
     } else {
-        return Ok(user.is_admin);
+        return Err(ServerFnError::new("no access".to_string()));
     }
 
     Ok(true)
@@ -203,7 +222,8 @@ where
     query = <E as EntityInfo>::extend_query_for_access(query, user, vec![]);
 
     if !filter.is_empty() {
-        query = query.filter(E::filter_column().contains(filter));
+        query =
+            <E as EntityInfo>::extend_query_for_filter(query, filter, db.get_database_backend());
     }
 
     for (parent, parent_id) in parents {
@@ -307,10 +327,11 @@ where
     <E as EntityTrait>::Model: IntoActiveModel<<E as EntityTrait>::ActiveModel>,
     <E::Column as FromStr>::Err: std::fmt::Debug,
 {
+    check_writable()?;
     let db = use_context::<DatabaseConnection>()
         .ok_or(ServerFnError::new("No database connection".to_string()))?;
 
-    check_access_by_data::<E>(&item, vec!["admin".to_string()])
+    check_access_by_data::<E>(&item, role::at_least(role::MAINTAINER))
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
 
@@ -330,10 +351,11 @@ where
     <E as EntityTrait>::Model: IntoActiveModel<<E as EntityTrait>::ActiveModel>,
     <E::Column as FromStr>::Err: std::fmt::Debug,
 {
+    check_writable()?;
     let db = use_context::<DatabaseConnection>()
         .ok_or(ServerFnError::new("No database connection".to_string()))?;
 
-    check_access_by_data::<E>(&item, vec!["admin".to_string()])
+    check_access_by_data::<E>(&item, role::at_least(role::MAINTAINER))
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
 
@@ -352,10 +374,11 @@ where
         From<uuid::Uuid>,
     <E::Column as FromStr>::Err: std::fmt::Debug,
 {
+    check_writable()?;
     let db = use_context::<DatabaseConnection>()
         .ok_or(ServerFnError::new("No database connection".to_string()))?;
 
-    check_access_by_id::<E>(id, vec!["admin".to_string()])
+    check_access_by_id::<E>(id, role::at_least(role::MAINTAINER))
         .await
         .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
 
@@ -0,0 +1,158 @@
+use ::chrono::NaiveDateTime;
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use crate::auth::AuthenticatedUser;
+    use crate::authenticated_user;
+    use crate::data_providers::user::check_admin;
+    use crate::entity;
+    use crate::model::api_token::{join_entitlements, ApiTokenRepo};
+    use crate::read_only::check_writable;
+}}
+
+/// Entitlements a token can be limited to, see `model::api_token::ENTITLEMENTS`.
+pub const TOKEN_ENTITLEMENTS: [&str; 3] = ["read", "product-manage", "admin"];
+
+/// A personal API token. The token itself is only shown once, when created or rotated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    /// Comma separated entitlements, `None` for all entitlements.
+    pub entitlements: Option<String>,
+    pub product_id: Option<Uuid>,
+    /// Name of the owner, only in the list of all tokens.
+    pub owner: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::model::api_token::ApiToken> for ApiToken {
+    fn from(model: crate::model::api_token::ApiToken) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            created_at: model.created_at,
+            last_used_at: model.last_used_at,
+            entitlements: model.entitlements,
+            product_id: model.product_id,
+            owner: None,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn context() -> Result<(DatabaseConnection, AuthenticatedUser), ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    Ok((db, user))
+}
+
+/// Returns the token if the user owns it or is an administrator.
+#[cfg(feature = "ssr")]
+async fn manageable_token(
+    db: &DatabaseConnection,
+    user: &AuthenticatedUser,
+    id: Uuid,
+) -> Result<crate::model::api_token::ApiToken, ServerFnError> {
+    entity::prelude::ApiToken::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .filter(|token| token.user_id == user.id || user.is_admin)
+        .ok_or(ServerFnError::new("Token not found".to_string()))
+}
+
+#[server]
+pub async fn api_token_list() -> Result<Vec<ApiToken>, ServerFnError> {
+    let (db, user) = context().await?;
+    let tokens = ApiTokenRepo::get_by_user(&db, user.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(tokens.into_iter().map(ApiToken::from).collect())
+}
+
+/// Returns the tokens of all users, for administrators.
+#[server]
+pub async fn api_token_list_all() -> Result<Vec<ApiToken>, ServerFnError> {
+    let (db, _) = check_admin().await?;
+    let tokens = ApiTokenRepo::get_all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(tokens
+        .into_iter()
+        .map(|(token, owner)| ApiToken {
+            owner: owner.map(|owner| owner.username),
+            ..ApiToken::from(token)
+        })
+        .collect())
+}
+
+#[server]
+pub async fn api_token_create(
+    name: String,
+    entitlements: Vec<String>,
+    product_id: Option<Uuid>,
+) -> Result<String, ServerFnError> {
+    check_writable()?;
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Token name is required".to_string()));
+    }
+    let entitlements = join_entitlements(&entitlements)
+        .map_err(|e| ServerFnError::new(format!("Unknown entitlement: {e}")))?;
+    let (db, user) = context().await?;
+    let (_, token) = ApiTokenRepo::create(&db, user.id, name.clone(), entitlements, product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "api_token",
+        "API token {} created by {}",
+        name,
+        user.username
+    );
+    Ok(token)
+}
+
+/// Replaces the token with a new one, keeping its name and scope, and returns the new token.
+#[server]
+pub async fn api_token_rotate(id: Uuid) -> Result<String, ServerFnError> {
+    check_writable()?;
+    let (db, user) = context().await?;
+    let token = manageable_token(&db, &user, id).await?;
+    let rotated = ApiTokenRepo::rotate(&db, token.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "api_token",
+        "API token {} rotated by {}",
+        token.name,
+        user.username
+    );
+    Ok(rotated)
+}
+
+#[server]
+pub async fn api_token_remove(id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = context().await?;
+    let token = manageable_token(&db, &user, id).await?;
+    ApiTokenRepo::remove(&db, token.user_id, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "api_token",
+        "API token {} revoked by {}",
+        token.name,
+        user.username
+    );
+    Ok(())
+}
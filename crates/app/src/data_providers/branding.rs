@@ -0,0 +1,106 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::DatabaseConnection;
+    use crate::data_providers::user::check_admin;
+    use crate::model::branding::{is_valid_color, is_valid_logo_url, BrandingRepo, MAX_TITLE_LENGTH};
+    use crate::read_only::check_writable;
+}}
+
+pub const DEFAULT_TITLE: &str = "Guardrail";
+
+/// Title, logo and accent color of the web interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Branding {
+    pub title: String,
+    pub logo_url: Option<String>,
+    pub accent_color: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            title: DEFAULT_TITLE.to_owned(),
+            logo_url: None,
+            accent_color: None,
+        }
+    }
+}
+
+impl Branding {
+    /// Returns the CSS variables of the branding, for the `style` of the root element.
+    pub fn css_variables(&self) -> String {
+        self.accent_color
+            .as_deref()
+            .map(|color| format!("--brand-accent: {color};"))
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the branding of the web interface. Also served to visitors that are not logged in,
+/// e.g. on the login page.
+#[server]
+pub async fn branding_get() -> Result<Branding, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let branding = BrandingRepo::get(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(branding
+        .map(|branding| Branding {
+            title: branding.title,
+            logo_url: branding.logo_url,
+            accent_color: branding.accent_color,
+        })
+        .unwrap_or_default())
+}
+
+#[server]
+pub async fn branding_update(
+    title: String,
+    logo_url: String,
+    accent_color: String,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    let title = match title.trim() {
+        "" => DEFAULT_TITLE.to_owned(),
+        title if title.chars().count() > MAX_TITLE_LENGTH => {
+            return Err(ServerFnError::new(format!(
+                "title is longer than {} characters",
+                MAX_TITLE_LENGTH
+            )))
+        }
+        title => title.to_owned(),
+    };
+    let logo_url = match logo_url.trim() {
+        "" => None,
+        url if is_valid_logo_url(url) => Some(url.to_owned()),
+        _ => {
+            return Err(ServerFnError::new(
+                "logo URL must be an http(s) URL or a path starting with /".to_string(),
+            ))
+        }
+    };
+    let accent_color = match accent_color.trim() {
+        "" => None,
+        color if is_valid_color(color) => Some(color.to_lowercase()),
+        _ => {
+            return Err(ServerFnError::new(
+                "accent color must be a hex color like #1e88e5".to_string(),
+            ))
+        }
+    };
+    BrandingRepo::set(&db, title.clone(), logo_url, accent_color)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "branding",
+        "branding changed to {} by {}",
+        title,
+        user.username
+    );
+    Ok(())
+}
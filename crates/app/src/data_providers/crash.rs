@@ -14,13 +14,23 @@ cfg_if! { if #[cfg(feature="ssr")] {
     use crate::entity;
     use crate::auth::AuthenticatedUser;
     use crate::data::{
-        add, count, delete_by_id, get_all, get_all_names, get_by_id, update, EntityInfo,
+        add, check_access_by_id, delete_by_id, get_all, get_all_names, get_by_id, update,
+        EntityInfo,
     };
+    use crate::authenticated_user;
+    use crate::model::annotation::{promoted_keys, spread, AnnotationRepo};
+    use crate::model::crash::CrashRepo;
+    use crate::model::crash_pdf::CrashPdf;
+    use crate::model::build_metadata::BuildMetadataRepo;
+    use crate::model::link_template::{crash_values, render_link, LinkTemplateRepo};
+    use crate::read_only::check_writable;
+    use crate::settings::settings;
 }}
 
 use super::ExtraRowTrait;
 use crate::classes::ClassesPreset;
 use crate::data::QueryParams;
+use crate::report::CrashInfo;
 
 #[derive(TableRow, Debug, Clone)]
 #[table(sortable, classes_provider = ClassesPreset)]
@@ -28,11 +38,14 @@ pub struct CrashRow {
     pub id: Uuid,
     pub product: String,
     pub version: String,
-    pub summary: String,
+    pub platform: String,
+    pub environment: String,
+    pub signature: String,
+    pub severity: String,
+    pub status: String,
+    pub processing: String,
     #[table(format(string = "%d/%m/%Y - %H:%M"))]
     pub created_at: NaiveDateTime,
-    #[table(format(string = "%d/%m/%Y - %H:%M"))]
-    pub updated_at: NaiveDateTime,
     #[table(skip)]
     pub product_id: Option<Uuid>,
     #[table(skip)]
@@ -50,6 +63,26 @@ pub struct Crash {
     pub version_id: Uuid,
     pub product: String,
     pub version: String,
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    pub platform: Option<String>,
+    pub environment: Option<String>,
+    /// Whether the crash was submitted with a credential, as opposed to a public submission.
+    pub authenticated: bool,
+    /// Set while the crash is on legal hold, which exempts it from deletion.
+    pub legal_hold_at: Option<NaiveDateTime>,
+    pub legal_hold_reason: Option<String>,
+    /// SHA-256 checksum sent by the client and verified against the minidump.
+    pub verified_checksum: Option<String>,
+    pub signature: Option<String>,
+    pub crash_type: Option<String>,
+    pub status: Option<String>,
+    /// Whether the minidump was processed: `pending`, `processing`, `failed` or `done`.
+    pub processing_status: String,
+    /// Why processing failed.
+    pub processing_error: Option<String>,
+    /// Seconds the clock of the client was ahead of the server when it sent the crash.
+    pub clock_skew: Option<i64>,
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -63,6 +96,239 @@ pub struct Crash {
     pub version_id: Uuid,
     pub product: String,
     pub version: String,
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    pub platform: Option<String>,
+    pub environment: Option<String>,
+    /// Whether the crash was submitted with a credential, as opposed to a public submission.
+    pub authenticated: bool,
+    /// Set while the crash is on legal hold, which exempts it from deletion.
+    pub legal_hold_at: Option<NaiveDateTime>,
+    pub legal_hold_reason: Option<String>,
+    /// SHA-256 checksum sent by the client and verified against the minidump.
+    pub verified_checksum: Option<String>,
+    pub signature: Option<String>,
+    pub crash_type: Option<String>,
+    pub status: Option<String>,
+    /// Whether the minidump was processed: `pending`, `processing`, `failed` or `done`.
+    pub processing_status: String,
+    /// Why processing failed.
+    pub processing_error: Option<String>,
+    /// Seconds the clock of the client was ahead of the server when it sent the crash.
+    pub clock_skew: Option<i64>,
+}
+
+/// How bad a crash is: fatal crashes have an exception or signal, non-fatal ones are dumps
+/// written without one, e.g. for a hang.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrashSeverity {
+    Fatal,
+    NonFatal,
+    Unknown,
+}
+
+impl CrashSeverity {
+    pub fn new(crash_type: Option<&str>, status: Option<&str>) -> Self {
+        match (crash_type, status) {
+            (Some(_), _) => CrashSeverity::Fatal,
+            (None, Some("OK")) => CrashSeverity::NonFatal,
+            _ => CrashSeverity::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrashSeverity::Fatal => "fatal",
+            CrashSeverity::NonFatal => "non-fatal",
+            CrashSeverity::Unknown => "unknown",
+        }
+    }
+}
+
+/// Time ranges offered as quick filters on the crashes page.
+pub const TIME_RANGES: [(&str, &str); 4] = [
+    ("24h", "Last 24 hours"),
+    ("7d", "Last 7 days"),
+    ("30d", "Last 30 days"),
+    ("90d", "Last 90 days"),
+];
+
+/// Splits a crash filter into its time range and the remaining filter. A time range is given as
+/// a leading `since:<n>h`, `since:<n>d` or `since:<n>w` term, e.g. `since:7d SIGSEGV`, or as a
+/// range of dates, e.g. `since:2024-11-01..2024-11-30`, see `time_range_bounds`.
+pub fn split_time_range(filter: &str) -> (Option<&str>, &str) {
+    let filter = filter.trim();
+    match filter.strip_prefix("since:") {
+        Some(rest) => {
+            let (range, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            (Some(range), rest.trim())
+        }
+        None => (None, filter),
+    }
+}
+
+/// Combines a time range and the remaining filter again, see `split_time_range`.
+pub fn with_time_range(range: Option<&str>, filter: &str) -> String {
+    match range {
+        Some(range) => format!("since:{} {}", range, filter).trim().to_string(),
+        None => filter.to_string(),
+    }
+}
+
+/// Returns the duration of a time range such as `24h` or `7d`.
+pub fn time_range_duration(range: &str) -> Option<chrono::Duration> {
+    let unit = range.chars().last()?;
+    let count: i64 = range[..range.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'h' => chrono::Duration::try_hours(count),
+        'd' => chrono::Duration::try_days(count),
+        'w' => chrono::Duration::try_weeks(count),
+        _ => None,
+    }
+}
+
+/// Returns the start and end of a time range. A relative range such as `7d` ends now; a range of
+/// dates, `<from>..<until>` with either date left out for an open end, includes the end date.
+pub fn time_range_bounds(
+    range: &str,
+    now: NaiveDateTime,
+) -> Option<(Option<NaiveDateTime>, Option<NaiveDateTime>)> {
+    let Some((from, until)) = range.split_once("..") else {
+        return Some((Some(now - time_range_duration(range)?), None));
+    };
+    let date = |date: &str| match date.trim() {
+        "" => Some(None),
+        date => chrono::NaiveDate::parse_from_str(date, DATE_FORMAT)
+            .ok()
+            .map(Some),
+    };
+    let from = date(from)?.map(|from| from.and_time(chrono::NaiveTime::MIN));
+    let until = match date(until)? {
+        Some(until) => Some(until.succ_opt()?.and_time(chrono::NaiveTime::MIN)),
+        None => None,
+    };
+    Some((from, until))
+}
+
+/// Format of the dates of a time range, as used by date inputs.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Prefix of the filter on the signature of crashes, e.g. `signature:QString::`.
+pub const SIGNATURE_FILTER: &str = "signature:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SdkCount {
+    pub sdk: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetValue {
+    pub value: String,
+    pub count: i64,
+}
+
+/// The most common values of an annotation among the crashes matching the current filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Facet {
+    pub key: String,
+    pub values: Vec<FacetValue>,
+}
+
+#[cfg(feature = "ssr")]
+const FACET_LIMIT: usize = 10;
+
+/// Key of the facet and filter on the platform of crashes, which is a column rather than an
+/// annotation.
+pub const PLATFORM_FACET: &str = "platform";
+/// Key of the facet and filter on the environment of crashes, which is a column as well.
+pub const ENVIRONMENT_FACET: &str = "environment";
+/// Key of the facet and filter on the processing status of crashes, e.g. `processing:failed`.
+pub const PROCESSING_FACET: &str = "processing";
+
+/// Returns the crash column behind a facet key, for facets that are not annotations.
+#[cfg(feature = "ssr")]
+fn facet_column(key: &str) -> Option<entity::crash::Column> {
+    match key {
+        PLATFORM_FACET => Some(entity::crash::Column::Platform),
+        ENVIRONMENT_FACET => Some(entity::crash::Column::Environment),
+        PROCESSING_FACET => Some(entity::crash::Column::ProcessingStatus),
+        _ => None,
+    }
+}
+
+/// The values of an annotation over the crashes with the same signature, see
+/// `crash_annotation_diff`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationSpread {
+    pub key: String,
+    pub crashes: i64,
+    pub distinct: usize,
+    pub values: Vec<FacetValue>,
+    pub constant: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationDiff {
+    pub signature: Option<String>,
+    /// Number of crashes compared, at most `DIFF_CRASHES`.
+    pub crashes: i64,
+    pub annotations: Vec<AnnotationSpread>,
+}
+
+/// Number of most recent crashes with the same signature that are compared.
+pub const DIFF_CRASHES: u64 = 1000;
+
+/// The signature of a crash as computed by `CrashInfo::signature`, for use in queries.
+#[cfg(feature = "ssr")]
+const SIGNATURE_EXPR: &str = r#"COALESCE("crash"."report"->'crashing_thread'->'frames'->0->>'function', "crash"."report"->'crashing_thread'->'frames'->0->>'module')"#;
+
+/// The signature of a crash, read from the report or the `signature` column depending on the
+/// phase of the `crash_signature` rollout.
+#[cfg(feature = "ssr")]
+fn signature_expr() -> String {
+    settings()
+        .rollout
+        .crash_signature
+        .read_expr(SIGNATURE_EXPR, r#""crash"."signature""#)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashLink {
+    pub name: String,
+    pub url: String,
+    /// Metadata of the CI build the link refers to, when the link template has a metadata URL
+    /// and the metadata has been fetched.
+    pub build: Option<BuildInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildArtifact {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub status: Option<String>,
+    pub artifacts: Vec<BuildArtifact>,
+    /// Why the metadata could not be fetched.
+    pub error: Option<String>,
+    pub fetched_at: NaiveDateTime,
+}
+
+#[cfg(feature = "ssr")]
+fn promoted_filter(filter: &str) -> Option<(&str, &str)> {
+    let (key, value) = filter.split_once(':')?;
+    let (key, value) = (key.trim(), value.trim());
+    let valid_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-');
+    if !valid_key || value.is_empty() {
+        return None;
+    }
+    Some((key, value))
 }
 
 #[cfg(feature = "ssr")]
@@ -73,23 +339,108 @@ impl EntityInfo for entity::crash::Entity {
         entity::crash::Column::Report
     }
 
+    // Signature, severity and status are derived from the report and cannot be sorted on.
     fn index_to_column(index: usize) -> Option<Self::Column> {
         match index {
             0 => Some(entity::crash::Column::Id),
-            1 => Some(entity::crash::Column::Report),
-            2 => Some(entity::crash::Column::Summary),
-            3 => Some(entity::crash::Column::CreatedAt),
-            4 => Some(entity::crash::Column::UpdatedAt),
+            1 => Some(entity::crash::Column::ProductId),
+            2 => Some(entity::crash::Column::VersionId),
+            3 => Some(entity::crash::Column::Platform),
+            4 => Some(entity::crash::Column::Environment),
+            8 => Some(entity::crash::Column::ProcessingStatus),
+            9 => Some(entity::crash::Column::CreatedAt),
             _ => None,
         }
     }
 
+    // A leading `since:` term limits the crashes to a time range, see `split_time_range`. A
+    // `platform:<platform>`, `environment:<environment>` or `processing:<status>` filter matches
+    // that column, an `issue:<id>` filter the crashes of an issue and a `signature:<text>` filter
+    // the crashes whose signature contains the text. Any other `key:value` filter matches a promoted
+    // annotation, which is served by the index on `crash.promoted` on PostgreSQL. Any other filter
+    // is matched against the report.
+    fn extend_query_for_filter(
+        query: Select<Self>,
+        filter: String,
+        backend: DbBackend,
+    ) -> Select<Self> {
+        let (range, filter) = split_time_range(&filter);
+        let now = chrono::Utc::now().naive_utc();
+        let (from, until) = range
+            .and_then(|range| time_range_bounds(range, now))
+            .unwrap_or_default();
+        let query = match from {
+            Some(from) => query.filter(entity::crash::Column::CreatedAt.gte(from)),
+            None => query,
+        };
+        let query = match until {
+            Some(until) => query.filter(entity::crash::Column::CreatedAt.lt(until)),
+            None => query,
+        };
+        if filter.is_empty() {
+            return query;
+        }
+        if let Some(signature) = filter.strip_prefix(SIGNATURE_FILTER) {
+            let pattern = signature
+                .trim()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            return query.filter(Expr::cust_with_values(
+                format!("{} ILIKE $1", signature_expr()),
+                [format!("%{}%", pattern)],
+            ));
+        }
+        if let Some(issue_id) = filter
+            .strip_prefix("issue:")
+            .and_then(|id| Uuid::parse_str(id.trim()).ok())
+        {
+            return query.filter(entity::crash::Column::IssueId.eq(issue_id));
+        }
+        if let Some((column, value)) = filter
+            .split_once(':')
+            .and_then(|(key, value)| Some((facet_column(key)?, value)))
+        {
+            return query.filter(column.eq(value.trim()));
+        }
+        match promoted_filter(filter) {
+            Some((key, value)) if backend == DbBackend::Postgres => {
+                query.filter(Expr::cust_with_values(
+                    r#""crash"."promoted" @> $1"#,
+                    [serde_json::json!({ key: value })],
+                ))
+            }
+            Some((key, value)) => query.filter(Expr::cust_with_values(
+                r#"json_extract("crash"."promoted", ?) = ?"#,
+                [format!("$.\"{}\"", key), value.to_owned()],
+            )),
+            None => query.filter(Self::filter_column().contains(filter)),
+        }
+    }
+
+    // The `signature` column is replaced by the signature of the current rollout phase.
     fn extend_query_for_view(query: Select<Self>) -> Select<Self> {
         query
+            .select_only()
+            .columns(
+                entity::crash::Column::iter()
+                    .filter(|column| *column != entity::crash::Column::Signature),
+            )
             .join(JoinType::LeftJoin, entity::crash::Relation::Product.def())
             .join(JoinType::LeftJoin, entity::crash::Relation::Version.def())
             .column_as(entity::product::Column::Name, "product")
             .column_as(entity::version::Column::Name, "version")
+            .column_as(Expr::cust(signature_expr()), "signature")
+            .column_as(
+                Expr::cust(r#""crash"."report"->'crash_info'->>'type'"#),
+                "crash_type",
+            )
+            .column_as(
+                Expr::cust(
+                    r#"COALESCE("crash"."report"->>'status', "crash"."report"->>'processing_error')"#,
+                ),
+                "status",
+            )
     }
 
     fn get_product_query(
@@ -112,15 +463,23 @@ impl EntityInfo for entity::crash::Entity {
 }
 impl From<Crash> for CrashRow {
     fn from(crash: Crash) -> Self {
+        let severity = CrashSeverity::new(crash.crash_type.as_deref(), crash.status.as_deref());
         Self {
             id: crash.id,
-            summary: crash.summary,
+            signature: crash.signature.unwrap_or(crash.summary),
+            severity: severity.as_str().to_string(),
+            status: crash
+                .status
+                .map(|status| status.to_lowercase())
+                .unwrap_or_else(|| "unprocessed".to_string()),
             created_at: crash.created_at,
-            updated_at: crash.updated_at,
             product_id: Some(crash.product_id),
             version_id: Some(crash.version_id),
             product: crash.product,
             version: crash.version,
+            platform: crash.platform.unwrap_or_else(|| "unknown".to_string()),
+            environment: crash.environment.unwrap_or_default(),
+            processing: crash.processing_status,
         }
     }
 }
@@ -128,6 +487,12 @@ impl From<Crash> for CrashRow {
 #[cfg(feature = "ssr")]
 impl From<entity::crash::Model> for Crash {
     fn from(model: entity::crash::Model) -> Self {
+        let info = CrashInfo::from_report(&model.report).unwrap_or_default();
+        let status = info.status.clone().or_else(|| {
+            model.report["processing_error"]
+                .as_str()
+                .map(str::to_string)
+        });
         Self {
             id: model.id,
             summary: model.summary,
@@ -137,6 +502,23 @@ impl From<entity::crash::Model> for Crash {
             version_id: model.version_id,
             product: "".to_string(),
             version: "".to_string(),
+            user_agent: model.user_agent,
+            sdk: model.sdk,
+            platform: model.platform,
+            environment: model.environment,
+            authenticated: model.authenticated,
+            legal_hold_at: model.legal_hold_at,
+            legal_hold_reason: model.legal_hold_reason,
+            verified_checksum: model.verified_checksum,
+            signature: settings()
+                .rollout
+                .crash_signature
+                .read(|| info.signature(), model.signature),
+            crash_type: info.crash_info.and_then(|details| details.crash_type),
+            status,
+            processing_status: model.processing_status,
+            processing_error: model.processing_error,
+            clock_skew: model.clock_skew,
         }
     }
 }
@@ -152,6 +534,23 @@ impl From<Crash> for entity::crash::ActiveModel {
             updated_at: sea_orm::NotSet,
             product_id: Set(crash.product_id),
             version_id: Set(crash.version_id),
+            user_agent: sea_orm::NotSet,
+            sdk: sea_orm::NotSet,
+            promoted: sea_orm::NotSet,
+            minidump_hash: sea_orm::NotSet,
+            platform: sea_orm::NotSet,
+            environment: sea_orm::NotSet,
+            issue_id: sea_orm::NotSet,
+            authenticated: sea_orm::NotSet,
+            legal_hold_at: sea_orm::NotSet,
+            legal_hold_by: sea_orm::NotSet,
+            legal_hold_reason: sea_orm::NotSet,
+            verified_checksum: sea_orm::NotSet,
+            signature: sea_orm::NotSet,
+            processing_status: sea_orm::NotSet,
+            processing_error: sea_orm::NotSet,
+            received_at: sea_orm::NotSet,
+            clock_skew: sea_orm::NotSet,
         }
     }
 }
@@ -162,7 +561,7 @@ impl ExtraRowTrait for CrashRow {
     }
 
     fn get_name(&self) -> String {
-        self.summary.clone()
+        self.signature.clone()
     }
 }
 
@@ -196,14 +595,493 @@ pub async fn crash_update(crash: Crash) -> Result<(), ServerFnError> {
     update::<entity::crash::Entity>(crash).await
 }
 
+/// Fails if any crash for which `column` equals `id` is on legal hold, see
+/// `CrashRepo::count_held`.
+#[cfg(feature = "ssr")]
+pub async fn check_not_held(column: entity::crash::Column, id: Uuid) -> Result<(), ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let held = CrashRepo::count_held(&db, column, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    match held {
+        0 => Ok(()),
+        _ => Err(ServerFnError::new(
+            "Crashes on legal hold cannot be deleted".to_string(),
+        )),
+    }
+}
+
 #[server]
 pub async fn crash_remove(id: Uuid) -> Result<(), ServerFnError> {
+    check_not_held(entity::crash::Column::Id, id).await?;
     delete_by_id::<entity::crash::Entity>(id).await
 }
 
+/// Places a crash on legal hold with the given reason, or releases it with `None`. Only for
+/// administrators.
+#[server]
+pub async fn crash_set_legal_hold(id: Uuid, reason: Option<String>) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if !user.is_admin {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+
+    let reason = reason.map(|reason| reason.trim().to_owned());
+    if reason.as_ref().is_some_and(|reason| reason.is_empty()) {
+        return Err(ServerFnError::new("A reason is required".to_string()));
+    }
+    CrashRepo::set_legal_hold(&db, id, reason.clone().map(|reason| (reason, user.id)))
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    match reason {
+        Some(reason) => tracing::info!(
+            audit = "legal_hold",
+            "crash {} placed on legal hold by {}: {}",
+            id,
+            user.username,
+            reason
+        ),
+        None => tracing::info!(
+            audit = "legal_hold",
+            "legal hold of crash {} released by {}",
+            id,
+            user.username
+        ),
+    }
+    Ok(())
+}
+
+/// Counts the crashes of the products the user has a role for. Unlike the generic `count`, this
+/// joins the product, which the access check of crashes relies on.
 #[server]
 pub async fn crash_count(
     #[server(default)] parents: HashMap<String, Uuid>,
 ) -> Result<usize, ServerFnError> {
-    count::<entity::crash::Entity>(parents).await
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let Some(user) = authenticated_user().await? else {
+        return Ok(0);
+    };
+
+    let mut query = entity::crash::Entity::find();
+    query = entity::crash::Entity::extend_query_for_view(query);
+    query = entity::crash::Entity::extend_query_for_access(query, user, vec![]);
+    for (parent, parent_id) in parents {
+        let column = entity::crash::Entity::id_to_column(parent)
+            .ok_or(ServerFnError::new("Invalid parent column".to_string()))?;
+        query = query.filter(column.eq(parent_id));
+    }
+
+    let count = PaginatorTrait::count(query, &db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(count as usize)
+}
+
+/// Starts rendering the PDF of the crash in the background. Returns whether the PDF is
+/// already available for download.
+#[server]
+pub async fn crash_pdf_request(id: Uuid) -> Result<bool, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    check_access_by_id::<entity::crash::Entity>(id, vec![]).await?;
+
+    if CrashPdf::is_ready(id) {
+        return Ok(true);
+    }
+    CrashPdf::spawn(db, id);
+    Ok(false)
+}
+
+/// Returns the crash after checking that the authenticated user may view it, with the report
+/// redacted for the user, see `CrashRepo::get_for_user`.
+#[cfg(feature = "ssr")]
+async fn crash_for_user(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<crate::model::crash::Crash, ServerFnError> {
+    check_access_by_id::<entity::crash::Entity>(id, vec![]).await?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    CrashRepo::get_for_user(db, id, user.id, user.is_admin)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the stack of the crashing thread as plain text, as served by the stack.txt API.
+#[server]
+pub async fn crash_stack_text(
+    id: Uuid,
+    frames: usize,
+    modules: bool,
+) -> Result<String, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let crash = crash_for_user(&db, id).await?;
+    let info = CrashInfo::from_report(&crash.report).unwrap_or_default();
+    Ok(info.stack_text(frames, modules))
+}
+
+/// Returns the crash details, crashing thread, modules, system and attachment findings of the
+/// report of a crash, for the crash page. The other threads, the provenance and the analysis are
+/// left out, and the redacted fields of the product are hidden.
+#[server]
+pub async fn crash_report_details(id: Uuid) -> Result<CrashInfo, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let crash = crash_for_user(&db, id).await?;
+    let info = CrashInfo::from_report(&crash.report).unwrap_or_default();
+    Ok(CrashInfo {
+        threads: vec![],
+        provenance: None,
+        analysis: vec![],
+        ..info
+    })
+}
+
+/// Returns whether the authenticated user sees the report of the crash unredacted, see
+/// `CrashRepo::sees_unredacted`.
+#[server]
+pub async fn crash_report_unredacted(id: Uuid) -> Result<bool, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    let product_id = entity::prelude::Crash::find_by_id(id)
+        .select_only()
+        .column(entity::crash::Column::ProductId)
+        .into_tuple::<Uuid>()
+        .one(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .ok_or(ServerFnError::new("not found".to_string()))?;
+    CrashRepo::sees_unredacted(&db, product_id, user.id, user.is_admin)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the stored crash report exactly as processing produced it, for debugging. Only
+/// available to users who see the report unredacted.
+#[server]
+pub async fn crash_raw_report(id: Uuid) -> Result<String, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    if !crash_report_unredacted(id).await? {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+    let crash = CrashRepo::get_by_id(&db, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    serde_json::to_string_pretty(&crash.report).map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the readable title of the crash, see `CrashInfo::title`.
+#[server]
+pub async fn crash_title(id: Uuid) -> Result<Option<String>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let crash = crash_for_user(&db, id).await?;
+    Ok(CrashInfo::from_report(&crash.report)
+        .ok()
+        .and_then(|info| info.title()))
+}
+
+/// Compares the annotations of the crashes with the same signature as the crash: annotations
+/// that have the same value on all of them, and the distribution of the ones that vary. Until
+/// crashes are grouped into issues, the signature defines the group.
+#[server]
+pub async fn crash_annotation_diff(id: Uuid) -> Result<AnnotationDiff, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let crash = crash_for_user(&db, id).await?;
+    let signature = settings().rollout.crash_signature.read(
+        || {
+            CrashInfo::from_report(&crash.report)
+                .ok()
+                .and_then(|info| info.signature())
+        },
+        crash.signature.clone(),
+    );
+    let Some(signature) = signature else {
+        return Ok(AnnotationDiff::default());
+    };
+
+    let crashes = entity::crash::Entity::find()
+        .select_only()
+        .column(entity::crash::Column::Id)
+        .filter(entity::crash::Column::ProductId.eq(crash.product_id))
+        .filter(Expr::cust_with_values(
+            format!("{} = $1", signature_expr()),
+            [signature.clone()],
+        ))
+        .order_by_desc(entity::crash::Column::CreatedAt)
+        .limit(DIFF_CRASHES);
+    let ids = crashes
+        .clone()
+        .into_tuple::<Uuid>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    let total = ids.len() as i64;
+
+    let counts = AnnotationRepo::value_counts(&db, crashes.into_query())
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    let annotations = spread(total, counts, FACET_LIMIT)
+        .into_iter()
+        .map(|spread| AnnotationSpread {
+            constant: spread.is_constant(total),
+            key: spread.key,
+            crashes: spread.crashes,
+            distinct: spread.distinct,
+            values: spread
+                .values
+                .into_iter()
+                .map(|(value, count)| FacetValue { value, count })
+                .collect(),
+        })
+        .collect();
+
+    Ok(AnnotationDiff {
+        signature: Some(signature),
+        crashes: total,
+        annotations,
+    })
+}
+
+#[server]
+pub async fn crash_links(id: Uuid) -> Result<Vec<CrashLink>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let crash = crash_for_user(&db, id).await?;
+    let templates = LinkTemplateRepo::get_by_product(&db, crash.product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    let values = crash_values(
+        crash.id,
+        crash.product_id,
+        crash.version_id,
+        crash
+            .annotations
+            .into_iter()
+            .map(|annotation| (annotation.key, annotation.value)),
+    );
+
+    let mut links = vec![];
+    for template in templates {
+        let Some(url) = render_link(&template.url, &values) else {
+            continue;
+        };
+        let metadata_url = template
+            .metadata_url
+            .as_deref()
+            .and_then(|metadata_url| render_link(metadata_url, &values));
+        let build = match metadata_url {
+            Some(metadata_url) => BuildMetadataRepo::get(&db, template.id, &metadata_url)
+                .await
+                .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+                .map(BuildInfo::from),
+            None => None,
+        };
+        links.push(CrashLink {
+            name: template.name,
+            url,
+            build,
+        });
+    }
+    Ok(links)
+}
+
+#[server]
+pub async fn crash_sdk_distribution(
+    #[server(default)] parents: HashMap<String, Uuid>,
+) -> Result<Vec<SdkCount>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    let mut query = entity::crash::Entity::find();
+    query = entity::crash::Entity::extend_query_for_view(query);
+    query = entity::crash::Entity::extend_query_for_access(query, user, vec![]);
+    for (parent, parent_id) in parents {
+        let column = entity::crash::Entity::id_to_column(parent)
+            .ok_or(ServerFnError::new("Invalid parent column".to_string()))?;
+        query = query.filter(column.eq(parent_id));
+    }
+
+    let counts = query
+        .select_only()
+        .column(entity::crash::Column::Sdk)
+        .column_as(entity::crash::Column::Id.count(), "count")
+        .group_by(entity::crash::Column::Sdk)
+        .into_tuple::<(Option<String>, i64)>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    let mut counts: Vec<SdkCount> = counts
+        .into_iter()
+        .map(|(sdk, count)| SdkCount {
+            sdk: sdk.unwrap_or_else(|| "unknown".to_string()),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(counts)
+}
+
+/// Counts annotation values for the given keys over the crashes matching `filter`. Without keys,
+/// the platform, the environment, the processing status and the promoted annotations of the
+/// products in scope are used, as those can also be filtered on efficiently.
+#[server]
+pub async fn crash_facets(
+    #[server(default)] parents: HashMap<String, Uuid>,
+    filter: String,
+    keys: Vec<String>,
+) -> Result<Vec<Facet>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    let columns: Vec<(&str, entity::crash::Column)> =
+        [PLATFORM_FACET, ENVIRONMENT_FACET, PROCESSING_FACET]
+            .into_iter()
+            .filter(|column| keys.is_empty() || keys.iter().any(|key| key == column))
+            .filter_map(|key| Some((key, facet_column(key)?)))
+            .collect();
+    let keys = if keys.is_empty() {
+        let mut products = entity::product::Entity::find();
+        if let Some(product_id) = parents.get("product_id") {
+            products = products.filter(entity::product::Column::Id.eq(*product_id));
+        }
+        let mut keys: Vec<String> = products
+            .all(&db)
+            .await
+            .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+            .iter()
+            .flat_map(|product| promoted_keys(product.promoted_annotations.as_deref()))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    } else {
+        keys
+    };
+    let keys: Vec<String> = keys
+        .into_iter()
+        .filter(|key| facet_column(key).is_none())
+        .collect();
+    if keys.is_empty() && columns.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut query = entity::crash::Entity::find();
+    query = entity::crash::Entity::extend_query_for_view(query);
+    query = entity::crash::Entity::extend_query_for_access(query, user, vec![]);
+    if !filter.is_empty() {
+        query = entity::crash::Entity::extend_query_for_filter(
+            query,
+            filter,
+            db.get_database_backend(),
+        );
+    }
+    for (parent, parent_id) in parents {
+        let column = entity::crash::Entity::id_to_column(parent)
+            .ok_or(ServerFnError::new("Invalid parent column".to_string()))?;
+        query = query.filter(column.eq(parent_id));
+    }
+
+    let mut facets = vec![];
+    for (key, column) in columns {
+        let counts = query
+            .clone()
+            .select_only()
+            .column(column)
+            .column_as(entity::crash::Column::Id.count(), "count")
+            .group_by(column)
+            .into_tuple::<(Option<String>, i64)>()
+            .all(&db)
+            .await
+            .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+        let mut values: Vec<FacetValue> = counts
+            .into_iter()
+            .filter_map(|(value, count)| {
+                Some(FacetValue {
+                    value: value?,
+                    count,
+                })
+            })
+            .collect();
+        values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        values.truncate(FACET_LIMIT);
+        if !values.is_empty() {
+            facets.push(Facet {
+                key: key.to_string(),
+                values,
+            });
+        }
+    }
+    if keys.is_empty() {
+        return Ok(facets);
+    }
+
+    let crashes = query
+        .select_only()
+        .column(entity::crash::Column::Id)
+        .into_query();
+
+    let counts = entity::annotation::Entity::find()
+        .select_only()
+        .column(entity::annotation::Column::Key)
+        .column(entity::annotation::Column::Value)
+        .column_as(entity::annotation::Column::CrashId.count(), "count")
+        .filter(entity::annotation::Column::Key.is_in(keys.clone()))
+        .filter(entity::annotation::Column::CrashId.in_subquery(crashes))
+        .group_by(entity::annotation::Column::Key)
+        .group_by(entity::annotation::Column::Value)
+        .into_tuple::<(String, String, i64)>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    let mut values: HashMap<String, Vec<FacetValue>> = HashMap::new();
+    for (key, value, count) in counts {
+        values
+            .entry(key)
+            .or_default()
+            .push(FacetValue { value, count });
+    }
+    facets.extend(keys.into_iter().filter_map(|key| {
+        let mut values = values.remove(&key)?;
+        values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        values.truncate(FACET_LIMIT);
+        Some(Facet { key, values })
+    }));
+    Ok(facets)
 }
@@ -0,0 +1,85 @@
+use ::chrono::NaiveDateTime;
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use crate::entity;
+    use crate::authenticated_user;
+    use crate::data::check_access_by_id;
+    use crate::model::device_key::DeviceKeyRepo;
+    use crate::model::role;
+    use crate::read_only::check_writable;
+}}
+
+/// A device key of a product with the number of crashes submitted with it. The key hash is never
+/// sent to the browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceKey {
+    pub id: Uuid,
+    pub name: String,
+    pub entitlements: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub submission_count: i64,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::model::device_key::DeviceKey> for DeviceKey {
+    fn from(model: crate::model::device_key::DeviceKey) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            entitlements: model.entitlements,
+            created_at: model.created_at,
+            submission_count: model.submission_count,
+            last_used_at: model.last_used_at,
+        }
+    }
+}
+
+#[server]
+pub async fn device_key_list(product_id: Uuid) -> Result<Vec<DeviceKey>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    check_access_by_id::<entity::product::Entity>(product_id, role::at_least(role::ADMIN)).await?;
+
+    let keys = DeviceKeyRepo::get_by_product(&db, product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(keys.into_iter().map(DeviceKey::from).collect())
+}
+
+/// Creates a device key for the product and returns its ID and the key. The key itself is only
+/// shown once, when created.
+#[server]
+pub async fn device_key_create(
+    product_id: Uuid,
+    name: String,
+) -> Result<(Uuid, String), ServerFnError> {
+    check_writable()?;
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Key name is required".to_string()));
+    }
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    check_access_by_id::<entity::product::Entity>(product_id, role::at_least(role::ADMIN)).await?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    let (id, key) = DeviceKeyRepo::create(&db, product_id, name.clone())
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "device_key",
+        "device key {} for product {} created by {}",
+        name,
+        product_id,
+        user.username
+    );
+    Ok((id, key))
+}
@@ -0,0 +1,422 @@
+use ::chrono::{NaiveDate, NaiveDateTime};
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use ::chrono::NaiveTime;
+    use sea_orm::*;
+    use sea_query::{Alias, Expr};
+    use crate::entity;
+    use crate::auth::AuthenticatedUser;
+    use crate::authenticated_user;
+    use crate::data::{check_access_by_id, get_all, get_by_id, EntityInfo};
+    use crate::model::issue::IssueRepo;
+    use crate::model::role;
+    use crate::read_only::check_writable;
+}}
+
+pub const ISSUE_OPEN: &str = "open";
+pub const ISSUE_RESOLVED: &str = "resolved";
+pub const ISSUE_IGNORED: &str = "ignored";
+pub const ISSUE_MUTED: &str = "muted";
+pub const ISSUE_SNOOZED: &str = "snoozed";
+
+/// Statuses of issues, with their label.
+pub const ISSUE_STATUSES: [(&str, &str); 5] = [
+    (ISSUE_OPEN, "Open"),
+    (ISSUE_RESOLVED, "Resolved"),
+    (ISSUE_IGNORED, "Ignored"),
+    (ISSUE_MUTED, "Muted"),
+    (ISSUE_SNOOZED, "Snoozed"),
+];
+
+/// Statuses of issues that are known and not actionable. These issues are left out of lists
+/// unless their status is asked for, and crashes of them are not notified.
+pub const QUIET_STATUSES: [&str; 2] = [ISSUE_MUTED, ISSUE_SNOOZED];
+
+/// Maximum number of days without crashes after which issues are resolved automatically.
+pub const MAX_AUTO_RESOLVE_DAYS: i32 = 365;
+
+use crate::data::QueryParams;
+
+/// Crashes of a product grouped by their signature.
+#[cfg(feature = "ssr")]
+#[derive(FromQueryResult, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Issue {
+    pub id: Uuid,
+    pub signature: String,
+    pub title: String,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub count: i64,
+    pub product_id: Uuid,
+    pub product: String,
+    pub status: String,
+    pub assignee_id: Option<Uuid>,
+    pub assignee: Option<String>,
+    /// Until when a snoozed issue is hidden.
+    pub snoozed_until: Option<NaiveDateTime>,
+    /// Number of days without crashes after which the issue is resolved.
+    pub auto_resolve_days: Option<i32>,
+}
+
+/// Crashes of a product grouped by their signature.
+#[cfg(not(feature = "ssr"))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Issue {
+    pub id: Uuid,
+    pub signature: String,
+    pub title: String,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub count: i64,
+    pub product_id: Uuid,
+    pub product: String,
+    pub status: String,
+    pub assignee_id: Option<Uuid>,
+    pub assignee: Option<String>,
+    /// Until when a snoozed issue is hidden.
+    pub snoozed_until: Option<NaiveDateTime>,
+    /// Number of days without crashes after which the issue is resolved.
+    pub auto_resolve_days: Option<i32>,
+}
+
+/// Returns the crash filter that lists the crashes of an issue.
+pub fn issue_filter(id: Uuid) -> String {
+    format!("issue:{}", id)
+}
+
+/// Splits an issue filter into its status and the remaining filter on the title. A status is
+/// given as a leading `status:<status>` term, e.g. `status:open Timer`.
+pub fn split_status(filter: &str) -> (Option<&str>, &str) {
+    let filter = filter.trim();
+    match filter.strip_prefix("status:") {
+        Some(rest) => {
+            let (status, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            (Some(status), rest.trim())
+        }
+        None => (None, filter),
+    }
+}
+
+/// Combines a status and the remaining filter again, see `split_status`.
+pub fn with_status(status: Option<&str>, filter: &str) -> String {
+    match status {
+        Some(status) => format!("status:{} {}", status, filter).trim().to_string(),
+        None => filter.to_string(),
+    }
+}
+
+/// Number of issues with a status among the issues matching a filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[cfg(feature = "ssr")]
+impl From<entity::issue::Model> for Issue {
+    fn from(model: entity::issue::Model) -> Self {
+        Self {
+            id: model.id,
+            signature: model.signature,
+            title: model.title,
+            first_seen: model.first_seen,
+            last_seen: model.last_seen,
+            count: model.count,
+            product_id: model.product_id,
+            product: "".to_string(),
+            status: model.status,
+            assignee_id: model.assignee_id,
+            assignee: None,
+            snoozed_until: model.snoozed_until,
+            auto_resolve_days: model.auto_resolve_days,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl EntityInfo for entity::issue::Entity {
+    type View = Issue;
+
+    fn filter_column() -> Self::Column {
+        entity::issue::Column::Title
+    }
+
+    fn index_to_column(index: usize) -> Option<Self::Column> {
+        match index {
+            0 => Some(entity::issue::Column::Title),
+            1 => Some(entity::issue::Column::ProductId),
+            2 => Some(entity::issue::Column::Count),
+            3 => Some(entity::issue::Column::FirstSeen),
+            4 => Some(entity::issue::Column::LastSeen),
+            5 => Some(entity::issue::Column::Status),
+            _ => None,
+        }
+    }
+
+    // Without a status term, muted and snoozed issues are left out; they are listed under their
+    // own status.
+    fn extend_query_for_filter(
+        query: Select<Self>,
+        filter: String,
+        _backend: DbBackend,
+    ) -> Select<Self> {
+        let (status, filter) = split_status(&filter);
+        let query = match status {
+            Some(status) => query.filter(entity::issue::Column::Status.eq(status)),
+            None => query.filter(entity::issue::Column::Status.is_not_in(QUIET_STATUSES)),
+        };
+        if filter.is_empty() {
+            return query;
+        }
+        query.filter(Self::filter_column().contains(filter))
+    }
+
+    // The assignee is joined under an alias, as the access check joins the users of the roles.
+    fn extend_query_for_view(query: Select<Self>) -> Select<Self> {
+        query
+            .join(JoinType::LeftJoin, entity::issue::Relation::Product.def())
+            .join_as(
+                JoinType::LeftJoin,
+                entity::issue::Entity::belongs_to(entity::user::Entity)
+                    .from(entity::issue::Column::AssigneeId)
+                    .to(entity::user::Column::Id)
+                    .into(),
+                Alias::new("assignee"),
+            )
+            .column_as(entity::product::Column::Name, "product")
+            .column_as(
+                Expr::col((Alias::new("assignee"), entity::user::Column::Username)),
+                "assignee",
+            )
+    }
+
+    fn get_product_query(
+        _user: &AuthenticatedUser,
+        data: &Self::View,
+    ) -> Option<Select<entity::product::Entity>> {
+        let query = entity::product::Entity::find().filter(
+            Expr::col((entity::product::Entity, entity::product::Column::Id)).eq(data.product_id),
+        );
+        Some(query)
+    }
+
+    fn id_to_column(id_name: String) -> Option<Self::Column> {
+        match id_name.as_str() {
+            "product_id" => Some(entity::issue::Column::ProductId),
+            _ => None,
+        }
+    }
+}
+
+#[server]
+pub async fn issue_get(id: Uuid) -> Result<Issue, ServerFnError> {
+    get_by_id::<entity::issue::Entity>(id).await
+}
+
+#[server]
+pub async fn issue_list(
+    #[server(default)] parents: HashMap<String, Uuid>,
+    query_params: QueryParams,
+) -> Result<Vec<Issue>, ServerFnError> {
+    get_all::<entity::issue::Entity>(query_params, parents).await
+}
+
+/// Returns the number of issues per status among the issues matching the filter, ignoring the
+/// status term of the filter.
+#[server]
+pub async fn issue_status_counts(
+    #[server(default)] parents: HashMap<String, Uuid>,
+    filter: String,
+) -> Result<Vec<StatusCount>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    let mut query = entity::issue::Entity::find();
+    query = entity::issue::Entity::extend_query_for_view(query);
+    query = entity::issue::Entity::extend_query_for_access(query, user, vec![]);
+    let (_, filter) = split_status(&filter);
+    if !filter.is_empty() {
+        query = query.filter(entity::issue::Column::Title.contains(filter));
+    }
+    if let Some(product_id) = parents.get("product_id") {
+        query = query.filter(entity::issue::Column::ProductId.eq(*product_id));
+    }
+
+    let counts = query
+        .select_only()
+        .column(entity::issue::Column::Status)
+        .column_as(entity::issue::Column::Id.count(), "count")
+        .group_by(entity::issue::Column::Status)
+        .into_tuple::<(String, i64)>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(counts
+        .into_iter()
+        .map(|(status, count)| StatusCount { status, count })
+        .collect())
+}
+
+/// Returns the names of the versions with crashes of each issue the user can access.
+#[server]
+pub async fn issue_versions(ids: Vec<Uuid>) -> Result<HashMap<Uuid, Vec<String>>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+
+    let mut query = entity::issue::Entity::find();
+    query = entity::issue::Entity::extend_query_for_view(query);
+    query = entity::issue::Entity::extend_query_for_access(query, user, vec![]);
+    let ids = query
+        .select_only()
+        .column(entity::issue::Column::Id)
+        .filter(entity::issue::Column::Id.is_in(ids))
+        .into_tuple::<Uuid>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    IssueRepo::affected_versions(&db, ids)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Sets the status of the issues, e.g. to resolve a selection of issues at once.
+#[server]
+pub async fn issue_set_status(ids: Vec<Uuid>, status: String) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if status == ISSUE_SNOOZED {
+        return Err(ServerFnError::new(
+            "Issues are snoozed until a date".to_string(),
+        ));
+    }
+    if !ISSUE_STATUSES.iter().any(|(name, _)| *name == status) {
+        return Err(ServerFnError::new(format!("Unknown status '{}'", status)));
+    }
+    for id in &ids {
+        check_access_by_id::<entity::issue::Entity>(*id, role::at_least(role::MAINTAINER)).await?;
+    }
+
+    IssueRepo::set_status(&db, ids.clone(), &status)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "issue",
+        "issues {:?} marked {} by {}",
+        ids,
+        status,
+        user.username
+    );
+    Ok(())
+}
+
+/// Hides the issues until the start of `until`, UTC; crashes of them are not notified until
+/// then.
+#[server]
+pub async fn issue_snooze(ids: Vec<Uuid>, until: NaiveDate) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    let until = until.and_time(NaiveTime::MIN);
+    if until <= chrono::Utc::now().naive_utc() {
+        return Err(ServerFnError::new(
+            "Issues can only be snoozed until a future date".to_string(),
+        ));
+    }
+    for id in &ids {
+        check_access_by_id::<entity::issue::Entity>(*id, role::at_least(role::MAINTAINER)).await?;
+    }
+
+    IssueRepo::snooze(&db, ids.clone(), until)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "issue",
+        "issues {:?} snoozed until {} by {}",
+        ids,
+        until,
+        user.username
+    );
+    Ok(())
+}
+
+/// Resolves the issues automatically once they have had no crashes for `days` days, or stops
+/// doing so when `days` is `None`.
+#[server]
+pub async fn issue_set_auto_resolve(
+    ids: Vec<Uuid>,
+    days: Option<i32>,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if days.is_some_and(|days| !(1..=MAX_AUTO_RESOLVE_DAYS).contains(&days)) {
+        return Err(ServerFnError::new(format!(
+            "Issues are resolved after 1 to {} days without crashes",
+            MAX_AUTO_RESOLVE_DAYS
+        )));
+    }
+    for id in &ids {
+        check_access_by_id::<entity::issue::Entity>(*id, role::at_least(role::MAINTAINER)).await?;
+    }
+
+    IssueRepo::set_auto_resolve(&db, ids.clone(), days)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    match days {
+        Some(days) => tracing::info!(
+            audit = "issue",
+            "issues {:?} set to resolve after {} days without crashes by {}",
+            ids,
+            days,
+            user.username
+        ),
+        None => tracing::info!(
+            audit = "issue",
+            "auto-resolve of issues {:?} disabled by {}",
+            ids,
+            user.username
+        ),
+    }
+    Ok(())
+}
+
+/// Assigns the issues to the user, or clears their assignee when `assign` is false.
+#[server]
+pub async fn issue_assign(ids: Vec<Uuid>, assign: bool) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    for id in &ids {
+        check_access_by_id::<entity::issue::Entity>(*id, role::at_least(role::MAINTAINER)).await?;
+    }
+
+    IssueRepo::set_assignee(&db, ids, assign.then_some(user.id))
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(())
+}
@@ -1,8 +1,17 @@
+pub mod api_token;
+pub mod branding;
 pub mod crash;
+pub mod device_key;
+pub mod issue;
+pub mod organization;
 pub mod product;
+pub mod search;
+pub mod setup;
+pub mod stats;
 pub mod symbols;
 pub mod user;
 pub mod version;
+pub mod webhook;
 
 use leptos::*;
 use uuid::Uuid;
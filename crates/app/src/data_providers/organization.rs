@@ -0,0 +1,166 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use crate::entity;
+    use crate::data_providers::user::check_admin;
+    use crate::model::organization::OrganizationRepo;
+    use crate::read_only::check_writable;
+}}
+
+/// An organization with the products it owns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub products: Vec<(Uuid, String)>,
+}
+
+/// A member of an organization and their role in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationMemberInfo {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: String,
+}
+
+/// Returns all organizations with their products. Only for administrators.
+#[server]
+pub async fn organization_list() -> Result<Vec<OrganizationInfo>, ServerFnError> {
+    let (db, _) = check_admin().await?;
+    let organizations = OrganizationRepo::get_all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    let products = entity::prelude::Product::find()
+        .filter(entity::product::Column::OrganizationId.is_not_null())
+        .order_by_asc(entity::product::Column::Name)
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(organizations
+        .into_iter()
+        .map(|organization| OrganizationInfo {
+            products: products
+                .iter()
+                .filter(|product| product.organization_id == Some(organization.id))
+                .map(|product| (product.id, product.name.clone()))
+                .collect(),
+            id: organization.id,
+            name: organization.name,
+        })
+        .collect())
+}
+
+#[server]
+pub async fn organization_add(name: String) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    OrganizationRepo::create(&db, &name)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "organization",
+        "organization {} created by {}",
+        name.trim(),
+        user.username
+    );
+    Ok(())
+}
+
+#[server]
+pub async fn organization_remove(id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    OrganizationRepo::delete(&db, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "organization",
+        "organization {} deleted by {}",
+        id,
+        user.username
+    );
+    Ok(())
+}
+
+/// Moves a product to an organization, or out of any organization for `None`.
+#[server]
+pub async fn organization_set_product(
+    product_id: Uuid,
+    organization_id: Option<Uuid>,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    OrganizationRepo::set_product(&db, product_id, organization_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "organization",
+        "product {} moved to organization {:?} by {}",
+        product_id,
+        organization_id,
+        user.username
+    );
+    Ok(())
+}
+
+#[server]
+pub async fn organization_members(id: Uuid) -> Result<Vec<OrganizationMemberInfo>, ServerFnError> {
+    let (db, _) = check_admin().await?;
+    let members = OrganizationRepo::members(&db, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(members
+        .into_iter()
+        .filter_map(|(member, user)| {
+            user.map(|user| OrganizationMemberInfo {
+                user_id: user.id,
+                username: user.username,
+                role: member.role,
+            })
+        })
+        .collect())
+}
+
+/// Makes the user a member of the organization, replacing the role the user had in it.
+#[server]
+pub async fn organization_add_member(
+    id: Uuid,
+    user_id: Uuid,
+    role: String,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    OrganizationRepo::add_member(&db, id, user_id, &role)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "organization",
+        "user {} given role {} in organization {} by {}",
+        user_id,
+        role,
+        id,
+        user.username
+    );
+    Ok(())
+}
+
+#[server]
+pub async fn organization_remove_member(id: Uuid, user_id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    OrganizationRepo::remove_member(&db, id, user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "organization",
+        "user {} removed from organization {} by {}",
+        user_id,
+        id,
+        user.username
+    );
+    Ok(())
+}
@@ -14,8 +14,11 @@ cfg_if! { if #[cfg(feature="ssr")] {
     use crate::entity;
     use crate::auth::AuthenticatedUser;
     use crate::data::{
-        add, count, delete_by_id, get_all, get_all_names, get_by_id, update, EntityInfo,
+        add, check_access_by_id, count, get_all, get_all_names, get_by_id, update, EntityInfo,
     };
+    use crate::model::product_deletion::ProductDeletionRepo;
+    use crate::model::role;
+    use crate::read_only::check_writable;
 }}
 
 use super::ExtraRowTrait;
@@ -40,6 +43,26 @@ pub struct Product {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub max_build_age_days: Option<i32>,
+    pub build_age_overrides: Option<String>,
+    pub build_age_allow_list: Option<String>,
+    pub promoted_annotations: Option<String>,
+    pub redacted_fields: Option<String>,
+    pub allowed_content_types: Option<String>,
+    pub environments: Option<String>,
+    pub accepting_crashes: bool,
+    pub public_submissions: bool,
+    pub repository_url: Option<String>,
+    pub source_roots: Option<String>,
+    pub attachment_processors: Option<String>,
+    pub default_annotations: Option<String>,
+    pub auto_create_versions: bool,
+    pub ingestion_windows: Option<String>,
+    pub organization_id: Option<Uuid>,
+    pub max_minidump_size: Option<i64>,
+    pub max_attachment_size: Option<i64>,
+    pub max_attachments: Option<i32>,
+    pub max_daily_uploads: Option<i32>,
 }
 
 #[cfg(feature = "ssr")]
@@ -49,6 +72,26 @@ pub struct Product {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub max_build_age_days: Option<i32>,
+    pub build_age_overrides: Option<String>,
+    pub build_age_allow_list: Option<String>,
+    pub promoted_annotations: Option<String>,
+    pub redacted_fields: Option<String>,
+    pub allowed_content_types: Option<String>,
+    pub environments: Option<String>,
+    pub accepting_crashes: bool,
+    pub public_submissions: bool,
+    pub repository_url: Option<String>,
+    pub source_roots: Option<String>,
+    pub attachment_processors: Option<String>,
+    pub default_annotations: Option<String>,
+    pub auto_create_versions: bool,
+    pub ingestion_windows: Option<String>,
+    pub organization_id: Option<Uuid>,
+    pub max_minidump_size: Option<i64>,
+    pub max_attachment_size: Option<i64>,
+    pub max_attachments: Option<i32>,
+    pub max_daily_uploads: Option<i32>,
 }
 
 #[cfg(feature = "ssr")]
@@ -69,6 +112,13 @@ impl EntityInfo for entity::product::Entity {
         }
     }
 
+    fn extend_query_for_view(query: Select<Self>) -> Select<Self> {
+        query.filter(
+            entity::product::Column::Id
+                .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+        )
+    }
+
     fn get_product_query(
         _user: &AuthenticatedUser,
         data: &Self::View,
@@ -96,6 +146,26 @@ impl From<entity::product::Model> for Product {
             name: model.name,
             created_at: model.created_at,
             updated_at: model.updated_at,
+            max_build_age_days: model.max_build_age_days,
+            build_age_overrides: model.build_age_overrides,
+            build_age_allow_list: model.build_age_allow_list,
+            promoted_annotations: model.promoted_annotations,
+            redacted_fields: model.redacted_fields,
+            allowed_content_types: model.allowed_content_types,
+            environments: model.environments,
+            accepting_crashes: model.accepting_crashes,
+            public_submissions: model.public_submissions,
+            repository_url: model.repository_url,
+            source_roots: model.source_roots,
+            attachment_processors: model.attachment_processors,
+            default_annotations: model.default_annotations,
+            auto_create_versions: model.auto_create_versions,
+            ingestion_windows: model.ingestion_windows,
+            organization_id: model.organization_id,
+            max_minidump_size: model.max_minidump_size,
+            max_attachment_size: model.max_attachment_size,
+            max_attachments: model.max_attachments,
+            max_daily_uploads: model.max_daily_uploads,
         }
     }
 }
@@ -106,6 +176,28 @@ impl From<Product> for entity::product::ActiveModel {
         Self {
             id: Set(product.id),
             name: Set(product.name),
+            max_build_age_days: Set(product.max_build_age_days),
+            build_age_overrides: Set(product.build_age_overrides),
+            build_age_allow_list: Set(product.build_age_allow_list),
+            promoted_annotations: Set(product.promoted_annotations),
+            redacted_fields: Set(product.redacted_fields),
+            allowed_content_types: Set(product.allowed_content_types),
+            environments: Set(product.environments),
+            accepting_crashes: Set(product.accepting_crashes),
+            public_submissions: Set(product.public_submissions),
+            repository_url: Set(product.repository_url),
+            source_roots: Set(product.source_roots),
+            attachment_processors: Set(product.attachment_processors),
+            default_annotations: Set(product.default_annotations),
+            auto_create_versions: Set(product.auto_create_versions),
+            ingestion_windows: Set(product.ingestion_windows),
+            // Only administrators move products between organizations, see
+            // `organization_set_product`.
+            organization_id: sea_orm::NotSet,
+            max_minidump_size: Set(product.max_minidump_size),
+            max_attachment_size: Set(product.max_attachment_size),
+            max_attachments: Set(product.max_attachments),
+            max_daily_uploads: Set(product.max_daily_uploads),
             created_at: sea_orm::NotSet,
             updated_at: sea_orm::NotSet,
         }
@@ -144,12 +236,22 @@ pub async fn product_add(product: Product) -> Result<(), ServerFnError> {
 
 #[server]
 pub async fn product_update(product: Product) -> Result<(), ServerFnError> {
+    check_access_by_id::<entity::product::Entity>(product.id, role::at_least(role::ADMIN)).await?;
     update::<entity::product::Entity>(product).await
 }
 
 #[server]
 pub async fn product_remove(id: Uuid) -> Result<(), ServerFnError> {
-    delete_by_id::<entity::product::Entity>(id).await
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    check_access_by_id::<entity::product::Entity>(id, role::at_least(role::ADMIN)).await?;
+
+    ProductDeletionRepo::schedule(&db, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(())
 }
 
 #[server]
@@ -0,0 +1,118 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use uuid::Uuid;
+    use crate::entity;
+    use crate::authenticated_user;
+    use crate::model::api_token::ApiTokenRepo;
+    use crate::model::search::SearchRepo;
+    use crate::report::CrashInfo;
+}}
+
+pub const SEARCH_CRASH: &str = "crash";
+pub const SEARCH_ISSUE: &str = "issue";
+pub const SEARCH_SYMBOLS: &str = "symbols";
+
+/// Minimum length of a search, shorter ones match too much to be useful.
+pub const MIN_SEARCH_LENGTH: usize = 3;
+
+/// A crash, issue or symbol file found by `global_search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// One of `SEARCH_CRASH`, `SEARCH_ISSUE` and `SEARCH_SYMBOLS`.
+    pub kind: String,
+    pub title: String,
+    pub detail: String,
+    pub product: String,
+    /// Page of the result, relative to the path prefix.
+    pub url: String,
+}
+
+/// Returns the products the user can see, or `None` for admins, who can see all products.
+#[cfg(feature = "ssr")]
+async fn visible_products(db: &DatabaseConnection) -> Result<Option<Vec<Uuid>>, ServerFnError> {
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    ApiTokenRepo::readable_products(db, user.id, user.is_admin)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the products the user can see like `visible_products`, limited to `product` if given.
+#[cfg(feature = "ssr")]
+pub(crate) async fn visible_products_of(
+    db: &DatabaseConnection,
+    product: Option<Uuid>,
+) -> Result<Option<Vec<Uuid>>, ServerFnError> {
+    Ok(match (visible_products(db).await?, product) {
+        (None, product) => product.map(|product| vec![product]),
+        (Some(products), None) => Some(products),
+        (Some(products), Some(product)) => {
+            Some(products.into_iter().filter(|id| *id == product).collect())
+        }
+    })
+}
+
+/// Searches crashes by id, issues by signature and symbols by build id or module over the
+/// products the user can see. Crashes come first, then issues and symbols.
+#[server]
+pub async fn global_search(query: String) -> Result<Vec<SearchResult>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let query = query.trim();
+    if query.chars().count() < MIN_SEARCH_LENGTH {
+        return Ok(vec![]);
+    }
+    let products = visible_products(&db).await?;
+    let products = products.as_deref();
+    let product_name =
+        |product: Option<entity::product::Model>| product.map(|p| p.name).unwrap_or_default();
+    let db_error = |e: DbErr| ServerFnError::new(format!("{e:?}"));
+
+    let mut results = vec![];
+    if let Ok(id) = Uuid::parse_str(query) {
+        if let Some((crash, product)) = SearchRepo::crash_by_id(&db, id, products)
+            .await
+            .map_err(db_error)?
+        {
+            let info = CrashInfo::from_report(&crash.report).ok();
+            results.push(SearchResult {
+                kind: SEARCH_CRASH.to_owned(),
+                title: info
+                    .as_ref()
+                    .and_then(|info| info.title())
+                    .unwrap_or_else(|| crash.id.to_string()),
+                detail: info.and_then(|info| info.signature()).unwrap_or_default(),
+                product: product_name(product),
+                url: format!("/crash?crash={}", crash.id),
+            });
+        }
+    }
+
+    let issues = SearchRepo::issues_by_signature(&db, query, products)
+        .await
+        .map_err(db_error)?;
+    results.extend(issues.into_iter().map(|(issue, product)| SearchResult {
+        kind: SEARCH_ISSUE.to_owned(),
+        title: issue.title,
+        detail: issue.signature,
+        product: product_name(product),
+        url: format!("/issue?issue={}", issue.id),
+    }));
+
+    let symbols = SearchRepo::symbols_by_id(&db, query, products)
+        .await
+        .map_err(db_error)?;
+    results.extend(symbols.into_iter().map(|(symbols, product)| SearchResult {
+        kind: SEARCH_SYMBOLS.to_owned(),
+        title: symbols.module_id,
+        detail: format!("{} {} {}", symbols.build_id, symbols.os, symbols.arch),
+        product: product_name(product),
+        url: format!("/admin/symbols?version={}", symbols.version_id),
+    }));
+    Ok(results)
+}
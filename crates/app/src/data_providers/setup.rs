@@ -0,0 +1,31 @@
+use cfg_if::cfg_if;
+use leptos::*;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use crate::data_providers::user::check_admin;
+    use crate::model::user::UserRepo;
+    use crate::settings::settings;
+}}
+
+/// Returns whether the installation still has to be set up, i.e. no user has registered yet.
+#[server]
+pub async fn setup_required() -> Result<bool, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    UserRepo::is_empty(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the address that clients send signed requests to, e.g. `https://example.com/ingest`.
+#[server]
+pub async fn setup_ingest_url() -> Result<String, ServerFnError> {
+    check_admin().await?;
+    let server = &settings().server;
+    Ok(format!(
+        "{}{}/ingest",
+        server.site.trim_end_matches('/'),
+        server.path_prefix
+    ))
+}
@@ -0,0 +1,98 @@
+use cfg_if::cfg_if;
+use chrono::{NaiveDate, NaiveDateTime};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::DatabaseConnection;
+    use crate::data_providers::search::visible_products_of;
+    use crate::model::stats::StatsRepo;
+}}
+
+pub const GROUP_DAY: &str = "day";
+pub const GROUP_VERSION: &str = "version";
+pub const GROUP_CHANNEL: &str = "channel";
+
+/// Number of days covered by the statistics when not given.
+pub const DEFAULT_DAYS: i64 = 30;
+/// Maximum number of days covered by the statistics.
+pub const MAX_DAYS: i64 = 365;
+/// Number of signatures in `CrashStats::top_signatures`.
+pub const TOP_SIGNATURES: u64 = 10;
+
+/// What crash counts are grouped by. The channel is the release channel of the version, e.g.
+/// `beta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsGroup {
+    Day,
+    Version,
+    Channel,
+}
+
+/// Parses a comma separated list of groups, e.g. `day,version`. Returns the unknown group on
+/// failure.
+pub fn parse_group_by(value: &str) -> Result<Vec<StatsGroup>, String> {
+    let mut groups = vec![];
+    for group in value.split(',').map(str::trim).filter(|g| !g.is_empty()) {
+        let group = match group {
+            GROUP_DAY => StatsGroup::Day,
+            GROUP_VERSION => StatsGroup::Version,
+            GROUP_CHANNEL => StatsGroup::Channel,
+            _ => return Err(group.to_owned()),
+        };
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    Ok(groups)
+}
+
+/// Number of crashes of a group. Fields the crashes are not grouped by are `None`, as are
+/// versions without channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashCount {
+    pub day: Option<NaiveDate>,
+    pub version: Option<String>,
+    pub channel: Option<String>,
+    pub count: i64,
+}
+
+/// Number of crashes of the issue with `signature`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureCount {
+    pub issue_id: Uuid,
+    pub signature: String,
+    pub title: String,
+    pub count: i64,
+}
+
+/// Crashes reported since `since`, with the signatures of the most crashes first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashStats {
+    pub since: NaiveDateTime,
+    pub total: u64,
+    pub counts: Vec<CrashCount>,
+    pub top_signatures: Vec<SignatureCount>,
+}
+
+/// Returns the crash statistics of the last `days` days over the products the user can see, or
+/// only those of `product`. `group_by` is a comma separated list of `day`, `version` and
+/// `channel`.
+#[server]
+pub async fn crash_stats(
+    product: Option<Uuid>,
+    group_by: String,
+    days: i64,
+) -> Result<CrashStats, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let groups = parse_group_by(&group_by)
+        .map_err(|group| ServerFnError::new(format!("cannot group by '{}'", group)))?;
+    let products = visible_products_of(&db, product).await?;
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(days.clamp(1, MAX_DAYS));
+    StatsRepo::crash_stats(&db, products, since, &groups, TOP_SIGNATURES)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
@@ -16,11 +16,54 @@ cfg_if! { if #[cfg(feature="ssr")] {
         add, count, delete_by_id, get_all, get_all_names, get_by_id, update, EntityInfo,
     };
     use crate::auth::AuthenticatedUser;
+    use crate::authenticated_user;
+    use crate::build_id::{debug_id_from_elf_build_id, normalize_debug_id};
+    use crate::data_providers::search::visible_products_of;
+    use crate::model::missing_symbols::MissingSymbolsRepo;
+    use crate::model::symbols::{symbols_dir, symbols_file, SymbolsRepo, ORPHANED_BATCH};
+    use crate::read_only::check_writable;
 }}
 
 use super::ExtraRowTrait;
 use crate::classes::ClassesPreset;
 use crate::data::QueryParams;
+use crate::prefix::prefixed;
+
+#[allow(non_snake_case)]
+#[component]
+fn DownloadCellRenderer<F>(
+    class: String,
+    #[prop(into)] value: MaybeSignal<String>,
+    on_change: F,
+    index: usize,
+) -> impl IntoView
+where
+    F: Fn(String) + 'static,
+{
+    let _ = (on_change, index);
+    view! {
+        <td class=class>
+            <a class="link link-primary" href=value download>
+                "Download"
+            </a>
+        </td>
+    }
+}
+
+pub fn format_size(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
 
 #[derive(TableRow, Debug, Clone)]
 #[table(sortable, classes_provider = ClassesPreset)]
@@ -33,10 +76,13 @@ pub struct SymbolsRow {
     pub build_id: String,
     pub module_id: String,
     pub file_location: String,
+    pub size: String,
     #[table(format(string = "%d/%m/%Y - %H:%M"))]
     pub created_at: NaiveDateTime,
     #[table(format(string = "%d/%m/%Y - %H:%M"))]
     pub updated_at: NaiveDateTime,
+    #[table(renderer = "DownloadCellRenderer", skip_sort)]
+    pub download: String,
     #[table(skip)]
     pub product_id: Option<Uuid>,
     #[table(skip)]
@@ -54,8 +100,10 @@ pub struct Symbols {
     pub build_id: String,
     pub module_id: String,
     pub file_location: String,
+    pub file_size: Option<i64>,
     pub product_id: Uuid,
     pub version_id: Uuid,
+    pub quarantine_reason: Option<String>,
     pub product: String,
     pub version: String,
 }
@@ -71,8 +119,10 @@ pub struct Symbols {
     pub build_id: String,
     pub module_id: String,
     pub file_location: String,
+    pub file_size: Option<i64>,
     pub product_id: Uuid,
     pub version_id: Uuid,
+    pub quarantine_reason: Option<String>,
     pub product: String,
     pub version: String,
 }
@@ -88,17 +138,36 @@ impl EntityInfo for entity::symbols::Entity {
     fn index_to_column(index: usize) -> Option<Self::Column> {
         match index {
             0 => Some(entity::symbols::Column::Id),
-            1 => Some(entity::symbols::Column::Os),
-            2 => Some(entity::symbols::Column::Arch),
-            3 => Some(entity::symbols::Column::BuildId),
-            4 => Some(entity::symbols::Column::ModuleId),
-            5 => Some(entity::symbols::Column::FileLocation),
-            6 => Some(entity::symbols::Column::CreatedAt),
-            7 => Some(entity::symbols::Column::UpdatedAt),
+            3 => Some(entity::symbols::Column::Os),
+            4 => Some(entity::symbols::Column::Arch),
+            5 => Some(entity::symbols::Column::BuildId),
+            6 => Some(entity::symbols::Column::ModuleId),
+            7 => Some(entity::symbols::Column::FileLocation),
+            8 => Some(entity::symbols::Column::FileSize),
+            9 => Some(entity::symbols::Column::CreatedAt),
+            10 => Some(entity::symbols::Column::UpdatedAt),
             _ => None,
         }
     }
 
+    fn extend_query_for_filter(
+        query: Select<Self>,
+        filter: String,
+        _backend: DbBackend,
+    ) -> Select<Self> {
+        let mut condition = Condition::any()
+            .add(entity::symbols::Column::BuildId.contains(filter.clone()))
+            .add(entity::symbols::Column::ModuleId.contains(filter.clone()));
+        if let Some(build_id) = normalize_debug_id(&filter) {
+            condition = condition.add(entity::symbols::Column::BuildId.eq(build_id));
+        }
+        // ELF modules are also known by their build ID, e.g. as shown by `file`.
+        if let Some(build_id) = debug_id_from_elf_build_id(&filter) {
+            condition = condition.add(entity::symbols::Column::BuildId.eq(build_id));
+        }
+        query.filter(condition)
+    }
+
     fn extend_query_for_view(query: Select<Self>) -> Select<Self> {
         query
             .join(JoinType::LeftJoin, entity::symbols::Relation::Product.def())
@@ -134,8 +203,10 @@ impl From<Symbols> for SymbolsRow {
             build_id: symbols.build_id,
             module_id: symbols.module_id,
             file_location: symbols.file_location,
+            size: symbols.file_size.map(format_size).unwrap_or_default(),
             created_at: symbols.created_at,
             updated_at: symbols.updated_at,
+            download: prefixed(&format!("/download/symbols/{}", symbols.id)),
             product_id: Some(symbols.product_id),
             version_id: Some(symbols.version_id),
             product: symbols.product,
@@ -154,10 +225,12 @@ impl From<entity::symbols::Model> for Symbols {
             build_id: model.build_id,
             module_id: model.module_id,
             file_location: model.file_location,
+            file_size: model.file_size,
             created_at: model.created_at,
             updated_at: model.updated_at,
             product_id: model.product_id,
             version_id: model.version_id,
+            quarantine_reason: model.quarantine_reason,
             product: "".to_string(),
             version: "".to_string(),
         }
@@ -174,10 +247,12 @@ impl From<Symbols> for entity::symbols::ActiveModel {
             build_id: Set(symbols.build_id),
             module_id: Set(symbols.module_id),
             file_location: Set(symbols.file_location),
+            file_size: Set(symbols.file_size),
             created_at: sea_orm::NotSet,
             updated_at: sea_orm::NotSet,
             product_id: Set(symbols.product_id),
             version_id: Set(symbols.version_id),
+            quarantine_reason: Set(symbols.quarantine_reason),
         }
     }
 }
@@ -233,3 +308,158 @@ pub async fn symbols_count(
 ) -> Result<usize, ServerFnError> {
     count::<entity::symbols::Entity>(parents).await
 }
+
+#[server]
+pub async fn symbols_list_orphaned(
+    #[server(default)] parents: HashMap<String, Uuid>,
+    days: i64,
+) -> Result<Vec<Symbols>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if !user.is_admin {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+
+    let product_id = parents.get("product_id").cloned();
+    let ids: Vec<Uuid> = SymbolsRepo::get_orphaned(&db, product_id, days)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .into_iter()
+        .map(|symbols| symbols.id)
+        .collect();
+
+    // The orphans are ordered by creation, so the rows of consecutive batches are as well.
+    let mut rows = vec![];
+    for ids in ids.chunks(ORPHANED_BATCH as usize) {
+        let mut query = entity::symbols::Entity::find();
+        query = entity::symbols::Entity::extend_query_for_view(query);
+        rows.extend(
+            query
+                .filter(entity::symbols::Column::Id.is_in(ids.iter().copied()))
+                .order_by_asc(entity::symbols::Column::CreatedAt)
+                .order_by_asc(entity::symbols::Column::Id)
+                .into_model::<Symbols>()
+                .all(&db)
+                .await
+                .map_err(|e| ServerFnError::new(format!("{e:?}")))?,
+        );
+    }
+    Ok(rows)
+}
+
+/// Returns the symbols that were quarantined at upload, for review by an admin.
+#[server]
+pub async fn symbols_list_quarantined(
+    #[server(default)] parents: HashMap<String, Uuid>,
+) -> Result<Vec<Symbols>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if !user.is_admin {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+
+    let product_id = parents.get("product_id").cloned();
+    let ids: Vec<Uuid> = SymbolsRepo::get_quarantined(&db, product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .into_iter()
+        .map(|symbols| symbols.id)
+        .collect();
+
+    let mut query = entity::symbols::Entity::find();
+    query = entity::symbols::Entity::extend_query_for_view(query);
+    query
+        .filter(entity::symbols::Column::Id.is_in(ids))
+        .order_by_asc(entity::symbols::Column::CreatedAt)
+        .into_model::<Symbols>()
+        .all(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Releases quarantined symbols after review, moving the file to where minidump processing
+/// picks it up.
+#[server]
+pub async fn symbols_release(id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if !user.is_admin {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+
+    let symbols = entity::prelude::Symbols::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .ok_or(ServerFnError::new("not found".to_string()))?;
+    if symbols.quarantine_reason.is_none() {
+        return Ok(());
+    }
+
+    let target = symbols_file(&symbols_dir(), &symbols.module_id, &symbols.build_id);
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    }
+    tokio::fs::rename(&symbols.file_location, &target)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    SymbolsRepo::release(&db, id, target.to_string_lossy().into_owned())
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    MissingSymbolsRepo::resolve(&db, &symbols.module_id, &symbols.build_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(())
+}
+
+/// Maximum number of missing symbols shown.
+pub const MAX_MISSING_SYMBOLS: u64 = 1000;
+
+/// A module for which crashes of a product had no symbols.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissingSymbols {
+    pub product: String,
+    pub module_id: String,
+    pub build_id: String,
+    /// Number of crashes without symbols for the module.
+    pub count: i32,
+    pub last_seen: NaiveDateTime,
+}
+
+/// Returns the modules without symbols of the products the user can see, or only those of
+/// `product`. The modules missing in the most crashes come first.
+#[server]
+pub async fn symbols_missing(product: Option<Uuid>) -> Result<Vec<MissingSymbols>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let products = visible_products_of(&db, product).await?;
+    let missing = MissingSymbolsRepo::list(&db, products, MAX_MISSING_SYMBOLS)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(missing
+        .into_iter()
+        .map(|(missing, product)| MissingSymbols {
+            product,
+            module_id: missing.module_id,
+            build_id: missing.build_id,
+            count: missing.count,
+            last_seen: missing.last_seen,
+        })
+        .collect())
+}
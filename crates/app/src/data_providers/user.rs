@@ -12,15 +12,38 @@ cfg_if! { if #[cfg(feature="ssr")] {
     use std::collections::HashMap;
     use crate::entity;
     use crate::auth::AuthenticatedUser;
+    use crate::authenticated_user;
+    use crate::model::role::RoleRepo;
+    use crate::model::user::UserRepo;
     use crate::data::{
-        add, count, delete_by_id, get_all, get_all_names, get_by_id, update, EntityInfo,
+        add, count, get_all, get_all_names, get_by_id, update, EntityInfo,
     };
+    use crate::read_only::check_writable;
 }}
 
 use super::ExtraRowTrait;
 use crate::classes::ClassesPreset;
 use crate::data::QueryParams;
 
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLE_MAINTAINER: &str = "maintainer";
+pub const ROLE_VIEWER: &str = "viewer";
+
+/// Roles of users for a product, from most to least privileged, with their label.
+pub const ROLES: [(&str, &str); 3] = [
+    (ROLE_ADMIN, "Admin"),
+    (ROLE_MAINTAINER, "Maintainer"),
+    (ROLE_VIEWER, "Viewer"),
+];
+
+/// A role of a user for a product.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRole {
+    pub product_id: Uuid,
+    pub product: String,
+    pub role: String,
+}
+
 #[derive(TableRow, Debug, Clone)]
 #[table(sortable, classes_provider = ClassesPreset)]
 pub struct UserRow {
@@ -31,6 +54,7 @@ pub struct UserRow {
     pub created_at: NaiveDateTime,
     #[table(format(string = "%d/%m/%Y - %H:%M"))]
     pub updated_at: NaiveDateTime,
+    pub active: bool,
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -42,6 +66,7 @@ pub struct User {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub last_login_at: Option<NaiveDateTime>,
+    pub deactivated_at: Option<NaiveDateTime>,
     // pub roles: Vec<String>,
 }
 
@@ -54,6 +79,7 @@ pub struct User {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub last_login_at: Option<NaiveDateTime>,
+    pub deactivated_at: Option<NaiveDateTime>,
     //pub roles: Vec<String>,
 }
 
@@ -72,6 +98,7 @@ impl EntityInfo for entity::user::Entity {
             2 => Some(entity::user::Column::IsAdmin),
             3 => Some(entity::user::Column::CreatedAt),
             4 => Some(entity::user::Column::UpdatedAt),
+            5 => Some(entity::user::Column::DeactivatedAt),
             _ => None,
         }
     }
@@ -98,6 +125,7 @@ impl From<User> for UserRow {
             username: user.username,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            active: user.deactivated_at.is_none(),
         }
     }
 }
@@ -111,6 +139,7 @@ impl From<entity::user::Model> for User {
             created_at: model.created_at,
             updated_at: model.updated_at,
             last_login_at: model.last_authenticated,
+            deactivated_at: model.deactivated_at,
             // roles: vec![],
         }
     }
@@ -126,6 +155,7 @@ impl From<User> for entity::user::ActiveModel {
             created_at: sea_orm::NotSet,
             updated_at: sea_orm::NotSet,
             last_authenticated: sea_orm::NotSet,
+            deactivated_at: sea_orm::NotSet,
         }
     }
 }
@@ -201,7 +231,109 @@ pub async fn user_update(user: User) -> Result<(), ServerFnError> {
 
 #[server]
 pub async fn user_remove(id: Uuid) -> Result<(), ServerFnError> {
-    delete_by_id::<entity::user::Entity>(id).await
+    user_delete(id, None).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn check_admin() -> Result<(DatabaseConnection, AuthenticatedUser), ServerFnError>
+{
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let user = authenticated_user()
+        .await?
+        .ok_or(ServerFnError::new("No authenticated user".to_string()))?;
+    if !user.is_admin {
+        return Err(ServerFnError::new("no access".to_string()));
+    }
+    Ok((db, user))
+}
+
+#[cfg(feature = "ssr")]
+async fn check_admin_for(id: Uuid) -> Result<DatabaseConnection, ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    if user.id == id {
+        return Err(ServerFnError::new(
+            "cannot deactivate or delete your own account".to_string(),
+        ));
+    }
+    Ok(db)
+}
+
+#[server]
+pub async fn user_set_active(id: Uuid, active: bool) -> Result<(), ServerFnError> {
+    let db = check_admin_for(id).await?;
+    UserRepo::set_active(&db, id, active)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+#[server]
+pub async fn user_delete(id: Uuid, reassign_to: Option<Uuid>) -> Result<(), ServerFnError> {
+    let db = check_admin_for(id).await?;
+    UserRepo::delete(&db, id, reassign_to)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Returns the roles of the user for each product. Only for administrators.
+#[server]
+pub async fn user_roles(id: Uuid) -> Result<Vec<UserRole>, ServerFnError> {
+    let (db, _) = check_admin().await?;
+    let roles = RoleRepo::get_by_user(&db, id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(roles
+        .into_iter()
+        .filter_map(|(role, product)| {
+            product.map(|product| UserRole {
+                product_id: product.id,
+                product: product.name,
+                role: role.name,
+            })
+        })
+        .collect())
+}
+
+/// Gives the user a role for the product, replacing the role the user had for it.
+#[server]
+pub async fn user_assign_role(
+    id: Uuid,
+    product_id: Uuid,
+    role: String,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    RoleRepo::assign(&db, id, product_id, &role)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "role",
+        "user {} given role {} for product {} by {}",
+        id,
+        role,
+        product_id,
+        user.username
+    );
+    Ok(())
+}
+
+#[server]
+pub async fn user_revoke_role(id: Uuid, product_id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let (db, user) = check_admin().await?;
+    RoleRepo::revoke(&db, id, product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    tracing::info!(
+        audit = "role",
+        "role of user {} for product {} revoked by {}",
+        id,
+        product_id,
+        user.username
+    );
+    Ok(())
 }
 
 #[server]
@@ -11,9 +11,13 @@ cfg_if! { if #[cfg(feature="ssr")] {
     use sea_query::Expr;
     use crate::entity;
     use crate::data::{
-        add, count, delete_by_id, get_all, get_all_names, get_by_id, update, EntityInfo,
+        add, check_access_by_data, count, delete_by_id, get_all, get_all_names, get_by_id,
+        update, EntityInfo,
     };
     use crate::auth::AuthenticatedUser;
+    use crate::model::role;
+    use crate::model::version::{VersionRepo, VersionUpsert};
+    use crate::read_only::check_writable;
 }}
 
 use super::ExtraRowTrait;
@@ -32,6 +36,8 @@ pub struct VersionRow {
     pub created_at: NaiveDateTime,
     #[table(format(string = "%d/%m/%Y - %H:%M"))]
     pub updated_at: NaiveDateTime,
+    pub channel: String,
+    pub release_date: String,
     #[table(skip)]
     pub product_id: Option<Uuid>,
 }
@@ -45,6 +51,8 @@ pub struct Version {
     pub hash: String,
     pub tag: String,
     pub product_id: Uuid,
+    pub channel: Option<String>,
+    pub release_date: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -58,6 +66,8 @@ pub struct Version {
     pub hash: String,
     pub tag: String,
     pub product_id: Uuid,
+    pub channel: Option<String>,
+    pub release_date: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -79,6 +89,8 @@ impl EntityInfo for entity::version::Entity {
             4 => Some(entity::version::Column::ProductId),
             5 => Some(entity::version::Column::CreatedAt),
             6 => Some(entity::version::Column::UpdatedAt),
+            7 => Some(entity::version::Column::Channel),
+            8 => Some(entity::version::Column::ReleaseDate),
             _ => None,
         }
     }
@@ -117,6 +129,11 @@ impl From<Version> for VersionRow {
             product_id: Some(version.product_id),
             created_at: version.created_at,
             updated_at: version.updated_at,
+            channel: version.channel.unwrap_or_default(),
+            release_date: version
+                .release_date
+                .map(|date| date.format("%d/%m/%Y").to_string())
+                .unwrap_or_default(),
             product: version.product,
         }
     }
@@ -131,6 +148,8 @@ impl From<entity::version::Model> for Version {
             hash: model.hash,
             tag: model.tag,
             product_id: model.product_id,
+            channel: model.channel,
+            release_date: model.release_date,
             created_at: model.created_at,
             updated_at: model.updated_at,
             product: "".to_string(),
@@ -147,12 +166,65 @@ impl From<Version> for entity::version::ActiveModel {
             hash: Set(version.hash),
             tag: Set(version.tag),
             product_id: Set(version.product_id),
+            channel: Set(version.channel),
+            release_date: Set(version.release_date),
             created_at: sea_orm::NotSet,
             updated_at: sea_orm::NotSet,
         }
     }
 }
 
+/// Parses a release date given as an RFC 3339 timestamp, a date and time without time zone,
+/// or a plain date.
+pub fn parse_release_date(value: &str) -> Option<chrono::NaiveDateTime> {
+    let value = value.trim();
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|date| date.naive_utc())
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Parses versions for a bulk import, one per line as `name,tag,hash,channel,release_date`.
+/// Only the name is required; empty lines and lines starting with `#` are skipped.
+pub fn parse_version_lines(text: &str, product_id: Uuid) -> Result<Vec<Version>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| {
+            let mut fields = line.split(',').map(str::trim);
+            let mut next = || fields.next().unwrap_or_default().to_owned();
+            let (name, tag, hash, channel, release_date) = (next(), next(), next(), next(), next());
+            if name.is_empty() {
+                return Err(format!("line {}: missing version name", n));
+            }
+            let release_date = match release_date.as_str() {
+                "" => None,
+                date => Some(
+                    parse_release_date(date)
+                        .ok_or_else(|| format!("line {}: invalid release date '{}'", n, date))?,
+                ),
+            };
+            Ok(Version {
+                id: Uuid::new_v4(),
+                name,
+                tag,
+                hash,
+                channel: (!channel.is_empty()).then_some(channel),
+                release_date,
+                product_id,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
 impl ExtraRowTrait for VersionRow {
     fn get_id(&self) -> Uuid {
         self.id
@@ -195,6 +267,7 @@ pub async fn version_update(version: Version) -> Result<(), ServerFnError> {
 
 #[server]
 pub async fn version_remove(id: Uuid) -> Result<(), ServerFnError> {
+    super::crash::check_not_held(entity::crash::Column::VersionId, id).await?;
     delete_by_id::<entity::version::Entity>(id).await
 }
 
@@ -204,3 +277,41 @@ pub async fn version_count(
 ) -> Result<usize, ServerFnError> {
     count::<entity::version::Entity>(parents).await
 }
+
+#[server]
+pub async fn version_import(versions: Vec<Version>) -> Result<usize, ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+
+    let Some(first) = versions.first() else {
+        return Ok(0);
+    };
+    let product_id = first.product_id;
+    if versions
+        .iter()
+        .any(|version| version.product_id != product_id)
+    {
+        return Err(ServerFnError::new(
+            "versions of multiple products".to_string(),
+        ));
+    }
+    check_access_by_data::<entity::version::Entity>(first, role::at_least(role::MAINTAINER))
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+
+    let dtos = versions
+        .into_iter()
+        .map(|version| VersionUpsert {
+            name: version.name,
+            hash: Some(version.hash),
+            tag: Some(version.tag),
+            channel: version.channel,
+            release_date: version.release_date,
+        })
+        .collect();
+    let result = VersionRepo::upsert_many(&db, product_id, dtos)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(result.len())
+}
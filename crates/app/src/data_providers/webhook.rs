@@ -0,0 +1,194 @@
+use ::chrono::NaiveDateTime;
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+cfg_if! { if #[cfg(feature="ssr")] {
+    use sea_orm::*;
+    use crate::entity;
+    use crate::data::check_access_by_id;
+    use crate::model::base::Repo;
+    use crate::model::role;
+    use crate::model::webhook::{subscribed_events, WebhookCreateDto, WebhookRepo};
+    use crate::read_only::check_writable;
+}}
+
+pub const CRASH_PROCESSED: &str = "crash_processed";
+pub const NEW_ISSUE: &str = "new_issue";
+pub const REGRESSION: &str = "regression";
+pub const ALERT: &str = "alert";
+pub const UPLOAD_QUARANTINED: &str = "upload_quarantined";
+pub const TEST: &str = "test";
+
+/// Events webhooks can subscribe to, with their label.
+pub const WEBHOOK_EVENTS: [(&str, &str); 5] = [
+    (CRASH_PROCESSED, "Crash processed"),
+    (NEW_ISSUE, "New issue"),
+    (REGRESSION, "Regression"),
+    (ALERT, "Alert"),
+    (UPLOAD_QUARANTINED, "Upload source quarantined"),
+];
+
+/// A webhook endpoint of a product. The secret is never sent back to the browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub has_secret: bool,
+    pub events: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub event: String,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: NaiveDateTime,
+}
+
+impl WebhookDelivery {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none() && self.status_code.is_some()
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::model::webhook::Webhook> for Webhook {
+    fn from(model: crate::model::webhook::Webhook) -> Self {
+        Self {
+            id: model.id,
+            url: model.url,
+            has_secret: model.secret.is_some_and(|secret| !secret.is_empty()),
+            events: subscribed_events(&model.events),
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::model::webhook::WebhookDelivery> for WebhookDelivery {
+    fn from(model: crate::model::webhook::WebhookDelivery) -> Self {
+        Self {
+            event: model.event,
+            status_code: model.status_code,
+            error: model.error,
+            duration_ms: model.duration_ms,
+            created_at: model.created_at,
+        }
+    }
+}
+
+/// Returns the webhook after checking that the user may manage the webhooks of its product.
+#[cfg(feature = "ssr")]
+async fn get_webhook(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<crate::model::webhook::Webhook, ServerFnError> {
+    let webhook = entity::prelude::Webhook::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?
+        .ok_or(ServerFnError::new("not found".to_string()))?;
+    check_access_by_id::<entity::product::Entity>(webhook.product_id, role::at_least(role::ADMIN))
+        .await?;
+    Ok(webhook)
+}
+
+#[server]
+pub async fn webhook_list(product_id: Uuid) -> Result<Vec<Webhook>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    check_access_by_id::<entity::product::Entity>(product_id, role::at_least(role::ADMIN)).await?;
+
+    let webhooks = WebhookRepo::get_by_product(&db, product_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(webhooks.into_iter().map(Webhook::from).collect())
+}
+
+#[server]
+pub async fn webhook_add(
+    product_id: Uuid,
+    url: String,
+    secret: String,
+    events: Vec<String>,
+) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    check_access_by_id::<entity::product::Entity>(product_id, role::at_least(role::ADMIN)).await?;
+
+    let url = url.trim().to_owned();
+    match url::Url::parse(&url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => (),
+        _ => return Err(ServerFnError::new(format!("Invalid webhook URL '{}'", url))),
+    }
+    if events.is_empty() {
+        return Err(ServerFnError::new("Select at least one event".to_string()));
+    }
+    if let Some(event) = events
+        .iter()
+        .find(|event| !WEBHOOK_EVENTS.iter().any(|(name, _)| name == event))
+    {
+        return Err(ServerFnError::new(format!("Unknown event '{}'", event)));
+    }
+
+    let secret = secret.trim();
+    Repo::create(
+        &db,
+        WebhookCreateDto {
+            url,
+            secret: (!secret.is_empty()).then(|| secret.to_owned()),
+            events: events.join(","),
+            product_id,
+        },
+    )
+    .await
+    .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(())
+}
+
+#[server]
+pub async fn webhook_remove(id: Uuid) -> Result<(), ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let webhook = get_webhook(&db, id).await?;
+    Repo::delete_by_id::<entity::webhook::Entity>(&db, webhook.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))
+}
+
+/// Sends a test event to the webhook and returns the result of the delivery.
+#[server]
+pub async fn webhook_test(id: Uuid) -> Result<WebhookDelivery, ServerFnError> {
+    check_writable()?;
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let webhook = get_webhook(&db, id).await?;
+    let data = serde_json::json!({ "message": "Test event from Guardrail" });
+    let delivery = WebhookRepo::deliver(&db, &webhook, TEST, data)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(WebhookDelivery {
+        event: delivery.event,
+        status_code: delivery.status_code,
+        error: delivery.error,
+        duration_ms: delivery.duration_ms,
+        created_at: chrono::Utc::now().naive_utc(),
+    })
+}
+
+#[server]
+pub async fn webhook_deliveries(id: Uuid) -> Result<Vec<WebhookDelivery>, ServerFnError> {
+    let db = use_context::<DatabaseConnection>()
+        .ok_or(ServerFnError::new("No database connection".to_string()))?;
+    let webhook = get_webhook(&db, id).await?;
+    let deliveries = WebhookRepo::get_deliveries(&db, webhook.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:?}")))?;
+    Ok(deliveries.into_iter().map(WebhookDelivery::from).collect())
+}
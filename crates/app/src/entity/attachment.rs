@@ -17,6 +17,8 @@ pub struct Model {
     pub size: i64,
     pub filename: String,
     pub crash_id: Uuid,
+    pub compression: Option<String>,
+    pub processed_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
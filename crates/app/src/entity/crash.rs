@@ -17,6 +17,24 @@ pub struct Model {
     pub report: Json,
     pub version_id: Uuid,
     pub product_id: Uuid,
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub promoted: Option<Json>,
+    pub minidump_hash: Option<String>,
+    pub platform: Option<String>,
+    pub environment: Option<String>,
+    pub issue_id: Option<Uuid>,
+    pub authenticated: bool,
+    pub legal_hold_at: Option<DateTime>,
+    pub legal_hold_by: Option<Uuid>,
+    pub legal_hold_reason: Option<String>,
+    pub verified_checksum: Option<String>,
+    pub signature: Option<String>,
+    pub processing_status: String,
+    pub processing_error: Option<String>,
+    pub received_at: Option<DateTime>,
+    pub clock_skew: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -25,6 +43,14 @@ pub enum Relation {
     Annotation,
     #[sea_orm(has_many = "super::attachment::Entity")]
     Attachment,
+    #[sea_orm(
+        belongs_to = "super::issue::Entity",
+        from = "Column::IssueId",
+        to = "super::issue::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Issue,
     #[sea_orm(
         belongs_to = "super::product::Entity",
         from = "Column::ProductId",
@@ -55,6 +81,12 @@ impl Related<super::attachment::Entity> for Entity {
     }
 }
 
+impl Related<super::issue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Issue.def()
+    }
+}
+
 impl Related<super::product::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Product.def()
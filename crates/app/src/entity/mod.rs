@@ -2,14 +2,34 @@
 
 pub mod prelude;
 
+pub mod alert;
 pub mod annotation;
+pub mod api_token;
 pub mod attachment;
+pub mod branding;
+pub mod build_metadata;
+pub mod client_diagnostic;
 pub mod crash;
 pub mod credential;
+pub mod device_key;
+pub mod issue;
+pub mod job_lease;
+pub mod job_state;
+pub mod link_template;
+pub mod missing_symbols;
+pub mod notification;
+pub mod organization;
+pub mod organization_member;
 pub mod product;
+pub mod product_deletion;
 pub mod role;
 pub mod sea_orm_active_enums;
 pub mod session;
+pub mod symbol_conversion;
 pub mod symbols;
+pub mod upload_count;
+pub mod upload_quarantine;
 pub mod user;
 pub mod version;
+pub mod webhook;
+pub mod webhook_delivery;
@@ -1,12 +1,32 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0
 
+pub use super::alert::Entity as Alert;
 pub use super::annotation::Entity as Annotation;
+pub use super::api_token::Entity as ApiToken;
 pub use super::attachment::Entity as Attachment;
+pub use super::branding::Entity as Branding;
+pub use super::build_metadata::Entity as BuildMetadata;
+pub use super::client_diagnostic::Entity as ClientDiagnostic;
 pub use super::crash::Entity as Crash;
 pub use super::credential::Entity as Credential;
+pub use super::device_key::Entity as DeviceKey;
+pub use super::issue::Entity as Issue;
+pub use super::job_lease::Entity as JobLease;
+pub use super::job_state::Entity as JobState;
+pub use super::link_template::Entity as LinkTemplate;
+pub use super::missing_symbols::Entity as MissingSymbols;
+pub use super::notification::Entity as Notification;
+pub use super::organization::Entity as Organization;
+pub use super::organization_member::Entity as OrganizationMember;
 pub use super::product::Entity as Product;
+pub use super::product_deletion::Entity as ProductDeletion;
 pub use super::role::Entity as Role;
 pub use super::session::Entity as Session;
+pub use super::symbol_conversion::Entity as SymbolConversion;
 pub use super::symbols::Entity as Symbols;
+pub use super::upload_count::Entity as UploadCount;
+pub use super::upload_quarantine::Entity as UploadQuarantine;
 pub use super::user::Entity as User;
 pub use super::version::Entity as Version;
+pub use super::webhook::Entity as Webhook;
+pub use super::webhook_delivery::Entity as WebhookDelivery;
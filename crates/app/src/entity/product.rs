@@ -14,18 +14,80 @@ pub struct Model {
     pub updated_at: DateTime,
     #[sea_orm(unique)]
     pub name: String,
+    pub max_build_age_days: Option<i32>,
+    pub build_age_overrides: Option<String>,
+    pub build_age_allow_list: Option<String>,
+    pub promoted_annotations: Option<String>,
+    pub redacted_fields: Option<String>,
+    pub allowed_content_types: Option<String>,
+    pub environments: Option<String>,
+    pub accepting_crashes: bool,
+    pub public_submissions: bool,
+    pub repository_url: Option<String>,
+    pub source_roots: Option<String>,
+    pub attachment_processors: Option<String>,
+    pub default_annotations: Option<String>,
+    pub auto_create_versions: bool,
+    pub ingestion_windows: Option<String>,
+    pub organization_id: Option<Uuid>,
+    pub max_minidump_size: Option<i64>,
+    pub max_attachment_size: Option<i64>,
+    pub max_attachments: Option<i32>,
+    pub max_daily_uploads: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::alert::Entity")]
+    Alert,
+    #[sea_orm(has_many = "super::client_diagnostic::Entity")]
+    ClientDiagnostic,
     #[sea_orm(has_many = "super::crash::Entity")]
     Crash,
+    #[sea_orm(has_many = "super::device_key::Entity")]
+    DeviceKey,
+    #[sea_orm(has_many = "super::issue::Entity")]
+    Issue,
+    #[sea_orm(has_many = "super::link_template::Entity")]
+    LinkTemplate,
+    #[sea_orm(has_many = "super::missing_symbols::Entity")]
+    MissingSymbols,
+    #[sea_orm(has_many = "super::notification::Entity")]
+    Notification,
+    #[sea_orm(
+        belongs_to = "super::organization::Entity",
+        from = "Column::OrganizationId",
+        to = "super::organization::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Organization,
     #[sea_orm(has_many = "super::role::Entity")]
     Role,
+    #[sea_orm(has_many = "super::symbol_conversion::Entity")]
+    SymbolConversion,
     #[sea_orm(has_many = "super::symbols::Entity")]
     Symbols,
+    #[sea_orm(has_many = "super::upload_count::Entity")]
+    UploadCount,
+    #[sea_orm(has_many = "super::upload_quarantine::Entity")]
+    UploadQuarantine,
     #[sea_orm(has_many = "super::version::Entity")]
     Version,
+    #[sea_orm(has_many = "super::webhook::Entity")]
+    Webhook,
+}
+
+impl Related<super::alert::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Alert.def()
+    }
+}
+
+impl Related<super::client_diagnostic::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ClientDiagnostic.def()
+    }
 }
 
 impl Related<super::crash::Entity> for Entity {
@@ -34,22 +96,82 @@ impl Related<super::crash::Entity> for Entity {
     }
 }
 
+impl Related<super::device_key::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DeviceKey.def()
+    }
+}
+
+impl Related<super::issue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Issue.def()
+    }
+}
+
+impl Related<super::link_template::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LinkTemplate.def()
+    }
+}
+
+impl Related<super::missing_symbols::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MissingSymbols.def()
+    }
+}
+
+impl Related<super::notification::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Notification.def()
+    }
+}
+
+impl Related<super::organization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Organization.def()
+    }
+}
+
 impl Related<super::role::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Role.def()
     }
 }
 
+impl Related<super::symbol_conversion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SymbolConversion.def()
+    }
+}
+
 impl Related<super::symbols::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Symbols.def()
     }
 }
 
+impl Related<super::upload_count::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UploadCount.def()
+    }
+}
+
+impl Related<super::upload_quarantine::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UploadQuarantine.def()
+    }
+}
+
 impl Related<super::version::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Version.def()
     }
 }
 
+impl Related<super::webhook::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Webhook.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
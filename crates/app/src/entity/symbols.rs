@@ -17,8 +17,10 @@ pub struct Model {
     pub build_id: String,
     pub module_id: String,
     pub file_location: String,
+    pub file_size: Option<i64>,
     pub product_id: Uuid,
     pub version_id: Uuid,
+    pub quarantine_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
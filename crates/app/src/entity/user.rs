@@ -16,22 +16,39 @@ pub struct Model {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub last_authenticated: Option<DateTime>,
+    pub deactivated_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::api_token::Entity")]
+    ApiToken,
     #[sea_orm(has_many = "super::credential::Entity")]
     Credential,
+    #[sea_orm(has_many = "super::organization_member::Entity")]
+    OrganizationMember,
     #[sea_orm(has_many = "super::role::Entity")]
     Role,
 }
 
+impl Related<super::api_token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiToken.def()
+    }
+}
+
 impl Related<super::credential::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Credential.def()
     }
 }
 
+impl Related<super::organization_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrganizationMember.def()
+    }
+}
+
 impl Related<super::role::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Role.def()
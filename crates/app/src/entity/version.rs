@@ -16,10 +16,14 @@ pub struct Model {
     pub hash: String,
     pub tag: String,
     pub product_id: Uuid,
+    pub channel: Option<String>,
+    pub release_date: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::alert::Entity")]
+    Alert,
     #[sea_orm(has_many = "super::crash::Entity")]
     Crash,
     #[sea_orm(
@@ -34,6 +38,12 @@ pub enum Relation {
     Symbols,
 }
 
+impl Related<super::alert::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Alert.def()
+    }
+}
+
 impl Related<super::crash::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Crash.def()
@@ -1,10 +1,16 @@
 use cfg_if::cfg_if;
 
 pub mod auth;
+pub mod build_id;
 pub mod classes;
 pub mod components;
 pub mod data;
 pub mod data_providers;
+pub mod pdf;
+pub mod prefix;
+pub mod read_only;
+pub mod report;
+pub mod rollout;
 pub mod settings;
 
 cfg_if! { if #[cfg(feature="ssr")] {
@@ -18,19 +24,33 @@ use leptos_router::*;
 
 use auth::AuthenticatedUser;
 use components::{
-    crashes::CrashPage,
+    branding::BrandingPage,
+    crash::Crash,
+    crashes::{CrashPage, CrashesPage},
+    dashboard::Dashboard,
+    device_keys::DeviceKeysPage,
     error_template::{AppError, ErrorTemplate},
+    issues::{IssuePage, IssuesPage},
     login::LoginPage,
     navbar::Navbar,
+    organizations::OrganizationsPage,
+    playground::PlaygroundPage,
     products::ProductsPage,
     profile::ProfilePage,
     register::RegisterPage,
+    setup::SetupPage,
     symbols::SymbolsPage,
+    tokens::TokensPage,
     users::UsersPage,
     versions::VersionsPage,
+    webhooks::WebhooksPage,
 };
+use data_providers::branding::{branding_get, Branding, DEFAULT_TITLE};
+use prefix::{path_prefix, prefixed};
+use read_only::is_read_only;
 
 type UserResource = Resource<i64, Option<AuthenticatedUser>>;
+type BrandingResource = Resource<(), Branding>;
 
 #[server(GetUser)]
 pub async fn authenticated_user() -> Result<Option<AuthenticatedUser>, ServerFnError> {
@@ -56,53 +76,77 @@ pub fn App() -> impl IntoView {
     let user = create_local_resource(user_info_trigger, move |_| async move {
         authenticated_user().await.unwrap_or(None)
     });
+    let branding: BrandingResource = create_blocking_resource(
+        || (),
+        |_| async move { branding_get().await.unwrap_or_default() },
+    );
+    provide_context(branding);
 
     view! {
-        <Stylesheet id="leptos" href="/pkg/site.css"/>
+        <Stylesheet id="leptos" href=prefixed("/pkg/site.css")/>
         <Stylesheet href="https://fonts.googleapis.com/css?family=Montserrat:300,400,500&display=swap"/>
 
         <Html class="dark" lang="en"/>
 
-        <Title text="GuardRail"/>
+        <Title text=move || {
+            branding.get().map_or_else(|| DEFAULT_TITLE.to_owned(), |branding| branding.title)
+        }/>
         <Meta charset="utf-8"/>
         <Meta name="viewport" content="width=device-width, initial-scale=1.0, maximum-scale=1.0"/>
         <Meta name="description" content="Crashpad server"/>
         <Meta name="keywords" content="crashes, minidump"/>
+        <Meta name="path-prefix" content=path_prefix()/>
+        <Meta name="read-only" content=is_read_only().to_string()/>
 
-        <Title text="Welcome to Leptos"/>
-
-        <Router fallback=|| {
+        <Router base=path_prefix() fallback=|| {
             let mut outside_errors = Errors::default();
             outside_errors.insert_with_default_key(AppError::NotFound);
             view! { <ErrorTemplate outside_errors/> }.into_view()
         }>
-            <div class="container h-screen max-w-full flex flex-col">
+            <div
+                class="container h-screen max-w-full flex flex-col"
+                style=move || branding.get().map(|branding| branding.css_variables())
+            >
                 <header class="sticky top-0 z-50 p-1">
-                    <Navbar trigger=user_info_trigger user=user/>
+                    <Navbar trigger=user_info_trigger user=user branding=branding/>
+                    <Show when=is_read_only>
+                        <div class="alert alert-warning rounded-btn my-1 p-2">
+                            "Read-only mirror: browsing and exports work, changes are disabled"
+                        </div>
+                    </Show>
                 </header>
                 <main class="flex-1 overflow-hidden p-1 flex flex-col">
                     <Routes>
-                        <Route path="" view=HomePage/>
+                        <Route path="" view=Dashboard/>
                         <Route
                             path="/auth/login"
                             view=move || view! { <LoginPage trigger=user_info_trigger/> }
                         />
                         <Route path="/auth/register" view=RegisterPage/>
+                        <Route
+                            path="/setup"
+                            view=move || view! { <SetupPage trigger=user_info_trigger/> }
+                        />
                         <Route path="/auth/profile" view=ProfilePage/>
+                        <Route path="/issues" view=IssuesPage/>
+                        <Route path="/issue" view=IssuePage/>
+                        <Route path="/crashes" view=CrashesPage/>
+                        <Route path="/crash" view=Crash/>
                         <Route path="/admin/users" view=UsersPage/>
+                        <Route path="/admin/tokens" view=TokensPage/>
+                        <Route path="/admin/playground" view=PlaygroundPage/>
+                        <Route path="/admin/organizations" view=OrganizationsPage/>
+                        <Route path="/admin/branding" view=BrandingPage/>
                         <Route path="/admin/products" view=ProductsPage/>
                         <Route path="/admin/versions" view=VersionsPage/>
                         <Route path="/admin/symbols" view=SymbolsPage/>
+                        <Route path="/admin/webhooks" view=WebhooksPage/>
+                        <Route path="/admin/device_keys" view=DeviceKeysPage/>
                         <Route path="/admin/crashes" view=CrashPage/>
+                        <Route path="/admin/crash" view=Crash/>
                     </Routes>
                 </main>
             </div>
         </Router>
     }
 }
-
-#[allow(non_snake_case)]
-#[component]
-fn HomePage() -> impl IntoView {
-    view! { <h1>"Welcome to Guardrail!"</h1> }
-}
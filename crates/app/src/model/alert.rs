@@ -0,0 +1,75 @@
+use super::base::HasId;
+use crate::entity;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+use tracing::instrument;
+
+pub type Alert = entity::alert::Model;
+pub type AlertCreateDto = entity::alert::CreateModel;
+pub type AlertUpdateDto = entity::alert::UpdateModel;
+
+impl HasId for entity::alert::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct AlertRepo;
+impl AlertRepo {
+    /// Raises the alert, unless an alert was already raised for the same product, version,
+    /// environment and window. Returns the id of the new alert, or `None` if it was raised
+    /// before.
+    #[instrument(skip_all)]
+    pub async fn raise(db: &DbConn, alert: AlertCreateDto) -> Result<Option<uuid::Uuid>, DbErr> {
+        let alert = alert.into_active_model();
+        let id = alert.id.clone().unwrap();
+        let inserted = entity::prelude::Alert::insert(alert)
+            .on_conflict(OnConflict::new().do_nothing().to_owned())
+            .exec_without_returning(db)
+            .await?;
+        Ok((inserted > 0).then_some(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_raise() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+
+        let window_start = chrono::Utc::now().naive_utc();
+        let alert = |environment: Option<&str>| AlertCreateDto {
+            severity: "warning".to_owned(),
+            message: "crashes".to_owned(),
+            observed: 10,
+            expected: 1.0,
+            window_start,
+            product_id,
+            version_id: None,
+            environment: environment.map(str::to_owned),
+        };
+        assert!(AlertRepo::raise(&db, alert(None)).await.unwrap().is_some());
+        assert!(AlertRepo::raise(&db, alert(None)).await.unwrap().is_none());
+        assert!(AlertRepo::raise(&db, alert(Some("staging")))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(AlertRepo::raise(&db, alert(Some("staging")))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(entity::prelude::Alert::find().count(&db).await.unwrap(), 2);
+    }
+}
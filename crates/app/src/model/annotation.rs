@@ -1,3 +1,8 @@
+use sea_orm::sea_query::SelectStatement;
+use sea_orm::*;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
 use super::base::HasId;
 use crate::entity;
 
@@ -10,3 +15,205 @@ impl HasId for entity::annotation::Model {
         self.id
     }
 }
+
+/// Parses the comma-separated `promoted_annotations` setting of a product.
+pub fn promoted_keys(setting: Option<&str>) -> Vec<String> {
+    let mut keys: Vec<String> = setting
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_owned)
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Merges the `default_annotations` setting of a product, comma-separated `key=value` pairs,
+/// into the annotations sent by the client. Values sent by the client win.
+pub fn with_defaults(
+    setting: Option<&str>,
+    annotations: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut merged: BTreeMap<String, String> = setting
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_owned(), value.trim().to_owned()))
+        })
+        .collect();
+    merged.extend(annotations);
+    merged
+}
+
+/// Builds the `promoted` column of a crash: a JSON object with the values of the promoted
+/// annotations. Returns `None` when the crash has none of them.
+pub fn promote(keys: &[String], annotations: &[Annotation]) -> Option<serde_json::Value> {
+    let promoted: serde_json::Map<String, serde_json::Value> = annotations
+        .iter()
+        .filter(|annotation| keys.contains(&annotation.key))
+        .map(|annotation| {
+            (
+                annotation.key.clone(),
+                serde_json::Value::String(annotation.value.clone()),
+            )
+        })
+        .collect();
+    (!promoted.is_empty()).then_some(serde_json::Value::Object(promoted))
+}
+
+/// The values of an annotation over a group of crashes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationSpread {
+    pub key: String,
+    /// Number of crashes of the group that have the annotation.
+    pub crashes: i64,
+    /// Number of distinct values.
+    pub distinct: usize,
+    /// The most common values with their number of crashes.
+    pub values: Vec<(String, i64)>,
+}
+
+impl AnnotationSpread {
+    /// An annotation is constant when all crashes of the group have it, with the same value.
+    pub fn is_constant(&self, total: i64) -> bool {
+        self.distinct == 1 && self.crashes == total
+    }
+}
+
+/// Groups counts of `(key, value)` pairs over a group of `total` crashes by key. Constant
+/// annotations come first, the others by how many crashes have them. At most `limit` values
+/// are kept per key.
+pub fn spread(
+    total: i64,
+    counts: Vec<(String, String, i64)>,
+    limit: usize,
+) -> Vec<AnnotationSpread> {
+    let mut spreads: Vec<AnnotationSpread> = vec![];
+    for (key, value, count) in counts {
+        match spreads.iter_mut().find(|spread| spread.key == key) {
+            Some(spread) => {
+                spread.crashes += count;
+                spread.distinct += 1;
+                spread.values.push((value, count));
+            }
+            None => spreads.push(AnnotationSpread {
+                key,
+                crashes: count,
+                distinct: 1,
+                values: vec![(value, count)],
+            }),
+        }
+    }
+    for spread in spreads.iter_mut() {
+        spread
+            .values
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        spread.values.truncate(limit);
+    }
+    spreads.sort_by(|a, b| {
+        b.is_constant(total)
+            .cmp(&a.is_constant(total))
+            .then_with(|| b.crashes.cmp(&a.crashes))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    spreads
+}
+
+pub struct AnnotationRepo;
+impl AnnotationRepo {
+    /// Counts the crashes per annotation key and value over the crashes selected by `crashes`,
+    /// which must select crash ids.
+    #[instrument(skip_all)]
+    pub async fn value_counts(
+        db: &DbConn,
+        crashes: SelectStatement,
+    ) -> Result<Vec<(String, String, i64)>, DbErr> {
+        entity::annotation::Entity::find()
+            .select_only()
+            .column(entity::annotation::Column::Key)
+            .column(entity::annotation::Column::Value)
+            .column_as(entity::annotation::Column::CrashId.count(), "count")
+            .filter(entity::annotation::Column::CrashId.in_subquery(crashes))
+            .group_by(entity::annotation::Column::Key)
+            .group_by(entity::annotation::Column::Value)
+            .into_tuple::<(String, String, i64)>()
+            .all(db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::sea_orm_active_enums::AnnotationKind;
+
+    fn annotation(key: &str, value: &str) -> Annotation {
+        Annotation {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: chrono::Utc::now().naive_utc(),
+            key: key.to_owned(),
+            kind: AnnotationKind::User,
+            value: value.to_owned(),
+            crash_id: uuid::Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn test_promote() {
+        let keys = promoted_keys(Some(" os_version, channel,,os_version "));
+        assert_eq!(keys, vec!["channel".to_owned(), "os_version".to_owned()]);
+        assert!(promoted_keys(None).is_empty());
+
+        let annotations = vec![
+            annotation("os_version", "10.0.19045"),
+            annotation("locale", "nl_NL"),
+        ];
+        assert_eq!(
+            promote(&keys, &annotations),
+            Some(serde_json::json!({ "os_version": "10.0.19045" }))
+        );
+        assert_eq!(promote(&keys, &annotations[1..]), None);
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let client = BTreeMap::from([("tier".to_owned(), "free".to_owned())]);
+        assert_eq!(
+            with_defaults(Some(" team = desktop, tier=gold,broken,=x "), client),
+            BTreeMap::from([
+                ("team".to_owned(), "desktop".to_owned()),
+                ("tier".to_owned(), "free".to_owned()),
+            ])
+        );
+        assert!(with_defaults(None, BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_spread() {
+        let count = |key: &str, value: &str, count| (key.to_owned(), value.to_owned(), count);
+        let spreads = spread(
+            4,
+            vec![
+                count("locale", "nl_NL", 1),
+                count("gpu", "NVIDIA 546.33", 4),
+                count("locale", "en_US", 3),
+                count("plugin", "exercises", 2),
+            ],
+            1,
+        );
+
+        let keys: Vec<&str> = spreads.iter().map(|spread| spread.key.as_str()).collect();
+        assert_eq!(keys, vec!["gpu", "locale", "plugin"]);
+        assert!(spreads[0].is_constant(4));
+        assert!(!spreads[1].is_constant(4));
+        assert_eq!(spreads[1].distinct, 2);
+        assert_eq!(spreads[1].values, vec![("en_US".to_owned(), 3)]);
+        // Present with a single value, but not on all crashes.
+        assert!(!spreads[2].is_constant(4));
+    }
+}
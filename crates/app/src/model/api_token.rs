@@ -0,0 +1,433 @@
+use super::base::{HasId, Repo};
+use super::organization::OrganizationRepo;
+use super::role;
+use crate::entity;
+use crate::read_only::is_read_only;
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+
+pub type ApiToken = entity::api_token::Model;
+pub type ApiTokenCreateDto = entity::api_token::CreateModel;
+pub type ApiTokenUpdateDto = entity::api_token::UpdateModel;
+
+/// Prefix of personal API tokens, so that they are recognizable in scripts and secret scanners.
+pub const TOKEN_PREFIX: &str = "grt_";
+/// Role that allows updating a product through the products API. Also the entitlement of tokens
+/// for the products API.
+pub const PRODUCT_MANAGE: &str = "product-manage";
+/// Entitlement of tokens for the read and export API.
+pub const READ: &str = "read";
+/// Entitlement of tokens for the administration API. Only tokens of administrators that are not
+/// limited to a product can use it.
+pub const ADMIN: &str = "admin";
+/// Entitlements a token can be limited to. A token without entitlements has all of them.
+pub const ENTITLEMENTS: [&str; 3] = [READ, PRODUCT_MANAGE, ADMIN];
+/// How often the last use of a token is recorded, so that scripts polling the API do not write
+/// to the database on every request.
+const LAST_USED_INTERVAL_SECS: i64 = 5 * 60;
+
+impl HasId for entity::api_token::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+impl entity::api_token::Model {
+    /// Returns whether the token may be used for the API of the entitlement.
+    pub fn has_entitlement(&self, entitlement: &str) -> bool {
+        self.entitlements.as_deref().map_or(true, |entitlements| {
+            entitlements.split(',').any(|e| e.trim() == entitlement)
+        })
+    }
+
+    /// Limits the products the owner has access to, `None` for all products, to the product of
+    /// the token.
+    pub fn scope_products(&self, products: Option<Vec<uuid::Uuid>>) -> Option<Vec<uuid::Uuid>> {
+        match self.product_id {
+            None => products,
+            Some(product_id) => Some(
+                products
+                    .map_or(true, |products| products.contains(&product_id))
+                    .then(|| vec![product_id])
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+/// Returns the entitlements as stored with a token, `None` for all entitlements, or the first
+/// unknown entitlement.
+pub fn join_entitlements(entitlements: &[String]) -> Result<Option<String>, String> {
+    let mut joined = Vec::new();
+    for entitlement in entitlements.iter().map(|e| e.trim()) {
+        if !ENTITLEMENTS.contains(&entitlement) {
+            return Err(entitlement.to_owned());
+        }
+        if !joined.contains(&entitlement) {
+            joined.push(entitlement);
+        }
+    }
+    Ok((!joined.is_empty()).then(|| joined.join(",")))
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Returns whether a use of the token at `now` is recorded, given its last recorded use.
+fn records_use(last_used_at: Option<chrono::NaiveDateTime>, now: chrono::NaiveDateTime) -> bool {
+    last_used_at.map_or(true, |last_used_at| {
+        (now - last_used_at).num_seconds() >= LAST_USED_INTERVAL_SECS
+    })
+}
+
+fn generate_token() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+pub struct ApiTokenRepo;
+impl ApiTokenRepo {
+    /// Creates a personal API token, limited to the comma separated `entitlements` and to
+    /// `product_id` when given. The token itself is only returned here; the database only holds
+    /// its hash.
+    #[instrument(skip_all)]
+    pub async fn create(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+        name: String,
+        entitlements: Option<String>,
+        product_id: Option<uuid::Uuid>,
+    ) -> Result<(uuid::Uuid, String), DbErr> {
+        let token = generate_token();
+        let dto = ApiTokenCreateDto {
+            name,
+            token_hash: hash_token(&token),
+            user_id,
+            last_used_at: None,
+            entitlements,
+            product_id,
+        };
+        let id = Repo::create(db, dto).await?;
+        Ok((id, token))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_by_user(db: &DbConn, user_id: uuid::Uuid) -> Result<Vec<ApiToken>, DbErr> {
+        entity::prelude::ApiToken::find()
+            .filter(entity::api_token::Column::UserId.eq(user_id))
+            .order_by_asc(entity::api_token::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Returns all tokens with their owners, for administrators.
+    #[instrument(skip_all)]
+    pub async fn get_all(
+        db: &DbConn,
+    ) -> Result<Vec<(ApiToken, Option<entity::user::Model>)>, DbErr> {
+        entity::prelude::ApiToken::find()
+            .find_also_related(entity::prelude::User)
+            .order_by_asc(entity::api_token::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Replaces the token of `id` with a new one, keeping its name and scope. The old token is
+    /// rejected right away. Returns the new token.
+    #[instrument(skip_all)]
+    pub async fn rotate(db: &DbConn, id: uuid::Uuid) -> Result<String, DbErr> {
+        let token = generate_token();
+        let result = entity::prelude::ApiToken::update_many()
+            .col_expr(
+                entity::api_token::Column::TokenHash,
+                sea_query::Expr::value(hash_token(&token)),
+            )
+            .col_expr(
+                entity::api_token::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::api_token::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(DbErr::RecordNotFound("token not found".to_owned()));
+        }
+        Ok(token)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn remove(db: &DbConn, user_id: uuid::Uuid, id: uuid::Uuid) -> Result<(), DbErr> {
+        entity::prelude::ApiToken::delete_many()
+            .filter(entity::api_token::Column::Id.eq(id))
+            .filter(entity::api_token::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the owner of the token and the token, or `None` if the token is unknown or the
+    /// owner is deactivated. Records the use of the token, at most once per
+    /// `LAST_USED_INTERVAL_SECS`, and not on a read-only instance.
+    #[instrument(skip_all)]
+    pub async fn authenticate(
+        db: &DbConn,
+        token: &str,
+    ) -> Result<Option<(entity::user::Model, ApiToken)>, DbErr> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let Some((api_token, user)) = entity::prelude::ApiToken::find()
+            .filter(entity::api_token::Column::TokenHash.eq(hash_token(token)))
+            .find_also_related(entity::prelude::User)
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        if !is_read_only() && records_use(api_token.last_used_at, now) {
+            entity::prelude::ApiToken::update_many()
+                .col_expr(
+                    entity::api_token::Column::LastUsedAt,
+                    sea_query::Expr::value(now),
+                )
+                .filter(entity::api_token::Column::Id.eq(api_token.id))
+                .exec(db)
+                .await?;
+        }
+        Ok(user
+            .filter(|user| user.deactivated_at.is_none())
+            .map(|user| (user, api_token)))
+    }
+
+    /// Returns the products the user may read, based on their roles and organizations. `None`
+    /// means all products. Takes the id and admin flag only, so that it serves both the users of
+    /// API tokens and those of sessions.
+    #[instrument(skip_all)]
+    pub async fn readable_products(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+        is_admin: bool,
+    ) -> Result<Option<Vec<uuid::Uuid>>, DbErr> {
+        if is_admin {
+            return Ok(None);
+        }
+        let products = entity::prelude::Role::find()
+            .select_only()
+            .column(entity::role::Column::ProductId)
+            .filter(entity::role::Column::UserId.eq(user_id))
+            .filter(entity::role::Column::ProductId.is_not_null())
+            .distinct()
+            .into_tuple::<Option<uuid::Uuid>>()
+            .all(db)
+            .await?;
+        let mut products: Vec<_> = products.into_iter().flatten().collect();
+        products.extend(OrganizationRepo::member_products(db, user_id, &[]).await?);
+        products.sort();
+        products.dedup();
+        Ok(Some(products))
+    }
+
+    /// Returns the products the user may manage, i.e. those with an `admin` or `product-manage`
+    /// role and those of organizations in which the user is admin. `None` means all products.
+    #[instrument(skip_all)]
+    pub async fn manageable_products(
+        db: &DbConn,
+        user: &entity::user::Model,
+    ) -> Result<Option<Vec<uuid::Uuid>>, DbErr> {
+        if user.is_admin {
+            return Ok(None);
+        }
+        let products = entity::prelude::Role::find()
+            .select_only()
+            .column(entity::role::Column::ProductId)
+            .filter(entity::role::Column::UserId.eq(user.id))
+            .filter(entity::role::Column::Name.is_in([role::ADMIN, PRODUCT_MANAGE]))
+            .filter(entity::role::Column::ProductId.is_not_null())
+            .distinct()
+            .into_tuple::<Option<uuid::Uuid>>()
+            .all(db)
+            .await?;
+        let mut products: Vec<_> = products.into_iter().flatten().collect();
+        products.extend(
+            OrganizationRepo::member_products(db, user.id, &role::at_least(role::ADMIN)).await?,
+        );
+        products.sort();
+        products.dedup();
+        Ok(Some(products))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity,
+        model::{
+            api_token::{
+                hash_token, records_use, ApiTokenRepo, ADMIN, PRODUCT_MANAGE, READ, TOKEN_PREFIX,
+            },
+            base::Repo,
+            product::ProductCreateDto,
+        },
+    };
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_api_token() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let user_id = Repo::create(
+            &db,
+            entity::user::CreateModel {
+                username: "developer".to_owned(),
+                is_admin: false,
+                last_authenticated: None,
+                deactivated_at: None,
+            },
+        )
+        .await
+        .unwrap();
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        Repo::create(
+            &db,
+            entity::role::CreateModel {
+                name: "developer".to_owned(),
+                user_id,
+                product_id: Some(product_id),
+            },
+        )
+        .await
+        .unwrap();
+
+        let (id, token) = ApiTokenRepo::create(&db, user_id, "scripts".to_owned(), None, None)
+            .await
+            .unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+
+        let tokens = ApiTokenRepo::get_by_user(&db, user_id).await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].token_hash, hash_token(&token));
+
+        let (user, api_token) = ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.id, user_id);
+        assert!(api_token.has_entitlement(ADMIN));
+        assert_eq!(
+            ApiTokenRepo::readable_products(&db, user.id, user.is_admin)
+                .await
+                .unwrap(),
+            Some(vec![product_id])
+        );
+        assert_eq!(
+            ApiTokenRepo::manageable_products(&db, &user).await.unwrap(),
+            Some(vec![])
+        );
+        assert!(ApiTokenRepo::authenticate(&db, "grt_unknown")
+            .await
+            .unwrap()
+            .is_none());
+
+        let rotated = ApiTokenRepo::rotate(&db, id).await.unwrap();
+        assert!(ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(ApiTokenRepo::authenticate(&db, &rotated)
+            .await
+            .unwrap()
+            .is_some());
+
+        ApiTokenRepo::remove(&db, user_id, id).await.unwrap();
+        assert!(ApiTokenRepo::authenticate(&db, &rotated)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_join_entitlements() {
+        assert_eq!(join_entitlements(&[]), Ok(None));
+        assert_eq!(
+            join_entitlements(&["read".to_owned(), " read".to_owned(), "admin".to_owned()]),
+            Ok(Some("read,admin".to_owned()))
+        );
+        assert_eq!(
+            join_entitlements(&["upload".to_owned()]),
+            Err("upload".to_owned())
+        );
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_scope() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let user_id = Repo::create(
+            &db,
+            entity::user::CreateModel {
+                username: "release".to_owned(),
+                is_admin: true,
+                last_authenticated: None,
+                deactivated_at: None,
+            },
+        )
+        .await
+        .unwrap();
+        let product_id = uuid::Uuid::new_v4();
+        let other_id = uuid::Uuid::new_v4();
+        let (_, token) = ApiTokenRepo::create(
+            &db,
+            user_id,
+            "pipeline".to_owned(),
+            Some(format!("{}, {}", READ, PRODUCT_MANAGE)),
+            Some(product_id),
+        )
+        .await
+        .unwrap();
+
+        let (_, api_token) = ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(api_token.last_used_at.is_none());
+        assert!(api_token.has_entitlement(READ));
+        assert!(api_token.has_entitlement(PRODUCT_MANAGE));
+        assert!(!api_token.has_entitlement(ADMIN));
+        assert_eq!(api_token.scope_products(None), Some(vec![product_id]));
+        assert_eq!(
+            api_token.scope_products(Some(vec![product_id, other_id])),
+            Some(vec![product_id])
+        );
+        assert_eq!(api_token.scope_products(Some(vec![other_id])), Some(vec![]));
+
+        let tokens = ApiTokenRepo::get_all(&db).await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].0.last_used_at.is_some());
+        assert_eq!(tokens[0].1.as_ref().map(|user| user.id), Some(user_id));
+    }
+
+    #[test]
+    fn test_records_use() {
+        let now = chrono::Utc::now().naive_utc();
+        assert!(records_use(None, now));
+        assert!(!records_use(Some(now - chrono::Duration::minutes(1)), now));
+        assert!(records_use(Some(now - chrono::Duration::minutes(5)), now));
+    }
+}
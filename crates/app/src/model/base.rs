@@ -1,4 +1,5 @@
 use sea_orm::*;
+use tracing::instrument;
 
 pub trait HasId {
     fn id(&self) -> uuid::Uuid;
@@ -6,6 +7,7 @@ pub trait HasId {
 pub struct Repo;
 
 impl Repo {
+    #[instrument(skip_all)]
     pub async fn create<E, D, A>(db: &DbConn, data: D) -> Result<uuid::Uuid, DbErr>
     where
         E: EntityTrait,
@@ -17,6 +19,7 @@ impl Repo {
         Ok(model.id())
     }
 
+    #[instrument(skip_all)]
     pub async fn update<E, D, A>(db: &DbConn, data: D) -> Result<uuid::Uuid, DbErr>
     where
         E: EntityTrait,
@@ -30,6 +33,7 @@ impl Repo {
         Ok(model.id())
     }
 
+    #[instrument(skip_all)]
     pub async fn delete_by_id<E>(db: &DbConn, id: uuid::Uuid) -> Result<(), DbErr>
     where
         E: EntityTrait,
@@ -40,6 +44,7 @@ impl Repo {
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub async fn get_all<E>(db: &DbConn) -> Result<Vec<<E as EntityTrait>::Model>, DbErr>
     where
         E: EntityTrait,
@@ -47,6 +52,7 @@ impl Repo {
         <E as EntityTrait>::find().all(db).await
     }
 
+    #[instrument(skip_all)]
     pub async fn get_by_id<E>(
         db: &DbConn,
         id: uuid::Uuid,
@@ -58,6 +64,7 @@ impl Repo {
         <E as EntityTrait>::find_by_id(id).one(db).await
     }
 
+    #[instrument(skip_all)]
     pub async fn get_by_column<E, Id, C>(
         db: &DbConn,
         column: C,
@@ -71,6 +78,7 @@ impl Repo {
         E::find().filter(column.eq(key)).one(db).await
     }
 
+    #[instrument(skip_all)]
     pub async fn get_all_by_column<E, Id, C>(
         db: &DbConn,
         column: C,
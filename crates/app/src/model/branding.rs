@@ -0,0 +1,133 @@
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::entity;
+
+pub type Branding = entity::branding::Model;
+
+// The web interface shows the title, logo and accent color of the deployment, so that teams that
+// run their own instance can recognize it. Branding is stored as a single record that
+// administrators edit; without one the defaults of `data_providers::branding` are used.
+
+/// Maximum length of the title, in characters.
+pub const MAX_TITLE_LENGTH: usize = 64;
+
+impl HasId for entity::branding::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns whether the color is a CSS hex color, `#rgb` or `#rrggbb`. Other CSS values are not
+/// accepted, as the color ends up in a style attribute.
+pub fn is_valid_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Returns whether the logo URL is an `http(s)` URL or an absolute path on this server.
+pub fn is_valid_logo_url(url: &str) -> bool {
+    let valid_chars = !url
+        .chars()
+        .any(|c| c.is_whitespace() || c == '"' || c == '\'');
+    valid_chars
+        && (url.starts_with("https://")
+            || url.starts_with("http://")
+            || (url.starts_with('/') && !url.starts_with("//")))
+}
+
+pub struct BrandingRepo;
+impl BrandingRepo {
+    #[instrument(skip_all)]
+    pub async fn get(db: &DbConn) -> Result<Option<Branding>, DbErr> {
+        entity::prelude::Branding::find().one(db).await
+    }
+
+    /// Replaces the branding. The values must have been validated.
+    #[instrument(skip_all)]
+    pub async fn set(
+        db: &DbConn,
+        title: String,
+        logo_url: Option<String>,
+        accent_color: Option<String>,
+    ) -> Result<(), DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        match Self::get(db).await? {
+            Some(branding) => {
+                let mut branding = branding.into_active_model();
+                branding.title = Set(title);
+                branding.logo_url = Set(logo_url);
+                branding.accent_color = Set(accent_color);
+                branding.updated_at = Set(now);
+                branding.update(db).await?;
+            }
+            None => {
+                entity::branding::ActiveModel {
+                    id: Set(uuid::Uuid::new_v4()),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    title: Set(title),
+                    logo_url: Set(logo_url),
+                    accent_color: Set(accent_color),
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_validation() {
+        assert!(is_valid_color("#0af"));
+        assert!(is_valid_color("#00AAff"));
+        assert!(!is_valid_color("00aaff"));
+        assert!(!is_valid_color("#00aaf"));
+        assert!(!is_valid_color("#0af; background: url(x)"));
+        assert!(!is_valid_color("red"));
+
+        assert!(is_valid_logo_url("https://example.com/logo.svg"));
+        assert!(is_valid_logo_url("/static/logo.png"));
+        assert!(!is_valid_logo_url("//example.com/logo.svg"));
+        assert!(!is_valid_logo_url("javascript:alert(1)"));
+        assert!(!is_valid_logo_url("https://example.com/a\" onerror=\"x"));
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_set() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        assert_eq!(BrandingRepo::get(&db).await.unwrap(), None);
+        BrandingRepo::set(&db, "Crashes".to_owned(), None, Some("#0af".to_owned()))
+            .await
+            .unwrap();
+        BrandingRepo::set(
+            &db,
+            "Desktop crashes".to_owned(),
+            Some("/static/logo.png".to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let branding = BrandingRepo::get(&db).await.unwrap().unwrap();
+        assert_eq!(branding.title, "Desktop crashes");
+        assert_eq!(branding.logo_url.as_deref(), Some("/static/logo.png"));
+        assert_eq!(branding.accent_color, None);
+        assert_eq!(
+            entity::prelude::Branding::find().count(&db).await.unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use serde_json::Value;
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::data_providers::crash::{BuildArtifact, BuildInfo};
+use crate::entity;
+
+pub type BuildMetadata = entity::build_metadata::Model;
+pub type BuildMetadataCreateDto = entity::build_metadata::CreateModel;
+
+// Link templates of a product can have a `metadata_url` next to their `url`, e.g. the API URL of
+// the CI pipeline that built a commit, rendered from the annotations of a crash like the link
+// itself. The metadata is fetched by a background job and cached here per template and rendered
+// URL, so that the crash page shows the status and artifacts of the build without waiting for
+// the CI server. The metadata of builds that have finished does not change and is kept.
+
+/// Statuses of builds that have finished. GitHub Actions reports these as the `conclusion` of a
+/// run, GitLab as the `status` of a pipeline.
+const FINISHED: [&str; 8] = [
+    "success",
+    "failure",
+    "failed",
+    "cancelled",
+    "canceled",
+    "skipped",
+    "timed_out",
+    "neutral",
+];
+
+impl HasId for entity::build_metadata::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+impl From<BuildMetadata> for BuildInfo {
+    fn from(metadata: BuildMetadata) -> Self {
+        Self {
+            status: metadata.status,
+            artifacts: metadata
+                .artifacts
+                .and_then(|artifacts| serde_json::from_value(artifacts).ok())
+                .unwrap_or_default(),
+            error: metadata.error,
+            fetched_at: metadata.fetched_at,
+        }
+    }
+}
+
+/// Returns whether the build has finished, so that its metadata no longer changes.
+pub fn is_finished(status: Option<&str>) -> bool {
+    status.is_some_and(|status| FINISHED.contains(&status.to_lowercase().as_str()))
+}
+
+/// Reads the status and artifacts of a build from the response of a CI server. The status is
+/// the `conclusion` (GitHub Actions) when set, or the `status` otherwise. Artifacts are read from
+/// an `artifacts` array of objects with a `name` and a `url`, `web_url`, `download_url` or
+/// `archive_download_url`.
+pub fn parse_metadata(json: &Value) -> (Option<String>, Vec<BuildArtifact>) {
+    let status = ["conclusion", "status"]
+        .iter()
+        .find_map(|key| json.get(key)?.as_str())
+        .map(str::to_owned);
+    let artifacts = json
+        .get("artifacts")
+        .and_then(Value::as_array)
+        .map(|artifacts| {
+            artifacts
+                .iter()
+                .filter_map(|artifact| {
+                    let name = artifact.get("name")?.as_str()?;
+                    let url = ["url", "web_url", "download_url", "archive_download_url"]
+                        .iter()
+                        .find_map(|key| artifact.get(key)?.as_str())?;
+                    Some(BuildArtifact {
+                        name: name.to_owned(),
+                        url: url.to_owned(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    (status, artifacts)
+}
+
+pub struct BuildMetadataRepo;
+impl BuildMetadataRepo {
+    #[instrument(skip_all)]
+    pub async fn get(
+        db: &DbConn,
+        link_template_id: uuid::Uuid,
+        url: &str,
+    ) -> Result<Option<BuildMetadata>, DbErr> {
+        entity::prelude::BuildMetadata::find()
+            .filter(entity::build_metadata::Column::LinkTemplateId.eq(link_template_id))
+            .filter(entity::build_metadata::Column::Url.eq(url))
+            .one(db)
+            .await
+    }
+
+    /// Returns whether the metadata must be fetched, i.e. when it has not been fetched before,
+    /// or when the build had not finished and the metadata was fetched before `stale_before`.
+    pub fn needs_fetch(metadata: Option<&BuildMetadata>, stale_before: NaiveDateTime) -> bool {
+        match metadata {
+            None => true,
+            Some(metadata) => {
+                !is_finished(metadata.status.as_deref()) && metadata.fetched_at < stale_before
+            }
+        }
+    }
+
+    /// Stores the metadata fetched from `url`, or the error that occurred, replacing what was
+    /// fetched before.
+    #[instrument(skip_all)]
+    pub async fn store(
+        db: &DbConn,
+        link_template_id: uuid::Uuid,
+        url: &str,
+        fetched: Result<(Option<String>, Vec<BuildArtifact>), String>,
+    ) -> Result<(), DbErr> {
+        let (status, artifacts, error) = match fetched {
+            Ok((status, artifacts)) => (
+                status,
+                Some(serde_json::to_value(artifacts).unwrap_or(Value::Null)),
+                None,
+            ),
+            Err(error) => (None, None, Some(error)),
+        };
+        let fetched_at = chrono::Utc::now().naive_utc();
+        match Self::get(db, link_template_id, url).await? {
+            Some(metadata) => {
+                let mut metadata = metadata.into_active_model();
+                metadata.status = Set(status);
+                metadata.artifacts = Set(artifacts);
+                metadata.error = Set(error);
+                metadata.fetched_at = Set(fetched_at);
+                metadata.updated_at = Set(fetched_at);
+                metadata.update(db).await?;
+            }
+            None => {
+                BuildMetadataCreateDto {
+                    url: url.to_owned(),
+                    status,
+                    artifacts,
+                    error,
+                    fetched_at,
+                    link_template_id,
+                }
+                .into_active_model()
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_metadata() {
+        let github = json!({
+            "status": "completed",
+            "conclusion": "success",
+            "artifacts": [
+                { "name": "installer", "archive_download_url": "https://ci/artifacts/1/zip" },
+                { "name": "no url" },
+            ],
+        });
+        assert_eq!(
+            parse_metadata(&github),
+            (
+                Some("success".to_owned()),
+                vec![BuildArtifact {
+                    name: "installer".to_owned(),
+                    url: "https://ci/artifacts/1/zip".to_owned(),
+                }]
+            )
+        );
+
+        let gitlab = json!({ "status": "running", "conclusion": null });
+        assert_eq!(
+            parse_metadata(&gitlab),
+            (Some("running".to_owned()), vec![])
+        );
+        assert_eq!(parse_metadata(&json!([])), (None, vec![]));
+    }
+
+    #[test]
+    fn test_needs_fetch() {
+        let now = chrono::Utc::now().naive_utc();
+        let metadata = |status: &str| BuildMetadata {
+            id: uuid::Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+            url: "https://ci/api/builds/1".to_owned(),
+            status: Some(status.to_owned()),
+            artifacts: None,
+            error: None,
+            fetched_at: now - chrono::Duration::hours(1),
+            link_template_id: uuid::Uuid::new_v4(),
+        };
+        assert!(BuildMetadataRepo::needs_fetch(None, now));
+        assert!(BuildMetadataRepo::needs_fetch(
+            Some(&metadata("running")),
+            now
+        ));
+        assert!(!BuildMetadataRepo::needs_fetch(
+            Some(&metadata("running")),
+            now - chrono::Duration::hours(2)
+        ));
+        assert!(!BuildMetadataRepo::needs_fetch(
+            Some(&metadata("Failed")),
+            now
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::{HasId, Repo};
+use crate::entity;
+
+pub type ClientDiagnostic = entity::client_diagnostic::Model;
+pub type ClientDiagnosticCreateDto = entity::client_diagnostic::CreateModel;
+
+/// Kinds of upload failures that clients report. Other kinds are stored as `other`.
+pub const KINDS: [&str; 6] = [
+    "http_error",
+    "network",
+    "timeout",
+    "too_large",
+    "rejected",
+    "other",
+];
+
+/// Maximum length of the message of a report, in characters.
+pub const MAX_MESSAGE_LENGTH: usize = 500;
+
+impl HasId for entity::client_diagnostic::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns the kind of a report as stored, one of `KINDS`.
+pub fn normalize_kind(kind: &str) -> &'static str {
+    let kind = kind.trim().to_lowercase();
+    KINDS
+        .iter()
+        .find(|known| **known == kind)
+        .copied()
+        .unwrap_or("other")
+}
+
+pub struct ClientDiagnosticRepo;
+impl ClientDiagnosticRepo {
+    /// Returns the most recent reports of the product, newest first.
+    #[instrument(skip_all)]
+    pub async fn get_by_product(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        limit: u64,
+    ) -> Result<Vec<ClientDiagnostic>, DbErr> {
+        entity::prelude::ClientDiagnostic::find()
+            .filter(entity::client_diagnostic::Column::ProductId.eq(product_id))
+            .order_by_desc(entity::client_diagnostic::Column::CreatedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// Stores a report and removes the reports of the product that were stored before
+    /// `kept_since`, or that are beyond the `max_kept` most recent ones.
+    #[instrument(skip_all)]
+    pub async fn record(
+        db: &DbConn,
+        dto: ClientDiagnosticCreateDto,
+        kept_since: NaiveDateTime,
+        max_kept: u64,
+    ) -> Result<uuid::Uuid, DbErr> {
+        let product_id = dto.product_id;
+        let id = Repo::create(db, dto).await?;
+
+        let oldest_kept: Option<NaiveDateTime> = entity::prelude::ClientDiagnostic::find()
+            .select_only()
+            .column(entity::client_diagnostic::Column::CreatedAt)
+            .filter(entity::client_diagnostic::Column::ProductId.eq(product_id))
+            .order_by_desc(entity::client_diagnostic::Column::CreatedAt)
+            .offset(max_kept.saturating_sub(1))
+            .into_tuple()
+            .one(db)
+            .await?;
+        let kept_since = oldest_kept.map_or(kept_since, |oldest| oldest.max(kept_since));
+        entity::prelude::ClientDiagnostic::delete_many()
+            .filter(entity::client_diagnostic::Column::ProductId.eq(product_id))
+            .filter(entity::client_diagnostic::Column::CreatedAt.lt(kept_since))
+            .exec(db)
+            .await?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_normalize_kind() {
+        assert_eq!(normalize_kind(" Too_Large"), "too_large");
+        assert_eq!(normalize_kind("disk_full"), "other");
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let report = |kind: &str| ClientDiagnosticCreateDto {
+            kind: kind.to_owned(),
+            status_code: Some(413),
+            message: None,
+            version: Some("1.11.0".to_owned()),
+            sdk: None,
+            attempts: Some(3),
+            failed_at: None,
+            product_id,
+        };
+
+        let long_ago = chrono::Utc::now().naive_utc() - chrono::Duration::days(1);
+        for _ in 0..5 {
+            ClientDiagnosticRepo::record(&db, report("too_large"), long_ago, 3)
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        let kept = ClientDiagnosticRepo::get_by_product(&db, product_id, 10)
+            .await
+            .unwrap();
+        assert_eq!(kept.len(), 3);
+
+        // Reports from before the retention period are removed as well.
+        let kept_since = chrono::Utc::now().naive_utc();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        ClientDiagnosticRepo::record(&db, report("network"), kept_since, 3)
+            .await
+            .unwrap();
+        let kept = ClientDiagnosticRepo::get_by_product(&db, product_id, 10)
+            .await
+            .unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].kind, "network");
+    }
+}
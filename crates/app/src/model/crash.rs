@@ -1,15 +1,30 @@
 use super::base::HasId;
+use super::product::RedactionRules;
+use super::role;
+use super::user::UserRepo;
 pub use crate::entity::annotation::Model as Annotation;
 pub use crate::entity::attachment::Model as Attachment;
 
 use chrono::NaiveDateTime;
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
+use tracing::instrument;
 use uuid::Uuid;
 
+use crate::report::CrashInfo;
+
 pub type CrashCreateDto = crate::entity::crash::CreateModel;
 pub type CrashUpdateDto = crate::entity::crash::UpdateModel;
 
+/// Processing status of a crash whose minidump is stored, or queued during an ingestion window,
+/// but not processed yet.
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_PROCESSING: &str = "processing";
+/// Processing status of a crash whose minidump could not be processed; the error is stored with
+/// it. The crash can be reprocessed.
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_DONE: &str = "done";
+
 impl HasId for Crash {
     fn id(&self) -> uuid::Uuid {
         self.id
@@ -31,6 +46,12 @@ pub struct Crash {
     pub summary: String,
     pub version_id: Uuid,
     pub product_id: Uuid,
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    pub processing_status: String,
+    pub processing_error: Option<String>,
+    pub received_at: Option<NaiveDateTime>,
+    pub clock_skew: Option<i64>,
     pub annotations: Vec<Annotation>,
     pub attachments: Vec<Attachment>,
 }
@@ -45,6 +66,12 @@ impl From<crate::entity::crash::Model> for Crash {
             summary: crash.summary,
             version_id: crash.version_id,
             product_id: crash.product_id,
+            user_agent: crash.user_agent,
+            sdk: crash.sdk,
+            processing_status: crash.processing_status,
+            processing_error: crash.processing_error,
+            received_at: crash.received_at,
+            clock_skew: crash.clock_skew,
             annotations: vec![],
             attachments: vec![],
         }
@@ -53,6 +80,7 @@ impl From<crate::entity::crash::Model> for Crash {
 pub struct CrashRepo;
 
 impl CrashRepo {
+    #[instrument(skip_all)]
     pub async fn get_by_id(db: &DbConn, id: uuid::Uuid) -> Result<Crash, DbErr> {
         let model = crate::entity::prelude::Crash::find_by_id(id)
             .one(db)
@@ -73,16 +101,341 @@ impl CrashRepo {
         crash.attachments = attachments.into_iter().map(Attachment::from).collect();
         Ok(crash)
     }
+
+    /// Returns whether the user sees the reports of the product unredacted. Administrators and
+    /// admins of the product do.
+    #[instrument(skip_all)]
+    pub async fn sees_unredacted(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        is_admin: bool,
+    ) -> Result<bool, DbErr> {
+        Ok(is_admin || UserRepo::has_role(db, user_id, product_id, role::ADMIN).await?)
+    }
+
+    /// Returns the redaction rules of the product that apply to the user, see
+    /// `sees_unredacted`.
+    #[instrument(skip_all)]
+    pub async fn redaction_rules(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        is_admin: bool,
+    ) -> Result<RedactionRules, DbErr> {
+        if Self::sees_unredacted(db, product_id, user_id, is_admin).await? {
+            return Ok(RedactionRules::default());
+        }
+        let product = crate::entity::prelude::Product::find_by_id(product_id)
+            .one(db)
+            .await?;
+        Ok(product
+            .as_ref()
+            .map(RedactionRules::from)
+            .unwrap_or_default())
+    }
+
+    /// Returns the crash with the redaction rules of its product applied to the report for the
+    /// user. Every page and API that shows a report to a user loads the crash with this; access
+    /// to the product is checked by the caller.
+    #[instrument(skip_all)]
+    pub async fn get_for_user(
+        db: &DbConn,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        is_admin: bool,
+    ) -> Result<Crash, DbErr> {
+        let mut crash = Self::get_by_id(db, id).await?;
+        Self::redaction_rules(db, crash.product_id, user_id, is_admin)
+            .await?
+            .apply(&mut crash.report);
+        Ok(crash)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn find_by_minidump_hash<C: ConnectionTrait>(
+        db: &C,
+        product_id: uuid::Uuid,
+        hash: &str,
+    ) -> Result<Option<uuid::Uuid>, DbErr> {
+        crate::entity::prelude::Crash::find()
+            .select_only()
+            .column(crate::entity::crash::Column::Id)
+            .filter(crate::entity::crash::Column::ProductId.eq(product_id))
+            .filter(crate::entity::crash::Column::MinidumpHash.eq(hash))
+            .into_tuple::<uuid::Uuid>()
+            .one(db)
+            .await
+    }
+
+    /// Returns the crash of the product with the minidump hash, if any, within the transaction
+    /// that stores a crash with that hash. The crash table is partitioned on PostgreSQL, where
+    /// the index on `(product_id, minidump_hash)` cannot be unique, so the submissions of a
+    /// minidump are serialized with an advisory lock until the transaction ends. Elsewhere, the
+    /// unique index decides between concurrent submissions.
+    async fn find_duplicate(
+        txn: &DatabaseTransaction,
+        product_id: uuid::Uuid,
+        hash: Option<&str>,
+    ) -> Result<Option<uuid::Uuid>, DbErr> {
+        let Some(hash) = hash else {
+            return Ok(None);
+        };
+        if txn.get_database_backend() != DbBackend::Postgres {
+            return Ok(None);
+        }
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+            [format!("{}/{}", product_id, hash).into()],
+        ))
+        .await?;
+        Self::find_by_minidump_hash(txn, product_id, hash).await
+    }
+
+    /// Returns the active model of a new crash. A crash received from a client is dated by the
+    /// time the server received it, so that the statistics, partitions and retention of crashes
+    /// all use the server clock, also when storing the crash was delayed.
+    fn new_crash(crash: CrashCreateDto) -> crate::entity::crash::ActiveModel {
+        let received_at = crash.received_at;
+        let mut crash = crash.into_active_model();
+        if let Some(received_at) = received_at {
+            crash.created_at = Set(received_at);
+        }
+        crash
+    }
+
+    /// Stores a crash unless the same minidump was already submitted for the product. Returns the
+    /// id of the stored crash and whether it was created by this call. See `find_duplicate` for
+    /// how concurrent submissions are decided.
+    #[instrument(skip_all)]
+    pub async fn create_unique(
+        db: &DbConn,
+        crash: CrashCreateDto,
+    ) -> Result<(uuid::Uuid, bool), DbErr> {
+        let product_id = crash.product_id;
+        let hash = crash.minidump_hash.clone();
+        let txn = db.begin().await?;
+        if let Some(id) = Self::find_duplicate(&txn, product_id, hash.as_deref()).await? {
+            return Ok((id, false));
+        }
+        match Self::new_crash(crash).insert(&txn).await {
+            Ok(model) => {
+                txn.commit().await?;
+                Ok((model.id, true))
+            }
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                txn.rollback().await?;
+                let Some(hash) = hash else {
+                    return Err(e);
+                };
+                let id = Self::find_by_minidump_hash(db, product_id, &hash)
+                    .await?
+                    .ok_or(e)?;
+                Ok((id, false))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like `create_unique`, but also creates the version of the crash unless the product already
+    /// has a version of that name, in the same transaction, so that no version is left without
+    /// the crash it was created for.
+    #[instrument(skip_all)]
+    pub async fn create_unique_with_version(
+        db: &DbConn,
+        mut crash: CrashCreateDto,
+        version: &crate::entity::version::Model,
+    ) -> Result<(uuid::Uuid, bool), DbErr> {
+        let product_id = crash.product_id;
+        let hash = crash.minidump_hash.clone();
+        let txn = db.begin().await?;
+        if let Some(id) = Self::find_duplicate(&txn, product_id, hash.as_deref()).await? {
+            return Ok((id, false));
+        }
+        let existing = crate::entity::prelude::Version::find()
+            .filter(crate::entity::version::Column::ProductId.eq(version.product_id))
+            .filter(crate::entity::version::Column::Name.eq(version.name.clone()))
+            .one(&txn)
+            .await?;
+        crash.version_id = match existing {
+            Some(existing) => existing.id,
+            None => {
+                let version = crate::entity::version::ActiveModel {
+                    id: Set(version.id),
+                    name: Set(version.name.clone()),
+                    hash: Set(version.hash.clone()),
+                    tag: Set(version.tag.clone()),
+                    product_id: Set(version.product_id),
+                    channel: Set(version.channel.clone()),
+                    release_date: Set(version.release_date),
+                    created_at: Set(version.created_at),
+                    updated_at: Set(version.updated_at),
+                };
+                version.insert(&txn).await?.id
+            }
+        };
+
+        match Self::new_crash(crash).insert(&txn).await {
+            Ok(model) => {
+                txn.commit().await?;
+                Ok((model.id, true))
+            }
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                txn.rollback().await?;
+                let Some(hash) = hash else {
+                    return Err(e);
+                };
+                let id = Self::find_by_minidump_hash(db, product_id, &hash)
+                    .await?
+                    .ok_or(e)?;
+                Ok((id, false))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Takes over the claim of a crash whose minidump was never processed. A claim is abandoned
+    /// when the crash has no report and was not updated since `stale_before`. Returns whether the
+    /// claim was taken over; of concurrent callers, only one succeeds.
+    #[instrument(skip_all)]
+    pub async fn reclaim(
+        db: &DbConn,
+        id: uuid::Uuid,
+        stale_before: NaiveDateTime,
+    ) -> Result<bool, DbErr> {
+        let Some(crash) = crate::entity::prelude::Crash::find_by_id(id)
+            .one(db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        if !crash.report.is_null() || crash.updated_at >= stale_before {
+            return Ok(false);
+        }
+        let result = crate::entity::prelude::Crash::update_many()
+            .col_expr(
+                crate::entity::crash::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(crate::entity::crash::Column::Id.eq(id))
+            .filter(crate::entity::crash::Column::UpdatedAt.eq(crash.updated_at))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Records the processing status of a crash, with the error of a failed crash.
+    #[instrument(skip_all)]
+    pub async fn set_processing_status(
+        db: &DbConn,
+        id: uuid::Uuid,
+        status: &str,
+        error: Option<String>,
+    ) -> Result<(), DbErr> {
+        crate::entity::prelude::Crash::update_many()
+            .col_expr(
+                crate::entity::crash::Column::ProcessingStatus,
+                sea_query::Expr::value(status),
+            )
+            .col_expr(
+                crate::entity::crash::Column::ProcessingError,
+                sea_query::Expr::value(error),
+            )
+            .filter(crate::entity::crash::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Places a crash on legal hold, with the reason and the user placing it, or releases it
+    /// with `None`. Crashes on hold and their attachments are exempt from all deletion.
+    #[instrument(skip_all)]
+    pub async fn set_legal_hold(
+        db: &DbConn,
+        id: uuid::Uuid,
+        hold: Option<(String, uuid::Uuid)>,
+    ) -> Result<crate::entity::crash::Model, DbErr> {
+        let crash = crate::entity::prelude::Crash::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("crash not found".to_owned()))?;
+        let mut crash = crash.into_active_model();
+        let (at, reason, by) = match hold {
+            Some((reason, by)) => (Some(chrono::Utc::now().naive_utc()), Some(reason), Some(by)),
+            None => (None, None, None),
+        };
+        crash.legal_hold_at = Set(at);
+        crash.legal_hold_reason = Set(reason);
+        crash.legal_hold_by = Set(by);
+        crash.update(db).await
+    }
+
+    /// Counts the crashes on legal hold for which `column` equals `id`, e.g. the held crashes of
+    /// a version before deleting it.
+    #[instrument(skip_all)]
+    pub async fn count_held(
+        db: &DbConn,
+        column: crate::entity::crash::Column,
+        id: uuid::Uuid,
+    ) -> Result<u64, DbErr> {
+        crate::entity::prelude::Crash::find()
+            .filter(column.eq(id))
+            .filter(crate::entity::crash::Column::LegalHoldAt.is_not_null())
+            .count(db)
+            .await
+    }
+
+    /// Stores the signature of up to `limit` crashes after `after` in the `signature` column.
+    /// Returns the last crash visited, to continue with, or `None` when no crashes are left.
+    /// Crashes without signature stay empty.
+    #[instrument(skip_all)]
+    pub async fn backfill_signatures(
+        db: &DbConn,
+        after: Option<uuid::Uuid>,
+        limit: u64,
+    ) -> Result<Option<uuid::Uuid>, DbErr> {
+        let mut query = crate::entity::prelude::Crash::find()
+            .filter(crate::entity::crash::Column::Signature.is_null())
+            .order_by_asc(crate::entity::crash::Column::Id)
+            .limit(limit);
+        if let Some(after) = after {
+            query = query.filter(crate::entity::crash::Column::Id.gt(after));
+        }
+        let crashes = query.all(db).await?;
+        let last = crashes.last().map(|crash| crash.id);
+        for crash in crashes {
+            let Some(signature) = CrashInfo::from_report(&crash.report)
+                .ok()
+                .and_then(|info| info.signature())
+            else {
+                continue;
+            };
+            crate::entity::prelude::Crash::update_many()
+                .col_expr(
+                    crate::entity::crash::Column::Signature,
+                    sea_query::Expr::value(signature),
+                )
+                .filter(crate::entity::crash::Column::Id.eq(crash.id))
+                .filter(crate::entity::crash::Column::Signature.is_null())
+                .exec(db)
+                .await?;
+        }
+        Ok(last)
+    }
 }
 #[cfg(test)]
 mod tests {
-    use crate::{entity::sea_orm_active_enums::AnnotationKind, model::crash::CrashRepo};
+    use crate::entity::sea_orm_active_enums::AnnotationKind;
+    use crate::model::crash::{CrashRepo, STATUS_DONE, STATUS_FAILED};
     use serial_test::serial;
 
     use migration::{Migrator, MigratorTrait};
-    use sea_orm::{Database, DatabaseConnection};
+    use sea_orm::{Database, DatabaseConnection, EntityTrait};
 
     use crate::model::base::Repo;
+    use crate::model::role::{RoleRepo, ADMIN, VIEWER};
+    use crate::model::user::UserCreateDto;
 
     #[serial]
     #[tokio::test]
@@ -90,9 +443,7 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product = crate::entity::product::CreateModel {
-            name: "Workrave".to_owned(),
-        };
+        let product = crate::entity::product::CreateModel::named("Workrave");
         let idp = Repo::create(&db, product).await.unwrap();
 
         let version = crate::entity::version::CreateModel {
@@ -100,6 +451,8 @@ mod tests {
             hash: "test_hash1".to_owned(),
             tag: "test_tag1".to_owned(),
             product_id: idp,
+            channel: None,
+            release_date: None,
         };
         let idv = Repo::create(&db, version).await.unwrap();
 
@@ -108,6 +461,23 @@ mod tests {
             summary: "test_summary1".to_owned(),
             version_id: idv,
             product_id: idp,
+            user_agent: None,
+            sdk: None,
+            promoted: None,
+            minidump_hash: None,
+            platform: None,
+            environment: None,
+            issue_id: None,
+            authenticated: true,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: None,
+            signature: None,
+            processing_status: "done".to_owned(),
+            processing_error: None,
+            received_at: None,
+            clock_skew: None,
         };
         let idc = Repo::create(&db, crash).await.unwrap();
 
@@ -117,6 +487,8 @@ mod tests {
             size: 1,
             filename: "test_filename1".to_owned(),
             crash_id: idc,
+            compression: None,
+            processed_at: None,
         };
         let idat1 = Repo::create(&db, attachment1).await.unwrap();
 
@@ -126,6 +498,8 @@ mod tests {
             size: 2,
             filename: "test_filename2".to_owned(),
             crash_id: idc,
+            compression: None,
+            processed_at: None,
         };
         let idat2 = Repo::create(&db, attachment2).await.unwrap();
 
@@ -168,5 +542,313 @@ mod tests {
         assert_eq!(c.attachments[1].size, 2);
         assert_eq!(c.attachments[1].filename, "test_filename2");
         assert_eq!(c.attachments[1].crash_id, idc);
+        assert_eq!(c.processing_status, STATUS_DONE);
+        assert_eq!(c.processing_error, None);
+
+        CrashRepo::set_processing_status(&db, idc, STATUS_FAILED, Some("timeout".to_owned()))
+            .await
+            .unwrap();
+        let c = CrashRepo::get_by_id(&db, idc).await.unwrap();
+        assert_eq!(c.processing_status, STATUS_FAILED);
+        assert_eq!(c.processing_error.as_deref(), Some("timeout"));
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_unique_with_version() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product = crate::entity::product::CreateModel {
+            auto_create_versions: true,
+            ..crate::entity::product::CreateModel::named("Workrave")
+        };
+        let idp = Repo::create(&db, product).await.unwrap();
+
+        let now = chrono::Utc::now().naive_utc();
+        let version = |id| crate::entity::version::Model {
+            id,
+            created_at: now,
+            updated_at: now,
+            name: "1.12.0".to_owned(),
+            hash: "abc123".to_owned(),
+            tag: "v1.12.0".to_owned(),
+            product_id: idp,
+            channel: None,
+            release_date: None,
+        };
+        let crash = |minidump_hash: &str| crate::entity::crash::CreateModel {
+            report: serde_json::Value::Null,
+            summary: "".to_owned(),
+            version_id: uuid::Uuid::nil(),
+            product_id: idp,
+            user_agent: None,
+            sdk: None,
+            promoted: None,
+            minidump_hash: Some(minidump_hash.to_owned()),
+            platform: None,
+            environment: None,
+            issue_id: None,
+            authenticated: true,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: None,
+            signature: None,
+            processing_status: "done".to_owned(),
+            processing_error: None,
+            received_at: None,
+            clock_skew: None,
+        };
+
+        let first_version = version(uuid::Uuid::new_v4());
+        let (first, created) =
+            CrashRepo::create_unique_with_version(&db, crash("abc"), &first_version)
+                .await
+                .unwrap();
+        assert!(created);
+
+        // A version created concurrently under another id is reused.
+        let (second, created) = CrashRepo::create_unique_with_version(
+            &db,
+            crash("def"),
+            &version(uuid::Uuid::new_v4()),
+        )
+        .await
+        .unwrap();
+        assert!(created);
+        let (duplicate, created) =
+            CrashRepo::create_unique_with_version(&db, crash("abc"), &first_version)
+                .await
+                .unwrap();
+        assert!(!created);
+        assert_eq!(duplicate, first);
+
+        let versions = crate::entity::prelude::Version::find()
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].id, first_version.id);
+        assert_eq!(versions[0].tag, "v1.12.0");
+        for id in [first, second] {
+            let crash = crate::entity::prelude::Crash::find_by_id(id)
+                .one(&db)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(crash.version_id, first_version.id);
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_unique() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product = crate::entity::product::CreateModel::named("Workrave");
+        let idp = Repo::create(&db, product).await.unwrap();
+
+        let version = crate::entity::version::CreateModel {
+            name: "1.0.0".to_owned(),
+            hash: "test_hash1".to_owned(),
+            tag: "test_tag1".to_owned(),
+            product_id: idp,
+            channel: None,
+            release_date: None,
+        };
+        let idv = Repo::create(&db, version).await.unwrap();
+
+        let crash = |minidump_hash: Option<&str>| crate::entity::crash::CreateModel {
+            report: serde_json::Value::Null,
+            summary: "".to_owned(),
+            version_id: idv,
+            product_id: idp,
+            user_agent: None,
+            sdk: None,
+            promoted: None,
+            minidump_hash: minidump_hash.map(str::to_owned),
+            platform: None,
+            environment: None,
+            issue_id: None,
+            authenticated: true,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: None,
+            signature: None,
+            processing_status: "done".to_owned(),
+            processing_error: None,
+            received_at: None,
+            clock_skew: None,
+        };
+
+        let (first, created) = CrashRepo::create_unique(&db, crash(Some("abc")))
+            .await
+            .unwrap();
+        assert!(created);
+        let (second, created) = CrashRepo::create_unique(&db, crash(Some("abc")))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(first, second);
+
+        let (other, created) = CrashRepo::create_unique(&db, crash(Some("def")))
+            .await
+            .unwrap();
+        assert!(created);
+        assert_ne!(first, other);
+
+        let (a, _) = CrashRepo::create_unique(&db, crash(None)).await.unwrap();
+        let (b, _) = CrashRepo::create_unique(&db, crash(None)).await.unwrap();
+        assert_ne!(a, b);
+
+        // Received crashes are dated by the receive time.
+        let received_at = chrono::Utc::now().naive_utc() - chrono::Duration::minutes(5);
+        let (id, _) = CrashRepo::create_unique(
+            &db,
+            crate::entity::crash::CreateModel {
+                received_at: Some(received_at),
+                clock_skew: Some(-30),
+                ..crash(Some("ghi"))
+            },
+        )
+        .await
+        .unwrap();
+        let received = CrashRepo::get_by_id(&db, id).await.unwrap();
+        assert_eq!(received.created_at, received_at);
+        assert_eq!(received.received_at, Some(received_at));
+        assert_eq!(received.clock_skew, Some(-30));
+
+        // Only claims without report that were not updated recently can be taken over.
+        let now = chrono::Utc::now().naive_utc();
+        let hour = chrono::Duration::hours(1);
+        assert!(!CrashRepo::reclaim(&db, first, now - hour).await.unwrap());
+        assert!(CrashRepo::reclaim(&db, first, now + hour).await.unwrap());
+
+        let processed = Repo::create(
+            &db,
+            crate::entity::crash::CreateModel {
+                report: serde_json::json!({ "status": "OK" }),
+                ..crash(None)
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!CrashRepo::reclaim(&db, processed, now + hour)
+            .await
+            .unwrap());
+
+        let user = uuid::Uuid::new_v4();
+        let held = CrashRepo::set_legal_hold(&db, processed, Some(("litigation".to_owned(), user)))
+            .await
+            .unwrap();
+        assert!(held.legal_hold_at.is_some());
+        assert_eq!(held.legal_hold_by, Some(user));
+        assert_eq!(held.legal_hold_reason.as_deref(), Some("litigation"));
+        let column = crate::entity::crash::Column::ProductId;
+        assert_eq!(CrashRepo::count_held(&db, column, idp).await.unwrap(), 1);
+
+        let released = CrashRepo::set_legal_hold(&db, processed, None)
+            .await
+            .unwrap();
+        assert!(released.legal_hold_at.is_none());
+        assert!(released.legal_hold_by.is_none());
+        assert_eq!(CrashRepo::count_held(&db, column, idp).await.unwrap(), 0);
+
+        let signed = Repo::create(
+            &db,
+            crate::entity::crash::CreateModel {
+                report: serde_json::json!({ "crashing_thread": { "frames": [
+                    { "module": "workrave.exe", "function": "Timer::tick" }
+                ]}}),
+                ..crash(None)
+            },
+        )
+        .await
+        .unwrap();
+        let mut after = None;
+        while let Some(last) = CrashRepo::backfill_signatures(&db, after, 2).await.unwrap() {
+            after = Some(last);
+        }
+        let crashes = crate::entity::prelude::Crash::find()
+            .all(&db)
+            .await
+            .unwrap();
+        for crash in crashes {
+            let expected = (crash.id == signed).then(|| "Timer::tick".to_owned());
+            assert_eq!(crash.signature, expected);
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_for_user() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product = crate::entity::product::CreateModel {
+            redacted_fields: Some("crash_info.address".to_owned()),
+            ..crate::entity::product::CreateModel::named("Workrave")
+        };
+        let idp = Repo::create(&db, product).await.unwrap();
+        let version = crate::entity::version::CreateModel {
+            name: "1.0.0".to_owned(),
+            hash: "test_hash1".to_owned(),
+            tag: "test_tag1".to_owned(),
+            product_id: idp,
+            channel: None,
+            release_date: None,
+        };
+        let idv = Repo::create(&db, version).await.unwrap();
+        let crash = crate::entity::crash::CreateModel {
+            report: serde_json::json!({ "crash_info": { "address": "0x0" } }),
+            summary: "test_summary1".to_owned(),
+            version_id: idv,
+            product_id: idp,
+            user_agent: None,
+            sdk: None,
+            promoted: None,
+            minidump_hash: None,
+            platform: None,
+            environment: None,
+            issue_id: None,
+            authenticated: true,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: None,
+            signature: None,
+            processing_status: "done".to_owned(),
+            processing_error: None,
+            received_at: None,
+            clock_skew: None,
+        };
+        let idc = Repo::create(&db, crash).await.unwrap();
+
+        let mut users = vec![];
+        for username in ["viewer", "owner"] {
+            let user = UserCreateDto {
+                username: username.to_owned(),
+                is_admin: false,
+                last_authenticated: None,
+                deactivated_at: None,
+            };
+            users.push(Repo::create(&db, user).await.unwrap());
+        }
+        RoleRepo::assign(&db, users[0], idp, VIEWER).await.unwrap();
+        RoleRepo::assign(&db, users[1], idp, ADMIN).await.unwrap();
+
+        let redacted = CrashRepo::get_for_user(&db, idc, users[0], false)
+            .await
+            .unwrap();
+        assert_eq!(redacted.report["crash_info"]["address"], "[redacted]");
+        for (user, is_admin) in [(users[1], false), (users[0], true)] {
+            let crash = CrashRepo::get_for_user(&db, idc, user, is_admin)
+                .await
+                .unwrap();
+            assert_eq!(crash.report["crash_info"]["address"], "0x0");
+        }
     }
 }
@@ -0,0 +1,227 @@
+use sea_orm::*;
+use std::path::PathBuf;
+use thiserror::Error;
+use tracing::{error, info, instrument};
+
+use super::product::RedactionRules;
+use crate::entity;
+use crate::pdf::{PdfWriter, Style};
+use crate::report::CrashInfo;
+use crate::settings::settings;
+
+/// Limits that keep the PDF of crashes with deep stacks or many modules readable.
+const MAX_FRAMES: usize = 50;
+const MAX_MODULES: usize = 200;
+
+#[derive(Error, Debug)]
+pub enum CrashPdfError {
+    #[error("crash not found")]
+    NotFound,
+
+    #[error("database error: {0}")]
+    Database(#[from] DbErr),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// PDF summary of a crash, for attaching to support tickets. PDFs are rendered in the
+/// background and stored under `reports` in the base path. A PDF is shared by everyone who may
+/// view the crash, so it is rendered with the redaction rules of the product applied.
+pub struct CrashPdf;
+
+impl CrashPdf {
+    pub fn path(crash_id: uuid::Uuid) -> PathBuf {
+        std::path::Path::new(&settings().server.base_path)
+            .join("reports")
+            .join(format!("{}.pdf", crash_id))
+    }
+
+    pub fn is_ready(crash_id: uuid::Uuid) -> bool {
+        Self::path(crash_id).is_file()
+    }
+
+    /// Returns the top frame of the crashing thread, or the crash summary if the report has no
+    /// usable stack.
+    pub fn signature(crash: &entity::crash::Model, info: &CrashInfo) -> String {
+        info.signature().unwrap_or_else(|| crash.summary.clone())
+    }
+
+    pub fn render(crash: &entity::crash::Model, product: &str, version: &str) -> Vec<u8> {
+        let info = CrashInfo::from_report(&crash.report).unwrap_or_default();
+        let details = info.crash_info.clone().unwrap_or_default();
+
+        let mut pdf = PdfWriter::new();
+        pdf.text(Style::Title, &format!("Crash {}", crash.id));
+        pdf.text(Style::Text, &Self::signature(crash, &info));
+        pdf.space(10.0);
+
+        pdf.text(Style::Heading, "Metadata");
+        let metadata = [
+            ("Product", product.to_owned()),
+            ("Version", version.to_owned()),
+            (
+                "Received",
+                crash.created_at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            ),
+            ("Summary", crash.summary.clone()),
+            ("Crash type", details.crash_type.unwrap_or_default()),
+            ("Crash address", details.address.unwrap_or_default()),
+            ("Status", info.status.clone().unwrap_or_default()),
+            ("SDK", crash.sdk.clone().unwrap_or_default()),
+            ("User agent", crash.user_agent.clone().unwrap_or_default()),
+        ];
+        for (name, value) in metadata {
+            if !value.is_empty() {
+                pdf.text(Style::Text, &format!("{}: {}", name, value));
+            }
+        }
+        pdf.space(10.0);
+
+        pdf.text(Style::Heading, "Crashing thread");
+        let frames = info
+            .crashing_thread
+            .as_ref()
+            .map(|thread| thread.frames.as_slice())
+            .unwrap_or_default();
+        if frames.is_empty() {
+            pdf.text(Style::Text, "No stack available");
+        }
+        for (i, frame) in frames.iter().take(MAX_FRAMES).enumerate() {
+            pdf.text(Style::Mono, &format!("{:>3} {}", i, frame.describe()));
+        }
+        if frames.len() > MAX_FRAMES {
+            pdf.text(
+                Style::Text,
+                &format!("{} more frames", frames.len() - MAX_FRAMES),
+            );
+        }
+        pdf.space(10.0);
+
+        pdf.text(Style::Heading, "Modules");
+        for module in info.modules.iter().take(MAX_MODULES) {
+            pdf.text(
+                Style::Mono,
+                &format!(
+                    "{} {} {}",
+                    module.filename,
+                    module.version.as_deref().unwrap_or("-"),
+                    module.debug_id.as_deref().unwrap_or("-")
+                ),
+            );
+        }
+        if info.modules.len() > MAX_MODULES {
+            pdf.text(
+                Style::Text,
+                &format!("{} more modules", info.modules.len() - MAX_MODULES),
+            );
+        }
+        pdf.finish()
+    }
+
+    #[instrument(skip_all)]
+    pub async fn generate(
+        db: &DatabaseConnection,
+        crash_id: uuid::Uuid,
+    ) -> Result<PathBuf, CrashPdfError> {
+        let mut crash = entity::prelude::Crash::find_by_id(crash_id)
+            .one(db)
+            .await?
+            .ok_or(CrashPdfError::NotFound)?;
+        let product = entity::prelude::Product::find_by_id(crash.product_id)
+            .one(db)
+            .await?;
+        if let Some(product) = &product {
+            RedactionRules::from(product).apply(&mut crash.report);
+        }
+        let product = product.map(|product| product.name).unwrap_or_default();
+        let version = entity::prelude::Version::find_by_id(crash.version_id)
+            .one(db)
+            .await?
+            .map(|version| version.name)
+            .unwrap_or_default();
+
+        let pdf = Self::render(&crash, &product, &version);
+
+        // Write to a temporary file first, so that a download never sees a partial PDF.
+        let path = Self::path(crash_id);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("pdf.tmp");
+        tokio::fs::write(&tmp, pdf).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(path)
+    }
+
+    /// Renders the PDF in the background; it can be downloaded once `is_ready` returns true.
+    pub fn spawn(db: DatabaseConnection, crash_id: uuid::Uuid) {
+        tokio::spawn(async move {
+            match Self::generate(&db, crash_id).await {
+                Ok(path) => info!("rendered crash report {:?}", path),
+                Err(e) => error!("failed to render crash report of {}: {}", crash_id, e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let crash = entity::crash::Model {
+            id: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: chrono::Utc::now().naive_utc(),
+            summary: "EXCEPTION_ACCESS_VIOLATION_READ".to_owned(),
+            report: json!({
+                "crash_info": { "type": "EXCEPTION_ACCESS_VIOLATION_READ", "address": "0x0" },
+                "crashing_thread": { "frames": [
+                    { "module": "workrave.exe", "function": "Timer::tick", "file": "timer.cc", "line": 42 },
+                    { "module": "workrave.exe", "function": "main" }
+                ]},
+                "modules": [{ "filename": "workrave.exe", "version": "1.11.0" }],
+            }),
+            version_id: uuid::Uuid::new_v4(),
+            product_id: uuid::Uuid::new_v4(),
+            user_agent: None,
+            sdk: Some("crashpad".to_owned()),
+            promoted: None,
+            minidump_hash: None,
+            platform: None,
+            environment: None,
+            issue_id: None,
+            authenticated: true,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: None,
+            signature: None,
+            processing_status: "done".to_owned(),
+            processing_error: None,
+            received_at: None,
+            clock_skew: None,
+        };
+        let info = CrashInfo::from_report(&crash.report).unwrap();
+        assert_eq!(CrashPdf::signature(&crash, &info), "Timer::tick");
+
+        let pdf = String::from_utf8(CrashPdf::render(&crash, "Workrave", "1.11.0")).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains("(Product: Workrave) Tj"));
+        assert!(pdf.contains("workrave.exe!Timer::tick [timer.cc:42]"));
+        assert!(pdf.contains("(workrave.exe 1.11.0 -) Tj"));
+
+        let empty = entity::crash::Model {
+            report: serde_json::Value::Null,
+            ..crash
+        };
+        let info = CrashInfo::from_report(&empty.report).unwrap_or_default();
+        assert_eq!(
+            CrashPdf::signature(&empty, &info),
+            "EXCEPTION_ACCESS_VIOLATION_READ"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use super::api_token::hash_token;
+use super::base::{HasId, Repo};
+use crate::entity;
+use sea_orm::*;
+use tracing::instrument;
+
+pub type DeviceKey = entity::device_key::Model;
+pub type DeviceKeyCreateDto = entity::device_key::CreateModel;
+pub type DeviceKeyUpdateDto = entity::device_key::UpdateModel;
+
+/// Allows creating and editing the versions of the key's product.
+pub const VERSION_MANAGE: &str = "version-manage";
+
+impl HasId for entity::device_key::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+impl entity::device_key::Model {
+    /// Returns whether the key was granted the entitlement, in addition to uploading crashes.
+    pub fn has_entitlement(&self, entitlement: &str) -> bool {
+        self.entitlements
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .any(|e| e.trim() == entitlement)
+    }
+}
+
+pub struct DeviceKeyRepo;
+impl DeviceKeyRepo {
+    /// Creates a device key with a random key. The key itself is only returned here; the database
+    /// only holds its SHA-256 digest, which is also the HMAC key of signed requests.
+    #[instrument(skip_all)]
+    pub async fn create(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        name: String,
+    ) -> Result<(uuid::Uuid, String), DbErr> {
+        let key = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let dto = DeviceKeyCreateDto {
+            name,
+            key_hash: hash_token(&key),
+            product_id,
+            entitlements: None,
+            submission_count: 0,
+            last_used_at: None,
+        };
+        let id = Repo::create(db, dto).await?;
+        Ok((id, key))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_by_product(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+    ) -> Result<Vec<DeviceKey>, DbErr> {
+        entity::prelude::DeviceKey::find()
+            .filter(entity::device_key::Column::ProductId.eq(product_id))
+            .order_by_asc(entity::device_key::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Adds submissions to the count of the key and moves its last use forward. Submissions are
+    /// recorded in batches, so `last_used_at` is the time of the last submission of the batch.
+    #[instrument(skip_all)]
+    pub async fn record_submissions(
+        db: &DbConn,
+        id: uuid::Uuid,
+        submissions: i64,
+        last_used_at: chrono::NaiveDateTime,
+    ) -> Result<(), DbErr> {
+        entity::prelude::DeviceKey::update_many()
+            .col_expr(
+                entity::device_key::Column::SubmissionCount,
+                sea_query::Expr::col(entity::device_key::Column::SubmissionCount).add(submissions),
+            )
+            .col_expr(
+                entity::device_key::Column::LastUsedAt,
+                sea_query::Expr::value(last_used_at),
+            )
+            .filter(entity::device_key::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    async fn create_product(db: &DbConn) -> uuid::Uuid {
+        Repo::create(db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap()
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = create_product(&db).await;
+        let (id, key) = DeviceKeyRepo::create(&db, product_id, "default".to_owned())
+            .await
+            .unwrap();
+
+        let keys = DeviceKeyRepo::get_by_product(&db, product_id)
+            .await
+            .unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].id, id);
+        assert_eq!(keys[0].key_hash, hash_token(&key));
+        assert_ne!(keys[0].key_hash, key);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record_submissions() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = create_product(&db).await;
+        let id = Repo::create(
+            &db,
+            DeviceKeyCreateDto {
+                name: "ci".to_owned(),
+                key_hash: "hash".to_owned(),
+                product_id,
+                entitlements: None,
+                submission_count: 0,
+                last_used_at: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let first = chrono::NaiveDate::from_ymd_opt(2024, 12, 15)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        DeviceKeyRepo::record_submissions(&db, id, 3, first)
+            .await
+            .unwrap();
+        let last = first + chrono::Duration::seconds(30);
+        DeviceKeyRepo::record_submissions(&db, id, 2, last)
+            .await
+            .unwrap();
+
+        let keys = DeviceKeyRepo::get_by_product(&db, product_id)
+            .await
+            .unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].submission_count, 5);
+        assert_eq!(keys[0].last_used_at, Some(last));
+    }
+}
@@ -0,0 +1,536 @@
+use sea_orm::*;
+use std::collections::HashMap;
+use tracing::instrument;
+
+use super::base::{HasId, Repo};
+use crate::data_providers::issue::{ISSUE_MUTED, ISSUE_OPEN, ISSUE_RESOLVED, ISSUE_SNOOZED};
+use crate::entity;
+
+pub type Issue = entity::issue::Model;
+pub type IssueCreateDto = entity::issue::CreateModel;
+pub type IssueUpdateDto = entity::issue::UpdateModel;
+
+impl HasId for entity::issue::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// What recording a crash did to its issue, see `IssueRepo::record`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueChange {
+    /// The crash is the first of a new issue.
+    Created,
+    /// The issue was resolved, and was opened again by the crash.
+    Reopened,
+    /// The issue existed and keeps its status.
+    Unchanged,
+}
+
+pub struct IssueRepo;
+impl IssueRepo {
+    #[instrument(skip_all)]
+    pub async fn find_by_signature(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        signature: &str,
+    ) -> Result<Option<Issue>, DbErr> {
+        entity::prelude::Issue::find()
+            .filter(entity::issue::Column::ProductId.eq(product_id))
+            .filter(entity::issue::Column::Signature.eq(signature))
+            .one(db)
+            .await
+    }
+
+    /// Returns the issue of the signature, creating it on its first crash, which it counts.
+    /// Returns the id of the issue and what the crash changed. A resolved issue, whether by hand
+    /// or by `auto_resolve`, is opened again, so that a crash that returns is not hidden. The
+    /// unique index on `(product_id, signature)` decides between concurrent crashes. Later
+    /// crashes are not counted here, as counting every crash would contend on the issue; they
+    /// are added in batches with `add_crashes`.
+    #[instrument(skip_all)]
+    pub async fn record(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        signature: &str,
+        title: &str,
+    ) -> Result<(uuid::Uuid, IssueChange), DbErr> {
+        if let Some(issue) = Self::find_by_signature(db, product_id, signature).await? {
+            return Ok((issue.id, Self::reopen(db, &issue).await?));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let dto = IssueCreateDto {
+            signature: signature.to_owned(),
+            title: title.to_owned(),
+            first_seen: now,
+            last_seen: now,
+            count: 1,
+            product_id,
+            status: ISSUE_OPEN.to_owned(),
+            assignee_id: None,
+            snoozed_until: None,
+            auto_resolve_days: None,
+        };
+        match Repo::create(db, dto).await {
+            Ok(id) => Ok((id, IssueChange::Created)),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                let issue = Self::find_by_signature(db, product_id, signature)
+                    .await?
+                    .ok_or(e)?;
+                Ok((issue.id, Self::reopen(db, &issue).await?))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Opens the issue again if it is resolved. Only one of concurrent crashes reopens it.
+    async fn reopen(db: &DbConn, issue: &Issue) -> Result<IssueChange, DbErr> {
+        if issue.status != ISSUE_RESOLVED {
+            return Ok(IssueChange::Unchanged);
+        }
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Status,
+                sea_query::Expr::value(ISSUE_OPEN),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.eq(issue.id))
+            .filter(entity::issue::Column::Status.eq(ISSUE_RESOLVED))
+            .exec(db)
+            .await?;
+        Ok(match result.rows_affected {
+            0 => IssueChange::Unchanged,
+            _ => IssueChange::Reopened,
+        })
+    }
+
+    /// Adds `crashes` to the count of the issue, and moves its last seen time forward to
+    /// `last_seen`.
+    #[instrument(skip_all)]
+    pub async fn add_crashes<C: ConnectionTrait>(
+        db: &C,
+        id: uuid::Uuid,
+        crashes: i64,
+        last_seen: chrono::NaiveDateTime,
+    ) -> Result<(), DbErr> {
+        entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Count,
+                sea_query::Expr::col(entity::issue::Column::Count).add(crashes),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::LastSeen,
+                sea_query::Expr::value(last_seen),
+            )
+            .filter(entity::issue::Column::Id.eq(id))
+            .filter(entity::issue::Column::LastSeen.lt(last_seen))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Sets the status of the issues, see `ISSUE_STATUSES`. Use `snooze` to snooze issues.
+    #[instrument(skip_all)]
+    pub async fn set_status(db: &DbConn, ids: Vec<uuid::Uuid>, status: &str) -> Result<u64, DbErr> {
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Status,
+                sea_query::Expr::value(status),
+            )
+            .col_expr(
+                entity::issue::Column::SnoozedUntil,
+                sea_query::Expr::value(Option::<chrono::NaiveDateTime>::None),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Assigns the issues to a user, or clears their assignee.
+    #[instrument(skip_all)]
+    pub async fn set_assignee(
+        db: &DbConn,
+        ids: Vec<uuid::Uuid>,
+        assignee_id: Option<uuid::Uuid>,
+    ) -> Result<u64, DbErr> {
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::AssigneeId,
+                sea_query::Expr::value(assignee_id),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Hides the issues until `until`, after which `wake_snoozed` opens them again.
+    #[instrument(skip_all)]
+    pub async fn snooze(
+        db: &DbConn,
+        ids: Vec<uuid::Uuid>,
+        until: chrono::NaiveDateTime,
+    ) -> Result<u64, DbErr> {
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Status,
+                sea_query::Expr::value(ISSUE_SNOOZED),
+            )
+            .col_expr(
+                entity::issue::Column::SnoozedUntil,
+                sea_query::Expr::value(until),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Resolves the issues automatically once they have had no crashes for `days` days, see
+    /// `auto_resolve`, or stops doing so with `None`.
+    #[instrument(skip_all)]
+    pub async fn set_auto_resolve(
+        db: &DbConn,
+        ids: Vec<uuid::Uuid>,
+        days: Option<i32>,
+    ) -> Result<u64, DbErr> {
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::AutoResolveDays,
+                sea_query::Expr::value(days),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::issue::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Returns whether notifications about crashes of the issue are suppressed at `now`, i.e.
+    /// the issue is muted or snoozed.
+    #[instrument(skip_all)]
+    pub async fn is_silenced(
+        db: &DbConn,
+        id: uuid::Uuid,
+        now: chrono::NaiveDateTime,
+    ) -> Result<bool, DbErr> {
+        let count = entity::prelude::Issue::find()
+            .filter(entity::issue::Column::Id.eq(id))
+            .filter(
+                Condition::any()
+                    .add(entity::issue::Column::Status.eq(ISSUE_MUTED))
+                    .add(
+                        Condition::all()
+                            .add(entity::issue::Column::Status.eq(ISSUE_SNOOZED))
+                            .add(entity::issue::Column::SnoozedUntil.gt(now)),
+                    ),
+            )
+            .count(db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Opens the snoozed issues whose snooze ended before `now`. Returns the number of opened
+    /// issues.
+    #[instrument(skip_all)]
+    pub async fn wake_snoozed(db: &DbConn, now: chrono::NaiveDateTime) -> Result<u64, DbErr> {
+        let result = entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Status,
+                sea_query::Expr::value(ISSUE_OPEN),
+            )
+            .col_expr(
+                entity::issue::Column::SnoozedUntil,
+                sea_query::Expr::value(Option::<chrono::NaiveDateTime>::None),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(now),
+            )
+            .filter(entity::issue::Column::Status.eq(ISSUE_SNOOZED))
+            .filter(entity::issue::Column::SnoozedUntil.lte(now))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Resolves the open issues with auto-resolve that have had no crashes for their number of
+    /// days at `now`. Returns the resolved issues.
+    #[instrument(skip_all)]
+    pub async fn auto_resolve(
+        db: &DbConn,
+        now: chrono::NaiveDateTime,
+    ) -> Result<Vec<uuid::Uuid>, DbErr> {
+        // The number of days differs per issue, which is simpler to compare here than in SQL
+        // that works on both SQLite and PostgreSQL.
+        let candidates = entity::prelude::Issue::find()
+            .select_only()
+            .column(entity::issue::Column::Id)
+            .column(entity::issue::Column::LastSeen)
+            .column(entity::issue::Column::AutoResolveDays)
+            .filter(entity::issue::Column::Status.eq(ISSUE_OPEN))
+            .filter(entity::issue::Column::AutoResolveDays.is_not_null())
+            .into_tuple::<(uuid::Uuid, chrono::NaiveDateTime, i32)>()
+            .all(db)
+            .await?;
+        let ids: Vec<_> = candidates
+            .into_iter()
+            .filter(|(_, last_seen, days)| {
+                *last_seen + chrono::Duration::days(i64::from(*days)) <= now
+            })
+            .map(|(id, _, _)| id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+        entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::Status,
+                sea_query::Expr::value(ISSUE_RESOLVED),
+            )
+            .col_expr(
+                entity::issue::Column::UpdatedAt,
+                sea_query::Expr::value(now),
+            )
+            .filter(entity::issue::Column::Id.is_in(ids.clone()))
+            .filter(entity::issue::Column::Status.eq(ISSUE_OPEN))
+            .exec(db)
+            .await?;
+        Ok(ids)
+    }
+
+    /// Returns the names of the versions with crashes of each issue, sorted by name.
+    #[instrument(skip_all)]
+    pub async fn affected_versions(
+        db: &DbConn,
+        ids: Vec<uuid::Uuid>,
+    ) -> Result<HashMap<uuid::Uuid, Vec<String>>, DbErr> {
+        let rows = entity::prelude::Crash::find()
+            .select_only()
+            .column(entity::crash::Column::IssueId)
+            .column(entity::version::Column::Name)
+            .distinct()
+            .join(JoinType::InnerJoin, entity::crash::Relation::Version.def())
+            .filter(entity::crash::Column::IssueId.is_in(ids))
+            .order_by_asc(entity::version::Column::Name)
+            .into_tuple::<(uuid::Uuid, String)>()
+            .all(db)
+            .await?;
+
+        let mut versions: HashMap<uuid::Uuid, Vec<String>> = HashMap::new();
+        for (issue_id, version) in rows {
+            versions.entry(issue_id).or_default().push(version);
+        }
+        Ok(versions)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn assign(
+        db: &DbConn,
+        crash_id: uuid::Uuid,
+        issue_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        entity::prelude::Crash::update_many()
+            .col_expr(
+                entity::crash::Column::IssueId,
+                sea_query::Expr::value(issue_id),
+            )
+            .filter(entity::crash::Column::Id.eq(crash_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+
+        let (first, change) = IssueRepo::record(&db, product_id, "Timer::tick", "SIGSEGV")
+            .await
+            .unwrap();
+        assert_eq!(change, IssueChange::Created);
+        let (second, change) = IssueRepo::record(&db, product_id, "Timer::tick", "SIGSEGV")
+            .await
+            .unwrap();
+        assert_eq!(change, IssueChange::Unchanged);
+        assert_eq!(first, second);
+        let (other, change) = IssueRepo::record(&db, product_id, "Timer::reset", "SIGABRT")
+            .await
+            .unwrap();
+        assert_eq!(change, IssueChange::Created);
+        assert_ne!(first, other);
+
+        // A crash of a resolved issue opens it again, once.
+        IssueRepo::set_status(&db, vec![other], ISSUE_RESOLVED)
+            .await
+            .unwrap();
+        let (_, change) = IssueRepo::record(&db, product_id, "Timer::reset", "SIGABRT")
+            .await
+            .unwrap();
+        assert_eq!(change, IssueChange::Reopened);
+        let (_, change) = IssueRepo::record(&db, product_id, "Timer::reset", "SIGABRT")
+            .await
+            .unwrap();
+        assert_eq!(change, IssueChange::Unchanged);
+        let issue = IssueRepo::find_by_signature(&db, product_id, "Timer::reset")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.status, ISSUE_OPEN);
+
+        let issue = IssueRepo::find_by_signature(&db, product_id, "Timer::tick")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.count, 1);
+        assert_eq!(issue.title, "SIGSEGV");
+        assert_eq!(issue.last_seen, issue.first_seen);
+
+        // Later crashes are added in batches; the last seen time never moves back.
+        let later = issue.last_seen + chrono::Duration::minutes(5);
+        IssueRepo::add_crashes(&db, first, 2, later).await.unwrap();
+        IssueRepo::add_crashes(&db, first, 1, issue.first_seen)
+            .await
+            .unwrap();
+        let issue = IssueRepo::find_by_signature(&db, product_id, "Timer::tick")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.count, 4);
+        assert_eq!(issue.last_seen, later);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_triage() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let (first, _) = IssueRepo::record(&db, product_id, "Timer::tick", "SIGSEGV")
+            .await
+            .unwrap();
+        let (second, _) = IssueRepo::record(&db, product_id, "Timer::reset", "SIGABRT")
+            .await
+            .unwrap();
+
+        let issue = IssueRepo::find_by_signature(&db, product_id, "Timer::tick")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.status, ISSUE_OPEN);
+        assert_eq!(issue.assignee_id, None);
+
+        let updated = IssueRepo::set_status(&db, vec![first, second], "resolved")
+            .await
+            .unwrap();
+        assert_eq!(updated, 2);
+        let assignee = uuid::Uuid::new_v4();
+        IssueRepo::set_assignee(&db, vec![first], Some(assignee))
+            .await
+            .unwrap();
+
+        let issue = Repo::get_by_id::<entity::issue::Entity>(&db, first)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.status, "resolved");
+        assert_eq!(issue.assignee_id, Some(assignee));
+        let issue = Repo::get_by_id::<entity::issue::Entity>(&db, second)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.assignee_id, None);
+
+        // Snoozing hides the issue until the date, muting until it is opened again.
+        let now = chrono::Utc::now().naive_utc();
+        let day = chrono::Duration::days(1);
+        IssueRepo::snooze(&db, vec![first], now + day)
+            .await
+            .unwrap();
+        IssueRepo::set_status(&db, vec![second], ISSUE_MUTED)
+            .await
+            .unwrap();
+        assert!(IssueRepo::is_silenced(&db, first, now).await.unwrap());
+        assert!(IssueRepo::is_silenced(&db, second, now).await.unwrap());
+        assert!(!IssueRepo::is_silenced(&db, first, now + day * 2)
+            .await
+            .unwrap());
+        assert_eq!(IssueRepo::wake_snoozed(&db, now).await.unwrap(), 0);
+        assert_eq!(
+            IssueRepo::wake_snoozed(&db, now + day * 2).await.unwrap(),
+            1
+        );
+        let issue = Repo::get_by_id::<entity::issue::Entity>(&db, first)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.status, ISSUE_OPEN);
+        assert_eq!(issue.snoozed_until, None);
+
+        // Only open issues are resolved, once they had no crashes for their number of days.
+        IssueRepo::set_auto_resolve(&db, vec![first, second], Some(7))
+            .await
+            .unwrap();
+        assert!(IssueRepo::auto_resolve(&db, now).await.unwrap().is_empty());
+        assert_eq!(
+            IssueRepo::auto_resolve(&db, now + day * 8).await.unwrap(),
+            vec![first]
+        );
+        let issue = Repo::get_by_id::<entity::issue::Entity>(&db, second)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.status, ISSUE_MUTED);
+
+        // Issues without crashes affect no versions.
+        let versions = IssueRepo::affected_versions(&db, vec![first, second])
+            .await
+            .unwrap();
+        assert!(versions.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::entity;
+use sea_orm::*;
+use tracing::instrument;
+
+pub type JobLease = entity::job_lease::Model;
+
+/// Leases let periodic jobs run on one replica at a time. A replica holds the lease of a job
+/// until it expires and renews it each time it runs the job; when the replica stops, another
+/// one takes over once the lease has expired.
+pub struct JobLeaseRepo;
+impl JobLeaseRepo {
+    /// Acquires or renews the lease of the job for `holder`. Returns whether `holder` holds the
+    /// lease.
+    #[instrument(skip_all)]
+    pub async fn acquire(
+        db: &DbConn,
+        job: &str,
+        holder: &str,
+        duration: chrono::Duration,
+    ) -> Result<bool, DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        let expires_at = now + duration;
+
+        // A single conditional update, so that only one of the replicas racing for an expired
+        // lease gets it.
+        let result = entity::prelude::JobLease::update_many()
+            .col_expr(
+                entity::job_lease::Column::Holder,
+                sea_query::Expr::value(holder),
+            )
+            .col_expr(
+                entity::job_lease::Column::ExpiresAt,
+                sea_query::Expr::value(expires_at),
+            )
+            .col_expr(
+                entity::job_lease::Column::UpdatedAt,
+                sea_query::Expr::value(now),
+            )
+            .filter(entity::job_lease::Column::Id.eq(job))
+            .filter(
+                Condition::any()
+                    .add(entity::job_lease::Column::Holder.eq(holder))
+                    .add(entity::job_lease::Column::ExpiresAt.lt(now)),
+            )
+            .exec(db)
+            .await?;
+        if result.rows_affected > 0 {
+            return Ok(true);
+        }
+
+        let lease = entity::job_lease::ActiveModel {
+            id: Set(job.to_owned()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            holder: Set(holder.to_owned()),
+            expires_at: Set(expires_at),
+        };
+        match entity::prelude::JobLease::insert(lease)
+            .exec_without_returning(db)
+            .await
+        {
+            Ok(_) => Ok(true),
+            // The lease exists and is held by another replica.
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gives up the lease, so that another replica can take over without waiting for it to
+    /// expire.
+    #[instrument(skip_all)]
+    pub async fn release(db: &DbConn, job: &str, holder: &str) -> Result<(), DbErr> {
+        entity::prelude::JobLease::delete_many()
+            .filter(entity::job_lease::Column::Id.eq(job))
+            .filter(entity::job_lease::Column::Holder.eq(holder))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::job_lease::JobLeaseRepo;
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_job_lease() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let minute = chrono::Duration::minutes(1);
+
+        assert!(JobLeaseRepo::acquire(&db, "anomaly", "a", minute)
+            .await
+            .unwrap());
+        assert!(!JobLeaseRepo::acquire(&db, "anomaly", "b", minute)
+            .await
+            .unwrap());
+        assert!(JobLeaseRepo::acquire(&db, "anomaly", "a", minute)
+            .await
+            .unwrap());
+        assert!(JobLeaseRepo::acquire(&db, "promotion", "b", minute)
+            .await
+            .unwrap());
+
+        // An expired lease is taken over.
+        assert!(JobLeaseRepo::acquire(&db, "anomaly", "a", -minute)
+            .await
+            .unwrap());
+        assert!(JobLeaseRepo::acquire(&db, "anomaly", "b", minute)
+            .await
+            .unwrap());
+        assert!(!JobLeaseRepo::acquire(&db, "anomaly", "a", minute)
+            .await
+            .unwrap());
+
+        JobLeaseRepo::release(&db, "anomaly", "b").await.unwrap();
+        assert!(JobLeaseRepo::acquire(&db, "anomaly", "a", minute)
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::entity;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::instrument;
+
+pub type JobState = entity::job_state::Model;
+
+/// Periodic jobs keep their progress in the database, so that a restart or another replica
+/// taking over the lease of the job continues where the job stopped instead of starting over.
+/// The state of a job is stored as JSON under a key chosen by the job.
+pub struct JobStateRepo;
+impl JobStateRepo {
+    /// Returns the stored state, or `None` if none has been stored.
+    #[instrument(skip_all)]
+    pub async fn get<T: DeserializeOwned>(db: &DbConn, key: &str) -> Result<Option<T>, DbErr> {
+        let Some(state) = entity::prelude::JobState::find_by_id(key.to_owned())
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        serde_json::from_value(state.state)
+            .map(Some)
+            .map_err(|e| DbErr::Json(e.to_string()))
+    }
+
+    /// Stores the state, replacing the previous one.
+    #[instrument(skip_all)]
+    pub async fn set<T: Serialize>(db: &DbConn, key: &str, state: &T) -> Result<(), DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        let state = entity::job_state::ActiveModel {
+            id: Set(key.to_owned()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            state: Set(serde_json::to_value(state).map_err(|e| DbErr::Json(e.to_string()))?),
+        };
+        entity::prelude::JobState::insert(state)
+            .on_conflict(
+                OnConflict::column(entity::job_state::Column::Id)
+                    .update_columns([
+                        entity::job_state::Column::State,
+                        entity::job_state::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the stored state, so that the job starts over.
+    #[instrument(skip_all)]
+    pub async fn remove(db: &DbConn, key: &str) -> Result<(), DbErr> {
+        entity::prelude::JobState::delete_by_id(key.to_owned())
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::job_state::JobStateRepo;
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_job_state() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        assert_eq!(
+            JobStateRepo::get::<Vec<String>>(&db, "promotion")
+                .await
+                .unwrap(),
+            None
+        );
+        JobStateRepo::set(&db, "promotion", &vec!["a"])
+            .await
+            .unwrap();
+        JobStateRepo::set(&db, "promotion", &vec!["a", "b"])
+            .await
+            .unwrap();
+        assert_eq!(
+            JobStateRepo::get::<Vec<String>>(&db, "promotion")
+                .await
+                .unwrap(),
+            Some(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert!(JobStateRepo::get::<u32>(&db, "promotion").await.is_err());
+
+        JobStateRepo::remove(&db, "promotion").await.unwrap();
+        assert_eq!(
+            JobStateRepo::get::<Vec<String>>(&db, "promotion")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use super::base::HasId;
+use crate::entity;
+use sea_orm::*;
+use tracing::instrument;
+
+pub type LinkTemplate = entity::link_template::Model;
+pub type LinkTemplateCreateDto = entity::link_template::CreateModel;
+pub type LinkTemplateUpdateDto = entity::link_template::UpdateModel;
+
+impl HasId for entity::link_template::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct LinkTemplateRepo;
+impl LinkTemplateRepo {
+    #[instrument(skip_all)]
+    pub async fn get_by_product(
+        db: &DatabaseConnection,
+        product_id: uuid::Uuid,
+    ) -> Result<Vec<entity::link_template::Model>, DbErr> {
+        entity::prelude::LinkTemplate::find()
+            .filter(entity::link_template::Column::ProductId.eq(product_id))
+            .order_by_asc(entity::link_template::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Returns the templates that have a metadata URL, of all products.
+    #[instrument(skip_all)]
+    pub async fn get_with_metadata_url(
+        db: &DatabaseConnection,
+    ) -> Result<Vec<entity::link_template::Model>, DbErr> {
+        entity::prelude::LinkTemplate::find()
+            .filter(entity::link_template::Column::MetadataUrl.is_not_null())
+            .all(db)
+            .await
+    }
+}
+
+/// Returns the values that the templates of a crash can refer to: its annotations and the ids
+/// of the crash, its product and its version.
+pub fn crash_values(
+    crash_id: uuid::Uuid,
+    product_id: uuid::Uuid,
+    version_id: uuid::Uuid,
+    annotations: impl IntoIterator<Item = (String, String)>,
+) -> HashMap<String, String> {
+    let mut values: HashMap<String, String> = annotations.into_iter().collect();
+    values.insert("crash_id".to_owned(), crash_id.to_string());
+    values.insert("product_id".to_owned(), product_id.to_string());
+    values.insert("version_id".to_owned(), version_id.to_string());
+    values
+}
+
+/// Characters encoded in values: the path segment set of the URL standard, with the delimiters
+/// of query parameters added, so that a value stays a single path segment or query value.
+const VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'&')
+    .add(b'+')
+    .add(b'=');
+
+/// Expands `{key}` placeholders in a link template with percent-encoded values. Returns `None`
+/// when the template refers to a key that has no value, so that no broken links are shown.
+pub fn render_link(template: &str, values: &HashMap<String, String>) -> Option<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start;
+        let key = rest[start + 1..end].trim();
+        let value = values.get(key)?;
+        result.push_str(&rest[..start]);
+        result.extend(utf8_percent_encode(value, VALUE));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_link;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_render_link() {
+        let values = HashMap::from([
+            ("trace_id".to_owned(), "abc123".to_owned()),
+            ("session_id".to_owned(), "a b&c".to_owned()),
+            ("module".to_owned(), "src/main.rs".to_owned()),
+        ]);
+
+        assert_eq!(
+            render_link("https://grafana/trace/{trace_id}", &values).as_deref(),
+            Some("https://grafana/trace/abc123")
+        );
+        assert_eq!(
+            render_link("https://loki/?q={session_id}&t={ trace_id }", &values).as_deref(),
+            Some("https://loki/?q=a%20b%26c&t=abc123")
+        );
+        assert_eq!(
+            render_link("https://git/blob/{module}", &values).as_deref(),
+            Some("https://git/blob/src%2Fmain.rs")
+        );
+        assert_eq!(
+            render_link("https://example.com/", &values).as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(render_link("https://grafana/{missing}", &values), None);
+        assert_eq!(render_link("https://grafana/{trace_id", &values), None);
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use std::collections::BTreeSet;
+use tracing::{instrument, warn};
+
+use super::base::HasId;
+use crate::build_id::{normalize_debug_file, normalize_debug_id};
+use crate::entity;
+use crate::report::CrashInfo;
+
+pub type MissingSymbols = entity::missing_symbols::Model;
+
+// Modules without symbols have unsymbolicated frames. For every product the modules for which
+// minidump-processor found no symbols are counted, so that release engineers know which symbol
+// files to upload. A module is no longer missing once its symbols are stored; symbol files are
+// looked up by module and build id only, so that resolves the module for all products.
+
+impl HasId for entity::missing_symbols::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns the normalized `(debug_file, debug_id)` pairs of the modules of a crash report for
+/// which minidump-processor found no symbols.
+pub fn missing_modules(report: &serde_json::Value) -> Vec<(String, String)> {
+    if report.is_null() {
+        return vec![];
+    }
+    let info = match CrashInfo::from_report(report) {
+        Ok(info) => info,
+        Err(e) => {
+            warn!("ignoring modules of crash report: {}", e);
+            return vec![];
+        }
+    };
+    info.modules
+        .iter()
+        .filter(|module| module.missing_symbols)
+        .filter_map(|module| {
+            Some((
+                normalize_debug_file(module.debug_file.as_deref()?),
+                normalize_debug_id(module.debug_id.as_deref()?)?,
+            ))
+        })
+        .filter(|(debug_file, _)| !debug_file.is_empty())
+        .collect()
+}
+
+pub struct MissingSymbolsRepo;
+impl MissingSymbolsRepo {
+    /// Counts a crash of the product that had no symbols for `modules`, as returned by
+    /// `missing_modules`.
+    #[instrument(skip_all)]
+    pub async fn record(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        modules: Vec<(String, String)>,
+        seen: NaiveDateTime,
+    ) -> Result<(), DbErr> {
+        let modules: BTreeSet<(String, String)> = modules.into_iter().collect();
+        for (module_id, build_id) in modules {
+            // Insert when the module was not missing before; when another crash inserted it
+            // concurrently the unique index rejects the insert, and the count is updated instead.
+            for _ in 0..2 {
+                let updated = entity::prelude::MissingSymbols::update_many()
+                    .col_expr(
+                        entity::missing_symbols::Column::Count,
+                        sea_query::Expr::col(entity::missing_symbols::Column::Count).add(1),
+                    )
+                    .col_expr(
+                        entity::missing_symbols::Column::LastSeen,
+                        sea_query::Expr::value(seen),
+                    )
+                    .col_expr(
+                        entity::missing_symbols::Column::UpdatedAt,
+                        sea_query::Expr::value(seen),
+                    )
+                    .filter(entity::missing_symbols::Column::ProductId.eq(product_id))
+                    .filter(entity::missing_symbols::Column::ModuleId.eq(&module_id))
+                    .filter(entity::missing_symbols::Column::BuildId.eq(&build_id))
+                    .exec(db)
+                    .await?;
+                if updated.rows_affected > 0 {
+                    break;
+                }
+
+                let missing = entity::missing_symbols::ActiveModel {
+                    id: Set(uuid::Uuid::new_v4()),
+                    created_at: Set(seen),
+                    updated_at: Set(seen),
+                    module_id: Set(module_id.clone()),
+                    build_id: Set(build_id.clone()),
+                    count: Set(1),
+                    last_seen: Set(seen),
+                    product_id: Set(product_id),
+                };
+                match entity::prelude::MissingSymbols::insert(missing)
+                    .exec_without_returning(db)
+                    .await
+                {
+                    Ok(_) => break,
+                    Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the missing symbols of `products`, `None` for all products, with the name of
+    /// their product. The modules missing in the most crashes come first.
+    #[instrument(skip_all)]
+    pub async fn list(
+        db: &DbConn,
+        products: Option<Vec<uuid::Uuid>>,
+        limit: u64,
+    ) -> Result<Vec<(MissingSymbols, String)>, DbErr> {
+        let mut query = entity::prelude::MissingSymbols::find();
+        if let Some(products) = products {
+            query = query.filter(entity::missing_symbols::Column::ProductId.is_in(products));
+        }
+        Ok(query
+            .find_also_related(entity::prelude::Product)
+            .order_by_desc(entity::missing_symbols::Column::Count)
+            .order_by_desc(entity::missing_symbols::Column::LastSeen)
+            .limit(limit)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(missing, product)| {
+                let product = product.map(|product| product.name).unwrap_or_default();
+                (missing, product)
+            })
+            .collect())
+    }
+
+    /// Forgets a missing module once its symbols have been stored. Returns the number of products
+    /// for which it was missing.
+    #[instrument(skip_all)]
+    pub async fn resolve(db: &DbConn, module_id: &str, build_id: &str) -> Result<u64, DbErr> {
+        let result = entity::prelude::MissingSymbols::delete_many()
+            .filter(entity::missing_symbols::Column::ModuleId.eq(module_id))
+            .filter(entity::missing_symbols::Column::BuildId.eq(build_id))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_missing_modules() {
+        let report = serde_json::json!({
+            "modules": [
+                { "debug_file": "workrave.pdb", "debug_id": "ABCDEF0123456789ABCDEF01234567891" },
+                {
+                    "debug_file": "C:\\Windows\\ntdll.pdb",
+                    "debug_id": "01234567-89ab-cdef-0123-456789abcdef-1",
+                    "missing_symbols": true
+                },
+                { "debug_file": "unknown.pdb", "missing_symbols": true }
+            ]
+        });
+        assert_eq!(
+            missing_modules(&report),
+            vec![(
+                "ntdll.pdb".to_owned(),
+                "0123456789ABCDEF0123456789ABCDEF1".to_owned()
+            )]
+        );
+        assert!(missing_modules(&serde_json::Value::Null).is_empty());
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let ntdll = (
+            "ntdll.pdb".to_owned(),
+            "0123456789ABCDEF0123456789ABCDEF1".to_owned(),
+        );
+        let libc = (
+            "libc.so.6".to_owned(),
+            "ABCDEF0123456789ABCDEF01234567890".to_owned(),
+        );
+        let first = chrono::NaiveDate::from_ymd_opt(2025, 1, 20)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        let second = first + chrono::Duration::hours(1);
+
+        MissingSymbolsRepo::record(&db, product_id, vec![ntdll.clone(), ntdll.clone()], first)
+            .await
+            .unwrap();
+        MissingSymbolsRepo::record(&db, product_id, vec![ntdll.clone(), libc.clone()], second)
+            .await
+            .unwrap();
+
+        let missing = MissingSymbolsRepo::list(&db, None, 100).await.unwrap();
+        assert_eq!(missing.len(), 2);
+        let (ntdll_missing, product) = &missing[0];
+        assert_eq!(product, "Workrave");
+        assert_eq!(ntdll_missing.module_id, ntdll.0);
+        assert_eq!(ntdll_missing.count, 2);
+        assert_eq!(ntdll_missing.last_seen, second);
+        assert!(MissingSymbolsRepo::list(&db, Some(vec![]), 100)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            MissingSymbolsRepo::resolve(&db, &ntdll.0, &ntdll.1)
+                .await
+                .unwrap(),
+            1
+        );
+        let missing = MissingSymbolsRepo::list(&db, None, 100).await.unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].0.module_id, libc.0);
+    }
+}
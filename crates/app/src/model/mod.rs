@@ -1,7 +1,33 @@
+pub mod alert;
 pub mod annotation;
+pub mod api_token;
 pub mod attachment;
 pub mod base;
+pub mod branding;
+pub mod build_metadata;
+pub mod client_diagnostic;
 pub mod crash;
+pub mod crash_pdf;
+pub mod device_key;
+pub mod issue;
+pub mod job_lease;
+pub mod job_state;
+pub mod link_template;
+pub mod missing_symbols;
+pub mod notification;
+pub mod organization;
+pub mod partition;
 pub mod product;
+pub mod product_deletion;
+pub mod query_metrics;
+pub mod role;
+pub mod search;
+pub mod session;
+pub mod stats;
+pub mod symbol_conversion;
 pub mod symbols;
+pub mod upload_count;
+pub mod upload_quarantine;
+pub mod user;
 pub mod version;
+pub mod webhook;
@@ -0,0 +1,227 @@
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use tracing::{error, instrument};
+
+use super::base::{HasId, Repo};
+use crate::entity;
+use crate::settings::settings;
+
+pub type Notification = entity::notification::Model;
+pub type NotificationCreateDto = entity::notification::CreateModel;
+
+pub const EMAIL: &str = "email";
+pub const WEBHOOK: &str = "webhook";
+
+pub const PENDING: &str = "pending";
+pub const SENT: &str = "sent";
+pub const FAILED: &str = "failed";
+
+impl HasId for entity::notification::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns when a notification that failed `attempts` times is sent again. The delay doubles
+/// with every attempt.
+pub fn next_attempt_at(now: NaiveDateTime, attempts: i32, retry_delay: u64) -> NaiveDateTime {
+    let factor = 1u64 << attempts.saturating_sub(1).clamp(0, 16);
+    now + chrono::Duration::seconds(retry_delay.saturating_mul(factor) as i64)
+}
+
+/// Notifications are recorded before they are sent, one per recipient, so that they can be
+/// retried, and remain as a record of what was sent to whom.
+pub struct NotificationRepo;
+impl NotificationRepo {
+    /// Records a notification for each `(channel, recipient)`, to be sent right away.
+    #[instrument(skip_all)]
+    pub async fn queue(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        event: &str,
+        subject: &str,
+        payload: serde_json::Value,
+        recipients: &[(&str, String)],
+    ) -> Result<usize, DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        for (channel, recipient) in recipients {
+            Repo::create(
+                db,
+                NotificationCreateDto {
+                    event: event.to_owned(),
+                    channel: (*channel).to_owned(),
+                    recipient: recipient.clone(),
+                    subject: subject.to_owned(),
+                    payload: payload.clone(),
+                    status: PENDING.to_owned(),
+                    attempts: 0,
+                    next_attempt_at: Some(now),
+                    sent_at: None,
+                    error: None,
+                    product_id,
+                },
+            )
+            .await?;
+        }
+        Ok(recipients.len())
+    }
+
+    /// Records the event for all recipients configured in `notifications`, in the background.
+    /// The notifications are sent by the notification job.
+    pub fn notify(
+        db: &DatabaseConnection,
+        product_id: uuid::Uuid,
+        event: &'static str,
+        subject: String,
+        data: serde_json::Value,
+    ) {
+        let config = &settings().notifications;
+        let recipients: Vec<(&str, String)> = config
+            .email_recipients
+            .iter()
+            .map(|recipient| (EMAIL, recipient.clone()))
+            .chain(config.webhooks.iter().map(|url| (WEBHOOK, url.clone())))
+            .collect();
+        if recipients.is_empty() {
+            return;
+        }
+
+        let db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = Self::queue(&db, product_id, event, &subject, data, &recipients).await {
+                error!("failed to queue {} notifications: {:?}", event, e);
+            }
+        });
+    }
+
+    /// Returns the pending notifications that are due at `now`, oldest first.
+    #[instrument(skip_all)]
+    pub async fn get_due(
+        db: &DbConn,
+        now: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<Notification>, DbErr> {
+        entity::prelude::Notification::find()
+            .filter(entity::notification::Column::Status.eq(PENDING))
+            .filter(entity::notification::Column::NextAttemptAt.lte(now))
+            .order_by_asc(entity::notification::Column::NextAttemptAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// Records the result of an attempt to send a notification. A failed notification is
+    /// retried later, unless it was attempted `max_attempts` times.
+    #[instrument(skip_all)]
+    pub async fn record_attempt(
+        db: &DbConn,
+        notification: Notification,
+        result: Result<(), String>,
+        max_attempts: i32,
+        retry_delay: u64,
+    ) -> Result<Notification, DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        let attempts = notification.attempts + 1;
+        let mut notification = notification.into_active_model();
+        notification.attempts = Set(attempts);
+        match result {
+            Ok(()) => {
+                notification.status = Set(SENT.to_owned());
+                notification.sent_at = Set(Some(now));
+                notification.next_attempt_at = Set(None);
+                notification.error = Set(None);
+            }
+            Err(error) => {
+                if attempts >= max_attempts {
+                    notification.status = Set(FAILED.to_owned());
+                    notification.next_attempt_at = Set(None);
+                } else {
+                    notification.next_attempt_at =
+                        Set(Some(next_attempt_at(now, attempts, retry_delay)));
+                }
+                notification.error = Set(Some(error));
+            }
+        }
+        notification.updated_at = Set(now);
+        notification.update(db).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_next_attempt_at() {
+        let now = chrono::Utc::now().naive_utc();
+        assert_eq!(
+            next_attempt_at(now, 1, 60),
+            now + chrono::Duration::seconds(60)
+        );
+        assert_eq!(
+            next_attempt_at(now, 3, 60),
+            now + chrono::Duration::seconds(240)
+        );
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record_attempt() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let queued = NotificationRepo::queue(
+            &db,
+            product_id,
+            "new_issue",
+            "New crash in Workrave",
+            serde_json::json!({ "signature": "crash" }),
+            &[
+                (EMAIL, "dev@example.org".to_owned()),
+                (WEBHOOK, "http://127.0.0.1:9/hook".to_owned()),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(queued, 2);
+
+        let now = chrono::Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        let mut due = NotificationRepo::get_due(&db, now, 10).await.unwrap();
+        assert_eq!(due.len(), 2);
+
+        let sent = NotificationRepo::record_attempt(&db, due.remove(0), Ok(()), 2, 60)
+            .await
+            .unwrap();
+        assert_eq!((sent.status.as_str(), sent.attempts), (SENT, 1));
+        assert!(sent.sent_at.is_some());
+
+        let failed = NotificationRepo::record_attempt(
+            &db,
+            due.remove(0),
+            Err("connection refused".to_owned()),
+            2,
+            60,
+        )
+        .await
+        .unwrap();
+        assert_eq!(failed.status, PENDING);
+        assert!(NotificationRepo::get_due(&db, now, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let failed =
+            NotificationRepo::record_attempt(&db, failed, Err("timeout".to_owned()), 2, 60)
+                .await
+                .unwrap();
+        assert_eq!(failed.status, FAILED);
+        assert_eq!(failed.next_attempt_at, None);
+        assert_eq!(failed.error.as_deref(), Some("timeout"));
+    }
+}
@@ -0,0 +1,284 @@
+use sea_orm::*;
+use sea_query::{Expr, Query, SelectStatement};
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::data_providers::user::ROLES;
+use crate::entity;
+
+pub type Organization = entity::organization::Model;
+pub type OrganizationCreateDto = entity::organization::CreateModel;
+pub type OrganizationMember = entity::organization_member::Model;
+pub type OrganizationMemberCreateDto = entity::organization_member::CreateModel;
+
+// Products can be owned by an organization. Members of an organization get their role in the
+// organization for each of its products, in addition to the roles they have for individual
+// products. Products of an organization are not visible to users outside it unless they were
+// given a role for the product itself. Visibility is enforced by the queries of the application,
+// see `ApiTokenRepo::readable_products`. Row-level security policies in the database were left
+// out of scope; the database has none for any table.
+
+impl HasId for entity::organization::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+impl HasId for entity::organization_member::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct OrganizationRepo;
+impl OrganizationRepo {
+    /// Returns all organizations, ordered by name.
+    #[instrument(skip_all)]
+    pub async fn get_all(db: &DbConn) -> Result<Vec<Organization>, DbErr> {
+        entity::prelude::Organization::find()
+            .order_by_asc(entity::organization::Column::Name)
+            .all(db)
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn create(db: &DbConn, name: &str) -> Result<uuid::Uuid, DbErr> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DbErr::Custom("organization name is empty".to_owned()));
+        }
+        let organization = OrganizationCreateDto {
+            name: name.to_owned(),
+        }
+        .into_active_model()
+        .insert(db)
+        .await?;
+        Ok(organization.id)
+    }
+
+    /// Deletes an organization. Its products are kept without an organization, and the
+    /// memberships are removed by the cascade.
+    #[instrument(skip_all)]
+    pub async fn delete(db: &DbConn, id: uuid::Uuid) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        entity::prelude::Product::update_many()
+            .col_expr(
+                entity::product::Column::OrganizationId,
+                Expr::value(Option::<uuid::Uuid>::None),
+            )
+            .filter(entity::product::Column::OrganizationId.eq(id))
+            .exec(&txn)
+            .await?;
+        entity::prelude::Organization::delete_by_id(id)
+            .exec(&txn)
+            .await?;
+        txn.commit().await
+    }
+
+    /// Moves the product to the organization, or out of any organization for `None`.
+    #[instrument(skip_all)]
+    pub async fn set_product(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        organization_id: Option<uuid::Uuid>,
+    ) -> Result<(), DbErr> {
+        if let Some(organization_id) = organization_id {
+            entity::prelude::Organization::find_by_id(organization_id)
+                .one(db)
+                .await?
+                .ok_or(DbErr::RecordNotFound("organization not found".to_owned()))?;
+        }
+        entity::prelude::Product::update_many()
+            .col_expr(
+                entity::product::Column::OrganizationId,
+                Expr::value(organization_id),
+            )
+            .filter(entity::product::Column::Id.eq(product_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the members of the organization with their user, ordered by username.
+    #[instrument(skip_all)]
+    pub async fn members(
+        db: &DbConn,
+        organization_id: uuid::Uuid,
+    ) -> Result<Vec<(OrganizationMember, Option<entity::user::Model>)>, DbErr> {
+        entity::prelude::OrganizationMember::find()
+            .filter(entity::organization_member::Column::OrganizationId.eq(organization_id))
+            .find_also_related(entity::prelude::User)
+            .order_by_asc(entity::user::Column::Username)
+            .all(db)
+            .await
+    }
+
+    /// Makes the user a member of the organization with `role`, replacing the role the user
+    /// had in it.
+    #[instrument(skip_all)]
+    pub async fn add_member(
+        db: &DbConn,
+        organization_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        role: &str,
+    ) -> Result<uuid::Uuid, DbErr> {
+        if !ROLES.iter().any(|(name, _)| *name == role) {
+            return Err(DbErr::Custom(format!("unknown role '{}'", role)));
+        }
+        let txn = db.begin().await?;
+        Self::delete_member(&txn, organization_id, user_id).await?;
+        let member = OrganizationMemberCreateDto {
+            role: role.to_owned(),
+            organization_id,
+            user_id,
+        }
+        .into_active_model()
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(member.id)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn remove_member(
+        db: &DbConn,
+        organization_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        Self::delete_member(db, organization_id, user_id).await
+    }
+
+    async fn delete_member<C: ConnectionTrait>(
+        db: &C,
+        organization_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        entity::prelude::OrganizationMember::delete_many()
+            .filter(entity::organization_member::Column::OrganizationId.eq(organization_id))
+            .filter(entity::organization_member::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Sub-query selecting the IDs of the organizations in which the user has one of `roles`,
+    /// or any role when `roles` is empty.
+    pub fn organization_ids(user_id: uuid::Uuid, roles: &[String]) -> SelectStatement {
+        let mut query = Query::select()
+            .column(entity::organization_member::Column::OrganizationId)
+            .from(entity::organization_member::Entity)
+            .and_where(entity::organization_member::Column::UserId.eq(user_id))
+            .to_owned();
+        if !roles.is_empty() {
+            query.and_where(entity::organization_member::Column::Role.is_in(roles.to_vec()));
+        }
+        query
+    }
+
+    /// Returns the products of the organizations in which the user has one of `roles`, or any
+    /// role when `roles` is empty.
+    #[instrument(skip_all)]
+    pub async fn member_products(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+        roles: &[String],
+    ) -> Result<Vec<uuid::Uuid>, DbErr> {
+        entity::prelude::Product::find()
+            .select_only()
+            .column(entity::product::Column::Id)
+            .filter(
+                entity::product::Column::OrganizationId
+                    .in_subquery(Self::organization_ids(user_id, roles)),
+            )
+            .into_tuple::<uuid::Uuid>()
+            .all(db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::role::{self, ADMIN, VIEWER};
+    use crate::model::user::{UserCreateDto, UserRepo};
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_members() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let user_id = Repo::create(
+            &db,
+            UserCreateDto {
+                username: "member".to_owned(),
+                is_admin: false,
+                last_authenticated: None,
+                deactivated_at: None,
+            },
+        )
+        .await
+        .unwrap();
+        let product = |name: &str| ProductCreateDto::named(name);
+        let workrave = Repo::create(&db, product("Workrave")).await.unwrap();
+        let other = Repo::create(&db, product("Other")).await.unwrap();
+
+        let acme = OrganizationRepo::create(&db, "Acme").await.unwrap();
+        assert!(OrganizationRepo::create(&db, " ").await.is_err());
+        OrganizationRepo::set_product(&db, workrave, Some(acme))
+            .await
+            .unwrap();
+        assert!(
+            OrganizationRepo::set_product(&db, other, Some(uuid::Uuid::new_v4()))
+                .await
+                .is_err()
+        );
+
+        assert!(OrganizationRepo::add_member(&db, acme, user_id, "owner")
+            .await
+            .is_err());
+        OrganizationRepo::add_member(&db, acme, user_id, ADMIN)
+            .await
+            .unwrap();
+        OrganizationRepo::add_member(&db, acme, user_id, VIEWER)
+            .await
+            .unwrap();
+        let members = OrganizationRepo::members(&db, acme).await.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].0.role, VIEWER);
+
+        assert_eq!(
+            OrganizationRepo::member_products(&db, user_id, &[])
+                .await
+                .unwrap(),
+            vec![workrave]
+        );
+        assert!(
+            OrganizationRepo::member_products(&db, user_id, &role::at_least(ADMIN))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(UserRepo::has_role(&db, user_id, workrave, VIEWER)
+            .await
+            .unwrap());
+        assert!(!UserRepo::has_role(&db, user_id, other, VIEWER)
+            .await
+            .unwrap());
+
+        OrganizationRepo::delete(&db, acme).await.unwrap();
+        let workrave = Repo::get_by_id::<entity::product::Entity>(&db, workrave)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(workrave.organization_id, None);
+        assert!(OrganizationRepo::members(&db, acme)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
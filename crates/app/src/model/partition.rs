@@ -0,0 +1,185 @@
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime};
+use sea_orm::*;
+use tracing::instrument;
+
+// On PostgreSQL, the crash and annotation tables are partitioned by month of `created_at`, see
+// the migration that partitions them. The nightly maintenance creates the partitions of the
+// coming months, and the crash retention drops the partitions of months that have expired as a
+// whole. Rows of a month without a partition are kept in the default partition, and moved to
+// the partition of their month when it is created. Other databases have no partitions, and
+// crashes are deleted row by row.
+
+/// The partitioned tables. Annotations are created with or after their crash, so an annotation
+/// is never in the partition of an earlier month than its crash.
+pub const PARTITIONED_TABLES: [&str; 2] = ["crash", "annotation"];
+
+/// Returns the first day of the month of `at`.
+pub fn month_of(at: NaiveDateTime) -> NaiveDate {
+    at.date().with_day(1).unwrap_or(at.date())
+}
+
+/// Returns the first day of the month after `month`.
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month + Months::new(1)
+}
+
+/// Returns the name of the partition of `table` for `month`, as created by
+/// `create_month_partition`.
+pub fn partition_name(table: &str, month: NaiveDate) -> String {
+    format!("{}_p{}", table, month.format("%Y%m"))
+}
+
+/// Returns the month of a partition of `table`, or `None` for other tables, such as the default
+/// partition.
+fn partition_month(table: &str, name: &str) -> Option<NaiveDate> {
+    let suffix = name.strip_prefix(table)?.strip_prefix("_p")?;
+    NaiveDate::parse_from_str(&format!("{}01", suffix), "%Y%m%d").ok()
+}
+
+pub struct PartitionRepo;
+impl PartitionRepo {
+    /// Returns whether the crash table is partitioned, which is only the case on PostgreSQL.
+    #[instrument(skip_all)]
+    pub async fn is_partitioned(db: &DbConn) -> Result<bool, DbErr> {
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(false);
+        }
+        let row = db
+            .query_one(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT EXISTS (SELECT 1 FROM pg_partitioned_table \
+                 WHERE partrelid = 'crash'::regclass) AS partitioned",
+            ))
+            .await?;
+        match row {
+            Some(row) => row.try_get("", "partitioned"),
+            None => Ok(false),
+        }
+    }
+
+    /// Creates the partitions of all partitioned tables for `month`, unless they exist.
+    #[instrument(skip_all)]
+    pub async fn create(db: &DbConn, month: NaiveDate) -> Result<(), DbErr> {
+        for table in PARTITIONED_TABLES {
+            db.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT create_month_partition($1, $2)",
+                [table.into(), month.into()],
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the months that the crash table has a partition for, oldest first.
+    #[instrument(skip_all)]
+    pub async fn months(db: &DbConn) -> Result<Vec<NaiveDate>, DbErr> {
+        let rows = db
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT c.relname AS name FROM pg_inherits i \
+                 JOIN pg_class c ON c.oid = i.inhrelid \
+                 WHERE i.inhparent = 'crash'::regclass",
+            ))
+            .await?;
+        let mut months = Vec::new();
+        for row in rows {
+            let name: String = row.try_get("", "name")?;
+            months.extend(partition_month("crash", &name));
+        }
+        months.sort();
+        Ok(months)
+    }
+
+    /// Returns whether the partitions of `month` can be dropped: no crash in the month is on
+    /// legal hold, and no annotation in the month belongs to a crash on legal hold.
+    #[instrument(skip_all)]
+    pub async fn is_droppable(db: &DbConn, month: NaiveDate) -> Result<bool, DbErr> {
+        let sql = format!(
+            "SELECT NOT EXISTS (SELECT 1 FROM {crash} WHERE legal_hold_at IS NOT NULL) \
+             AND NOT EXISTS (SELECT 1 FROM {annotation} a JOIN crash c ON c.id = a.crash_id \
+             WHERE c.legal_hold_at IS NOT NULL) AS droppable",
+            crash = partition_name("crash", month),
+            annotation = partition_name("annotation", month),
+        );
+        let row = db
+            .query_one(Statement::from_string(DbBackend::Postgres, sql))
+            .await?;
+        match row {
+            Some(row) => row.try_get("", "droppable"),
+            None => Ok(false),
+        }
+    }
+
+    /// Drops the partitions of all partitioned tables for `month`, with all their rows. The
+    /// annotations of the crashes of the month that were created in a later month are deleted
+    /// first, as they are in a partition that is kept. No triggers run, so the attachments of
+    /// the crashes must be deleted before.
+    #[instrument(skip_all)]
+    pub async fn drop_month(db: &DbConn, month: NaiveDate) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                "DELETE FROM annotation WHERE created_at >= $1 \
+                 AND crash_id IN (SELECT id FROM {})",
+                partition_name("crash", month)
+            ),
+            [next_month(month).into()],
+        ))
+        .await?;
+        let partitions = PARTITIONED_TABLES
+            .iter()
+            .map(|table| partition_name(table, month))
+            .collect::<Vec<_>>()
+            .join(", ");
+        txn.execute_unprepared(&format!("DROP TABLE IF EXISTS {}", partitions))
+            .await?;
+        txn.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection};
+
+    use crate::model::partition::{
+        month_of, next_month, partition_month, partition_name, PartitionRepo,
+    };
+
+    #[test]
+    fn test_months() {
+        let at = NaiveDate::from_ymd_opt(2025, 12, 31)
+            .unwrap()
+            .and_hms_opt(23, 59, 59)
+            .unwrap();
+        let month = month_of(at);
+        assert_eq!(month, NaiveDate::from_ymd_opt(2025, 12, 1).unwrap());
+        assert_eq!(
+            next_month(month),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_partition_name() {
+        let month = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        assert_eq!(partition_name("crash", month), "crash_p202502");
+        assert_eq!(partition_month("crash", "crash_p202502"), Some(month));
+        assert_eq!(partition_month("crash", "crash_default"), None);
+        assert_eq!(partition_month("crash", "annotation_p202502"), None);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_is_partitioned() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        assert!(!PartitionRepo::is_partitioned(&db).await.unwrap());
+    }
+}
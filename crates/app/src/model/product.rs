@@ -1,3 +1,7 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+
 use super::base::HasId;
 use crate::entity;
 
@@ -11,6 +15,557 @@ impl HasId for entity::product::Model {
     }
 }
 
+impl ProductCreateDto {
+    /// A product that accepts crashes, with all limits and options unset.
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            max_build_age_days: None,
+            build_age_overrides: None,
+            build_age_allow_list: None,
+            promoted_annotations: None,
+            redacted_fields: None,
+            allowed_content_types: None,
+            environments: None,
+            accepting_crashes: true,
+            public_submissions: false,
+            repository_url: None,
+            source_roots: None,
+            attachment_processors: None,
+            default_annotations: None,
+            auto_create_versions: false,
+            ingestion_windows: None,
+            organization_id: None,
+            max_minidump_size: None,
+            max_attachment_size: None,
+            max_attachments: None,
+            max_daily_uploads: None,
+        }
+    }
+}
+
+/// Upload acceptance rules based on the age of the build a crash was reported for.
+///
+/// `max_build_age_days` is the default limit; `build_age_overrides` holds per-channel limits as
+/// `channel=days` pairs separated by commas, where `0` disables the limit for that channel;
+/// `build_age_allow_list` holds comma-separated version names that are always accepted. The age
+/// of a build is the time since the release date of its version; builds of versions without a
+/// release date are rejected when a limit applies, as their age is unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildAgePolicy {
+    pub max_age_days: Option<i64>,
+    pub overrides: HashMap<String, i64>,
+    pub allow_list: HashSet<String>,
+}
+
+impl BuildAgePolicy {
+    pub fn max_age(&self, channel: Option<&str>) -> Option<Duration> {
+        let days = channel
+            .and_then(|channel| self.overrides.get(channel).copied())
+            .or(self.max_age_days)?;
+        (days > 0).then(|| Duration::days(days))
+    }
+
+    pub fn accepts(
+        &self,
+        version: &str,
+        channel: Option<&str>,
+        release_date: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+    ) -> bool {
+        if self.allow_list.contains(version) {
+            return true;
+        }
+        match (self.max_age(channel), release_date) {
+            (Some(max_age), Some(release_date)) => now - release_date <= max_age,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+impl From<&Product> for BuildAgePolicy {
+    fn from(product: &Product) -> Self {
+        let overrides = product
+            .build_age_overrides
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (channel, days) = entry.split_once('=')?;
+                Some((channel.trim().to_owned(), days.trim().parse().ok()?))
+            })
+            .collect();
+        let allow_list = product
+            .build_age_allow_list
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|version| !version.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        Self {
+            max_age_days: product.max_build_age_days.map(i64::from),
+            overrides,
+            allow_list,
+        }
+    }
+}
+
+/// Report fields that are hidden from viewers who are not admins of the product, see
+/// `CrashRepo::get_for_user`; the stored report is left intact.
+///
+/// `redacted_fields` holds comma-separated paths into the report, with `.` between keys and `*`
+/// matching every key or array element, e.g. `crash_info.address, modules.*.base_address`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactionRules {
+    pub paths: Vec<Vec<String>>,
+}
+
+impl RedactionRules {
+    pub const REDACTED: &'static str = "[redacted]";
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn apply(&self, report: &mut serde_json::Value) {
+        for path in &self.paths {
+            Self::redact(report, path);
+        }
+    }
+
+    fn redact(value: &mut serde_json::Value, path: &[String]) {
+        let Some((key, rest)) = path.split_first() else {
+            *value = serde_json::Value::String(Self::REDACTED.to_owned());
+            return;
+        };
+        match value {
+            serde_json::Value::Object(map) if key == "*" => {
+                map.values_mut().for_each(|value| Self::redact(value, rest))
+            }
+            serde_json::Value::Object(map) => {
+                if let Some(value) = map.get_mut(key) {
+                    Self::redact(value, rest)
+                }
+            }
+            serde_json::Value::Array(items) if key == "*" => {
+                items.iter_mut().for_each(|value| Self::redact(value, rest))
+            }
+            serde_json::Value::Array(items) => {
+                if let Some(value) = key.parse().ok().and_then(|i: usize| items.get_mut(i)) {
+                    Self::redact(value, rest)
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl From<&Product> for RedactionRules {
+    fn from(product: &Product) -> Self {
+        let paths = product
+            .redacted_fields
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(|path| path.split('.').map(str::to_owned).collect())
+            .collect();
+        Self { paths }
+    }
+}
+
+/// Annotation of a crash holding the commit the crashing build was made from. Source links use
+/// the hash of the version when absent.
+pub const COMMIT_ANNOTATION: &str = "commit";
+
+/// Links from the frames of a report to their source in the repository of the product.
+///
+/// `repository_url` is the web address of a GitHub repository; `source_roots` holds
+/// comma-separated prefixes of the build machines that are stripped from the source paths of
+/// frames, e.g. `C:/build/workrave/, /home/ci/workrave/`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceLinks {
+    pub repository: Option<String>,
+    pub roots: Vec<String>,
+}
+
+impl SourceLinks {
+    /// Returns the permalink to a line of a file at the commit, or `None` when the file is not
+    /// below one of the source roots.
+    pub fn link(&self, commit: &str, file: &str, line: Option<u64>) -> Option<String> {
+        let repository = self.repository.as_deref()?;
+        let file = file.replace('\\', "/");
+        let path = if self.roots.is_empty() {
+            file.trim_start_matches('/')
+        } else {
+            self.roots
+                .iter()
+                .find_map(|root| file.strip_prefix(root.as_str()))?
+        };
+        let path = path.trim_start_matches('/');
+        if path.is_empty() {
+            return None;
+        }
+        let mut url = format!("{}/blob/{}/{}", repository, commit, path);
+        if let Some(line) = line {
+            url.push_str(&format!("#L{}", line));
+        }
+        Some(url)
+    }
+
+    /// Adds a `source_url` to the frames of the report that have a source file.
+    pub fn apply(&self, report: &mut serde_json::Value, commit: &str) {
+        if self.repository.is_none() || commit.is_empty() {
+            return;
+        }
+        if let Some(frames) = report.pointer_mut("/crashing_thread/frames") {
+            self.link_frames(frames, commit);
+        }
+        if let Some(threads) = report
+            .get_mut("threads")
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            for thread in threads {
+                if let Some(frames) = thread.get_mut("frames") {
+                    self.link_frames(frames, commit);
+                }
+            }
+        }
+    }
+
+    fn link_frames(&self, frames: &mut serde_json::Value, commit: &str) {
+        let Some(frames) = frames.as_array_mut() else {
+            return;
+        };
+        for frame in frames {
+            let Some(file) = frame.get("file").and_then(serde_json::Value::as_str) else {
+                continue;
+            };
+            let line = frame.get("line").and_then(serde_json::Value::as_u64);
+            if let Some(url) = self.link(commit, file, line) {
+                frame["source_url"] = serde_json::Value::String(url);
+            }
+        }
+    }
+}
+
+impl From<&Product> for SourceLinks {
+    fn from(product: &Product) -> Self {
+        let repository = product
+            .repository_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .map(str::to_owned);
+        let roots = product
+            .source_roots
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|root| root.trim().replace('\\', "/"))
+            .filter(|root| !root.is_empty())
+            .collect();
+        Self { repository, roots }
+    }
+}
+
+/// Kind of file uploaded by clients; each kind has its own list of accepted content types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadKind {
+    Minidump,
+    Symbols,
+    Attachment,
+}
+
+impl UploadKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadKind::Minidump => "minidump",
+            UploadKind::Symbols => "symbols",
+            UploadKind::Attachment => "attachment",
+        }
+    }
+}
+
+/// Content types accepted for an upload kind.
+///
+/// The defaults come from the server settings. `allowed_content_types` of a product holds
+/// comma-separated `kind=type` pairs that replace the defaults of that kind, e.g.
+/// `attachment=text/plain, attachment=image/*`. A type ending in `/*` matches all subtypes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentTypePolicy {
+    pub allowed: Vec<String>,
+}
+
+impl ContentTypePolicy {
+    pub fn new(product: &Product, kind: UploadKind, defaults: &[String]) -> Self {
+        let overrides: Vec<String> = product
+            .allowed_content_types
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (entry_kind, content_type) = entry.split_once('=')?;
+                (entry_kind.trim() == kind.as_str())
+                    .then(|| content_type.trim().to_ascii_lowercase())
+            })
+            .filter(|content_type| !content_type.is_empty())
+            .collect();
+        let allowed = if overrides.is_empty() {
+            defaults
+                .iter()
+                .map(|content_type| content_type.to_ascii_lowercase())
+                .collect()
+        } else {
+            overrides
+        };
+        Self { allowed }
+    }
+
+    /// Checks the content type, ignoring parameters such as `charset`.
+    pub fn accepts(&self, content_type: &str) -> bool {
+        let content_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let family = content_type.split('/').next().unwrap_or_default();
+        self.allowed.iter().any(|allowed| {
+            allowed == "*/*"
+                || *allowed == content_type
+                || allowed
+                    .strip_suffix("/*")
+                    .is_some_and(|allowed| allowed == family)
+        })
+    }
+}
+
+/// Environments a product accepts crashes from, e.g. `production`, `staging` and `dev`.
+///
+/// `environments` of a product holds comma-separated names. Without a list, any well formed name
+/// is accepted. Names are compared in lower case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvironmentPolicy {
+    pub allowed: Vec<String>,
+}
+
+impl EnvironmentPolicy {
+    pub const MAX_LENGTH: usize = 32;
+
+    fn is_well_formed(environment: &str) -> bool {
+        !environment.is_empty()
+            && environment.len() <= Self::MAX_LENGTH
+            && environment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// Returns the normalized name of the environment, or `None` if it is not accepted.
+    pub fn check(&self, environment: &str) -> Option<String> {
+        let environment = environment.trim().to_ascii_lowercase();
+        if !Self::is_well_formed(&environment) {
+            return None;
+        }
+        (self.allowed.is_empty() || self.allowed.contains(&environment)).then_some(environment)
+    }
+}
+
+impl From<&Product> for EnvironmentPolicy {
+    fn from(product: &Product) -> Self {
+        let allowed = product
+            .environments
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|environment| environment.trim().to_ascii_lowercase())
+            .filter(|environment| !environment.is_empty())
+            .collect();
+        Self { allowed }
+    }
+}
+
+/// What happens to crashes uploaded during an ingestion window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionMode {
+    /// Crashes are stored but processed when the window ends.
+    Queue,
+    /// Crashes are rejected; clients are asked to retry when the window ends.
+    Reject,
+}
+
+/// When an ingestion window is in effect, in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowPeriod {
+    /// Every day between two times; a window that ends before it starts spans midnight.
+    Daily { from: NaiveTime, until: NaiveTime },
+    /// Once, e.g. for planned maintenance.
+    Once {
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    },
+}
+
+/// A period in which a product does not process or does not accept crashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngestionWindow {
+    pub mode: IngestionMode,
+    pub period: WindowPeriod,
+    /// Shown to clients instead of the default message.
+    pub message: Option<String>,
+}
+
+impl IngestionWindow {
+    const TIME_FORMAT: &'static str = "%H:%M";
+    const DATE_TIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M";
+
+    /// Parses a window as `<mode> <from>..<until> [message]`, see `IngestionSchedule`.
+    fn parse(entry: &str) -> Option<Self> {
+        let (mode, rest) = entry.trim().split_once(char::is_whitespace)?;
+        let mode = match mode.to_ascii_lowercase().as_str() {
+            "queue" => IngestionMode::Queue,
+            "reject" => IngestionMode::Reject,
+            _ => return None,
+        };
+        let rest = rest.trim_start();
+        let (period, message) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let (from, until) = period.split_once("..")?;
+        let time = |value: &str| NaiveTime::parse_from_str(value, Self::TIME_FORMAT).ok();
+        let date_time =
+            |value: &str| NaiveDateTime::parse_from_str(value, Self::DATE_TIME_FORMAT).ok();
+        let period = match (time(from), time(until)) {
+            (Some(from), Some(until)) if from != until => WindowPeriod::Daily { from, until },
+            (Some(_), Some(_)) => return None,
+            _ => {
+                let from = date_time(from)?;
+                let until = date_time(until).filter(|until| *until > from)?;
+                WindowPeriod::Once { from, until }
+            }
+        };
+        let message = message.trim();
+        Some(Self {
+            mode,
+            period,
+            message: (!message.is_empty()).then(|| message.to_owned()),
+        })
+    }
+
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        match self.period {
+            WindowPeriod::Daily { from, until } if from < until => {
+                from <= now.time() && now.time() < until
+            }
+            WindowPeriod::Daily { from, until } => from <= now.time() || now.time() < until,
+            WindowPeriod::Once { from, until } => from <= now && now < until,
+        }
+    }
+
+    /// Returns when the window that contains `now` ends.
+    pub fn ends(&self, now: NaiveDateTime) -> NaiveDateTime {
+        match self.period {
+            WindowPeriod::Daily { until, .. } => {
+                let end = now.date().and_time(until);
+                if end <= now {
+                    end + Duration::days(1)
+                } else {
+                    end
+                }
+            }
+            WindowPeriod::Once { until, .. } => until,
+        }
+    }
+
+    /// Returns the message for clients uploading a crash of `product` at `now`.
+    pub fn message(&self, product: &str, now: NaiveDateTime) -> String {
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        let ends = self.ends(now).format("%Y-%m-%d %H:%M");
+        match self.mode {
+            IngestionMode::Queue => {
+                format!("crashes of {} are processed after {} UTC", product, ends)
+            }
+            IngestionMode::Reject => {
+                format!("{} does not accept crashes until {} UTC", product, ends)
+            }
+        }
+    }
+}
+
+/// Maintenance windows and quiet hours of a product, in which crashes are queued or rejected.
+///
+/// `ingestion_windows` holds windows separated by `;` as `<mode> <from>..<until> [message]`.
+/// The mode is `queue` or `reject`. Times are in UTC, as `HH:MM` for a window every day or as
+/// `YYYY-MM-DDTHH:MM` for a single window, e.g.
+/// `queue 01:00..03:00; reject 2025-01-12T22:00..2025-01-13T02:00 Database upgrade`. The
+/// optional message is returned to clients. Invalid windows are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestionSchedule {
+    pub windows: Vec<IngestionWindow>,
+}
+
+impl IngestionSchedule {
+    /// Returns the window in effect at `now`. When windows overlap, rejecting takes precedence
+    /// over queueing.
+    pub fn active(&self, now: NaiveDateTime) -> Option<&IngestionWindow> {
+        let mut active = self.windows.iter().filter(|window| window.contains(now));
+        active
+            .clone()
+            .find(|window| window.mode == IngestionMode::Reject)
+            .or_else(|| active.next())
+    }
+}
+
+impl From<&Product> for IngestionSchedule {
+    fn from(product: &Product) -> Self {
+        let windows = product
+            .ingestion_windows
+            .as_deref()
+            .unwrap_or_default()
+            .split(';')
+            .filter_map(IngestionWindow::parse)
+            .collect();
+        Self { windows }
+    }
+}
+
+/// Limits on the uploads of a product, `None` for no limit. Sizes are in bytes. Uploads per day
+/// are counted by `UploadCountRepo`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UploadQuota {
+    pub max_minidump_size: Option<u64>,
+    pub max_attachment_size: Option<u64>,
+    pub max_attachments: Option<usize>,
+    pub max_daily_uploads: Option<i32>,
+}
+
+impl From<&Product> for UploadQuota {
+    fn from(product: &Product) -> Self {
+        // Like the maximum build age, limits of zero or less are no limit; products that should
+        // not receive crashes stop accepting them instead.
+        Self {
+            max_minidump_size: product
+                .max_minidump_size
+                .filter(|size| *size > 0)
+                .map(|size| size as u64),
+            max_attachment_size: product
+                .max_attachment_size
+                .filter(|size| *size > 0)
+                .map(|size| size as u64),
+            max_attachments: product
+                .max_attachments
+                .filter(|max| *max > 0)
+                .map(|max| max as usize),
+            max_daily_uploads: product.max_daily_uploads.filter(|max| *max > 0),
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 #[cfg(test)]
 mod tests {
@@ -18,13 +573,256 @@ mod tests {
         entity,
         model::{
             base::Repo,
-            product::{ProductCreateDto, ProductUpdateDto},
+            product::{
+                BuildAgePolicy, ContentTypePolicy, EnvironmentPolicy, IngestionMode,
+                IngestionSchedule, ProductCreateDto, ProductUpdateDto, RedactionRules, SourceLinks,
+                UploadKind, UploadQuota,
+            },
         },
     };
     use serial_test::serial;
 
     use migration::{Migrator, MigratorTrait};
-    use sea_orm::{Database, DatabaseConnection, EntityTrait};
+    use sea_orm::{Database, DatabaseConnection, EntityTrait, IntoActiveModel, TryIntoModel};
+
+    fn workrave() -> crate::model::product::Product {
+        ProductCreateDto::named("Workrave")
+            .into_active_model()
+            .try_into_model()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_build_age_policy() {
+        let product = crate::model::product::Product {
+            max_build_age_days: Some(180),
+            build_age_overrides: Some("beta=30, nightly=0, bogus".to_owned()),
+            build_age_allow_list: Some("1.10.0, 1.10.1".to_owned()),
+            ..workrave()
+        };
+        let policy = BuildAgePolicy::from(&product);
+
+        let now = chrono::Utc::now().naive_utc();
+        let old = now - chrono::Duration::days(60);
+        let ancient = now - chrono::Duration::days(365);
+
+        let (old, ancient) = (Some(old), Some(ancient));
+
+        assert!(policy.accepts("1.11.0", None, old, now));
+        assert!(policy.accepts("1.11.0", Some("stable"), old, now));
+        assert!(!policy.accepts("1.11.0", None, ancient, now));
+        assert!(!policy.accepts("1.11.0", Some("beta"), old, now));
+        assert!(policy.accepts("1.11.0", Some("nightly"), ancient, now));
+        assert!(policy.accepts("1.10.1", Some("beta"), ancient, now));
+        assert!(!policy.accepts("1.11.0", None, None, now));
+        assert!(policy.accepts("1.11.0", Some("nightly"), None, now));
+        assert!(policy.accepts("1.10.1", None, None, now));
+        assert!(BuildAgePolicy::default().accepts("1.11.0", None, ancient, now));
+        assert!(BuildAgePolicy::default().accepts("1.11.0", None, None, now));
+    }
+
+    #[test]
+    fn test_content_type_policy() {
+        let mut product = workrave();
+        let defaults = vec!["application/octet-stream".to_owned(), "text/*".to_owned()];
+
+        let policy = ContentTypePolicy::new(&product, UploadKind::Attachment, &defaults);
+        assert!(policy.accepts("application/octet-stream"));
+        assert!(policy.accepts("text/plain; charset=utf-8"));
+        assert!(policy.accepts("Text/Plain"));
+        assert!(!policy.accepts("image/png"));
+        assert!(!policy.accepts("textual/plain"));
+
+        product.allowed_content_types =
+            Some("attachment=image/*, minidump=application/x-dmp, bogus".to_owned());
+        let policy = ContentTypePolicy::new(&product, UploadKind::Attachment, &defaults);
+        assert_eq!(policy.allowed, vec!["image/*".to_owned()]);
+        assert!(policy.accepts("image/png"));
+        assert!(!policy.accepts("text/plain"));
+
+        let policy = ContentTypePolicy::new(&product, UploadKind::Symbols, &defaults);
+        assert_eq!(policy.allowed, defaults);
+    }
+
+    #[test]
+    fn test_environment_policy() {
+        let mut product = workrave();
+
+        let policy = EnvironmentPolicy::from(&product);
+        assert_eq!(policy.check(" Nightly "), Some("nightly".to_owned()));
+        assert_eq!(policy.check(""), None);
+        assert_eq!(policy.check("prod/eu"), None);
+        assert_eq!(
+            policy.check(&"x".repeat(EnvironmentPolicy::MAX_LENGTH + 1)),
+            None
+        );
+
+        product.environments = Some("production, Staging,,dev".to_owned());
+        let policy = EnvironmentPolicy::from(&product);
+        assert_eq!(policy.allowed, vec!["production", "staging", "dev"]);
+        assert_eq!(policy.check("STAGING"), Some("staging".to_owned()));
+        assert_eq!(policy.check("nightly"), None);
+    }
+
+    #[test]
+    fn test_upload_quota() {
+        let mut product = workrave();
+        assert_eq!(UploadQuota::from(&product), UploadQuota::default());
+
+        product.max_minidump_size = Some(10 * 1024 * 1024);
+        product.max_attachment_size = Some(-1);
+        product.max_attachments = Some(3);
+        product.max_daily_uploads = Some(0);
+        assert_eq!(
+            UploadQuota::from(&product),
+            UploadQuota {
+                max_minidump_size: Some(10 * 1024 * 1024),
+                max_attachment_size: None,
+                max_attachments: Some(3),
+                max_daily_uploads: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ingestion_schedule() {
+        let mut product = workrave();
+        let at =
+            |value: &str| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(
+            IngestionSchedule::from(&product).active(at("2025-01-12 23:00")),
+            None
+        );
+
+        product.ingestion_windows = Some(
+            "queue 22:00..02:00; reject 2025-01-12T23:00..2025-01-13T01:00  Database upgrade; \
+             pause 01:00..02:00; queue 03:00..03:00; reject 2025-01-13T01:00..2025-01-12T23:00"
+                .to_owned(),
+        );
+        let schedule = IngestionSchedule::from(&product);
+        assert_eq!(schedule.windows.len(), 2);
+
+        let window = schedule.active(at("2025-01-12 22:30")).unwrap();
+        assert_eq!(window.mode, IngestionMode::Queue);
+        assert_eq!(window.ends(at("2025-01-12 22:30")), at("2025-01-13 02:00"));
+        assert_eq!(
+            window.message("Workrave", at("2025-01-12 22:30")),
+            "crashes of Workrave are processed after 2025-01-13 02:00 UTC"
+        );
+
+        let window = schedule.active(at("2025-01-13 00:30")).unwrap();
+        assert_eq!(window.mode, IngestionMode::Reject);
+        assert_eq!(window.ends(at("2025-01-13 00:30")), at("2025-01-13 01:00"));
+        assert_eq!(
+            window.message("Workrave", at("2025-01-13 00:30")),
+            "Database upgrade"
+        );
+
+        let window = schedule.active(at("2025-01-14 01:30")).unwrap();
+        assert_eq!(window.mode, IngestionMode::Queue);
+        assert_eq!(window.ends(at("2025-01-14 01:30")), at("2025-01-14 02:00"));
+        assert_eq!(schedule.active(at("2025-01-14 02:00")), None);
+        assert_eq!(schedule.active(at("2025-01-14 12:00")), None);
+    }
+
+    #[test]
+    fn test_redaction_rules() {
+        let product = crate::model::product::Product {
+            redacted_fields: Some(
+                "crash_info.address, modules.*.base_address, environment".to_owned(),
+            ),
+            ..workrave()
+        };
+        let rules = RedactionRules::from(&product);
+        assert_eq!(rules.paths.len(), 3);
+
+        let mut report = serde_json::json!({
+            "crash_info": { "address": "0x0000dead", "type": "EXCEPTION_ACCESS_VIOLATION" },
+            "modules": [
+                { "filename": "workrave.exe", "base_address": "0x00400000" },
+                { "filename": "harpoon.dll" }
+            ],
+            "environment": { "PATH": "C:\\Windows" }
+        });
+        rules.apply(&mut report);
+
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "crash_info": { "address": "[redacted]", "type": "EXCEPTION_ACCESS_VIOLATION" },
+                "modules": [
+                    { "filename": "workrave.exe", "base_address": "[redacted]" },
+                    { "filename": "harpoon.dll" }
+                ],
+                "environment": "[redacted]"
+            })
+        );
+        assert!(RedactionRules::default().is_empty());
+    }
+
+    #[test]
+    fn test_source_links() {
+        let mut product = crate::model::product::Product {
+            repository_url: Some("https://github.com/rcaelers/workrave/".to_owned()),
+            source_roots: Some("C:\\build\\workrave\\, /home/ci/workrave/".to_owned()),
+            ..workrave()
+        };
+        let links = SourceLinks::from(&product);
+        assert_eq!(
+            links.repository.as_deref(),
+            Some("https://github.com/rcaelers/workrave")
+        );
+        assert_eq!(
+            links.roots,
+            vec!["C:/build/workrave/", "/home/ci/workrave/"]
+        );
+        assert_eq!(
+            links.link(
+                "abc123",
+                "C:\\build\\workrave\\libs\\core\\src\\Core.cc",
+                Some(42)
+            ),
+            Some(
+                "https://github.com/rcaelers/workrave/blob/abc123/libs/core/src/Core.cc#L42"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            links.link("abc123", "d:\\a01\\vctools\\crt\\exe_common.inl", None),
+            None
+        );
+
+        let mut report = serde_json::json!({
+            "crashing_thread": { "frames": [
+                { "function": "Core::tick", "file": "/home/ci/workrave/libs/core/src/Core.cc", "line": 7 },
+                { "function": "main" }
+            ]},
+            "threads": [{ "frames": [
+                { "file": "/home/ci/workrave/ui/app/main.cc" }
+            ]}]
+        });
+        links.apply(&mut report, "abc123");
+        assert_eq!(
+            report["crashing_thread"]["frames"][0]["source_url"],
+            "https://github.com/rcaelers/workrave/blob/abc123/libs/core/src/Core.cc#L7"
+        );
+        assert!(report["crashing_thread"]["frames"][1]
+            .get("source_url")
+            .is_none());
+        assert_eq!(
+            report["threads"][0]["frames"][0]["source_url"],
+            "https://github.com/rcaelers/workrave/blob/abc123/ui/app/main.cc"
+        );
+
+        product.repository_url = None;
+        let mut report = serde_json::json!({
+            "crashing_thread": { "frames": [{ "file": "/home/ci/workrave/main.cc" }] }
+        });
+        SourceLinks::from(&product).apply(&mut report, "abc123");
+        assert!(report["crashing_thread"]["frames"][0]
+            .get("source_url")
+            .is_none());
+    }
 
     #[serial]
     #[tokio::test]
@@ -32,14 +830,10 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product1 = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product1 = ProductCreateDto::named("Workrave");
         let id1 = Repo::create(&db, product1.clone()).await.unwrap();
 
-        let product2 = ProductCreateDto {
-            name: "Scroom".to_owned(),
-        };
+        let product2 = ProductCreateDto::named("Scroom");
         let id2 = Repo::create(&db, product2.clone()).await.unwrap();
 
         let model1 = entity::product::Entity::find_by_id(id1)
@@ -63,9 +857,7 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product1 = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product1 = ProductCreateDto::named("Workrave");
         let id1 = Repo::create(&db, product1.clone()).await.unwrap();
 
         let model1 = entity::product::Entity::find_by_id(id1)
@@ -82,9 +874,7 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product1 = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product1 = ProductCreateDto::named("Workrave");
         let id = Repo::create(&db, product1.clone()).await.unwrap();
 
         let model = entity::product::Entity::find_by_id(id)
@@ -97,6 +887,26 @@ mod tests {
         let product2 = ProductUpdateDto {
             id,
             name: "Scroom".to_owned(),
+            max_build_age_days: None,
+            build_age_overrides: None,
+            build_age_allow_list: None,
+            promoted_annotations: None,
+            redacted_fields: None,
+            allowed_content_types: None,
+            environments: None,
+            accepting_crashes: true,
+            public_submissions: false,
+            repository_url: None,
+            source_roots: None,
+            attachment_processors: None,
+            default_annotations: None,
+            auto_create_versions: false,
+            ingestion_windows: None,
+            organization_id: None,
+            max_minidump_size: None,
+            max_attachment_size: None,
+            max_attachments: None,
+            max_daily_uploads: None,
         };
 
         Repo::update(&db, product2.clone()).await.unwrap();
@@ -115,9 +925,7 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product = ProductCreateDto::named("Workrave");
         let id = Repo::create(&db, product.clone()).await.unwrap();
 
         let model = Repo::get_by_id::<entity::product::Entity>(&db, id)
@@ -138,9 +946,7 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product = ProductCreateDto::named("Workrave");
         let id = Repo::create(&db, product.clone()).await.unwrap();
 
         let model = Repo::get_by_column::<entity::product::Entity, _, _>(
@@ -170,14 +976,10 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product1 = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product1 = ProductCreateDto::named("Workrave");
         let id1 = Repo::create(&db, product1.clone()).await.unwrap();
 
-        let product2 = ProductCreateDto {
-            name: "Scroom".to_owned(),
-        };
+        let product2 = ProductCreateDto::named("Scroom");
         let id2 = Repo::create(&db, product2.clone()).await.unwrap();
 
         let model = Repo::get_all::<entity::product::Entity>(&db).await.unwrap();
@@ -194,14 +996,10 @@ mod tests {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
-        let product1 = ProductCreateDto {
-            name: "Workrave".to_owned(),
-        };
+        let product1 = ProductCreateDto::named("Workrave");
         let id1 = Repo::create(&db, product1.clone()).await.unwrap();
 
-        let product2 = ProductCreateDto {
-            name: "Scroom".to_owned(),
-        };
+        let product2 = ProductCreateDto::named("Scroom");
         let id2 = Repo::create(&db, product2.clone()).await.unwrap();
 
         Repo::delete_by_id::<entity::product::Entity>(&db, id2)
@@ -0,0 +1,162 @@
+use super::base::{HasId, Repo};
+use crate::entity;
+use sea_orm::*;
+use sea_query::{Query, SelectStatement};
+use tracing::instrument;
+
+pub type ProductDeletion = entity::product_deletion::Model;
+pub type ProductDeletionCreateDto = entity::product_deletion::CreateModel;
+pub type ProductDeletionUpdateDto = entity::product_deletion::UpdateModel;
+
+pub const STATE_PENDING: &str = "pending";
+pub const STATE_DONE: &str = "done";
+
+impl HasId for entity::product_deletion::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct ProductDeletionRepo;
+impl ProductDeletionRepo {
+    /// Marks a product for deletion. The product is hidden immediately; its data is removed
+    /// later by the cleanup job. Scheduling an already scheduled product is a no-op.
+    #[instrument(skip_all)]
+    pub async fn schedule(db: &DbConn, product_id: uuid::Uuid) -> Result<uuid::Uuid, DbErr> {
+        if let Some(deletion) = entity::prelude::ProductDeletion::find()
+            .filter(entity::product_deletion::Column::ProductId.eq(product_id))
+            .one(db)
+            .await?
+        {
+            return Ok(deletion.id);
+        }
+
+        let product = entity::prelude::Product::find_by_id(product_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("product not found".to_owned()))?;
+
+        let dto = ProductDeletionCreateDto {
+            product_id,
+            product_name: product.name,
+            state: STATE_PENDING.to_owned(),
+            crashes_deleted: 0,
+            symbols_deleted: 0,
+            finished_at: None,
+        };
+        Repo::create(db, dto).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_pending(db: &DbConn) -> Result<Vec<ProductDeletion>, DbErr> {
+        entity::prelude::ProductDeletion::find()
+            .filter(entity::product_deletion::Column::State.eq(STATE_PENDING))
+            .order_by_asc(entity::product_deletion::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Returns whether the product is scheduled for deletion.
+    #[instrument(skip_all)]
+    pub async fn is_scheduled(db: &DbConn, product_id: uuid::Uuid) -> Result<bool, DbErr> {
+        let count = entity::prelude::ProductDeletion::find()
+            .filter(entity::product_deletion::Column::ProductId.eq(product_id))
+            .count(db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Cancels a pending deletion of the product. Data that was already removed is not restored.
+    /// Returns whether a deletion was cancelled.
+    #[instrument(skip_all)]
+    pub async fn unschedule(db: &DbConn, product_id: uuid::Uuid) -> Result<bool, DbErr> {
+        let result = entity::prelude::ProductDeletion::delete_many()
+            .filter(entity::product_deletion::Column::ProductId.eq(product_id))
+            .filter(entity::product_deletion::Column::State.eq(STATE_PENDING))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Sub-query selecting the IDs of all products that are (being) deleted.
+    pub fn scheduled_product_ids() -> SelectStatement {
+        Query::select()
+            .column(entity::product_deletion::Column::ProductId)
+            .from(entity::product_deletion::Entity)
+            .to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity,
+        model::{
+            base::Repo,
+            product::ProductCreateDto,
+            product_deletion::{ProductDeletionRepo, STATE_PENDING},
+        },
+    };
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_schedule() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product = ProductCreateDto::named("Workrave");
+        let product_id = Repo::create(&db, product).await.unwrap();
+        let other_id = Repo::create(&db, ProductCreateDto::named("Scroom"))
+            .await
+            .unwrap();
+
+        let id1 = ProductDeletionRepo::schedule(&db, product_id)
+            .await
+            .unwrap();
+        let id2 = ProductDeletionRepo::schedule(&db, product_id)
+            .await
+            .unwrap();
+        assert_eq!(id1, id2);
+
+        let pending = ProductDeletionRepo::get_pending(&db).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].product_id, product_id);
+        assert_eq!(pending[0].product_name, "Workrave");
+        assert_eq!(pending[0].state, STATE_PENDING);
+
+        let visible = entity::prelude::Product::find()
+            .filter(
+                entity::product::Column::Id
+                    .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+            )
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, other_id);
+
+        assert!(ProductDeletionRepo::schedule(&db, uuid::Uuid::new_v4())
+            .await
+            .is_err());
+
+        assert!(ProductDeletionRepo::is_scheduled(&db, product_id)
+            .await
+            .unwrap());
+        assert!(!ProductDeletionRepo::is_scheduled(&db, other_id)
+            .await
+            .unwrap());
+        assert!(ProductDeletionRepo::unschedule(&db, product_id)
+            .await
+            .unwrap());
+        assert!(!ProductDeletionRepo::unschedule(&db, product_id)
+            .await
+            .unwrap());
+        assert!(!ProductDeletionRepo::is_scheduled(&db, product_id)
+            .await
+            .unwrap());
+    }
+}
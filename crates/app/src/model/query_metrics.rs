@@ -0,0 +1,107 @@
+use sea_orm::metric::Info;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::settings::settings;
+
+// Repo methods run in a span named after the method, see the `#[instrument]` attributes in the
+// repos. The database connection reports every statement to `observe`, which attributes it to
+// the repo of the innermost span, e.g. `crash` for `CrashRepo::get_by_id`, and keeps a latency
+// histogram per repo for the metrics endpoint. Statements outside a repo count as `other`.
+//
+// Slow statements are logged with their SQL, but without the values bound to it, as those may
+// contain user data.
+
+/// Upper bounds of the buckets of the statement duration histograms, in seconds.
+pub const BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+const OTHER: &str = "other";
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryHistogram {
+    /// Statements per bucket, not cumulative; the last entry counts those above all bounds.
+    pub buckets: [u64; BUCKETS.len() + 1],
+    pub sum: Duration,
+}
+
+impl QueryHistogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += elapsed;
+    }
+}
+
+fn histograms() -> &'static Mutex<BTreeMap<&'static str, QueryHistogram>> {
+    static HISTOGRAMS: OnceLock<Mutex<BTreeMap<&'static str, QueryHistogram>>> = OnceLock::new();
+    HISTOGRAMS.get_or_init(Default::default)
+}
+
+/// Returns the repo and the method of the current span, e.g. `("crash", "get_by_id")`.
+fn current_query() -> (&'static str, &'static str) {
+    tracing::Span::current()
+        .metadata()
+        .and_then(|metadata| {
+            metadata
+                .target()
+                .strip_prefix("app::model::")
+                .map(|repo| (repo, metadata.name()))
+        })
+        .unwrap_or((OTHER, OTHER))
+}
+
+/// Records a statement executed by the database connection. Install it with
+/// `DatabaseConnection::set_metric_callback`.
+pub fn observe(info: &Info<'_>) {
+    let (repo, query) = current_query();
+    histograms()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(repo)
+        .or_default()
+        .observe(info.elapsed);
+
+    let elapsed_ms = info.elapsed.as_millis() as u64;
+    let threshold = settings().database.slow_query_threshold;
+    if threshold > 0 && elapsed_ms >= threshold {
+        warn!(
+            repo,
+            query,
+            elapsed_ms,
+            failed = info.failed,
+            "slow query: {}",
+            info.statement.sql
+        );
+    } else {
+        debug!(repo, query, elapsed_ms, failed = info.failed, "query");
+    }
+}
+
+/// Returns the statement duration histograms per repo.
+pub fn snapshot() -> BTreeMap<&'static str, QueryHistogram> {
+    histograms()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe() {
+        let mut histogram = QueryHistogram::default();
+        histogram.observe(Duration::from_micros(800));
+        histogram.observe(Duration::from_millis(30));
+        histogram.observe(Duration::from_secs(2));
+        assert_eq!(histogram.buckets, [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(histogram.sum, Duration::from_micros(2_030_800));
+    }
+}
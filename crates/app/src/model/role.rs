@@ -0,0 +1,174 @@
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::data_providers::user::ROLES;
+use crate::entity;
+
+pub use crate::data_providers::user::{
+    ROLE_ADMIN as ADMIN, ROLE_MAINTAINER as MAINTAINER, ROLE_VIEWER as VIEWER,
+};
+
+pub type Role = entity::role::Model;
+pub type RoleCreateDto = entity::role::CreateModel;
+
+// Users get access to a product through a role for that product. Viewers can see the crashes,
+// issues, versions and symbols of the product, maintainers can also change them, and admins
+// can also change the settings of the product, its webhooks and its device keys. Users with
+// `is_admin` set have access to all products.
+
+/// Returns the names of the roles that can be assigned, from most to least privileged.
+fn names() -> [&'static str; 3] {
+    ROLES.map(|(name, _)| name)
+}
+
+impl HasId for entity::role::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns the roles that grant at least the permissions of `role`.
+pub fn at_least(role: &str) -> Vec<String> {
+    let names = names();
+    let rank = names
+        .iter()
+        .position(|name| *name == role)
+        .unwrap_or(names.len() - 1);
+    names[..=rank].iter().map(|name| name.to_string()).collect()
+}
+
+pub struct RoleRepo;
+impl RoleRepo {
+    /// Returns the roles of the user with their product, ordered by product.
+    #[instrument(skip_all)]
+    pub async fn get_by_user(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+    ) -> Result<Vec<(Role, Option<entity::product::Model>)>, DbErr> {
+        entity::prelude::Role::find()
+            .filter(entity::role::Column::UserId.eq(user_id))
+            .find_also_related(entity::prelude::Product)
+            .order_by_asc(entity::product::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Gives the user `role` for the product, replacing the role the user had for it. Other
+    /// roles, such as `product-manage`, are kept.
+    #[instrument(skip_all)]
+    pub async fn assign(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+        product_id: uuid::Uuid,
+        role: &str,
+    ) -> Result<uuid::Uuid, DbErr> {
+        if !names().contains(&role) {
+            return Err(DbErr::Custom(format!("unknown role '{}'", role)));
+        }
+        let txn = db.begin().await?;
+        Self::delete(&txn, user_id, product_id).await?;
+        let role = RoleCreateDto {
+            name: role.to_owned(),
+            user_id,
+            product_id: Some(product_id),
+        }
+        .into_active_model()
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(role.id)
+    }
+
+    /// Removes the role the user had for the product.
+    #[instrument(skip_all)]
+    pub async fn revoke(
+        db: &DbConn,
+        user_id: uuid::Uuid,
+        product_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        Self::delete(db, user_id, product_id).await
+    }
+
+    async fn delete<C: ConnectionTrait>(
+        db: &C,
+        user_id: uuid::Uuid,
+        product_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        entity::prelude::Role::delete_many()
+            .filter(entity::role::Column::UserId.eq(user_id))
+            .filter(entity::role::Column::ProductId.eq(product_id))
+            .filter(entity::role::Column::Name.is_in(names()))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::user::{UserCreateDto, UserRepo};
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_at_least() {
+        assert_eq!(at_least(ADMIN), vec!["admin"]);
+        assert_eq!(at_least(MAINTAINER), vec!["admin", "maintainer"]);
+        assert_eq!(at_least(VIEWER), vec!["admin", "maintainer", "viewer"]);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_assign() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let user_id = Repo::create(
+            &db,
+            UserCreateDto {
+                username: "maintainer".to_owned(),
+                is_admin: false,
+                last_authenticated: None,
+                deactivated_at: None,
+            },
+        )
+        .await
+        .unwrap();
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+
+        assert!(RoleRepo::assign(&db, user_id, product_id, "owner")
+            .await
+            .is_err());
+        RoleRepo::assign(&db, user_id, product_id, VIEWER)
+            .await
+            .unwrap();
+        RoleRepo::assign(&db, user_id, product_id, MAINTAINER)
+            .await
+            .unwrap();
+        assert!(UserRepo::has_role(&db, user_id, product_id, VIEWER)
+            .await
+            .unwrap());
+        assert!(!UserRepo::has_role(&db, user_id, product_id, ADMIN)
+            .await
+            .unwrap());
+        let roles = RoleRepo::get_by_user(&db, user_id).await.unwrap();
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].0.name, MAINTAINER);
+        assert_eq!(
+            roles[0].1.as_ref().map(|product| product.id),
+            Some(product_id)
+        );
+
+        RoleRepo::revoke(&db, user_id, product_id).await.unwrap();
+        assert!(RoleRepo::get_by_user(&db, user_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use sea_orm::*;
+use tracing::instrument;
+
+use crate::build_id::debug_id_from_elf_build_id;
+use crate::entity;
+
+// The search box in the navbar looks up crashes by id, issues by a fragment of their signature
+// and symbols by a fragment of their build id or module, or by the ELF build ID of the module,
+// over the products the user has a role for. Products are passed as `None` for admins, who can
+// see all products.
+
+/// Maximum number of results per kind.
+pub const MAX_RESULTS: u64 = 10;
+
+type WithProduct<M> = (M, Option<entity::product::Model>);
+
+/// Returns the build id fragment in the form build ids are stored in, see
+/// `build_id::normalize_debug_id`, e.g. `3F2504E0` for `3f2504e0-`.
+pub fn build_id_fragment(fragment: &str) -> String {
+    fragment.trim().replace('-', "").to_uppercase()
+}
+
+pub struct SearchRepo;
+impl SearchRepo {
+    #[instrument(skip_all)]
+    pub async fn crash_by_id(
+        db: &DbConn,
+        id: uuid::Uuid,
+        products: Option<&[uuid::Uuid]>,
+    ) -> Result<Option<WithProduct<entity::crash::Model>>, DbErr> {
+        let mut query = entity::prelude::Crash::find_by_id(id);
+        if let Some(products) = products {
+            query = query.filter(entity::crash::Column::ProductId.is_in(products.to_vec()));
+        }
+        query
+            .find_also_related(entity::prelude::Product)
+            .one(db)
+            .await
+    }
+
+    /// Returns the issues whose signature contains `fragment`, most recently seen first.
+    #[instrument(skip_all)]
+    pub async fn issues_by_signature(
+        db: &DbConn,
+        fragment: &str,
+        products: Option<&[uuid::Uuid]>,
+    ) -> Result<Vec<WithProduct<entity::issue::Model>>, DbErr> {
+        let mut query = entity::prelude::Issue::find()
+            .filter(entity::issue::Column::Signature.contains(fragment.trim()));
+        if let Some(products) = products {
+            query = query.filter(entity::issue::Column::ProductId.is_in(products.to_vec()));
+        }
+        query
+            .find_also_related(entity::prelude::Product)
+            .order_by_desc(entity::issue::Column::LastSeen)
+            .limit(MAX_RESULTS)
+            .all(db)
+            .await
+    }
+
+    /// Returns the symbols whose build id or module contains `fragment`, or whose build id is the
+    /// debug id of the ELF build ID `fragment`, newest first.
+    #[instrument(skip_all)]
+    pub async fn symbols_by_id(
+        db: &DbConn,
+        fragment: &str,
+        products: Option<&[uuid::Uuid]>,
+    ) -> Result<Vec<WithProduct<entity::symbols::Model>>, DbErr> {
+        let mut condition = Condition::any()
+            .add(entity::symbols::Column::BuildId.contains(build_id_fragment(fragment)))
+            .add(entity::symbols::Column::ModuleId.contains(fragment.trim()));
+        if let Some(build_id) = debug_id_from_elf_build_id(fragment) {
+            condition = condition.add(entity::symbols::Column::BuildId.eq(build_id));
+        }
+        let mut query = entity::prelude::Symbols::find().filter(condition);
+        if let Some(products) = products {
+            query = query.filter(entity::symbols::Column::ProductId.is_in(products.to_vec()));
+        }
+        query
+            .find_also_related(entity::prelude::Product)
+            .order_by_desc(entity::symbols::Column::CreatedAt)
+            .limit(MAX_RESULTS)
+            .all(db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::symbols::SymbolsCreateDto;
+    use crate::model::version::VersionCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_build_id_fragment() {
+        assert_eq!(build_id_fragment(" 3f2504e0-4f89 "), "3F2504E04F89");
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_symbols_by_id() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product = |name: &str| ProductCreateDto::named(name);
+        let mut symbols = vec![];
+        for (name, module_id) in [("Workrave", "workrave.pdb"), ("Other", "other.pdb")] {
+            let product_id = Repo::create(&db, product(name)).await.unwrap();
+            let version_id = Repo::create(
+                &db,
+                VersionCreateDto {
+                    name: "1.11.0".to_owned(),
+                    hash: "".to_owned(),
+                    tag: "v1.11.0".to_owned(),
+                    product_id,
+                    channel: None,
+                    release_date: None,
+                },
+            )
+            .await
+            .unwrap();
+            symbols.push(
+                Repo::create(
+                    &db,
+                    SymbolsCreateDto {
+                        os: "windows".to_owned(),
+                        arch: "x86_64".to_owned(),
+                        build_id: "3F2504E04F8911D39A0C0305E82C33011".to_owned(),
+                        module_id: module_id.to_owned(),
+                        file_location: format!("symbols/{}", module_id),
+                        file_size: None,
+                        product_id,
+                        version_id,
+                        quarantine_reason: None,
+                    },
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let workrave = Repo::get_by_id::<entity::symbols::Entity>(&db, symbols[0])
+            .await
+            .unwrap()
+            .unwrap()
+            .product_id;
+
+        let found = SearchRepo::symbols_by_id(&db, "3f2504e0-4f89", None)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        let found = SearchRepo::symbols_by_id(&db, "3f2504e0-4f89", Some(&[workrave]))
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].1.as_ref().map(|p| p.name.as_str()),
+            Some("Workrave")
+        );
+        let found = SearchRepo::symbols_by_id(&db, "other", None).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.id, symbols[1]);
+    }
+}
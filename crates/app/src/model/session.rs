@@ -0,0 +1,115 @@
+use crate::entity;
+use chrono::NaiveDateTime;
+use sea_orm::*;
+use tracing::instrument;
+
+/// Sessions of the web interface, stored by the session store of the server. Stale sessions are
+/// deleted by the nightly maintenance: expired sessions, and sessions without expiry that were
+/// not saved for a long time.
+pub struct SessionRepo;
+impl SessionRepo {
+    /// Deletes one batch of sessions that expired before `now`, or that have no expiry and were
+    /// last saved before `abandoned_before`. Returns the number of deleted sessions.
+    #[instrument(skip_all)]
+    pub async fn delete_stale(
+        db: &DbConn,
+        now: NaiveDateTime,
+        abandoned_before: NaiveDateTime,
+        batch_size: u64,
+    ) -> Result<u64, DbErr> {
+        let ids: Vec<String> = entity::prelude::Session::find()
+            .select_only()
+            .column(entity::session::Column::Id)
+            .filter(
+                Condition::any()
+                    .add(entity::session::Column::ExpiresAt.lt(now))
+                    .add(
+                        Condition::all()
+                            .add(entity::session::Column::ExpiresAt.is_null())
+                            .add(entity::session::Column::UpdatedAt.lt(abandoned_before)),
+                    ),
+            )
+            .limit(batch_size)
+            .into_tuple()
+            .all(db)
+            .await?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = entity::prelude::Session::delete_many()
+            .filter(entity::session::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity;
+    use crate::model::session::SessionRepo;
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveValue::Set, Database, DatabaseConnection, EntityTrait};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_stale() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let now = chrono::Utc::now().naive_utc();
+        let day = chrono::Duration::days(1);
+
+        let sessions = [
+            ("expired", Some(now - day), now),
+            ("active", Some(now + day), now - day * 60),
+            ("abandoned", None, now - day * 60),
+            ("recent", None, now),
+        ];
+        for (id, expires_at, updated_at) in sessions {
+            let session = entity::session::ActiveModel {
+                id: Set(id.to_owned()),
+                expires_at: Set(expires_at),
+                data: Set(vec![]),
+                created_at: Set(updated_at),
+                updated_at: Set(updated_at),
+            };
+            entity::prelude::Session::insert(session)
+                .exec_without_returning(&db)
+                .await
+                .unwrap();
+        }
+
+        // Batches are deleted until none are left.
+        assert_eq!(
+            SessionRepo::delete_stale(&db, now, now - day * 30, 1)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            SessionRepo::delete_stale(&db, now, now - day * 30, 10)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            SessionRepo::delete_stale(&db, now, now - day * 30, 10)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let mut left: Vec<String> = entity::prelude::Session::find()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["active", "recent"]);
+    }
+}
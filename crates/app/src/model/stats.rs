@@ -0,0 +1,280 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use sea_orm::*;
+use sea_query::Expr;
+use tracing::instrument;
+
+use crate::data_providers::issue::QUIET_STATUSES;
+use crate::data_providers::stats::{CrashCount, CrashStats, SignatureCount, StatsGroup};
+use crate::entity;
+
+// Crash statistics for the dashboard and the statistics API, aggregated by the database. Crashes
+// are grouped by the day they were reported (UTC), the name of their version and the release
+// channel of their version. Crashes are dated by the server when it receives them, never by the
+// clock of the client.
+
+/// Day of a crash as `YYYY-MM-DD`, the same on PostgreSQL and SQLite.
+const DAY: &str = "CAST(DATE(crash.created_at) AS TEXT)";
+
+fn crashes(
+    products: Option<Vec<uuid::Uuid>>,
+    since: NaiveDateTime,
+) -> Select<entity::crash::Entity> {
+    let mut query =
+        entity::prelude::Crash::find().filter(entity::crash::Column::CreatedAt.gte(since));
+    if let Some(products) = products {
+        query = query.filter(entity::crash::Column::ProductId.is_in(products));
+    }
+    query
+}
+
+pub struct StatsRepo;
+impl StatsRepo {
+    /// Counts the crashes of `products`, `None` for all products, reported since `since`, per
+    /// combination of `groups`. Without groups there is a single count.
+    #[instrument(skip_all)]
+    pub async fn crash_counts(
+        db: &DbConn,
+        products: Option<Vec<uuid::Uuid>>,
+        since: NaiveDateTime,
+        groups: &[StatsGroup],
+    ) -> Result<Vec<CrashCount>, DbErr> {
+        let mut query = crashes(products, since).select_only();
+        if groups.contains(&StatsGroup::Day) {
+            query = query
+                .column_as(Expr::cust(DAY), "day")
+                .group_by(Expr::cust(DAY));
+        } else {
+            query = query.column_as(Expr::cust("NULL"), "day");
+        }
+        if groups.contains(&StatsGroup::Version) || groups.contains(&StatsGroup::Channel) {
+            query = query.join(JoinType::InnerJoin, entity::crash::Relation::Version.def());
+        }
+        for (group, column, name) in [
+            (
+                StatsGroup::Version,
+                entity::version::Column::Name,
+                "version",
+            ),
+            (
+                StatsGroup::Channel,
+                entity::version::Column::Channel,
+                "channel",
+            ),
+        ] {
+            if groups.contains(&group) {
+                query = query.column_as(column, name).group_by(column);
+            } else {
+                query = query.column_as(Expr::cust("NULL"), name);
+            }
+        }
+
+        let mut counts: Vec<CrashCount> = query
+            .column_as(entity::crash::Column::Id.count(), "count")
+            .into_tuple::<(Option<String>, Option<String>, Option<String>, i64)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(day, version, channel, count)| CrashCount {
+                day: day.and_then(|day| NaiveDate::parse_from_str(&day, "%Y-%m-%d").ok()),
+                version,
+                channel,
+                count,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            a.day
+                .cmp(&b.day)
+                .then_with(|| b.count.cmp(&a.count))
+                .then_with(|| a.version.cmp(&b.version))
+                .then_with(|| a.channel.cmp(&b.channel))
+        });
+        Ok(counts)
+    }
+
+    /// Returns the `limit` signatures with the most crashes of `products` since `since`. Muted
+    /// and snoozed issues are left out.
+    #[instrument(skip_all)]
+    pub async fn top_signatures(
+        db: &DbConn,
+        products: Option<Vec<uuid::Uuid>>,
+        since: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<SignatureCount>, DbErr> {
+        Ok(crashes(products, since)
+            .select_only()
+            .column(entity::issue::Column::Id)
+            .column(entity::issue::Column::Signature)
+            .column(entity::issue::Column::Title)
+            .column_as(entity::crash::Column::Id.count(), "count")
+            .join(JoinType::InnerJoin, entity::crash::Relation::Issue.def())
+            .filter(entity::issue::Column::Status.is_not_in(QUIET_STATUSES))
+            .group_by(entity::issue::Column::Id)
+            .group_by(entity::issue::Column::Signature)
+            .group_by(entity::issue::Column::Title)
+            .order_by_desc(entity::crash::Column::Id.count())
+            .order_by_asc(entity::issue::Column::Signature)
+            .limit(limit)
+            .into_tuple::<(uuid::Uuid, String, String, i64)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(issue_id, signature, title, count)| SignatureCount {
+                issue_id,
+                signature,
+                title,
+                count,
+            })
+            .collect())
+    }
+
+    /// Returns the total, the counts per `groups` and the top `limit` signatures of the crashes
+    /// of `products` since `since`.
+    #[instrument(skip_all)]
+    pub async fn crash_stats(
+        db: &DbConn,
+        products: Option<Vec<uuid::Uuid>>,
+        since: NaiveDateTime,
+        groups: &[StatsGroup],
+        limit: u64,
+    ) -> Result<CrashStats, DbErr> {
+        Ok(CrashStats {
+            since,
+            total: crashes(products.clone(), since).count(db).await?,
+            counts: Self::crash_counts(db, products.clone(), since, groups).await?,
+            top_signatures: Self::top_signatures(db, products, since, limit).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_providers::stats::parse_group_by;
+    use crate::model::base::Repo;
+    use crate::model::issue::IssueRepo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::version::VersionCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_parse_group_by() {
+        assert_eq!(
+            parse_group_by("day, version,day"),
+            Ok(vec![StatsGroup::Day, StatsGroup::Version])
+        );
+        assert_eq!(parse_group_by(""), Ok(vec![]));
+        assert_eq!(parse_group_by("day,os"), Err("os".to_owned()));
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_crash_stats() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let mut versions = vec![];
+        for (name, channel) in [("1.11.0", Some("stable")), ("1.12.0-beta1", Some("beta"))] {
+            versions.push(
+                Repo::create(
+                    &db,
+                    VersionCreateDto {
+                        name: name.to_owned(),
+                        hash: "".to_owned(),
+                        tag: format!("v{}", name),
+                        product_id,
+                        channel: channel.map(str::to_owned),
+                        release_date: None,
+                    },
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let (heartbeat, _) = IssueRepo::record(&db, product_id, "Core::heartbeat", "heartbeat")
+            .await
+            .unwrap();
+        let (timer, _) = IssueRepo::record(&db, product_id, "Timer::tick", "tick")
+            .await
+            .unwrap();
+
+        let day = |day: u32| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+        let crashes = [
+            (day(19), versions[0], Some(heartbeat)),
+            (day(20), versions[0], Some(heartbeat)),
+            (day(20), versions[1], Some(heartbeat)),
+            (day(20), versions[1], Some(timer)),
+            (day(21), versions[1], None),
+            // Before the statistics period.
+            (day(1), versions[0], Some(timer)),
+        ];
+        for (day, version_id, issue_id) in crashes {
+            let created_at = day.and_hms_opt(12, 30, 0).unwrap();
+            entity::crash::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                created_at: Set(created_at),
+                updated_at: Set(created_at),
+                summary: Set("crash".to_owned()),
+                report: Set(serde_json::json!({})),
+                version_id: Set(version_id),
+                product_id: Set(product_id),
+                issue_id: Set(issue_id),
+                authenticated: Set(false),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let since = day(15).and_hms_opt(0, 0, 0).unwrap();
+        let stats = StatsRepo::crash_stats(&db, None, since, &[StatsGroup::Day], 1)
+            .await
+            .unwrap();
+        assert_eq!(stats.total, 5);
+        assert_eq!(
+            stats
+                .counts
+                .iter()
+                .map(|count| (count.day.unwrap(), count.count))
+                .collect::<Vec<_>>(),
+            vec![(day(19), 1), (day(20), 3), (day(21), 1)]
+        );
+        assert_eq!(stats.top_signatures.len(), 1);
+        assert_eq!(stats.top_signatures[0].signature, "Core::heartbeat");
+        assert_eq!(stats.top_signatures[0].count, 3);
+
+        let counts = StatsRepo::crash_counts(
+            &db,
+            Some(vec![product_id]),
+            since,
+            &[StatsGroup::Version, StatsGroup::Channel],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            counts
+                .iter()
+                .map(|count| (
+                    count.day,
+                    count.version.as_deref(),
+                    count.channel.as_deref(),
+                    count.count
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (None, Some("1.12.0-beta1"), Some("beta"), 3),
+                (None, Some("1.11.0"), Some("stable"), 2),
+            ]
+        );
+
+        let counts = StatsRepo::crash_counts(&db, Some(vec![]), since, &[])
+            .await
+            .unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].count, 0);
+    }
+}
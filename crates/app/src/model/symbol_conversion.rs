@@ -0,0 +1,150 @@
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::entity;
+
+pub type SymbolConversion = entity::symbol_conversion::Model;
+pub type SymbolConversionCreateDto = entity::symbol_conversion::CreateModel;
+
+// Native debug files, such as PDB files and ELF or Mach-O files with DWARF debug info, are
+// uploaded as is and converted to Breakpad symbols in the background. The uploaded file is kept
+// next to the converted symbols, so that it can be converted again, e.g. with a newer dump_syms.
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_CONVERTED: &str = "converted";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Maximum length of the error of a failed conversion, in characters.
+pub const MAX_ERROR_LENGTH: usize = 2000;
+
+impl HasId for entity::symbol_conversion::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct SymbolConversionRepo;
+impl SymbolConversionRepo {
+    /// Returns the oldest conversions that have not run yet.
+    #[instrument(skip_all)]
+    pub async fn get_pending(db: &DbConn, limit: u64) -> Result<Vec<SymbolConversion>, DbErr> {
+        entity::prelude::SymbolConversion::find()
+            .filter(entity::symbol_conversion::Column::Status.eq(STATUS_PENDING))
+            .order_by_asc(entity::symbol_conversion::Column::CreatedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// Marks a conversion as done, with the symbols it produced.
+    #[instrument(skip_all)]
+    pub async fn converted(
+        db: &DbConn,
+        id: uuid::Uuid,
+        symbols_id: uuid::Uuid,
+    ) -> Result<(), DbErr> {
+        Self::finish(db, id, STATUS_CONVERTED, Some(symbols_id), None).await
+    }
+
+    /// Marks a conversion as failed. It is not retried.
+    #[instrument(skip_all)]
+    pub async fn failed(db: &DbConn, id: uuid::Uuid, error: &str) -> Result<(), DbErr> {
+        let error = error.chars().take(MAX_ERROR_LENGTH).collect();
+        Self::finish(db, id, STATUS_FAILED, None, Some(error)).await
+    }
+
+    async fn finish(
+        db: &DbConn,
+        id: uuid::Uuid,
+        status: &str,
+        symbols_id: Option<uuid::Uuid>,
+        error: Option<String>,
+    ) -> Result<(), DbErr> {
+        entity::prelude::SymbolConversion::update_many()
+            .col_expr(
+                entity::symbol_conversion::Column::Status,
+                Expr::value(status),
+            )
+            .col_expr(
+                entity::symbol_conversion::Column::SymbolsId,
+                Expr::value(symbols_id),
+            )
+            .col_expr(entity::symbol_conversion::Column::Error, Expr::value(error))
+            .col_expr(
+                entity::symbol_conversion::Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::symbol_conversion::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::version::VersionCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_conversion() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let version_id = Repo::create(
+            &db,
+            VersionCreateDto {
+                name: "1.11.0".to_owned(),
+                hash: "".to_owned(),
+                tag: "v1.11.0".to_owned(),
+                product_id,
+                channel: None,
+                release_date: None,
+            },
+        )
+        .await
+        .unwrap();
+        let conversion = |file_name: &str| SymbolConversionCreateDto {
+            file_name: file_name.to_owned(),
+            file_location: format!("symbols/native/{}", file_name),
+            file_size: 1024,
+            status: STATUS_PENDING.to_owned(),
+            error: None,
+            symbols_id: None,
+            product_id,
+            version_id,
+        };
+
+        let failed_id = Repo::create(&db, conversion("workrave.pdb")).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        Repo::create(&db, conversion("libworkrave.so"))
+            .await
+            .unwrap();
+        let pending = SymbolConversionRepo::get_pending(&db, 10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].id, failed_id);
+
+        SymbolConversionRepo::failed(&db, failed_id, &"x".repeat(MAX_ERROR_LENGTH + 10))
+            .await
+            .unwrap();
+        let pending = SymbolConversionRepo::get_pending(&db, 10).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].file_name, "libworkrave.so");
+
+        let failed = Repo::get_by_id::<entity::symbol_conversion::Entity>(&db, failed_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.status, STATUS_FAILED);
+        assert_eq!(failed.error.unwrap().len(), MAX_ERROR_LENGTH);
+    }
+}
@@ -1,5 +1,10 @@
+use std::path::{Path, PathBuf};
+
 use super::base::HasId;
 use crate::entity;
+use crate::settings::settings;
+use sea_orm::*;
+use tracing::instrument;
 
 pub type Symbols = entity::symbols::Model;
 pub type SymbolsCreateDto = entity::symbols::CreateModel;
@@ -10,3 +15,290 @@ impl HasId for entity::symbols::Model {
         self.id
     }
 }
+
+/// Directory the symbol supplier reads symbol files from when processing minidumps.
+pub fn symbols_dir() -> PathBuf {
+    Path::new(&settings().server.base_path).join("symbols")
+}
+
+/// Quarantined symbol files are kept outside of `symbols_dir`, so that processing never uses
+/// them.
+pub fn quarantine_dir() -> PathBuf {
+    Path::new(&settings().server.base_path)
+        .join("quarantine")
+        .join("symbols")
+}
+
+/// Uploaded native debug files are kept below this directory, see `symbol_conversion`.
+pub fn native_symbols_dir() -> PathBuf {
+    Path::new(&settings().server.base_path).join("native-symbols")
+}
+
+/// Location of a symbol file below `dir`, in the layout the symbol supplier expects.
+pub fn symbols_file(dir: &Path, module_id: &str, build_id: &str) -> PathBuf {
+    dir.join(module_id)
+        .join(build_id)
+        .join(module_id.replace(".pdb", ".sym"))
+}
+
+pub struct SymbolsRepo;
+impl SymbolsRepo {
+    #[instrument(skip_all)]
+    pub async fn get_quarantined(
+        db: &DatabaseConnection,
+        product_id: Option<uuid::Uuid>,
+    ) -> Result<Vec<entity::symbols::Model>, DbErr> {
+        let mut query = entity::prelude::Symbols::find()
+            .filter(entity::symbols::Column::QuarantineReason.is_not_null());
+        if let Some(product_id) = product_id {
+            query = query.filter(entity::symbols::Column::ProductId.eq(product_id));
+        }
+        query
+            .order_by_asc(entity::symbols::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Marks quarantined symbols as reviewed, now stored at `file_location`.
+    #[instrument(skip_all)]
+    pub async fn release(
+        db: &DatabaseConnection,
+        id: uuid::Uuid,
+        file_location: String,
+    ) -> Result<(), DbErr> {
+        entity::prelude::Symbols::update_many()
+            .col_expr(
+                entity::symbols::Column::FileLocation,
+                sea_query::Expr::value(file_location),
+            )
+            .col_expr(
+                entity::symbols::Column::QuarantineReason,
+                sea_query::Expr::value(Option::<String>::None),
+            )
+            .col_expr(
+                entity::symbols::Column::UpdatedAt,
+                sea_query::Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(entity::symbols::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the symbols that are not referenced by any crash reported in the last `days`
+    /// days, optionally limited to a single product, ordered by creation.
+    #[instrument(skip_all)]
+    pub async fn get_orphaned(
+        db: &DatabaseConnection,
+        product_id: Option<uuid::Uuid>,
+        days: i64,
+    ) -> Result<Vec<entity::symbols::Model>, DbErr> {
+        let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(days);
+        let mut query = entity::prelude::Symbols::find().filter(not_referenced(
+            db.get_database_backend(),
+            product_id,
+            since,
+        ));
+        if let Some(product_id) = product_id {
+            query = query.filter(entity::symbols::Column::ProductId.eq(product_id));
+        }
+
+        // Every symbols file is checked against the modules of all recent crashes, so the orphans
+        // are fetched in batches, each continuing after the last orphan of the previous one.
+        let mut orphaned: Vec<entity::symbols::Model> = vec![];
+        loop {
+            let mut batch = query.clone();
+            if let Some(last) = orphaned.last() {
+                batch = batch.filter(
+                    Condition::any()
+                        .add(entity::symbols::Column::CreatedAt.gt(last.created_at))
+                        .add(
+                            Condition::all()
+                                .add(entity::symbols::Column::CreatedAt.eq(last.created_at))
+                                .add(entity::symbols::Column::Id.gt(last.id)),
+                        ),
+                );
+            }
+            let batch = batch
+                .order_by_asc(entity::symbols::Column::CreatedAt)
+                .order_by_asc(entity::symbols::Column::Id)
+                .limit(ORPHANED_BATCH)
+                .all(db)
+                .await?;
+            let done = (batch.len() as u64) < ORPHANED_BATCH;
+            orphaned.extend(batch);
+            if done {
+                return Ok(orphaned);
+            }
+        }
+    }
+}
+
+/// Number of orphaned symbols fetched per query by `SymbolsRepo::get_orphaned`.
+pub const ORPHANED_BATCH: u64 = 500;
+
+/// Condition that holds for symbols whose module is not listed in the report of any crash
+/// created since `since`, of the product if given. Module names are compared without the
+/// directory the module was built in, and debug ids in upper case without dashes, the form in
+/// which symbols are stored.
+fn not_referenced(
+    backend: DbBackend,
+    product_id: Option<uuid::Uuid>,
+    since: chrono::NaiveDateTime,
+) -> sea_query::SimpleExpr {
+    let (modules, field) = match backend {
+        DbBackend::Postgres => (
+            r#"jsonb_array_elements("crash"."report" -> 'modules') AS "module"("value")"#,
+            r#""module"."value" ->> '{}'"#,
+        ),
+        _ => (
+            r#"json_each("crash"."report", '$.modules') AS "module""#,
+            r#"json_extract("module"."value", '$.{}')"#,
+        ),
+    };
+    let debug_file = field.replace("{}", "debug_file");
+    let debug_id = field.replace("{}", "debug_id");
+    let product = match product_id {
+        Some(_) => r#"AND "crash"."product_id" = ?"#,
+        None => "",
+    };
+    let sql = format!(
+        r#"NOT EXISTS (SELECT 1 FROM "crash", {modules}
+            WHERE "crash"."created_at" >= ? {product}
+            AND replace(upper({debug_id}), '-', '') = "symbols"."build_id"
+            AND ({debug_file} = "symbols"."module_id"
+                OR substr({debug_file}, length({debug_file}) - length("symbols"."module_id"))
+                    IN ('/' || "symbols"."module_id", '\' || "symbols"."module_id")))"#
+    );
+    let mut values: Vec<Value> = vec![since.into()];
+    values.extend(product_id.map(Value::from));
+    sea_query::Expr::cust_with_values(sql, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{symbols_file, SymbolsRepo};
+    use crate::entity;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use crate::model::version::VersionCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::*;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_orphaned() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let version_id = Repo::create(
+            &db,
+            VersionCreateDto {
+                name: "1.11.0".to_owned(),
+                hash: "".to_owned(),
+                tag: "v1.11.0".to_owned(),
+                product_id,
+                channel: None,
+                release_date: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let now = chrono::Utc::now().naive_utc();
+        let modules = [
+            ("workrave.pdb", "ABCDEF0123456789ABCDEF01234567891"),
+            ("harpoon.pdb", "0123456789ABCDEF0123456789ABCDEF1"),
+            ("unused.pdb", "0123456789ABCDEF0123456789ABCDEF1"),
+        ];
+        for (age, (module_id, build_id)) in modules.into_iter().enumerate() {
+            let created_at = now - chrono::Duration::minutes(age as i64);
+            entity::symbols::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                created_at: Set(created_at),
+                updated_at: Set(created_at),
+                os: Set("windows".to_owned()),
+                arch: Set("x86_64".to_owned()),
+                build_id: Set(build_id.to_owned()),
+                module_id: Set(module_id.to_owned()),
+                file_location: Set(format!("{}/{}", module_id, build_id)),
+                product_id: Set(product_id),
+                version_id: Set(version_id),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let crashes = [
+            (
+                now,
+                serde_json::json!({ "modules": [
+                    { "debug_file": "C:\\build\\workrave.pdb", "debug_id": "abcdef01-2345-6789-abcd-ef0123456789-1" },
+                    { "debug_file": "unused.pdb" }
+                ]}),
+            ),
+            // Processing of the minidump has not finished yet.
+            (now, serde_json::Value::Null),
+            // Too old to keep its modules.
+            (
+                now - chrono::Duration::days(60),
+                serde_json::json!({ "modules": [
+                    { "debug_file": "harpoon.pdb", "debug_id": "0123456789ABCDEF0123456789ABCDEF1" }
+                ]}),
+            ),
+        ];
+        for (created_at, report) in crashes {
+            entity::crash::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                created_at: Set(created_at),
+                updated_at: Set(created_at),
+                summary: Set("crash".to_owned()),
+                report: Set(report),
+                version_id: Set(version_id),
+                product_id: Set(product_id),
+                authenticated: Set(false),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let orphaned = |symbols: Vec<entity::symbols::Model>| {
+            symbols
+                .into_iter()
+                .map(|symbols| symbols.module_id)
+                .collect::<Vec<_>>()
+        };
+        let symbols = SymbolsRepo::get_orphaned(&db, None, 30).await.unwrap();
+        assert_eq!(orphaned(symbols), vec!["unused.pdb", "harpoon.pdb"]);
+        let symbols = SymbolsRepo::get_orphaned(&db, Some(product_id), 30)
+            .await
+            .unwrap();
+        assert_eq!(orphaned(symbols), vec!["unused.pdb", "harpoon.pdb"]);
+        let symbols = SymbolsRepo::get_orphaned(&db, Some(uuid::Uuid::new_v4()), 30)
+            .await
+            .unwrap();
+        assert!(symbols.is_empty());
+    }
+
+    #[test]
+    fn test_symbols_file() {
+        assert_eq!(
+            symbols_file(
+                std::path::Path::new("symbols"),
+                "workrave.pdb",
+                "ABCDEF0123456789ABCDEF01234567891"
+            ),
+            std::path::Path::new(
+                "symbols/workrave.pdb/ABCDEF0123456789ABCDEF01234567891/workrave.sym"
+            )
+        );
+    }
+}
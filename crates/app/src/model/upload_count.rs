@@ -0,0 +1,159 @@
+use chrono::NaiveDate;
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::HasId;
+use crate::entity;
+
+pub type UploadCount = entity::upload_count::Model;
+
+// Products can limit the number of uploads per day, see `UploadQuota`. Uploads are counted per
+// product and UTC day. Counts of past days are removed by the nightly maintenance, which resets
+// the counters.
+
+impl HasId for entity::upload_count::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct UploadCountRepo;
+impl UploadCountRepo {
+    /// Returns the number of uploads of the product on `day`.
+    #[instrument(skip_all)]
+    pub async fn get(db: &DbConn, product_id: uuid::Uuid, day: NaiveDate) -> Result<i32, DbErr> {
+        Ok(entity::prelude::UploadCount::find()
+            .filter(entity::upload_count::Column::ProductId.eq(product_id))
+            .filter(entity::upload_count::Column::Day.eq(day))
+            .one(db)
+            .await?
+            .map_or(0, |count| count.count))
+    }
+
+    /// Counts an upload of the product on `day`, unless the product already had `max` uploads
+    /// that day. Returns whether the upload was counted.
+    #[instrument(skip_all)]
+    pub async fn record(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        day: NaiveDate,
+        max: Option<i32>,
+    ) -> Result<bool, DbErr> {
+        if max.is_some_and(|max| max <= 0) {
+            return Ok(false);
+        }
+        let now = chrono::Utc::now().naive_utc();
+        // A single conditional update, so that concurrent uploads cannot exceed the quota.
+        for _ in 0..2 {
+            let mut update = entity::prelude::UploadCount::update_many()
+                .col_expr(
+                    entity::upload_count::Column::Count,
+                    sea_query::Expr::col(entity::upload_count::Column::Count).add(1),
+                )
+                .col_expr(
+                    entity::upload_count::Column::UpdatedAt,
+                    sea_query::Expr::value(now),
+                )
+                .filter(entity::upload_count::Column::ProductId.eq(product_id))
+                .filter(entity::upload_count::Column::Day.eq(day));
+            if let Some(max) = max {
+                update = update.filter(entity::upload_count::Column::Count.lt(max));
+            }
+            if update.exec(db).await?.rows_affected > 0 {
+                return Ok(true);
+            }
+            if Self::get(db, product_id, day).await? > 0 {
+                return Ok(false);
+            }
+
+            let count = entity::upload_count::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                created_at: Set(now),
+                updated_at: Set(now),
+                day: Set(day),
+                count: Set(1),
+                product_id: Set(product_id),
+            };
+            match entity::prelude::UploadCount::insert(count)
+                .exec_without_returning(db)
+                .await
+            {
+                Ok(_) => return Ok(true),
+                // Another upload created the count first, count this one as an update.
+                Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Removes the counts of the days before `day`. Returns the number of removed counts.
+    #[instrument(skip_all)]
+    pub async fn delete_before(db: &DbConn, day: NaiveDate) -> Result<u64, DbErr> {
+        let result = entity::prelude::UploadCount::delete_many()
+            .filter(entity::upload_count::Column::Day.lt(day))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(
+            &db,
+            ProductCreateDto {
+                max_daily_uploads: Some(2),
+                ..ProductCreateDto::named("Workrave")
+            },
+        )
+        .await
+        .unwrap();
+        let yesterday = NaiveDate::from_ymd_opt(2025, 1, 17).unwrap();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 18).unwrap();
+
+        assert!(UploadCountRepo::record(&db, product_id, yesterday, None)
+            .await
+            .unwrap());
+        assert!(UploadCountRepo::record(&db, product_id, today, Some(2))
+            .await
+            .unwrap());
+        assert!(UploadCountRepo::record(&db, product_id, today, Some(2))
+            .await
+            .unwrap());
+        assert!(!UploadCountRepo::record(&db, product_id, today, Some(2))
+            .await
+            .unwrap());
+        assert!(!UploadCountRepo::record(&db, product_id, today, Some(0))
+            .await
+            .unwrap());
+        assert_eq!(
+            UploadCountRepo::get(&db, product_id, today).await.unwrap(),
+            2
+        );
+
+        assert_eq!(UploadCountRepo::delete_before(&db, today).await.unwrap(), 1);
+        assert_eq!(
+            UploadCountRepo::get(&db, product_id, yesterday)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            UploadCountRepo::get(&db, product_id, today).await.unwrap(),
+            2
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use sea_orm::*;
+use tracing::instrument;
+
+use super::base::{HasId, Repo};
+use crate::entity;
+
+pub type UploadQuarantine = entity::upload_quarantine::Model;
+pub type UploadQuarantineCreateDto = entity::upload_quarantine::CreateModel;
+
+impl HasId for entity::upload_quarantine::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct UploadQuarantineRepo;
+impl UploadQuarantineRepo {
+    fn active_condition(now: chrono::NaiveDateTime) -> Condition {
+        Condition::all()
+            .add(entity::upload_quarantine::Column::ExpiresAt.gt(now))
+            .add(entity::upload_quarantine::Column::LiftedAt.is_null())
+    }
+
+    /// Returns the quarantine in effect for any of the sources of an upload.
+    #[instrument(skip_all)]
+    pub async fn find_active(
+        db: &DbConn,
+        sources: &[String],
+    ) -> Result<Option<UploadQuarantine>, DbErr> {
+        if sources.is_empty() {
+            return Ok(None);
+        }
+        entity::prelude::UploadQuarantine::find()
+            .filter(entity::upload_quarantine::Column::Source.is_in(sources.iter().cloned()))
+            .filter(Self::active_condition(chrono::Utc::now().naive_utc()))
+            .order_by_desc(entity::upload_quarantine::Column::ExpiresAt)
+            .one(db)
+            .await
+    }
+
+    /// Returns the quarantines in effect, the most recent first.
+    #[instrument(skip_all)]
+    pub async fn get_active(db: &DbConn) -> Result<Vec<UploadQuarantine>, DbErr> {
+        entity::prelude::UploadQuarantine::find()
+            .filter(Self::active_condition(chrono::Utc::now().naive_utc()))
+            .order_by_desc(entity::upload_quarantine::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Returns all quarantines of the source, including expired and lifted ones.
+    #[instrument(skip_all)]
+    pub async fn get_history(db: &DbConn, source: &str) -> Result<Vec<UploadQuarantine>, DbErr> {
+        entity::prelude::UploadQuarantine::find()
+            .filter(entity::upload_quarantine::Column::Source.eq(source))
+            .order_by_desc(entity::upload_quarantine::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Rejects uploads of the source for `duration` seconds.
+    #[instrument(skip_all)]
+    pub async fn quarantine(
+        db: &DbConn,
+        source: &str,
+        reason: String,
+        product_id: uuid::Uuid,
+        duration: u64,
+    ) -> Result<uuid::Uuid, DbErr> {
+        let expires_at =
+            chrono::Utc::now().naive_utc() + chrono::Duration::seconds(duration as i64);
+        Repo::create(
+            db,
+            UploadQuarantineCreateDto {
+                source: source.to_owned(),
+                reason,
+                expires_at,
+                lifted_at: None,
+                lifted_by: None,
+                product_id,
+            },
+        )
+        .await
+    }
+
+    /// Ends a quarantine before it expires. The quarantine is kept, with the user that lifted
+    /// it, so that it remains visible in the history of the source.
+    #[instrument(skip_all)]
+    pub async fn lift(
+        db: &DbConn,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<UploadQuarantine>, DbErr> {
+        let Some(quarantine) = entity::prelude::UploadQuarantine::find_by_id(id)
+            .filter(entity::upload_quarantine::Column::LiftedAt.is_null())
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let mut active: entity::upload_quarantine::ActiveModel = quarantine.into();
+        active.lifted_at = Set(Some(now));
+        active.lifted_by = Set(Some(user_id));
+        active.updated_at = Set(now);
+        Ok(Some(active.update(db).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_quarantine() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let sources = vec!["device_key:ci".to_owned(), "ip:10.0.0.1".to_owned()];
+
+        assert!(UploadQuarantineRepo::find_active(&db, &sources)
+            .await
+            .unwrap()
+            .is_none());
+
+        let id = UploadQuarantineRepo::quarantine(
+            &db,
+            "ip:10.0.0.1",
+            "too many invalid uploads".to_owned(),
+            product_id,
+            3600,
+        )
+        .await
+        .unwrap();
+        UploadQuarantineRepo::quarantine(&db, "ip:10.0.0.2", "expired".to_owned(), product_id, 0)
+            .await
+            .unwrap();
+
+        let active = UploadQuarantineRepo::find_active(&db, &sources)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(active.id, id);
+        assert_eq!(
+            UploadQuarantineRepo::get_active(&db).await.unwrap().len(),
+            1
+        );
+
+        let user_id = uuid::Uuid::new_v4();
+        let lifted = UploadQuarantineRepo::lift(&db, id, user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lifted.lifted_by, Some(user_id));
+        assert!(UploadQuarantineRepo::lift(&db, id, user_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(UploadQuarantineRepo::find_active(&db, &sources)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            UploadQuarantineRepo::get_history(&db, "ip:10.0.0.1")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use super::base::HasId;
+use super::organization::OrganizationRepo;
+use super::role;
+use crate::entity;
+use sea_orm::*;
+use tracing::instrument;
+
+pub type User = entity::user::Model;
+pub type UserCreateDto = entity::user::CreateModel;
+pub type UserUpdateDto = entity::user::UpdateModel;
+
+impl HasId for entity::user::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+pub struct UserRepo;
+impl UserRepo {
+    /// Returns whether no user has registered yet, so that the installation still has to be set
+    /// up. The first user to register becomes an administrator.
+    #[instrument(skip_all)]
+    pub async fn is_empty(db: &DbConn) -> Result<bool, DbErr> {
+        Ok(entity::prelude::User::find().count(db).await? == 0)
+    }
+
+    /// Deactivated users can no longer log in, their sessions end and their API tokens are
+    /// rejected, but everything they own is kept.
+    #[instrument(skip_all)]
+    pub async fn set_active(db: &DbConn, id: uuid::Uuid, active: bool) -> Result<(), DbErr> {
+        let deactivated_at = (!active).then(|| chrono::Utc::now().naive_utc());
+        entity::prelude::User::update_many()
+            .col_expr(
+                entity::user::Column::DeactivatedAt,
+                sea_query::Expr::value(deactivated_at),
+            )
+            .filter(entity::user::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the user if it exists and is not deactivated. Sessions of the web interface are
+    /// checked against it on every request, see `auth::layer`.
+    #[instrument(skip_all)]
+    pub async fn get_active(db: &DbConn, id: uuid::Uuid) -> Result<Option<User>, DbErr> {
+        entity::prelude::User::find_by_id(id)
+            .filter(entity::user::Column::DeactivatedAt.is_null())
+            .one(db)
+            .await
+    }
+
+    /// Returns whether the user has `role`, or a role with more permissions, for the product.
+    /// Any role for the product, or in the organization of the product, allows viewing it.
+    #[instrument(skip_all)]
+    pub async fn has_role(
+        db: &DbConn,
+        id: uuid::Uuid,
+        product_id: uuid::Uuid,
+        role: &str,
+    ) -> Result<bool, DbErr> {
+        let roles = match role {
+            role::VIEWER => vec![],
+            role => role::at_least(role),
+        };
+        let mut query = entity::prelude::Role::find()
+            .filter(entity::role::Column::UserId.eq(id))
+            .filter(entity::role::Column::ProductId.eq(product_id));
+        if !roles.is_empty() {
+            query = query.filter(entity::role::Column::Name.is_in(roles.clone()));
+        }
+        if query.count(db).await? > 0 {
+            return Ok(true);
+        }
+        let members = entity::prelude::Product::find_by_id(product_id)
+            .filter(
+                entity::product::Column::OrganizationId
+                    .in_subquery(OrganizationRepo::organization_ids(id, &roles)),
+            )
+            .count(db)
+            .await?;
+        Ok(members > 0)
+    }
+
+    /// Deletes a user. Issues assigned to the user are assigned to `reassign_to` when given and
+    /// left unassigned otherwise. API tokens are always revoked, so that they do not keep working
+    /// on behalf of another user; roles and credentials are removed by the cascade.
+    #[instrument(skip_all)]
+    pub async fn delete(
+        db: &DbConn,
+        id: uuid::Uuid,
+        reassign_to: Option<uuid::Uuid>,
+    ) -> Result<(), DbErr> {
+        if reassign_to == Some(id) {
+            return Err(DbErr::Custom(
+                "cannot reassign to the deleted user".to_owned(),
+            ));
+        }
+
+        let txn = db.begin().await?;
+        if let Some(new_assignee) = reassign_to {
+            entity::prelude::User::find_by_id(new_assignee)
+                .one(&txn)
+                .await?
+                .ok_or(DbErr::RecordNotFound("user not found".to_owned()))?;
+        }
+        entity::prelude::Issue::update_many()
+            .col_expr(
+                entity::issue::Column::AssigneeId,
+                sea_query::Expr::value(reassign_to),
+            )
+            .filter(entity::issue::Column::AssigneeId.eq(id))
+            .exec(&txn)
+            .await?;
+        entity::prelude::ApiToken::delete_many()
+            .filter(entity::api_token::Column::UserId.eq(id))
+            .exec(&txn)
+            .await?;
+        entity::prelude::User::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity,
+        model::{
+            api_token::ApiTokenRepo,
+            base::Repo,
+            issue::IssueRepo,
+            product::ProductCreateDto,
+            user::{UserCreateDto, UserRepo},
+        },
+    };
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection, EntityTrait};
+
+    async fn create_user(db: &DatabaseConnection, username: &str) -> uuid::Uuid {
+        let user = UserCreateDto {
+            username: username.to_owned(),
+            is_admin: false,
+            last_authenticated: None,
+            deactivated_at: None,
+        };
+        Repo::create(db, user).await.unwrap()
+    }
+
+    async fn create_issue(db: &DatabaseConnection, assignee: uuid::Uuid) -> uuid::Uuid {
+        let product = ProductCreateDto::named("Workrave");
+        let product_id = Repo::create(db, product).await.unwrap();
+        let (issue, _) = IssueRepo::record(db, product_id, "Timer::tick", "SIGSEGV")
+            .await
+            .unwrap();
+        IssueRepo::set_assignee(db, vec![issue], Some(assignee))
+            .await
+            .unwrap();
+        issue
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_is_empty() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        assert!(UserRepo::is_empty(&db).await.unwrap());
+        create_user(&db, "admin").await;
+        assert!(!UserRepo::is_empty(&db).await.unwrap());
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deactivate_and_delete() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let leaving = create_user(&db, "leaving").await;
+        let staying = create_user(&db, "staying").await;
+        let (_, token) = ApiTokenRepo::create(&db, leaving, "ci".to_owned(), None, None)
+            .await
+            .unwrap();
+
+        UserRepo::set_active(&db, leaving, false).await.unwrap();
+        assert!(ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .is_none());
+        UserRepo::set_active(&db, leaving, true).await.unwrap();
+        assert!(ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .is_some());
+
+        assert!(UserRepo::delete(&db, leaving, Some(leaving)).await.is_err());
+        let issue = create_issue(&db, leaving).await;
+        assert!(UserRepo::get_active(&db, leaving).await.unwrap().is_some());
+        UserRepo::set_active(&db, leaving, false).await.unwrap();
+        assert!(UserRepo::get_active(&db, leaving).await.unwrap().is_none());
+
+        UserRepo::delete(&db, leaving, Some(staying)).await.unwrap();
+        assert!(entity::prelude::User::find_by_id(leaving)
+            .one(&db)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(ApiTokenRepo::authenticate(&db, &token)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(entity::prelude::ApiToken::find()
+            .all(&db)
+            .await
+            .unwrap()
+            .is_empty());
+        let issue = entity::prelude::Issue::find_by_id(issue)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.assignee_id, Some(staying));
+
+        UserRepo::delete(&db, staying, None).await.unwrap();
+        let issue = entity::prelude::Issue::find_by_id(issue.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(issue.assignee_id, None);
+    }
+}
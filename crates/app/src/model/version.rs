@@ -1,11 +1,27 @@
 use super::base::HasId;
 use crate::entity;
 use sea_orm::*;
+use tracing::instrument;
 
 pub type Version = entity::version::Model;
 pub type VersionCreateDto = entity::version::CreateModel;
 pub type VersionUpdateDto = entity::version::UpdateModel;
 
+/// Annotation of a crash holding the tag of the version, used when the version is created for
+/// the crash. Its hash is taken from the `commit` annotation.
+pub const TAG_ANNOTATION: &str = "version_tag";
+
+/// A version to store with `upsert_many`. Fields that are `None` are left as they are when the
+/// version already exists, so that a re-post with only some of the fields keeps the others.
+#[derive(Debug, Clone, Default)]
+pub struct VersionUpsert {
+    pub name: String,
+    pub hash: Option<String>,
+    pub tag: Option<String>,
+    pub channel: Option<String>,
+    pub release_date: Option<chrono::NaiveDateTime>,
+}
+
 impl HasId for entity::version::Model {
     fn id(&self) -> uuid::Uuid {
         self.id
@@ -14,6 +30,7 @@ impl HasId for entity::version::Model {
 
 pub struct VersionRepo;
 impl VersionRepo {
+    #[instrument(skip_all)]
     pub async fn get_by_product_and_name(
         db: &DatabaseConnection,
         product_id: uuid::Uuid,
@@ -30,4 +47,150 @@ impl VersionRepo {
             .map(entity::version::Model::from);
         Ok(version)
     }
+
+    /// Creates the versions of a product, or updates the fields given for them if a version with
+    /// the same name already exists. All versions are stored in a single transaction. Returns the
+    /// ID of each version and whether it was created.
+    #[instrument(skip_all)]
+    pub async fn upsert_many(
+        db: &DatabaseConnection,
+        product_id: uuid::Uuid,
+        versions: Vec<VersionUpsert>,
+    ) -> Result<Vec<(uuid::Uuid, bool)>, DbErr> {
+        let txn = db.begin().await?;
+        let mut result = Vec::with_capacity(versions.len());
+        for version in versions {
+            let existing = entity::prelude::Version::find()
+                .filter(entity::version::Column::ProductId.eq(product_id))
+                .filter(entity::version::Column::Name.eq(version.name.clone()))
+                .one(&txn)
+                .await?;
+            match existing {
+                None => {
+                    let dto = VersionCreateDto {
+                        name: version.name,
+                        hash: version.hash.unwrap_or_default(),
+                        tag: version.tag.unwrap_or_default(),
+                        product_id,
+                        channel: version.channel,
+                        release_date: version.release_date,
+                    };
+                    let model = dto.into_active_model().insert(&txn).await?;
+                    result.push((model.id, true));
+                }
+                Some(existing) => {
+                    let id = existing.id;
+                    let mut active = existing.into_active_model();
+                    if let Some(hash) = version.hash {
+                        active.hash.set_if_not_equals(hash);
+                    }
+                    if let Some(tag) = version.tag {
+                        active.tag.set_if_not_equals(tag);
+                    }
+                    if let Some(channel) = version.channel {
+                        active.channel.set_if_not_equals(Some(channel));
+                    }
+                    if let Some(release_date) = version.release_date {
+                        active.release_date.set_if_not_equals(Some(release_date));
+                    }
+                    if active.is_changed() {
+                        active.update(&txn).await?;
+                    }
+                    result.push((id, false));
+                }
+            }
+        }
+        txn.commit().await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data_providers::version::parse_release_date;
+    use crate::{
+        entity,
+        model::{
+            base::Repo,
+            product::ProductCreateDto,
+            version::{VersionRepo, VersionUpsert},
+        },
+    };
+    use serial_test::serial;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection, EntityTrait};
+
+    fn version(name: &str, channel: Option<&str>) -> VersionUpsert {
+        VersionUpsert {
+            name: name.to_owned(),
+            hash: None,
+            tag: Some(format!("v{}", name)),
+            channel: channel.map(str::to_owned),
+            release_date: parse_release_date("2024-10-01"),
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_upsert_many() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+
+        let created = VersionRepo::upsert_many(
+            &db,
+            product_id,
+            vec![
+                VersionUpsert {
+                    hash: Some("1234567890".to_owned()),
+                    ..version("1.11", None)
+                },
+                version("1.12", Some("beta")),
+            ],
+        )
+        .await
+        .unwrap();
+        assert!(created.iter().all(|(_, created)| *created));
+
+        let updated = VersionRepo::upsert_many(
+            &db,
+            product_id,
+            vec![VersionUpsert {
+                name: "1.11".to_owned(),
+                channel: Some("stable".to_owned()),
+                ..Default::default()
+            }],
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated, vec![(created[0].0, false)]);
+
+        let versions = entity::prelude::Version::find().all(&db).await.unwrap();
+        assert_eq!(versions.len(), 2);
+        let version = versions.iter().find(|v| v.name == "1.11").unwrap();
+        assert_eq!(version.product_id, product_id);
+        assert_eq!(version.channel.as_deref(), Some("stable"));
+        assert_eq!(version.hash, "1234567890");
+        assert_eq!(version.tag, "v1.11");
+        assert_eq!(
+            version.release_date,
+            parse_release_date("2024-10-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_parse_release_date() {
+        let expected = chrono::NaiveDate::from_ymd_opt(2024, 10, 1)
+            .unwrap()
+            .and_hms_opt(12, 30, 0);
+        assert_eq!(parse_release_date("2024-10-01T12:30:00"), expected);
+        assert_eq!(parse_release_date("2024-10-01T14:30:00+02:00"), expected);
+        assert_eq!(parse_release_date("2024-10-01 12:30:00"), expected);
+        assert!(parse_release_date("2024-10-01").is_some());
+        assert_eq!(parse_release_date("yesterday"), None);
+    }
 }
@@ -0,0 +1,272 @@
+use hmac::{Hmac, Mac};
+use sea_orm::*;
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use tracing::{error, instrument, warn};
+
+use super::base::{HasId, Repo};
+use crate::entity;
+
+pub type Webhook = entity::webhook::Model;
+pub type WebhookCreateDto = entity::webhook::CreateModel;
+pub type WebhookDelivery = entity::webhook_delivery::Model;
+pub type WebhookDeliveryCreateDto = entity::webhook_delivery::CreateModel;
+
+/// Number of deliveries kept per webhook for inspection.
+const KEPT_DELIVERIES: u64 = 50;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+impl HasId for entity::webhook::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+impl HasId for entity::webhook_delivery::Model {
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+}
+
+/// Returns the events a webhook subscribes to, from its comma separated setting.
+pub fn subscribed_events(events: &str) -> Vec<String> {
+    events
+        .split(',')
+        .map(str::trim)
+        .filter(|event| !event.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Signs a payload with the secret of the webhook, so that receivers can verify that it was
+/// sent by Guardrail. Sent as `X-Guardrail-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub struct WebhookRepo;
+impl WebhookRepo {
+    #[instrument(skip_all)]
+    pub async fn get_by_product(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+    ) -> Result<Vec<Webhook>, DbErr> {
+        entity::prelude::Webhook::find()
+            .filter(entity::webhook::Column::ProductId.eq(product_id))
+            .order_by_asc(entity::webhook::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// Returns the webhooks of the product that subscribe to the event.
+    #[instrument(skip_all)]
+    pub async fn get_subscribed(
+        db: &DbConn,
+        product_id: uuid::Uuid,
+        event: &str,
+    ) -> Result<Vec<Webhook>, DbErr> {
+        let webhooks = Self::get_by_product(db, product_id).await?;
+        Ok(webhooks
+            .into_iter()
+            .filter(|webhook| {
+                subscribed_events(&webhook.events)
+                    .iter()
+                    .any(|e| e == event)
+            })
+            .collect())
+    }
+
+    /// Returns the most recent deliveries of the webhook, newest first.
+    #[instrument(skip_all)]
+    pub async fn get_deliveries(
+        db: &DbConn,
+        webhook_id: uuid::Uuid,
+    ) -> Result<Vec<WebhookDelivery>, DbErr> {
+        entity::prelude::WebhookDelivery::find()
+            .filter(entity::webhook_delivery::Column::WebhookId.eq(webhook_id))
+            .order_by_desc(entity::webhook_delivery::Column::CreatedAt)
+            .limit(KEPT_DELIVERIES)
+            .all(db)
+            .await
+    }
+
+    /// Records a delivery attempt and removes the attempts beyond the ones kept for inspection.
+    async fn record_delivery(db: &DbConn, dto: WebhookDeliveryCreateDto) -> Result<(), DbErr> {
+        let webhook_id = dto.webhook_id;
+        Repo::create(db, dto).await?;
+
+        let kept = Self::get_deliveries(db, webhook_id).await?;
+        if let Some(oldest) = kept.last().filter(|_| kept.len() as u64 == KEPT_DELIVERIES) {
+            entity::prelude::WebhookDelivery::delete_many()
+                .filter(entity::webhook_delivery::Column::WebhookId.eq(webhook_id))
+                .filter(entity::webhook_delivery::Column::CreatedAt.lt(oldest.created_at))
+                .exec(db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Posts the event to the webhook and records the attempt. Returns the recorded delivery.
+    #[instrument(skip_all)]
+    pub async fn deliver(
+        db: &DbConn,
+        webhook: &Webhook,
+        event: &str,
+        data: serde_json::Value,
+    ) -> Result<WebhookDeliveryCreateDto, DbErr> {
+        let delivery_id = uuid::Uuid::new_v4();
+        let body = serde_json::json!({
+            "id": delivery_id,
+            "event": event,
+            "product_id": webhook.product_id,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "data": data,
+        })
+        .to_string();
+
+        let mut request = reqwest::Client::new()
+            .post(&webhook.url)
+            .timeout(TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "Guardrail")
+            .header("X-Guardrail-Event", event)
+            .header("X-Guardrail-Delivery", delivery_id.to_string());
+        if let Some(secret) = webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header("X-Guardrail-Signature", sign(secret, body.as_bytes()));
+        }
+
+        let start = Instant::now();
+        let (status_code, error) = match request.body(body).send().await {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16() as i32), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16() as i32),
+                response.status().canonical_reason().map(str::to_owned),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        if let Some(error) = &error {
+            warn!(
+                "webhook delivery of {} to {} failed: {}",
+                event, webhook.url, error
+            );
+        }
+
+        let dto = WebhookDeliveryCreateDto {
+            event: event.to_owned(),
+            status_code,
+            error,
+            duration_ms: start.elapsed().as_millis() as i64,
+            webhook_id: webhook.id,
+        };
+        Self::record_delivery(db, dto.clone()).await?;
+        Ok(dto)
+    }
+
+    /// Sends the event to all webhooks of the product that subscribe to it, in the background.
+    pub fn notify(
+        db: &DatabaseConnection,
+        product_id: uuid::Uuid,
+        event: &'static str,
+        data: serde_json::Value,
+    ) {
+        let db = db.clone();
+        tokio::spawn(async move {
+            let webhooks = match Self::get_subscribed(&db, product_id, event).await {
+                Ok(webhooks) => webhooks,
+                Err(e) => {
+                    error!(
+                        "failed to fetch webhooks of product {}: {:?}",
+                        product_id, e
+                    );
+                    return;
+                }
+            };
+            for webhook in webhooks {
+                if let Err(e) = Self::deliver(&db, &webhook, event, data.clone()).await {
+                    error!("failed to record webhook delivery: {:?}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use serial_test::serial;
+
+    #[test]
+    fn test_sign() {
+        // Test vector of RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            subscribed_events(" crash_processed,,alert "),
+            vec!["crash_processed", "alert"]
+        );
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliveries() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let webhook_id = Repo::create(
+            &db,
+            WebhookCreateDto {
+                url: "http://127.0.0.1:9/hook".to_owned(),
+                secret: None,
+                events: "crash_processed,alert".to_owned(),
+                product_id,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            WebhookRepo::get_subscribed(&db, product_id, "alert")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(WebhookRepo::get_subscribed(&db, product_id, "regression")
+            .await
+            .unwrap()
+            .is_empty());
+
+        for _ in 0..KEPT_DELIVERIES + 5 {
+            WebhookRepo::record_delivery(
+                &db,
+                WebhookDeliveryCreateDto {
+                    event: "alert".to_owned(),
+                    status_code: Some(200),
+                    error: None,
+                    duration_ms: 1,
+                    webhook_id,
+                },
+            )
+            .await
+            .unwrap();
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        let count = entity::prelude::WebhookDelivery::find()
+            .count(&db)
+            .await
+            .unwrap();
+        assert_eq!(count, KEPT_DELIVERIES);
+    }
+}
@@ -0,0 +1,215 @@
+//! Minimal PDF writer for text documents.
+//!
+//! Produces PDF 1.4 files with A4 pages using the standard Helvetica and Courier fonts, which
+//! every PDF reader provides, so no fonts need to be embedded. Text is limited to Latin-1;
+//! other characters are replaced by `?`. Long lines are wrapped on character count.
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Title,
+    Heading,
+    Text,
+    Mono,
+}
+
+impl Style {
+    fn font(&self) -> &'static str {
+        match self {
+            Style::Title | Style::Heading => "F2",
+            Style::Text => "F1",
+            Style::Mono => "F3",
+        }
+    }
+
+    fn size(&self) -> f32 {
+        match self {
+            Style::Title => 16.0,
+            Style::Heading => 12.0,
+            Style::Text => 10.0,
+            Style::Mono => 8.0,
+        }
+    }
+
+    /// Number of characters that fit on a line; Courier is 0.6 em wide, for Helvetica this is
+    /// an estimate.
+    fn max_chars(&self) -> usize {
+        let width = match self {
+            Style::Mono => 0.6,
+            _ => 0.55,
+        };
+        ((PAGE_WIDTH - 2.0 * MARGIN) / (self.size() * width)) as usize
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PdfWriter {
+    pages: Vec<String>,
+    content: String,
+    y: f32,
+}
+
+impl PdfWriter {
+    pub fn new() -> Self {
+        Self {
+            pages: vec![],
+            content: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    pub fn text(&mut self, style: Style, text: &str) {
+        let max_chars = style.max_chars();
+        let chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            self.space(style.size());
+        }
+        for chunk in chars.chunks(max_chars) {
+            let leading = style.size() * 1.3;
+            if self.y - leading < MARGIN {
+                self.new_page();
+            }
+            self.y -= leading;
+            self.content.push_str(&format!(
+                "BT /{} {} Tf {} {:.1} Td ({}) Tj ET\n",
+                style.font(),
+                style.size(),
+                MARGIN,
+                self.y,
+                escape(chunk)
+            ));
+        }
+    }
+
+    pub fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.content));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.new_page();
+        let page_count = self.pages.len();
+
+        // Objects: 1 catalog, 2 page tree, 3-5 fonts, then a page and its content per page.
+        let kids: Vec<String> = (0..page_count)
+            .map(|i| format!("{} 0 R", 6 + 2 * i))
+            .collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                page_count
+            ),
+            font("Helvetica"),
+            font("Helvetica-Bold"),
+            font("Courier"),
+        ];
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                7 + 2 * i
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+}
+
+fn font(name: &str) -> String {
+    format!(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+        name
+    )
+}
+
+/// Escapes text for a PDF string literal. Only printable ASCII is written as is, so that the
+/// content stream stays ASCII and its length equals its byte count.
+fn escape(text: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for &c in text {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        let text: Vec<char> = "f(a\\b) é ✓".chars().collect();
+        assert_eq!(escape(&text), "f\\(a\\\\b\\) \\351 ?");
+    }
+
+    #[test]
+    fn test_pdf_structure() {
+        let mut writer = PdfWriter::new();
+        writer.text(Style::Title, "Crash report");
+        for i in 0..200 {
+            writer.text(Style::Mono, &format!("frame {}", i));
+        }
+        let pdf = writer.finish();
+        let text = String::from_utf8(pdf.clone()).unwrap();
+
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 3"));
+        assert!(text.contains("(frame 199) Tj"));
+
+        // Every xref entry points at the start of its object.
+        let xref = text.rfind("startxref\n").unwrap();
+        let start: usize = text[xref + 10..].lines().next().unwrap().parse().unwrap();
+        let entries: Vec<&str> = text[start..].lines().skip(3).take(11).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use cfg_if::cfg_if;
+use std::sync::OnceLock;
+
+/// The sub-path the application is served under (e.g. `/guardrail`), or an empty string when
+/// served from the root. On the server this comes from the settings, in the browser it is read
+/// back from the `path-prefix` meta tag rendered by the server.
+pub fn path_prefix() -> &'static str {
+    static INSTANCE: OnceLock<String> = OnceLock::new();
+    INSTANCE.get_or_init(|| normalize(&load())).as_str()
+}
+
+/// Prepends the path prefix to an absolute path.
+pub fn prefixed(path: &str) -> String {
+    format!("{}{}", path_prefix(), path)
+}
+
+pub fn normalize(prefix: &str) -> String {
+    let prefix = prefix.trim().trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("/{prefix}")
+    }
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    fn load() -> String {
+        crate::settings::settings().server.path_prefix.clone()
+    }
+} else {
+    fn load() -> String {
+        leptos::document()
+            .query_selector("meta[name=path-prefix]")
+            .ok()
+            .flatten()
+            .and_then(|meta| meta.get_attribute("content"))
+            .unwrap_or_default()
+    }
+}}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(""), "");
+        assert_eq!(normalize("/"), "");
+        assert_eq!(normalize("guardrail"), "/guardrail");
+        assert_eq!(normalize("/guardrail/"), "/guardrail");
+        assert_eq!(normalize("tools/guardrail"), "/tools/guardrail");
+    }
+}
@@ -0,0 +1,36 @@
+use cfg_if::cfg_if;
+use std::sync::OnceLock;
+
+/// Explanation returned by mutating endpoints and server functions of a read-only instance.
+pub const READ_ONLY_MESSAGE: &str = "this instance is a read-only mirror, changes are disabled";
+
+/// Whether the instance is a read-only mirror, e.g. on a replica database. On the server this
+/// comes from the settings, in the browser it is read back from the `read-only` meta tag
+/// rendered by the server.
+pub fn is_read_only() -> bool {
+    static INSTANCE: OnceLock<bool> = OnceLock::new();
+    *INSTANCE.get_or_init(load)
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    fn load() -> bool {
+        crate::settings::settings().server.read_only
+    }
+
+    /// Fails mutating server functions of a read-only instance.
+    pub fn check_writable() -> Result<(), leptos::ServerFnError> {
+        match is_read_only() {
+            true => Err(leptos::ServerFnError::new(READ_ONLY_MESSAGE)),
+            false => Ok(()),
+        }
+    }
+} else {
+    fn load() -> bool {
+        leptos::document()
+            .query_selector("meta[name=read-only]")
+            .ok()
+            .flatten()
+            .and_then(|meta| meta.get_attribute("content"))
+            .is_some_and(|content| content == "true")
+    }
+}}
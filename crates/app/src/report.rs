@@ -0,0 +1,500 @@
+//! Versioned model of the crash report stored with each crash.
+//!
+//! Reports are produced by minidump-processor when a minidump is uploaded. Guardrail adds
+//! `schema_version`, `exception`, `provenance`, `analysis` and `attachments` to them. Only the
+//! fields guardrail relies on are modelled; other fields are kept in the stored JSON but ignored
+//! here.
+//!
+//! Version history:
+//! - 0: reports stored before the schema was versioned.
+//! - 1: `schema_version` added.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Number of frames shown in the plain text stack unless the caller asks for more or fewer.
+pub const DEFAULT_STACK_FRAMES: usize = 50;
+
+/// Prefixes of operating system and runtime modules, which are skipped when looking for the
+/// frame of the application that a crash title names.
+const SYSTEM_MODULES: [&str; 12] = [
+    "ntdll",
+    "kernel32",
+    "kernelbase",
+    "ucrtbase",
+    "msvcrt",
+    "vcruntime",
+    "libc.so",
+    "libc++",
+    "libstdc++",
+    "libpthread",
+    "libsystem_",
+    "libdyld",
+];
+
+/// Maximum length of a crash title, in characters.
+const MAX_TITLE_LENGTH: usize = 120;
+
+#[derive(Error, Debug)]
+pub enum ReportError {
+    #[error("report is not a JSON object")]
+    NotAnObject,
+
+    #[error("unsupported report schema version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("invalid report: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrashInfo {
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub crash_info: Option<CrashDetails>,
+    /// The exception read from the minidump, absent in reports stored before it was added.
+    #[serde(default)]
+    pub exception: Option<ExceptionDetails>,
+    #[serde(default)]
+    pub crashing_thread: Option<Thread>,
+    #[serde(default)]
+    pub threads: Vec<Thread>,
+    #[serde(default)]
+    pub modules: Vec<Module>,
+    #[serde(default)]
+    pub system_info: Option<SystemInfo>,
+    #[serde(default)]
+    pub provenance: Option<Value>,
+    #[serde(default)]
+    pub analysis: Vec<Value>,
+    /// Findings of the attachment processors, added after the upload.
+    #[serde(default)]
+    pub attachments: Vec<AttachmentFinding>,
+}
+
+/// What a processor extracted from an attachment of the crash, e.g. the last error lines of a
+/// log file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentFinding {
+    #[serde(default)]
+    pub attachment: String,
+    #[serde(default)]
+    pub processor: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrashDetails {
+    #[serde(rename = "type", default)]
+    pub crash_type: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub crashing_thread: Option<u64>,
+}
+
+/// The exception stream of the minidump: the raw exception code and the registers of the
+/// crashing thread when the exception was raised.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExceptionDetails {
+    /// Exception code as reported by the OS, e.g. `0xc0000005` on Windows or the signal number
+    /// on Linux and macOS.
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub flags: Option<String>,
+    #[serde(default)]
+    pub thread_id: Option<u32>,
+    /// Registers in the order of the CPU context, with the value padded to the register size.
+    #[serde(default)]
+    pub registers: Vec<Register>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Register {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemInfo {
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub cpu_arch: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Thread {
+    #[serde(default)]
+    pub thread_name: Option<String>,
+    #[serde(default)]
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub line: Option<u64>,
+    /// Permalink to the source line, see `SourceLinks`.
+    #[serde(default)]
+    pub source_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    #[serde(default)]
+    pub filename: String,
+    #[serde(default)]
+    pub debug_file: Option<String>,
+    #[serde(default)]
+    pub debug_id: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Set by minidump-processor when no symbol file of the module was found.
+    #[serde(default)]
+    pub missing_symbols: bool,
+}
+
+/// Strips the directory from a module path.
+fn module_name(module: &str) -> &str {
+    module.rsplit(['/', '\\']).next().unwrap_or(module)
+}
+
+impl Frame {
+    /// Returns whether the frame is in a module of the operating system or runtime.
+    fn is_system(&self) -> bool {
+        self.module.as_deref().is_some_and(|module| {
+            let name = module_name(module).to_lowercase();
+            SYSTEM_MODULES.iter().any(|prefix| name.starts_with(prefix))
+        })
+    }
+
+    /// Describes the frame as `module!function [file:line]`, with `?` for unknown parts.
+    pub fn describe(&self) -> String {
+        let location = match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!(" [{}:{}]", file, line),
+            _ => String::new(),
+        };
+        format!(
+            "{}!{}{}",
+            self.module.as_deref().unwrap_or("?"),
+            self.function.as_deref().unwrap_or("?"),
+            location
+        )
+    }
+}
+
+impl CrashInfo {
+    /// Parses and validates a stored report. Reports of a newer schema version than this build
+    /// supports are rejected.
+    pub fn from_report(report: &Value) -> Result<Self, ReportError> {
+        if !report.is_object() {
+            return Err(ReportError::NotAnObject);
+        }
+        let info = Self::deserialize(report)?;
+        if info.schema_version > SCHEMA_VERSION {
+            return Err(ReportError::UnsupportedVersion(info.schema_version));
+        }
+        Ok(info)
+    }
+
+    /// Returns the function of the top frame of the crashing thread, or its module if the
+    /// function is unknown.
+    pub fn signature(&self) -> Option<String> {
+        let frame = self.crashing_thread.as_ref()?.frames.first()?;
+        frame.function.clone().or_else(|| frame.module.clone())
+    }
+
+    /// Returns a readable title for the crash, e.g. `SIGSEGV in Timer::tick (workrave)`: the crash
+    /// reason, the function of the topmost frame outside the system libraries without its
+    /// parameters, and its module. The title is meant for display; crashes are grouped by the
+    /// signature, which does not change when these heuristics do.
+    pub fn title(&self) -> Option<String> {
+        let frames = self
+            .crashing_thread
+            .as_ref()
+            .map(|thread| thread.frames.as_slice())
+            .unwrap_or_default();
+        let frame = frames
+            .iter()
+            .find(|frame| frame.function.is_some() && !frame.is_system())
+            .or_else(|| frames.first());
+        let reason = self
+            .crash_info
+            .as_ref()
+            .and_then(|details| details.crash_type.clone());
+
+        let function = frame
+            .and_then(|frame| frame.function.as_deref())
+            .map(|function| match function.find('(') {
+                Some(index) if index > 0 => &function[..index],
+                _ => function,
+            });
+        let module = frame
+            .and_then(|frame| frame.module.as_deref())
+            .map(module_name);
+        let location = match (function, module) {
+            (Some(function), Some(module)) => Some(format!("{} ({})", function, module)),
+            (Some(function), None) => Some(function.to_owned()),
+            (None, Some(module)) => Some(module.to_owned()),
+            (None, None) => None,
+        };
+        let title = match (reason, location) {
+            (Some(reason), Some(location)) => format!("{} in {}", reason, location),
+            (Some(reason), None) => reason,
+            (None, Some(location)) => location,
+            (None, None) => return None,
+        };
+        Some(match title.char_indices().nth(MAX_TITLE_LENGTH) {
+            Some((index, _)) => format!("{}…", &title[..index]),
+            None => title,
+        })
+    }
+
+    /// Returns the platform the crash happened on as `<os>-<arch>`, e.g. `windows-x86_64`. The
+    /// names used by minidump-processor are normalized, so that the same platform reported by
+    /// different SDKs ends up in the same bucket.
+    pub fn platform(&self) -> Option<String> {
+        let system = self.system_info.as_ref()?;
+        let os = match system.os.as_deref()?.trim() {
+            "Windows NT" | "Windows" => "windows".to_owned(),
+            "Mac OS X" | "macOS" => "macos".to_owned(),
+            "Linux" => "linux".to_owned(),
+            "iOS" => "ios".to_owned(),
+            "Android" => "android".to_owned(),
+            "" => return None,
+            other => other.to_lowercase().replace(' ', "_"),
+        };
+        let arch = match system.cpu_arch.as_deref().map(str::trim) {
+            Some("amd64") | Some("x86_64") => "x86_64".to_owned(),
+            Some("arm64") | Some("aarch64") => "arm64".to_owned(),
+            Some("x86") | Some("i386") => "x86".to_owned(),
+            Some("") | None => "unknown".to_owned(),
+            Some(other) => other.to_lowercase(),
+        };
+        Some(format!("{}-{}", os, arch))
+    }
+
+    /// Renders the crashing thread as plain text, for pasting into tickets. Shows at most
+    /// `max_frames` frames and optionally the loaded modules.
+    pub fn stack_text(&self, max_frames: usize, with_modules: bool) -> String {
+        let mut lines = vec![];
+        if let Some(details) = &self.crash_info {
+            match (&details.crash_type, &details.address) {
+                (Some(crash_type), Some(address)) => {
+                    lines.push(format!("{} at {}", crash_type, address))
+                }
+                (Some(crash_type), None) => lines.push(crash_type.clone()),
+                _ => (),
+            }
+        }
+
+        let frames = self
+            .crashing_thread
+            .as_ref()
+            .map(|thread| thread.frames.as_slice())
+            .unwrap_or_default();
+        if frames.is_empty() {
+            lines.push("No stack available".to_owned());
+        }
+        for (i, frame) in frames.iter().take(max_frames).enumerate() {
+            lines.push(format!("{:>3}  {}", i, frame.describe()));
+        }
+        if frames.len() > max_frames {
+            lines.push(format!("     {} more frames", frames.len() - max_frames));
+        }
+
+        if with_modules && !self.modules.is_empty() {
+            lines.push(String::new());
+            lines.push("Modules:".to_owned());
+            for module in &self.modules {
+                lines.push(format!(
+                    "{} {} {}",
+                    module.filename,
+                    module.version.as_deref().unwrap_or("-"),
+                    module.debug_id.as_deref().unwrap_or("-")
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Stamps a new report with the current schema version and validates it before it is
+    /// stored.
+    pub fn stamp(report: &mut Value) -> Result<Self, ReportError> {
+        let fields = report.as_object_mut().ok_or(ReportError::NotAnObject)?;
+        fields.insert("schema_version".to_owned(), SCHEMA_VERSION.into());
+        Self::from_report(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stamp() {
+        let mut report = json!({
+            "status": "OK",
+            "crash_info": { "type": "EXCEPTION_ACCESS_VIOLATION_READ", "address": "0x0", "crashing_thread": 0 },
+            "threads": [{ "frames": [{ "function": "main", "module": "workrave.exe", "line": 12 }] }],
+            "modules": [{ "filename": "workrave.exe", "debug_file": "workrave.pdb" }],
+            "system_info": { "os": "Windows NT" },
+        });
+        let info = CrashInfo::stamp(&mut report).unwrap();
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert_eq!(info.schema_version, SCHEMA_VERSION);
+        assert_eq!(
+            info.crash_info.unwrap().crash_type.as_deref(),
+            Some("EXCEPTION_ACCESS_VIOLATION_READ")
+        );
+        assert_eq!(info.threads[0].frames[0].line, Some(12));
+        assert_eq!(info.modules[0].filename, "workrave.exe");
+
+        assert!(CrashInfo::stamp(&mut json!("report")).is_err());
+        let mut invalid = json!({ "modules": [{ "filename": 42 }] });
+        assert!(CrashInfo::stamp(&mut invalid).is_err());
+    }
+
+    #[test]
+    fn test_from_report() {
+        let legacy = CrashInfo::from_report(&json!({ "modules": [] })).unwrap();
+        assert_eq!(legacy.schema_version, 0);
+
+        let newer = json!({ "schema_version": SCHEMA_VERSION + 1 });
+        assert!(matches!(
+            CrashInfo::from_report(&newer),
+            Err(ReportError::UnsupportedVersion(_))
+        ));
+        assert!(CrashInfo::from_report(&Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_exception() {
+        let info = CrashInfo::from_report(&json!({
+            "crash_info": { "type": "EXCEPTION_ACCESS_VIOLATION_READ", "address": "0x0" },
+            "exception": {
+                "code": "0xc0000005",
+                "flags": "0x0",
+                "thread_id": 4242,
+                "registers": [
+                    { "name": "rip", "value": "0x00007ff6a1b21234" },
+                    { "name": "rsp", "value": "0x000000e1c3aff6c0" },
+                ],
+            },
+        }))
+        .unwrap();
+        let exception = info.exception.unwrap();
+        assert_eq!(exception.code.as_deref(), Some("0xc0000005"));
+        assert_eq!(exception.thread_id, Some(4242));
+        assert_eq!(exception.registers.len(), 2);
+        assert_eq!(exception.registers[0].name, "rip");
+
+        let legacy = CrashInfo::from_report(&json!({ "status": "OK" })).unwrap();
+        assert_eq!(legacy.exception, None);
+    }
+
+    #[test]
+    fn test_platform() {
+        let platform = |system_info: Value| {
+            CrashInfo::from_report(&json!({ "system_info": system_info }))
+                .unwrap()
+                .platform()
+        };
+        assert_eq!(
+            platform(json!({ "os": "Windows NT", "cpu_arch": "amd64" })).as_deref(),
+            Some("windows-x86_64")
+        );
+        assert_eq!(
+            platform(json!({ "os": "Mac OS X", "cpu_arch": "arm64" })).as_deref(),
+            Some("macos-arm64")
+        );
+        assert_eq!(
+            platform(json!({ "os": "Solaris" })).as_deref(),
+            Some("solaris-unknown")
+        );
+        assert_eq!(platform(json!({ "cpu_arch": "x86" })), None);
+        assert_eq!(CrashInfo::default().platform(), None);
+    }
+
+    #[test]
+    fn test_title() {
+        let info = CrashInfo::from_report(&json!({
+            "crash_info": { "type": "EXCEPTION_ACCESS_VIOLATION_READ" },
+            "crashing_thread": { "frames": [
+                { "module": "C:\\Windows\\System32\\ntdll.dll", "function": "RtlFreeHeap" },
+                { "module": "ucrtbase.dll", "function": "free" },
+                { "module": "workrave.exe", "function": "Timer::tick(int, bool)" },
+                { "module": "workrave.exe", "function": "main" }
+            ]},
+        }))
+        .unwrap();
+        assert_eq!(
+            info.title().as_deref(),
+            Some("EXCEPTION_ACCESS_VIOLATION_READ in Timer::tick (workrave.exe)")
+        );
+        assert_eq!(info.signature().as_deref(), Some("RtlFreeHeap"));
+
+        let info = CrashInfo::from_report(&json!({
+            "crashing_thread": { "frames": [{ "module": "/usr/lib/libc.so.6" }] },
+        }))
+        .unwrap();
+        assert_eq!(info.title().as_deref(), Some("libc.so.6"));
+
+        let info = CrashInfo::from_report(&json!({
+            "crash_info": { "type": "SIGABRT" },
+            "crashing_thread": { "frames": [{ "module": "workrave", "function": "x".repeat(200) }] },
+        }))
+        .unwrap();
+        let title = info.title().unwrap();
+        assert_eq!(title.chars().count(), MAX_TITLE_LENGTH + 1);
+        assert!(title.starts_with("SIGABRT in xxx") && title.ends_with('…'));
+
+        assert_eq!(CrashInfo::default().title(), None);
+    }
+
+    #[test]
+    fn test_stack_text() {
+        let info = CrashInfo::from_report(&json!({
+            "crash_info": { "type": "SIGSEGV", "address": "0x0" },
+            "crashing_thread": { "frames": [
+                { "module": "workrave", "function": "Timer::tick", "file": "timer.cc", "line": 42 },
+                { "module": "workrave", "function": "main" },
+                { "module": "libc.so.6" }
+            ]},
+            "modules": [{ "filename": "workrave", "version": "1.11.0" }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            info.stack_text(2, false),
+            "SIGSEGV at 0x0\n  0  workrave!Timer::tick [timer.cc:42]\n  1  workrave!main\n     1 more frames\n"
+        );
+        assert!(info
+            .stack_text(10, true)
+            .ends_with("  2  libc.so.6!?\n\nModules:\nworkrave 1.11.0 -\n"));
+        assert_eq!(
+            CrashInfo::default().stack_text(10, true),
+            "No stack available\n"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+// Moving data to a new column is rolled out in phases, so that the schema and the code never
+// have to change at the same time and every step can be rolled back:
+//
+// 1. `old`: the migration adds the new column; the code reads and writes the old data only.
+// 2. `dual`: new data is written to both, existing rows are backfilled in the background and
+//    reads prefer the new column, falling back to the old data for rows not backfilled yet.
+// 3. `new`: once the backfill is complete, only the new column is read and written. A later
+//    migration removes the old data.
+//
+// The phase of each move is configured in the `rollout` settings. Repositories ask the phase
+// what to write and use `ColumnPhase::read` or `ColumnPhase::read_expr` to read.
+
+/// Phase of moving data from an old to a new column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnPhase {
+    #[default]
+    Old,
+    Dual,
+    New,
+}
+
+impl ColumnPhase {
+    pub fn writes_old(self) -> bool {
+        self != ColumnPhase::New
+    }
+
+    pub fn writes_new(self) -> bool {
+        self != ColumnPhase::Old
+    }
+
+    /// Whether existing rows are copied to the new column.
+    pub fn backfills(self) -> bool {
+        self == ColumnPhase::Dual
+    }
+
+    /// Returns the value of a row. `old` derives the value from the old data and is only called
+    /// when needed.
+    pub fn read<T>(self, old: impl FnOnce() -> Option<T>, new: Option<T>) -> Option<T> {
+        match self {
+            ColumnPhase::Old => old(),
+            ColumnPhase::Dual => new.or_else(old),
+            ColumnPhase::New => new,
+        }
+    }
+
+    /// Returns the SQL expression that reads the value, given the expressions of the old and new
+    /// column.
+    pub fn read_expr(self, old: &str, new: &str) -> String {
+        match self {
+            ColumnPhase::Old => old.to_owned(),
+            ColumnPhase::Dual => format!("COALESCE({new}, {old})"),
+            ColumnPhase::New => new.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes() {
+        assert!(ColumnPhase::Old.writes_old() && !ColumnPhase::Old.writes_new());
+        assert!(ColumnPhase::Dual.writes_old() && ColumnPhase::Dual.writes_new());
+        assert!(!ColumnPhase::New.writes_old() && ColumnPhase::New.writes_new());
+        assert!(ColumnPhase::Dual.backfills());
+        assert!(!ColumnPhase::New.backfills());
+    }
+
+    #[test]
+    fn test_read() {
+        let old = || Some("old");
+        assert_eq!(ColumnPhase::Old.read(old, Some("new")), Some("old"));
+        assert_eq!(ColumnPhase::Dual.read(old, Some("new")), Some("new"));
+        assert_eq!(ColumnPhase::Dual.read(old, None), Some("old"));
+        assert_eq!(ColumnPhase::New.read(old, None), None);
+
+        assert_eq!(ColumnPhase::Old.read_expr("a", "b"), "a");
+        assert_eq!(ColumnPhase::Dual.read_expr("a", "b"), "COALESCE(b, a)");
+        assert_eq!(ColumnPhase::New.read_expr("a", "b"), "b");
+    }
+}
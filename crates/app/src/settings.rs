@@ -1,17 +1,89 @@
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::{env, sync::OnceLock};
 
+use crate::rollout::ColumnPhase;
+
 pub fn settings() -> &'static Settings {
     static INSTANCE: OnceLock<Settings> = OnceLock::new();
     INSTANCE.get_or_init(|| Settings::new().expect("Failed to setup settings"))
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize)]
 pub struct Server {
+    /// Address to listen on, an IPv4 or IPv6 address; `0.0.0.0` or `::` listens on all interfaces.
+    #[serde(default = "default_bind_address")]
+    pub bind_address: IpAddr,
     pub port: u16,
     pub base_path: String,
     pub site: String,
+    #[serde(default)]
+    pub path_prefix: String,
+    /// YAML file with products and device keys to create or update at startup.
+    #[serde(default)]
+    pub bootstrap: Option<String>,
+    /// Serve as a read-only mirror, e.g. on a replica database: all changes are rejected and
+    /// background jobs do not run.
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub tls: Tls,
+    /// Listeners in addition to the one on `bind_address` and `port`, e.g. plain HTTP for a
+    /// reverse proxy next to HTTPS.
+    #[serde(default)]
+    pub listeners: Vec<Listener>,
+}
+
+impl Server {
+    /// Returns the addresses to listen on, and whether each serves HTTPS.
+    pub fn listeners(&self) -> Vec<(SocketAddr, bool)> {
+        std::iter::once((
+            SocketAddr::new(self.bind_address, self.port),
+            self.tls.enabled,
+        ))
+        .chain(self.listeners.iter().map(|listener| {
+            (
+                SocketAddr::new(listener.address, listener.port),
+                listener.tls,
+            )
+        }))
+        .collect()
+    }
+}
+
+fn default_bind_address() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+}
+
+/// Certificate of HTTPS listeners, as PEM files.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Tls {
+    /// Whether the listener on `server.port` serves HTTPS instead of HTTP.
+    pub enabled: bool,
+    pub cert: String,
+    pub key: String,
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cert: "dev/cert.pem".into(),
+            key: "dev/key.pem".into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Listener {
+    #[serde(default = "default_bind_address")]
+    pub address: IpAddr,
+    pub port: u16,
+    /// Serve HTTPS with the certificate of `server.tls` instead of HTTP.
+    #[serde(default)]
+    pub tls: bool,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -20,6 +92,8 @@ pub struct Auth {
     pub origin: String,
     pub name: String,
     pub jwk: Jwk,
+    #[serde(default)]
+    pub upload_token: UploadToken,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -27,6 +101,540 @@ pub struct Jwk {
     pub key: String,
 }
 
+/// Short-lived upload credentials handed out by `/api/upload/token` and `/ingest/token`.
+/// Tokens are signed with `secret` (HS256); an empty secret disables the exchange.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct UploadToken {
+    pub secret: String,
+    /// Lifetime of a token in seconds.
+    pub lifetime: u64,
+}
+
+impl Default for UploadToken {
+    fn default() -> Self {
+        Self {
+            secret: "".into(),
+            lifetime: 15 * 60,
+        }
+    }
+}
+
+/// Upload handling.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Ingest {
+    /// Store the IP address of the submitter in the crash report.
+    pub record_submitter_ip: bool,
+    /// Number of reverse proxies in front of the server that append to `X-Forwarded-For`. The
+    /// submitter IP is taken from the entries appended by them; zero ignores the header.
+    pub trusted_proxies: usize,
+    /// Maximum time in seconds for processing a minidump.
+    pub processing_timeout: u64,
+    /// Maximum size in bytes of a request body, e.g. an upload with all its attachments.
+    pub max_upload_size: usize,
+    /// Maximum size in bytes of a gzip or zstd compressed upload after decompression.
+    pub max_decompressed_size: usize,
+    /// Time in seconds a resumable upload may take before it is discarded.
+    pub resumable_upload_lifetime: u64,
+    /// Number of minidumps a replica processes at the same time.
+    pub processing_slots: usize,
+    /// Number of processing slots a single product may use at the same time.
+    pub processing_slots_per_product: usize,
+    /// Reuse the stackwalk output of a minidump that was processed before with the same symbols.
+    pub stackwalk_cache: bool,
+}
+
+impl Default for Ingest {
+    fn default() -> Self {
+        Self {
+            record_submitter_ip: false,
+            trusted_proxies: 0,
+            processing_timeout: 120,
+            max_upload_size: 100 * 1024 * 1024,
+            max_decompressed_size: 500 * 1024 * 1024,
+            resumable_upload_lifetime: 24 * 60 * 60,
+            processing_slots: 4,
+            processing_slots_per_product: 2,
+            stackwalk_cache: true,
+        }
+    }
+}
+
+/// Shedding of uploads when the server is under pressure, e.g. when the database or storage is
+/// slow. The number of uploads handled at the same time adapts to their latency, and uploads
+/// beyond it are rejected right away, so that clients retry later instead of timing out.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LoadShedding {
+    pub enabled: bool,
+    /// Latency in milliseconds above which an upload counts as slow and the number of concurrent
+    /// uploads is reduced.
+    pub target_latency: u64,
+    /// Number of concurrent uploads that are always accepted.
+    pub min_concurrency: usize,
+    /// Number of concurrent uploads when uploads are handled within the target latency.
+    pub max_concurrency: usize,
+    /// Time in seconds after which a client may retry a rejected upload.
+    pub retry_after: u64,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_latency: 10_000,
+            min_concurrency: 4,
+            max_concurrency: 64,
+            retry_after: 5,
+        }
+    }
+}
+
+/// Import of crashes from another crash server at startup.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Import {
+    /// Directory with the crashes to import; importing is disabled when empty.
+    pub source: String,
+    /// Maximum number of crashes imported per second.
+    pub rate: u32,
+}
+
+impl Default for Import {
+    fn default() -> Self {
+        Self {
+            source: "".into(),
+            rate: 5,
+        }
+    }
+}
+
+/// Report analyzers that run after a minidump has been processed.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Analyzers {
+    /// Names of the built-in analyzers to run.
+    pub enabled: Vec<String>,
+    /// File names of modules that are known to cause crashes, for the `bad_modules` analyzer.
+    pub bad_modules: Vec<String>,
+}
+
+/// Nightly maintenance of the database.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Maintenance {
+    /// Hour of the day, in UTC, at which the maintenance runs.
+    pub hour: u32,
+    /// Number of sessions deleted per statement.
+    pub session_batch_size: u64,
+    /// Days after which sessions without expiry that were not used are deleted.
+    pub abandoned_session_days: i64,
+    /// Days after which crashes are deleted, unless they are on legal hold. Crashes are kept
+    /// forever without it.
+    pub crash_retention_days: Option<i64>,
+    /// Number of crashes deleted per statement, when crashes cannot be deleted by dropping a
+    /// monthly partition.
+    pub crash_batch_size: u64,
+    /// Number of months ahead for which partitions of the crash tables are created, on
+    /// PostgreSQL.
+    pub partition_months_ahead: u32,
+}
+
+impl Default for Maintenance {
+    fn default() -> Self {
+        Self {
+            hour: 3,
+            session_batch_size: 1000,
+            abandoned_session_days: 30,
+            crash_retention_days: None,
+            crash_batch_size: 100,
+            partition_months_ahead: 3,
+        }
+    }
+}
+
+/// Notifications sent when a crash with a signature that was not seen before is reported. Each
+/// notification is sent to every configured email recipient and webhook, regardless of product.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    /// Addresses that receive an email per notification.
+    pub email_recipients: Vec<String>,
+    /// URLs that receive a JSON payload per notification.
+    pub webhooks: Vec<String>,
+    pub smtp: Smtp,
+    /// Number of times a notification is sent before it is given up.
+    pub max_attempts: i32,
+    /// Time in seconds before the first retry; it doubles with every further attempt.
+    pub retry_delay: u64,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            email_recipients: vec![],
+            webhooks: vec![],
+            smtp: Smtp::default(),
+            max_attempts: 5,
+            retry_delay: 60,
+        }
+    }
+}
+
+/// Mail server that notifications are sent through.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Smtp {
+    pub host: String,
+    pub port: u16,
+    /// Whether to connect with TLS, otherwise the connection is upgraded with STARTTLS.
+    pub tls: bool,
+    /// Empty to send without authentication.
+    pub username: String,
+    pub password: String,
+    pub from: String,
+}
+
+impl Default for Smtp {
+    fn default() -> Self {
+        Self {
+            host: "localhost".into(),
+            port: 587,
+            tls: false,
+            username: String::new(),
+            password: String::new(),
+            from: "Guardrail <guardrail@localhost>".into(),
+        }
+    }
+}
+
+/// Reports of crash reporting clients that failed to upload a crash, sent to
+/// `/api/client-diagnostics`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ClientDiagnostics {
+    pub enabled: bool,
+    /// Maximum number of reports per hour from an IP address.
+    pub rate_limit: usize,
+    /// Days after which reports are deleted.
+    pub retention_days: i64,
+    /// Maximum number of reports kept per product, the most recent ones.
+    pub max_reports: u64,
+}
+
+impl Default for ClientDiagnostics {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rate_limit: 20,
+            retention_days: 30,
+            max_reports: 10_000,
+        }
+    }
+}
+
+/// Prometheus metrics served at `/metrics`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Metrics {
+    pub enabled: bool,
+    /// Bearer token that Prometheus must send, empty to serve the metrics without one.
+    pub token: String,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            token: String::new(),
+        }
+    }
+}
+
+/// Upstream symbol servers that symbols of modules missing from crashes are fetched from, e.g.
+/// `https://symbols.mozilla.org`. The servers must serve Breakpad symbol files.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SymbolServers {
+    pub urls: Vec<String>,
+    /// Time in seconds a download may take.
+    pub timeout: u64,
+    /// Maximum size in bytes of a downloaded symbol file.
+    pub max_size: u64,
+}
+
+impl Default for SymbolServers {
+    fn default() -> Self {
+        Self {
+            urls: vec![],
+            timeout: 60,
+            max_size: 500 * 1024 * 1024,
+        }
+    }
+}
+
+/// Conversion of uploaded native debug files, such as PDB files and ELF files with DWARF debug
+/// info, to Breakpad symbols with Mozilla's `dump_syms`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SymbolConversion {
+    /// The `dump_syms` executable, looked up in `PATH` unless it is a path.
+    pub dump_syms: String,
+    /// Time in seconds a conversion may take.
+    pub timeout: u64,
+}
+
+impl Default for SymbolConversion {
+    fn default() -> Self {
+        Self {
+            dump_syms: "dump_syms".to_owned(),
+            timeout: 10 * 60,
+        }
+    }
+}
+
+/// Metadata of the CI builds that crashes link to, fetched from the `metadata_url` of the link
+/// templates of a product.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CiBuilds {
+    /// Time in seconds a request may take.
+    pub timeout: u64,
+    /// Time in seconds after which the metadata of builds that have not finished is fetched
+    /// again.
+    pub refresh: u64,
+    /// Credentials sent to CI servers.
+    pub authorization: Vec<CiAuthorization>,
+}
+
+impl Default for CiBuilds {
+    fn default() -> Self {
+        Self {
+            timeout: 10,
+            refresh: 10 * 60,
+            authorization: vec![],
+        }
+    }
+}
+
+/// `Authorization` header sent with requests to URLs that start with `url_prefix`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CiAuthorization {
+    pub url_prefix: String,
+    pub header: String,
+}
+
+/// Personal data removed from crashes before they are stored.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Minidumps {
+    /// Redactions applied to annotation values and to the processed report, in order.
+    pub scrub_rules: Vec<ScrubRule>,
+    /// Annotations that are stored, compared case-insensitively. All are stored when empty.
+    pub allowed_annotations: Vec<String>,
+    /// Annotations that are never stored, compared case-insensitively.
+    pub blocked_annotations: Vec<String>,
+}
+
+impl Default for Minidumps {
+    fn default() -> Self {
+        let rule = |pattern: &str, replacement: &str| ScrubRule {
+            pattern: pattern.to_owned(),
+            replacement: replacement.to_owned(),
+            products: vec![],
+        };
+        Self {
+            scrub_rules: vec![
+                rule(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[email]"),
+                rule(
+                    r"(?i)([a-z]:[\\/]+(?:users|documents and settings)[\\/]+)[^\\/]+",
+                    "${1}[user]",
+                ),
+                rule(r"(/(?:home|Users)/)[^/]+", "${1}[user]"),
+            ],
+            allowed_annotations: vec![],
+            blocked_annotations: vec![],
+        }
+    }
+}
+
+/// Replaces the matches of a regular expression.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScrubRule {
+    pub pattern: String,
+    /// Replacement of a match, which may refer to groups of the pattern as `${1}`.
+    pub replacement: String,
+    /// Names of the products the rule applies to, all products when empty.
+    pub products: Vec<String>,
+}
+
+/// Processing of attachments after upload. Products choose the processors that run on their
+/// attachments with `attachment_processors`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AttachmentProcessing {
+    /// Number of error lines kept per log attachment, the most recent ones.
+    pub error_lines: usize,
+    /// Words that mark a line of a log as an error, matched case-insensitively.
+    pub error_patterns: Vec<String>,
+    /// Attachments larger than this are not processed.
+    pub max_size: u64,
+}
+
+impl Default for AttachmentProcessing {
+    fn default() -> Self {
+        Self {
+            error_lines: 20,
+            error_patterns: vec![
+                "error".into(),
+                "fatal".into(),
+                "exception".into(),
+                "panic".into(),
+                "critical".into(),
+            ],
+            max_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// Content types accepted for uploads, per kind of file. Products can replace these lists with
+/// their `allowed_content_types`. A type ending in `/*` matches all subtypes.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ContentTypes {
+    pub minidump: Vec<String>,
+    pub symbols: Vec<String>,
+    pub attachment: Vec<String>,
+}
+
+impl Default for ContentTypes {
+    fn default() -> Self {
+        Self {
+            minidump: vec![
+                "application/octet-stream".into(),
+                "application/x-dmp".into(),
+                "application/x-minidump".into(),
+            ],
+            symbols: vec!["application/octet-stream".into(), "text/plain".into()],
+            attachment: vec![
+                "application/octet-stream".into(),
+                "application/json".into(),
+                "application/zip".into(),
+                "text/*".into(),
+                "image/*".into(),
+            ],
+        }
+    }
+}
+
+/// Location of stored files below `server.base_path`, per kind of file. Templates can use
+/// `{product}`, `{yyyy}`, `{mm}`, `{dd}`, `{uuid}` and `{filename}`; attachments also `{crash}`.
+/// Symbols always use the Breakpad layout that the symbolizer expects.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Storage {
+    pub minidump: String,
+    pub attachment: String,
+    /// Compression of stored minidumps and attachments, `zstd` or `none`.
+    pub compression: String,
+    pub compression_level: i32,
+    /// Number of times a stored file that is not found is read again before processing fails,
+    /// for shared storage on which files written by another replica appear with a delay.
+    pub read_retries: u32,
+    /// Time in milliseconds before the first retry; it doubles with every further attempt.
+    pub read_retry_delay: u64,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            minidump: "minidumps/{filename}".into(),
+            attachment: "attachments/{crash}/{filename}".into(),
+            compression: "zstd".into(),
+            compression_level: 3,
+            read_retries: 4,
+            read_retry_delay: 250,
+        }
+    }
+}
+
+/// Short-lived cache of read API responses.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Cache {
+    /// Time in seconds a response is served from the cache; zero disables caching.
+    pub ttl: u64,
+    /// Maximum number of cached responses.
+    pub max_entries: usize,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            ttl: 10,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// Quarantine of upload sources, i.e. credentials and IP addresses, that submit many invalid
+/// uploads.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Quarantine {
+    /// Number of invalid uploads per hour after which a source is quarantined; zero disables
+    /// quarantining.
+    pub max_invalid_uploads: usize,
+    /// Time in seconds uploads of a quarantined source are rejected.
+    pub duration: u64,
+}
+
+impl Default for Quarantine {
+    fn default() -> Self {
+        Self {
+            max_invalid_uploads: 50,
+            duration: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Crash submissions without a token, for products with public submissions enabled.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PublicSubmissions {
+    /// Number of submissions, and of challenges, per hour accepted from a single IP address.
+    pub rate_limit: usize,
+    /// Number of leading zero bits of the proof-of-work a submission must include; zero disables
+    /// the proof-of-work.
+    pub proof_of_work_bits: u32,
+    /// Time in seconds a proof-of-work challenge can be used.
+    pub challenge_lifetime: u64,
+    /// Maximum number of challenges that are issued and not used or expired yet.
+    pub max_challenges: usize,
+}
+
+impl Default for PublicSubmissions {
+    fn default() -> Self {
+        Self {
+            rate_limit: 10,
+            proof_of_work_bits: 16,
+            challenge_lifetime: 10 * 60,
+            max_challenges: 10000,
+        }
+    }
+}
+
+/// Phases of the data moves that are being rolled out, see `crate::rollout`.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Rollout {
+    /// Move of the crash signature from the report to the `crash.signature` column.
+    pub crash_signature: ColumnPhase,
+}
+
 #[derive(Debug, Deserialize, Default)]
 pub struct Logger {
     pub directory: String,
@@ -38,6 +646,8 @@ pub struct Logger {
 pub struct Database {
     pub uri: String,
     pub name: String,
+    /// Statements that take at least this many milliseconds are logged as slow, 0 to disable.
+    pub slow_query_threshold: u64,
 }
 
 impl Default for Database {
@@ -45,6 +655,7 @@ impl Default for Database {
         Self {
             uri: "xx".into(),
             name: "".into(),
+            slow_query_threshold: 500,
         }
     }
 }
@@ -55,6 +666,44 @@ pub struct Settings {
     pub logger: Logger,
     pub database: Database,
     pub auth: Auth,
+    #[serde(default)]
+    pub ingest: Ingest,
+    #[serde(default)]
+    pub load_shedding: LoadShedding,
+    #[serde(default)]
+    pub analyzers: Analyzers,
+    #[serde(default)]
+    pub attachment_processing: AttachmentProcessing,
+    #[serde(default)]
+    pub symbol_servers: SymbolServers,
+    #[serde(default)]
+    pub symbol_conversion: SymbolConversion,
+    #[serde(default)]
+    pub ci_builds: CiBuilds,
+    #[serde(default)]
+    pub minidumps: Minidumps,
+    #[serde(default)]
+    pub metrics: Metrics,
+    #[serde(default)]
+    pub maintenance: Maintenance,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub import: Import,
+    #[serde(default)]
+    pub cache: Cache,
+    #[serde(default)]
+    pub quarantine: Quarantine,
+    #[serde(default)]
+    pub public_submissions: PublicSubmissions,
+    #[serde(default)]
+    pub client_diagnostics: ClientDiagnostics,
+    #[serde(default)]
+    pub content_types: ContentTypes,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub rollout: Rollout,
 }
 
 impl Settings {
@@ -0,0 +1,248 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// Longest wait between two attempts, also when the server asks to wait longer.
+const MAX_DELAY: Duration = Duration::from_secs(5 * 60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid server URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("invalid response: {0}")]
+    Response(#[from] serde_json::Error),
+    #[error("server returned {status}: {message}")]
+    Server { status: StatusCode, message: String },
+}
+
+/// A file to upload as a multipart form, with the form fields that precede it.
+#[derive(Debug, Clone)]
+pub struct Upload {
+    /// Endpoint relative to the server URL, e.g. `api/symbols/upload`.
+    pub endpoint: &'static str,
+    pub query: Vec<(&'static str, String)>,
+    pub fields: Vec<(String, String)>,
+    /// Name of the form field of the file.
+    pub field: &'static str,
+    pub file: PathBuf,
+}
+
+/// Sends uploads to a Guardrail server. Uploads that fail because the server is unavailable or
+/// busy are retried, waiting longer after every attempt or as long as the server asks.
+pub struct Client {
+    http: reqwest::Client,
+    url: Url,
+    token: Option<String>,
+    retries: u32,
+    compress: bool,
+}
+
+impl Client {
+    pub fn new(
+        url: Url,
+        token: Option<String>,
+        retries: u32,
+        compress: bool,
+    ) -> Result<Self, Error> {
+        let http = reqwest::Client::builder()
+            .user_agent(concat!("guardrail-cli/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            http,
+            url,
+            token,
+            retries,
+            compress,
+        })
+    }
+
+    /// Uploads the file and returns the JSON response of the server.
+    pub async fn upload(&self, upload: &Upload) -> Result<serde_json::Value, Error> {
+        let mut url = endpoint(&self.url, upload.endpoint)?;
+        url.query_pairs_mut().extend_pairs(&upload.query);
+
+        let content = tokio::fs::read(&upload.file).await?;
+        let boundary = format!("guardrail-{}", uuid::Uuid::new_v4().simple());
+        let mut body = multipart(
+            &boundary,
+            &upload.fields,
+            upload.field,
+            &file_name(&upload.file),
+            &content,
+        );
+        if self.compress {
+            body = gzip(&body)?;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .http
+                .post(url.clone())
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={}", boundary),
+                )
+                .body(body.clone());
+            if self.compress {
+                request = request.header(CONTENT_ENCODING, "gzip");
+            }
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            let delay = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    return Ok(serde_json::from_str(&response.text().await?)?);
+                }
+                Ok(response) if retryable(response.status()) && attempt < self.retries => {
+                    retry_after(&response).unwrap_or_else(|| backoff(attempt))
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let message = response.text().await.unwrap_or_default();
+                    return Err(Error::Server { status, message });
+                }
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < self.retries => {
+                    backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            attempt += 1;
+            tokio::time::sleep(delay.min(MAX_DELAY)).await;
+        }
+    }
+}
+
+/// Returns the URL of the endpoint on the server, keeping the path prefix of the server URL.
+fn endpoint(url: &Url, endpoint: &str) -> Result<Url, Error> {
+    Ok(Url::parse(&format!(
+        "{}/{}",
+        url.as_str().trim_end_matches('/'),
+        endpoint
+    ))?)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "upload".to_owned())
+}
+
+/// Encodes the fields and the file as `multipart/form-data`. Fields come first, as the server
+/// reads e.g. the annotations of a crash before its minidump.
+fn multipart(
+    boundary: &str,
+    fields: &[(String, String)],
+    name: &str,
+    filename: &str,
+    content: &[u8],
+) -> Vec<u8> {
+    let escape = |value: &str| value.replace(['"', '\r', '\n'], "_");
+    let mut body = Vec::with_capacity(content.len() + 512);
+    for (key, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary,
+                escape(key),
+                value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            escape(name),
+            escape(filename)
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Whether the server may accept the upload when it is sent again later.
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Time to wait before the next attempt: 1, 2, 4, ... seconds.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.min(8))
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        let url = Url::parse("https://crashes.example.com/guardrail/").unwrap();
+        assert_eq!(
+            endpoint(&url, "api/symbols/upload").unwrap().as_str(),
+            "https://crashes.example.com/guardrail/api/symbols/upload"
+        );
+        let url = Url::parse("https://crashes.example.com").unwrap();
+        assert_eq!(
+            endpoint(&url, "api/minidump/upload").unwrap().as_str(),
+            "https://crashes.example.com/api/minidump/upload"
+        );
+    }
+
+    #[test]
+    fn test_multipart() {
+        let fields = vec![("channel".to_owned(), "beta".to_owned())];
+        let body = multipart("b", &fields, "upload_file_minidump", "a\".dmp", b"MDMP");
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--b\r\nContent-Disposition: form-data; name=\"channel\"\r\n\r\nbeta\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"upload_file_minidump\"; \
+             filename=\"a_.dmp\"\r\nContent-Type: application/octet-stream\r\n\r\nMDMP\r\n--b--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_retry() {
+        assert!(retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retryable(StatusCode::BAD_REQUEST));
+        assert!(!retryable(StatusCode::UNAUTHORIZED));
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+    }
+}
@@ -0,0 +1,240 @@
+mod client;
+
+use clap::{Parser, Subcommand};
+use futures::stream::{self, StreamExt};
+use std::path::PathBuf;
+use std::pin::pin;
+use std::process::ExitCode;
+use url::Url;
+
+use client::{Client, Upload};
+
+/// Uploads symbols and minidumps to a Guardrail server, e.g. from a CI pipeline.
+#[derive(Debug, Parser)]
+#[command(name = "guardrail-cli", version)]
+struct Cli {
+    /// URL of the server, including its path prefix.
+    #[arg(long, env = "GUARDRAIL_URL")]
+    url: Url,
+    /// Token sent as bearer token with every upload.
+    #[arg(long, env = "GUARDRAIL_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Number of times an upload is retried when the server is unavailable or busy.
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+    /// Number of files uploaded at the same time.
+    #[arg(long, short, default_value_t = 4)]
+    jobs: usize,
+    /// Sends files as they are instead of compressed with gzip.
+    #[arg(long)]
+    no_compress: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Manages symbol files.
+    Symbols {
+        #[command(subcommand)]
+        command: SymbolsCommand,
+    },
+    /// Manages crashes.
+    Crash {
+        #[command(subcommand)]
+        command: CrashCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SymbolsCommand {
+    /// Uploads Breakpad symbol files.
+    Upload {
+        #[arg(long)]
+        product: String,
+        #[arg(long)]
+        version: String,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CrashCommand {
+    /// Uploads minidumps, each as a crash.
+    Upload {
+        #[arg(long)]
+        product: String,
+        #[arg(long)]
+        version: String,
+        #[arg(long)]
+        channel: Option<String>,
+        /// Environment the crashes were reported from, e.g. `staging`.
+        #[arg(long)]
+        environment: Option<String>,
+        /// Annotation of the crashes as `key=value`; can be repeated.
+        #[arg(long = "annotation", value_parser = parse_annotation)]
+        annotations: Vec<(String, String)>,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+fn parse_annotation(annotation: &str) -> Result<(String, String), String> {
+    match annotation.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_owned(), value.to_owned()))
+        }
+        _ => Err(format!("expected key=value, got '{}'", annotation)),
+    }
+}
+
+fn uploads(command: Command) -> Vec<Upload> {
+    match command {
+        Command::Symbols {
+            command:
+                SymbolsCommand::Upload {
+                    product,
+                    version,
+                    files,
+                },
+        } => files
+            .into_iter()
+            .map(|file| Upload {
+                endpoint: "api/symbols/upload",
+                query: vec![("product", product.clone()), ("version", version.clone())],
+                fields: vec![],
+                field: "upload_file_symbols",
+                file,
+            })
+            .collect(),
+        Command::Crash {
+            command:
+                CrashCommand::Upload {
+                    product,
+                    version,
+                    channel,
+                    environment,
+                    annotations,
+                    files,
+                },
+        } => {
+            let mut query = vec![("product", product), ("version", version)];
+            query.extend(channel.map(|channel| ("channel", channel)));
+            query.extend(environment.map(|environment| ("environment", environment)));
+            files
+                .into_iter()
+                .map(|file| Upload {
+                    endpoint: "api/minidump/upload",
+                    query: query.clone(),
+                    fields: annotations.clone(),
+                    field: "upload_file_minidump",
+                    file,
+                })
+                .collect()
+        }
+    }
+}
+
+/// Describes the response of a successful upload.
+fn outcome(response: &serde_json::Value) -> String {
+    let result = response["result"].as_str().unwrap_or("ok");
+    match response["crash_id"].as_str() {
+        Some(crash_id) => format!("{} (crash {})", result, crash_id),
+        None => result.to_owned(),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let client = match Client::new(cli.url, cli.token, cli.retries, !cli.no_compress) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let uploads = uploads(cli.command);
+    let total = uploads.len();
+    let client = &client;
+    let mut results = pin!(stream::iter(uploads)
+        .map(|upload| async move {
+            let result = client.upload(&upload).await;
+            (upload.file, result)
+        })
+        .buffer_unordered(cli.jobs.max(1)));
+
+    let mut failed = 0;
+    while let Some((file, result)) = results.next().await {
+        match result {
+            Ok(response) => println!("{}: {}", file.display(), outcome(&response)),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{}: {}", file.display(), e);
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} of {} uploads failed", failed, total);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotation() {
+        assert_eq!(
+            parse_annotation("build=1234").unwrap(),
+            ("build".to_owned(), "1234".to_owned())
+        );
+        assert_eq!(
+            parse_annotation("url=https://example.com/?a=b").unwrap(),
+            ("url".to_owned(), "https://example.com/?a=b".to_owned())
+        );
+        assert!(parse_annotation("build").is_err());
+        assert!(parse_annotation("=1234").is_err());
+    }
+
+    #[test]
+    fn test_uploads() {
+        let cli = Cli::parse_from([
+            "guardrail-cli",
+            "--url",
+            "https://crashes.example.com",
+            "crash",
+            "upload",
+            "--product",
+            "Workrave",
+            "--version",
+            "1.11.0",
+            "--channel",
+            "beta",
+            "--annotation",
+            "build=1234",
+            "a.dmp",
+            "b.dmp",
+        ]);
+        let uploads = uploads(cli.command);
+        assert_eq!(uploads.len(), 2);
+        assert_eq!(uploads[0].endpoint, "api/minidump/upload");
+        assert_eq!(
+            uploads[1].query,
+            vec![
+                ("product", "Workrave".to_owned()),
+                ("version", "1.11.0".to_owned()),
+                ("channel", "beta".to_owned()),
+            ]
+        );
+        assert_eq!(
+            uploads[1].fields,
+            vec![("build".to_owned(), "1234".to_owned())]
+        );
+        assert_eq!(uploads[1].file, PathBuf::from("b.dmp"));
+    }
+}
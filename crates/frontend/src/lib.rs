@@ -25,5 +25,7 @@ pub fn hydrate() {
     .expect("Unable to configure tracing");
     console_error_panic_hook::set_once();
 
+    leptos::server_fn::client::set_server_url(app::prefix::path_prefix());
+
     leptos::mount_to_body(App);
 }
@@ -11,6 +11,59 @@ mod m20230930_000008_create_session_table;
 mod m20231210_000009_create_user_table;
 mod m20231210_000010_create_credential_table;
 mod m20240608_000011_create_role_table;
+mod m20240801_000012_create_link_template_table;
+mod m20240805_000013_add_build_age_policy_to_product;
+mod m20240810_000014_add_file_size_to_symbols;
+mod m20240815_000015_create_device_key_table;
+mod m20240820_000016_create_alert_table;
+mod m20240825_000017_create_product_deletion_table;
+mod m20240901_000018_add_client_hints_to_crash;
+mod m20240905_000019_add_annotation_promotion;
+mod m20240910_000020_create_api_token_table;
+mod m20240915_000021_add_redacted_fields_to_product;
+mod m20240920_000022_add_deactivated_at_to_user;
+mod m20240925_000023_add_minidump_hash_to_crash;
+mod m20240930_000024_add_annotation_facet_index;
+mod m20241005_000025_add_version_metadata;
+mod m20241010_000026_add_allowed_content_types_to_product;
+mod m20241015_000027_add_platform_to_crash;
+mod m20241020_000028_add_compression_to_attachment;
+mod m20241025_000029_create_job_lease_table;
+mod m20241030_000030_create_webhook_table;
+mod m20241105_000031_add_quarantine_to_symbols;
+mod m20241110_000032_add_environment;
+mod m20241115_000033_add_accepting_crashes_to_product;
+mod m20241120_000034_create_issue_table;
+mod m20241125_000035_create_upload_quarantine_table;
+mod m20241130_000036_add_public_submissions;
+mod m20241205_000037_add_source_links_to_product;
+mod m20241210_000038_add_triage_to_issue;
+mod m20241215_000039_add_usage_to_device_key;
+mod m20241220_000040_add_attachment_processing;
+mod m20241225_000041_add_legal_hold_to_crash;
+mod m20241227_000042_add_verified_checksum_to_crash;
+mod m20241228_000043_add_session_expires_at_index;
+mod m20241229_000044_create_notification_table;
+mod m20241230_000045_add_default_annotations_to_product;
+mod m20241231_000046_add_auto_create_versions_to_product;
+mod m20250101_000047_create_client_diagnostic_table;
+mod m20250105_000048_create_symbol_conversion_table;
+mod m20250110_000049_add_ingestion_windows_to_product;
+mod m20250112_000050_create_organization_tables;
+mod m20250115_000051_create_build_metadata_table;
+mod m20250118_000052_add_upload_quotas;
+mod m20250120_000053_create_branding_table;
+mod m20250122_000054_create_missing_symbols_table;
+mod m20250124_000055_add_signature_to_crash;
+mod m20250126_000056_add_noise_controls_to_issue;
+mod m20250127_000057_add_scope_to_api_token;
+mod m20250129_000058_partition_crash_tables;
+mod m20250131_000059_add_processing_status_to_crash;
+mod m20250202_000060_add_clock_skew_to_crash;
+mod m20250204_000061_move_default_partition_rows;
+mod m20250206_000062_create_job_state_table;
+mod m20250208_000063_normalize_symbols_ids;
+mod m20250210_000064_add_unique_index_to_alert;
 
 pub struct Migrator;
 pub use m20230930_000008_create_session_table::Session as SessionColumns;
@@ -30,6 +83,59 @@ impl MigratorTrait for Migrator {
             Box::new(m20231210_000009_create_user_table::Migration),
             Box::new(m20231210_000010_create_credential_table::Migration),
             Box::new(m20240608_000011_create_role_table::Migration),
+            Box::new(m20240801_000012_create_link_template_table::Migration),
+            Box::new(m20240805_000013_add_build_age_policy_to_product::Migration),
+            Box::new(m20240810_000014_add_file_size_to_symbols::Migration),
+            Box::new(m20240815_000015_create_device_key_table::Migration),
+            Box::new(m20240820_000016_create_alert_table::Migration),
+            Box::new(m20240825_000017_create_product_deletion_table::Migration),
+            Box::new(m20240901_000018_add_client_hints_to_crash::Migration),
+            Box::new(m20240905_000019_add_annotation_promotion::Migration),
+            Box::new(m20240910_000020_create_api_token_table::Migration),
+            Box::new(m20240915_000021_add_redacted_fields_to_product::Migration),
+            Box::new(m20240920_000022_add_deactivated_at_to_user::Migration),
+            Box::new(m20240925_000023_add_minidump_hash_to_crash::Migration),
+            Box::new(m20240930_000024_add_annotation_facet_index::Migration),
+            Box::new(m20241005_000025_add_version_metadata::Migration),
+            Box::new(m20241010_000026_add_allowed_content_types_to_product::Migration),
+            Box::new(m20241015_000027_add_platform_to_crash::Migration),
+            Box::new(m20241020_000028_add_compression_to_attachment::Migration),
+            Box::new(m20241025_000029_create_job_lease_table::Migration),
+            Box::new(m20241030_000030_create_webhook_table::Migration),
+            Box::new(m20241105_000031_add_quarantine_to_symbols::Migration),
+            Box::new(m20241110_000032_add_environment::Migration),
+            Box::new(m20241115_000033_add_accepting_crashes_to_product::Migration),
+            Box::new(m20241120_000034_create_issue_table::Migration),
+            Box::new(m20241125_000035_create_upload_quarantine_table::Migration),
+            Box::new(m20241130_000036_add_public_submissions::Migration),
+            Box::new(m20241205_000037_add_source_links_to_product::Migration),
+            Box::new(m20241210_000038_add_triage_to_issue::Migration),
+            Box::new(m20241215_000039_add_usage_to_device_key::Migration),
+            Box::new(m20241220_000040_add_attachment_processing::Migration),
+            Box::new(m20241225_000041_add_legal_hold_to_crash::Migration),
+            Box::new(m20241227_000042_add_verified_checksum_to_crash::Migration),
+            Box::new(m20241228_000043_add_session_expires_at_index::Migration),
+            Box::new(m20241229_000044_create_notification_table::Migration),
+            Box::new(m20241230_000045_add_default_annotations_to_product::Migration),
+            Box::new(m20241231_000046_add_auto_create_versions_to_product::Migration),
+            Box::new(m20250101_000047_create_client_diagnostic_table::Migration),
+            Box::new(m20250105_000048_create_symbol_conversion_table::Migration),
+            Box::new(m20250110_000049_add_ingestion_windows_to_product::Migration),
+            Box::new(m20250112_000050_create_organization_tables::Migration),
+            Box::new(m20250115_000051_create_build_metadata_table::Migration),
+            Box::new(m20250118_000052_add_upload_quotas::Migration),
+            Box::new(m20250120_000053_create_branding_table::Migration),
+            Box::new(m20250122_000054_create_missing_symbols_table::Migration),
+            Box::new(m20250124_000055_add_signature_to_crash::Migration),
+            Box::new(m20250126_000056_add_noise_controls_to_issue::Migration),
+            Box::new(m20250127_000057_add_scope_to_api_token::Migration),
+            Box::new(m20250129_000058_partition_crash_tables::Migration),
+            Box::new(m20250131_000059_add_processing_status_to_crash::Migration),
+            Box::new(m20250202_000060_add_clock_skew_to_crash::Migration),
+            Box::new(m20250204_000061_move_default_partition_rows::Migration),
+            Box::new(m20250206_000062_create_job_state_table::Migration),
+            Box::new(m20250208_000063_normalize_symbols_ids::Migration),
+            Box::new(m20250210_000064_add_unique_index_to_alert::Migration),
         ]
     }
 }
@@ -57,7 +57,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Attachment {
+pub enum Attachment {
     Table,
     Id,
     CreatedAt,
@@ -76,7 +76,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Annotation {
+pub enum Annotation {
     Table,
     Id,
     CreatedAt,
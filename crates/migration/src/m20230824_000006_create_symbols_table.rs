@@ -63,7 +63,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Symbols {
+pub enum Symbols {
     Table,
     Id,
     CreatedAt,
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LinkTemplate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LinkTemplate::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(LinkTemplate::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(LinkTemplate::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LinkTemplate::Name).string().not_null())
+                    .col(ColumnDef::new(LinkTemplate::Url).string().not_null())
+                    .col(ColumnDef::new(LinkTemplate::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-link_template-product")
+                            .from(LinkTemplate::Table, LinkTemplate::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-unique-link_template-product-and-name")
+                            .col(LinkTemplate::Name)
+                            .col(LinkTemplate::ProductId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LinkTemplate::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum LinkTemplate {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Name,
+    Url,
+    ProductId,
+}
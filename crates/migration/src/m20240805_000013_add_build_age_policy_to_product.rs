@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// SQLite only supports a single column per ALTER TABLE statement.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Product::Table)
+                    .add_column(ColumnDef::new(ProductBuildAge::MaxBuildAgeDays).integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Product::Table)
+                    .add_column(ColumnDef::new(ProductBuildAge::BuildAgeOverrides).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Product::Table)
+                    .add_column(ColumnDef::new(ProductBuildAge::BuildAgeAllowList).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            ProductBuildAge::BuildAgeAllowList,
+            ProductBuildAge::BuildAgeOverrides,
+            ProductBuildAge::MaxBuildAgeDays,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Product::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ProductBuildAge {
+    MaxBuildAgeDays,
+    BuildAgeOverrides,
+    BuildAgeAllowList,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeviceKey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeviceKey::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DeviceKey::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(DeviceKey::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(DeviceKey::Name).string().not_null())
+                    .col(ColumnDef::new(DeviceKey::KeyHash).string().not_null())
+                    .col(ColumnDef::new(DeviceKey::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-device_key-product")
+                            .from(DeviceKey::Table, DeviceKey::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-unique-device_key-product-and-name")
+                            .col(DeviceKey::Name)
+                            .col(DeviceKey::ProductId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeviceKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DeviceKey {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Name,
+    KeyHash,
+    ProductId,
+}
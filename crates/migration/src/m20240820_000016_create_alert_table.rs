@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+use super::m20230824_000002_create_version_table::Version;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Alert::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Alert::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Alert::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Alert::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Alert::Severity).string().not_null())
+                    .col(ColumnDef::new(Alert::Message).string().not_null())
+                    .col(ColumnDef::new(Alert::Observed).big_integer().not_null())
+                    .col(ColumnDef::new(Alert::Expected).double().not_null())
+                    .col(ColumnDef::new(Alert::WindowStart).date_time().not_null())
+                    .col(ColumnDef::new(Alert::ProductId).uuid().not_null())
+                    .col(ColumnDef::new(Alert::VersionId).uuid())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-alert-product")
+                            .from(Alert::Table, Alert::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-alert-version")
+                            .from(Alert::Table, Alert::VersionId)
+                            .to(Version::Table, Version::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Alert::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Alert {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Severity,
+    Message,
+    Observed,
+    Expected,
+    WindowStart,
+    ProductId,
+    VersionId,
+}
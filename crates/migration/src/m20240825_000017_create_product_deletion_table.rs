@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// No foreign key on the product: the deletion record outlives the product it tracks.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductDeletion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProductDeletion::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductDeletion::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ProductDeletion::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ProductDeletion::ProductId)
+                            .uuid()
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductDeletion::ProductName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProductDeletion::State).string().not_null())
+                    .col(
+                        ColumnDef::new(ProductDeletion::CrashesDeleted)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProductDeletion::SymbolsDeleted)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ProductDeletion::FinishedAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProductDeletion::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ProductDeletion {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    ProductId,
+    ProductName,
+    State,
+    CrashesDeleted,
+    SymbolsDeleted,
+    FinishedAt,
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashClientHints::UserAgent).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashClientHints::Sdk).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [CrashClientHints::Sdk, CrashClientHints::UserAgent] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Crash::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashClientHints {
+    UserAgent,
+    Sdk,
+}
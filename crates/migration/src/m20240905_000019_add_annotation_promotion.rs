@@ -0,0 +1,69 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Product::Table)
+                    .add_column(ColumnDef::new(AnnotationPromotion::PromotedAnnotations).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(AnnotationPromotion::Promoted).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        if let DbBackend::Postgres = db.get_database_backend() {
+            db.execute_unprepared(
+                "CREATE INDEX IF NOT EXISTS idx_crash_promoted ON crash USING GIN (promoted jsonb_path_ops)",
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if let DbBackend::Postgres = db.get_database_backend() {
+            db.execute_unprepared("DROP INDEX IF EXISTS idx_crash_promoted")
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .drop_column(AnnotationPromotion::Promoted)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Product::Table)
+                    .drop_column(AnnotationPromotion::PromotedAnnotations)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum AnnotationPromotion {
+    PromotedAnnotations,
+    Promoted,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20231210_000009_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiToken::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ApiToken::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(ApiToken::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ApiToken::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ApiToken::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(ApiToken::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ApiToken::UserId).uuid().not_null())
+                    .col(ColumnDef::new(ApiToken::LastUsedAt).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_token-user")
+                            .from(ApiToken::Table, ApiToken::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .name("idx-unique-api_token-user-and-name")
+                            .col(ApiToken::Name)
+                            .col(ApiToken::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiToken::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ApiToken {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Name,
+    TokenHash,
+    UserId,
+    LastUsedAt,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashDeduplication::MinidumpHash).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .unique()
+                    .name("idx-unique-crash-product-and-minidump_hash")
+                    .table(Crash::Table)
+                    .col(Crash::ProductId)
+                    .col(CrashDeduplication::MinidumpHash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-unique-crash-product-and-minidump_hash")
+                    .table(Crash::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .drop_column(CrashDeduplication::MinidumpHash)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashDeduplication {
+    MinidumpHash,
+}
@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000005_create_annotation_table::Annotation;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-annotation-key-and-value")
+                    .table(Annotation::Table)
+                    .col(Annotation::Key)
+                    .col(Annotation::Value)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-annotation-key-and-value")
+                    .table(Annotation::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
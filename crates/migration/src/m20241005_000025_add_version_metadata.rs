@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000002_create_version_table::Version;
+use super::m20240815_000015_create_device_key_table::DeviceKey;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Version::Table)
+                    .add_column(ColumnDef::new(VersionMetadata::Channel).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Version::Table)
+                    .add_column(ColumnDef::new(VersionMetadata::ReleaseDate).date_time())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeviceKey::Table)
+                    .add_column(ColumnDef::new(DeviceKeyEntitlements::Entitlements).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeviceKey::Table)
+                    .drop_column(DeviceKeyEntitlements::Entitlements)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Version::Table)
+                    .drop_column(VersionMetadata::ReleaseDate)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Version::Table)
+                    .drop_column(VersionMetadata::Channel)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum VersionMetadata {
+    Channel,
+    ReleaseDate,
+}
+
+#[derive(DeriveIden)]
+pub enum DeviceKeyEntitlements {
+    Entitlements,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashPlatform::Platform).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-crash-product-and-platform")
+                    .table(Crash::Table)
+                    .col(Crash::ProductId)
+                    .col(CrashPlatform::Platform)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-crash-product-and-platform")
+                    .table(Crash::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .drop_column(CrashPlatform::Platform)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashPlatform {
+    Platform,
+}
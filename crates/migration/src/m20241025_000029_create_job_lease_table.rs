@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobLease::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(JobLease::Id).text().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(JobLease::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(JobLease::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(JobLease::Holder).text().not_null())
+                    .col(ColumnDef::new(JobLease::ExpiresAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JobLease::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum JobLease {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Holder,
+    ExpiresAt,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000006_create_symbols_table::Symbols;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Symbols::Table)
+                    .add_column(ColumnDef::new(SymbolsQuarantine::QuarantineReason).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Symbols::Table)
+                    .drop_column(SymbolsQuarantine::QuarantineReason)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum SymbolsQuarantine {
+    QuarantineReason,
+}
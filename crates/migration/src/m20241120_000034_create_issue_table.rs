@@ -0,0 +1,126 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Issue::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Issue::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Issue::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Issue::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Issue::Signature).string().not_null())
+                    .col(ColumnDef::new(Issue::Title).string().not_null())
+                    .col(ColumnDef::new(Issue::FirstSeen).date_time().not_null())
+                    .col(ColumnDef::new(Issue::LastSeen).date_time().not_null())
+                    .col(
+                        ColumnDef::new(Issue::Count)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Issue::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-issue-product")
+                            .from(Issue::Table, Issue::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-issue-product-and-signature")
+                    .table(Issue::Table)
+                    .col(Issue::ProductId)
+                    .col(Issue::Signature)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // No foreign key, as SQLite cannot add one to an existing table. Issues are only removed
+        // together with their product, and with it its crashes.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashIssue::IssueId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-crash-issue")
+                    .table(Crash::Table)
+                    .col(CrashIssue::IssueId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-crash-issue")
+                    .table(Crash::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .drop_column(CrashIssue::IssueId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Issue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Issue {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Signature,
+    Title,
+    FirstSeen,
+    LastSeen,
+    Count,
+    ProductId,
+}
+
+#[derive(DeriveIden)]
+pub enum CrashIssue {
+    IssueId,
+}
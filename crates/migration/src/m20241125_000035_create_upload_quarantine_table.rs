@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UploadQuarantine::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UploadQuarantine::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UploadQuarantine::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(UploadQuarantine::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(UploadQuarantine::Source).string().not_null())
+                    .col(ColumnDef::new(UploadQuarantine::Reason).string().not_null())
+                    .col(
+                        ColumnDef::new(UploadQuarantine::ExpiresAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UploadQuarantine::LiftedAt).date_time())
+                    .col(ColumnDef::new(UploadQuarantine::LiftedBy).uuid())
+                    .col(
+                        ColumnDef::new(UploadQuarantine::ProductId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-upload_quarantine-product")
+                            .from(UploadQuarantine::Table, UploadQuarantine::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-upload_quarantine-source")
+                    .table(UploadQuarantine::Table)
+                    .col(UploadQuarantine::Source)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UploadQuarantine::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum UploadQuarantine {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Source,
+    Reason,
+    ExpiresAt,
+    LiftedAt,
+    LiftedBy,
+    ProductId,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20241120_000034_create_issue_table::Issue;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// SQLite only supports a single column per ALTER TABLE statement. The assignee has no foreign
+// key, as SQLite cannot add one to an existing table; users are deactivated, not removed.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Issue::Table)
+                    .add_column(
+                        ColumnDef::new(IssueTriage::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Issue::Table)
+                    .add_column(ColumnDef::new(IssueTriage::AssigneeId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-issue-status")
+                    .table(Issue::Table)
+                    .col(IssueTriage::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-issue-status")
+                    .table(Issue::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [IssueTriage::AssigneeId, IssueTriage::Status] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Issue::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum IssueTriage {
+    Status,
+    AssigneeId,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240815_000015_create_device_key_table::DeviceKey;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// SQLite only supports a single column per ALTER TABLE statement.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeviceKey::Table)
+                    .add_column(
+                        ColumnDef::new(DeviceKeyUsage::SubmissionCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DeviceKey::Table)
+                    .add_column(ColumnDef::new(DeviceKeyUsage::LastUsedAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [DeviceKeyUsage::LastUsedAt, DeviceKeyUsage::SubmissionCount] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(DeviceKey::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DeviceKeyUsage {
+    SubmissionCount,
+    LastUsedAt,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// SQLite only supports a single column per ALTER TABLE statement. A crash is on hold while
+// `legal_hold_at` is set.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashLegalHold::LegalHoldAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashLegalHold::LegalHoldBy).uuid())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashLegalHold::LegalHoldReason).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            CrashLegalHold::LegalHoldReason,
+            CrashLegalHold::LegalHoldBy,
+            CrashLegalHold::LegalHoldAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Crash::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashLegalHold {
+    LegalHoldAt,
+    LegalHoldBy,
+    LegalHoldReason,
+}
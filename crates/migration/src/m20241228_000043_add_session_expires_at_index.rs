@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230930_000008_create_session_table::Session;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Lets the nightly maintenance find expired sessions without scanning the session table.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-session-expires_at")
+                    .table(Session::Table)
+                    .col(Session::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-session-expires_at")
+                    .table(Session::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
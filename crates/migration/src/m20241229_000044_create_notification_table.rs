@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Notification::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Notification::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Notification::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Notification::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Notification::Event).string().not_null())
+                    .col(ColumnDef::new(Notification::Channel).string().not_null())
+                    .col(ColumnDef::new(Notification::Recipient).string().not_null())
+                    .col(ColumnDef::new(Notification::Subject).string().not_null())
+                    .col(
+                        ColumnDef::new(Notification::Payload)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Notification::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(Notification::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Notification::NextAttemptAt).date_time())
+                    .col(ColumnDef::new(Notification::SentAt).date_time())
+                    .col(ColumnDef::new(Notification::Error).string())
+                    .col(ColumnDef::new(Notification::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-notification-product")
+                            .from(Notification::Table, Notification::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-notification-next_attempt_at")
+                    .table(Notification::Table)
+                    .col(Notification::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Notification::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Notification {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Event,
+    Channel,
+    Recipient,
+    Subject,
+    Payload,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    SentAt,
+    Error,
+    ProductId,
+}
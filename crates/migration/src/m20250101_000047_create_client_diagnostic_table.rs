@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ClientDiagnostic::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ClientDiagnostic::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ClientDiagnostic::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ClientDiagnostic::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ClientDiagnostic::Kind).string().not_null())
+                    .col(ColumnDef::new(ClientDiagnostic::StatusCode).integer())
+                    .col(ColumnDef::new(ClientDiagnostic::Message).string())
+                    .col(ColumnDef::new(ClientDiagnostic::Version).string())
+                    .col(ColumnDef::new(ClientDiagnostic::Sdk).string())
+                    .col(ColumnDef::new(ClientDiagnostic::Attempts).integer())
+                    .col(ColumnDef::new(ClientDiagnostic::FailedAt).date_time())
+                    .col(
+                        ColumnDef::new(ClientDiagnostic::ProductId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-client_diagnostic-product")
+                            .from(ClientDiagnostic::Table, ClientDiagnostic::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-client_diagnostic-product-and-created")
+                    .table(ClientDiagnostic::Table)
+                    .col(ClientDiagnostic::ProductId)
+                    .col(ClientDiagnostic::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ClientDiagnostic::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ClientDiagnostic {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Kind,
+    StatusCode,
+    Message,
+    Version,
+    Sdk,
+    Attempts,
+    FailedAt,
+    ProductId,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+use super::m20230824_000002_create_version_table::Version;
+use super::m20230824_000006_create_symbols_table::Symbols;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SymbolConversion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SymbolConversion::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::FileName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::FileLocation)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::FileSize)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SymbolConversion::Status).string().not_null())
+                    .col(ColumnDef::new(SymbolConversion::Error).string())
+                    .col(ColumnDef::new(SymbolConversion::SymbolsId).uuid())
+                    .col(
+                        ColumnDef::new(SymbolConversion::ProductId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolConversion::VersionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-symbol_conversion-symbols")
+                            .from(SymbolConversion::Table, SymbolConversion::SymbolsId)
+                            .to(Symbols::Table, Symbols::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-symbol_conversion-product")
+                            .from(SymbolConversion::Table, SymbolConversion::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-symbol_conversion-version")
+                            .from(SymbolConversion::Table, SymbolConversion::VersionId)
+                            .to(Version::Table, Version::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-symbol_conversion-status")
+                    .table(SymbolConversion::Table)
+                    .col(SymbolConversion::Status)
+                    .col(SymbolConversion::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SymbolConversion::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SymbolConversion {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    FileName,
+    FileLocation,
+    FileSize,
+    Status,
+    Error,
+    SymbolsId,
+    ProductId,
+    VersionId,
+}
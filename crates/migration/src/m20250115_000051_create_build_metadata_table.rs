@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240801_000012_create_link_template_table::LinkTemplate;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LinkTemplate::Table)
+                    .add_column(ColumnDef::new(MetadataUrl::MetadataUrl).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildMetadata::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BuildMetadata::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildMetadata::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(BuildMetadata::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(BuildMetadata::Url).string().not_null())
+                    .col(ColumnDef::new(BuildMetadata::Status).string())
+                    .col(ColumnDef::new(BuildMetadata::Artifacts).json_binary())
+                    .col(ColumnDef::new(BuildMetadata::Error).string())
+                    .col(
+                        ColumnDef::new(BuildMetadata::FetchedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildMetadata::LinkTemplateId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-build_metadata-link_template")
+                            .from(BuildMetadata::Table, BuildMetadata::LinkTemplateId)
+                            .to(LinkTemplate::Table, LinkTemplate::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-build_metadata-link_template-and-url")
+                    .table(BuildMetadata::Table)
+                    .col(BuildMetadata::LinkTemplateId)
+                    .col(BuildMetadata::Url)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildMetadata::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LinkTemplate::Table)
+                    .drop_column(MetadataUrl::MetadataUrl)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BuildMetadata {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Url,
+    Status,
+    Artifacts,
+    Error,
+    FetchedAt,
+    LinkTemplateId,
+}
+
+#[derive(DeriveIden)]
+enum MetadataUrl {
+    MetadataUrl,
+}
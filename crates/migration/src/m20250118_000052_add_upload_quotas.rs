@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite adds one column per statement.
+        for column in [
+            ColumnDef::new(UploadQuota::MaxMinidumpSize)
+                .big_integer()
+                .to_owned(),
+            ColumnDef::new(UploadQuota::MaxAttachmentSize)
+                .big_integer()
+                .to_owned(),
+            ColumnDef::new(UploadQuota::MaxAttachments)
+                .integer()
+                .to_owned(),
+            ColumnDef::new(UploadQuota::MaxDailyUploads)
+                .integer()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Product::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UploadCount::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UploadCount::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UploadCount::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(UploadCount::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(UploadCount::Day).date().not_null())
+                    .col(ColumnDef::new(UploadCount::Count).integer().not_null())
+                    .col(ColumnDef::new(UploadCount::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-upload_count-product")
+                            .from(UploadCount::Table, UploadCount::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-upload_count-product-and-day")
+                    .table(UploadCount::Table)
+                    .col(UploadCount::ProductId)
+                    .col(UploadCount::Day)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UploadCount::Table).to_owned())
+            .await?;
+        for column in [
+            UploadQuota::MaxMinidumpSize,
+            UploadQuota::MaxAttachmentSize,
+            UploadQuota::MaxAttachments,
+            UploadQuota::MaxDailyUploads,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Product::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UploadQuota {
+    MaxMinidumpSize,
+    MaxAttachmentSize,
+    MaxAttachments,
+    MaxDailyUploads,
+}
+
+#[derive(DeriveIden)]
+enum UploadCount {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Day,
+    Count,
+    ProductId,
+}
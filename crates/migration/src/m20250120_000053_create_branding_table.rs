@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Branding::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Branding::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Branding::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Branding::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Branding::Title).string().not_null())
+                    .col(ColumnDef::new(Branding::LogoUrl).string())
+                    .col(ColumnDef::new(Branding::AccentColor).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Branding::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Branding {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Title,
+    LogoUrl,
+    AccentColor,
+}
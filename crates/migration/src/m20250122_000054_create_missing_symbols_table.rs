@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000001_create_product_table::Product;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MissingSymbols::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MissingSymbols::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MissingSymbols::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(MissingSymbols::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(MissingSymbols::ModuleId).string().not_null())
+                    .col(ColumnDef::new(MissingSymbols::BuildId).string().not_null())
+                    .col(ColumnDef::new(MissingSymbols::Count).integer().not_null())
+                    .col(
+                        ColumnDef::new(MissingSymbols::LastSeen)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MissingSymbols::ProductId).uuid().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-missing_symbols-product")
+                            .from(MissingSymbols::Table, MissingSymbols::ProductId)
+                            .to(Product::Table, Product::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-missing_symbols-product-module-build")
+                    .table(MissingSymbols::Table)
+                    .col(MissingSymbols::ProductId)
+                    .col(MissingSymbols::ModuleId)
+                    .col(MissingSymbols::BuildId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MissingSymbols::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MissingSymbols {
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    ModuleId,
+    BuildId,
+    Count,
+    LastSeen,
+    ProductId,
+}
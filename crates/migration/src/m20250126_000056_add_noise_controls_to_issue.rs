@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20241120_000034_create_issue_table::Issue;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Snoozed issues are hidden until `snoozed_until`. Issues with `auto_resolve_days` are resolved
+// once they have had no crashes for that many days. SQLite only supports a single column per
+// ALTER TABLE statement.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            ColumnDef::new(IssueNoise::SnoozedUntil)
+                .date_time()
+                .to_owned(),
+            ColumnDef::new(IssueNoise::AutoResolveDays)
+                .integer()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Issue::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [IssueNoise::SnoozedUntil, IssueNoise::AutoResolveDays] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Issue::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum IssueNoise {
+    SnoozedUntil,
+    AutoResolveDays,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240910_000020_create_api_token_table::ApiToken;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tokens can be limited to entitlements, e.g. `read`, and to a single product. Tokens without
+// either keep all permissions of their owner. SQLite only supports a single column per ALTER
+// TABLE statement.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            ColumnDef::new(ApiTokenScope::Entitlements)
+                .string()
+                .to_owned(),
+            ColumnDef::new(ApiTokenScope::ProductId).uuid().to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ApiToken::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [ApiTokenScope::Entitlements, ApiTokenScope::ProductId] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ApiToken::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ApiTokenScope {
+    Entitlements,
+    ProductId,
+}
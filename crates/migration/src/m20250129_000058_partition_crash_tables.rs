@@ -0,0 +1,167 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+// On PostgreSQL, the crash and annotation tables are partitioned by month of `created_at`, so
+// that old crashes can be removed by dropping a partition. A partitioned table can only have
+// unique indexes that include the partition key, so:
+//
+// - the primary keys become `(id, created_at)`;
+// - annotations and attachments can no longer have a foreign key to the crash, and are deleted
+//   together with their crash by a trigger instead;
+// - the index on the minidump hash is no longer unique; `CrashRepo` serializes the submissions of
+//   a minidump instead.
+//
+// Partitions are created per month by `create_month_partition`, for the months of the existing
+// rows and the months ahead, and later by the nightly maintenance. Rows outside all monthly
+// partitions end up in the default partition.
+
+/// Months after the current month for which partitions are created.
+const MONTHS_AHEAD: u32 = 3;
+
+const CREATE_PARTITION_FUNCTION: &str = "
+    CREATE OR REPLACE FUNCTION create_month_partition(parent text, month date)
+    RETURNS void AS $$
+    DECLARE
+        start date := date_trunc('month', month);
+    BEGIN
+        EXECUTE format(
+            'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+            parent || '_p' || to_char(start, 'YYYYMM'),
+            parent,
+            start,
+            (start + interval '1 month')::date
+        );
+    END;
+    $$ language 'plpgsql';";
+
+const CREATE_DEPENDENTS_TRIGGER: &str = "
+    CREATE OR REPLACE FUNCTION delete_crash_dependents()
+    RETURNS TRIGGER AS $$
+    BEGIN
+        DELETE FROM annotation WHERE crash_id = OLD.id;
+        DELETE FROM attachment WHERE crash_id = OLD.id;
+        RETURN OLD;
+    END;
+    $$ language 'plpgsql';
+
+    CREATE TRIGGER trigger_crash_dependents
+    AFTER DELETE ON crash
+    FOR EACH ROW EXECUTE PROCEDURE delete_crash_dependents();";
+
+const CRASH_CONSTRAINTS: &str = r#"
+    ALTER TABLE crash ADD CONSTRAINT "fk-crash-project" FOREIGN KEY (product_id)
+        REFERENCES product (id) ON DELETE CASCADE ON UPDATE CASCADE;
+    ALTER TABLE crash ADD CONSTRAINT "fk-crash-version" FOREIGN KEY (version_id)
+        REFERENCES version (id) ON DELETE CASCADE ON UPDATE CASCADE;
+    CREATE INDEX "idx-crash-issue" ON crash (issue_id);
+    CREATE INDEX "idx-crash-product-and-signature" ON crash (product_id, signature);
+    CREATE INDEX "idx-crash-product-and-environment" ON crash (product_id, environment);
+    CREATE INDEX "idx-crash-product-and-platform" ON crash (product_id, platform);
+    CREATE INDEX idx_crash_promoted ON crash USING GIN (promoted jsonb_path_ops);"#;
+
+const ANNOTATION_INDEXES: &str = r#"
+    CREATE INDEX "idx-annotation-key-and-value" ON annotation (key, value);"#;
+
+/// Returns the statements that replace `table` by a table partitioned by month, with the same
+/// columns and rows.
+fn partition(table: &str) -> String {
+    format!(
+        r#"
+        ALTER TABLE {table} RENAME TO {table}_unpartitioned;
+        CREATE TABLE {table} (LIKE {table}_unpartitioned INCLUDING DEFAULTS)
+            PARTITION BY RANGE (created_at);
+        CREATE TABLE {table}_default PARTITION OF {table} DEFAULT;
+        DO $$
+        DECLARE
+            month date;
+        BEGIN
+            FOR month IN
+                SELECT generate_series(
+                    date_trunc('month', (SELECT COALESCE(MIN(created_at), now()) FROM {table}_unpartitioned)),
+                    date_trunc('month', now()) + interval '{MONTHS_AHEAD} months',
+                    interval '1 month'
+                )::date
+            LOOP
+                PERFORM create_month_partition('{table}', month);
+            END LOOP;
+        END $$;
+        INSERT INTO {table} SELECT * FROM {table}_unpartitioned;
+        DROP TABLE {table}_unpartitioned CASCADE;
+        ALTER TABLE {table} ADD PRIMARY KEY (id, created_at);
+        CREATE INDEX "idx-{table}-id" ON {table} (id);"#
+    )
+}
+
+/// Returns the statements that replace the partitioned `table` by a plain table, with the same
+/// columns and rows.
+fn unpartition(table: &str) -> String {
+    format!(
+        r#"
+        ALTER TABLE {table} RENAME TO {table}_partitioned;
+        CREATE TABLE {table} (LIKE {table}_partitioned INCLUDING DEFAULTS);
+        INSERT INTO {table} SELECT * FROM {table}_partitioned;
+        DROP TABLE {table}_partitioned CASCADE;
+        ALTER TABLE {table} ADD PRIMARY KEY (id);"#
+    )
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        db.execute_unprepared(CREATE_PARTITION_FUNCTION).await?;
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE annotation DROP CONSTRAINT IF EXISTS "fk-annotation-crash";
+            ALTER TABLE attachment DROP CONSTRAINT IF EXISTS "fk-attachment-crash";"#,
+        )
+        .await?;
+        db.execute_unprepared(&partition("crash")).await?;
+        db.execute_unprepared(CRASH_CONSTRAINTS).await?;
+        db.execute_unprepared(
+            r#"CREATE INDEX "idx-crash-product-and-minidump_hash" ON crash (product_id, minidump_hash);
+            CREATE INDEX "idx-crash-product-and-created_at" ON crash (product_id, created_at);"#,
+        )
+        .await?;
+        db.execute_unprepared(&partition("annotation")).await?;
+        db.execute_unprepared(ANNOTATION_INDEXES).await?;
+        db.execute_unprepared(r#"CREATE INDEX "idx-annotation-crash" ON annotation (crash_id);"#)
+            .await?;
+        db.execute_unprepared(CREATE_DEPENDENTS_TRIGGER).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        db.execute_unprepared(
+            "DROP TRIGGER IF EXISTS trigger_crash_dependents ON crash;
+            DROP FUNCTION IF EXISTS delete_crash_dependents;",
+        )
+        .await?;
+        db.execute_unprepared(&unpartition("annotation")).await?;
+        db.execute_unprepared(ANNOTATION_INDEXES).await?;
+        db.execute_unprepared(&unpartition("crash")).await?;
+        db.execute_unprepared(CRASH_CONSTRAINTS).await?;
+        db.execute_unprepared(
+            r#"CREATE UNIQUE INDEX "idx-unique-crash-product-and-minidump_hash" ON crash (product_id, minidump_hash);
+            ALTER TABLE annotation ADD CONSTRAINT "fk-annotation-crash" FOREIGN KEY (crash_id)
+                REFERENCES crash (id) ON DELETE CASCADE ON UPDATE CASCADE;
+            ALTER TABLE attachment ADD CONSTRAINT "fk-attachment-crash" FOREIGN KEY (crash_id)
+                REFERENCES crash (id) ON DELETE CASCADE ON UPDATE CASCADE;
+            DROP FUNCTION IF EXISTS create_month_partition;"#,
+        )
+        .await?;
+        Ok(())
+    }
+}
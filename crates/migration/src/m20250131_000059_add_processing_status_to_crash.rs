@@ -0,0 +1,102 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Whether the minidump of a crash has been processed: `pending` while it is stored or queued,
+// `processing`, `failed` with the error, or `done`. The status of existing crashes is derived
+// from their report: crashes without report are pending, and the processing errors stored in the
+// report mark crashes that are queued or failed.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(
+                        ColumnDef::new(CrashProcessing::ProcessingStatus)
+                            .string()
+                            .not_null()
+                            .default("done"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashProcessing::ProcessingError).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-crash-product-and-processing_status")
+                    .table(Crash::Table)
+                    .col(Crash::ProductId)
+                    .col(CrashProcessing::ProcessingStatus)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let (is_null, processing_error) = match db.get_database_backend() {
+            DbBackend::Postgres => (
+                "jsonb_typeof(report) = 'null'",
+                "report->>'processing_error'",
+            ),
+            _ => (
+                "json_type(report) = 'null'",
+                "json_extract(report, '$.processing_error')",
+            ),
+        };
+        db.execute_unprepared(&format!(
+            "UPDATE crash SET processing_status = 'pending' \
+             WHERE {is_null} OR {processing_error} = 'queued'"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "UPDATE crash SET processing_status = 'failed', processing_error = summary \
+             WHERE {processing_error} = 'timeout' OR summary LIKE 'Processing failed:%'"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-crash-product-and-processing_status")
+                    .table(Crash::Table)
+                    .to_owned(),
+            )
+            .await?;
+        for column in [
+            CrashProcessing::ProcessingError,
+            CrashProcessing::ProcessingStatus,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Crash::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashProcessing {
+    ProcessingStatus,
+    ProcessingError,
+}
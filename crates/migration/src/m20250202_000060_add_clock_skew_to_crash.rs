@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230824_000003_create_crash_table::Crash;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The time at which the server received the upload of a crash, and how far the clock of the
+// client was ahead of the server at that time, in seconds, when the client sent its time. The
+// receive time of existing crashes is their creation time.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashClock::ReceivedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Crash::Table)
+                    .add_column(ColumnDef::new(CrashClock::ClockSkew).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE crash SET received_at = created_at")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [CrashClock::ClockSkew, CrashClock::ReceivedAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Crash::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrashClock {
+    ReceivedAt,
+    ClockSkew,
+}
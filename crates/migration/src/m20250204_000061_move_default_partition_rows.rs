@@ -0,0 +1,108 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+// Rows outside all monthly partitions end up in the default partition, e.g. crashes of clients
+// with a clock far ahead. PostgreSQL refuses to create a partition for a month when the default
+// partition has rows of that month, so `create_month_partition` now creates the partition as a
+// plain table, moves the rows of the month out of the default partition into it, and attaches
+// it. Moving crashes must not delete their annotations and attachments, so the trigger that
+// deletes them skips rows deleted while `guardrail.moving_partition` is set.
+
+const CREATE_PARTITION_FUNCTION: &str = "
+    CREATE OR REPLACE FUNCTION create_month_partition(parent text, month date)
+    RETURNS void AS $$
+    DECLARE
+        start date := date_trunc('month', month);
+        next date := (date_trunc('month', month) + interval '1 month')::date;
+        name text := parent || '_p' || to_char(date_trunc('month', month), 'YYYYMM');
+    BEGIN
+        IF to_regclass(name) IS NOT NULL THEN
+            RETURN;
+        END IF;
+        EXECUTE format('CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS)', name, parent);
+        PERFORM set_config('guardrail.moving_partition', 'on', true);
+        EXECUTE format(
+            'WITH moved AS (DELETE FROM %I WHERE created_at >= %L AND created_at < %L RETURNING *)
+             INSERT INTO %I SELECT * FROM moved',
+            parent || '_default',
+            start,
+            next,
+            name
+        );
+        PERFORM set_config('guardrail.moving_partition', 'off', true);
+        EXECUTE format(
+            'ALTER TABLE %I ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+            parent,
+            name,
+            start,
+            next
+        );
+    END;
+    $$ language 'plpgsql';";
+
+const PREVIOUS_PARTITION_FUNCTION: &str = "
+    CREATE OR REPLACE FUNCTION create_month_partition(parent text, month date)
+    RETURNS void AS $$
+    DECLARE
+        start date := date_trunc('month', month);
+    BEGIN
+        EXECUTE format(
+            'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+            parent || '_p' || to_char(start, 'YYYYMM'),
+            parent,
+            start,
+            (start + interval '1 month')::date
+        );
+    END;
+    $$ language 'plpgsql';";
+
+const DEPENDENTS_FUNCTION: &str = "
+    CREATE OR REPLACE FUNCTION delete_crash_dependents()
+    RETURNS TRIGGER AS $$
+    BEGIN
+        IF current_setting('guardrail.moving_partition', true) = 'on' THEN
+            RETURN OLD;
+        END IF;
+        DELETE FROM annotation WHERE crash_id = OLD.id;
+        DELETE FROM attachment WHERE crash_id = OLD.id;
+        RETURN OLD;
+    END;
+    $$ language 'plpgsql';";
+
+const PREVIOUS_DEPENDENTS_FUNCTION: &str = "
+    CREATE OR REPLACE FUNCTION delete_crash_dependents()
+    RETURNS TRIGGER AS $$
+    BEGIN
+        DELETE FROM annotation WHERE crash_id = OLD.id;
+        DELETE FROM attachment WHERE crash_id = OLD.id;
+        RETURN OLD;
+    END;
+    $$ language 'plpgsql';";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        db.execute_unprepared(DEPENDENTS_FUNCTION).await?;
+        db.execute_unprepared(CREATE_PARTITION_FUNCTION).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(());
+        }
+
+        db.execute_unprepared(PREVIOUS_PARTITION_FUNCTION).await?;
+        db.execute_unprepared(PREVIOUS_DEPENDENTS_FUNCTION).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use sea_orm::prelude::Uuid;
+use sea_orm_migration::prelude::*;
+
+// Symbols uploaded before debug ids and files were normalized keep the ids as the client sent
+// them, with dashes, lowercase digits or the full path of the module, and are not matched with
+// the modules of crashes. This rewrites them in the form `app::build_id` stores: the build id as
+// uppercase hex GUID followed by the age, and the module id as a plain file name. Build ids that
+// are not debug ids are left as they are. The original ids cannot be restored.
+
+/// Same as `app::build_id::normalize_debug_id`, which the migration cannot depend on.
+fn normalize_debug_id(id: &str) -> Option<String> {
+    let id = id.trim();
+    let id = id
+        .strip_prefix('{')
+        .map(|id| id.replacen('}', "", 1))
+        .unwrap_or_else(|| id.to_owned())
+        .replace('-', "");
+
+    if !(32..=40).contains(&id.len()) || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let (guid, age) = id.split_at(32);
+    let age = if age.is_empty() {
+        0
+    } else {
+        u32::from_str_radix(age, 16).ok()?
+    };
+    Some(format!("{}{:X}", guid.to_ascii_uppercase(), age))
+}
+
+/// Same as `app::build_id::normalize_debug_file`.
+fn normalize_debug_file(file: &str) -> String {
+    file.trim()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = db.get_database_backend();
+        let select = Query::select()
+            .columns([Symbols::Id, Symbols::BuildId, Symbols::ModuleId])
+            .from(Symbols::Table)
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let id: Uuid = row.try_get("", "id")?;
+            let build_id: String = row.try_get("", "build_id")?;
+            let module_id: String = row.try_get("", "module_id")?;
+
+            let normalized_build_id =
+                normalize_debug_id(&build_id).unwrap_or_else(|| build_id.clone());
+            let normalized_module_id = normalize_debug_file(&module_id);
+            if normalized_build_id == build_id && normalized_module_id == module_id {
+                continue;
+            }
+            let update = Query::update()
+                .table(Symbols::Table)
+                .value(Symbols::BuildId, normalized_build_id)
+                .value(Symbols::ModuleId, normalized_module_id)
+                .and_where(Expr::col(Symbols::Id).eq(id))
+                .to_owned();
+            db.execute(backend.build(&update)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Symbols {
+    Table,
+    Id,
+    BuildId,
+    ModuleId,
+}
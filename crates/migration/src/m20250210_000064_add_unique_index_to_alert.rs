@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+// The anomaly job raised the alert of a window again when it ran twice in the same hour, e.g.
+// after a restart. Alerts are now unique per product, window, version and environment; the
+// version and environment are optional, so the index covers them with a placeholder for NULL,
+// which would otherwise never conflict. Duplicates raised before are removed, the first alert
+// of a window is kept.
+
+const DELETE_DUPLICATES: &str = "
+    DELETE FROM alert WHERE EXISTS (
+        SELECT 1 FROM alert AS earlier
+        WHERE earlier.product_id = alert.product_id
+        AND earlier.window_start = alert.window_start
+        AND COALESCE(earlier.version_id, '00000000-0000-0000-0000-000000000000')
+            = COALESCE(alert.version_id, '00000000-0000-0000-0000-000000000000')
+        AND COALESCE(earlier.environment, '') = COALESCE(alert.environment, '')
+        AND (earlier.created_at < alert.created_at
+            OR (earlier.created_at = alert.created_at AND earlier.id < alert.id))
+    )";
+
+const CREATE_INDEX: &str = "
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_alert_window ON alert (
+        product_id,
+        window_start,
+        (COALESCE(version_id, '00000000-0000-0000-0000-000000000000')),
+        (COALESCE(environment, ''))
+    )";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(DELETE_DUPLICATES).await?;
+        db.execute_unprepared(CREATE_INDEX).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX IF EXISTS idx_alert_window")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use serde_json::{json, Value};
+
+use super::{Analysis, Analyzer, Finding};
+use app::build_id::normalize_debug_file;
+
+/// Reports modules that are known to cause crashes, such as injected third-party DLLs or
+/// outdated drivers, when they are loaded in the crashed process.
+pub struct BadModulesAnalyzer {
+    modules: Vec<String>,
+}
+
+impl BadModulesAnalyzer {
+    pub const NAME: &'static str = "bad_modules";
+
+    pub fn new(modules: &[String]) -> Self {
+        Self {
+            modules: modules
+                .iter()
+                .map(|module| module.trim().to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl Analyzer for BadModulesAnalyzer {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn analyze(&self, report: &Value) -> Analysis {
+        let found: Vec<Value> = report["modules"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|module| {
+                let filename = module["filename"].as_str()?;
+                let name = normalize_debug_file(filename).to_lowercase();
+                self.modules
+                    .contains(&name)
+                    .then(|| json!({ "module": filename, "version": module["version"] }))
+            })
+            .collect();
+
+        if found.is_empty() {
+            return Analysis::default();
+        }
+        Analysis {
+            findings: vec![Finding {
+                analyzer: Self::NAME.to_owned(),
+                summary: "Known bad modules are loaded".to_owned(),
+                details: json!({ "modules": found }),
+            }],
+            tags: vec!["bad-module".to_owned()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bad_modules() {
+        let analyzer = BadModulesAnalyzer::new(&["EvilHook.dll".to_owned()]);
+
+        let report = json!({ "modules": [{ "filename": "C:\\Windows\\kernel32.dll" }] });
+        assert!(analyzer.analyze(&report).findings.is_empty());
+
+        let report = json!({
+            "modules": [
+                { "filename": "C:\\Windows\\kernel32.dll" },
+                { "filename": "C:\\Program Files\\Evil\\evilhook.dll", "version": "1.2" },
+            ]
+        });
+        let analysis = analyzer.analyze(&report);
+        assert_eq!(analysis.tags, vec!["bad-module"]);
+        assert_eq!(
+            analysis.findings[0].details["modules"][0]["module"],
+            "C:\\Program Files\\Evil\\evilhook.dll"
+        );
+    }
+}
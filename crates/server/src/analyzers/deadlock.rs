@@ -0,0 +1,114 @@
+use serde_json::{json, Value};
+
+use super::{Analysis, Analyzer, Finding};
+
+/// Functions in which a thread blocks while acquiring a lock.
+const LOCK_WAIT_FUNCTIONS: &[&str] = &[
+    "RtlEnterCriticalSection",
+    "RtlpEnterCriticalSectionContended",
+    "RtlpWaitOnCriticalSection",
+    "RtlAcquireSRWLockExclusive",
+    "RtlAcquireSRWLockShared",
+    "__lll_lock_wait",
+    "pthread_mutex_lock",
+    "pthread_rwlock_rdlock",
+    "pthread_rwlock_wrlock",
+    "__psynch_mutexwait",
+    "_pthread_mutex_firstfit_lock_wait",
+];
+
+/// Number of frames from the top of a stack that are checked for a lock wait.
+const DEPTH: usize = 4;
+
+/// Reports a possible deadlock when two or more threads are waiting for a lock.
+pub struct DeadlockAnalyzer;
+
+impl DeadlockAnalyzer {
+    pub const NAME: &'static str = "deadlock";
+
+    fn waiting_for_lock(thread: &Value) -> Option<&str> {
+        thread["frames"]
+            .as_array()?
+            .iter()
+            .take(DEPTH)
+            .filter_map(|frame| frame["function"].as_str())
+            .find(|function| {
+                LOCK_WAIT_FUNCTIONS
+                    .iter()
+                    .any(|wait| function.starts_with(wait))
+            })
+    }
+}
+
+impl Analyzer for DeadlockAnalyzer {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn analyze(&self, report: &Value) -> Analysis {
+        let waiting: Vec<Value> = report["threads"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, thread)| {
+                Self::waiting_for_lock(thread)
+                    .map(|function| json!({ "thread": index, "function": function }))
+            })
+            .collect();
+
+        if waiting.len() < 2 {
+            return Analysis::default();
+        }
+        Analysis {
+            findings: vec![Finding {
+                analyzer: Self::NAME.to_owned(),
+                summary: format!("{} threads are waiting for a lock", waiting.len()),
+                details: json!({ "threads": waiting }),
+            }],
+            tags: vec!["deadlock".to_owned()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(functions: &[&str]) -> Value {
+        let frames: Vec<Value> = functions
+            .iter()
+            .map(|function| json!({ "function": function }))
+            .collect();
+        json!({ "frames": frames })
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let report = json!({
+            "threads": [
+                thread(&["main", "g_main_loop_run"]),
+                thread(&["__lll_lock_wait", "pthread_mutex_lock", "worker"]),
+            ]
+        });
+        let analysis = DeadlockAnalyzer.analyze(&report);
+        assert!(analysis.findings.is_empty());
+
+        let report = json!({
+            "threads": [
+                thread(&["NtWaitForAlertByThreadId", "RtlpWaitOnCriticalSection", "a"]),
+                thread(&["__lll_lock_wait", "pthread_mutex_lock", "worker"]),
+                thread(&["main"]),
+            ]
+        });
+        let analysis = DeadlockAnalyzer.analyze(&report);
+        assert_eq!(analysis.tags, vec!["deadlock"]);
+        assert_eq!(
+            analysis.findings[0].details["threads"],
+            json!([
+                { "thread": 0, "function": "RtlpWaitOnCriticalSection" },
+                { "thread": 1, "function": "__lll_lock_wait" },
+            ])
+        );
+    }
+}
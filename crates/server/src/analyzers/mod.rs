@@ -0,0 +1,143 @@
+mod bad_modules;
+mod deadlock;
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use tracing::warn;
+
+use app::settings;
+
+// Analyzers inspect the processed report of every uploaded minidump. Their findings are stored
+// in the report under `analysis` and their tags are added to the crash as system annotations
+// with the key `tag`.
+//
+// Built-in analyzers are enabled by name in the `analyzers` settings; other analyzers can be
+// added with `Analyzers::register`.
+
+pub use bad_modules::BadModulesAnalyzer;
+pub use deadlock::DeadlockAnalyzer;
+
+/// Annotation key under which analyzer tags are stored.
+pub const TAG_KEY: &str = "tag";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub analyzer: String,
+    pub summary: String,
+    pub details: Value,
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub findings: Vec<Finding>,
+    pub tags: Vec<String>,
+}
+
+pub trait Analyzer: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn analyze(&self, report: &Value) -> Analysis;
+}
+
+#[derive(Default)]
+pub struct Analyzers {
+    analyzers: Vec<Box<dyn Analyzer>>,
+}
+
+impl fmt::Debug for Analyzers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.analyzers.iter().map(|analyzer| analyzer.name()))
+            .finish()
+    }
+}
+
+impl Analyzers {
+    pub fn from_settings(settings: &settings::Analyzers) -> Self {
+        let mut analyzers = Self::default();
+        for name in &settings.enabled {
+            match name.as_str() {
+                DeadlockAnalyzer::NAME => analyzers.register(DeadlockAnalyzer),
+                BadModulesAnalyzer::NAME => {
+                    analyzers.register(BadModulesAnalyzer::new(&settings.bad_modules))
+                }
+                _ => warn!("unknown analyzer {}", name),
+            }
+        }
+        analyzers
+    }
+
+    pub fn register(&mut self, analyzer: impl Analyzer + 'static) {
+        self.analyzers.push(Box::new(analyzer));
+    }
+
+    /// Runs all analyzers on the report, adds their findings to it and returns the tags for the
+    /// crash.
+    pub fn run(&self, report: &mut Value) -> BTreeSet<String> {
+        let mut findings = vec![];
+        let mut tags = BTreeSet::new();
+        for analyzer in &self.analyzers {
+            let analysis = analyzer.analyze(report);
+            findings.extend(analysis.findings);
+            tags.extend(analysis.tags);
+        }
+
+        if !findings.is_empty() {
+            if let Some(report) = report.as_object_mut() {
+                report.insert("analysis".to_owned(), serde_json::json!(findings));
+            }
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Always;
+
+    impl Analyzer for Always {
+        fn name(&self) -> &'static str {
+            "always"
+        }
+
+        fn analyze(&self, _report: &Value) -> Analysis {
+            Analysis {
+                findings: vec![Finding {
+                    analyzer: self.name().to_owned(),
+                    summary: "found".to_owned(),
+                    details: Value::Null,
+                }],
+                tags: vec!["b".to_owned(), "a".to_owned()],
+            }
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut analyzers = Analyzers::default();
+        let mut report = json!({ "threads": [] });
+        assert!(analyzers.run(&mut report).is_empty());
+        assert!(report.get("analysis").is_none());
+
+        analyzers.register(Always);
+        let tags = analyzers.run(&mut report);
+        assert_eq!(tags.into_iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(report["analysis"][0]["analyzer"], "always");
+        assert_eq!(report["analysis"][0]["summary"], "found");
+    }
+
+    #[test]
+    fn test_from_settings() {
+        let settings = settings::Analyzers {
+            enabled: vec!["deadlock".to_owned(), "unknown".to_owned()],
+            bad_modules: vec![],
+        };
+        let analyzers = Analyzers::from_settings(&settings);
+        assert_eq!(format!("{:?}", analyzers), "[\"deadlock\"]");
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{
+    entity::{alert, prelude::Alert},
+    model::alert::{AlertCreateDto, AlertUpdateDto},
+};
+
+use super::base::{NoneFilter, Resource};
+
+impl Resource for Alert {
+    type Entity = alert::Entity;
+    type ActiveModel = alert::ActiveModel;
+    type Data = alert::Model;
+    type CreateData = AlertCreateDto;
+    type UpdateData = AlertUpdateDto;
+    type Filter = NoneFilter;
+}
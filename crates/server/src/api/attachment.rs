@@ -1,9 +1,21 @@
 use crate::{
+    app_state::AppState,
     entity::{attachment, prelude::Attachment},
-    model::attachment::{AttachmentCreateDto, AttachmentUpdateDto},
+    model::{
+        attachment::{AttachmentCreateDto, AttachmentUpdateDto},
+        base::Repo,
+    },
+    utils::compression,
 };
+use app::auth::AuthSession;
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use uuid::Uuid;
 
-use super::base::{NoneFilter, Resource};
+use super::base::{Api, NoneFilter, Resource};
+use super::crash::CrashApi;
+use super::error::ApiError;
 
 impl Resource for Attachment {
     type Entity = attachment::Entity;
@@ -14,6 +26,63 @@ impl Resource for Attachment {
     type Filter = NoneFilter;
 }
 
+pub struct AttachmentApi;
+
+impl AttachmentApi {
+    /// Deletes an attachment, unless its crash is on legal hold.
+    pub async fn remove(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        if let Some(attachment) = Repo::get_by_id::<attachment::Entity>(&state.db, id).await? {
+            CrashApi::check_not_held(
+                &state.db,
+                crate::entity::crash::Column::Id,
+                attachment.crash_id,
+            )
+            .await?;
+        }
+        Api::remove_by_id::<Attachment>(Path(id), State(state)).await
+    }
+
+    /// Serves an attachment, such as the minidump of a crash, decompressed if it is stored
+    /// compressed.
+    pub async fn download(
+        State(state): State<AppState>,
+        auth_session: AuthSession,
+        Path(id): Path<Uuid>,
+    ) -> Result<Response, ApiError> {
+        let attachment = Repo::get_by_id::<attachment::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("attachment".to_owned(), id.to_string()))?;
+        let crash = Repo::get_by_id::<crate::entity::crash::Entity>(&state.db, attachment.crash_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::ForeignKeyError("crash".to_owned(), attachment.crash_id.to_string())
+            })?;
+        CrashApi::check_download_access(&state, auth_session, crash.product_id).await?;
+
+        let path = std::path::Path::new(&attachment.filename);
+        let content = compression::read_file(path, attachment.compression.as_deref()).await?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = name.strip_suffix(".zst").unwrap_or(name.as_str());
+        Ok((
+            [
+                (header::CONTENT_TYPE, attachment.mime_type),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", name.replace('"', "")),
+                ),
+            ],
+            content,
+        )
+            .into_response())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{api::base::tests::*, entity::attachment};
@@ -126,6 +126,7 @@ impl Api {
         R: Resource,
     {
         let p: R::CreateData = Self::process_payload::<R, _>(&state.db, payload, headers).await?;
+        state.cache.invalidate_all();
         Repo::create(&state.db, p)
             .await
             .map(|id| (serde_json::json!({ "result": "ok", "id": id }).to_string()))
@@ -140,6 +141,7 @@ impl Api {
     where
         R: Resource,
     {
+        state.cache.invalidate_all();
         Repo::update(&state.db, payload)
             .await
             .map(|_| (serde_json::json!({ "result": "ok"}).to_string()))
@@ -180,6 +182,7 @@ impl Api {
         <<R::Entity as sea_orm::EntityTrait>::PrimaryKey as sea_orm::PrimaryKeyTrait>::ValueType:
             From<uuid::Uuid>,
     {
+        state.cache.invalidate_all();
         Repo::delete_by_id::<R::Entity>(&state.db, id)
             .await
             .map(|p| (serde_json::json!({ "result": "ok", "id": p }).to_string()))
@@ -198,7 +201,7 @@ pub mod tests {
     use url::Url;
     use webauthn_rs::WebauthnBuilder;
 
-    use crate::api::routes::routes_test;
+    use crate::api::routes::{meta_routes, product_routes, routes_test, v1_routes};
     use ::axum::Router;
     use ::axum_test::TestServer;
 
@@ -214,7 +217,8 @@ pub mod tests {
             .expect("setting default subscriber failed");
     }
 
-    pub async fn run_server() -> TestServer {
+    /// State of a server on an empty in-memory database.
+    pub async fn test_state() -> AppState {
         let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
         Migrator::up(&db, None).await.unwrap();
 
@@ -225,16 +229,32 @@ pub mod tests {
         let builder = builder.rp_name("Guardrail");
 
         // let auth_client = Arc::new(crate::auth::oidc::test_stubs::OidcClientStub {});
-        let state = AppState {
+        AppState {
             db,
             leptos_options: Default::default(),
             routes: vec![],
             // auth_client,
             webauthn: Arc::new(builder.build().expect("Invalid configuration")),
-        };
+            nonces: Default::default(),
+            analyzers: Default::default(),
+            cache: Default::default(),
+            uploads: Default::default(),
+            public: Default::default(),
+            diagnostics: Default::default(),
+            usage: Default::default(),
+            issue_counts: Default::default(),
+            crashes: Default::default(),
+        }
+    }
+
+    pub async fn run_server() -> TestServer {
+        let state = test_state().await;
 
         let app = Router::new()
             // FIXME: duplicate code
+            .merge(meta_routes())
+            .merge(product_routes(state.clone()))
+            .merge(v1_routes(state.clone()))
             .nest("/api", routes_test().await)
             .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
             .with_state(state)
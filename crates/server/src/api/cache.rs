@@ -0,0 +1,366 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::read::ReadAccess;
+use crate::app_state::AppState;
+use crate::settings;
+
+// Dashboards poll the read API for the same data over and over. Successful GET responses are
+// kept for a few seconds and served with an ETag and Last-Modified header, so that clients can
+// revalidate with If-None-Match or If-Modified-Since and get a 304 without a body.
+//
+// The access of the token is part of the cache key: tokens of administrators with the same
+// products share entries, and so do tokens of the same user with the same products, as reports
+// are redacted depending on the user. All others do not. Code that changes crashes, versions or
+// products calls `invalidate_product` (or `invalidate_all`) so that stale entries are not served
+// until they expire. Invalidations are also broadcast to `subscribe`rs, see `live`.
+
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+/// Number of changes buffered per subscriber before it lags behind.
+const CHANGE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    body: Bytes,
+    content_type: Option<HeaderValue>,
+    etag: String,
+    last_modified: DateTime<Utc>,
+    stored_at: Instant,
+    /// Product the response is limited to, `None` if it may contain any product.
+    product: Option<Uuid>,
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    /// Changed product, `None` if any product may have changed.
+    changes: broadcast::Sender<Option<Uuid>>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+        }
+    }
+}
+
+impl ResponseCache {
+    fn get(&self, key: &str, ttl: Duration) -> Option<CachedResponse> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|entry| entry.stored_at.elapsed() < ttl)
+            .cloned()
+    }
+
+    fn insert(
+        &self,
+        key: String,
+        body: Bytes,
+        content_type: Option<HeaderValue>,
+        product: Option<Uuid>,
+        max_entries: usize,
+    ) -> CachedResponse {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let etag = etag(&body);
+        // Keep the modification time when the content did not change since the last refresh.
+        let last_modified = entries
+            .get(&key)
+            .filter(|entry| entry.etag == etag)
+            .map_or_else(Utc::now, |entry| entry.last_modified);
+
+        if entries.len() >= max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        let entry = CachedResponse {
+            body,
+            content_type,
+            etag,
+            last_modified,
+            stored_at: Instant::now(),
+            product,
+        };
+        entries.insert(key, entry.clone());
+        entry
+    }
+
+    /// Drops all responses that may include data of the product.
+    pub fn invalidate_product(&self, product_id: Uuid) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| entry.product.is_some_and(|product| product != product_id));
+        // Sending only fails when nobody is subscribed.
+        let _ = self.changes.send(Some(product_id));
+    }
+
+    pub fn invalidate_all(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        let _ = self.changes.send(None);
+    }
+
+    /// Returns a receiver of the products that change from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Option<Uuid>> {
+        self.changes.subscribe()
+    }
+}
+
+fn etag(body: &[u8]) -> String {
+    format!("\"{}\"", &hex::encode(Sha256::digest(body))[..32])
+}
+
+fn cache_key(access: &ReadAccess, uri: &str) -> String {
+    let products = match &access.products {
+        None => "*".to_owned(),
+        Some(products) => {
+            let mut products: Vec<String> = products.iter().map(Uuid::to_string).collect();
+            products.sort();
+            products.join(",")
+        }
+    };
+    let user = match access.is_admin {
+        true => "admin".to_owned(),
+        false => access.user_id.to_string(),
+    };
+    format!("{}|{}|{}", user, products, uri)
+}
+
+/// Returns the product a request is limited to, from the `product` query parameter.
+fn product_param(query: Option<&str>) -> Option<Uuid> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == "product")
+        .and_then(|(_, value)| Uuid::parse_str(value).ok())
+}
+
+/// Returns whether the client already has the current version of the response.
+fn not_modified(headers: &HeaderMap, entry: &CachedResponse) -> bool {
+    if let Some(tags) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    {
+        return tags
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == entry.etag);
+    }
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| NaiveDateTime::parse_from_str(value, HTTP_DATE).ok())
+        .is_some_and(|since| entry.last_modified.timestamp() <= since.and_utc().timestamp())
+}
+
+fn respond(headers: &HeaderMap, entry: CachedResponse) -> Response {
+    let ttl = settings().cache.ttl;
+    let mut response = if not_modified(headers, &entry) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let mut response = Response::new(Body::from(entry.body));
+        if let Some(content_type) = entry.content_type {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, content_type);
+        }
+        response
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(&entry.etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    if let Ok(last_modified) =
+        HeaderValue::from_str(&entry.last_modified.format(HTTP_DATE).to_string())
+    {
+        response_headers.insert(header::LAST_MODIFIED, last_modified);
+    }
+    if let Ok(cache_control) = HeaderValue::from_str(&format!("private, max-age={}", ttl)) {
+        response_headers.insert(header::CACHE_CONTROL, cache_control);
+    }
+    response
+}
+
+/// Serves GET requests of the read API from the cache. Runs after `verify_api_token`, which
+/// provides the access used in the cache key.
+pub async fn cache_response(
+    State(state): State<AppState>,
+    Extension(access): Extension<ReadAccess>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let config = &settings().cache;
+    if request.method() != Method::GET || config.ttl == 0 {
+        return Ok(next.run(request).await);
+    }
+
+    let key = cache_key(&access, &request.uri().to_string());
+    let headers = request.headers().clone();
+    if let Some(entry) = state.cache.get(&key, Duration::from_secs(config.ttl)) {
+        return Ok(respond(&headers, entry));
+    }
+
+    let product = product_param(request.uri().query());
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|_| ApiError::Failure)?;
+    let entry = state.cache.insert(
+        key,
+        body,
+        parts.headers.get(header::CONTENT_TYPE).cloned(),
+        product,
+        config.max_entries,
+    );
+    Ok(respond(&headers, entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(is_admin: bool, products: Option<Vec<Uuid>>) -> ReadAccess {
+        ReadAccess {
+            user_id: Uuid::nil(),
+            is_admin,
+            products,
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(
+            cache_key(&access(false, Some(vec![a, b])), "/read/crash"),
+            cache_key(&access(false, Some(vec![b, a])), "/read/crash")
+        );
+        assert_ne!(
+            cache_key(&access(false, Some(vec![a])), "/read/crash"),
+            cache_key(&access(false, Some(vec![b])), "/read/crash")
+        );
+        assert_ne!(
+            cache_key(&access(true, None), "/read/crash"),
+            cache_key(&access(false, None), "/read/crash")
+        );
+        let other_user = ReadAccess {
+            user_id: Uuid::new_v4(),
+            ..access(false, Some(vec![a]))
+        };
+        assert_ne!(
+            cache_key(&access(false, Some(vec![a])), "/read/crash"),
+            cache_key(&other_user, "/read/crash")
+        );
+        let other_admin = ReadAccess {
+            user_id: Uuid::new_v4(),
+            ..access(true, None)
+        };
+        assert_eq!(
+            cache_key(&access(true, None), "/read/crash"),
+            cache_key(&other_admin, "/read/crash")
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = ResponseCache::default();
+        let ttl = Duration::from_secs(60);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        cache.insert("all".to_owned(), Bytes::from("[]"), None, None, 10);
+        cache.insert("a".to_owned(), Bytes::from("[]"), None, Some(a), 10);
+        cache.insert("b".to_owned(), Bytes::from("[]"), None, Some(b), 10);
+
+        cache.invalidate_product(a);
+        assert!(cache.get("all", ttl).is_none());
+        assert!(cache.get("a", ttl).is_none());
+        assert!(cache.get("b", ttl).is_some());
+
+        cache.invalidate_all();
+        assert!(cache.get("b", ttl).is_none());
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let cache = ResponseCache::default();
+        cache.invalidate_all();
+
+        let mut changes = cache.subscribe();
+        let product = Uuid::new_v4();
+        cache.invalidate_product(product);
+        cache.invalidate_all();
+        assert_eq!(changes.try_recv().unwrap(), Some(product));
+        assert_eq!(changes.try_recv().unwrap(), None);
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let cache = ResponseCache::default();
+        let entry = cache.insert("key".to_owned(), Bytes::from("[]"), None, None, 10);
+        let refreshed = cache.insert("key".to_owned(), Bytes::from("[]"), None, None, 10);
+        assert_eq!(refreshed.last_modified, entry.last_modified);
+
+        let mut headers = HeaderMap::new();
+        assert!(!not_modified(&headers, &entry));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&entry.etag).unwrap(),
+        );
+        assert!(not_modified(&headers, &entry));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!not_modified(&headers, &entry));
+
+        let mut headers = HeaderMap::new();
+        let since = entry.last_modified.format(HTTP_DATE).to_string();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&since).unwrap(),
+        );
+        assert!(not_modified(&headers, &entry));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Mon, 01 Jan 2024 00:00:00 GMT"),
+        );
+        assert!(!not_modified(&headers, &entry));
+    }
+
+    #[test]
+    fn test_product_param() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            product_param(Some(&format!("limit=10&product={}", id))),
+            Some(id)
+        );
+        assert_eq!(product_param(Some("limit=10")), None);
+        assert_eq!(product_param(None), None);
+    }
+}
@@ -0,0 +1,146 @@
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use tracing::info;
+
+use super::error::ApiError;
+use super::metrics::metrics;
+use super::minidump::Provenance;
+use crate::app_state::AppState;
+use crate::model::base::Repo;
+use crate::model::client_diagnostic::{
+    self, ClientDiagnosticCreateDto, ClientDiagnosticRepo, MAX_MESSAGE_LENGTH,
+};
+use crate::{entity, settings};
+
+// SDKs report uploads that failed on the client, e.g. because the network was down or the server
+// refused the minidump, so that widespread ingestion problems show up on the server even though
+// no crash arrived. The endpoint needs no credential, as a client with a broken credential must
+// still be able to report; reports are limited per IP address, and only kept for a while.
+
+#[derive(Debug, Deserialize)]
+pub struct ClientDiagnosticRequest {
+    pub product: String,
+    pub version: Option<String>,
+    /// Name and version of the SDK, e.g. `crashpad/3.0`.
+    pub sdk: Option<String>,
+    pub kind: String,
+    /// HTTP status of the failed upload, if the server responded.
+    pub status_code: Option<u16>,
+    pub message: Option<String>,
+    /// Time of the failure on the client, in seconds since the epoch.
+    pub failed_at: Option<i64>,
+    pub attempts: Option<i32>,
+}
+
+impl ClientDiagnosticRequest {
+    fn into_dto(self, product_id: uuid::Uuid) -> ClientDiagnosticCreateDto {
+        ClientDiagnosticCreateDto {
+            kind: client_diagnostic::normalize_kind(&self.kind).to_owned(),
+            status_code: self.status_code.map(i32::from),
+            message: self
+                .message
+                .map(|message| message.chars().take(MAX_MESSAGE_LENGTH).collect()),
+            version: self.version,
+            sdk: self.sdk,
+            attempts: self.attempts,
+            failed_at: self
+                .failed_at
+                .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+                .map(|time| time.naive_utc()),
+            product_id,
+        }
+    }
+}
+
+pub struct ClientDiagnosticsApi;
+
+impl ClientDiagnosticsApi {
+    pub async fn report(
+        State(state): State<AppState>,
+        peer: Option<ConnectInfo<SocketAddr>>,
+        headers: HeaderMap,
+        payload: String,
+    ) -> Result<Response, ApiError> {
+        let config = &settings().client_diagnostics;
+        if !config.enabled {
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        let request: ClientDiagnosticRequest = serde_json::from_str(&payload)?;
+
+        let now = chrono::Utc::now();
+        let ip =
+            Provenance::client_ip(&headers, peer.map(|ConnectInfo(peer)| peer)).unwrap_or_default();
+        if !state
+            .diagnostics
+            .allow(&ip, now.timestamp(), config.rate_limit)
+        {
+            info!("rejecting client diagnostic from {}: rate limited", ip);
+            return Err(ApiError::RateLimited(format!(
+                "at most {} reports per hour",
+                config.rate_limit
+            )));
+        }
+
+        let product = Repo::get_by_column::<entity::product::Entity, _, _>(
+            &state.db,
+            entity::product::Column::Name,
+            request.product.clone(),
+        )
+        .await?
+        .ok_or_else(|| ApiError::UploadRejected("unknown product".to_owned()))?;
+
+        let dto = request.into_dto(product.id);
+        metrics().record_client_diagnostic(client_diagnostic::normalize_kind(&dto.kind));
+        let kept_since = now.naive_utc() - chrono::Duration::days(config.retention_days);
+        let id =
+            ClientDiagnosticRepo::record(&state.db, dto, kept_since, config.max_reports).await?;
+        Ok(serde_json::json!({ "result": "ok", "id": id })
+            .to_string()
+            .into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_dto() {
+        let request: ClientDiagnosticRequest = serde_json::from_str(
+            r#"{
+                "product": "Workrave",
+                "version": "1.11.0",
+                "kind": "HTTP_ERROR",
+                "status_code": 413,
+                "failed_at": 1700000000
+            }"#,
+        )
+        .unwrap();
+        let product_id = uuid::Uuid::new_v4();
+        let dto = request.into_dto(product_id);
+        assert_eq!(dto.kind, "http_error");
+        assert_eq!(dto.status_code, Some(413));
+        assert_eq!(
+            dto.failed_at.map(|time| time.and_utc().timestamp()),
+            Some(1700000000)
+        );
+        assert_eq!(dto.product_id, product_id);
+
+        let request = ClientDiagnosticRequest {
+            product: "Workrave".to_owned(),
+            version: None,
+            sdk: None,
+            kind: "disk_full".to_owned(),
+            status_code: None,
+            message: Some("x".repeat(MAX_MESSAGE_LENGTH + 10)),
+            failed_at: None,
+            attempts: None,
+        };
+        let dto = request.into_dto(product_id);
+        assert_eq!(dto.kind, "other");
+        assert_eq!(dto.message.unwrap().len(), MAX_MESSAGE_LENGTH);
+    }
+}
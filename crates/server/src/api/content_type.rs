@@ -0,0 +1,34 @@
+use super::error::ApiError;
+use crate::model::product::{ContentTypePolicy, Product, UploadKind};
+use crate::settings;
+
+/// Content type assumed for files that are uploaded without one.
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+fn defaults(kind: UploadKind) -> &'static [String] {
+    let content_types = &settings().content_types;
+    match kind {
+        UploadKind::Minidump => &content_types.minidump,
+        UploadKind::Symbols => &content_types.symbols,
+        UploadKind::Attachment => &content_types.attachment,
+    }
+}
+
+/// Returns the content type of an uploaded file, or an error listing the accepted types if the
+/// product does not accept it for this kind of upload.
+pub fn check_content_type(
+    product: &Product,
+    kind: UploadKind,
+    content_type: Option<&str>,
+) -> Result<String, ApiError> {
+    let content_type = content_type.unwrap_or(DEFAULT_CONTENT_TYPE).to_owned();
+    let policy = ContentTypePolicy::new(product, kind, defaults(kind));
+    if !policy.accepts(&content_type) {
+        return Err(ApiError::UnsupportedContentType {
+            kind: kind.as_str(),
+            content_type,
+            allowed: policy.allowed,
+        });
+    }
+    Ok(content_type)
+}
@@ -1,18 +1,34 @@
 use super::{
-    base::{Resource, ResourceFilter},
+    base::{Api, Resource, ResourceFilter},
     error::ApiError,
+    minidump::MinidumpApi,
 };
 use crate::{
+    app_state::AppState,
     entity::{crash, prelude::Crash},
     model::{
         base::Repo,
-        crash::{CrashCreateDto, CrashUpdateDto},
+        crash::{CrashCreateDto, CrashRepo, CrashUpdateDto},
+        crash_pdf::CrashPdf,
+        role::VIEWER,
+        user::UserRepo,
         version::VersionRepo,
     },
+    report::{CrashInfo, DEFAULT_STACK_FRAMES},
 };
+use app::auth::AuthSession;
 use async_trait::async_trait;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
 use sea_orm::DatabaseConnection;
+use serde::Deserialize;
 use std::str::FromStr;
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+use tracing::info;
 use uuid::Uuid;
 
 impl Resource for Crash {
@@ -65,6 +81,211 @@ impl ResourceFilter for crash::Model {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LegalHoldRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StackParams {
+    pub frames: Option<usize>,
+    pub modules: Option<bool>,
+}
+
+pub struct CrashApi;
+
+impl CrashApi {
+    /// Checks that the user of the web session may download files of the product.
+    pub(super) async fn check_download_access(
+        state: &AppState,
+        auth_session: AuthSession,
+        product_id: Uuid,
+    ) -> Result<(), ApiError> {
+        let user = auth_session.user.ok_or(ApiError::AccessDenied)?;
+        if !user.is_admin && !UserRepo::has_role(&state.db, user.id, product_id, VIEWER).await? {
+            return Err(ApiError::AccessDenied);
+        }
+        Ok(())
+    }
+
+    /// Fails if any crash for which `column` equals `id` is on legal hold. Checked before
+    /// deleting a crash, or anything whose deletion cascades to crashes or their attachments.
+    pub(super) async fn check_not_held(
+        db: &DatabaseConnection,
+        column: crash::Column,
+        id: Uuid,
+    ) -> Result<(), ApiError> {
+        match CrashRepo::count_held(db, column, id).await? {
+            0 => Ok(()),
+            1 => Err(ApiError::LegalHold("1 crash".to_owned())),
+            held => Err(ApiError::LegalHold(format!("{} crashes", held))),
+        }
+    }
+
+    pub async fn remove(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        Self::check_not_held(&state.db, crash::Column::Id, id).await?;
+        Api::remove_by_id::<Crash>(Path(id), State(state)).await
+    }
+
+    /// Places a crash on legal hold, which exempts it and its attachments from all deletion
+    /// until the hold is released.
+    pub async fn place_legal_hold(
+        State(state): State<AppState>,
+        Extension(user): Extension<crate::entity::user::Model>,
+        Path(id): Path<Uuid>,
+        Json(request): Json<LegalHoldRequest>,
+    ) -> Result<String, ApiError> {
+        let reason = request.reason.trim();
+        if reason.is_empty() {
+            return Err(ApiError::APIFailure("a reason is required".to_owned()));
+        }
+        let crash =
+            CrashRepo::set_legal_hold(&state.db, id, Some((reason.to_owned(), user.id))).await?;
+        info!(
+            audit = "legal_hold",
+            "crash {} placed on legal hold by {}: {}", id, user.username, reason
+        );
+        Ok(serde_json::json!({ "result": "ok", "payload": crash }).to_string())
+    }
+
+    pub async fn release_legal_hold(
+        State(state): State<AppState>,
+        Extension(user): Extension<crate::entity::user::Model>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        let crash = CrashRepo::set_legal_hold(&state.db, id, None).await?;
+        info!(
+            audit = "legal_hold",
+            "legal hold of crash {} released by {}", id, user.username
+        );
+        Ok(serde_json::json!({ "result": "ok", "payload": crash }).to_string())
+    }
+
+    /// Starts rendering the PDF of a crash. The PDF can be fetched from the returned download
+    /// location once it is ready.
+    pub async fn request_pdf(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        Repo::get_by_id::<crash::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+
+        let status = if CrashPdf::is_ready(id) {
+            "ready"
+        } else {
+            CrashPdf::spawn(state.db.clone(), id);
+            "pending"
+        };
+        Ok(serde_json::json!({
+            "result": "ok",
+            "status": status,
+            "download": format!("/download/crash/{}/pdf", id)
+        })
+        .to_string())
+    }
+
+    /// Processes the stored minidump of a crash again, see `MinidumpApi::reprocess`.
+    pub async fn reprocess(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        let crash = Repo::get_by_id::<crash::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+        MinidumpApi::reprocess(&state, crash).await?;
+        Ok(serde_json::json!({
+            "result": "ok",
+            "crash_id": id,
+        })
+        .to_string())
+    }
+
+    /// Returns the symbolicated stack of the crashing thread as plain text.
+    pub async fn stack_text(
+        Path(id): Path<Uuid>,
+        Query(params): Query<StackParams>,
+        State(state): State<AppState>,
+    ) -> Result<Response, ApiError> {
+        let crash = Repo::get_by_id::<crash::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+
+        let info = CrashInfo::from_report(&crash.report).unwrap_or_default();
+        let text = info.stack_text(
+            params.frames.unwrap_or(DEFAULT_STACK_FRAMES),
+            params.modules.unwrap_or(false),
+        );
+        Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response())
+    }
+
+    /// Serves the PDF of a crash. Returns 202 and starts rendering if the PDF does not exist yet.
+    pub async fn download_pdf(
+        State(state): State<AppState>,
+        auth_session: AuthSession,
+        Path(id): Path<Uuid>,
+    ) -> Result<Response, ApiError> {
+        let crash = Repo::get_by_id::<crash::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+        Self::check_download_access(&state, auth_session, crash.product_id).await?;
+
+        if !CrashPdf::is_ready(id) {
+            CrashPdf::spawn(state.db.clone(), id);
+            return Ok((
+                StatusCode::ACCEPTED,
+                [(header::RETRY_AFTER, "5")],
+                "report is being generated",
+            )
+                .into_response());
+        }
+
+        let file = File::open(CrashPdf::path(id)).await?;
+        Ok((
+            [
+                (header::CONTENT_TYPE, "application/pdf".to_owned()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"crash-{}.pdf\"", id),
+                ),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response())
+    }
+
+    /// Downloads the stored crash report as processing produced it, without redaction. Only
+    /// for users who see the report unredacted, to debug processing issues.
+    pub async fn download_report(
+        State(state): State<AppState>,
+        auth_session: AuthSession,
+        Path(id): Path<Uuid>,
+    ) -> Result<Response, ApiError> {
+        let user = auth_session.user.ok_or(ApiError::AccessDenied)?;
+        let crash = Repo::get_by_id::<crash::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+        if !CrashRepo::sees_unredacted(&state.db, crash.product_id, user.id, user.is_admin).await? {
+            return Err(ApiError::AccessDenied);
+        }
+
+        Ok((
+            [
+                (header::CONTENT_TYPE, "application/json".to_owned()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"crash-{}.json\"", id),
+                ),
+            ],
+            serde_json::to_string_pretty(&crash.report)?,
+        )
+            .into_response())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{api::base::tests::*, entity::crash};
@@ -245,6 +466,119 @@ mod tests {
         assert_eq!(crashes.payload[0].id.to_string(), crash1.id);
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_request_pdf() {
+        let context = Context::new().await;
+
+        let response = context
+            .server
+            .post("/api/crash")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+               "report":"Report1", "version": "1.11", "product": "Workrave", "summary": "Summary1"
+            }))
+            .await;
+        response.assert_status_ok();
+        let crash = response.json::<ApiResponseWithId>();
+
+        let response = context
+            .server
+            .post(format!("/api/crash/{}/pdf", crash.id).as_str())
+            .await;
+        response.assert_status_ok();
+        let json = response.json::<serde_json::Value>();
+        assert_eq!(json["result"], "ok");
+        assert_eq!(
+            json["download"],
+            format!("/download/crash/{}/pdf", crash.id)
+        );
+
+        let response = context
+            .server
+            .post(format!("/api/crash/{}/pdf", uuid::Uuid::new_v4()).as_str())
+            .await;
+        response.assert_status_not_found();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_reprocess() {
+        let context = Context::new().await;
+
+        let response = context
+            .server
+            .post("/api/crash")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+               "report":"Report1", "version": "1.11", "product": "Workrave", "summary": "Summary1"
+            }))
+            .await;
+        response.assert_status_ok();
+        let crash = response.json::<ApiResponseWithId>();
+
+        // Crashes created through the API have no minidump to process.
+        let response = context
+            .server
+            .post(format!("/api/crash/{}/reprocess", crash.id).as_str())
+            .await;
+        response.assert_status_not_found();
+
+        let response = context
+            .server
+            .post(format!("/api/crash/{}/reprocess", uuid::Uuid::new_v4()).as_str())
+            .await;
+        response.assert_status_not_found();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_stack_text() {
+        let context = Context::new().await;
+
+        let response = context
+            .server
+            .post("/api/crash")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+               "report": {
+                   "crash_info": { "type": "SIGSEGV", "address": "0x0" },
+                   "crashing_thread": { "frames": [
+                       { "module": "workrave", "function": "Timer::tick" },
+                       { "module": "workrave", "function": "main" }
+                   ]},
+                   "modules": [{ "filename": "workrave", "version": "1.11.0" }]
+               },
+               "version": "1.11", "product": "Workrave", "summary": "Summary1"
+            }))
+            .await;
+        response.assert_status_ok();
+        let crash = response.json::<ApiResponseWithId>();
+
+        let response = context
+            .server
+            .get(format!("/api/crash/{}/stack.txt?frames=1", crash.id).as_str())
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.text(),
+            "SIGSEGV at 0x0\n  0  workrave!Timer::tick\n     1 more frames\n"
+        );
+
+        let response = context
+            .server
+            .get(format!("/api/crash/{}/stack.txt?modules=true", crash.id).as_str())
+            .await;
+        response.assert_status_ok();
+        assert!(response.text().ends_with("Modules:\nworkrave 1.11.0 -\n"));
+
+        let response = context
+            .server
+            .get(format!("/api/crash/{}/stack.txt", uuid::Uuid::new_v4()).as_str())
+            .await;
+        response.assert_status_not_found();
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_incomplete_json() {
@@ -0,0 +1,214 @@
+use axum::extract::{Extension, Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::stream::{self, Stream};
+use sea_orm::sqlx::postgres::PgListener;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::read::ReadAccess;
+use crate::app_state::AppState;
+
+// Dashboards show new crashes in near real time by following a stream of server-sent events
+// instead of polling the read API. Every crash is published once it has been processed. On
+// PostgreSQL crashes are published with NOTIFY and received by every replica with LISTEN, so that
+// a client receives the crashes processed by all replicas. On SQLite there is a single server and
+// crashes are only broadcast in memory.
+
+/// PostgreSQL channel on which processed crashes are published.
+pub const NOTIFY_CHANNEL: &str = "guardrail_crashes";
+
+/// Name of the event sent for a processed crash.
+pub const CRASH_EVENT: &str = "crash";
+
+const CAPACITY: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// A processed crash, as sent to clients.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashEvent {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub product: String,
+    pub version: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct CrashFeed {
+    events: broadcast::Sender<CrashEvent>,
+}
+
+impl Default for CrashFeed {
+    fn default() -> Self {
+        Self {
+            events: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl CrashFeed {
+    pub fn subscribe(&self) -> broadcast::Receiver<CrashEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publishes a processed crash to the clients of all replicas. The crash is only broadcast
+    /// to the clients of this replica when it cannot be published with NOTIFY.
+    pub async fn publish(&self, db: &DatabaseConnection, event: CrashEvent) {
+        if db.get_database_backend() == DbBackend::Postgres {
+            let payload = match serde_json::to_string(&event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!("failed to serialize crash event: {:?}", e);
+                    return;
+                }
+            };
+            let statement = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_notify($1, $2)",
+                [NOTIFY_CHANNEL.into(), payload.into()],
+            );
+            match db.execute(statement).await {
+                Ok(_) => return,
+                Err(e) => warn!("failed to publish crash {}: {:?}", event.id, e),
+            }
+        }
+        // Nobody listening is not an error.
+        let _ = self.events.send(event);
+    }
+
+    /// Broadcasts the crashes published with NOTIFY by any replica to the clients of this
+    /// replica. Returns when the connection to the database fails.
+    async fn forward(&self, db: &DatabaseConnection) -> Result<(), sea_orm::sqlx::Error> {
+        let mut listener = PgListener::connect_with(db.get_postgres_connection_pool()).await?;
+        listener.listen(NOTIFY_CHANNEL).await?;
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<CrashEvent>(notification.payload()) {
+                Ok(event) => {
+                    let _ = self.events.send(event);
+                }
+                Err(e) => warn!("ignoring invalid crash event: {:?}", e),
+            }
+        }
+    }
+}
+
+/// Receives the crashes published by all replicas for as long as the server runs. Only needed on
+/// PostgreSQL.
+pub async fn listen(db: DatabaseConnection, feed: Arc<CrashFeed>) {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return;
+    }
+    loop {
+        if let Err(e) = feed.forward(&db).await {
+            error!("listening for crashes failed: {:?}", e);
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CrashFeedParams {
+    /// Limits the events to crashes of this product.
+    pub product: Option<Uuid>,
+}
+
+/// Returns whether a crash is sent to a client with `access` that watches `product`, `None` for
+/// all its products.
+fn visible(event: &CrashEvent, access: &ReadAccess, product: Option<Uuid>) -> bool {
+    access.allows(event.product_id) && product.map_or(true, |product| product == event.product_id)
+}
+
+pub struct CrashFeedApi;
+
+impl CrashFeedApi {
+    /// Streams the crashes the token owner may read as `crash` events with the crash as JSON.
+    /// Crashes missed by a slow client are skipped.
+    pub async fn stream(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<CrashFeedParams>,
+    ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+        if let Some(product) = params.product {
+            if !access.allows(product) {
+                return Err(ApiError::AccessDenied);
+            }
+        }
+
+        let receiver = state.crashes.subscribe();
+        let stream = stream::unfold(receiver, move |mut receiver| {
+            let access = access.clone();
+            async move {
+                let event = loop {
+                    match receiver.recv().await {
+                        Ok(event) if visible(&event, &access, params.product) => break event,
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("crash stream skipped {} crashes", missed);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                };
+                let event = Event::default()
+                    .event(CRASH_EVENT)
+                    .json_data(&event)
+                    .unwrap_or_else(|_| Event::default().event(CRASH_EVENT));
+                Some((Ok(event), receiver))
+            }
+        });
+        Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(product_id: Uuid) -> CrashEvent {
+        CrashEvent {
+            id: Uuid::new_v4(),
+            product_id,
+            product: "Workrave".to_owned(),
+            version: "1.11.0".to_owned(),
+            signature: Some("workrave::Core::heartbeat".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_visible() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let admin = ReadAccess {
+            user_id: Uuid::new_v4(),
+            is_admin: true,
+            products: None,
+        };
+        let viewer = ReadAccess {
+            user_id: Uuid::new_v4(),
+            is_admin: false,
+            products: Some(vec![a]),
+        };
+        assert!(visible(&event(b), &admin, None));
+        assert!(!visible(&event(b), &admin, Some(a)));
+        assert!(visible(&event(a), &viewer, None));
+        assert!(visible(&event(a), &viewer, Some(a)));
+        assert!(!visible(&event(b), &viewer, None));
+    }
+
+    #[tokio::test]
+    async fn test_publish_in_memory() {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let feed = CrashFeed::default();
+        let mut receiver = feed.subscribe();
+        let crash = event(Uuid::new_v4());
+        feed.publish(&db, crash.clone()).await;
+        assert_eq!(receiver.try_recv().unwrap(), crash);
+    }
+}
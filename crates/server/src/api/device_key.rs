@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    entity::{device_key, prelude::DeviceKey},
+    model::{
+        base::Repo,
+        device_key::{DeviceKeyCreateDto, DeviceKeyUpdateDto},
+    },
+};
+
+use super::{
+    base::{Resource, ResourceFilter},
+    error::ApiError,
+};
+
+impl Resource for DeviceKey {
+    type Entity = device_key::Entity;
+    type ActiveModel = device_key::ActiveModel;
+    type Data = device_key::Model;
+    type CreateData = DeviceKeyCreateDto;
+    type UpdateData = DeviceKeyUpdateDto;
+    type Filter = DeviceKey;
+}
+
+#[async_trait]
+impl ResourceFilter for DeviceKey {
+    async fn req(
+        db: &DatabaseConnection,
+        json: serde_json::Value,
+    ) -> Result<serde_json::Value, ApiError> {
+        let product = json["product"].as_str();
+        if let Some(product) = product {
+            let product_id = Repo::get_by_column::<crate::entity::product::Entity, _, _>(
+                db,
+                crate::entity::product::Column::Name,
+                product.to_owned(),
+            )
+            .await?
+            .map(|product| product.id)
+            .ok_or_else(|| ApiError::ForeignKeyError("product".to_owned(), product.to_owned()))?;
+            let mut json = json.clone();
+            json["product_id"] = serde_json::Value::String(product_id.to_string());
+            return Ok(json);
+        }
+        Ok(json)
+    }
+}
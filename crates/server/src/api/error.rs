@@ -1,6 +1,6 @@
 use axum::{
     extract::multipart::MultipartError,
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -21,9 +21,74 @@ pub enum ApiError {
     #[error("API failure")]
     UtilsError(#[from] UtilsError),
 
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("invalid upload token: {0}")]
+    InvalidUploadToken(String),
+
+    #[error("invalid API token: {0}")]
+    InvalidApiToken(String),
+
+    #[error("access denied")]
+    AccessDenied,
+
+    #[error("upload rejected: {0}")]
+    UploadRejected(String),
+
+    #[error("upload source quarantined: {0}")]
+    Quarantined(String),
+
+    #[error("too many submissions: {0}")]
+    RateLimited(String),
+
+    #[error("{}", app::read_only::READ_ONLY_MESSAGE)]
+    ReadOnly,
+
+    /// The product is in an ingestion window that rejects crashes, see `IngestionSchedule`.
+    #[error("{message}")]
+    IngestionPaused { message: String, retry_after: u64 },
+
+    /// The product received its maximum number of uploads for the day, see `UploadQuota`.
+    #[error("{product} accepts at most {max} uploads per day")]
+    DailyQuotaExceeded {
+        product: String,
+        max: i32,
+        retry_after: u64,
+    },
+
+    /// The server sheds uploads under pressure, see `load_shed`.
+    #[error("server is overloaded, retry later")]
+    Overloaded { retry_after: u64 },
+
+    #[error("{product} accepts at most {max} attachments per crash")]
+    TooManyAttachments { product: String, max: usize },
+
+    #[error("upload conflict: {0}")]
+    UploadConflict(String),
+
+    #[error("on legal hold: {0}")]
+    LegalHold(String),
+
+    #[error("invalid Content-Range: {0}")]
+    InvalidContentRange(String),
+
+    #[error(
+        "content type '{content_type}' is not allowed for {kind} uploads, allowed: {}",
+        .allowed.join(", ")
+    )]
+    UnsupportedContentType {
+        kind: &'static str,
+        content_type: String,
+        allowed: Vec<String>,
+    },
+
     #[error("{0} not found with ID '{1}'")]
     ForeignKeyError(String, String),
 
+    #[error("product {0} is scheduled for deletion")]
+    ProductDeleted(String),
+
     #[error("database error: `{0}`")]
     DatabaseError(#[from] DbErr),
 
@@ -33,6 +98,15 @@ pub enum ApiError {
     #[error("failed to process minidump: `{0}`")]
     MinidumpProcessError(#[from] ProcessError),
 
+    #[error("processing the minidump took longer than {0} seconds")]
+    ProcessingTimeout(u64),
+
+    #[error("invalid crash report: {0}")]
+    InvalidReport(String),
+
+    #[error("minidump does not match checksum {0}")]
+    ChecksumMismatch(String),
+
     #[error("io-error: `{0}`")]
     IOError(#[from] std::io::Error),
 
@@ -50,6 +124,12 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let s = self.to_string();
         print!("{}", s);
+        let retry_after = match &self {
+            ApiError::IngestionPaused { retry_after, .. }
+            | ApiError::DailyQuotaExceeded { retry_after, .. }
+            | ApiError::Overloaded { retry_after } => Some(*retry_after),
+            _ => None,
+        };
         let (status, error_message) = match self {
             ApiError::Failure => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -62,8 +142,34 @@ impl IntoResponse for ApiError {
             ApiError::JoinError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
             ApiError::JsonError(err) => (StatusCode::BAD_REQUEST, format!("invalid JSON: {}", err)),
             ApiError::MinidumpProcessError(err) => (StatusCode::BAD_REQUEST, err.to_string()),
+            ApiError::ProcessingTimeout(_) => (StatusCode::UNPROCESSABLE_ENTITY, s),
+            ApiError::InvalidReport(_) => (StatusCode::UNPROCESSABLE_ENTITY, s),
+            ApiError::ChecksumMismatch(_) => (StatusCode::BAD_REQUEST, s),
             ApiError::APIFailure(err) => (StatusCode::BAD_REQUEST, err.to_string()),
+            ApiError::InvalidSignature(_) => (StatusCode::UNAUTHORIZED, s),
+            ApiError::InvalidUploadToken(_) => (StatusCode::UNAUTHORIZED, s),
+            ApiError::InvalidApiToken(_) => (StatusCode::UNAUTHORIZED, s),
+            ApiError::AccessDenied => (StatusCode::FORBIDDEN, s),
+            ApiError::UploadRejected(_) => (StatusCode::FORBIDDEN, s),
+            ApiError::Quarantined(_) => (StatusCode::TOO_MANY_REQUESTS, s),
+            ApiError::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, s),
+            ApiError::ReadOnly => (StatusCode::METHOD_NOT_ALLOWED, s),
+            ApiError::IngestionPaused { .. } => (StatusCode::SERVICE_UNAVAILABLE, s),
+            ApiError::DailyQuotaExceeded { .. } => (StatusCode::TOO_MANY_REQUESTS, s),
+            ApiError::Overloaded { .. } => (StatusCode::SERVICE_UNAVAILABLE, s),
+            ApiError::TooManyAttachments { .. } => (StatusCode::PAYLOAD_TOO_LARGE, s),
+            ApiError::UploadConflict(_) => (StatusCode::CONFLICT, s),
+            ApiError::LegalHold(_) => (StatusCode::CONFLICT, s),
+            ApiError::InvalidContentRange(_) => (StatusCode::BAD_REQUEST, s),
+            ApiError::UnsupportedContentType { .. } => (StatusCode::UNSUPPORTED_MEDIA_TYPE, s),
             ApiError::ForeignKeyError(_r, _k) => (StatusCode::NOT_FOUND, s),
+            ApiError::ProductDeleted(_) => (StatusCode::GONE, s),
+            ApiError::UtilsError(
+                err @ (UtilsError::DecompressedTooLarge(_) | UtilsError::UploadTooLarge(_)),
+            ) => (StatusCode::PAYLOAD_TOO_LARGE, err.to_string()),
+            ApiError::UtilsError(err @ UtilsError::InvalidArchive(_)) => {
+                (StatusCode::BAD_REQUEST, err.to_string())
+            }
             ApiError::UtilsError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
 
@@ -72,7 +178,15 @@ impl IntoResponse for ApiError {
             "error": error_message,
         }));
 
-        (status, body).into_response()
+        match retry_after {
+            Some(retry_after) => (
+                status,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                body,
+            )
+                .into_response(),
+            None => (status, body).into_response(),
+        }
     }
 }
 
@@ -0,0 +1,251 @@
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use super::error::ApiError;
+use crate::app_state::AppState;
+use crate::entity;
+
+// Logical export of the data model to verify backups and to feed audits, next to database dumps.
+// Each entity is exported as JSON lines, one record per line, ordered by id and paginated with
+// the id of the last record of the previous page. Every record names the records it refers to in
+// `refs`, so that an export can be checked for dangling references without knowing the schema.
+// Entities are listed in `ENTITIES` in an order in which records only refer to records of
+// entities listed before them. Crashes are exported without their report, and symbols without
+// their location on the server.
+
+/// Version of the format of the records. Incremented when fields are removed or change meaning.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Entities that can be exported, in the order in which they refer to each other.
+pub const ENTITIES: [&str; 5] = ["product", "version", "issue", "crash", "symbols"];
+
+/// Header with the `after` parameter of the next page, absent on the last page.
+pub const NEXT_HEADER: &str = "x-export-next";
+
+const DEFAULT_LIMIT: u64 = 1000;
+const MAX_LIMIT: u64 = 10000;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    /// Id of the last record of the previous page.
+    pub after: Option<Uuid>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportRecord {
+    pub version: u32,
+    pub entity: &'static str,
+    pub id: Uuid,
+    /// Records this record refers to, by entity. `None` for optional references that are unset.
+    pub refs: BTreeMap<&'static str, Option<Uuid>>,
+    pub record: Value,
+}
+
+impl ExportRecord {
+    fn new<M: Serialize>(
+        entity: &'static str,
+        id: Uuid,
+        refs: impl IntoIterator<Item = (&'static str, Option<Uuid>)>,
+        model: &M,
+        omitted: &[&str],
+    ) -> Self {
+        let mut record = serde_json::to_value(model).unwrap_or(Value::Null);
+        if let Some(fields) = record.as_object_mut() {
+            omitted.iter().for_each(|field| {
+                fields.remove(*field);
+            });
+        }
+        Self {
+            version: FORMAT_VERSION,
+            entity,
+            id,
+            refs: refs.into_iter().collect(),
+            record,
+        }
+    }
+}
+
+async fn page<E>(
+    db: &DatabaseConnection,
+    id: E::Column,
+    after: Option<Uuid>,
+    limit: u64,
+) -> Result<Vec<E::Model>, DbErr>
+where
+    E: EntityTrait,
+{
+    let mut query = E::find();
+    if let Some(after) = after {
+        query = query.filter(id.gt(after));
+    }
+    query.order_by_asc(id).limit(limit).all(db).await
+}
+
+/// Returns a page of the records of an entity of `ENTITIES`.
+async fn records(
+    db: &DatabaseConnection,
+    name: &str,
+    after: Option<Uuid>,
+    limit: u64,
+) -> Result<Vec<ExportRecord>, ApiError> {
+    let records = match name {
+        "product" => page::<entity::product::Entity>(db, entity::product::Column::Id, after, limit)
+            .await?
+            .iter()
+            .map(|product| {
+                ExportRecord::new(
+                    "product",
+                    product.id,
+                    [("organization", product.organization_id)],
+                    product,
+                    &[],
+                )
+            })
+            .collect(),
+        "version" => page::<entity::version::Entity>(db, entity::version::Column::Id, after, limit)
+            .await?
+            .iter()
+            .map(|version| {
+                ExportRecord::new(
+                    "version",
+                    version.id,
+                    [("product", Some(version.product_id))],
+                    version,
+                    &[],
+                )
+            })
+            .collect(),
+        "issue" => page::<entity::issue::Entity>(db, entity::issue::Column::Id, after, limit)
+            .await?
+            .iter()
+            .map(|issue| {
+                ExportRecord::new(
+                    "issue",
+                    issue.id,
+                    [("product", Some(issue.product_id))],
+                    issue,
+                    &[],
+                )
+            })
+            .collect(),
+        "crash" => page::<entity::crash::Entity>(db, entity::crash::Column::Id, after, limit)
+            .await?
+            .iter()
+            .map(|crash| {
+                ExportRecord::new(
+                    "crash",
+                    crash.id,
+                    [
+                        ("product", Some(crash.product_id)),
+                        ("version", Some(crash.version_id)),
+                        ("issue", crash.issue_id),
+                    ],
+                    crash,
+                    &["report"],
+                )
+            })
+            .collect(),
+        "symbols" => page::<entity::symbols::Entity>(db, entity::symbols::Column::Id, after, limit)
+            .await?
+            .iter()
+            .map(|symbols| {
+                ExportRecord::new(
+                    "symbols",
+                    symbols.id,
+                    [
+                        ("product", Some(symbols.product_id)),
+                        ("version", Some(symbols.version_id)),
+                    ],
+                    symbols,
+                    &["file_location"],
+                )
+            })
+            .collect(),
+        _ => {
+            return Err(ApiError::APIFailure(format!(
+                "unknown entity '{}', expected one of {}",
+                name,
+                ENTITIES.join(", ")
+            )))
+        }
+    };
+    Ok(records)
+}
+
+/// Returns the records as JSON lines.
+fn json_lines(records: &[ExportRecord]) -> Result<String, serde_json::Error> {
+    records.iter().try_fold(String::new(), |mut lines, record| {
+        lines.push_str(&serde_json::to_string(record)?);
+        lines.push('\n');
+        Ok(lines)
+    })
+}
+
+pub struct ExportApi;
+
+impl ExportApi {
+    /// Serves a page of the records of an entity as `application/x-ndjson`. The `after`
+    /// parameter of the next page is returned in the `X-Export-Next` header.
+    pub async fn entity(
+        Path(name): Path<String>,
+        State(state): State<AppState>,
+        Query(params): Query<ExportParams>,
+    ) -> Result<Response, ApiError> {
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let records = records(&state.db, &name, params.after, limit).await?;
+        let body = json_lines(&records)?;
+
+        let mut response = ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response();
+        if records.len() as u64 == limit {
+            if let Some(last) = records.last() {
+                response.headers_mut().insert(
+                    NEXT_HEADER,
+                    header::HeaderValue::from_str(&last.id.to_string())
+                        .expect("uuid is a valid header value"),
+                );
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_lines() {
+        let id = Uuid::new_v4();
+        let product_id = Uuid::new_v4();
+        let record = ExportRecord::new(
+            "symbols",
+            id,
+            [("product", Some(product_id)), ("version", None)],
+            &json!({ "module_id": "workrave.pdb", "file_location": "symbols/workrave.pdb" }),
+            &["file_location"],
+        );
+        let lines = json_lines(&[record]).unwrap();
+        assert!(lines.ends_with('\n'));
+        let parsed: Value = serde_json::from_str(lines.trim_end()).unwrap();
+        assert_eq!(
+            parsed,
+            json!({
+                "version": FORMAT_VERSION,
+                "entity": "symbols",
+                "id": id,
+                "refs": { "product": product_id, "version": null },
+                "record": { "module_id": "workrave.pdb" },
+            })
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{
+    entity::{issue, prelude::Issue},
+    model::issue::{IssueCreateDto, IssueUpdateDto},
+};
+
+use super::base::{NoneFilter, Resource};
+
+impl Resource for Issue {
+    type Entity = issue::Entity;
+    type ActiveModel = issue::ActiveModel;
+    type Data = issue::Model;
+    type CreateData = IssueCreateDto;
+    type UpdateData = IssueUpdateDto;
+    type Filter = NoneFilter;
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDateTime;
+use sea_orm::{DatabaseConnection, DbErr, TransactionTrait};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::model::issue::IssueRepo;
+
+// Issues count their crashes and when they were last seen. Updating the issue for every crash
+// would make crashes of a frequent issue contend on its row, so crashes of existing issues are
+// counted in memory and written in batches by `flush`, in one transaction. Issue lists lag
+// behind by at most the flush interval of `jobs::issue_counts`. The server flushes once more when
+// it shuts down gracefully; counts of a replica that is killed before flushing are lost.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingCrashes {
+    pub crashes: i64,
+    pub last_seen: NaiveDateTime,
+}
+
+#[derive(Debug, Default)]
+pub struct IssueCounts {
+    pending: Mutex<HashMap<Uuid, PendingCrashes>>,
+}
+
+impl IssueCounts {
+    /// Counts a crash of the issue.
+    pub fn record(&self, issue: Uuid, now: NaiveDateTime) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let counts = pending.entry(issue).or_insert(PendingCrashes {
+            crashes: 0,
+            last_seen: now,
+        });
+        counts.crashes += 1;
+        counts.last_seen = counts.last_seen.max(now);
+    }
+
+    /// Returns the crashes counted since the last call.
+    pub fn take(&self) -> HashMap<Uuid, PendingCrashes> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *pending)
+    }
+
+    /// Adds crashes that could not be written back to the pending ones.
+    fn restore(&self, counts: impl Iterator<Item = (Uuid, PendingCrashes)>) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for (issue, counted) in counts {
+            pending
+                .entry(issue)
+                .and_modify(|pending| {
+                    pending.crashes += counted.crashes;
+                    pending.last_seen = pending.last_seen.max(counted.last_seen);
+                })
+                .or_insert(counted);
+        }
+    }
+
+    /// Writes the counted crashes to their issues in one transaction. Returns the number of
+    /// issues updated. On failure, nothing is written and the crashes are kept for the next
+    /// flush.
+    pub async fn flush(&self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let pending = self.take();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let result = async {
+            let txn = db.begin().await?;
+            for (issue, counted) in &pending {
+                IssueRepo::add_crashes(&txn, *issue, counted.crashes, counted.last_seen).await?;
+            }
+            txn.commit().await
+        }
+        .await;
+        match result {
+            Ok(()) => Ok(pending.len()),
+            Err(e) => {
+                self.restore(pending.into_iter());
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let counts = IssueCounts::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = chrono::Utc::now().naive_utc();
+        let later = now + chrono::Duration::seconds(5);
+
+        counts.record(a, later);
+        counts.record(a, now);
+        counts.record(b, now);
+
+        let pending = counts.take();
+        assert_eq!(
+            pending[&a],
+            PendingCrashes {
+                crashes: 2,
+                last_seen: later
+            }
+        );
+        assert_eq!(pending[&b].crashes, 1);
+        assert!(counts.take().is_empty());
+
+        counts.record(a, now);
+        counts.restore(pending.into_iter());
+        let pending = counts.take();
+        assert_eq!(pending[&a].crashes, 3);
+        assert_eq!(pending[&a].last_seen, later);
+    }
+}
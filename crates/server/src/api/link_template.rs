@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    entity::{link_template, prelude::LinkTemplate},
+    model::{
+        base::Repo,
+        link_template::{LinkTemplateCreateDto, LinkTemplateUpdateDto},
+    },
+};
+
+use super::{
+    base::{Resource, ResourceFilter},
+    error::ApiError,
+};
+
+impl Resource for LinkTemplate {
+    type Entity = link_template::Entity;
+    type ActiveModel = link_template::ActiveModel;
+    type Data = link_template::Model;
+    type CreateData = LinkTemplateCreateDto;
+    type UpdateData = LinkTemplateUpdateDto;
+    type Filter = LinkTemplate;
+}
+
+#[async_trait]
+impl ResourceFilter for LinkTemplate {
+    async fn req(
+        db: &DatabaseConnection,
+        json: serde_json::Value,
+    ) -> Result<serde_json::Value, ApiError> {
+        let product = json["product"].as_str();
+        if let Some(product) = product {
+            let product_id = Repo::get_by_column::<crate::entity::product::Entity, _, _>(
+                db,
+                crate::entity::product::Column::Name,
+                product.to_owned(),
+            )
+            .await?
+            .map(|product| product.id)
+            .ok_or_else(|| ApiError::ForeignKeyError("product".to_owned(), product.to_owned()))?;
+            let mut json = json.clone();
+            json["product_id"] = serde_json::Value::String(product_id.to_string());
+            return Ok(json);
+        }
+        Ok(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::base::tests::*;
+    use crate::entity::link_template;
+    use serial_test::serial;
+
+    #[derive(serde::Deserialize, Debug)]
+    pub struct ApiResponseWithVecPayload {
+        pub result: String,
+        pub payload: Vec<link_template::Model>,
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_add_link_template() {
+        let server = run_server().await;
+
+        let response = server
+            .post("/api/product")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+                "name":"Workrave" ,
+            }))
+            .await;
+        response.assert_status_ok();
+        let product = response.json::<ApiResponseWithId>();
+
+        let response = server
+            .post("/api/link_template")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+                "name": "Logs",
+                "url": "https://grafana.example.com/explore?session={session_id}",
+                "product": "Workrave"
+            }))
+            .await;
+        response.assert_status_ok();
+        let link = response.json::<ApiResponseWithId>();
+        assert_eq!(link.result, "ok");
+
+        let response = server
+            .post("/api/link_template")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+                "name": "Trace",
+                "url": "https://tempo.example.com/trace/{trace_id}",
+                "product": "Unknown"
+            }))
+            .await;
+        response.assert_status_not_ok();
+        let failed = response.json::<ApiResponseFailed>();
+        assert_eq!(failed.result, "failed");
+
+        let response = server
+            .get("/api/link_template")
+            .content_type("application/json")
+            .await;
+        response.assert_status_ok();
+        let links = response.json::<ApiResponseWithVecPayload>();
+        assert_eq!(links.result, "ok");
+        assert_eq!(links.payload.len(), 1);
+        assert_eq!(links.payload[0].id.to_string(), link.id);
+        assert_eq!(links.payload[0].product_id.to_string(), product.id);
+        assert_eq!(links.payload[0].name, "Logs");
+    }
+}
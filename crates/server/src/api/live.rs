@@ -0,0 +1,131 @@
+use app::auth::AuthSession;
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use super::error::ApiError;
+use crate::app_state::AppState;
+use crate::model::api_token::ApiTokenRepo;
+
+// The web interface keeps its tables of crashes and issues up to date without polling. Every
+// change that invalidates the response cache is forwarded to the browser as a server-sent event.
+// Changes in quick succession, e.g. during a burst of uploads, are combined into a single event
+// so that clients refresh at most once per debounce interval.
+
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Name of the event sent when crashes or issues of a product changed.
+pub const CHANGED_EVENT: &str = "changed";
+
+#[derive(Debug, Deserialize)]
+pub struct LiveParams {
+    /// Limits the events to changes of this product.
+    pub product: Option<Uuid>,
+}
+
+/// Returns whether a change is of interest to a client watching `watched`, `None` for all
+/// products. A change without a product may affect any product.
+fn affects(change: Option<Uuid>, watched: Option<&[Uuid]>) -> bool {
+    match (change, watched) {
+        (Some(change), Some(watched)) => watched.contains(&change),
+        _ => true,
+    }
+}
+
+/// Waits for the next change of interest and skips the changes that follow within the debounce
+/// interval. Returns the changed product, `None` if any product may have changed, or nothing when
+/// the server shuts down.
+async fn next_change(
+    receiver: &mut broadcast::Receiver<Option<Uuid>>,
+    watched: Option<&[Uuid]>,
+    debounce: Duration,
+) -> Option<Option<Uuid>> {
+    let change = loop {
+        match receiver.recv().await {
+            Ok(change) if affects(change, watched) => break change,
+            Ok(_) => continue,
+            // Changes were missed, so anything may have changed.
+            Err(RecvError::Lagged(_)) => break None,
+            Err(RecvError::Closed) => return None,
+        }
+    };
+    tokio::time::sleep(debounce).await;
+    *receiver = receiver.resubscribe();
+    Some(change)
+}
+
+pub struct LiveApi;
+
+impl LiveApi {
+    pub async fn events(
+        State(state): State<AppState>,
+        auth_session: AuthSession,
+        Query(params): Query<LiveParams>,
+    ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+        let user = auth_session.user.ok_or(ApiError::AccessDenied)?;
+
+        let products = ApiTokenRepo::readable_products(&state.db, user.id, user.is_admin).await?;
+        let watched = match (params.product, products) {
+            (Some(product), Some(products)) if !products.contains(&product) => {
+                return Err(ApiError::AccessDenied)
+            }
+            (Some(product), _) => Some(vec![product]),
+            (None, products) => products,
+        };
+
+        let receiver = state.cache.subscribe();
+        let stream = stream::unfold(receiver, move |mut receiver| {
+            let watched = watched.clone();
+            async move {
+                let change = next_change(&mut receiver, watched.as_deref(), DEBOUNCE).await?;
+                let data = change.map_or_else(|| "*".to_owned(), |product| product.to_string());
+                let event = Event::default().event(CHANGED_EVENT).data(data);
+                Some((Ok(event), receiver))
+            }
+        });
+        Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affects() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert!(affects(Some(a), None));
+        assert!(affects(None, Some(&[a])));
+        assert!(affects(Some(a), Some(&[a, b])));
+        assert!(!affects(Some(b), Some(&[a])));
+    }
+
+    #[tokio::test]
+    async fn test_next_change() {
+        let (sender, mut receiver) = broadcast::channel(4);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let debounce = Duration::from_millis(50);
+
+        sender.send(Some(b)).unwrap();
+        sender.send(Some(a)).unwrap();
+        sender.send(Some(a)).unwrap();
+        let change = next_change(&mut receiver, Some(&[a]), debounce).await;
+        assert_eq!(change, Some(Some(a)));
+        // The second change of `a` was combined with the first.
+        assert!(receiver.try_recv().is_err());
+
+        for _ in 0..6 {
+            sender.send(Some(b)).unwrap();
+        }
+        let change = next_change(&mut receiver, Some(&[a]), debounce).await;
+        assert_eq!(change, Some(None));
+
+        drop(sender);
+        assert_eq!(next_change(&mut receiver, None, debounce).await, None);
+    }
+}
@@ -0,0 +1,182 @@
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::error::ApiError;
+use super::metrics::metrics;
+use crate::settings;
+
+// Uploads are shed before they are read when the server is under pressure. The number of uploads
+// handled at the same time adapts to their latency: it grows by one for every limit's worth of
+// uploads handled within the target latency, and shrinks by a tenth for every slow or failed
+// upload. Slow storage or a slow database makes uploads slow, so the limit drops and the excess
+// uploads are rejected with `503 Service Unavailable` and a `Retry-After` header, instead of
+// piling up until they time out.
+
+/// Factor by which the limit shrinks after a slow upload.
+const BACKOFF: f64 = 0.9;
+
+#[derive(Debug)]
+struct State {
+    limit: f64,
+    in_flight: usize,
+}
+
+#[derive(Debug)]
+pub struct LoadShedder {
+    min_limit: usize,
+    max_limit: usize,
+    target_latency: Duration,
+    state: Mutex<State>,
+}
+
+/// An admitted upload. Dropping the permit without completing it, e.g. when the client
+/// disconnects, does not change the limit.
+pub struct LoadPermit<'a> {
+    shedder: &'a LoadShedder,
+    started: Instant,
+    completed: bool,
+}
+
+impl LoadPermit<'_> {
+    /// Adapts the limit to the latency of the upload; failed uploads count as slow.
+    pub fn complete(mut self, failed: bool) {
+        self.completed = true;
+        self.shedder.complete(self.started.elapsed(), failed);
+    }
+}
+
+impl Drop for LoadPermit<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.shedder.lock().in_flight -= 1;
+        }
+    }
+}
+
+impl LoadShedder {
+    pub fn new(min_limit: usize, max_limit: usize, target_latency: Duration) -> Self {
+        let min_limit = min_limit.max(1);
+        let max_limit = max_limit.max(min_limit);
+        Self {
+            min_limit,
+            max_limit,
+            target_latency,
+            state: Mutex::new(State {
+                limit: max_limit as f64,
+                in_flight: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Admits an upload, or returns `None` when the limit has been reached.
+    pub fn try_acquire(&self) -> Option<LoadPermit<'_>> {
+        let mut state = self.lock();
+        if state.in_flight >= state.limit as usize {
+            return None;
+        }
+        state.in_flight += 1;
+        Some(LoadPermit {
+            shedder: self,
+            started: Instant::now(),
+            completed: false,
+        })
+    }
+
+    fn complete(&self, latency: Duration, failed: bool) {
+        let mut state = self.lock();
+        state.in_flight -= 1;
+        state.limit = if failed || latency > self.target_latency {
+            (state.limit * BACKOFF).max(self.min_limit as f64)
+        } else {
+            (state.limit + 1.0 / state.limit).min(self.max_limit as f64)
+        };
+    }
+
+    /// Returns the number of uploads being handled and the current limit.
+    pub fn load(&self) -> (usize, usize) {
+        let state = self.lock();
+        (state.in_flight, state.limit as usize)
+    }
+}
+
+pub fn load_shedder() -> &'static LoadShedder {
+    static SHEDDER: OnceLock<LoadShedder> = OnceLock::new();
+    SHEDDER.get_or_init(|| {
+        let config = &settings().load_shedding;
+        LoadShedder::new(
+            config.min_concurrency,
+            config.max_concurrency,
+            Duration::from_millis(config.target_latency),
+        )
+    })
+}
+
+/// Rejects uploads beyond the current limit, when load shedding is enabled.
+pub async fn shed_load(request: Request, next: Next) -> Result<Response, ApiError> {
+    let config = &settings().load_shedding;
+    if !config.enabled {
+        return Ok(next.run(request).await);
+    }
+    let Some(permit) = load_shedder().try_acquire() else {
+        let (in_flight, limit) = load_shedder().load();
+        warn!(
+            metric = "upload_shed",
+            "rejected upload, {} of {} concurrent uploads in progress", in_flight, limit
+        );
+        let error = ApiError::Overloaded {
+            retry_after: config.retry_after,
+        };
+        metrics().record_rejection(&error);
+        return Err(error);
+    };
+    let response = next.run(request).await;
+    permit.complete(response.status().is_server_error());
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit() {
+        let shedder = LoadShedder::new(2, 4, Duration::from_secs(1));
+        let permits: Vec<_> = (0..4).filter_map(|_| shedder.try_acquire()).collect();
+        assert_eq!(permits.len(), 4);
+        assert!(shedder.try_acquire().is_none());
+
+        // Slow uploads shrink the limit, down to the minimum.
+        for _ in 0..4 {
+            shedder.complete(Duration::from_secs(2), false);
+        }
+        assert_eq!(shedder.load().1, 2);
+        drop(permits);
+        assert_eq!(shedder.load().0, 0);
+
+        let _first = shedder.try_acquire().unwrap();
+        let _second = shedder.try_acquire().unwrap();
+        assert!(shedder.try_acquire().is_none());
+    }
+
+    #[test]
+    fn test_recovery() {
+        let shedder = LoadShedder::new(1, 3, Duration::from_secs(1));
+        shedder.try_acquire().unwrap().complete(true);
+        shedder.try_acquire().unwrap().complete(true);
+        assert_eq!(shedder.load(), (0, 2));
+
+        // Fast uploads grow the limit again, up to the maximum.
+        for _ in 0..20 {
+            shedder.try_acquire().unwrap().complete(false);
+        }
+        assert_eq!(shedder.load(), (0, 3));
+    }
+}
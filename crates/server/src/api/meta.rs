@@ -0,0 +1,141 @@
+use axum::Json;
+use serde::Serialize;
+
+use super::read::MAX_LIMIT;
+use crate::settings;
+use crate::utils::compression;
+
+// Lets clients and SDKs discover what this server supports before they upload. The endpoint
+// needs no authentication, so it only exposes settings that are not sensitive.
+
+/// Version of the upload and REST API. Increase it on incompatible changes.
+pub const API_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Features {
+    /// Minidumps can be uploaded in chunks with the `resumable` upload mode.
+    pub chunked_uploads: bool,
+    /// Content encodings accepted for request bodies.
+    pub compression: Vec<String>,
+    /// Values of the `format` query parameter of multipart uploads, e.g. `breakpad` for stock
+    /// Breakpad and Crashpad clients.
+    pub response_formats: Vec<String>,
+    pub upload_tokens: bool,
+    pub analyzers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Limits {
+    pub max_upload_size: usize,
+    pub processing_timeout: u64,
+    pub read_page_size: u64,
+}
+
+/// A way to upload minidumps, with the path relative to the path prefix.
+#[derive(Debug, Serialize)]
+pub struct UploadMode {
+    pub name: String,
+    pub path: String,
+    pub auth: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentTypes {
+    pub minidump: Vec<String>,
+    pub symbols: Vec<String>,
+    pub attachment: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetaResponse {
+    pub result: String,
+    pub version: String,
+    pub api_version: u32,
+    pub features: Features,
+    pub limits: Limits,
+    pub upload_modes: Vec<UploadMode>,
+    pub content_types: ContentTypes,
+}
+
+fn upload_mode(name: &str, path: &str, auth: &str) -> UploadMode {
+    UploadMode {
+        name: name.to_owned(),
+        path: path.to_owned(),
+        auth: auth.to_owned(),
+    }
+}
+
+pub struct MetaApi;
+
+impl MetaApi {
+    pub async fn get() -> Json<MetaResponse> {
+        let settings = settings();
+        let upload_tokens = !settings.auth.upload_token.secret.is_empty();
+
+        let mut upload_modes = vec![
+            upload_mode("jwt", "/api/minidump/upload", "bearer"),
+            upload_mode("device_key", "/ingest/minidump/upload", "signature"),
+            upload_mode("resumable", "/api/minidump/resumable", "bearer"),
+            upload_mode("resumable", "/ingest/minidump/resumable", "signature"),
+        ];
+        if upload_tokens {
+            upload_modes.push(upload_mode(
+                "upload_token",
+                "/upload/minidump/upload",
+                "bearer",
+            ));
+            upload_modes.push(upload_mode(
+                "resumable",
+                "/upload/minidump/resumable",
+                "bearer",
+            ));
+        }
+
+        Json(MetaResponse {
+            result: "ok".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            api_version: API_VERSION,
+            features: Features {
+                chunked_uploads: true,
+                compression: vec![compression::GZIP.to_owned(), compression::ZSTD.to_owned()],
+                response_formats: vec!["json".to_owned(), "breakpad".to_owned()],
+                upload_tokens,
+                analyzers: settings.analyzers.enabled.clone(),
+            },
+            limits: Limits {
+                max_upload_size: settings.ingest.max_upload_size,
+                processing_timeout: settings.ingest.processing_timeout,
+                read_page_size: MAX_LIMIT,
+            },
+            upload_modes,
+            content_types: ContentTypes {
+                minidump: settings.content_types.minidump.clone(),
+                symbols: settings.content_types.symbols.clone(),
+                attachment: settings.content_types.attachment.clone(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::base::tests::*;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_meta() {
+        let server = run_server().await;
+
+        let response = server.get("/api/meta").await;
+        response.assert_status_ok();
+        let meta = response.json::<serde_json::Value>();
+        assert_eq!(meta["result"], "ok");
+        assert_eq!(meta["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(meta["api_version"], super::API_VERSION);
+        assert_eq!(meta["features"]["chunked_uploads"], true);
+        assert_eq!(meta["features"]["compression"][0], "gzip");
+        assert!(meta["limits"]["max_upload_size"].as_u64().unwrap() > 0);
+        assert_eq!(meta["upload_modes"][0]["path"], "/api/minidump/upload");
+    }
+}
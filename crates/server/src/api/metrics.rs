@@ -0,0 +1,443 @@
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use super::error::ApiError;
+use super::load_shed::load_shedder;
+use super::scheduler::scheduler;
+use crate::model::query_metrics::{self, QueryHistogram};
+use crate::settings;
+use crate::utils::error::UtilsError;
+
+// Counters of the ingestion pipeline, served at `/metrics` in the Prometheus text format. The
+// counters are kept per replica; Prometheus scrapes every replica and sums them. Background
+// jobs count their work here too, as they run in the same process.
+
+const UPLOADS_RECEIVED: &str = "guardrail_uploads_received_total";
+const UPLOADS_REJECTED: &str = "guardrail_uploads_rejected_total";
+const PROCESSING_DURATION: &str = "guardrail_processing_duration_seconds";
+const UPLOADS_IN_FLIGHT: &str = "guardrail_uploads_in_flight";
+const UPLOAD_CONCURRENCY_LIMIT: &str = "guardrail_upload_concurrency_limit";
+const QUEUE_DEPTH: &str = "guardrail_processing_queue_depth";
+const PROCESSING: &str = "guardrail_processing_running";
+const SYMBOL_LOOKUPS: &str = "guardrail_symbol_lookups_total";
+const SYMBOLS_FETCHED: &str = "guardrail_symbols_fetched_total";
+const SYMBOL_CONVERSIONS: &str = "guardrail_symbol_conversions_total";
+const ATTACHMENTS_PROCESSED: &str = "guardrail_attachments_processed_total";
+const SESSIONS_DELETED: &str = "guardrail_sessions_deleted_total";
+const CLIENT_DIAGNOSTICS: &str = "guardrail_client_diagnostics_total";
+const QUERY_DURATION: &str = "guardrail_db_query_duration_seconds";
+
+/// Upper bounds of the buckets of the processing duration histogram, in seconds.
+const DURATION_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; the last entry counts those above all bounds.
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str) {
+        describe(out, name, "histogram", "Time spent processing minidumps.");
+        let mut count = 0;
+        for (bound, bucket) in DURATION_BUCKETS.iter().zip(&self.buckets) {
+            count += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        count += self.buckets[DURATION_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// Renders the statement durations of a repo, see `query_metrics`.
+fn render_queries(out: &mut String, repo: &str, histogram: &QueryHistogram) {
+    let mut count = 0;
+    for (bound, bucket) in query_metrics::BUCKETS.iter().zip(&histogram.buckets) {
+        count += bucket;
+        let _ = writeln!(
+            out,
+            "{}_bucket{{repo=\"{}\",le=\"{}\"}} {}",
+            QUERY_DURATION, repo, bound, count
+        );
+    }
+    count += histogram.buckets[query_metrics::BUCKETS.len()];
+    let _ = writeln!(
+        out,
+        "{}_bucket{{repo=\"{}\",le=\"+Inf\"}} {}",
+        QUERY_DURATION, repo, count
+    );
+    let _ = writeln!(
+        out,
+        "{}_sum{{repo=\"{}\"}} {}",
+        QUERY_DURATION,
+        repo,
+        histogram.sum.as_secs_f64()
+    );
+    let _ = writeln!(
+        out,
+        "{}_count{{repo=\"{}\"}} {}",
+        QUERY_DURATION, repo, count
+    );
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    uploads_received: AtomicU64,
+    uploads_rejected: Mutex<BTreeMap<&'static str, u64>>,
+    processing_duration: Histogram,
+    symbols_found: AtomicU64,
+    symbols_missing: AtomicU64,
+    symbols_fetched: AtomicU64,
+    symbols_converted: AtomicU64,
+    symbol_conversions_failed: AtomicU64,
+    attachments_processed: AtomicU64,
+    sessions_deleted: AtomicU64,
+    client_diagnostics: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+/// Returns the reason an upload was rejected, as used in the `reason` label.
+fn rejection_reason(error: &ApiError) -> &'static str {
+    match error {
+        ApiError::InvalidSignature(_)
+        | ApiError::InvalidUploadToken(_)
+        | ApiError::InvalidApiToken(_)
+        | ApiError::AccessDenied => "unauthorized",
+        ApiError::UploadRejected(_) => "policy",
+        ApiError::Quarantined(_) => "quarantined",
+        ApiError::RateLimited(_) => "rate_limited",
+        ApiError::ReadOnly => "read_only",
+        ApiError::IngestionPaused { .. } => "maintenance",
+        ApiError::DailyQuotaExceeded { .. } => "quota",
+        ApiError::Overloaded { .. } => "overloaded",
+        ApiError::UnsupportedContentType { .. } => "content_type",
+        ApiError::UtilsError(
+            UtilsError::DecompressedTooLarge(_) | UtilsError::UploadTooLarge(_),
+        )
+        | ApiError::TooManyAttachments { .. } => "too_large",
+        ApiError::ForeignKeyError(_, _) | ApiError::ProductDeleted(_) => "unknown_product",
+        ApiError::MinidumpError(_)
+        | ApiError::MinidumpProcessError(_)
+        | ApiError::InvalidReport(_)
+        | ApiError::JsonError(_)
+        | ApiError::MultiPartError(_) => "invalid",
+        ApiError::ProcessingTimeout(_) => "timeout",
+        ApiError::ChecksumMismatch(_) => "checksum",
+        _ => "error",
+    }
+}
+
+impl Metrics {
+    pub fn record_upload(&self) {
+        self.uploads_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rejection(&self, error: &ApiError) {
+        let mut rejected = self
+            .uploads_rejected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *rejected.entry(rejection_reason(error)).or_default() += 1;
+    }
+
+    pub fn record_processing(&self, duration: Duration) {
+        self.processing_duration.observe(duration);
+    }
+
+    /// Counts the modules of a processed minidump with and without symbols.
+    pub fn record_symbols(&self, found: usize, missing: usize) {
+        self.symbols_found
+            .fetch_add(found as u64, Ordering::Relaxed);
+        self.symbols_missing
+            .fetch_add(missing as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_symbols_fetched(&self, fetched: usize) {
+        self.symbols_fetched
+            .fetch_add(fetched as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_symbol_conversion(&self, converted: bool) {
+        let counter = if converted {
+            &self.symbols_converted
+        } else {
+            &self.symbol_conversions_failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_attachments_processed(&self, processed: usize) {
+        self.attachments_processed
+            .fetch_add(processed as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_sessions_deleted(&self, deleted: u64) {
+        self.sessions_deleted.fetch_add(deleted, Ordering::Relaxed);
+    }
+
+    /// Counts a report of an upload that failed on the client, by kind.
+    pub fn record_client_diagnostic(&self, kind: &'static str) {
+        let mut reports = self
+            .client_diagnostics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *reports.entry(kind).or_default() += 1;
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+
+        describe(
+            &mut out,
+            UPLOADS_RECEIVED,
+            "counter",
+            "Minidump uploads received.",
+        );
+        let _ = writeln!(out, "{} {}", UPLOADS_RECEIVED, load(&self.uploads_received));
+        describe(
+            &mut out,
+            UPLOADS_REJECTED,
+            "counter",
+            "Minidump uploads rejected, by reason.",
+        );
+        let rejected = self
+            .uploads_rejected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for (reason, count) in rejected {
+            let _ = writeln!(
+                out,
+                "{}{{reason=\"{}\"}} {}",
+                UPLOADS_REJECTED, reason, count
+            );
+        }
+
+        let (in_flight, limit) = load_shedder().load();
+        describe(
+            &mut out,
+            UPLOADS_IN_FLIGHT,
+            "gauge",
+            "Uploads being handled, when load shedding is enabled.",
+        );
+        let _ = writeln!(out, "{} {}", UPLOADS_IN_FLIGHT, in_flight);
+        describe(
+            &mut out,
+            UPLOAD_CONCURRENCY_LIMIT,
+            "gauge",
+            "Uploads handled at the same time before uploads are shed.",
+        );
+        let _ = writeln!(out, "{} {}", UPLOAD_CONCURRENCY_LIMIT, limit);
+
+        self.processing_duration
+            .render(&mut out, PROCESSING_DURATION);
+        let (running, waiting) = scheduler().load();
+        describe(
+            &mut out,
+            QUEUE_DEPTH,
+            "gauge",
+            "Minidumps waiting for a processing slot.",
+        );
+        let _ = writeln!(out, "{} {}", QUEUE_DEPTH, waiting);
+        describe(&mut out, PROCESSING, "gauge", "Minidumps being processed.");
+        let _ = writeln!(out, "{} {}", PROCESSING, running);
+
+        describe(
+            &mut out,
+            SYMBOL_LOOKUPS,
+            "counter",
+            "Symbol lookups of modules, by result.",
+        );
+        let _ = writeln!(
+            out,
+            "{}{{result=\"hit\"}} {}",
+            SYMBOL_LOOKUPS,
+            load(&self.symbols_found)
+        );
+        let _ = writeln!(
+            out,
+            "{}{{result=\"miss\"}} {}",
+            SYMBOL_LOOKUPS,
+            load(&self.symbols_missing)
+        );
+        describe(
+            &mut out,
+            SYMBOLS_FETCHED,
+            "counter",
+            "Symbol files fetched from upstream servers.",
+        );
+        let _ = writeln!(out, "{} {}", SYMBOLS_FETCHED, load(&self.symbols_fetched));
+        describe(
+            &mut out,
+            SYMBOL_CONVERSIONS,
+            "counter",
+            "Native debug files converted to Breakpad symbols, by result.",
+        );
+        let _ = writeln!(
+            out,
+            "{}{{result=\"converted\"}} {}",
+            SYMBOL_CONVERSIONS,
+            load(&self.symbols_converted)
+        );
+        let _ = writeln!(
+            out,
+            "{}{{result=\"failed\"}} {}",
+            SYMBOL_CONVERSIONS,
+            load(&self.symbol_conversions_failed)
+        );
+        describe(
+            &mut out,
+            ATTACHMENTS_PROCESSED,
+            "counter",
+            "Attachments processed.",
+        );
+        let _ = writeln!(
+            out,
+            "{} {}",
+            ATTACHMENTS_PROCESSED,
+            load(&self.attachments_processed)
+        );
+        describe(
+            &mut out,
+            SESSIONS_DELETED,
+            "counter",
+            "Stale sessions deleted by the nightly maintenance.",
+        );
+        let _ = writeln!(out, "{} {}", SESSIONS_DELETED, load(&self.sessions_deleted));
+        describe(
+            &mut out,
+            CLIENT_DIAGNOSTICS,
+            "counter",
+            "Uploads that failed on the client, as reported by SDKs, by kind.",
+        );
+        let reports = self
+            .client_diagnostics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for (kind, count) in reports {
+            let _ = writeln!(out, "{}{{kind=\"{}\"}} {}", CLIENT_DIAGNOSTICS, kind, count);
+        }
+
+        describe(
+            &mut out,
+            QUERY_DURATION,
+            "histogram",
+            "Time spent in database statements, by repo.",
+        );
+        for (repo, histogram) in query_metrics::snapshot() {
+            render_queries(&mut out, repo, &histogram);
+        }
+        out
+    }
+}
+
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+pub struct MetricsApi;
+
+impl MetricsApi {
+    /// Serves the metrics to Prometheus. Requires the configured bearer token, if any.
+    pub async fn get(headers: HeaderMap) -> Response {
+        let config = &settings().metrics;
+        if !config.enabled {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        if !config.token.is_empty() {
+            let token = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            if token != Some(config.token.as_str()) {
+                return StatusCode::UNAUTHORIZED.into_response();
+            }
+        }
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            metrics().render(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record_upload();
+        metrics.record_upload();
+        metrics.record_rejection(&ApiError::RateLimited("busy".to_owned()));
+        metrics.record_rejection(&ApiError::InvalidReport("empty".to_owned()));
+        metrics.record_rejection(&ApiError::InvalidReport("empty".to_owned()));
+        metrics.record_processing(Duration::from_millis(300));
+        metrics.record_processing(Duration::from_secs(200));
+        metrics.record_symbols(3, 1);
+        metrics.record_client_diagnostic("timeout");
+
+        let text = metrics.render();
+        assert!(text.contains("guardrail_uploads_received_total 2\n"));
+        assert!(text.contains("guardrail_uploads_rejected_total{reason=\"invalid\"} 2\n"));
+        assert!(text.contains("guardrail_uploads_rejected_total{reason=\"rate_limited\"} 1\n"));
+        assert!(text.contains("guardrail_processing_duration_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(text.contains("guardrail_processing_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(text.contains("guardrail_processing_duration_seconds_bucket{le=\"120\"} 1\n"));
+        assert!(text.contains("guardrail_processing_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("guardrail_processing_duration_seconds_sum 200.3\n"));
+        assert!(text.contains("guardrail_symbol_lookups_total{result=\"miss\"} 1\n"));
+        assert!(text.contains("guardrail_client_diagnostics_total{kind=\"timeout\"} 1\n"));
+    }
+
+    #[test]
+    fn test_render_queries() {
+        let histogram = QueryHistogram {
+            buckets: [0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 1],
+            sum: Duration::from_millis(3004),
+        };
+        let mut text = String::new();
+        render_queries(&mut text, "crash", &histogram);
+        assert!(text.contains(
+            "guardrail_db_query_duration_seconds_bucket{repo=\"crash\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "guardrail_db_query_duration_seconds_bucket{repo=\"crash\",le=\"0.0025\"} 2\n"
+        ));
+        assert!(text
+            .contains("guardrail_db_query_duration_seconds_bucket{repo=\"crash\",le=\"1\"} 3\n"));
+        assert!(text.contains(
+            "guardrail_db_query_duration_seconds_bucket{repo=\"crash\",le=\"+Inf\"} 4\n"
+        ));
+        assert!(text.contains("guardrail_db_query_duration_seconds_sum{repo=\"crash\"} 3.004\n"));
+        assert!(text.contains("guardrail_db_query_duration_seconds_count{repo=\"crash\"} 4\n"));
+    }
+}
@@ -1,37 +1,289 @@
 use axum::extract::multipart::Field;
-use axum::extract::{Multipart, Query, State};
+use axum::extract::{ConnectInfo, Extension, Multipart, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::NaiveDateTime;
+use jwt_authorizer::{JwtClaims, RegisteredClaims};
 use minidump::Minidump;
 use minidump_processor::ProcessorOptions;
 use minidump_unwind::{simple_symbol_supplier, Symbolizer};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::path::PathBuf;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::task;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use super::content_type::check_content_type;
+use super::crash_feed::CrashEvent;
 use super::error::ApiError;
+use super::metrics::metrics;
+use super::public::PUBLIC_CREDENTIAL;
+use super::quarantine::{check_quarantine, record_upload_error, upload_sources};
+use super::scheduler::scheduler;
+use super::signature::{DEVICE_KEY_CREDENTIAL, TIMESTAMP_HEADER};
+use crate::analyzers::TAG_KEY;
 use crate::app_state::AppState;
+use crate::data_providers::webhook;
+use crate::entity::sea_orm_active_enums::AnnotationKind;
+use crate::model::annotation::with_defaults;
 use crate::model::base::Repo;
-use crate::model::version::VersionRepo;
+use crate::model::crash::{
+    CrashRepo, STATUS_DONE, STATUS_FAILED, STATUS_PENDING, STATUS_PROCESSING,
+};
+use crate::model::issue::{IssueChange, IssueRepo};
+use crate::model::missing_symbols::{missing_modules, MissingSymbolsRepo};
+use crate::model::notification::NotificationRepo;
+use crate::model::product::{
+    BuildAgePolicy, EnvironmentPolicy, IngestionMode, IngestionSchedule, SourceLinks, UploadKind,
+    UploadQuota, COMMIT_ANNOTATION,
+};
+use crate::model::product_deletion::ProductDeletionRepo;
+use crate::model::symbols::symbols_dir;
+use crate::model::upload_count::UploadCountRepo;
+use crate::model::version::{VersionRepo, TAG_ANNOTATION};
+use crate::model::webhook::WebhookRepo;
+use crate::report::{CrashInfo, ExceptionDetails};
+use crate::utils::compression;
+use crate::utils::error::UtilsError;
+use crate::utils::exception;
+use crate::utils::scrub::scrubber;
+use crate::utils::stackwalk_cache;
+use crate::utils::storage_path::StoragePath;
 use crate::utils::stream_to_file::stream_to_file;
 use crate::{entity, settings};
 
 pub struct MinidumpApi;
 
+/// Tag of crashes whose minidump could not be processed within `ingest.processing_timeout`.
+const PROCESSING_TIMEOUT_TAG: &str = "processing-timeout";
+/// Tag of crashes uploaded during an ingestion window that queues crashes. They are processed
+/// when the window ends, see `jobs::ingestion_windows`.
+pub(crate) const PROCESSING_QUEUED_TAG: &str = "processing-queued";
+/// Name of the attachment that holds the minidump of a crash.
+const MINIDUMP_ATTACHMENT: &str = "minidump";
+
 #[derive(Debug, Deserialize)]
 pub struct MinidumpRequestParams {
     pub product: String,
     pub version: String,
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Environment the crash was reported from, e.g. `production` or `staging`.
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// Format of the response to an upload, selected with the `format` query parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    /// A `MinidumpResponse`.
+    #[default]
+    Json,
+    /// The plain text `CrashID=<id>` expected by stock Breakpad and Crashpad clients. Errors are
+    /// still reported as JSON; these clients only look at the status code.
+    Breakpad,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ResponseParams {
+    #[serde(default)]
+    pub format: ResponseFormat,
+}
+
+/// Information about the crash reporting client, taken from the request headers.
+#[derive(Debug, Clone, Default)]
+pub struct ClientHints {
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    /// SHA-256 checksum of the minidump, as lowercase hex, verified after it has been stored.
+    pub checksum: Option<String>,
+    /// Time at which the server received the upload, which dates the crash. Imports use the time
+    /// the crash was submitted to the other crash server; without it, crashes are dated when they
+    /// are stored.
+    pub received_at: Option<NaiveDateTime>,
+    /// Unix time at which the client sent the upload, by its own clock.
+    pub sent_at: Option<i64>,
+}
+
+impl ClientHints {
+    const SDK_HEADER: &'static str = "x-guardrail-sdk";
+    const CHECKSUM_HEADER: &'static str = "x-guardrail-sha256";
+    /// Multipart field with the checksum; it must precede the minidump.
+    const CHECKSUM_FIELD: &'static str = "minidump_sha256";
+
+    pub(super) fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_owned())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            user_agent: get(header::USER_AGENT.as_str()),
+            sdk: get(Self::SDK_HEADER),
+            checksum: get(Self::CHECKSUM_HEADER).map(|checksum| checksum.to_lowercase()),
+            received_at: Some(chrono::Utc::now().naive_utc()),
+            sent_at: get(TIMESTAMP_HEADER).and_then(|time| time.parse().ok()),
+        }
+    }
+
+    /// Returns how many seconds the clock of the client was ahead of the server clock when it
+    /// sent the upload, or `None` if the client did not send its time.
+    pub fn clock_skew(&self) -> Option<i64> {
+        Some(self.sent_at? - self.received_at?.and_utc().timestamp())
+    }
+}
+
+/// Credential that authorized an upload. The authentication middleware adds it to the request
+/// extensions; uploads authenticated by a JWT use the claims instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadCredential {
+    pub kind: &'static str,
+    pub id: Option<String>,
+    pub name: Option<String>,
+}
+
+impl UploadCredential {
+    /// Returns the kind and name, or id, of the credential, e.g. `device_key:ci`.
+    pub fn describe(&self) -> String {
+        match self.name.as_deref().or(self.id.as_deref()) {
+            Some(name) => format!("{}:{}", self.kind, name),
+            None => self.kind.to_owned(),
+        }
+    }
+}
+
+impl From<RegisteredClaims> for UploadCredential {
+    fn from(claims: RegisteredClaims) -> Self {
+        Self {
+            kind: "jwt",
+            id: claims.jti,
+            name: claims.sub,
+        }
+    }
+}
+
+/// Who submitted a crash, stored in the report under `provenance` so that processing and
+/// exports do not need to look it up.
+#[derive(Debug, Clone, Default)]
+pub struct Provenance {
+    pub credential: Option<UploadCredential>,
+    pub submitter_ip: Option<String>,
+}
+
+impl Provenance {
+    const FORWARDED_FOR_HEADER: &'static str = "x-forwarded-for";
+
+    /// Collects the provenance of an upload from the credential set by the authentication
+    /// layer, or the JWT claims, and the request.
+    pub(super) fn from_request(
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+        headers: &HeaderMap,
+        peer: Option<ConnectInfo<SocketAddr>>,
+    ) -> Self {
+        Self {
+            credential: credential
+                .map(|Extension(credential)| credential)
+                .or_else(|| claims.map(|JwtClaims(claims)| claims.into())),
+            submitter_ip: Self::submitter_ip(headers, peer.map(|ConnectInfo(peer)| peer)),
+        }
+    }
+
+    fn submitter_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<String> {
+        if !settings().ingest.record_submitter_ip {
+            return None;
+        }
+        Self::client_ip(headers, peer)
+    }
+
+    /// Returns the address of the client, taken from `X-Forwarded-For` when the server is behind
+    /// trusted reverse proxies, see `forwarded_for`.
+    pub(super) fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<String> {
+        let trusted_proxies = settings().ingest.trusted_proxies;
+        if trusted_proxies > 0 {
+            let forwarded = Self::forwarded_for(headers, trusted_proxies);
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        peer.map(|peer| peer.ip().to_string())
+    }
+
+    /// Returns whether the upload was made with a credential, i.e. not as a public submission.
+    pub fn is_authenticated(&self) -> bool {
+        !matches!(&self.credential, Some(credential) if credential.kind == PUBLIC_CREDENTIAL)
+    }
+
+    /// Returns the device key that signed the upload, if any.
+    pub fn device_key(&self) -> Option<uuid::Uuid> {
+        self.credential
+            .as_ref()
+            .filter(|credential| credential.kind == DEVICE_KEY_CREDENTIAL)
+            .and_then(|credential| credential.id.as_deref())
+            .and_then(|id| uuid::Uuid::parse_str(id).ok())
+    }
+
+    /// Returns the client address from `X-Forwarded-For`. Each proxy appends the address it
+    /// received the request from, and the client can send any list to begin with, so only the
+    /// entries appended by the `trusted_proxies` proxies in front of the server can be believed.
+    /// The left-most of those is the client; when the list is shorter, its first entry is.
+    fn forwarded_for(headers: &HeaderMap, trusted_proxies: usize) -> Option<String> {
+        let entries: Vec<&str> = headers
+            .get(Self::FORWARDED_FOR_HEADER)
+            .and_then(|value| value.to_str().ok())?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let client = entries.len().saturating_sub(trusted_proxies);
+        entries
+            .get(client)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| entry.to_string())
+    }
+
+    fn add_to_report(
+        &self,
+        report: &mut Value,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+    ) {
+        let Some(report) = report.as_object_mut() else {
+            return;
+        };
+        let mut provenance = json!({
+            "product": { "id": product.id, "name": product.name },
+            "version": { "id": version.id, "name": version.name },
+            "credential": self.credential,
+            "received_at": chrono::Utc::now().naive_utc(),
+        });
+        if let Some(ip) = &self.submitter_ip {
+            provenance["submitter_ip"] = json!(ip);
+        }
+        report.insert("provenance".to_owned(), provenance);
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct MinidumpResponse {
     pub result: String,
+    /// Id of the crash; a repeated submission of the same minidump returns the id of the first.
+    pub crash_id: Option<uuid::Uuid>,
+    /// Explains why the crash is not processed yet, with `result` set to `queued`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 impl MinidumpApi {
-    async fn get_product(
+    pub(super) async fn get_product(
         state: &AppState,
         params: &MinidumpRequestParams,
     ) -> Result<crate::model::product::Product, ApiError> {
@@ -49,11 +301,14 @@ impl MinidumpApi {
             }
         }
         .ok_or(ApiError::Failure)?;
+        if ProductDeletionRepo::is_scheduled(&state.db, product.id).await? {
+            return Err(ApiError::ProductDeleted(product.name));
+        }
         info!("product: {:?}", product.id);
         Ok(product)
     }
 
-    async fn get_version(
+    pub(super) async fn get_version(
         state: &AppState,
         product_id: uuid::Uuid,
         params: &MinidumpRequestParams,
@@ -68,59 +323,376 @@ impl MinidumpApi {
                 return Err(ApiError::Failure);
             }
         }
-        .ok_or(ApiError::Failure)?;
+        .ok_or_else(|| {
+            ApiError::UploadRejected(format!("version {} is not registered", params.version))
+        })?;
         info!("version: {:?}", version.id);
         Ok(version)
     }
 
-    async fn get_minidump_file(name: String) -> Result<PathBuf, ApiError> {
-        let upload_path = std::path::Path::new(&settings().server.base_path).join("minidumps");
-        let minidump_file = std::path::Path::new(&upload_path).join(name);
-        tokio::fs::create_dir_all(&upload_path).await?;
-        Ok(minidump_file)
+    /// Returns the version of an upload. Products with `auto_create_versions` accept versions that
+    /// are not registered; the returned version is then created with the crash, with the hash
+    /// and tag of the `commit` and `version_tag` annotations, see `store_crash`. Its release
+    /// date is the time of the first upload, so that build age limits accept it.
+    pub(super) async fn get_or_new_version(
+        state: &AppState,
+        product: &crate::model::product::Product,
+        params: &MinidumpRequestParams,
+        annotations: &BTreeMap<String, String>,
+    ) -> Result<crate::model::version::Version, ApiError> {
+        match Self::get_version(state, product.id, params).await {
+            Err(ApiError::UploadRejected(_)) if product.auto_create_versions => {
+                let now = chrono::Utc::now().naive_utc();
+                let annotation = |key| annotations.get(key).cloned().unwrap_or_default();
+                Ok(crate::model::version::Version {
+                    id: uuid::Uuid::new_v4(),
+                    created_at: now,
+                    updated_at: now,
+                    name: params.version.clone(),
+                    hash: annotation(COMMIT_ANNOTATION),
+                    tag: annotation(TAG_ANNOTATION),
+                    product_id: product.id,
+                    channel: params.channel.clone(),
+                    release_date: Some(now),
+                })
+            }
+            version => version,
+        }
+    }
+
+    pub(super) fn check_build_age(
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+    ) -> Result<(), ApiError> {
+        let policy = BuildAgePolicy::from(product);
+        let now = chrono::Utc::now().naive_utc();
+        if !policy.accepts(
+            &version.name,
+            version.channel.as_deref(),
+            version.release_date,
+            now,
+        ) {
+            info!(
+                "rejecting crash for {} {}: build too old",
+                product.name, version.name
+            );
+            return Err(ApiError::UploadRejected(format!(
+                "version {} of {} is too old",
+                version.name, product.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rejects uploads for products that were switched to not accept crashes, e.g. for an end of
+    /// life release.
+    pub(super) fn check_accepting_crashes(
+        product: &crate::model::product::Product,
+    ) -> Result<(), ApiError> {
+        if product.accepting_crashes {
+            return Ok(());
+        }
+        info!(
+            "rejecting crash for {}: not accepting crashes",
+            product.name
+        );
+        Err(ApiError::UploadRejected(format!(
+            "{} does not accept crashes",
+            product.name
+        )))
+    }
+
+    /// Rejects uploads for products in an ingestion window that rejects crashes, asking the
+    /// client to retry when the window ends.
+    pub(super) fn check_ingestion_window(
+        product: &crate::model::product::Product,
+    ) -> Result<(), ApiError> {
+        let now = chrono::Utc::now().naive_utc();
+        let schedule = IngestionSchedule::from(product);
+        match schedule.active(now) {
+            Some(window) if window.mode == IngestionMode::Reject => {
+                info!("rejecting crash for {}: ingestion window", product.name);
+                Err(ApiError::IngestionPaused {
+                    message: window.message(&product.name, now),
+                    retry_after: (window.ends(now) - now).num_seconds().max(1) as u64,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts the upload against the daily quota of the product. Uploads are rejected once the
+    /// quota is reached, asking the client to retry the next day (UTC).
+    pub(super) async fn check_daily_quota(
+        state: &AppState,
+        product: &crate::model::product::Product,
+    ) -> Result<(), ApiError> {
+        let Some(max) = UploadQuota::from(product).max_daily_uploads else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now().naive_utc();
+        if UploadCountRepo::record(&state.db, product.id, now.date(), Some(max)).await? {
+            return Ok(());
+        }
+        info!(
+            "rejecting crash for {}: daily quota of {} uploads reached",
+            product.name, max
+        );
+        let tomorrow =
+            now.date().and_time(chrono::NaiveTime::default()) + chrono::Duration::days(1);
+        Err(ApiError::DailyQuotaExceeded {
+            product: product.name.clone(),
+            max,
+            retry_after: (tomorrow - now).num_seconds().max(1) as u64,
+        })
     }
 
-    async fn get_attachment_file(crash: uuid::Uuid, name: String) -> Result<PathBuf, ApiError> {
-        let upload_path = std::path::Path::new(&settings().server.base_path)
-            .join("attachments")
-            .join(crash.to_string());
-        let minidump_file = std::path::Path::new(&upload_path).join(name);
-        tokio::fs::create_dir_all(&upload_path).await?;
-        Ok(minidump_file)
+    /// Rejects minidumps that are larger than the quota of the product.
+    pub(super) fn check_minidump_size(
+        product: &crate::model::product::Product,
+        size: u64,
+    ) -> Result<(), ApiError> {
+        match UploadQuota::from(product).max_minidump_size {
+            Some(max) if size > max => {
+                info!("rejecting crash for {}: minidump too large", product.name);
+                Err(UtilsError::UploadTooLarge(max).into())
+            }
+            _ => Ok(()),
+        }
     }
 
+    /// Returns the message for clients when crashes of the product are queued, see
+    /// `IngestionSchedule`.
+    pub(super) fn queued_message(product: &crate::model::product::Product) -> Option<String> {
+        let now = chrono::Utc::now().naive_utc();
+        let schedule = IngestionSchedule::from(product);
+        schedule
+            .active(now)
+            .filter(|window| window.mode == IngestionMode::Queue)
+            .map(|window| window.message(&product.name, now))
+    }
+
+    /// Returns the normalized environment of the upload, or an error if the product does not
+    /// accept it.
+    pub(super) fn check_environment(
+        product: &crate::model::product::Product,
+        params: &MinidumpRequestParams,
+    ) -> Result<Option<String>, ApiError> {
+        let Some(environment) = params.environment.as_deref() else {
+            return Ok(None);
+        };
+        let policy = EnvironmentPolicy::from(product);
+        match policy.check(environment) {
+            Some(environment) => Ok(Some(environment)),
+            None => {
+                info!(
+                    "rejecting crash for {}: unknown environment {}",
+                    product.name, environment
+                );
+                Err(ApiError::UploadRejected(format!(
+                    "environment {} is not accepted by {}",
+                    environment, product.name
+                )))
+            }
+        }
+    }
+
+    pub async fn get_minidump_file(product: &str, name: &str) -> Result<PathBuf, ApiError> {
+        Ok(StoragePath::new(product, name)
+            .create(&settings().storage.minidump)
+            .await?)
+    }
+
+    async fn get_attachment_file(
+        product: &str,
+        crash: uuid::Uuid,
+        name: &str,
+    ) -> Result<PathBuf, ApiError> {
+        Ok(StoragePath::new(product, name)
+            .with_crash(crash)
+            .create(&settings().storage.attachment)
+            .await?)
+    }
+
+    /// Checks the checksum sent by the client against the minidump, which was verified when it
+    /// matches either the minidump as uploaded or, if it was compressed, as decompressed. The
+    /// minidump is removed when the checksum does not match.
+    async fn verify_checksum(
+        minidump_file: &Path,
+        expected: &str,
+        uploaded_hash: &str,
+        minidump_hash: &str,
+    ) -> Result<(), ApiError> {
+        if expected == uploaded_hash || expected == minidump_hash {
+            return Ok(());
+        }
+        warn!(
+            "checksum mismatch for {:?}: expected {}, received {}",
+            minidump_file, expected, uploaded_hash
+        );
+        if let Err(e) = tokio::fs::remove_file(minidump_file).await {
+            error!("failed to remove minidump {:?}: {:?}", minidump_file, e);
+        }
+        Err(ApiError::ChecksumMismatch(expected.to_owned()))
+    }
+
+    async fn hash_minidump_file(minidump_file: &Path) -> Result<String, ApiError> {
+        Ok(compression::hash_file(minidump_file, compression::from_path(minidump_file)).await?)
+    }
+
+    /// Claims the crash before processing the minidump, so that concurrent submissions of the same
+    /// minidump are only processed once. Returns the crash id and whether it was created.
     async fn store_crash(
-        report: serde_json::Value,
-        product: crate::model::product::Product,
-        version: crate::model::version::Version,
+        minidump_hash: String,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        environment: Option<String>,
+        client: &ClientHints,
+        authenticated: bool,
         state: &AppState,
-    ) -> Result<uuid::Uuid, ApiError> {
+    ) -> Result<(uuid::Uuid, bool), ApiError> {
         let dto = entity::crash::CreateModel {
-            report, //: report, // TODO: .to_string(),
+            report: Value::Null,
             summary: "".to_string(),
             product_id: product.id,
             version_id: version.id,
+            user_agent: client.user_agent.clone(),
+            sdk: client.sdk.clone(),
+            promoted: None,
+            minidump_hash: Some(minidump_hash),
+            platform: None,
+            environment,
+            issue_id: None,
+            authenticated,
+            legal_hold_at: None,
+            legal_hold_by: None,
+            legal_hold_reason: None,
+            verified_checksum: client.checksum.clone(),
+            signature: None,
+            processing_status: STATUS_PENDING.to_owned(),
+            processing_error: None,
+            received_at: Some(
+                client
+                    .received_at
+                    .unwrap_or_else(|| chrono::Utc::now().naive_utc()),
+            ),
+            clock_skew: client.clock_skew(),
         };
-        let id = Repo::create(&state.db, dto).await.map_err(|e| {
+        let created = match product.auto_create_versions {
+            true => CrashRepo::create_unique_with_version(&state.db, dto, version).await,
+            false => CrashRepo::create_unique(&state.db, dto).await,
+        };
+        created.map_err(|e| {
+            error!("error: {:?}", e);
+            ApiError::Failure
+        })
+    }
+
+    /// Stores the processed report, which completes the processing of the crash. The signature
+    /// is also stored in its own column once the `crash_signature` rollout writes it.
+    async fn store_report(
+        crash_id: uuid::Uuid,
+        report: serde_json::Value,
+        info: &CrashInfo,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        let mut crash = entity::crash::ActiveModel {
+            id: Set(crash_id),
+            report: Set(report),
+            platform: Set(info.platform()),
+            processing_status: Set(STATUS_DONE.to_owned()),
+            processing_error: Set(None),
+            ..Default::default()
+        };
+        if settings().rollout.crash_signature.writes_new() {
+            crash.signature = Set(info.signature());
+        }
+        crash.update(&state.db).await.map_err(|e| {
             error!("error: {:?}", e);
             ApiError::Failure
         })?;
-        Ok(id)
+        Ok(())
+    }
+
+    /// Groups the crash into the issue of its signature. Returns the issue and what the crash
+    /// changed, e.g. whether it created or reopened the issue, or `None` for crashes without a
+    /// signature. Crashes of existing issues are counted in batches, see `IssueCounts`.
+    async fn store_issue(
+        crash_id: uuid::Uuid,
+        product_id: uuid::Uuid,
+        info: &CrashInfo,
+        state: &AppState,
+    ) -> Result<Option<(uuid::Uuid, IssueChange)>, ApiError> {
+        let Some(signature) = info.signature() else {
+            return Ok(None);
+        };
+        let title = info.title().unwrap_or_else(|| signature.clone());
+        let (issue_id, change) =
+            IssueRepo::record(&state.db, product_id, &signature, &title).await?;
+        if change != IssueChange::Created {
+            state
+                .issue_counts
+                .record(issue_id, chrono::Utc::now().naive_utc());
+        }
+        IssueRepo::assign(&state.db, crash_id, issue_id).await?;
+        Ok(Some((issue_id, change)))
+    }
+
+    /// Compresses a stored file if compression is enabled. Returns the path of the stored file
+    /// and its compression.
+    async fn compress(path: PathBuf) -> Result<(PathBuf, Option<String>), ApiError> {
+        if let Some(compression) = compression::from_path(&path) {
+            return Ok((path, Some(compression.to_owned())));
+        }
+        match compression::configured() {
+            Some(compression) => {
+                let level = settings().storage.compression_level;
+                let path = compression::compress_file(&path, level).await?;
+                Ok((path, Some(compression.to_owned())))
+            }
+            None => Ok((path, None)),
+        }
+    }
+
+    /// Keeps the processed minidump as attachment of the crash, so that it can be downloaded
+    /// and is removed together with the crash.
+    async fn store_minidump(
+        crash_id: uuid::Uuid,
+        minidump_file: PathBuf,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        let filesize = tokio::fs::metadata(&minidump_file).await?.len() as i64;
+        let (minidump_file, compression) = Self::compress(minidump_file).await?;
+        Self::store_attachment(
+            crash_id,
+            MINIDUMP_ATTACHMENT.to_string(),
+            minidump_file.to_str().ok_or(ApiError::Failure)?.to_string(),
+            filesize,
+            "application/x-dmp".to_string(),
+            compression,
+            state,
+        )
+        .await?;
+        Ok(())
     }
 
     async fn store_attachment(
         crash_id: uuid::Uuid,
+        name: String,
         filename: String,
         filesize: i64,
         mime_type: String,
+        compression: Option<String>,
         state: &AppState,
     ) -> Result<uuid::Uuid, ApiError> {
         let dto = entity::attachment::CreateModel {
-            name: "minidump".to_string(),
+            name,
             mime_type,
             size: filesize,
             filename,
             crash_id,
+            compression,
+            processed_at: None,
         };
         let id = Repo::create(&state.db, dto).await.map_err(|e| {
             error!("error: {:?}", e);
@@ -129,16 +701,150 @@ impl MinidumpApi {
         Ok(id)
     }
 
+    /// Removes the crash claimed by `store_crash` when its minidump could not be processed.
+    async fn discard_crash(crash_id: uuid::Uuid, state: &AppState) {
+        if let Err(e) = Repo::delete_by_id::<entity::crash::Entity>(&state.db, crash_id).await {
+            error!("failed to remove unprocessed crash: {:?}", e);
+        }
+    }
+
+    /// Keeps the crash of a minidump that could not be processed in time, so that it can be
+    /// found and so that a retry of the upload does not process it again.
+    async fn mark_timed_out(
+        crash_id: uuid::Uuid,
+        timeout: u64,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        let summary = format!("Processing timed out after {} seconds", timeout);
+        let crash = entity::crash::ActiveModel {
+            id: Set(crash_id),
+            summary: Set(summary.clone()),
+            report: Set(json!({ "processing_error": "timeout", "timeout": timeout })),
+            processing_status: Set(STATUS_FAILED.to_owned()),
+            processing_error: Set(Some(summary)),
+            ..Default::default()
+        };
+        crash.update(&state.db).await.map_err(|e| {
+            error!("error: {:?}", e);
+            ApiError::Failure
+        })?;
+        Self::store_tags(
+            crash_id,
+            BTreeSet::from([PROCESSING_TIMEOUT_TAG.to_owned()]),
+            state,
+        )
+        .await
+    }
+
+    /// Stores a crash that is processed when the ingestion window of its product ends. The
+    /// provenance is stored now, as it is kept when the crash is processed.
+    async fn mark_queued(
+        crash_id: uuid::Uuid,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        provenance: &Provenance,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        let mut report = json!({ "processing_error": "queued" });
+        provenance.add_to_report(&mut report, product, version);
+        let crash = entity::crash::ActiveModel {
+            id: Set(crash_id),
+            summary: Set("Queued until the ingestion window of the product ends".to_owned()),
+            report: Set(report),
+            processing_status: Set(STATUS_PENDING.to_owned()),
+            processing_error: Set(None),
+            ..Default::default()
+        };
+        crash.update(&state.db).await?;
+        Self::store_tags(
+            crash_id,
+            BTreeSet::from([PROCESSING_QUEUED_TAG.to_owned()]),
+            state,
+        )
+        .await
+    }
+
+    async fn store_tags(
+        crash_id: uuid::Uuid,
+        tags: BTreeSet<String>,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        for tag in tags {
+            let dto = entity::annotation::CreateModel {
+                key: TAG_KEY.to_string(),
+                kind: AnnotationKind::System,
+                value: tag,
+                crash_id,
+            };
+            Repo::create(&state.db, dto).await.map_err(|e| {
+                error!("error: {:?}", e);
+                ApiError::Failure
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Counts the modules of a new crash without symbols for the missing symbols report. A
+    /// failure is logged, as it does not affect the crash.
+    async fn store_missing_symbols(product_id: uuid::Uuid, report: &Value, state: &AppState) {
+        let modules = missing_modules(report);
+        if modules.is_empty() {
+            return;
+        }
+        let now = chrono::Utc::now().naive_utc();
+        if let Err(e) = MissingSymbolsRepo::record(&state.db, product_id, modules, now).await {
+            error!("failed to record missing symbols: {:?}", e);
+        }
+    }
+
+    /// Stores the annotations of a new crash: those sent by the client, completed with the
+    /// default annotations of the product.
+    pub async fn store_annotations(
+        crash_id: uuid::Uuid,
+        product: &crate::model::product::Product,
+        annotations: BTreeMap<String, String>,
+        state: &AppState,
+    ) -> Result<(), ApiError> {
+        let annotations = scrubber().annotations(&product.name, annotations);
+        let annotations = with_defaults(product.default_annotations.as_deref(), annotations);
+        for (key, value) in annotations {
+            let dto = entity::annotation::CreateModel {
+                key,
+                kind: AnnotationKind::User,
+                value,
+                crash_id,
+            };
+            Repo::create(&state.db, dto).await?;
+        }
+        Ok(())
+    }
+
     async fn process_minidump_file(minidump_file: PathBuf) -> Result<serde_json::Value, ApiError> {
         debug!("minidump_file: {:?}", minidump_file);
-        let dump = Minidump::read_path(minidump_file)?;
+        let content =
+            compression::read_stored_file(&minidump_file, compression::from_path(&minidump_file))
+                .await?;
+        let minidump_hash = Sha256::digest(&content);
+        let dump = Minidump::read(content)?;
+        let exception = exception::details(&dump);
+
+        // Only released symbols are used; quarantined files are kept elsewhere.
+        let path = symbols_dir();
+
+        let cache = settings().ingest.stackwalk_cache.then(|| {
+            let symbols = stackwalk_cache::available_symbols(&dump, &path);
+            stackwalk_cache::key(&minidump_hash, &symbols)
+        });
+        if let Some(key) = &cache {
+            if let Some(json) = stackwalk_cache::get(&stackwalk_cache::cache_dir(), key).await {
+                debug!("stackwalk of {:?} found in cache", minidump_file);
+                return Ok(Self::add_exception(json, exception));
+            }
+        }
 
         let mut options = ProcessorOptions::default();
         options.recover_function_args = true;
 
-        let path = std::path::Path::new(&settings().server.base_path)
-            .join("symbols")
-            .to_path_buf();
         debug!("provider: {:?}", path);
         let provider = Symbolizer::new(simple_symbol_supplier(vec![path]));
 
@@ -150,61 +856,444 @@ impl MinidumpApi {
         let json: Value = serde_json::from_slice(&json_output)?;
 
         debug!("json: {:?}", json);
-        Ok(json)
+        if let Some(key) = &cache {
+            if let Err(e) = stackwalk_cache::put(&stackwalk_cache::cache_dir(), key, &json).await {
+                warn!("failed to cache stackwalk of {:?}: {:?}", minidump_file, e);
+            }
+        }
+        Ok(Self::add_exception(json, exception))
+    }
+
+    /// Adds the exception of the minidump to the stackwalk output. It is not cached with the
+    /// output, as it is cheap to read from the minidump.
+    fn add_exception(mut json: Value, exception: Option<ExceptionDetails>) -> Value {
+        let exception = exception.and_then(|exception| serde_json::to_value(exception).ok());
+        if let (Some(fields), Some(exception)) = (json.as_object_mut(), exception) {
+            fields.insert("exception".to_owned(), exception);
+        }
+        json
+    }
+
+    /// Processes the minidump on a blocking thread, so that stack walking does not hold up the
+    /// async workers. Returns `None` if processing takes longer than `timeout` seconds; the
+    /// blocking thread cannot be stopped and finishes in the background.
+    async fn process_in_time(
+        minidump_file: PathBuf,
+        timeout: u64,
+    ) -> Option<Result<serde_json::Value, ApiError>> {
+        let started = Instant::now();
+        let runtime = tokio::runtime::Handle::current();
+        let data = tokio::time::timeout(Duration::from_secs(timeout), async {
+            task::spawn_blocking(move || {
+                runtime.block_on(Self::process_minidump_file(minidump_file))
+            })
+            .await?
+        })
+        .await
+        .ok();
+        metrics().record_processing(started.elapsed());
+        if let Some(Ok(info)) = data
+            .as_ref()
+            .and_then(|data| data.as_ref().ok())
+            .map(CrashInfo::from_report)
+        {
+            let missing = info
+                .modules
+                .iter()
+                .filter(|module| module.missing_symbols)
+                .count();
+            metrics().record_symbols(info.modules.len() - missing, missing);
+        }
+        data
     }
 
     async fn handle_minidump_upload(
         state: &AppState,
         params: &MinidumpRequestParams,
+        client: &ClientHints,
+        provenance: &Provenance,
+        annotations: &BTreeMap<String, String>,
         field: Field<'_>,
-    ) -> Result<uuid::Uuid, ApiError> {
+    ) -> Result<(uuid::Uuid, bool), ApiError> {
         let filename = field
             .file_name()
             .map(|name| name.to_string())
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-        let minidump_file = Self::get_minidump_file(filename).await?;
 
         let product = Self::get_product(state, params).await?;
-        let version = Self::get_version(state, product.id, params).await?;
+        let version = Self::get_or_new_version(state, &product, params, annotations).await?;
+        Self::check_build_age(&product, &version)?;
+        Self::check_accepting_crashes(&product)?;
+        Self::check_ingestion_window(&product)?;
+        let environment = Self::check_environment(&product, params)?;
+        check_content_type(&product, UploadKind::Minidump, field.content_type())?;
+        Self::check_daily_quota(state, &product).await?;
+
+        let minidump_file = Self::get_minidump_file(&product.name, &filename).await?;
 
-        stream_to_file(&minidump_file, field).await?;
+        let max_size = UploadQuota::from(&product).max_minidump_size;
+        stream_to_file(&minidump_file, field, max_size).await?;
 
-        let data = task::spawn_blocking(move || Self::process_minidump_file(minidump_file))
+        Self::ingest(
+            state,
+            &product,
+            &version,
+            environment,
+            minidump_file,
+            client,
+            provenance,
+        )
+        .await
+    }
+
+    /// Stores and processes a minidump that has been written to `minidump_file`, decompressing it
+    /// first if it was uploaded with gzip or zstd. Returns the id of the crash and whether it was
+    /// created; an earlier submission of the same minidump returns the existing crash.
+    pub async fn ingest(
+        state: &AppState,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        environment: Option<String>,
+        minidump_file: PathBuf,
+        client: &ClientHints,
+        provenance: &Provenance,
+    ) -> Result<(uuid::Uuid, bool), ApiError> {
+        let uploaded_hash = match client.checksum {
+            Some(_) => Some(compression::hash_file(&minidump_file, None).await?),
+            None => None,
+        };
+        let max_size = settings().ingest.max_decompressed_size as u64;
+        if let Some(compression) = compression::decompress_upload(&minidump_file, max_size).await? {
+            debug!("decompressed {} minidump {:?}", compression, minidump_file);
+        }
+        let hash = Self::hash_minidump_file(&minidump_file).await?;
+        if let (Some(expected), Some(uploaded_hash)) = (&client.checksum, &uploaded_hash) {
+            Self::verify_checksum(&minidump_file, expected, uploaded_hash, &hash).await?;
+        }
+        let _permit = scheduler().acquire(product.id).await;
+
+        let timeout = settings().ingest.processing_timeout;
+        let (crash_id, created) = Self::store_crash(
+            hash,
+            product,
+            version,
+            environment.clone(),
+            client,
+            provenance.is_authenticated(),
+            state,
+        )
+        .await?;
+        if let Some(key) = provenance.device_key() {
+            state.usage.record(key, chrono::Utc::now().naive_utc());
+        }
+        if !created {
+            // A claim that was never completed, e.g. because the server restarted while
+            // processing the minidump, is taken over instead of returning a crash without report.
+            let stale_before =
+                chrono::Utc::now().naive_utc() - chrono::Duration::seconds(2 * timeout as i64);
+            if !CrashRepo::reclaim(&state.db, crash_id, stale_before).await? {
+                info!(
+                    "duplicate minidump for {} {}, returning crash {}",
+                    product.name, version.name, crash_id
+                );
+                if let Err(e) = tokio::fs::remove_file(&minidump_file).await {
+                    error!("failed to remove minidump {:?}: {:?}", minidump_file, e);
+                }
+                return Ok((crash_id, false));
+            }
+            info!("processing abandoned crash {} again", crash_id);
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        if let Some(window) = IngestionSchedule::from(product)
+            .active(now)
+            .filter(|window| window.mode == IngestionMode::Queue)
+        {
+            info!(
+                "queueing crash {} of {} until {}",
+                crash_id,
+                product.name,
+                window.ends(now)
+            );
+            Self::mark_queued(crash_id, product, version, provenance, state).await?;
+            Self::store_minidump(crash_id, minidump_file, state).await?;
+            state.cache.invalidate_product(product.id);
+            return Ok((crash_id, true));
+        }
+
+        CrashRepo::set_processing_status(&state.db, crash_id, STATUS_PROCESSING, None).await?;
+        let data = Self::process_in_time(minidump_file.clone(), timeout).await;
+        let mut data = match data {
+            Some(Ok(data)) => data,
+            None => {
+                warn!(
+                    metric = "minidump_processing_timeout",
+                    product = %product.name,
+                    version = %version.name,
+                    "processing crash {} timed out after {} seconds",
+                    crash_id,
+                    timeout
+                );
+                Self::mark_timed_out(crash_id, timeout, state).await?;
+                Self::store_minidump(crash_id, minidump_file, state).await?;
+                state.cache.invalidate_product(product.id);
+                return Err(ApiError::ProcessingTimeout(timeout));
+            }
+            Some(Err(e)) => {
+                Self::discard_crash(crash_id, state).await;
+                return Err(e);
+            }
+        };
+        let tags = state.analyzers.run(&mut data);
+        provenance.add_to_report(&mut data, product, version);
+        SourceLinks::from(product).apply(&mut data, &version.hash);
+        scrubber().report(&product.name, &mut data);
+        let info = match CrashInfo::stamp(&mut data) {
+            Ok(info) => info,
+            Err(e) => {
+                Self::discard_crash(crash_id, state).await;
+                return Err(ApiError::InvalidReport(e.to_string()));
+            }
+        };
+        Self::store_missing_symbols(product.id, &data, state).await;
+        Self::store_report(crash_id, data, &info, state).await?;
+        let issue = Self::store_issue(crash_id, product.id, &info, state).await?;
+        Self::store_tags(crash_id, tags, state).await?;
+        Self::store_minidump(crash_id, minidump_file, state).await?;
+        state.cache.invalidate_product(product.id);
+        // New issues and resolved issues that crash again are notified.
+        let event = match issue {
+            Some((issue_id, IssueChange::Created)) => Some((issue_id, webhook::NEW_ISSUE, "New")),
+            Some((issue_id, IssueChange::Reopened)) => {
+                Some((issue_id, webhook::REGRESSION, "Regressed"))
+            }
+            _ => None,
+        };
+        if let Some((issue_id, event, label)) = event {
+            let data = json!({
+                "issue_id": issue_id,
+                "crash_id": crash_id,
+                "product": product.name,
+                "version": version.name,
+                "signature": info.signature(),
+                "title": info.title(),
+            });
+            WebhookRepo::notify(&state.db, product.id, event, data.clone());
+            NotificationRepo::notify(
+                &state.db,
+                product.id,
+                event,
+                format!(
+                    "{} crash in {}: {}",
+                    label,
+                    product.name,
+                    info.signature().unwrap_or_else(|| "unknown".to_owned())
+                ),
+                data,
+            );
+        }
+        // Crashes of muted and snoozed issues are not notified.
+        let silenced = match issue {
+            Some((issue_id, IssueChange::Unchanged)) => {
+                IssueRepo::is_silenced(&state.db, issue_id, chrono::Utc::now().naive_utc()).await?
+            }
+            _ => false,
+        };
+        if !silenced {
+            WebhookRepo::notify(
+                &state.db,
+                product.id,
+                webhook::CRASH_PROCESSED,
+                json!({
+                    "crash_id": crash_id,
+                    "issue_id": issue.map(|(issue_id, _)| issue_id),
+                    "product": product.name,
+                    "version": version.name,
+                    "signature": info.signature(),
+                    "title": info.title(),
+                    "platform": info.platform(),
+                    "environment": environment,
+                    "authenticated": provenance.is_authenticated(),
+                }),
+            );
+        }
+        state
+            .crashes
+            .publish(
+                &state.db,
+                CrashEvent {
+                    id: crash_id,
+                    product_id: product.id,
+                    product: product.name.clone(),
+                    version: version.name.clone(),
+                    signature: info.signature(),
+                },
+            )
+            .await;
+
+        Ok((crash_id, true))
+    }
+
+    /// Returns the attachment that holds the minidump of a crash.
+    pub(super) async fn stored_minidump(
+        db: &DatabaseConnection,
+        crash_id: uuid::Uuid,
+    ) -> Result<entity::attachment::Model, ApiError> {
+        entity::prelude::Attachment::find()
+            .filter(entity::attachment::Column::CrashId.eq(crash_id))
+            .filter(entity::attachment::Column::Name.eq(MINIDUMP_ATTACHMENT))
+            .one(db)
             .await?
-            .await?;
+            .ok_or_else(|| {
+                ApiError::ForeignKeyError("minidump of crash".to_owned(), crash_id.to_string())
+            })
+    }
+
+    /// Processes the stored minidump of a crash again, e.g. when symbols were uploaded after the
+    /// crash or when processing timed out. The provenance of the submission is kept and the tags
+    /// of the analyzers are replaced. The crash is marked as failed, with the error, when it
+    /// cannot be processed.
+    pub(crate) async fn reprocess(
+        state: &AppState,
+        crash: entity::crash::Model,
+    ) -> Result<(), ApiError> {
+        let crash_id = crash.id;
+        CrashRepo::set_processing_status(&state.db, crash_id, STATUS_PROCESSING, None).await?;
+        let result = Self::process_again(state, crash).await;
+        if let Err(e) = &result {
+            let error = Some(e.to_string());
+            if let Err(e) =
+                CrashRepo::set_processing_status(&state.db, crash_id, STATUS_FAILED, error).await
+            {
+                error!("failed to mark crash {} as failed: {:?}", crash_id, e);
+            }
+        }
+        result
+    }
 
-        let crash_id = Self::store_crash(data, product, version, state).await?;
+    async fn process_again(state: &AppState, crash: entity::crash::Model) -> Result<(), ApiError> {
+        let minidump = Self::stored_minidump(&state.db, crash.id).await?;
+        let _permit = scheduler().acquire(crash.product_id).await;
 
-        Ok(crash_id)
+        let timeout = settings().ingest.processing_timeout;
+        let mut data = Self::process_in_time(PathBuf::from(&minidump.filename), timeout)
+            .await
+            .ok_or(ApiError::ProcessingTimeout(timeout))??;
+        let tags = state.analyzers.run(&mut data);
+        // Provenance and attachment findings are not part of the minidump, keep them.
+        if let Some(report) = data.as_object_mut() {
+            for key in ["provenance", "attachments"] {
+                if let Some(value) = crash.report.get(key) {
+                    report.insert(key.to_owned(), value.clone());
+                }
+            }
+        }
+        Self::link_sources(state, &crash, &mut data).await?;
+        if let Some(product) =
+            Repo::get_by_id::<entity::product::Entity>(&state.db, crash.product_id).await?
+        {
+            scrubber().report(&product.name, &mut data);
+        }
+        let info =
+            CrashInfo::stamp(&mut data).map_err(|e| ApiError::InvalidReport(e.to_string()))?;
+        Self::store_report(crash.id, data, &info, state).await?;
+        // Crashes keep their issue, so that reprocessing does not count them twice; crashes that
+        // could not be processed before are grouped now.
+        if crash.issue_id.is_none() {
+            Self::store_issue(crash.id, crash.product_id, &info, state).await?;
+        }
+        if crash.report.get("processing_error").is_some() {
+            let summary = entity::crash::ActiveModel {
+                id: Set(crash.id),
+                summary: Set(String::new()),
+                ..Default::default()
+            };
+            summary.update(&state.db).await?;
+        }
+
+        entity::prelude::Annotation::delete_many()
+            .filter(entity::annotation::Column::CrashId.eq(crash.id))
+            .filter(entity::annotation::Column::Key.eq(TAG_KEY))
+            .filter(entity::annotation::Column::Kind.eq(AnnotationKind::System))
+            .exec(&state.db)
+            .await?;
+        Self::store_tags(crash.id, tags, state).await?;
+        state.cache.invalidate_product(crash.product_id);
+        info!("reprocessed crash {}", crash.id);
+        Ok(())
+    }
+
+    /// Links the frames of a reprocessed report to their source, at the commit annotated on the
+    /// crash or else at the hash of its version.
+    async fn link_sources(
+        state: &AppState,
+        crash: &entity::crash::Model,
+        data: &mut Value,
+    ) -> Result<(), ApiError> {
+        let Some(product) =
+            Repo::get_by_id::<entity::product::Entity>(&state.db, crash.product_id).await?
+        else {
+            return Ok(());
+        };
+        let commit = entity::prelude::Annotation::find()
+            .filter(entity::annotation::Column::CrashId.eq(crash.id))
+            .filter(entity::annotation::Column::Key.eq(COMMIT_ANNOTATION))
+            .one(&state.db)
+            .await?
+            .map(|annotation| annotation.value);
+        let commit = match commit {
+            Some(commit) => commit,
+            None => Repo::get_by_id::<entity::version::Entity>(&state.db, crash.version_id)
+                .await?
+                .map(|version| version.hash)
+                .unwrap_or_default(),
+        };
+        SourceLinks::from(&product).apply(data, commit.trim());
+        Ok(())
     }
 
+    /// Stores an attachment of a crash that has `attachments` attachments so far.
     async fn handle_attachment_upload(
         crash_id: uuid::Uuid,
+        attachments: usize,
         state: &AppState,
-        _params: &MinidumpRequestParams,
+        params: &MinidumpRequestParams,
         field: Field<'_>,
     ) -> Result<(), ApiError> {
+        let product = Self::get_product(state, params).await?;
+        let quota = UploadQuota::from(&product);
+        if let Some(max) = quota.max_attachments.filter(|max| attachments >= *max) {
+            info!(
+                "rejecting attachment for {}: too many attachments",
+                product.name
+            );
+            return Err(ApiError::TooManyAttachments {
+                product: product.name.clone(),
+                max,
+            });
+        }
+        let mimetype = check_content_type(&product, UploadKind::Attachment, field.content_type())?;
+
+        let name = field.name().unwrap_or("attachment").to_string();
         let filename = field
             .file_name()
             .map(|name| name.to_string())
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-        let attachment_file = Self::get_attachment_file(crash_id, filename).await?;
+        let attachment_file = Self::get_attachment_file(&product.name, crash_id, &filename).await?;
 
-        let mimetype = field
-            .content_type()
-            .unwrap_or("application/octet-stream")
-            .to_owned();
-
-        stream_to_file(&attachment_file, field).await?;
+        let filesize = stream_to_file(&attachment_file, field, quota.max_attachment_size).await?;
+        let (attachment_file, compression) = Self::compress(attachment_file).await?;
 
         Self::store_attachment(
             crash_id,
+            name,
             attachment_file
                 .to_str()
                 .ok_or(ApiError::Failure)?
                 .to_string(),
-            0, // TODO: compute filesize
+            filesize as i64,
             mimetype,
+            compression,
             state,
         )
         .await?;
@@ -215,33 +1304,306 @@ impl MinidumpApi {
     pub async fn upload(
         State(state): State<AppState>,
         Query(params): Query<MinidumpRequestParams>,
+        Query(response): Query<ResponseParams>,
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+        peer: Option<ConnectInfo<SocketAddr>>,
+        headers: HeaderMap,
+        multipart: Multipart,
+    ) -> Result<Response, ApiError> {
+        let client = ClientHints::from_headers(&headers);
+        let provenance = Provenance::from_request(credential, claims, &headers, peer);
+        let sources = upload_sources(&provenance);
+        metrics().record_upload();
+        check_quarantine(&state, &sources)
+            .await
+            .inspect_err(|e| metrics().record_rejection(e))?;
+
+        let crash_id = match Self::receive(&state, &params, &client, &provenance, multipart).await {
+            Ok(crash_id) => crash_id,
+            Err(e) => {
+                metrics().record_rejection(&e);
+                if let Ok(product) = Self::get_product(&state, &params).await {
+                    record_upload_error(&state, &sources, product.id, &e).await;
+                }
+                return Err(e);
+            }
+        };
+        // Crashes are queued during an ingestion window; tell the client instead of reporting them
+        // as processed.
+        let message = match crash_id {
+            Some(_) => Self::get_product(&state, &params)
+                .await
+                .ok()
+                .and_then(|product| Self::queued_message(&product)),
+            None => None,
+        };
+        if response.format == ResponseFormat::Breakpad {
+            return Ok((
+                [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+                Self::breakpad_response(crash_id),
+            )
+                .into_response());
+        }
+        Ok(Json(MinidumpResponse {
+            result: match message {
+                Some(_) => "queued".to_string(),
+                None => "ok".to_string(),
+            },
+            crash_id,
+            message,
+        })
+        .into_response())
+    }
+
+    /// Returns the body of a Breakpad response, which Crashpad stores as the id of the report.
+    fn breakpad_response(crash_id: Option<uuid::Uuid>) -> String {
+        crash_id
+            .map(|id| format!("CrashID={}\n", id))
+            .unwrap_or_default()
+    }
+
+    /// Stores the minidump and attachments of a multipart upload. Returns the id of the crash.
+    async fn receive(
+        state: &AppState,
+        params: &MinidumpRequestParams,
+        client: &ClientHints,
+        provenance: &Provenance,
         mut multipart: Multipart,
-    ) -> Result<Json<MinidumpResponse>, ApiError> {
+    ) -> Result<Option<uuid::Uuid>, ApiError> {
         let mut crash_id: Option<uuid::Uuid> = None;
+        let mut duplicate = false;
+        let mut attachments = 0;
+        let mut client = client.clone();
+        let mut annotations = BTreeMap::new();
 
         while let Some(field) = multipart.next_field().await? {
             match field.name() {
                 Some("upload_file_minidump") => {
-                    crash_id = Some(Self::handle_minidump_upload(&state, &params, field).await?)
+                    let (id, created) = Self::handle_minidump_upload(
+                        state,
+                        params,
+                        &client,
+                        provenance,
+                        &annotations,
+                        field,
+                    )
+                    .await?;
+                    crash_id = Some(id);
+                    duplicate = !created;
+                }
+                Some(ClientHints::CHECKSUM_FIELD) => {
+                    if crash_id.is_some() {
+                        return Err(ApiError::APIFailure(format!(
+                            "{} must precede the minidump",
+                            ClientHints::CHECKSUM_FIELD
+                        )));
+                    }
+                    let checksum = field.text().await?;
+                    client.checksum = Some(checksum.trim().to_lowercase());
                 }
                 Some("options") => {
                     let content = field.bytes().await?;
                     info!("options: {:?}", content);
                 }
+                // Crashpad and Breakpad clients send annotations as fields without a file.
+                Some(name) if field.file_name().is_none() => {
+                    let name = name.to_owned();
+                    annotations.insert(name, field.text().await?);
+                }
+                Some(_) if duplicate => {
+                    debug!("ignoring attachment of duplicate submission");
+                }
                 Some(_) => {
                     Self::handle_attachment_upload(
                         crash_id.ok_or(ApiError::Failure)?,
-                        &state,
-                        &params,
+                        attachments,
+                        state,
+                        params,
                         field,
                     )
-                    .await?
+                    .await?;
+                    attachments += 1;
                 }
                 _ => (),
             }
         }
-        Ok(Json(MinidumpResponse {
-            result: "ok".to_string(),
-        }))
+        if let (Some(crash_id), false) = (crash_id, duplicate) {
+            let product = Self::get_product(state, params).await?;
+            Self::store_annotations(crash_id, &product, annotations, state).await?;
+        }
+        Ok(crash_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_add_exception() {
+        let exception = ExceptionDetails {
+            code: Some("0xc0000005".to_owned()),
+            ..Default::default()
+        };
+        let json =
+            MinidumpApi::add_exception(serde_json::json!({ "status": "OK" }), Some(exception));
+        assert_eq!(json["exception"]["code"], "0xc0000005");
+        assert_eq!(json["status"], "OK");
+
+        let json = MinidumpApi::add_exception(serde_json::json!({ "status": "OK" }), None);
+        assert!(json.get("exception").is_none());
+    }
+
+    #[test]
+    fn test_breakpad_response() {
+        let id = uuid::Uuid::new_v4();
+        assert_eq!(
+            MinidumpApi::breakpad_response(Some(id)),
+            format!("CrashID={}\n", id)
+        );
+        assert_eq!(MinidumpApi::breakpad_response(None), "");
+
+        let params: ResponseParams = serde_json::from_str(r#"{"format":"breakpad"}"#).unwrap();
+        assert_eq!(params.format, ResponseFormat::Breakpad);
+        assert_eq!(ResponseParams::default().format, ResponseFormat::Json);
+    }
+
+    #[test]
+    fn test_clock_skew() {
+        let mut headers = HeaderMap::new();
+        let client = ClientHints::from_headers(&headers);
+        assert!(client.received_at.is_some());
+        assert_eq!(client.clock_skew(), None);
+
+        headers.insert("x-guardrail-timestamp", "1700000090".parse().unwrap());
+        let client = ClientHints {
+            received_at: chrono::DateTime::from_timestamp(1700000000, 0)
+                .map(|time| time.naive_utc()),
+            ..ClientHints::from_headers(&headers)
+        };
+        assert_eq!(client.sent_at, Some(1700000090));
+        assert_eq!(client.clock_skew(), Some(90));
+        assert_eq!(ClientHints::default().clock_skew(), None);
+    }
+
+    #[test]
+    fn test_forwarded_for() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Provenance::forwarded_for(&headers, 1), None);
+
+        // The client sent a spoofed entry, the proxies appended the client and the first proxy.
+        headers.insert(
+            "x-forwarded-for",
+            "198.51.100.1, 203.0.113.7, 10.0.0.1".parse().unwrap(),
+        );
+        assert_eq!(
+            Provenance::forwarded_for(&headers, 1).as_deref(),
+            Some("10.0.0.1")
+        );
+        assert_eq!(
+            Provenance::forwarded_for(&headers, 2).as_deref(),
+            Some("203.0.113.7")
+        );
+        assert_eq!(
+            Provenance::forwarded_for(&headers, 5).as_deref(),
+            Some("198.51.100.1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-guardrail-sha256", " ABCDEF ".parse().unwrap());
+        assert_eq!(
+            ClientHints::from_headers(&headers).checksum.as_deref(),
+            Some("abcdef")
+        );
+
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("crash.dmp");
+        tokio::fs::write(&path, b"MDMP").await.unwrap();
+
+        let verify =
+            |expected: &'static str| MinidumpApi::verify_checksum(&path, expected, "aa", "bb");
+        assert!(verify("aa").await.is_ok());
+        assert!(verify("bb").await.is_ok());
+        assert!(path.exists());
+        assert!(matches!(
+            verify("cc").await,
+            Err(ApiError::ChecksumMismatch(_))
+        ));
+        assert!(!path.exists());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_auto_created_version_build_age() {
+        let state = crate::api::base::tests::test_state().await;
+        let product = crate::model::product::ProductCreateDto {
+            max_build_age_days: Some(30),
+            build_age_overrides: Some("beta=7".to_owned()),
+            auto_create_versions: true,
+            ..crate::model::product::ProductCreateDto::named("Workrave")
+        };
+        let product_id = Repo::create(&state.db, product).await.unwrap();
+        let product = entity::prelude::Product::find_by_id(product_id)
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        for channel in [None, Some("beta".to_owned())] {
+            let params = MinidumpRequestParams {
+                product: "Workrave".to_owned(),
+                version: "1.11.0".to_owned(),
+                channel,
+                environment: None,
+            };
+            let version =
+                MinidumpApi::get_or_new_version(&state, &product, &params, &BTreeMap::new())
+                    .await
+                    .unwrap();
+            assert!(version.release_date.is_some());
+            assert!(MinidumpApi::check_build_age(&product, &version).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_is_authenticated() {
+        let provenance = |kind| Provenance {
+            credential: Some(UploadCredential {
+                kind,
+                id: None,
+                name: None,
+            }),
+            submitter_ip: None,
+        };
+        assert!(provenance("upload_token").is_authenticated());
+        assert!(!provenance(PUBLIC_CREDENTIAL).is_authenticated());
+        assert!(Provenance::default().is_authenticated());
+    }
+
+    #[test]
+    fn test_device_key() {
+        let key = uuid::Uuid::new_v4();
+        let provenance = |kind, id: Option<String>| Provenance {
+            credential: Some(UploadCredential {
+                kind,
+                id,
+                name: Some("ci".to_owned()),
+            }),
+            submitter_ip: None,
+        };
+        assert_eq!(
+            provenance(DEVICE_KEY_CREDENTIAL, Some(key.to_string())).device_key(),
+            Some(key)
+        );
+        assert_eq!(provenance("jwt", Some(key.to_string())).device_key(), None);
+        assert_eq!(provenance(DEVICE_KEY_CREDENTIAL, None).device_key(), None);
     }
 }
@@ -1,11 +1,54 @@
+mod alert;
 mod annotation;
 mod attachment;
 mod base;
+mod cache;
+mod client_diagnostics;
+mod content_type;
 mod crash;
+mod crash_feed;
+mod device_key;
 mod error;
+mod export;
+mod issue;
+mod issue_counts;
+mod link_template;
+mod live;
+mod load_shed;
+mod meta;
+mod metrics;
 mod minidump;
+mod openapi;
 mod product;
+mod product_manage;
+mod public;
+mod quarantine;
+mod read;
+mod read_only;
+mod resumable;
 mod routes;
+mod scheduler;
+mod signature;
+mod stats;
 mod symbols;
+mod token_usage;
+mod upload_token;
+mod v1;
 mod version;
-pub use routes::routes;
+pub use cache::ResponseCache;
+pub use crash_feed::{listen as listen_for_crashes, CrashFeed};
+pub use error::ApiError;
+pub use issue_counts::IssueCounts;
+pub use metrics::metrics;
+pub use minidump::{ClientHints, MinidumpApi, Provenance, UploadCredential, PROCESSING_QUEUED_TAG};
+pub use public::PublicGuard;
+pub use quarantine::UploadGuard;
+pub use read_only::reject_writes;
+pub use resumable::{staging_dir, StagedUpload};
+pub use routes::{
+    download_routes, export_routes, live_routes, meta_routes, product_routes, public_routes,
+    read_routes, routes, signed_routes, upload_routes, v1_routes,
+};
+pub use signature::NonceCache;
+pub use symbols::SymbolsApi;
+pub use token_usage::TokenUsage;
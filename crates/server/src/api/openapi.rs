@@ -0,0 +1,149 @@
+use axum::Json;
+use serde_json::{json, Map, Value};
+
+// An OpenAPI document of the APIs that scripts and integrations call with a personal API token,
+// for the API playground of the web interface and for generating clients. It is maintained by
+// hand next to the routes, see `routes.rs`. Paths are relative to the path prefix.
+
+/// Name of the security scheme of endpoints that need a personal API token.
+const API_TOKEN: &str = "api_token";
+
+/// An endpoint that needs a personal API token.
+struct Endpoint {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+}
+
+const fn endpoint(method: &'static str, path: &'static str, summary: &'static str) -> Endpoint {
+    Endpoint {
+        method,
+        path,
+        summary,
+    }
+}
+
+const ENDPOINTS: &[Endpoint] = &[
+    endpoint("get", "/read/product", "List products"),
+    endpoint("get", "/read/version", "List versions"),
+    endpoint("get", "/read/crash", "List crashes"),
+    endpoint("get", "/read/crash/{id}", "Get a crash"),
+    endpoint("get", "/read/issue", "List issues"),
+    endpoint("get", "/read/issue/{id}", "Get an issue"),
+    endpoint("get", "/api/crashes/stream", "Stream processed crashes"),
+    endpoint("get", "/api/crashes/{id}", "Export a crash"),
+    endpoint("get", "/api/crashes/{id}/minidump", "Download a minidump"),
+    endpoint("get", "/api/stats/crashes", "Crash statistics"),
+    endpoint("get", "/api/symbols/missing", "Missing symbols"),
+    endpoint("get", "/api/products", "List products"),
+    endpoint("post", "/api/products", "Create a product"),
+    endpoint("get", "/api/products/{id}", "Get a product"),
+    endpoint("patch", "/api/products/{id}", "Update a product"),
+    endpoint("delete", "/api/products/{id}", "Delete a product"),
+    endpoint("get", "/api/v1/products", "List products"),
+    endpoint("post", "/api/v1/products", "Create a product"),
+    endpoint("get", "/api/v1/products/{id}", "Get a product"),
+    endpoint("patch", "/api/v1/products/{id}", "Update a product"),
+    endpoint("delete", "/api/v1/products/{id}", "Delete a product"),
+    endpoint("get", "/api/v1/versions", "List versions"),
+    endpoint("post", "/api/v1/versions", "Create a version"),
+    endpoint("get", "/api/v1/versions/{id}", "Get a version"),
+    endpoint("patch", "/api/v1/versions/{id}", "Update a version"),
+    endpoint("delete", "/api/v1/versions/{id}", "Delete a version"),
+    endpoint("get", "/api/v1/tokens", "List API tokens"),
+    endpoint("post", "/api/v1/tokens", "Create an API token"),
+    endpoint("delete", "/api/v1/tokens/{id}", "Revoke an API token"),
+    endpoint("get", "/api/v1/device_keys", "List device keys"),
+    endpoint("get", "/api/v1/quarantines", "List quarantined sources"),
+    endpoint("delete", "/api/v1/quarantines/{id}", "Lift a quarantine"),
+    endpoint(
+        "put",
+        "/api/v1/crashes/{id}/legal_hold",
+        "Place a legal hold",
+    ),
+    endpoint(
+        "delete",
+        "/api/v1/crashes/{id}/legal_hold",
+        "Release a legal hold",
+    ),
+    endpoint("get", "/api/v1/export/{entity}", "Export an entity"),
+];
+
+/// Returns the parameters of the `{name}` segments of `path`.
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+fn document() -> Value {
+    let mut paths = Map::new();
+    for endpoint in ENDPOINTS {
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "responses": { "200": { "description": "OK" } },
+            "security": [{ API_TOKEN: [] }],
+        });
+        let parameters = path_parameters(endpoint.path);
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(methods) = paths
+            .entry(endpoint.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+        {
+            methods.insert(endpoint.method.to_owned(), operation);
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Guardrail",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                API_TOKEN: { "type": "http", "scheme": "bearer" },
+            },
+        },
+    })
+}
+
+pub struct OpenApi;
+
+impl OpenApi {
+    pub async fn get() -> Json<Value> {
+        Json(document())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let document = document();
+        assert_eq!(document["openapi"], "3.0.3");
+
+        let product = &document["paths"]["/api/v1/products/{id}"];
+        assert_eq!(product["get"]["summary"], "Get a product");
+        assert!(product["patch"].is_object());
+        assert_eq!(product["delete"]["parameters"][0]["name"], "id");
+        assert_eq!(product["delete"]["security"][0][API_TOKEN], json!([]));
+
+        let products = &document["paths"]["/read/product"]["get"];
+        assert!(products.get("parameters").is_none());
+    }
+}
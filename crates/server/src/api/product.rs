@@ -1,9 +1,17 @@
+use axum::extract::{Path, State};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
 use crate::{
+    app_state::AppState,
     entity::{prelude::Product, product},
-    model::product::{ProductCreateDto, ProductUpdateDto},
+    model::{
+        product::{ProductCreateDto, ProductUpdateDto},
+        product_deletion::ProductDeletionRepo,
+    },
 };
 
 use super::base::{NoneFilter, Resource};
+use super::error::ApiError;
 
 impl Resource for Product {
     type Entity = product::Entity;
@@ -14,6 +22,30 @@ impl Resource for Product {
     type Filter = NoneFilter;
 }
 
+pub struct ProductApi;
+
+impl ProductApi {
+    pub async fn get_all(State(state): State<AppState>) -> Result<String, ApiError> {
+        let products = Product::find()
+            .filter(
+                product::Column::Id.not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+            )
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": products }).to_string())
+    }
+
+    /// Schedules the product for deletion; the data is removed by a background job.
+    pub async fn remove_by_id(
+        Path(id): Path<uuid::Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        let deletion = ProductDeletionRepo::schedule(&state.db, id).await?;
+        state.cache.invalidate_product(id);
+        Ok(serde_json::json!({ "result": "ok", "id": id, "deletion": deletion }).to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::api::base::tests::*;
@@ -0,0 +1,308 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Extension;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::read::authenticate;
+use crate::app_state::AppState;
+use crate::entity;
+use crate::model::api_token::{ApiTokenRepo, PRODUCT_MANAGE};
+use crate::model::base::Repo;
+use crate::model::product::ProductCreateDto;
+use crate::model::product_deletion::ProductDeletionRepo;
+
+// Products API for automation, e.g. to stop accepting crashes of a product at the end of its
+// life from a release pipeline. Authenticated by a personal API token. Administrators may create
+// and delete products; users with an `admin` or `product-manage` role for a product may update it.
+
+#[derive(Debug, Clone)]
+pub struct ManageAccess {
+    pub is_admin: bool,
+    /// Products the token owner may update, `None` for all products.
+    pub products: Option<Vec<Uuid>>,
+}
+
+impl ManageAccess {
+    fn check(&self, product_id: Uuid) -> Result<(), ApiError> {
+        match &self.products {
+            Some(products) if !products.contains(&product_id) => Err(ApiError::AccessDenied),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_admin(&self) -> Result<(), ApiError> {
+        match self.is_admin {
+            true => Ok(()),
+            false => Err(ApiError::AccessDenied),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductFilter {
+    /// Limits products to names containing this text.
+    pub name: Option<String>,
+    pub accepting_crashes: Option<bool>,
+}
+
+/// Settings of a product. Absent fields are left unchanged; an empty string clears a setting.
+/// Lists, e.g. `environments`, are comma separated as in the web interface.
+#[derive(Debug, Deserialize)]
+pub struct ProductRequest {
+    pub name: Option<String>,
+    /// Maximum age of builds in days, `0` to accept builds of any age.
+    pub max_build_age_days: Option<i32>,
+    pub build_age_overrides: Option<String>,
+    pub build_age_allow_list: Option<String>,
+    pub promoted_annotations: Option<String>,
+    pub redacted_fields: Option<String>,
+    pub allowed_content_types: Option<String>,
+    pub environments: Option<String>,
+    pub accepting_crashes: Option<bool>,
+    /// Accept crashes without a token, see `PublicApi`.
+    pub public_submissions: Option<bool>,
+    /// Repository that frames of crashes link to, see `SourceLinks`.
+    pub repository_url: Option<String>,
+    pub source_roots: Option<String>,
+    /// Processors run on the attachments of crashes, e.g. `log`.
+    pub attachment_processors: Option<String>,
+    /// Annotations added to every crash, as `key=value` pairs, see `with_defaults`.
+    pub default_annotations: Option<String>,
+    /// Create versions that are not registered when a crash is uploaded for them, instead of
+    /// rejecting the crash.
+    pub auto_create_versions: Option<bool>,
+    /// Windows in which crashes are queued or rejected, separated by `;`, see
+    /// `IngestionSchedule`.
+    pub ingestion_windows: Option<String>,
+    /// Maximum size of a minidump in bytes, `0` for no limit. See `UploadQuota`.
+    pub max_minidump_size: Option<i64>,
+    /// Maximum size of an attachment in bytes, `0` for no limit.
+    pub max_attachment_size: Option<i64>,
+    /// Maximum number of attachments per crash, `0` for no limit.
+    pub max_attachments: Option<i32>,
+    /// Maximum number of uploads per day (UTC), `0` for no limit.
+    pub max_daily_uploads: Option<i32>,
+}
+
+fn setting(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+impl ProductRequest {
+    fn name(&self) -> Result<Option<String>, ApiError> {
+        match self.name.as_deref().map(str::trim) {
+            Some("") => Err(ApiError::APIFailure("name must not be empty".to_owned())),
+            name => Ok(name.map(str::to_owned)),
+        }
+    }
+
+    fn apply(self, product: &mut entity::product::ActiveModel) -> Result<(), ApiError> {
+        if let Some(name) = self.name()? {
+            product.name = Set(name);
+        }
+        if let Some(days) = self.max_build_age_days {
+            product.max_build_age_days = Set((days > 0).then_some(days));
+        }
+        if let Some(value) = self.build_age_overrides {
+            product.build_age_overrides = Set(setting(value));
+        }
+        if let Some(value) = self.build_age_allow_list {
+            product.build_age_allow_list = Set(setting(value));
+        }
+        if let Some(value) = self.promoted_annotations {
+            product.promoted_annotations = Set(setting(value));
+        }
+        if let Some(value) = self.redacted_fields {
+            product.redacted_fields = Set(setting(value));
+        }
+        if let Some(value) = self.allowed_content_types {
+            product.allowed_content_types = Set(setting(value));
+        }
+        if let Some(value) = self.environments {
+            product.environments = Set(setting(value));
+        }
+        if let Some(accepting_crashes) = self.accepting_crashes {
+            product.accepting_crashes = Set(accepting_crashes);
+        }
+        if let Some(public_submissions) = self.public_submissions {
+            product.public_submissions = Set(public_submissions);
+        }
+        if let Some(value) = self.repository_url {
+            product.repository_url = Set(setting(value));
+        }
+        if let Some(value) = self.source_roots {
+            product.source_roots = Set(setting(value));
+        }
+        if let Some(value) = self.attachment_processors {
+            product.attachment_processors = Set(setting(value));
+        }
+        if let Some(value) = self.default_annotations {
+            product.default_annotations = Set(setting(value));
+        }
+        if let Some(auto_create_versions) = self.auto_create_versions {
+            product.auto_create_versions = Set(auto_create_versions);
+        }
+        if let Some(value) = self.ingestion_windows {
+            product.ingestion_windows = Set(setting(value));
+        }
+        if let Some(size) = self.max_minidump_size {
+            product.max_minidump_size = Set((size > 0).then_some(size));
+        }
+        if let Some(size) = self.max_attachment_size {
+            product.max_attachment_size = Set((size > 0).then_some(size));
+        }
+        if let Some(max) = self.max_attachments {
+            product.max_attachments = Set((max > 0).then_some(max));
+        }
+        if let Some(max) = self.max_daily_uploads {
+            product.max_daily_uploads = Set((max > 0).then_some(max));
+        }
+        Ok(())
+    }
+}
+
+pub async fn verify_manage_token(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let (user, token) = authenticate(&state, &request, PRODUCT_MANAGE).await?;
+    let products = ApiTokenRepo::manageable_products(&state.db, &user).await?;
+
+    // Tokens limited to a product can update it, but not create or delete products.
+    request.extensions_mut().insert(ManageAccess {
+        is_admin: user.is_admin && token.product_id.is_none(),
+        products: token.scope_products(products),
+    });
+    Ok(next.run(request).await)
+}
+
+pub struct ProductManageApi;
+
+impl ProductManageApi {
+    async fn find(state: &AppState, id: Uuid) -> Result<entity::product::Model, ApiError> {
+        entity::prelude::Product::find_by_id(id)
+            .filter(
+                entity::product::Column::Id
+                    .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+            )
+            .one(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("product".to_owned(), id.to_string()))
+    }
+
+    pub async fn list(
+        State(state): State<AppState>,
+        Extension(access): Extension<ManageAccess>,
+        Query(filter): Query<ProductFilter>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Product::find().filter(
+            entity::product::Column::Id
+                .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+        );
+        if let Some(products) = access.products {
+            query = query.filter(entity::product::Column::Id.is_in(products));
+        }
+        if let Some(name) = filter.name {
+            query = query.filter(entity::product::Column::Name.contains(name));
+        }
+        if let Some(accepting_crashes) = filter.accepting_crashes {
+            query = query.filter(entity::product::Column::AcceptingCrashes.eq(accepting_crashes));
+        }
+        let products = query
+            .order_by_asc(entity::product::Column::Name)
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": products }).to_string())
+    }
+
+    pub async fn get(
+        State(state): State<AppState>,
+        Extension(access): Extension<ManageAccess>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        access.check(id)?;
+        let product = Self::find(&state, id).await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": product }).to_string())
+    }
+
+    pub async fn create(
+        State(state): State<AppState>,
+        Extension(access): Extension<ManageAccess>,
+        payload: String,
+    ) -> Result<String, ApiError> {
+        access.check_admin()?;
+        let request: ProductRequest = serde_json::from_str(&payload)?;
+        let name = request
+            .name()?
+            .ok_or_else(|| ApiError::APIFailure("name is required".to_owned()))?;
+        let id = Repo::create(&state.db, ProductCreateDto::named(name)).await?;
+
+        let product = Self::find(&state, id).await?;
+        let mut active: entity::product::ActiveModel = product.into();
+        request.apply(&mut active)?;
+        let product = active.update(&state.db).await?;
+        state.cache.invalidate_all();
+        Ok(serde_json::json!({ "result": "ok", "id": id, "payload": product }).to_string())
+    }
+
+    pub async fn update(
+        State(state): State<AppState>,
+        Extension(access): Extension<ManageAccess>,
+        Path(id): Path<Uuid>,
+        payload: String,
+    ) -> Result<String, ApiError> {
+        access.check(id)?;
+        let request: ProductRequest = serde_json::from_str(&payload)?;
+        let product = Self::find(&state, id).await?;
+        let mut active: entity::product::ActiveModel = product.into();
+        request.apply(&mut active)?;
+        active.updated_at = Set(chrono::Utc::now().naive_utc());
+        let product = active.update(&state.db).await?;
+        state.cache.invalidate_product(id);
+        Ok(serde_json::json!({ "result": "ok", "payload": product }).to_string())
+    }
+
+    /// Schedules the product for deletion, like `ProductApi::remove_by_id`.
+    pub async fn remove(
+        State(state): State<AppState>,
+        Extension(access): Extension<ManageAccess>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        access.check_admin()?;
+        Self::find(&state, id).await?;
+        let deletion = ProductDeletionRepo::schedule(&state.db, id).await?;
+        state.cache.invalidate_product(id);
+        Ok(serde_json::json!({ "result": "ok", "id": id, "deletion": deletion }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::base::tests::*;
+    use axum::http::header;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_requires_api_token() {
+        let server = run_server().await;
+
+        let response = server.get("/api/products").await;
+        response.assert_status_unauthorized();
+        assert_eq!(response.json::<ApiResponseFailed>().result, "failed");
+
+        let response = server
+            .patch("/api/products/00000000-0000-0000-0000-000000000000")
+            .add_header(header::AUTHORIZATION, "Bearer grt_unknown".parse().unwrap())
+            .json(&serde_json::json!({ "accepting_crashes": false }))
+            .await;
+        response.assert_status_unauthorized();
+        assert_eq!(response.json::<ApiResponseFailed>().result, "failed");
+    }
+}
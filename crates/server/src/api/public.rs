@@ -0,0 +1,309 @@
+use axum::extract::{ConnectInfo, Query, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use tracing::info;
+
+use super::error::ApiError;
+use super::minidump::{Provenance, UploadCredential};
+use crate::app_state::AppState;
+use crate::model::base::Repo;
+use crate::{entity, settings};
+
+// Open-source products can accept crashes from anyone, without distributing tokens. Public
+// submissions are enabled per product and limited per IP address. When a proof-of-work is
+// required, the client fetches a challenge from `/public/challenge`, finds a nonce for which
+// SHA-256("{challenge}:{nonce}") starts with the requested number of zero bits, and sends
+//
+//   X-Guardrail-Proof  {challenge}:{nonce}
+//
+// with the upload. Challenges are kept in memory, are bound to the product they were issued
+// for and can be used once; at most `max_challenges` are open at a time. Challenges and
+// submissions are each limited to `rate_limit` per hour per client address, see
+// `Provenance::client_ip`. Crashes submitted this way are stored as not authenticated.
+
+const PROOF_HEADER: &str = "x-guardrail-proof";
+const WINDOW: i64 = 60 * 60;
+
+/// Kind of the credential of public submissions, see `Provenance::is_authenticated`.
+pub const PUBLIC_CREDENTIAL: &str = "public";
+
+#[derive(Debug, Default)]
+pub struct PublicGuard {
+    /// Times of the recent submissions per IP address.
+    submissions: Mutex<HashMap<String, VecDeque<i64>>>,
+    /// Times of the recently issued challenges per IP address.
+    issued: Mutex<HashMap<String, VecDeque<i64>>>,
+    /// Product and expiry time of the issued challenges.
+    challenges: Mutex<HashMap<String, (String, i64)>>,
+}
+
+/// Records an event of the address in `recent` and returns `false` when the address already
+/// had `limit` events within the last hour.
+fn record_recent(
+    recent: &Mutex<HashMap<String, VecDeque<i64>>>,
+    ip: &str,
+    now: i64,
+    limit: usize,
+) -> bool {
+    let mut recent = recent.lock().unwrap_or_else(|e| e.into_inner());
+    recent.retain(|_, times| times.back().is_some_and(|time| now - time < WINDOW));
+
+    let times = recent.entry(ip.to_owned()).or_default();
+    while times.front().is_some_and(|time| now - time >= WINDOW) {
+        times.pop_front();
+    }
+    if times.len() >= limit {
+        return false;
+    }
+    times.push_back(now);
+    true
+}
+
+impl PublicGuard {
+    /// Records a submission from the address and returns `false` when the address already made
+    /// `limit` submissions within the last hour.
+    pub fn allow(&self, ip: &str, now: i64, limit: usize) -> bool {
+        record_recent(&self.submissions, ip, now, limit)
+    }
+
+    /// Records a challenge requested by the address and returns `false` when the address
+    /// already requested `limit` challenges within the last hour, so that a single client
+    /// cannot take all of the `max_challenges` open challenges.
+    pub fn allow_challenge(&self, ip: &str, now: i64, limit: usize) -> bool {
+        record_recent(&self.issued, ip, now, limit)
+    }
+
+    /// Issues a challenge for a proof-of-work for the product. Returns `None` when `max`
+    /// challenges are open.
+    pub fn challenge(&self, product: &str, now: i64, lifetime: u64, max: usize) -> Option<String> {
+        let mut challenges = self.challenges.lock().unwrap_or_else(|e| e.into_inner());
+        if challenges.len() >= max {
+            challenges.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+        if challenges.len() >= max {
+            return None;
+        }
+        let challenge = uuid::Uuid::new_v4().simple().to_string();
+        challenges.insert(
+            challenge.clone(),
+            (product.to_owned(), now + lifetime as i64),
+        );
+        Some(challenge)
+    }
+
+    /// Uses up the challenge. Returns whether it was issued for the product and has not
+    /// expired.
+    fn redeem(&self, challenge: &str, product: &str, now: i64) -> bool {
+        let mut challenges = self.challenges.lock().unwrap_or_else(|e| e.into_inner());
+        challenges
+            .remove(challenge)
+            .is_some_and(|(issued_for, expires_at)| issued_for == product && expires_at > now)
+    }
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Returns whether the nonce solves the challenge with at least `bits` leading zero bits.
+pub fn check_proof(challenge: &str, nonce: &str, bits: u32) -> bool {
+    leading_zero_bits(&Sha256::digest(format!("{}:{}", challenge, nonce))) >= bits
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublicParams {
+    pub product: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChallengeResponse {
+    pub result: String,
+    pub challenge: String,
+    /// Number of leading zero bits the proof-of-work must have.
+    pub difficulty: u32,
+    pub expires_at: i64,
+}
+
+/// Rejects products that do not accept public submissions.
+async fn check_public(state: &AppState, product: &str) -> Result<(), ApiError> {
+    let product = Repo::get_by_column::<entity::product::Entity, _, _>(
+        &state.db,
+        entity::product::Column::Name,
+        product.to_owned(),
+    )
+    .await?;
+    match product {
+        Some(product) if product.public_submissions => Ok(()),
+        _ => Err(ApiError::UploadRejected(
+            "product does not accept public submissions".to_owned(),
+        )),
+    }
+}
+
+pub struct PublicApi;
+
+impl PublicApi {
+    pub async fn challenge(
+        State(state): State<AppState>,
+        Query(params): Query<PublicParams>,
+        peer: Option<ConnectInfo<SocketAddr>>,
+        headers: HeaderMap,
+    ) -> Result<Json<ChallengeResponse>, ApiError> {
+        check_public(&state, &params.product).await?;
+
+        let config = &settings().public_submissions;
+        let now = chrono::Utc::now().timestamp();
+        // Without a known address, all clients share one limit.
+        let ip =
+            Provenance::client_ip(&headers, peer.map(|ConnectInfo(peer)| peer)).unwrap_or_default();
+        if !state.public.allow_challenge(&ip, now, config.rate_limit) {
+            info!(
+                "rejecting challenge for {} from {}: rate limited",
+                params.product, ip
+            );
+            return Err(ApiError::RateLimited(format!(
+                "at most {} challenges per hour",
+                config.rate_limit
+            )));
+        }
+        let challenge = state
+            .public
+            .challenge(
+                &params.product,
+                now,
+                config.challenge_lifetime,
+                config.max_challenges,
+            )
+            .ok_or_else(|| ApiError::RateLimited("too many open challenges".to_owned()))?;
+        Ok(Json(ChallengeResponse {
+            result: "ok".to_owned(),
+            challenge,
+            difficulty: config.proof_of_work_bits,
+            expires_at: now + config.challenge_lifetime as i64,
+        }))
+    }
+}
+
+pub async fn verify_public_submission(
+    State(state): State<AppState>,
+    Query(params): Query<PublicParams>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    check_public(&state, &params.product).await?;
+
+    let config = &settings().public_submissions;
+    let now = chrono::Utc::now().timestamp();
+    if config.proof_of_work_bits > 0 {
+        let (challenge, nonce) = request
+            .headers()
+            .get(PROOF_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(':'))
+            .ok_or_else(|| ApiError::UploadRejected("missing proof-of-work".to_owned()))?;
+        if !check_proof(challenge, nonce, config.proof_of_work_bits)
+            || !state.public.redeem(challenge, &params.product, now)
+        {
+            return Err(ApiError::UploadRejected("invalid proof-of-work".to_owned()));
+        }
+    }
+
+    // Without a known address, all public submissions share one limit.
+    let ip = Provenance::client_ip(request.headers(), peer.map(|ConnectInfo(peer)| peer))
+        .unwrap_or_default();
+    if !state.public.allow(&ip, now, config.rate_limit) {
+        info!(
+            "rejecting public submission for {} from {}: rate limited",
+            params.product, ip
+        );
+        return Err(ApiError::RateLimited(format!(
+            "at most {} public submissions per hour",
+            config.rate_limit
+        )));
+    }
+
+    request.extensions_mut().insert(UploadCredential {
+        kind: PUBLIC_CREDENTIAL,
+        id: None,
+        name: None,
+    });
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow() {
+        let guard = PublicGuard::default();
+        assert!(guard.allow("10.0.0.1", 1000, 2));
+        assert!(guard.allow("10.0.0.1", 1001, 2));
+        assert!(!guard.allow("10.0.0.1", 1002, 2));
+        assert!(guard.allow("10.0.0.2", 1002, 2));
+
+        // Submissions older than an hour do not count.
+        assert!(guard.allow("10.0.0.1", 1000 + WINDOW, 2));
+        assert!(!guard.allow("10.0.0.1", 1000 + WINDOW, 2));
+    }
+
+    #[test]
+    fn test_allow_challenge() {
+        let guard = PublicGuard::default();
+        assert!(guard.allow_challenge("10.0.0.1", 1000, 2));
+        assert!(guard.allow_challenge("10.0.0.1", 1001, 2));
+        assert!(!guard.allow_challenge("10.0.0.1", 1002, 2));
+        assert!(guard.allow_challenge("10.0.0.2", 1002, 2));
+        // Challenges and submissions are limited separately.
+        assert!(guard.allow("10.0.0.1", 1002, 2));
+        assert!(guard.allow_challenge("10.0.0.1", 1000 + WINDOW, 2));
+    }
+
+    #[test]
+    fn test_proof_of_work() {
+        assert_eq!(leading_zero_bits(&[0, 0, 0xff]), 16);
+        assert_eq!(leading_zero_bits(&[0, 0x10, 0]), 11);
+
+        let guard = PublicGuard::default();
+        let challenge = guard.challenge("Workrave", 1000, 600, 10).unwrap();
+        let nonce = (0u64..)
+            .find(|nonce| check_proof(&challenge, &nonce.to_string(), 8))
+            .unwrap()
+            .to_string();
+        assert!(check_proof(&challenge, &nonce, 8));
+
+        assert!(!guard.redeem("unknown", "Workrave", 1000));
+        assert!(!guard.redeem(&challenge, "Workrave", 1600));
+        let challenge = guard.challenge("Workrave", 1000, 600, 10).unwrap();
+        assert!(!guard.redeem(&challenge, "Other", 1001));
+        let challenge = guard.challenge("Workrave", 1000, 600, 10).unwrap();
+        assert!(guard.redeem(&challenge, "Workrave", 1001));
+        // Challenges can only be used once.
+        assert!(!guard.redeem(&challenge, "Workrave", 1002));
+    }
+
+    #[test]
+    fn test_max_challenges() {
+        let guard = PublicGuard::default();
+        assert!(guard.challenge("Workrave", 1000, 600, 2).is_some());
+        assert!(guard.challenge("Workrave", 1000, 600, 2).is_some());
+        assert!(guard.challenge("Workrave", 1001, 600, 2).is_none());
+        // Expired challenges make room for new ones.
+        assert!(guard.challenge("Workrave", 1600, 600, 2).is_some());
+    }
+}
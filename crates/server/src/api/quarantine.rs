@@ -0,0 +1,210 @@
+use axum::extract::{Path, State};
+use axum::Extension;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::minidump::Provenance;
+use crate::app_state::AppState;
+use crate::data_providers::webhook::UPLOAD_QUARANTINED;
+use crate::model::upload_quarantine::UploadQuarantineRepo;
+use crate::model::webhook::WebhookRepo;
+use crate::utils::error::UtilsError;
+use crate::{entity, settings};
+
+// A leaked credential could be used to flood a product with garbage. Invalid uploads are counted
+// per source, i.e. per credential and per submitter IP, over the last hour. A source that exceeds
+// the limit is quarantined: its uploads are rejected until the quarantine expires or an
+// administrator lifts it. Quarantines are kept after they end as a record of what happened.
+//
+// IP addresses are only known when `ingest.record_submitter_ip` is enabled. Upload tokens carry
+// no identity of their own, so their uploads are only counted by IP address.
+
+const WINDOW: i64 = 60 * 60;
+
+#[derive(Debug, Default)]
+pub struct UploadGuard {
+    /// Times of the recent invalid uploads per source.
+    failures: Mutex<HashMap<String, VecDeque<i64>>>,
+}
+
+impl UploadGuard {
+    /// Records an invalid upload of the source and returns `true` when the source reached the
+    /// limit within the last hour. The count starts over once the limit is reached.
+    pub fn record_failure(&self, source: &str, now: i64, limit: usize) -> bool {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        failures.retain(|_, times| times.back().is_some_and(|time| now - time < WINDOW));
+
+        let times = failures.entry(source.to_owned()).or_default();
+        while times.front().is_some_and(|time| now - time >= WINDOW) {
+            times.pop_front();
+        }
+        times.push_back(now);
+        if times.len() < limit {
+            return false;
+        }
+        failures.remove(source);
+        true
+    }
+}
+
+/// Returns the sources of an upload, as stored in quarantines, e.g. `device_key:<id>` or
+/// `ip:<address>`.
+pub(super) fn upload_sources(provenance: &Provenance) -> Vec<String> {
+    let mut sources = vec![];
+    if let Some(credential) = &provenance.credential {
+        if let Some(id) = credential.id.as_ref().or(credential.name.as_ref()) {
+            sources.push(format!("{}:{}", credential.kind, id));
+        }
+    }
+    if let Some(ip) = &provenance.submitter_ip {
+        sources.push(format!("ip:{}", ip));
+    }
+    sources
+}
+
+/// Returns whether an error is caused by the content of the upload, as opposed to e.g. a
+/// policy of the product or a failure of the server.
+fn is_invalid_upload(error: &ApiError) -> bool {
+    matches!(
+        error,
+        ApiError::MinidumpError(_)
+            | ApiError::MinidumpProcessError(_)
+            | ApiError::ProcessingTimeout(_)
+            | ApiError::InvalidReport(_)
+            | ApiError::JsonError(_)
+            | ApiError::MultiPartError(_)
+            | ApiError::UnsupportedContentType { .. }
+            | ApiError::UtilsError(UtilsError::DecompressedTooLarge(_))
+    )
+}
+
+/// Rejects the upload when one of its sources is quarantined.
+pub(super) async fn check_quarantine(state: &AppState, sources: &[String]) -> Result<(), ApiError> {
+    match UploadQuarantineRepo::find_active(&state.db, sources).await? {
+        Some(quarantine) => Err(ApiError::Quarantined(format!(
+            "{} until {}: {}",
+            quarantine.source, quarantine.expires_at, quarantine.reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Counts a failed upload against its sources and quarantines the sources that reached the
+/// limit. Administrators are notified through the `upload_quarantined` webhooks of the product.
+pub(super) async fn record_upload_error(
+    state: &AppState,
+    sources: &[String],
+    product_id: Uuid,
+    error: &ApiError,
+) {
+    let config = &settings().quarantine;
+    if config.max_invalid_uploads == 0 || !is_invalid_upload(error) {
+        return;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    for source in sources {
+        if !state
+            .uploads
+            .record_failure(source, now, config.max_invalid_uploads)
+        {
+            continue;
+        }
+        let reason = format!(
+            "{} invalid uploads within an hour, last: {}",
+            config.max_invalid_uploads, error
+        );
+        warn!("quarantining upload source {}: {}", source, reason);
+        match UploadQuarantineRepo::quarantine(
+            &state.db,
+            source,
+            reason.clone(),
+            product_id,
+            config.duration,
+        )
+        .await
+        {
+            Ok(id) => WebhookRepo::notify(
+                &state.db,
+                product_id,
+                UPLOAD_QUARANTINED,
+                serde_json::json!({ "id": id, "source": source, "reason": reason }),
+            ),
+            Err(e) => warn!("failed to quarantine upload source {}: {:?}", source, e),
+        }
+    }
+}
+
+pub struct QuarantineApi;
+
+impl QuarantineApi {
+    pub async fn list(State(state): State<AppState>) -> Result<String, ApiError> {
+        let quarantines = UploadQuarantineRepo::get_active(&state.db).await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": quarantines }).to_string())
+    }
+
+    /// Lifts a quarantine, so that the source can upload again.
+    pub async fn lift(
+        State(state): State<AppState>,
+        Extension(user): Extension<entity::user::Model>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        let quarantine = UploadQuarantineRepo::lift(&state.db, id, user.id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("quarantine".to_owned(), id.to_string()))?;
+        info!(
+            "upload quarantine of {} lifted by {}",
+            quarantine.source, user.username
+        );
+        Ok(serde_json::json!({ "result": "ok", "payload": quarantine }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::minidump::UploadCredential;
+
+    #[test]
+    fn test_record_failure() {
+        let guard = UploadGuard::default();
+        assert!(!guard.record_failure("ip:10.0.0.1", 1000, 3));
+        assert!(!guard.record_failure("ip:10.0.0.1", 1001, 3));
+        assert!(!guard.record_failure("ip:10.0.0.2", 1001, 3));
+        assert!(guard.record_failure("ip:10.0.0.1", 1002, 3));
+        // The count starts over after reaching the limit.
+        assert!(!guard.record_failure("ip:10.0.0.1", 1003, 3));
+
+        // Failures older than an hour do not count.
+        assert!(!guard.record_failure("ip:10.0.0.2", 1000 + WINDOW, 3));
+        assert!(!guard.record_failure("ip:10.0.0.2", 1001 + WINDOW, 3));
+        assert!(guard.record_failure("ip:10.0.0.2", 1002 + WINDOW, 3));
+    }
+
+    #[test]
+    fn test_upload_sources() {
+        let provenance = Provenance {
+            credential: Some(UploadCredential {
+                kind: "device_key",
+                id: Some("42".to_owned()),
+                name: Some("ci".to_owned()),
+            }),
+            submitter_ip: Some("10.0.0.1".to_owned()),
+        };
+        assert_eq!(
+            upload_sources(&provenance),
+            vec!["device_key:42", "ip:10.0.0.1"]
+        );
+        assert!(upload_sources(&Provenance::default()).is_empty());
+
+        assert!(is_invalid_upload(&ApiError::InvalidReport(
+            "empty".to_owned()
+        )));
+        assert!(!is_invalid_upload(&ApiError::UploadRejected(
+            "old build".to_owned()
+        )));
+    }
+}
@@ -0,0 +1,416 @@
+use axum::body::Body;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::minidump::MinidumpApi;
+use crate::app_state::AppState;
+use crate::entity;
+use crate::model::api_token::{ApiTokenRepo, READ};
+use crate::model::crash::CrashRepo;
+use crate::model::product::RedactionRules;
+use crate::model::product_deletion::ProductDeletionRepo;
+use crate::utils::compression;
+
+// Read-only API for personal API tokens. Users only see the products they have a role for and
+// get reports with the product's redaction rules applied; administrators, and admins of the
+// product, see everything, see `CrashRepo::get_for_user`.
+
+const DEFAULT_LIMIT: u64 = 50;
+pub const MAX_LIMIT: u64 = 500;
+
+#[derive(Debug, Clone)]
+pub struct ReadAccess {
+    /// The owner of the token.
+    pub user_id: Uuid,
+    pub is_admin: bool,
+    /// Products the token owner may read, `None` for all products.
+    pub products: Option<Vec<Uuid>>,
+}
+
+impl ReadAccess {
+    pub(super) fn allows(&self, product_id: Uuid) -> bool {
+        self.products
+            .as_ref()
+            .map_or(true, |products| products.contains(&product_id))
+    }
+
+    /// Returns the products to query: `product` if given and readable, otherwise all readable
+    /// products, `None` for all products.
+    pub(super) fn products_of(&self, product: Option<Uuid>) -> Result<Option<Vec<Uuid>>, ApiError> {
+        match product {
+            Some(product) if !self.allows(product) => Err(ApiError::AccessDenied),
+            Some(product) => Ok(Some(vec![product])),
+            None => Ok(self.products.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadParams {
+    pub product: Option<Uuid>,
+    /// Limits crashes to a platform, e.g. `windows-x86_64`.
+    pub platform: Option<String>,
+    /// Limits crashes to an environment, e.g. `production`.
+    pub environment: Option<String>,
+    /// Limits crashes to an issue.
+    pub issue: Option<Uuid>,
+    /// Limits crashes to a processing status: `pending`, `processing`, `failed` or `done`.
+    pub processing_status: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Attachment of an exported crash, without its location on the server.
+#[derive(Debug, Serialize)]
+pub struct ExportedAttachment {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub name: String,
+    pub mime_type: String,
+    pub size: i64,
+}
+
+impl From<entity::attachment::Model> for ExportedAttachment {
+    fn from(attachment: entity::attachment::Model) -> Self {
+        Self {
+            id: attachment.id,
+            created_at: attachment.created_at,
+            name: attachment.name,
+            mime_type: attachment.mime_type,
+            size: attachment.size,
+        }
+    }
+}
+
+/// Returns whether the client accepts zstd compressed responses.
+fn accepts_zstd(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|encoding| {
+            let mut parts = encoding.split(';').map(str::trim);
+            parts.next() == Some(compression::ZSTD)
+                && parts.all(|param| param.replace(' ', "") != "q=0")
+        })
+}
+
+/// Returns the owner of the personal API token in the `Authorization` header, and the token.
+/// Tokens limited to other entitlements are rejected.
+pub(super) async fn authenticate(
+    state: &AppState,
+    request: &Request,
+    entitlement: &str,
+) -> Result<(entity::user::Model, entity::api_token::Model), ApiError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::InvalidApiToken("missing bearer token".to_owned()))?;
+
+    let (user, token) = ApiTokenRepo::authenticate(&state.db, token)
+        .await?
+        .ok_or_else(|| ApiError::InvalidApiToken("unknown token".to_owned()))?;
+    if !token.has_entitlement(entitlement) {
+        return Err(ApiError::AccessDenied);
+    }
+    Ok((user, token))
+}
+
+pub async fn verify_api_token(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let (user, token) = authenticate(&state, &request, READ).await?;
+    let products = ApiTokenRepo::readable_products(&state.db, user.id, user.is_admin).await?;
+
+    request.extensions_mut().insert(ReadAccess {
+        user_id: user.id,
+        is_admin: user.is_admin,
+        products: token.scope_products(products),
+    });
+    Ok(next.run(request).await)
+}
+
+pub struct ReadApi;
+
+impl ReadApi {
+    pub async fn products(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Product::find().filter(
+            entity::product::Column::Id
+                .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+        );
+        if let Some(products) = access.products {
+            query = query.filter(entity::product::Column::Id.is_in(products));
+        }
+        let products = query
+            .order_by_asc(entity::product::Column::Name)
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": products }).to_string())
+    }
+
+    pub async fn versions(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<ReadParams>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Version::find();
+        if let Some(product_id) = params.product {
+            query = query.filter(entity::version::Column::ProductId.eq(product_id));
+        }
+        if let Some(products) = access.products {
+            query = query.filter(entity::version::Column::ProductId.is_in(products));
+        }
+        let versions = query
+            .order_by_desc(entity::version::Column::CreatedAt)
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": versions }).to_string())
+    }
+
+    async fn redaction_rules(
+        state: &AppState,
+        access: &ReadAccess,
+        mut product_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, RedactionRules>, ApiError> {
+        product_ids.sort();
+        product_ids.dedup();
+        let mut rules = HashMap::new();
+        for product_id in product_ids {
+            let product_rules =
+                CrashRepo::redaction_rules(&state.db, product_id, access.user_id, access.is_admin)
+                    .await?;
+            rules.insert(product_id, product_rules);
+        }
+        Ok(rules)
+    }
+
+    pub async fn crashes(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<ReadParams>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Crash::find();
+        if let Some(product_id) = params.product {
+            query = query.filter(entity::crash::Column::ProductId.eq(product_id));
+        }
+        if let Some(platform) = params.platform {
+            query = query.filter(entity::crash::Column::Platform.eq(platform));
+        }
+        if let Some(environment) = params.environment {
+            query = query.filter(
+                entity::crash::Column::Environment.eq(environment.trim().to_ascii_lowercase()),
+            );
+        }
+        if let Some(issue_id) = params.issue {
+            query = query.filter(entity::crash::Column::IssueId.eq(issue_id));
+        }
+        if let Some(status) = params.processing_status {
+            query = query.filter(entity::crash::Column::ProcessingStatus.eq(status));
+        }
+        if let Some(products) = access.products.clone() {
+            query = query.filter(entity::crash::Column::ProductId.is_in(products));
+        }
+        let mut crashes = query
+            .order_by_desc(entity::crash::Column::CreatedAt)
+            .offset(params.offset.unwrap_or(0))
+            .limit(params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+            .all(&state.db)
+            .await?;
+
+        let product_ids = crashes.iter().map(|crash| crash.product_id).collect();
+        let rules = Self::redaction_rules(&state, &access, product_ids).await?;
+        for crash in &mut crashes {
+            if let Some(rules) = rules.get(&crash.product_id) {
+                rules.apply(&mut crash.report);
+            }
+        }
+        Ok(serde_json::json!({ "result": "ok", "payload": crashes }).to_string())
+    }
+
+    pub async fn crash(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+    ) -> Result<String, ApiError> {
+        let crash = CrashRepo::get_for_user(&state.db, id, access.user_id, access.is_admin).await?;
+        if !access.allows(crash.product_id) {
+            return Err(ApiError::AccessDenied);
+        }
+        Ok(serde_json::json!({ "result": "ok", "payload": crash }).to_string())
+    }
+
+    /// Returns the issues, the most recently seen first.
+    pub async fn issues(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<ReadParams>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Issue::find();
+        if let Some(product_id) = params.product {
+            query = query.filter(entity::issue::Column::ProductId.eq(product_id));
+        }
+        if let Some(products) = access.products {
+            query = query.filter(entity::issue::Column::ProductId.is_in(products));
+        }
+        let issues = query
+            .order_by_desc(entity::issue::Column::LastSeen)
+            .offset(params.offset.unwrap_or(0))
+            .limit(params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": issues }).to_string())
+    }
+
+    /// Returns the issue with the ids of its most recent crashes. The crashes themselves are
+    /// served by `/crash?issue=<id>`.
+    pub async fn issue(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+    ) -> Result<String, ApiError> {
+        let issue = entity::prelude::Issue::find_by_id(id)
+            .one(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("issue".to_owned(), id.to_string()))?;
+        if !access.allows(issue.product_id) {
+            return Err(ApiError::AccessDenied);
+        }
+
+        let crashes = entity::prelude::Crash::find()
+            .select_only()
+            .column(entity::crash::Column::Id)
+            .filter(entity::crash::Column::IssueId.eq(id))
+            .order_by_desc(entity::crash::Column::CreatedAt)
+            .limit(DEFAULT_LIMIT)
+            .into_tuple::<Uuid>()
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": issue, "crashes": crashes }).to_string())
+    }
+
+    /// Exports a crash for external analysis tools: all its columns, the report with the
+    /// product's redaction rules applied, its annotations and the metadata of its attachments.
+    /// The minidump itself is served by `crash_minidump`.
+    pub async fn crash_export(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+    ) -> Result<String, ApiError> {
+        let mut crash = entity::prelude::Crash::find_by_id(id)
+            .one(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+        if !access.allows(crash.product_id) {
+            return Err(ApiError::AccessDenied);
+        }
+        CrashRepo::redaction_rules(&state.db, crash.product_id, access.user_id, access.is_admin)
+            .await?
+            .apply(&mut crash.report);
+
+        let annotations = entity::prelude::Annotation::find()
+            .filter(entity::annotation::Column::CrashId.eq(id))
+            .order_by_asc(entity::annotation::Column::Key)
+            .all(&state.db)
+            .await?;
+        let attachments: Vec<ExportedAttachment> = entity::prelude::Attachment::find()
+            .filter(entity::attachment::Column::CrashId.eq(id))
+            .order_by_asc(entity::attachment::Column::CreatedAt)
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(ExportedAttachment::from)
+            .collect();
+        Ok(serde_json::json!({
+            "result": "ok",
+            "payload": {
+                "crash": crash,
+                "annotations": annotations,
+                "attachments": attachments,
+            },
+        })
+        .to_string())
+    }
+
+    /// Serves the minidump of a crash as it was uploaded. Minidumps that are stored compressed
+    /// are sent as is, with `Content-Encoding: zstd`, to clients that accept zstd, and are
+    /// decompressed for other clients.
+    pub async fn crash_minidump(
+        Path(id): Path<Uuid>,
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        headers: HeaderMap,
+    ) -> Result<Response, ApiError> {
+        let crash = entity::prelude::Crash::find_by_id(id)
+            .one(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("crash".to_owned(), id.to_string()))?;
+        if !access.allows(crash.product_id) {
+            return Err(ApiError::AccessDenied);
+        }
+        let minidump = MinidumpApi::stored_minidump(&state.db, id).await?;
+
+        let path = std::path::Path::new(&minidump.filename);
+        let response_headers = [
+            (header::CONTENT_TYPE, minidump.mime_type.clone()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"crash-{}.dmp\"", id),
+            ),
+        ];
+        let stream = match minidump.compression.as_deref() {
+            None => true,
+            Some(compression::ZSTD) => accepts_zstd(&headers),
+            Some(_) => false,
+        };
+        if !stream {
+            let content = compression::read_file(path, minidump.compression.as_deref()).await?;
+            return Ok((response_headers, content).into_response());
+        }
+
+        let file = tokio::fs::File::open(path).await?;
+        let body = Body::from_stream(ReaderStream::new(file));
+        let mut response = (response_headers, body).into_response();
+        if minidump.compression.is_some() {
+            response.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                header::HeaderValue::from_static(compression::ZSTD),
+            );
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_zstd() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_zstd(&headers));
+        headers.insert(header::ACCEPT_ENCODING, "gzip, br".parse().unwrap());
+        assert!(!accepts_zstd(&headers));
+        headers.insert(header::ACCEPT_ENCODING, "gzip, zstd;q=0.8".parse().unwrap());
+        assert!(accepts_zstd(&headers));
+        headers.insert(header::ACCEPT_ENCODING, "zstd; q=0".parse().unwrap());
+        assert!(!accepts_zstd(&headers));
+    }
+}
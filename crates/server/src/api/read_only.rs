@@ -0,0 +1,40 @@
+use axum::extract::Request;
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use super::error::ApiError;
+use crate::settings;
+
+// A read-only mirror, e.g. on a replica database, serves browsing and exports but no changes.
+// Requests that may change data are rejected before they reach the handlers, so that uploads
+// and management calls fail with an explanation instead of a database error. Server functions
+// of the web interface check the setting themselves.
+
+/// Returns whether a request with this method may change data.
+fn is_write(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+pub async fn reject_writes(request: Request, next: Next) -> Result<Response, ApiError> {
+    if settings().server.read_only && is_write(request.method()) {
+        return Err(ApiError::ReadOnly);
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_write() {
+        assert!(!is_write(&Method::GET));
+        assert!(!is_write(&Method::HEAD));
+        assert!(!is_write(&Method::OPTIONS));
+        assert!(is_write(&Method::POST));
+        assert!(is_write(&Method::PUT));
+        assert!(is_write(&Method::PATCH));
+        assert!(is_write(&Method::DELETE));
+    }
+}
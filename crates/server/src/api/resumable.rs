@@ -0,0 +1,458 @@
+use axum::body::Bytes;
+use axum::extract::{ConnectInfo, Extension, Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::Json;
+use jwt_authorizer::{JwtClaims, RegisteredClaims};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::content_type::check_content_type;
+use super::error::ApiError;
+use super::metrics::metrics;
+use super::minidump::{
+    ClientHints, MinidumpApi, MinidumpRequestParams, Provenance, UploadCredential,
+};
+use super::quarantine::{check_quarantine, record_upload_error, upload_sources};
+use crate::app_state::AppState;
+use crate::model::job_lease::JobLeaseRepo;
+use crate::model::product::UploadKind;
+use crate::settings;
+
+// Resumable minidump uploads for clients on unreliable connections:
+//
+//   POST /minidump/resumable?product=..&version=..   starts an upload, returns `upload_id`
+//   PUT  /minidump/resumable/{upload_id}             appends a chunk, with
+//                                                    `Content-Range: bytes {start}-{end}/{total}`
+//   GET  /minidump/resumable/{upload_id}             returns the offset to continue from
+//
+// A SHA-256 checksum of the minidump can be sent with `X-Guardrail-SHA256` when starting the
+// upload; it is verified once the last chunk has been received.
+//
+// Chunks must be sent in order; a chunk that does not start at the current offset is rejected
+// with 409 and the client resumes from the offset returned by GET. Only the credential that
+// started an upload may continue it. When the last chunk arrives, the minidump is moved to its
+// storage location, counted against the daily quota of the product and processed like a regular
+// upload.
+//
+// Uploads in progress are kept in the `staging` directory below the base path, as a data file
+// and a JSON file with the upload parameters. Uploads that are not finished within
+// `ingest.resumable_upload_lifetime` are removed by the staging cleanup job. A chunk is only
+// received while holding a lease on the upload, so that concurrent chunks for one upload are
+// rejected, also when they reach different replicas.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StagedUpload {
+    pub product: String,
+    pub version: String,
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+    pub user_agent: Option<String>,
+    pub sdk: Option<String>,
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Credential that started the upload, see `UploadCredential::describe`.
+    #[serde(default)]
+    pub owner: Option<String>,
+    pub created_at: i64,
+}
+
+impl StagedUpload {
+    pub fn expires_at(&self) -> i64 {
+        self.created_at + settings().ingest.resumable_upload_lifetime as i64
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumableUploadResponse {
+    pub result: String,
+    pub upload_id: Uuid,
+    pub offset: u64,
+    pub expires_at: i64,
+    /// Id of the crash, once the last chunk has been received.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crash_id: Option<Uuid>,
+}
+
+pub fn staging_dir() -> PathBuf {
+    std::path::Path::new(&settings().server.base_path).join("staging")
+}
+
+pub fn data_path(id: Uuid) -> PathBuf {
+    staging_dir().join(id.to_string())
+}
+
+pub fn metadata_path(id: Uuid) -> PathBuf {
+    staging_dir().join(format!("{}.json", id))
+}
+
+/// Parses `bytes {start}-{end}/{total}`. Returns the start, the exclusive end and the total.
+pub fn parse_content_range(value: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end, total): (u64, u64, u64) =
+        (start.parse().ok()?, end.parse().ok()?, total.parse().ok()?);
+    (start <= end && end < total).then_some((start, end + 1, total))
+}
+
+/// Lease on an upload while a chunk is received, released when the guard is dropped. The lease
+/// outlives the processing of the last chunk, and expires when the replica stops.
+struct ActiveGuard {
+    state: AppState,
+    lease: String,
+    holder: String,
+}
+
+impl ActiveGuard {
+    async fn acquire(state: &AppState, id: Uuid) -> Result<Self, ApiError> {
+        let lease = format!("upload/{}", id);
+        let holder = Uuid::new_v4().to_string();
+        let duration =
+            chrono::Duration::seconds(2 * settings().ingest.processing_timeout as i64 + 60);
+        if !JobLeaseRepo::acquire(&state.db, &lease, &holder, duration).await? {
+            return Err(ApiError::UploadConflict(
+                "another chunk is being received".to_owned(),
+            ));
+        }
+        Ok(Self {
+            state: state.clone(),
+            lease,
+            holder,
+        })
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let db = self.state.db.clone();
+        let lease = std::mem::take(&mut self.lease);
+        let holder = std::mem::take(&mut self.holder);
+        tokio::spawn(async move {
+            if let Err(e) = JobLeaseRepo::release(&db, &lease, &holder).await {
+                error!("failed to release lease {}: {:?}", lease, e);
+            }
+        });
+    }
+}
+
+/// Returns the credential of the request, as stored in `StagedUpload::owner`.
+fn owner(
+    credential: Option<Extension<UploadCredential>>,
+    claims: Option<JwtClaims<RegisteredClaims>>,
+    headers: &HeaderMap,
+) -> Option<String> {
+    Provenance::from_request(credential, claims, headers, None)
+        .credential
+        .as_ref()
+        .map(UploadCredential::describe)
+}
+
+async fn load(
+    id: Uuid,
+    params: &ResumeParams,
+    owner: &Option<String>,
+) -> Result<StagedUpload, ApiError> {
+    let not_found = || ApiError::ForeignKeyError("upload".to_owned(), id.to_string());
+    let metadata = match tokio::fs::read(metadata_path(id)).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(e.into()),
+    };
+    let upload: StagedUpload = serde_json::from_slice(&metadata)?;
+    if upload.expires_at() < chrono::Utc::now().timestamp() {
+        return Err(not_found());
+    }
+    if params
+        .product
+        .as_ref()
+        .is_some_and(|product| *product != upload.product)
+        || upload.owner != *owner
+    {
+        return Err(ApiError::AccessDenied);
+    }
+    Ok(upload)
+}
+
+async fn offset(id: Uuid) -> Result<u64, ApiError> {
+    Ok(tokio::fs::metadata(data_path(id)).await?.len())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResumeParams {
+    /// Set by clients that authenticate per product, e.g. with an upload token.
+    #[serde(default)]
+    pub product: Option<String>,
+}
+
+pub struct ResumableUploadApi;
+
+impl ResumableUploadApi {
+    pub async fn create(
+        State(state): State<AppState>,
+        Query(params): Query<MinidumpRequestParams>,
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+        headers: HeaderMap,
+    ) -> Result<Json<ResumableUploadResponse>, ApiError> {
+        let product = MinidumpApi::get_product(&state, &params).await?;
+        let version =
+            MinidumpApi::get_or_new_version(&state, &product, &params, &BTreeMap::new()).await?;
+        MinidumpApi::check_build_age(&product, &version)?;
+        MinidumpApi::check_accepting_crashes(&product)?;
+        MinidumpApi::check_ingestion_window(&product)?;
+        let environment = MinidumpApi::check_environment(&product, &params)?;
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        check_content_type(&product, UploadKind::Minidump, content_type)?;
+
+        let client = ClientHints::from_headers(&headers);
+        let upload = StagedUpload {
+            product: params.product,
+            version: params.version,
+            channel: params.channel,
+            environment,
+            user_agent: client.user_agent,
+            sdk: client.sdk,
+            checksum: client.checksum,
+            owner: owner(credential, claims, &headers),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        let id = Uuid::new_v4();
+        tokio::fs::create_dir_all(staging_dir()).await?;
+        tokio::fs::write(data_path(id), b"").await?;
+        tokio::fs::write(metadata_path(id), serde_json::to_vec(&upload)?).await?;
+
+        info!("started resumable upload {} for {}", id, upload.product);
+        Ok(Json(ResumableUploadResponse {
+            result: "ok".to_owned(),
+            upload_id: id,
+            offset: 0,
+            expires_at: upload.expires_at(),
+            crash_id: None,
+        }))
+    }
+
+    pub async fn status(
+        Path(id): Path<Uuid>,
+        Query(params): Query<ResumeParams>,
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+        headers: HeaderMap,
+    ) -> Result<Json<ResumableUploadResponse>, ApiError> {
+        let upload = load(id, &params, &owner(credential, claims, &headers)).await?;
+        Ok(Json(ResumableUploadResponse {
+            result: "ok".to_owned(),
+            upload_id: id,
+            offset: offset(id).await?,
+            expires_at: upload.expires_at(),
+            crash_id: None,
+        }))
+    }
+
+    pub async fn append(
+        State(state): State<AppState>,
+        Path(id): Path<Uuid>,
+        Query(params): Query<ResumeParams>,
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+        peer: Option<ConnectInfo<SocketAddr>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Json<ResumableUploadResponse>, ApiError> {
+        let provenance = Provenance::from_request(credential, claims, &headers, peer);
+        let owner = provenance
+            .credential
+            .as_ref()
+            .map(UploadCredential::describe);
+        let upload = load(id, &params, &owner).await?;
+        let _guard = ActiveGuard::acquire(&state, id).await?;
+
+        let range = headers
+            .get(header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| ApiError::InvalidContentRange("missing".to_owned()))?;
+        let (start, end, total) = parse_content_range(range)
+            .ok_or_else(|| ApiError::InvalidContentRange(range.to_owned()))?;
+        if end - start != body.len() as u64 {
+            return Err(ApiError::InvalidContentRange(
+                "range does not match the size of the body".to_owned(),
+            ));
+        }
+        if total > settings().ingest.max_upload_size as u64 {
+            return Err(ApiError::UploadRejected("minidump is too large".to_owned()));
+        }
+
+        let current = offset(id).await?;
+        if start != current {
+            return Err(ApiError::UploadConflict(format!(
+                "chunk starts at {}, expected {}",
+                start, current
+            )));
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(data_path(id))
+            .await?;
+        file.write_all(&body).await?;
+        file.flush().await?;
+
+        if end < total {
+            return Ok(Json(ResumableUploadResponse {
+                result: "ok".to_owned(),
+                upload_id: id,
+                offset: end,
+                expires_at: upload.expires_at(),
+                crash_id: None,
+            }));
+        }
+
+        let params = MinidumpRequestParams {
+            product: upload.product.clone(),
+            version: upload.version.clone(),
+            channel: upload.channel.clone(),
+            environment: upload.environment.clone(),
+        };
+        let sources = upload_sources(&provenance);
+        metrics().record_upload();
+        check_quarantine(&state, &sources)
+            .await
+            .inspect_err(|e| metrics().record_rejection(e))?;
+        let product = MinidumpApi::get_product(&state, &params).await?;
+        let version =
+            MinidumpApi::get_or_new_version(&state, &product, &params, &BTreeMap::new()).await?;
+        MinidumpApi::check_minidump_size(&product, total)?;
+        MinidumpApi::check_daily_quota(&state, &product).await?;
+
+        let minidump_file =
+            MinidumpApi::get_minidump_file(&product.name, &format!("{}.dmp", id)).await?;
+        tokio::fs::rename(data_path(id), &minidump_file).await?;
+        tokio::fs::remove_file(metadata_path(id)).await?;
+
+        let client = ClientHints {
+            user_agent: upload.user_agent.clone(),
+            sdk: upload.sdk.clone(),
+            checksum: upload.checksum.clone(),
+            // The upload is received with its last chunk.
+            ..ClientHints::from_headers(&headers)
+        };
+        let ingested = MinidumpApi::ingest(
+            &state,
+            &product,
+            &version,
+            upload.environment.clone(),
+            minidump_file,
+            &client,
+            &provenance,
+        )
+        .await;
+        let (crash_id, created) = match ingested {
+            Ok(ingested) => ingested,
+            Err(e) => {
+                metrics().record_rejection(&e);
+                record_upload_error(&state, &sources, product.id, &e).await;
+                return Err(e);
+            }
+        };
+        if created {
+            MinidumpApi::store_annotations(crash_id, &product, BTreeMap::new(), &state).await?;
+        }
+
+        info!("finished resumable upload {}: crash {}", id, crash_id);
+        Ok(Json(ResumableUploadResponse {
+            result: "ok".to_owned(),
+            upload_id: id,
+            offset: total,
+            expires_at: upload.expires_at(),
+            crash_id: Some(crash_id),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::base::tests::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/1000"), Some((0, 100, 1000)));
+        assert_eq!(
+            parse_content_range("bytes 900-999/1000"),
+            Some((900, 1000, 1000))
+        );
+        assert_eq!(parse_content_range("bytes 0-0/1"), Some((0, 1, 1)));
+
+        assert_eq!(parse_content_range("bytes 0-1000/1000"), None);
+        assert_eq!(parse_content_range("bytes 10-5/1000"), None);
+        assert_eq!(parse_content_range("bytes 0-99/*"), None);
+        assert_eq!(parse_content_range("items 0-99/1000"), None);
+        assert_eq!(parse_content_range("bytes 0-99"), None);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_resumable_upload() {
+        let server = run_server().await;
+        server
+            .post("/api/product")
+            .json(&serde_json::json!({ "name": "Workrave" }))
+            .await
+            .assert_status_ok();
+        server
+            .post("/api/version")
+            .json(&serde_json::json!({
+                "name": "1.11", "hash": "1234567890", "tag": "v1.11", "product": "Workrave"
+            }))
+            .await
+            .assert_status_ok();
+
+        let response = server
+            .post("/api/minidump/resumable?product=Workrave&version=1.11")
+            .await;
+        response.assert_status_ok();
+        let upload = response.json::<serde_json::Value>();
+        assert_eq!(upload["offset"], 0);
+        let path = format!(
+            "/api/minidump/resumable/{}",
+            upload["upload_id"].as_str().unwrap()
+        );
+
+        let response = server
+            .put(&path)
+            .add_header(header::CONTENT_RANGE, "bytes 0-3/10".parse().unwrap())
+            .bytes(Bytes::from_static(b"MDMP"))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["offset"], 4);
+
+        // Resending a chunk that was already received is rejected.
+        let response = server
+            .put(&path)
+            .add_header(header::CONTENT_RANGE, "bytes 0-3/10".parse().unwrap())
+            .bytes(Bytes::from_static(b"MDMP"))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+
+        let response = server.get(&path).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["offset"], 4);
+
+        let response = server
+            .get(&format!("/api/minidump/resumable/{}", Uuid::new_v4()))
+            .await;
+        response.assert_status_not_found();
+
+        let id = Uuid::parse_str(upload["upload_id"].as_str().unwrap()).unwrap();
+        tokio::fs::remove_file(data_path(id)).await.unwrap();
+        tokio::fs::remove_file(metadata_path(id)).await.unwrap();
+    }
+}
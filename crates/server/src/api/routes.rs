@@ -1,9 +1,37 @@
 use app::settings::settings;
+use axum::extract::DefaultBodyLimit;
 use axum::routing::{delete, get, post, put};
-use axum::Router;
+use axum::{middleware, Router};
 use jwt_authorizer::{Authorizer, IntoLayer, JwtAuthorizer, RegisteredClaims, Validation};
+use tower_http::decompression::RequestDecompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
 
-use super::{minidump::MinidumpApi, symbols::SymbolsApi};
+use super::{
+    attachment::AttachmentApi,
+    cache::cache_response,
+    client_diagnostics::ClientDiagnosticsApi,
+    crash::CrashApi,
+    crash_feed::CrashFeedApi,
+    export::ExportApi,
+    live::LiveApi,
+    load_shed::shed_load,
+    meta::MetaApi,
+    metrics::MetricsApi,
+    minidump::MinidumpApi,
+    openapi::OpenApi,
+    product::ProductApi,
+    product_manage::{verify_manage_token, ProductManageApi},
+    public::{verify_public_submission, PublicApi},
+    quarantine::QuarantineApi,
+    read::{verify_api_token, ReadApi},
+    resumable::ResumableUploadApi,
+    signature::verify_signature,
+    stats::StatsApi,
+    symbols::SymbolsApi,
+    upload_token::{upload_tokens_enabled, verify_upload_token, UploadTokenApi},
+    v1::{verify_admin_token, DeviceKeysV1Api, TokensV1Api, VersionsV1Api},
+    version::VersionApi,
+};
 use crate::entity::prelude;
 use crate::{api::base::Api, app_state::AppState};
 
@@ -17,21 +45,197 @@ pub async fn routes() -> Router<AppState> {
             .await
             .unwrap();
 
-    routes_api()
-        .await
-        .route("/minidump/upload", post(MinidumpApi::upload))
-        .layer(auth.into_layer())
+    let mut router = routes_api().await.merge(minidump_routes());
+    if upload_tokens_enabled() {
+        router = router.route("/upload/token", post(UploadTokenApi::issue));
+    }
+    router.layer(auth.into_layer())
+}
+
+/// Accepts request bodies compressed with gzip or zstd, as sent by e.g. Crashpad. The compressed
+/// body is limited to `max_upload_size` and the decompressed body to `max_decompressed_size`.
+fn with_decompression(router: Router<AppState>) -> Router<AppState> {
+    let ingest = &settings().ingest;
+    router
+        .layer(DefaultBodyLimit::max(ingest.max_decompressed_size))
+        .layer(RequestDecompressionLayer::new().no_br().no_deflate())
+        .layer(RequestBodyLimitLayer::new(ingest.max_upload_size))
+}
+
+/// Rejects uploads before their body is read when the server is under pressure, see `shed_load`.
+fn with_load_shedding(router: Router<AppState>) -> Router<AppState> {
+    router.layer(middleware::from_fn(shed_load))
+}
+
+/// Minidump upload routes shared by all ways of authenticating. Only single request uploads
+/// accept a compressed body; chunks of resumable uploads are stored as they are sent. Compressed
+/// minidumps are recognized either way, see `MinidumpApi::ingest`.
+fn minidump_routes() -> Router<AppState> {
+    with_load_shedding(with_decompression(
+        Router::new().route("/minidump/upload", post(MinidumpApi::upload)),
+    ))
+    .route("/minidump/resumable", post(ResumableUploadApi::create))
+    .route(
+        "/minidump/resumable/:id",
+        get(ResumableUploadApi::status).put(ResumableUploadApi::append),
+    )
+}
+
+/// Routes for devices that sign their requests with a device key instead of using a JWT.
+pub fn signed_routes(state: AppState) -> Router<AppState> {
+    let mut router = minidump_routes().route("/versions", post(VersionApi::create_signed));
+    if upload_tokens_enabled() {
+        router = router.route("/token", post(UploadTokenApi::issue));
+    }
+    router.layer(middleware::from_fn_with_state(state, verify_signature))
+}
+
+/// Routes for clients that use a short-lived upload token, see `UploadTokenApi::issue`. Returns
+/// `None` when upload tokens are not enabled.
+pub fn upload_routes() -> Option<Router<AppState>> {
+    upload_tokens_enabled()
+        .then(|| minidump_routes().layer(middleware::from_fn(verify_upload_token)))
+}
+
+/// Routes for products that accept crashes without a token, see `PublicApi`. Only single
+/// request uploads are accepted.
+pub fn public_routes(state: AppState) -> Router<AppState> {
+    with_load_shedding(with_decompression(
+        Router::new().route("/minidump/upload", post(MinidumpApi::upload)),
+    ))
+    .layer(middleware::from_fn_with_state(
+        state,
+        verify_public_submission,
+    ))
+    .route("/challenge", get(PublicApi::challenge))
+}
+
+/// Read-only routes for scripts, authenticated by a personal API token.
+pub fn read_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/product", get(ReadApi::products))
+        .route("/version", get(ReadApi::versions))
+        .route("/crash", get(ReadApi::crashes))
+        .route("/crash/:id", get(ReadApi::crash))
+        .route("/issue", get(ReadApi::issues))
+        .route("/issue/:id", get(ReadApi::issue))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            cache_response,
+        ))
+        .layer(middleware::from_fn_with_state(state, verify_api_token))
+}
+
+/// Export of crashes for external analysis tools, the stream of processed crashes and crash
+/// statistics for dashboards and the report of missing symbols, authenticated by a personal API
+/// token like the read API. Merged like `meta_routes`.
+pub fn export_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/crashes/stream", get(CrashFeedApi::stream))
+        .route("/api/crashes/:id", get(ReadApi::crash_export))
+        .route("/api/crashes/:id/minidump", get(ReadApi::crash_minidump))
+        .route("/api/stats/crashes", get(StatsApi::crashes))
+        .route("/api/symbols/missing", get(SymbolsApi::missing))
+        .layer(middleware::from_fn_with_state(state, verify_api_token))
+}
+
+/// Products API for automation, authenticated by a personal API token. Merged like
+/// `meta_routes`.
+pub fn product_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/products",
+            get(ProductManageApi::list).post(ProductManageApi::create),
+        )
+        .route(
+            "/api/products/:id",
+            get(ProductManageApi::get)
+                .patch(ProductManageApi::update)
+                .delete(ProductManageApi::remove),
+        )
+        .layer(middleware::from_fn_with_state(state, verify_manage_token))
+}
+
+/// Versioned REST API for provisioning, authenticated by an API token of an administrator. Merged
+/// like `product_routes`.
+pub fn v1_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/v1/products",
+            get(ProductManageApi::list).post(ProductManageApi::create),
+        )
+        .route(
+            "/api/v1/products/:id",
+            get(ProductManageApi::get)
+                .patch(ProductManageApi::update)
+                .delete(ProductManageApi::remove),
+        )
+        .route(
+            "/api/v1/versions",
+            get(VersionsV1Api::list).post(VersionsV1Api::create),
+        )
+        .route(
+            "/api/v1/versions/:id",
+            get(VersionsV1Api::get)
+                .patch(VersionsV1Api::update)
+                .delete(VersionsV1Api::remove),
+        )
+        .route(
+            "/api/v1/tokens",
+            get(TokensV1Api::list).post(TokensV1Api::create),
+        )
+        .route("/api/v1/tokens/:id", delete(TokensV1Api::remove))
+        .route("/api/v1/device_keys", get(DeviceKeysV1Api::list))
+        .route("/api/v1/quarantines", get(QuarantineApi::list))
+        .route("/api/v1/quarantines/:id", delete(QuarantineApi::lift))
+        .route(
+            "/api/v1/crashes/:id/legal_hold",
+            put(CrashApi::place_legal_hold).delete(CrashApi::release_legal_hold),
+        )
+        .route("/api/v1/export/:entity", get(ExportApi::entity))
+        .layer(middleware::from_fn_with_state(state, verify_admin_token))
+}
+
+/// Routes that need no authentication. These are merged instead of nested, as `/api` is
+/// already nested with the JWT protected routes. Reports of failed uploads are small, so their
+/// body is limited to a few kilobytes. The OpenAPI document describes the routes authenticated by
+/// a personal API token, and must be updated with them.
+pub fn meta_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/meta", get(MetaApi::get))
+        .route("/api/openapi.json", get(OpenApi::get))
+        .route(
+            "/api/client-diagnostics",
+            post(ClientDiagnosticsApi::report).layer(DefaultBodyLimit::max(4096)),
+        )
+        .route("/metrics", get(MetricsApi::get))
+}
+
+/// Routes for browser downloads, authenticated by the web session instead of a JWT.
+pub fn download_routes() -> Router<AppState> {
+    Router::new()
+        .route("/symbols/:id", get(SymbolsApi::download))
+        .route("/crash/:id/pdf", get(CrashApi::download_pdf))
+        .route("/crash/:id/json", get(CrashApi::download_report))
+        .route("/attachment/:id", get(AttachmentApi::download))
+}
+
+/// Server-sent events for the web interface, authenticated by the session like the downloads.
+pub fn live_routes() -> Router<AppState> {
+    Router::new().route("/events", get(LiveApi::events))
 }
 
 #[cfg(test)]
 pub async fn routes_test() -> Router<AppState> {
-    routes_api()
-        .await
-        .route("/minidump/upload", post(MinidumpApi::upload))
+    routes_api().await.merge(minidump_routes())
 }
 
 async fn routes_api() -> Router<AppState> {
     Router::new()
+        // Alert
+        .route("/alert", get(Api::get_all::<prelude::Alert>))
+        .route("/alert/:id", get(Api::get_by_id::<prelude::Alert>))
+        .route("/alert/:id", delete(Api::remove_by_id::<prelude::Alert>))
         // Annotation
         .route("/annotation", post(Api::create::<prelude::Annotation>))
         .route("/annotation", get(Api::get_all::<prelude::Annotation>))
@@ -51,25 +255,49 @@ async fn routes_api() -> Router<AppState> {
             "/attachment/:id",
             get(Api::get_by_id::<prelude::Attachment>),
         )
-        .route(
-            "/attachment/:id",
-            delete(Api::remove_by_id::<prelude::Attachment>),
-        )
+        .route("/attachment/:id", delete(AttachmentApi::remove))
         .route("/attachment/:id", put(Api::update::<prelude::Attachment>))
         // Crash
         .route("/crash", post(Api::create::<prelude::Crash>))
         .route("/crash", get(Api::get_all::<prelude::Crash>))
         .route("/crash/:id", get(Api::get_by_id::<prelude::Crash>))
-        .route("/crash/:id", delete(Api::remove_by_id::<prelude::Crash>))
+        .route("/crash/:id", delete(CrashApi::remove))
         .route("/crash/:id", put(Api::update::<prelude::Crash>))
+        .route("/crash/:id/pdf", post(CrashApi::request_pdf))
+        .route("/crash/:id/reprocess", post(CrashApi::reprocess))
+        .route("/crash/:id/stack.txt", get(CrashApi::stack_text))
+        // DeviceKey
+        .route("/device_key", post(Api::create::<prelude::DeviceKey>))
+        .route("/device_key", get(Api::get_all::<prelude::DeviceKey>))
+        .route("/device_key/:id", get(Api::get_by_id::<prelude::DeviceKey>))
+        .route(
+            "/device_key/:id",
+            delete(Api::remove_by_id::<prelude::DeviceKey>),
+        )
+        .route("/device_key/:id", put(Api::update::<prelude::DeviceKey>))
+        // Issue
+        .route("/issue", get(Api::get_all::<prelude::Issue>))
+        .route("/issue/:id", get(Api::get_by_id::<prelude::Issue>))
+        // LinkTemplate
+        .route("/link_template", post(Api::create::<prelude::LinkTemplate>))
+        .route("/link_template", get(Api::get_all::<prelude::LinkTemplate>))
+        .route(
+            "/link_template/:id",
+            get(Api::get_by_id::<prelude::LinkTemplate>),
+        )
+        .route(
+            "/link_template/:id",
+            delete(Api::remove_by_id::<prelude::LinkTemplate>),
+        )
+        .route(
+            "/link_template/:id",
+            put(Api::update::<prelude::LinkTemplate>),
+        )
         // Product
         .route("/product", post(Api::create::<prelude::Product>))
-        .route("/product", get(Api::get_all::<prelude::Product>))
+        .route("/product", get(ProductApi::get_all))
         .route("/product/:id", get(Api::get_by_id::<prelude::Product>))
-        .route(
-            "/product/:id",
-            delete(Api::remove_by_id::<prelude::Product>),
-        )
+        .route("/product/:id", delete(ProductApi::remove_by_id))
         .route("/product/:id", put(Api::update::<prelude::Product>))
         // Symbols
         .route("/symbols", post(Api::create::<prelude::Symbols>))
@@ -80,15 +308,24 @@ async fn routes_api() -> Router<AppState> {
             delete(Api::remove_by_id::<prelude::Symbols>),
         )
         .route("/symbols/:id", put(Api::update::<prelude::Symbols>))
+        // Breakpad symbol server layout; the first segment is the module id, but shares the name
+        // of the parameter with the routes above.
+        .route("/symbols/:id/:build_id/:filename", get(SymbolsApi::fetch))
         // Version
         .route("/version", post(Api::create::<prelude::Version>))
         .route("/version", get(Api::get_all::<prelude::Version>))
         .route("/version/:id", get(Api::get_by_id::<prelude::Version>))
+        .route("/version/:id", delete(VersionApi::remove))
+        .route("/version/:id", put(Api::update::<prelude::Version>))
         .route(
-            "/version/:id",
-            delete(Api::remove_by_id::<prelude::Version>),
+            "/product/:id/versions",
+            post(VersionApi::create_for_product),
         )
-        .route("/version/:id", put(Api::update::<prelude::Version>))
         // Symbols
-        .route("/symbols/upload", post(SymbolsApi::upload))
+        .merge(with_load_shedding(with_decompression(
+            Router::new()
+                .route("/symbols/upload", post(SymbolsApi::upload))
+                .route("/symbols/upload-archive", post(SymbolsApi::upload_archive))
+                .route("/symbols/upload-native", post(SymbolsApi::upload_native)),
+        )))
 }
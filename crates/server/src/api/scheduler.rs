@@ -0,0 +1,243 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::settings;
+
+// Processing a minidump is CPU heavy, so each replica only processes a limited number of
+// minidumps at the same time. Uploads that have to wait are admitted round-robin per product,
+// and a product never uses more than its share of the slots, so that a burst of crashes of one
+// product cannot starve the others.
+
+#[derive(Debug, Default)]
+struct State {
+    running: usize,
+    per_product: HashMap<Uuid, usize>,
+    waiting: HashMap<Uuid, VecDeque<oneshot::Sender<()>>>,
+    /// Products with waiting uploads, in the order in which they get the next free slot.
+    order: VecDeque<Uuid>,
+}
+
+#[derive(Debug)]
+pub struct ProcessingScheduler {
+    slots: usize,
+    slots_per_product: usize,
+    state: Mutex<State>,
+}
+
+/// A processing slot; the slot is handed to the next waiting upload when the permit is dropped.
+pub struct ProcessingPermit<'a> {
+    scheduler: &'a ProcessingScheduler,
+    product: Uuid,
+}
+
+impl Drop for ProcessingPermit<'_> {
+    fn drop(&mut self) {
+        self.scheduler.release(self.product);
+    }
+}
+
+/// An upload waiting for a slot. Gives the slot back if the upload is cancelled after the slot
+/// was handed to it.
+struct Waiting<'a> {
+    scheduler: &'a ProcessingScheduler,
+    product: Uuid,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if receiver.try_recv().is_ok() {
+                self.scheduler.release(self.product);
+            }
+        }
+    }
+}
+
+impl ProcessingScheduler {
+    pub fn new(slots: usize, slots_per_product: usize) -> Self {
+        let slots = slots.max(1);
+        Self {
+            slots,
+            slots_per_product: slots_per_product.clamp(1, slots),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn has_room(&self, state: &State, product: Uuid) -> bool {
+        state.running < self.slots
+            && state.per_product.get(&product).copied().unwrap_or(0) < self.slots_per_product
+    }
+
+    fn start(state: &mut State, product: Uuid) {
+        state.running += 1;
+        *state.per_product.entry(product).or_default() += 1;
+    }
+
+    /// Waits for a slot to process a minidump of the product.
+    pub async fn acquire(&self, product: Uuid) -> ProcessingPermit<'_> {
+        let receiver = {
+            let mut state = self.lock();
+            if self.has_room(&state, product) && !state.waiting.contains_key(&product) {
+                Self::start(&mut state, product);
+                return ProcessingPermit {
+                    scheduler: self,
+                    product,
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.waiting.entry(product).or_default().push_back(sender);
+            if !state.order.contains(&product) {
+                state.order.push_back(product);
+            }
+            receiver
+        };
+
+        let mut waiting = Waiting {
+            scheduler: self,
+            product,
+            receiver: Some(receiver),
+        };
+        if let Some(receiver) = waiting.receiver.as_mut() {
+            // Senders are only dropped after handing over a slot or when the waiter is gone.
+            let _ = receiver.await;
+        }
+        waiting.receiver = None;
+        ProcessingPermit {
+            scheduler: self,
+            product,
+        }
+    }
+
+    /// Returns the number of minidumps being processed and of uploads waiting for a slot.
+    pub fn load(&self) -> (usize, usize) {
+        let state = self.lock();
+        (
+            state.running,
+            state.waiting.values().map(VecDeque::len).sum(),
+        )
+    }
+
+    fn release(&self, product: Uuid) {
+        let mut state = self.lock();
+        state.running -= 1;
+        if let Some(count) = state.per_product.get_mut(&product) {
+            *count -= 1;
+            if *count == 0 {
+                state.per_product.remove(&product);
+            }
+        }
+        self.dispatch(&mut state);
+    }
+
+    /// Hands free slots to waiting uploads, taking turns between products.
+    fn dispatch(&self, state: &mut State) {
+        let mut skipped = 0;
+        while state.running < self.slots && skipped < state.order.len() {
+            let Some(product) = state.order.pop_front() else {
+                break;
+            };
+            if !self.has_room(state, product) {
+                state.order.push_back(product);
+                skipped += 1;
+                continue;
+            }
+            let Some(queue) = state.waiting.get_mut(&product) else {
+                continue;
+            };
+            let sender = queue.pop_front();
+            if queue.is_empty() {
+                state.waiting.remove(&product);
+            } else {
+                state.order.push_back(product);
+            }
+            // Sending fails if the upload stopped waiting; the slot then goes to the next one.
+            if sender.is_some_and(|sender| sender.send(()).is_ok()) {
+                Self::start(state, product);
+                skipped = 0;
+            }
+        }
+    }
+}
+
+pub fn scheduler() -> &'static ProcessingScheduler {
+    static SCHEDULER: OnceLock<ProcessingScheduler> = OnceLock::new();
+    SCHEDULER.get_or_init(|| {
+        let ingest = &settings().ingest;
+        ProcessingScheduler::new(ingest.processing_slots, ingest.processing_slots_per_product)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[tokio::test]
+    async fn test_slots_per_product() {
+        let scheduler = ProcessingScheduler::new(2, 1);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let first = scheduler.acquire(a).now_or_never().unwrap();
+        let mut second = Box::pin(scheduler.acquire(a));
+        assert!(futures::poll!(second.as_mut()).is_pending());
+        assert_eq!(scheduler.load(), (1, 1));
+
+        // Product b still gets the free slot.
+        let other = scheduler.acquire(b).now_or_never().unwrap();
+
+        drop(first);
+        let second = second.now_or_never().unwrap();
+        drop(second);
+        drop(other);
+        assert_eq!(scheduler.lock().running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let scheduler = ProcessingScheduler::new(1, 1);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let running = scheduler.acquire(a).now_or_never().unwrap();
+        let mut a2 = Box::pin(scheduler.acquire(a));
+        let mut a3 = Box::pin(scheduler.acquire(a));
+        let mut b1 = Box::pin(scheduler.acquire(b));
+        assert!(futures::poll!(a2.as_mut()).is_pending());
+        assert!(futures::poll!(a3.as_mut()).is_pending());
+        assert!(futures::poll!(b1.as_mut()).is_pending());
+
+        drop(running);
+        let running = a2.now_or_never().unwrap();
+
+        // b waited less long than a3, but it is b's turn.
+        drop(running);
+        assert!(futures::poll!(a3.as_mut()).is_pending());
+        let running = b1.now_or_never().unwrap();
+
+        drop(running);
+        drop(a3.now_or_never().unwrap());
+        assert_eq!(scheduler.lock().running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled() {
+        let scheduler = ProcessingScheduler::new(1, 1);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let running = scheduler.acquire(a).now_or_never().unwrap();
+        let mut cancelled = Box::pin(scheduler.acquire(b));
+        assert!(futures::poll!(cancelled.as_mut()).is_pending());
+        drop(cancelled);
+
+        drop(running);
+        assert_eq!(scheduler.lock().running, 0);
+        drop(scheduler.acquire(b).now_or_never().unwrap());
+    }
+}
@@ -0,0 +1,227 @@
+use axum::body::Body;
+use axum::extract::{Query, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use hmac::{Hmac, Mac};
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::info;
+
+use super::error::ApiError;
+use super::minidump::UploadCredential;
+use crate::app_state::AppState;
+use crate::{entity, settings};
+
+// Signed requests carry the following headers:
+//
+//   X-Guardrail-Key        ID of the device key
+//   X-Guardrail-Timestamp  Unix time in seconds
+//   X-Guardrail-Nonce      Unique value per request
+//   X-Guardrail-Signature  hex(HMAC-SHA256(key, payload))
+//
+// with the payload "{timestamp}\n{nonce}\n{query}\n{hex(SHA256(body))}", where `query` is the
+// canonical query string: the query parameters sorted by name and value and form encoded, so
+// that the product, version and channel of a request are signed along with its body.
+//
+// The HMAC key is the SHA-256 digest of the device key, stored on the server as `key_hash` (hex
+// encoded). As it is the secret that signatures are checked with, it never leaves the server:
+// it is not serialized, and no API returns it.
+
+/// Kind of the credential of signed requests, see `Provenance::device_key`.
+pub const DEVICE_KEY_CREDENTIAL: &str = "device_key";
+
+const KEY_HEADER: &str = "x-guardrail-key";
+pub(super) const TIMESTAMP_HEADER: &str = "x-guardrail-timestamp";
+const NONCE_HEADER: &str = "x-guardrail-nonce";
+const SIGNATURE_HEADER: &str = "x-guardrail-signature";
+
+const MAX_CLOCK_SKEW: i64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Remembers recently used nonces so that a signed request cannot be replayed while its
+/// timestamp is still accepted.
+#[derive(Debug, Default)]
+pub struct NonceCache {
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl NonceCache {
+    /// Records the nonce and returns `false` if it was already used.
+    pub fn check_and_insert(&self, nonce: String, now: i64) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, timestamp| now - *timestamp <= 2 * MAX_CLOCK_SKEW);
+        if seen.contains_key(&nonce) {
+            return false;
+        }
+        seen.insert(nonce, now);
+        true
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedRequestParams {
+    pub product: String,
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, ApiError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::InvalidSignature(format!("missing {} header", name)))
+}
+
+/// Returns the query string with its parameters sorted by name and value, form encoded.
+pub fn canonical_query(query: Option<&str>) -> String {
+    let mut pairs: Vec<(String, String)> =
+        url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    pairs.sort();
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+pub fn signing_payload(timestamp: &str, nonce: &str, query: &str, body: &[u8]) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        timestamp,
+        nonce,
+        query,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+pub fn verify(key_hash: &str, payload: &str, signature: &str) -> bool {
+    let (Ok(key), Ok(signature)) = (hex::decode(key_hash), hex::decode(signature)) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(&key) else {
+        return false;
+    };
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+pub async fn verify_signature(
+    State(state): State<AppState>,
+    Query(params): Query<SignedRequestParams>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let (mut parts, body) = request.into_parts();
+    let headers = &parts.headers;
+
+    let key_id = uuid::Uuid::parse_str(header(headers, KEY_HEADER)?)
+        .map_err(|_| ApiError::InvalidSignature("invalid key".to_owned()))?;
+    let timestamp = header(headers, TIMESTAMP_HEADER)?;
+    let nonce = header(headers, NONCE_HEADER)?;
+    let signature = header(headers, SIGNATURE_HEADER)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let time = timestamp
+        .parse::<i64>()
+        .map_err(|_| ApiError::InvalidSignature("invalid timestamp".to_owned()))?;
+    if (now - time).abs() > MAX_CLOCK_SKEW {
+        return Err(ApiError::InvalidSignature("request expired".to_owned()));
+    }
+
+    let (key, product) = entity::prelude::DeviceKey::find_by_id(key_id)
+        .find_also_related(entity::prelude::Product)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::InvalidSignature("invalid key".to_owned()))?;
+    if product.map(|product| product.name) != Some(params.product) {
+        return Err(ApiError::InvalidSignature(
+            "key not valid for product".to_owned(),
+        ));
+    }
+
+    let body = axum::body::to_bytes(body, settings().ingest.max_upload_size)
+        .await
+        .map_err(|_| ApiError::InvalidSignature("unreadable body".to_owned()))?;
+    let query = canonical_query(parts.uri.query());
+    let payload = signing_payload(timestamp, nonce, &query, &body);
+    if !verify(&key.key_hash, &payload, signature) {
+        return Err(ApiError::InvalidSignature("signature mismatch".to_owned()));
+    }
+
+    if !state
+        .nonces
+        .check_and_insert(format!("{}:{}", key_id, nonce), now)
+    {
+        return Err(ApiError::InvalidSignature("nonce already used".to_owned()));
+    }
+
+    info!("accepted signed request from device key {}", key.name);
+    parts.extensions.insert(UploadCredential {
+        kind: DEVICE_KEY_CREDENTIAL,
+        id: Some(key.id.to_string()),
+        name: Some(key.name.clone()),
+    });
+    parts.extensions.insert(key);
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(device_key: &str, payload: &str) -> (String, String) {
+        let key = Sha256::digest(device_key.as_bytes());
+        let mut mac = HmacSha256::new_from_slice(&key).unwrap();
+        mac.update(payload.as_bytes());
+        (hex::encode(key), hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_canonical_query() {
+        assert_eq!(
+            canonical_query(Some("version=1.0&product=Work%20rave&channel=beta")),
+            "channel=beta&product=Work+rave&version=1.0"
+        );
+        assert_eq!(canonical_query(None), "");
+    }
+
+    #[test]
+    fn test_verify() {
+        let query = canonical_query(Some("product=Workrave&version=1.0"));
+        let payload = signing_payload("1700000000", "abc", &query, b"minidump");
+        let (key_hash, signature) = sign("secret", &payload);
+
+        assert!(verify(&key_hash, &payload, &signature));
+        assert!(!verify(
+            &key_hash,
+            &signing_payload("1700000000", "abc", &query, b"tampered"),
+            &signature
+        ));
+        assert!(!verify(
+            &key_hash,
+            &signing_payload(
+                "1700000000",
+                "abc",
+                &canonical_query(Some("product=Workrave&version=2.0")),
+                b"minidump"
+            ),
+            &signature
+        ));
+        assert!(!verify(&key_hash, &payload, "not-hex"));
+        let (other_hash, _) = sign("other", &payload);
+        assert!(!verify(&other_hash, &payload, &signature));
+    }
+
+    #[test]
+    fn test_nonce_cache() {
+        let cache = NonceCache::default();
+        assert!(cache.check_and_insert("key:1".to_owned(), 1000));
+        assert!(!cache.check_and_insert("key:1".to_owned(), 1001));
+        assert!(cache.check_and_insert("key:2".to_owned(), 1001));
+        // Expired entries are dropped; the timestamp check rejects such requests anyway.
+        assert!(cache.check_and_insert("key:1".to_owned(), 1000 + 3 * MAX_CLOCK_SKEW));
+    }
+}
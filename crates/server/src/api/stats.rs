@@ -0,0 +1,41 @@
+use axum::extract::{Extension, Query, State};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::error::ApiError;
+use super::read::ReadAccess;
+use crate::app_state::AppState;
+use crate::data_providers::stats::{parse_group_by, DEFAULT_DAYS, MAX_DAYS, TOP_SIGNATURES};
+use crate::model::stats::StatsRepo;
+
+#[derive(Debug, Deserialize)]
+pub struct CrashStatsParams {
+    /// Limits the statistics to a product.
+    pub product: Option<Uuid>,
+    /// Comma separated list of `day`, `version` and `channel`.
+    pub group_by: Option<String>,
+    /// Number of days covered, at most `MAX_DAYS`.
+    pub days: Option<i64>,
+}
+
+pub struct StatsApi;
+
+impl StatsApi {
+    /// Returns the number of crashes of the last days of the products the token owner may read,
+    /// grouped as requested, with the signatures of the most crashes.
+    pub async fn crashes(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<CrashStatsParams>,
+    ) -> Result<String, ApiError> {
+        let products = access.products_of(params.product)?;
+        let groups = parse_group_by(params.group_by.as_deref().unwrap_or_default())
+            .map_err(|group| ApiError::APIFailure(format!("cannot group by '{}'", group)))?;
+        let days = params.days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS);
+        let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(days);
+
+        let stats =
+            StatsRepo::crash_stats(&state.db, products, since, &groups, TOP_SIGNATURES).await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": stats }).to_string())
+    }
+}
@@ -1,25 +1,42 @@
 use super::base::NoneFilter;
 use super::base::Resource;
+use super::content_type::check_content_type;
 use super::error::ApiError;
+use super::read::{ReadAccess, MAX_LIMIT};
 use crate::app_state::AppState;
 use crate::model::base::Repo;
+use crate::model::missing_symbols::MissingSymbolsRepo;
+use crate::model::product::UploadKind;
+use crate::model::product_deletion::ProductDeletionRepo;
+use crate::model::role::VIEWER;
+use crate::model::symbol_conversion::{SymbolConversionCreateDto, STATUS_PENDING};
+use crate::model::symbols::{native_symbols_dir, quarantine_dir, symbols_dir, symbols_file};
+use crate::model::user::UserRepo;
 use crate::model::version::VersionRepo;
 use crate::settings;
+use crate::utils::archive;
+use crate::utils::compression;
+use crate::utils::symbol_check::check_symbols;
 use crate::{
     entity::{prelude::Symbols, symbols},
     model::symbols::{SymbolsCreateDto, SymbolsUpdateDto},
 };
-use axum::body::Bytes;
+use app::auth::AuthSession;
+use app::build_id::{normalize_debug_file, normalize_debug_id};
+use axum::body::{Body, Bytes};
 use axum::extract::multipart::Field;
-use axum::extract::{Multipart, Query, State};
+use axum::extract::{Extension, Multipart, Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use axum::{BoxError, Json};
 use futures::prelude::*;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::fs::{self, File};
 use tokio::io::{self, AsyncBufReadExt, BufReader, BufWriter};
-use tokio_util::io::StreamReader;
-use tracing::{error, info};
+use tokio_util::io::{ReaderStream, StreamReader};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 impl Resource for Symbols {
@@ -42,13 +59,77 @@ pub struct SymbolsResponse {
     pub result: String,
 }
 
+/// Outcome of one file of an uploaded symbols archive.
 #[derive(Debug, Serialize)]
-struct SymbolsData {
+pub struct ArchiveFileResult {
+    pub name: String,
+    pub result: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantine_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ArchiveFileResult {
+    fn failed(name: String, error: String) -> Self {
+        Self {
+            name,
+            result: "failed".to_owned(),
+            module_id: None,
+            build_id: None,
+            quarantine_reason: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveResponse {
+    pub result: String,
+    pub stored: usize,
+    pub failed: usize,
+    pub files: Vec<ArchiveFileResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NativeSymbolsResponse {
+    pub result: String,
+    /// Ids of the conversions of the uploaded files.
+    pub conversions: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MissingSymbolsParams {
+    /// Limits the report to a product.
+    pub product: Option<Uuid>,
+    pub limit: Option<u64>,
+}
+
+/// A module for which crashes of a product had no symbols.
+#[derive(Debug, Serialize)]
+pub struct MissingSymbolsEntry {
+    pub module_id: String,
+    pub build_id: String,
+    pub product_id: Uuid,
+    pub product: String,
+    /// Number of crashes without symbols for the module.
+    pub count: i32,
+    pub last_seen: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SymbolsData {
     pub os: String,
     pub arch: String,
     pub build_id: String,
     pub module_id: String,
     pub file_location: String,
+    pub file_size: i64,
+    pub quarantine_reason: Option<String>,
 }
 
 pub struct SymbolsApi;
@@ -95,6 +176,9 @@ impl SymbolsApi {
             }
         }
         .ok_or(ApiError::Failure)?;
+        if ProductDeletionRepo::is_scheduled(&state.db, product.id).await? {
+            return Err(ApiError::ProductDeleted(product.name));
+        }
         info!("product: {:?}", product.id);
         Ok(product)
     }
@@ -121,7 +205,7 @@ impl SymbolsApi {
         Ok(version)
     }
 
-    async fn get_temp_symbols_file() -> Result<PathBuf, ApiError> {
+    pub(crate) async fn get_temp_symbols_file() -> Result<PathBuf, ApiError> {
         let id = uuid::Uuid::new_v4();
 
         let upload_path = std::path::Path::new(&settings().server.base_path)
@@ -141,21 +225,48 @@ impl SymbolsApi {
         Ok(first_line)
     }
 
-    async fn process_symbol_file(symbol_file: &PathBuf) -> Result<SymbolsData, ApiError> {
+    pub(crate) async fn process_symbol_file(
+        symbol_file: &PathBuf,
+    ) -> Result<SymbolsData, ApiError> {
         let first_line = Self::get_header(symbol_file).await?;
 
         let collection: Vec<&str> = first_line.split_whitespace().collect();
+        if collection.len() < 5 || collection[0] != "MODULE" {
+            return Err(ApiError::UploadRejected(
+                "invalid symbols header".to_owned(),
+            ));
+        }
         let os = String::from(collection[1]);
         let arch = String::from(collection[2]);
-        let build_id = String::from(collection[3]);
-        let module_id = String::from(collection[4]);
+        let build_id = normalize_debug_id(collection[3])
+            .ok_or_else(|| ApiError::UploadRejected("invalid debug id".to_owned()))?;
+        let module_id = normalize_debug_file(collection[4]);
 
-        let final_path = std::path::Path::new(&settings().server.base_path)
-            .join("symbols")
-            .join(&module_id)
-            .join(&build_id);
-        tokio::fs::create_dir_all(&final_path).await?;
-        let final_file = final_path.join(module_id.replace(".pdb", ".sym"));
+        let path = symbol_file.clone();
+        let check = tokio::task::spawn_blocking(move || {
+            check_symbols(std::io::BufReader::new(std::fs::File::open(path)?))
+        })
+        .await
+        .map_err(|_| ApiError::Failure)??;
+        let quarantine_reason = check.quarantine_reason();
+        let dir = match &quarantine_reason {
+            Some(reason) => {
+                warn!(
+                    "quarantining symbols of {} {}: {}",
+                    module_id, build_id, reason
+                );
+                quarantine_dir()
+            }
+            None => symbols_dir(),
+        };
+
+        let final_file = symbols_file(&dir, &module_id, &build_id);
+        if let Some(parent) = final_file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        fs::rename(&symbol_file, &final_file).await?;
+        let file_size = fs::metadata(&final_file).await?.len() as i64;
 
         let r = SymbolsData {
             os,
@@ -163,35 +274,38 @@ impl SymbolsApi {
             build_id,
             module_id,
             file_location: final_file.to_str().unwrap_or("").to_string(),
+            file_size,
+            quarantine_reason,
         };
-
-        fs::rename(&symbol_file, &final_file).await?;
         Ok(r)
     }
 
-    async fn store(
+    /// Stores the symbols processed by `process_symbol_file` and returns their id. Symbols that
+    /// are not quarantined are no longer reported as missing.
+    pub(crate) async fn store(
+        db: &DatabaseConnection,
         data: SymbolsData,
-        product: crate::model::product::Product,
-        version: crate::model::version::Version,
-        state: &AppState,
-    ) -> Result<(), ApiError> {
+        product_id: Uuid,
+        version_id: Uuid,
+    ) -> Result<Uuid, ApiError> {
+        if data.quarantine_reason.is_none() {
+            MissingSymbolsRepo::resolve(db, &data.module_id, &data.build_id).await?;
+        }
         let dto = SymbolsCreateDto {
             os: data.os,
             arch: data.arch,
             build_id: data.build_id,
             module_id: data.module_id,
             file_location: data.file_location,
-            product_id: product.id,
-            version_id: version.id,
+            file_size: Some(data.file_size),
+            product_id,
+            version_id,
+            quarantine_reason: data.quarantine_reason,
         };
-        Repo::create(&state.db, dto)
-            .await
-            .map(|_| ())
-            .map_err(|e| {
-                error!("error: {:?}", e);
-                ApiError::Failure
-            })?;
-        Ok(())
+        Repo::create(db, dto).await.map_err(|e| {
+            error!("error: {:?}", e);
+            ApiError::Failure
+        })
     }
 
     async fn handle_symbol_upload(
@@ -200,15 +314,23 @@ impl SymbolsApi {
         field: Field<'_>,
     ) -> Result<(), ApiError> {
         info!("handle_symbol_upload");
-        let symbol_file = Self::get_temp_symbols_file().await?;
-
         let product = Self::get_product(state, params).await?;
         info!("product: {:?}", product);
         let version = Self::get_version(state, product.id, params).await?;
         info!("version : {:?}", version);
+        check_content_type(&product, UploadKind::Symbols, field.content_type())?;
+
+        let symbol_file = Self::get_temp_symbols_file().await?;
 
         Self::stream_to_file(&symbol_file, field).await?;
         info!("received symbol file: {:?}", symbol_file);
+        let max_size = settings().ingest.max_decompressed_size as u64;
+        if let Some(compression) = compression::decompress_upload(&symbol_file, max_size).await? {
+            info!(
+                "decompressed {} symbol file: {:?}",
+                compression, symbol_file
+            );
+        }
 
         let data = Self::process_symbol_file(&symbol_file).await?;
         info!(
@@ -216,7 +338,7 @@ impl SymbolsApi {
             symbol_file, data.build_id
         );
 
-        Self::store(data, product, version, state).await?;
+        Self::store(&state.db, data, product.id, version.id).await?;
         info!("stored symbol file: {:?}", symbol_file);
 
         Ok(())
@@ -245,4 +367,276 @@ impl SymbolsApi {
             result: "ok".to_string(),
         }))
     }
+
+    /// Validates and stores one symbol file extracted from an archive.
+    async fn store_archive_file(
+        state: &AppState,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        name: String,
+        symbol_file: &PathBuf,
+    ) -> ArchiveFileResult {
+        let data = match Self::process_symbol_file(symbol_file).await {
+            Ok(data) => data,
+            Err(e) => {
+                let _ = fs::remove_file(symbol_file).await;
+                return ArchiveFileResult::failed(name, e.to_string());
+            }
+        };
+        let mut result = ArchiveFileResult {
+            name,
+            result: "ok".to_owned(),
+            module_id: Some(data.module_id.clone()),
+            build_id: Some(data.build_id.clone()),
+            quarantine_reason: data.quarantine_reason.clone(),
+            error: None,
+        };
+        if let Err(e) = Self::store(&state.db, data, product.id, version.id).await {
+            result.result = "failed".to_owned();
+            result.error = Some(e.to_string());
+        }
+        result
+    }
+
+    async fn handle_archive_upload(
+        state: &AppState,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        field: Field<'_>,
+    ) -> Result<Vec<ArchiveFileResult>, ApiError> {
+        let archive_file = Self::get_temp_symbols_file().await?;
+        Self::stream_to_file(&archive_file, field).await?;
+
+        let max_size = settings().ingest.max_decompressed_size as u64;
+        let entries = match compression::decompress_upload(&archive_file, max_size).await {
+            Ok(_) => {
+                let dir = archive_file.with_extension("files");
+                fs::create_dir_all(&dir).await?;
+                let entries = archive::extract(&archive_file, &dir, max_size).await;
+                let _ = fs::remove_file(&archive_file).await;
+                entries
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&archive_file).await;
+                Err(e)
+            }
+        }?;
+
+        let mut results = vec![];
+        for entry in entries {
+            results.push(match entry.file {
+                Ok(symbol_file) => {
+                    Self::store_archive_file(state, product, version, entry.name, &symbol_file)
+                        .await
+                }
+                Err(e) => ArchiveFileResult::failed(entry.name, e),
+            });
+        }
+        let _ = fs::remove_dir_all(archive_file.with_extension("files")).await;
+        Ok(results)
+    }
+
+    /// Stores the Breakpad symbol files of a zip or tar archive, optionally compressed, such as
+    /// all symbols of a build. Each file is validated and stored on its own; the response lists
+    /// the outcome per file.
+    pub async fn upload_archive(
+        State(state): State<AppState>,
+        Query(params): Query<SymbolsRequestParams>,
+        mut multipart: Multipart,
+    ) -> Result<Json<ArchiveResponse>, ApiError> {
+        let product = Self::get_product(&state, &params).await?;
+        let version = Self::get_version(&state, product.id, &params).await?;
+
+        let mut files = vec![];
+        while let Some(field) = multipart.next_field().await? {
+            if field.name() == Some("archive") {
+                files.extend(Self::handle_archive_upload(&state, &product, &version, field).await?);
+            }
+        }
+        let stored = files.iter().filter(|file| file.result == "ok").count();
+        info!(
+            "stored {} of {} symbol files of an archive for {} {}",
+            stored,
+            files.len(),
+            product.name,
+            version.name
+        );
+        Ok(Json(ArchiveResponse {
+            result: "ok".to_owned(),
+            stored,
+            failed: files.len() - stored,
+            files,
+        }))
+    }
+
+    /// Stores an uploaded native debug file below `native_symbols_dir` and queues its
+    /// conversion. Returns the id of the conversion.
+    async fn handle_native_upload(
+        state: &AppState,
+        product: &crate::model::product::Product,
+        version: &crate::model::version::Version,
+        field: Field<'_>,
+    ) -> Result<Uuid, ApiError> {
+        check_content_type(product, UploadKind::Symbols, field.content_type())?;
+        let file_name = normalize_debug_file(field.file_name().unwrap_or_default());
+        if file_name.is_empty() || file_name == "." || file_name == ".." {
+            return Err(ApiError::UploadRejected("missing file name".to_owned()));
+        }
+
+        let dir = native_symbols_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).await?;
+        let native_file = dir.join(&file_name);
+        let stored = async {
+            Self::stream_to_file(&native_file, field).await?;
+            let max_size = settings().ingest.max_decompressed_size as u64;
+            compression::decompress_upload(&native_file, max_size).await?;
+            let file_size = fs::metadata(&native_file).await?.len() as i64;
+            let dto = SymbolConversionCreateDto {
+                file_name,
+                file_location: native_file.to_str().unwrap_or("").to_string(),
+                file_size,
+                status: STATUS_PENDING.to_owned(),
+                error: None,
+                symbols_id: None,
+                product_id: product.id,
+                version_id: version.id,
+            };
+            Ok::<Uuid, ApiError>(Repo::create(&state.db, dto).await?)
+        }
+        .await;
+        if stored.is_err() {
+            let _ = fs::remove_dir_all(&dir).await;
+        }
+        stored
+    }
+
+    /// Stores native debug files, such as PDB files or ELF files with DWARF debug info, and
+    /// converts them to Breakpad symbols in the background, see `jobs::symbol_conversion`. The
+    /// uploaded files are kept next to the converted symbols.
+    pub async fn upload_native(
+        State(state): State<AppState>,
+        Query(params): Query<SymbolsRequestParams>,
+        mut multipart: Multipart,
+    ) -> Result<Json<NativeSymbolsResponse>, ApiError> {
+        let product = Self::get_product(&state, &params).await?;
+        let version = Self::get_version(&state, product.id, &params).await?;
+
+        let mut conversions = vec![];
+        while let Some(field) = multipart.next_field().await? {
+            if field.name() == Some("upload_file_native") {
+                let id = Self::handle_native_upload(&state, &product, &version, field).await?;
+                info!(
+                    "queued conversion {} of a native debug file for {} {}",
+                    id, product.name, version.name
+                );
+                conversions.push(id);
+            }
+        }
+        Ok(Json(NativeSymbolsResponse {
+            result: "ok".to_owned(),
+            conversions,
+        }))
+    }
+
+    /// Serves a symbol file in the layout of Breakpad symbol servers,
+    /// `<module_id>/<build_id>/<name>.sym`, so that tools such as minidump-stackwalk can use
+    /// Guardrail as symbol source. Files are looked up by module and build id; quarantined files
+    /// are not served.
+    pub async fn fetch(
+        State(state): State<AppState>,
+        Path((module_id, build_id, filename)): Path<(String, String, String)>,
+    ) -> Result<Response, ApiError> {
+        let not_found = || {
+            ApiError::ForeignKeyError(
+                "symbols".to_owned(),
+                format!("{}/{}/{}", module_id, build_id, filename),
+            )
+        };
+        if !filename.ends_with(".sym") {
+            return Err(not_found());
+        }
+        let debug_id = normalize_debug_id(&build_id).ok_or_else(not_found)?;
+        let symbols = Symbols::find()
+            .filter(symbols::Column::ModuleId.eq(normalize_debug_file(&module_id)))
+            .filter(symbols::Column::BuildId.eq(debug_id))
+            .filter(symbols::Column::QuarantineReason.is_null())
+            .order_by_desc(symbols::Column::CreatedAt)
+            .one(&state.db)
+            .await?
+            .ok_or_else(not_found)?;
+
+        let file = File::open(&symbols.file_location).await?;
+        let size = file.metadata().await?.len();
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/plain".to_owned()),
+                (header::CONTENT_LENGTH, size.to_string()),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response())
+    }
+
+    /// Lists the modules for which crashes had no symbols, for the products the token owner may
+    /// read. The modules missing in the most crashes come first; uploading their symbols removes
+    /// them from the list.
+    pub async fn missing(
+        State(state): State<AppState>,
+        Extension(access): Extension<ReadAccess>,
+        Query(params): Query<MissingSymbolsParams>,
+    ) -> Result<String, ApiError> {
+        let products = access.products_of(params.product)?;
+        let limit = params.limit.unwrap_or(MAX_LIMIT).min(MAX_LIMIT);
+        let missing: Vec<MissingSymbolsEntry> =
+            MissingSymbolsRepo::list(&state.db, products, limit)
+                .await?
+                .into_iter()
+                .map(|(missing, product)| MissingSymbolsEntry {
+                    module_id: missing.module_id,
+                    build_id: missing.build_id,
+                    product_id: missing.product_id,
+                    product,
+                    count: missing.count,
+                    last_seen: missing.last_seen,
+                })
+                .collect();
+        Ok(serde_json::json!({ "result": "ok", "payload": missing }).to_string())
+    }
+
+    pub async fn download(
+        State(state): State<AppState>,
+        auth_session: AuthSession,
+        Path(id): Path<Uuid>,
+    ) -> Result<Response, ApiError> {
+        let user = auth_session.user.ok_or(ApiError::AccessDenied)?;
+
+        let symbols = Repo::get_by_id::<symbols::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("symbols".to_owned(), id.to_string()))?;
+
+        if !user.is_admin
+            && !UserRepo::has_role(&state.db, user.id, symbols.product_id, VIEWER).await?
+        {
+            return Err(ApiError::AccessDenied);
+        }
+
+        let file = File::open(&symbols.file_location).await?;
+        let filename = std::path::Path::new(&symbols.file_location)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("symbols.sym")
+            .to_owned();
+
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/plain".to_owned()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", filename),
+                ),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response())
+    }
 }
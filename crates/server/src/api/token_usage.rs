@@ -0,0 +1,106 @@
+use chrono::NaiveDateTime;
+use sea_orm::{DatabaseConnection, DbErr};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::model::device_key::DeviceKeyRepo;
+
+// Device keys count the crashes they submitted, so that administrators can confirm that a client
+// is actually submitting. Submissions are counted in memory and written in batches by `flush`,
+// so that uploads do not each update their key. Counts of a replica that stops before flushing
+// are lost.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingUsage {
+    pub submissions: i64,
+    pub last_used_at: NaiveDateTime,
+}
+
+#[derive(Debug, Default)]
+pub struct TokenUsage {
+    pending: Mutex<HashMap<Uuid, PendingUsage>>,
+}
+
+impl TokenUsage {
+    /// Counts a submission with the device key.
+    pub fn record(&self, key: Uuid, now: NaiveDateTime) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = pending.entry(key).or_insert(PendingUsage {
+            submissions: 0,
+            last_used_at: now,
+        });
+        usage.submissions += 1;
+        usage.last_used_at = usage.last_used_at.max(now);
+    }
+
+    /// Returns the submissions counted since the last call.
+    pub fn take(&self) -> HashMap<Uuid, PendingUsage> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *pending)
+    }
+
+    /// Adds submissions that could not be written back to the pending ones.
+    fn restore(&self, usages: impl Iterator<Item = (Uuid, PendingUsage)>) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, usage) in usages {
+            pending
+                .entry(key)
+                .and_modify(|pending| {
+                    pending.submissions += usage.submissions;
+                    pending.last_used_at = pending.last_used_at.max(usage.last_used_at);
+                })
+                .or_insert(usage);
+        }
+    }
+
+    /// Writes the counted submissions to their device keys. Returns the number of keys updated.
+    /// On failure, the submissions that were not written are kept for the next flush.
+    pub async fn flush(&self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let mut pending = self.take().into_iter();
+        let mut updated = 0;
+        while let Some((key, usage)) = pending.next() {
+            let result =
+                DeviceKeyRepo::record_submissions(db, key, usage.submissions, usage.last_used_at)
+                    .await;
+            if let Err(e) = result {
+                self.restore(std::iter::once((key, usage)).chain(pending));
+                return Err(e);
+            }
+            updated += 1;
+        }
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let usage = TokenUsage::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = chrono::Utc::now().naive_utc();
+        let later = now + chrono::Duration::seconds(5);
+
+        usage.record(a, later);
+        usage.record(a, now);
+        usage.record(b, now);
+
+        let pending = usage.take();
+        assert_eq!(
+            pending[&a],
+            PendingUsage {
+                submissions: 2,
+                last_used_at: later
+            }
+        );
+        assert_eq!(pending[&b].submissions, 1);
+        assert!(usage.take().is_empty());
+
+        usage.record(a, now);
+        usage.restore(pending.into_iter());
+        assert_eq!(usage.take()[&a].submissions, 3);
+    }
+}
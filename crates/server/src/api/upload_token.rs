@@ -0,0 +1,209 @@
+use axum::extract::{Extension, Query, Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jwt_authorizer::{JwtClaims, RegisteredClaims};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use super::error::ApiError;
+use super::minidump::UploadCredential;
+use crate::app_state::AppState;
+use crate::model::base::Repo;
+use crate::{entity, settings};
+
+// Clients authenticate once, with their JWT or a device key signature, and exchange that for a
+// short-lived token that only allows uploading crashes for a single product. Upload tokens are
+// self-contained, so checking them does not need the database. Each token has an id and names
+// the credential it was issued to, which are recorded in the provenance of the crashes uploaded
+// with it.
+
+const AUDIENCE: &str = "guardrail-upload";
+const SCOPE: &str = "minidump:upload";
+const LEEWAY: u64 = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadClaims {
+    pub aud: String,
+    /// Name of the product the token is valid for.
+    pub sub: String,
+    pub scope: String,
+    pub iat: i64,
+    pub exp: i64,
+    /// Id of the token.
+    #[serde(default)]
+    pub jti: String,
+    /// The credential the token was issued to, e.g. `device_key:ci`.
+    #[serde(default)]
+    pub issued_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadTokenParams {
+    pub product: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadTokenResponse {
+    pub result: String,
+    pub token: String,
+    pub expires_at: i64,
+}
+
+pub fn encode_token(
+    secret: &str,
+    product: &str,
+    issued_to: Option<String>,
+    now: i64,
+    lifetime: u64,
+) -> Result<String, ApiError> {
+    let claims = UploadClaims {
+        aud: AUDIENCE.to_owned(),
+        sub: product.to_owned(),
+        scope: SCOPE.to_owned(),
+        iat: now,
+        exp: now + lifetime as i64,
+        jti: uuid::Uuid::new_v4().to_string(),
+        issued_to,
+    };
+    jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| {
+        error!("failed to encode upload token: {:?}", e);
+        ApiError::Failure
+    })
+}
+
+pub fn decode_token(secret: &str, token: &str, product: &str) -> Result<UploadClaims, ApiError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[AUDIENCE]);
+    validation.leeway = LEEWAY;
+
+    let claims = jsonwebtoken::decode::<UploadClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &validation,
+    )
+    .map_err(|e| ApiError::InvalidUploadToken(e.to_string()))?
+    .claims;
+
+    if claims.scope != SCOPE {
+        return Err(ApiError::InvalidUploadToken("invalid scope".to_owned()));
+    }
+    if claims.sub != product {
+        return Err(ApiError::InvalidUploadToken(
+            "token not valid for product".to_owned(),
+        ));
+    }
+    Ok(claims)
+}
+
+/// Returns whether upload tokens are enabled, i.e. whether `auth.upload_token.secret` is set.
+/// Without a secret, the routes that issue and accept upload tokens are not served.
+pub fn upload_tokens_enabled() -> bool {
+    !settings().auth.upload_token.secret.is_empty()
+}
+
+fn secret() -> Result<&'static str, ApiError> {
+    let secret = settings().auth.upload_token.secret.as_str();
+    if secret.is_empty() {
+        return Err(ApiError::UploadRejected(
+            "upload tokens are not enabled".to_owned(),
+        ));
+    }
+    Ok(secret)
+}
+
+pub struct UploadTokenApi;
+
+impl UploadTokenApi {
+    /// Issues an upload token. The caller has already been authenticated by the JWT layer or the
+    /// signature middleware.
+    pub async fn issue(
+        State(state): State<AppState>,
+        Query(params): Query<UploadTokenParams>,
+        credential: Option<Extension<UploadCredential>>,
+        claims: Option<JwtClaims<RegisteredClaims>>,
+    ) -> Result<Json<UploadTokenResponse>, ApiError> {
+        let secret = secret()?;
+
+        Repo::get_by_column::<entity::product::Entity, _, _>(
+            &state.db,
+            entity::product::Column::Name,
+            params.product.clone(),
+        )
+        .await?
+        .ok_or_else(|| ApiError::UploadRejected("unknown product".to_owned()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        let lifetime = settings().auth.upload_token.lifetime;
+        let issued_to = credential
+            .map(|Extension(credential)| credential)
+            .or_else(|| claims.map(|JwtClaims(claims)| claims.into()))
+            .map(|credential| credential.describe());
+        let token = encode_token(secret, &params.product, issued_to.clone(), now, lifetime)?;
+
+        info!(
+            "issued upload token for product {} to {}",
+            params.product,
+            issued_to.as_deref().unwrap_or("unknown")
+        );
+        Ok(Json(UploadTokenResponse {
+            result: "ok".to_owned(),
+            token,
+            expires_at: now + lifetime as i64,
+        }))
+    }
+}
+
+fn bearer(headers: &HeaderMap) -> Result<&str, ApiError> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::InvalidUploadToken("missing bearer token".to_owned()))
+}
+
+pub async fn verify_upload_token(
+    Query(params): Query<UploadTokenParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let claims = decode_token(secret()?, bearer(request.headers())?, &params.product)?;
+    request.extensions_mut().insert(UploadCredential {
+        kind: "upload_token",
+        id: Some(claims.jti).filter(|jti| !jti.is_empty()),
+        name: claims.issued_to,
+    });
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_token() {
+        let now = chrono::Utc::now().timestamp();
+        let token =
+            encode_token("secret", "Workrave", Some("jwt:ci".to_owned()), now, 900).unwrap();
+
+        let claims = decode_token("secret", &token, "Workrave").unwrap();
+        assert_eq!(claims.sub, "Workrave");
+        assert_eq!(claims.exp, now + 900);
+        assert_eq!(claims.issued_to.as_deref(), Some("jwt:ci"));
+        assert!(uuid::Uuid::parse_str(&claims.jti).is_ok());
+
+        assert!(decode_token("secret", &token, "Other").is_err());
+        assert!(decode_token("other", &token, "Workrave").is_err());
+        assert!(decode_token("secret", "garbage", "Workrave").is_err());
+
+        let expired = encode_token("secret", "Workrave", None, now - 3600, 900).unwrap();
+        assert!(decode_token("secret", &expired, "Workrave").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Extension;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::crash::CrashApi;
+use super::error::ApiError;
+use super::product_manage::ManageAccess;
+use super::read::authenticate;
+use super::version::NewVersion;
+use crate::app_state::AppState;
+use crate::data_providers::version::parse_release_date;
+use crate::entity;
+use crate::model::api_token::{join_entitlements, ApiTokenRepo, ADMIN};
+use crate::model::base::Repo;
+
+// Versioned REST API to provision products, versions and API tokens from CI without the web
+// interface. All routes need a personal API token of an administrator. Products are served by
+// `ProductManageApi`, with the access of an administrator, upload quarantines by
+// `QuarantineApi`, legal holds of crashes by `CrashApi` and the logical export of the data model
+// by `ExportApi`.
+
+pub async fn verify_admin_token(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let (user, token) = authenticate(&state, &request, ADMIN).await?;
+    if !user.is_admin || token.product_id.is_some() {
+        return Err(ApiError::AccessDenied);
+    }
+
+    request.extensions_mut().insert(ManageAccess {
+        is_admin: true,
+        products: None,
+    });
+    request.extensions_mut().insert(user);
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionFilter {
+    pub product: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionRequest {
+    pub product_id: Uuid,
+    #[serde(flatten)]
+    pub version: NewVersion,
+}
+
+/// Changes to a version. Absent fields are left unchanged; an empty string clears the channel
+/// and release date.
+#[derive(Debug, Deserialize)]
+pub struct VersionPatch {
+    pub name: Option<String>,
+    pub hash: Option<String>,
+    pub tag: Option<String>,
+    pub channel: Option<String>,
+    pub release_date: Option<String>,
+}
+
+impl VersionPatch {
+    fn apply(self, version: &mut entity::version::ActiveModel) -> Result<(), ApiError> {
+        if let Some(name) = self.name {
+            if name.trim().is_empty() {
+                return Err(ApiError::APIFailure("version name is empty".to_owned()));
+            }
+            version.name = Set(name.trim().to_owned());
+        }
+        if let Some(hash) = self.hash {
+            version.hash = Set(hash);
+        }
+        if let Some(tag) = self.tag {
+            version.tag = Set(tag);
+        }
+        if let Some(channel) = self.channel {
+            version.channel = Set(Some(channel).filter(|channel| !channel.is_empty()));
+        }
+        if let Some(date) = self.release_date {
+            let release_date = match date.trim() {
+                "" => None,
+                date => Some(parse_release_date(date).ok_or_else(|| {
+                    ApiError::APIFailure(format!("invalid release date '{}'", date))
+                })?),
+            };
+            version.release_date = Set(release_date);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenFilter {
+    /// Lists the tokens of this user instead of those of the caller.
+    pub user: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    pub name: String,
+    /// Owner of the token, the caller if absent.
+    pub user_id: Option<Uuid>,
+    /// Entitlements the token is limited to, e.g. `read`; all entitlements if empty.
+    #[serde(default)]
+    pub entitlements: Vec<String>,
+    /// Product the token is limited to.
+    pub product_id: Option<Uuid>,
+}
+
+/// An API token without its hash.
+#[derive(Debug, Serialize)]
+pub struct TokenView {
+    pub id: Uuid,
+    pub name: String,
+    pub user_id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    pub entitlements: Option<String>,
+    pub product_id: Option<Uuid>,
+}
+
+impl From<entity::api_token::Model> for TokenView {
+    fn from(token: entity::api_token::Model) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            user_id: token.user_id,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            entitlements: token.entitlements,
+            product_id: token.product_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceKeyFilter {
+    pub product: Option<Uuid>,
+}
+
+/// A device key without its hash, with the number of crashes submitted with it. Submissions are
+/// written in batches, so the latest ones may not be counted yet.
+#[derive(Debug, Serialize)]
+pub struct DeviceKeyView {
+    pub id: Uuid,
+    pub name: String,
+    pub product_id: Uuid,
+    pub entitlements: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub submission_count: i64,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<entity::device_key::Model> for DeviceKeyView {
+    fn from(key: entity::device_key::Model) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            product_id: key.product_id,
+            entitlements: key.entitlements,
+            created_at: key.created_at,
+            submission_count: key.submission_count,
+            last_used_at: key.last_used_at,
+        }
+    }
+}
+
+pub struct VersionsV1Api;
+
+impl VersionsV1Api {
+    async fn find(state: &AppState, id: Uuid) -> Result<entity::version::Model, ApiError> {
+        Repo::get_by_id::<entity::version::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("version".to_owned(), id.to_string()))
+    }
+
+    pub async fn list(
+        State(state): State<AppState>,
+        Query(filter): Query<VersionFilter>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::Version::find();
+        if let Some(product) = filter.product {
+            query = query.filter(entity::version::Column::ProductId.eq(product));
+        }
+        let versions = query
+            .order_by_asc(entity::version::Column::ProductId)
+            .order_by_asc(entity::version::Column::Name)
+            .all(&state.db)
+            .await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": versions }).to_string())
+    }
+
+    pub async fn get(
+        State(state): State<AppState>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        let version = Self::find(&state, id).await?;
+        Ok(serde_json::json!({ "result": "ok", "payload": version }).to_string())
+    }
+
+    pub async fn create(
+        State(state): State<AppState>,
+        payload: String,
+    ) -> Result<String, ApiError> {
+        let request: VersionRequest = serde_json::from_str(&payload)?;
+        Repo::get_by_id::<entity::product::Entity>(&state.db, request.product_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::ForeignKeyError("product".to_owned(), request.product_id.to_string())
+            })?;
+
+        let id = Repo::create(&state.db, request.version.into_dto(request.product_id)?).await?;
+        state.cache.invalidate_product(request.product_id);
+        let version = Self::find(&state, id).await?;
+        Ok(serde_json::json!({ "result": "ok", "id": id, "payload": version }).to_string())
+    }
+
+    pub async fn update(
+        State(state): State<AppState>,
+        Path(id): Path<Uuid>,
+        payload: String,
+    ) -> Result<String, ApiError> {
+        let patch: VersionPatch = serde_json::from_str(&payload)?;
+        let version = Self::find(&state, id).await?;
+        let product_id = version.product_id;
+        let mut active: entity::version::ActiveModel = version.into();
+        patch.apply(&mut active)?;
+        active.updated_at = Set(chrono::Utc::now().naive_utc());
+        let version = active.update(&state.db).await?;
+        state.cache.invalidate_product(product_id);
+        Ok(serde_json::json!({ "result": "ok", "payload": version }).to_string())
+    }
+
+    pub async fn remove(
+        State(state): State<AppState>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        let version = Self::find(&state, id).await?;
+        CrashApi::check_not_held(&state.db, entity::crash::Column::VersionId, id).await?;
+        Repo::delete_by_id::<entity::version::Entity>(&state.db, id).await?;
+        state.cache.invalidate_product(version.product_id);
+        Ok(serde_json::json!({ "result": "ok", "id": id }).to_string())
+    }
+}
+
+pub struct TokensV1Api;
+
+impl TokensV1Api {
+    pub async fn list(
+        State(state): State<AppState>,
+        Extension(user): Extension<entity::user::Model>,
+        Query(filter): Query<TokenFilter>,
+    ) -> Result<String, ApiError> {
+        let user_id = filter.user.unwrap_or(user.id);
+        let tokens: Vec<TokenView> = ApiTokenRepo::get_by_user(&state.db, user_id)
+            .await?
+            .into_iter()
+            .map(TokenView::from)
+            .collect();
+        Ok(serde_json::json!({ "result": "ok", "payload": tokens }).to_string())
+    }
+
+    /// Creates a token. The token itself is only part of this response.
+    pub async fn create(
+        State(state): State<AppState>,
+        Extension(user): Extension<entity::user::Model>,
+        payload: String,
+    ) -> Result<String, ApiError> {
+        let request: TokenRequest = serde_json::from_str(&payload)?;
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(ApiError::APIFailure("name must not be empty".to_owned()));
+        }
+        let user_id = request.user_id.unwrap_or(user.id);
+        Repo::get_by_id::<entity::user::Entity>(&state.db, user_id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("user".to_owned(), user_id.to_string()))?;
+
+        let entitlements = join_entitlements(&request.entitlements).map_err(|entitlement| {
+            ApiError::APIFailure(format!("unknown entitlement: {}", entitlement))
+        })?;
+        if let Some(product_id) = request.product_id {
+            Repo::get_by_id::<entity::product::Entity>(&state.db, product_id)
+                .await?
+                .ok_or_else(|| {
+                    ApiError::ForeignKeyError("product".to_owned(), product_id.to_string())
+                })?;
+        }
+
+        let (id, token) = ApiTokenRepo::create(
+            &state.db,
+            user_id,
+            name.to_owned(),
+            entitlements,
+            request.product_id,
+        )
+        .await?;
+        Ok(serde_json::json!({ "result": "ok", "id": id, "token": token }).to_string())
+    }
+
+    pub async fn remove(
+        State(state): State<AppState>,
+        Path(id): Path<Uuid>,
+    ) -> Result<String, ApiError> {
+        let token = Repo::get_by_id::<entity::api_token::Entity>(&state.db, id)
+            .await?
+            .ok_or_else(|| ApiError::ForeignKeyError("token".to_owned(), id.to_string()))?;
+        ApiTokenRepo::remove(&state.db, token.user_id, id).await?;
+        Ok(serde_json::json!({ "result": "ok", "id": id }).to_string())
+    }
+}
+
+pub struct DeviceKeysV1Api;
+
+impl DeviceKeysV1Api {
+    pub async fn list(
+        State(state): State<AppState>,
+        Query(filter): Query<DeviceKeyFilter>,
+    ) -> Result<String, ApiError> {
+        let mut query = entity::prelude::DeviceKey::find();
+        if let Some(product) = filter.product {
+            query = query.filter(entity::device_key::Column::ProductId.eq(product));
+        }
+        let keys: Vec<DeviceKeyView> = query
+            .order_by_asc(entity::device_key::Column::ProductId)
+            .order_by_asc(entity::device_key::Column::Name)
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(DeviceKeyView::from)
+            .collect();
+        Ok(serde_json::json!({ "result": "ok", "payload": keys }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::base::tests::*;
+    use axum::http::header;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_requires_api_token() {
+        let server = run_server().await;
+
+        for path in [
+            "/api/v1/products",
+            "/api/v1/versions",
+            "/api/v1/tokens",
+            "/api/v1/device_keys",
+        ] {
+            let response = server.get(path).await;
+            response.assert_status_unauthorized();
+            assert_eq!(response.json::<ApiResponseFailed>().result, "failed");
+        }
+
+        let response = server
+            .post("/api/v1/versions")
+            .add_header(header::AUTHORIZATION, "Bearer grt_unknown".parse().unwrap())
+            .json(&serde_json::json!({ "product_id": uuid::Uuid::new_v4(), "name": "1.0" }))
+            .await;
+        response.assert_status_unauthorized();
+        assert_eq!(response.json::<ApiResponseFailed>().result, "failed");
+    }
+}
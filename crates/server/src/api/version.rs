@@ -1,17 +1,26 @@
 use async_trait::async_trait;
+use axum::extract::{Path, Query, State};
+use axum::{Extension, Json};
 use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use tracing::info;
 
 use crate::{
+    app_state::AppState,
+    data_providers::version::parse_release_date,
     entity::{prelude::Version, version},
     model::{
         base::Repo,
-        version::{VersionCreateDto, VersionUpdateDto},
+        device_key::{DeviceKey, VERSION_MANAGE},
+        version::{VersionCreateDto, VersionRepo, VersionUpdateDto, VersionUpsert},
     },
 };
 
 use super::{
-    base::{Resource, ResourceFilter},
+    base::{Api, Resource, ResourceFilter},
+    crash::CrashApi,
     error::ApiError,
+    signature::SignedRequestParams,
 };
 
 impl Resource for Version {
@@ -47,6 +56,136 @@ impl ResourceFilter for Version {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct NewVersion {
+    pub name: String,
+    pub hash: Option<String>,
+    pub tag: Option<String>,
+    pub channel: Option<String>,
+    pub release_date: Option<String>,
+}
+
+/// A single version or a list of versions, so that CI can register a whole release train in
+/// one request.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NewVersions {
+    One(NewVersion),
+    Many(Vec<NewVersion>),
+}
+
+impl NewVersion {
+    /// Validates the version. Fields that were not posted stay `None`.
+    fn into_upsert(self) -> Result<VersionUpsert, ApiError> {
+        if self.name.trim().is_empty() {
+            return Err(ApiError::APIFailure("version name is empty".to_owned()));
+        }
+        let release_date =
+            match self.release_date.as_deref() {
+                None => None,
+                Some(date) => Some(parse_release_date(date).ok_or_else(|| {
+                    ApiError::APIFailure(format!("invalid release date '{}'", date))
+                })?),
+            };
+        Ok(VersionUpsert {
+            name: self.name.trim().to_owned(),
+            hash: self.hash,
+            tag: self.tag,
+            channel: self.channel.filter(|channel| !channel.is_empty()),
+            release_date,
+        })
+    }
+
+    pub(super) fn into_dto(self, product_id: uuid::Uuid) -> Result<VersionCreateDto, ApiError> {
+        let version = self.into_upsert()?;
+        Ok(VersionCreateDto {
+            name: version.name,
+            hash: version.hash.unwrap_or_default(),
+            tag: version.tag.unwrap_or_default(),
+            product_id,
+            channel: version.channel,
+            release_date: version.release_date,
+        })
+    }
+}
+
+pub struct VersionApi;
+
+impl VersionApi {
+    async fn upsert(
+        state: &AppState,
+        product_id: uuid::Uuid,
+        versions: NewVersions,
+    ) -> Result<Json<serde_json::Value>, ApiError> {
+        let versions = match versions {
+            NewVersions::One(version) => vec![version],
+            NewVersions::Many(versions) => versions,
+        };
+        let dtos = versions
+            .into_iter()
+            .map(NewVersion::into_upsert)
+            .collect::<Result<Vec<_>, _>>()?;
+        let names: Vec<String> = dtos.iter().map(|dto| dto.name.clone()).collect();
+
+        let result = VersionRepo::upsert_many(&state.db, product_id, dtos).await?;
+        state.cache.invalidate_product(product_id);
+        info!(
+            "stored {} version(s) of product {}",
+            result.len(),
+            product_id
+        );
+
+        let payload: Vec<serde_json::Value> = names
+            .into_iter()
+            .zip(result)
+            .map(|(name, (id, created))| {
+                serde_json::json!({ "id": id, "name": name, "created": created })
+            })
+            .collect();
+        Ok(Json(
+            serde_json::json!({ "result": "ok", "payload": payload }),
+        ))
+    }
+
+    /// Deletes a version, unless one of its crashes is on legal hold; the crashes of a version
+    /// are removed by the cascade.
+    pub async fn remove(
+        Path(id): Path<uuid::Uuid>,
+        State(state): State<AppState>,
+    ) -> Result<String, ApiError> {
+        CrashApi::check_not_held(&state.db, crate::entity::crash::Column::VersionId, id).await?;
+        Api::remove_by_id::<Version>(Path(id), State(state)).await
+    }
+
+    /// Creates or updates versions of a product, authenticated by a JWT.
+    pub async fn create_for_product(
+        Path(product_id): Path<uuid::Uuid>,
+        State(state): State<AppState>,
+        Json(versions): Json<NewVersions>,
+    ) -> Result<Json<serde_json::Value>, ApiError> {
+        Repo::get_by_id::<crate::entity::product::Entity>(&state.db, product_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::ForeignKeyError("product".to_owned(), product_id.to_string())
+            })?;
+        Self::upsert(&state, product_id, versions).await
+    }
+
+    /// Creates or updates versions of the product of a device key. The key needs the
+    /// `version-manage` entitlement.
+    pub async fn create_signed(
+        State(state): State<AppState>,
+        Query(_params): Query<SignedRequestParams>,
+        Extension(key): Extension<DeviceKey>,
+        Json(versions): Json<NewVersions>,
+    ) -> Result<Json<serde_json::Value>, ApiError> {
+        if !key.has_entitlement(VERSION_MANAGE) {
+            return Err(ApiError::AccessDenied);
+        }
+        Self::upsert(&state, key.product_id, versions).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::api::base::tests::*;
@@ -206,6 +345,77 @@ mod tests {
         assert_eq!(versions.payload[1].id.to_string(), version3.id);
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_create_for_product() {
+        let server = run_server().await;
+
+        let response = server
+            .post("/api/product")
+            .content_type("application/json")
+            .json(&serde_json::json!({
+                "name":"Workrave" ,
+            }))
+            .await;
+        response.assert_status_ok();
+        let product = response.json::<ApiResponseWithId>();
+
+        let response = server
+            .post(format!("/api/product/{}/versions", product.id).as_str())
+            .content_type("application/json")
+            .json(&serde_json::json!({
+                "name":"1.11", "hash":"1234567890", "tag": "v1.11", "channel": "stable",
+                "release_date": "2024-10-01"
+            }))
+            .await;
+        response.assert_status_ok();
+
+        let response = server
+            .post(format!("/api/product/{}/versions", product.id).as_str())
+            .content_type("application/json")
+            .json(&serde_json::json!([
+                { "name":"1.11", "tag": "v1.11", "channel": "beta" },
+                { "name":"1.12", "tag": "v1.12", "channel": "beta" }
+            ]))
+            .await;
+        response.assert_status_ok();
+        let result = response.json::<serde_json::Value>();
+        assert_eq!(result["payload"][0]["created"], false);
+        assert_eq!(result["payload"][1]["created"], true);
+
+        let response = server
+            .get("/api/version")
+            .content_type("application/json")
+            .await;
+        response.assert_status_ok();
+        let versions = response.json::<ApiResponseWithVecPayload>();
+        assert_eq!(versions.payload.len(), 2);
+        assert!(versions
+            .payload
+            .iter()
+            .all(|version| version.channel.as_deref() == Some("beta")));
+        let version = versions
+            .payload
+            .iter()
+            .find(|version| version.name == "1.11")
+            .unwrap();
+        assert_eq!(version.hash, "1234567890");
+
+        let response = server
+            .post(format!("/api/product/{}/versions", product.id).as_str())
+            .content_type("application/json")
+            .json(&serde_json::json!({ "name":"1.13", "release_date": "soon" }))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = server
+            .post(format!("/api/product/{}/versions", uuid::Uuid::new_v4()).as_str())
+            .content_type("application/json")
+            .json(&serde_json::json!({ "name":"1.13" }))
+            .await;
+        response.assert_status_not_found();
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_product_not_found() {
@@ -5,10 +5,27 @@ use sea_orm::DatabaseConnection;
 use std::sync::Arc;
 use webauthn_rs::prelude::*;
 
+use crate::analyzers::Analyzers;
+use crate::api::{
+    CrashFeed, IssueCounts, NonceCache, PublicGuard, ResponseCache, TokenUsage, UploadGuard,
+};
+
 #[derive(FromRef, Debug, Clone)]
 pub struct AppState {
     pub leptos_options: LeptosOptions,
     pub routes: Vec<RouteListing>,
     pub db: DatabaseConnection,
     pub webauthn: Arc<Webauthn>,
+    pub nonces: Arc<NonceCache>,
+    pub analyzers: Arc<Analyzers>,
+    pub cache: Arc<ResponseCache>,
+    pub uploads: Arc<UploadGuard>,
+    pub public: Arc<PublicGuard>,
+    /// Rate limit of the reports of failed uploads, see `ClientDiagnosticsApi`.
+    pub diagnostics: Arc<PublicGuard>,
+    pub usage: Arc<TokenUsage>,
+    /// Crashes of existing issues, counted in batches, see `IssueCounts`.
+    pub issue_counts: Arc<IssueCounts>,
+    /// Processed crashes, see `CrashFeedApi`.
+    pub crashes: Arc<CrashFeed>,
 }
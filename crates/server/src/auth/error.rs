@@ -15,6 +15,8 @@ pub enum AuthError {
     UserNotFound,
     #[error("User already exists")]
     UserAlreadyExists,
+    #[error("User is deactivated")]
+    UserDeactivated,
     // #[error("User has no credentials")]
     // UserHasNoCredentials,
     #[error("Deserialising session failed: {0}")]
@@ -33,6 +35,9 @@ impl IntoResponse for AuthError {
             AuthError::UserAlreadyExists => {
                 (StatusCode::BAD_REQUEST, "User already exists".to_string())
             }
+            AuthError::UserDeactivated => {
+                (StatusCode::FORBIDDEN, "User is deactivated".to_string())
+            }
             // AuthError::UserHasNoCredentials => (
             //     StatusCode::BAD_REQUEST,
             //     "User has no credentials".to_string(),
@@ -5,6 +5,7 @@ use crate::{
         self,
         prelude::{Credential, User},
     },
+    model::user::UserRepo,
 };
 use app::auth::AuthenticatedUser;
 use axum::{
@@ -101,14 +102,18 @@ pub async fn finish_register(
         .webauthn
         .finish_passkey_registration(&reg, &registration_state.passkey_registration)?;
 
+    // The first user sets up the installation, see `SetupPage`: they become an administrator and
+    // are signed in right away.
+    let first_user = UserRepo::is_empty(&state.db).await?;
     if user.is_none() {
         let user = entity::user::ActiveModel {
             id: Set(registration_state.user_unique_id),
             username: Set(registration_state.username),
-            is_admin: Set(false),
+            is_admin: Set(first_user),
             created_at: Set(Utc::now().naive_utc()),
             updated_at: Set(Utc::now().naive_utc()),
             last_authenticated: Set(None),
+            deactivated_at: Set(None),
         };
         user.insert(&state.db).await?;
     }
@@ -124,6 +129,16 @@ pub async fn finish_register(
     };
     cred.insert(&state.db).await?;
 
+    if first_user {
+        let user = User::find_by_id(registration_state.user_unique_id)
+            .one(&state.db)
+            .await?
+            .ok_or(AuthError::UserNotFound)?;
+        session
+            .insert("authenticated_user", AuthenticatedUser::new(user))
+            .await?;
+    }
+
     Ok(StatusCode::OK)
 }
 
@@ -186,6 +201,9 @@ pub async fn finish_authentication(
         .one(&state.db)
         .await?
         .ok_or(AuthError::UserNotFound)?;
+    if user.deactivated_at.is_some() {
+        return Err(AuthError::UserDeactivated);
+    }
 
     let authenticated_user = AuthenticatedUser::new(user);
     session
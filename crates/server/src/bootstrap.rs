@@ -0,0 +1,366 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use tracing::{info, warn};
+
+use crate::entity;
+use crate::model::base::Repo;
+use crate::model::device_key::DeviceKeyCreateDto;
+use crate::model::product::ProductCreateDto;
+use crate::model::product_deletion::ProductDeletionRepo;
+
+// Products and device keys can be declared in a YAML file (`server.bootstrap`) that is applied
+// at startup:
+//
+//   prune: false
+//   products:
+//     - name: Workrave
+//       max_build_age_days: 180
+//       build_age_overrides:
+//         beta: 30
+//       device_keys:
+//         - name: ci
+//           key_hash_env: WORKRAVE_CI_KEY_HASH
+//           entitlements: [version-manage]
+//
+// Device keys reference the SHA-256 hash of the key, either literally (`key_hash`) or through an
+// environment variable (`key_hash_env`), never the key itself. Resources that are not declared
+// are reported and only removed when `prune` is set.
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Bootstrap {
+    pub prune: bool,
+    pub products: Vec<ProductConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProductConfig {
+    pub name: String,
+    pub max_build_age_days: Option<i32>,
+    pub build_age_overrides: BTreeMap<String, i32>,
+    pub build_age_allow_list: Vec<String>,
+    pub promoted_annotations: Vec<String>,
+    pub redacted_fields: Vec<String>,
+    pub allowed_content_types: Vec<String>,
+    pub environments: Vec<String>,
+    pub default_annotations: BTreeMap<String, String>,
+    pub auto_create_versions: bool,
+    pub device_keys: Vec<DeviceKeyConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DeviceKeyConfig {
+    pub name: String,
+    pub key_hash: Option<String>,
+    pub key_hash_env: Option<String>,
+    pub entitlements: Vec<String>,
+}
+
+fn join(values: &[String]) -> Option<String> {
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+impl ProductConfig {
+    fn build_age_overrides(&self) -> Option<String> {
+        let overrides: Vec<String> = self
+            .build_age_overrides
+            .iter()
+            .map(|(channel, days)| format!("{}={}", channel, days))
+            .collect();
+        join(&overrides)
+    }
+
+    fn default_annotations(&self) -> Option<String> {
+        let annotations: Vec<String> = self
+            .default_annotations
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        join(&annotations)
+    }
+}
+
+impl DeviceKeyConfig {
+    fn key_hash(&self) -> Result<String, DbErr> {
+        match (&self.key_hash, &self.key_hash_env) {
+            (Some(key_hash), _) => Ok(key_hash.to_lowercase()),
+            (None, Some(var)) => std::env::var(var)
+                .map(|key_hash| key_hash.trim().to_lowercase())
+                .map_err(|_| DbErr::Custom(format!("environment variable {} is not set", var))),
+            (None, None) => Err(DbErr::Custom(format!(
+                "device key {} has no key_hash or key_hash_env",
+                self.name
+            ))),
+        }
+    }
+}
+
+pub fn load(path: &str) -> Result<Bootstrap, config::ConfigError> {
+    config::Config::builder()
+        .add_source(config::File::with_name(path))
+        .build()?
+        .try_deserialize()
+}
+
+async fn reconcile_product(
+    db: &DatabaseConnection,
+    config: &ProductConfig,
+) -> Result<uuid::Uuid, DbErr> {
+    let existing = entity::prelude::Product::find()
+        .filter(entity::product::Column::Name.eq(config.name.clone()))
+        .one(db)
+        .await?;
+
+    let Some(product) = existing else {
+        info!("bootstrap: creating product {}", config.name);
+        let dto = ProductCreateDto {
+            max_build_age_days: config.max_build_age_days,
+            build_age_overrides: config.build_age_overrides(),
+            build_age_allow_list: join(&config.build_age_allow_list),
+            promoted_annotations: join(&config.promoted_annotations),
+            redacted_fields: join(&config.redacted_fields),
+            allowed_content_types: join(&config.allowed_content_types),
+            environments: join(&config.environments),
+            default_annotations: config.default_annotations(),
+            auto_create_versions: config.auto_create_versions,
+            ..ProductCreateDto::named(config.name.clone())
+        };
+        return Repo::create(db, dto).await;
+    };
+
+    let id = product.id;
+    if ProductDeletionRepo::unschedule(db, id).await? {
+        warn!(
+            "bootstrap: product {} was scheduled for deletion, keeping it",
+            config.name
+        );
+    }
+    let mut active = product.into_active_model();
+    active
+        .max_build_age_days
+        .set_if_not_equals(config.max_build_age_days);
+    active
+        .build_age_overrides
+        .set_if_not_equals(config.build_age_overrides());
+    active
+        .build_age_allow_list
+        .set_if_not_equals(join(&config.build_age_allow_list));
+    active
+        .promoted_annotations
+        .set_if_not_equals(join(&config.promoted_annotations));
+    active
+        .redacted_fields
+        .set_if_not_equals(join(&config.redacted_fields));
+    active
+        .allowed_content_types
+        .set_if_not_equals(join(&config.allowed_content_types));
+    active
+        .environments
+        .set_if_not_equals(join(&config.environments));
+    active
+        .default_annotations
+        .set_if_not_equals(config.default_annotations());
+    active
+        .auto_create_versions
+        .set_if_not_equals(config.auto_create_versions);
+    if active.is_changed() {
+        warn!(
+            "bootstrap: product {} differs from configuration, updating",
+            config.name
+        );
+        active.update(db).await?;
+    }
+    Ok(id)
+}
+
+async fn reconcile_device_keys(
+    db: &DatabaseConnection,
+    product_id: uuid::Uuid,
+    config: &ProductConfig,
+    prune: bool,
+) -> Result<(), DbErr> {
+    let existing = entity::prelude::DeviceKey::find()
+        .filter(entity::device_key::Column::ProductId.eq(product_id))
+        .all(db)
+        .await?;
+
+    for key in &config.device_keys {
+        let key_hash = key.key_hash()?;
+        match existing.iter().find(|existing| existing.name == key.name) {
+            None => {
+                info!(
+                    "bootstrap: creating device key {} for product {}",
+                    key.name, config.name
+                );
+                let dto = DeviceKeyCreateDto {
+                    name: key.name.clone(),
+                    key_hash,
+                    product_id,
+                    entitlements: join(&key.entitlements),
+                    submission_count: 0,
+                    last_used_at: None,
+                };
+                Repo::create(db, dto).await?;
+            }
+            Some(existing) => {
+                let mut active = existing.clone().into_active_model();
+                active.key_hash.set_if_not_equals(key_hash);
+                active
+                    .entitlements
+                    .set_if_not_equals(join(&key.entitlements));
+                if active.is_changed() {
+                    warn!(
+                        "bootstrap: device key {} of product {} differs from configuration, updating",
+                        key.name, config.name
+                    );
+                    active.update(db).await?;
+                }
+            }
+        }
+    }
+
+    let declared: HashSet<&str> = config.device_keys.iter().map(|k| k.name.as_str()).collect();
+    for key in existing
+        .into_iter()
+        .filter(|key| !declared.contains(key.name.as_str()))
+    {
+        if prune {
+            warn!(
+                "bootstrap: removing unmanaged device key {} of product {}",
+                key.name, config.name
+            );
+            entity::prelude::DeviceKey::delete_by_id(key.id)
+                .exec(db)
+                .await?;
+        } else {
+            warn!(
+                "bootstrap: device key {} of product {} is not managed by configuration",
+                key.name, config.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Creates or updates the declared resources. Running it again without configuration changes
+/// does not modify the database.
+pub async fn reconcile(db: &DatabaseConnection, bootstrap: &Bootstrap) -> Result<(), DbErr> {
+    for product in &bootstrap.products {
+        let product_id = reconcile_product(db, product).await?;
+        reconcile_device_keys(db, product_id, product, bootstrap.prune).await?;
+    }
+
+    let declared: HashSet<&str> = bootstrap.products.iter().map(|p| p.name.as_str()).collect();
+    let unmanaged = entity::prelude::Product::find()
+        .filter(
+            entity::product::Column::Id
+                .not_in_subquery(ProductDeletionRepo::scheduled_product_ids()),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|product| !declared.contains(product.name.as_str()));
+    for product in unmanaged {
+        if bootstrap.prune {
+            warn!(
+                "bootstrap: scheduling unmanaged product {} for deletion",
+                product.name
+            );
+            ProductDeletionRepo::schedule(db, product.id).await?;
+        } else {
+            warn!(
+                "bootstrap: product {} is not managed by configuration",
+                product.name
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+    use serial_test::serial;
+
+    fn workrave() -> ProductConfig {
+        ProductConfig {
+            name: "Workrave".to_owned(),
+            max_build_age_days: Some(180),
+            build_age_overrides: BTreeMap::from([("beta".to_owned(), 30)]),
+            device_keys: vec![DeviceKeyConfig {
+                name: "ci".to_owned(),
+                key_hash: Some("ABCDEF".to_owned()),
+                key_hash_env: None,
+                entitlements: vec!["version-manage".to_owned()],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_reconcile() {
+        let db: DatabaseConnection = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let other = ProductCreateDto::named("Scroom");
+        Repo::create(&db, other).await.unwrap();
+
+        let mut bootstrap = Bootstrap {
+            prune: false,
+            products: vec![workrave()],
+        };
+        reconcile(&db, &bootstrap).await.unwrap();
+        reconcile(&db, &bootstrap).await.unwrap();
+
+        let products = entity::prelude::Product::find().all(&db).await.unwrap();
+        assert_eq!(products.len(), 2);
+        let product = products.iter().find(|p| p.name == "Workrave").unwrap();
+        assert_eq!(product.max_build_age_days, Some(180));
+        assert_eq!(product.build_age_overrides.as_deref(), Some("beta=30"));
+
+        let keys = entity::prelude::DeviceKey::find().all(&db).await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key_hash, "abcdef");
+        assert!(keys[0].has_entitlement("version-manage"));
+
+        bootstrap.products[0].max_build_age_days = None;
+        bootstrap.products[0].device_keys.clear();
+        bootstrap.prune = true;
+        reconcile(&db, &bootstrap).await.unwrap();
+
+        let product = entity::prelude::Product::find_by_id(product.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(product.max_build_age_days, None);
+        assert!(entity::prelude::DeviceKey::find()
+            .all(&db)
+            .await
+            .unwrap()
+            .is_empty());
+        let pending = ProductDeletionRepo::get_pending(&db).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].product_name, "Scroom");
+
+        // Declaring the product again cancels its deletion.
+        bootstrap.products.push(ProductConfig {
+            name: "Scroom".to_owned(),
+            ..Default::default()
+        });
+        reconcile(&db, &bootstrap).await.unwrap();
+        assert!(ProductDeletionRepo::get_pending(&db)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
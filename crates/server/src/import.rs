@@ -0,0 +1,369 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::api::{ApiError, ClientHints, MinidumpApi, Provenance, UploadCredential};
+use crate::app_state::AppState;
+use crate::entity;
+use crate::jobs::{hold_lease, release_lease};
+use crate::model::base::Repo;
+use crate::model::job_state::JobStateRepo;
+use crate::model::version::{VersionCreateDto, VersionRepo};
+
+// Imports crashes from a directory, such as a synced copy of a Socorro crash storage bucket, when
+// `import.source` is set. Two layouts are recognized:
+//
+//   v1/raw_crash/<date>/<crash id>   Socorro raw crash metadata (JSON)
+//   v1/dump/<crash id>               Socorro minidump
+//
+//   <crash id>.json                  metadata (JSON), using the Socorro field names
+//   <crash id>.dmp                   minidump
+//
+// `ProductName` and `Version` select the product and version; versions are created when
+// missing, products must exist. `submitted_timestamp` dates the crash. The remaining metadata
+// fields become annotations. Reading a bucket directly from S3 is out of scope; sync it to a
+// local directory first.
+//
+// Crashes are imported in the order of their ids by one replica at a time, the one holding the
+// `import` lease. The id of the last imported crash and the ids of crashes that failed are
+// stored in the database, so that an interrupted import, also when taken over by another
+// replica, resumes where it stopped and retries the failed crashes. Submitting the same minidump
+// twice returns the existing crash in any case.
+
+const LEASE: &str = "import";
+/// How often the lease is renewed while importing, and how often a replica that does not hold it
+/// tries again.
+const LEASE_PERIOD: Duration = Duration::from_secs(60);
+const MAPPED_FIELDS: &[&str] = &["ProductName", "Version", "uuid", "submitted_timestamp"];
+
+#[derive(Debug, Clone, PartialEq)]
+struct SourceCrash {
+    id: String,
+    metadata: PathBuf,
+    dump: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ImportedCrash {
+    product: String,
+    version: String,
+    submitted_at: Option<NaiveDateTime>,
+    annotations: BTreeMap<String, String>,
+}
+
+/// Progress of the import of a source: the id of the last crash that was processed and the ids
+/// of crashes that failed, which are tried again on the next start.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Progress {
+    after: Option<String>,
+    failed: BTreeSet<String>,
+}
+
+impl Progress {
+    fn is_pending(&self, id: &str) -> bool {
+        self.after.as_deref().map_or(true, |after| id > after) || self.failed.contains(id)
+    }
+
+    fn record(&mut self, id: &str, imported: bool) {
+        match imported {
+            true => self.failed.remove(id),
+            false => self.failed.insert(id.to_owned()),
+        };
+        if self.after.as_deref().map_or(true, |after| id > after) {
+            self.after = Some(id.to_owned());
+        }
+    }
+}
+
+fn state_key(source: &Path) -> String {
+    format!("import/{}", source.display())
+}
+
+fn map_raw_crash(raw: &Value) -> Option<ImportedCrash> {
+    let fields = raw.as_object()?;
+    let product = fields.get("ProductName")?.as_str()?.to_owned();
+    let version = fields.get("Version")?.as_str()?.to_owned();
+    let submitted_at = fields
+        .get("submitted_timestamp")
+        .and_then(Value::as_str)
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.naive_utc());
+    let annotations = fields
+        .iter()
+        .filter(|(key, _)| !MAPPED_FIELDS.contains(&key.as_str()))
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((key.clone(), value))
+        })
+        .collect();
+    Some(ImportedCrash {
+        product,
+        version,
+        submitted_at,
+        annotations,
+    })
+}
+
+fn list_socorro(source: &Path) -> std::io::Result<Vec<SourceCrash>> {
+    let mut crashes = vec![];
+    for date in std::fs::read_dir(source.join("v1").join("raw_crash"))? {
+        for entry in std::fs::read_dir(date?.path())? {
+            let metadata = entry?.path();
+            let Some(id) = metadata.file_name().and_then(|id| id.to_str()) else {
+                continue;
+            };
+            let dump = source.join("v1").join("dump").join(id);
+            crashes.push(SourceCrash {
+                id: id.to_owned(),
+                metadata,
+                dump,
+            });
+        }
+    }
+    Ok(crashes)
+}
+
+fn list_flat(source: &Path) -> std::io::Result<Vec<SourceCrash>> {
+    let mut crashes = vec![];
+    for entry in std::fs::read_dir(source)? {
+        let metadata = entry?.path();
+        if metadata.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(id) = metadata.file_stem().and_then(|id| id.to_str()) else {
+            continue;
+        };
+        crashes.push(SourceCrash {
+            id: id.to_owned(),
+            dump: metadata.with_extension("dmp"),
+            metadata,
+        });
+    }
+    Ok(crashes)
+}
+
+fn list(source: &Path) -> std::io::Result<Vec<SourceCrash>> {
+    let mut crashes = if source.join("v1").join("raw_crash").is_dir() {
+        list_socorro(source)?
+    } else {
+        list_flat(source)?
+    };
+    crashes.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(crashes)
+}
+
+async fn get_or_create_version(
+    state: &AppState,
+    product_id: uuid::Uuid,
+    name: &str,
+) -> Result<crate::model::version::Version, ApiError> {
+    if let Some(version) =
+        VersionRepo::get_by_product_and_name(&state.db, product_id, name.to_owned()).await?
+    {
+        return Ok(version);
+    }
+    info!("import: creating version {}", name);
+    let dto = VersionCreateDto {
+        name: name.to_owned(),
+        hash: "".to_owned(),
+        tag: "".to_owned(),
+        product_id,
+        channel: None,
+        release_date: None,
+    };
+    let id = Repo::create(&state.db, dto).await?;
+    Repo::get_by_id::<entity::version::Entity>(&state.db, id)
+        .await?
+        .ok_or(ApiError::Failure)
+}
+
+async fn import_crash(state: &AppState, crash: &SourceCrash) -> Result<uuid::Uuid, ApiError> {
+    let raw: Value = serde_json::from_slice(&tokio::fs::read(&crash.metadata).await?)?;
+    let imported = map_raw_crash(&raw)
+        .ok_or_else(|| ApiError::UploadRejected("missing ProductName or Version".to_owned()))?;
+
+    let product = Repo::get_by_column::<entity::product::Entity, _, _>(
+        &state.db,
+        entity::product::Column::Name,
+        imported.product.clone(),
+    )
+    .await?
+    .ok_or_else(|| ApiError::UploadRejected(format!("unknown product {}", imported.product)))?;
+    let version = get_or_create_version(state, product.id, &imported.version).await?;
+
+    let minidump_file =
+        MinidumpApi::get_minidump_file(&product.name, &format!("{}.dmp", crash.id)).await?;
+    tokio::fs::copy(&crash.dump, &minidump_file).await?;
+
+    let provenance = Provenance {
+        credential: Some(UploadCredential {
+            kind: "import",
+            id: Some(crash.id.clone()),
+            name: None,
+        }),
+        submitter_ip: None,
+    };
+    let client = ClientHints {
+        received_at: imported.submitted_at,
+        ..Default::default()
+    };
+    let (crash_id, created) = MinidumpApi::ingest(
+        state,
+        &product,
+        &version,
+        None,
+        minidump_file,
+        &client,
+        &provenance,
+    )
+    .await?;
+
+    if created {
+        MinidumpApi::store_annotations(crash_id, &product, imported.annotations, state).await?;
+    }
+    Ok(crash_id)
+}
+
+async fn import(state: &AppState, source: PathBuf, rate: u32) -> Result<usize, ApiError> {
+    let key = state_key(&source);
+    let mut progress: Progress = JobStateRepo::get(&state.db, &key)
+        .await?
+        .unwrap_or_default();
+    let crashes = tokio::task::spawn_blocking(move || list(&source)).await??;
+    let pending: Vec<SourceCrash> = crashes
+        .into_iter()
+        .filter(|crash| progress.is_pending(&crash.id))
+        .collect();
+    info!(
+        "import: {} crashes to import, {} failed before",
+        pending.len(),
+        progress.failed.len()
+    );
+
+    let mut renewed = Instant::now();
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / rate.max(1));
+    let mut imported = 0;
+    for crash in pending {
+        interval.tick().await;
+        if renewed.elapsed() >= LEASE_PERIOD {
+            if !hold_lease(&state.db, LEASE, LEASE_PERIOD).await {
+                warn!("import: lost the lease, stopping");
+                break;
+            }
+            renewed = Instant::now();
+        }
+        let result = import_crash(state, &crash).await;
+        match &result {
+            Ok(crash_id) => {
+                info!("import: imported {} as crash {}", crash.id, crash_id);
+                imported += 1;
+            }
+            Err(e) => warn!("import: skipping {}: {:?}", crash.id, e),
+        }
+        progress.record(&crash.id, result.is_ok());
+        JobStateRepo::set(&state.db, &key, &progress).await?;
+    }
+    Ok(imported)
+}
+
+pub async fn run(state: AppState, source: PathBuf, rate: u32) {
+    let mut interval = tokio::time::interval(LEASE_PERIOD);
+    loop {
+        interval.tick().await;
+        if hold_lease(&state.db, LEASE, LEASE_PERIOD).await {
+            break;
+        }
+    }
+    match import(&state, source, rate).await {
+        Ok(count) => info!("import: finished, {} crashes imported", count),
+        Err(e) => error!("import: failed: {:?}", e),
+    }
+    release_lease(&state.db, LEASE).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_map_raw_crash() {
+        let raw = json!({
+            "ProductName": "Workrave",
+            "Version": "1.10.50",
+            "ReleaseChannel": "beta",
+            "uuid": "de1bb258-cbbf-4589-a673-34f800160918",
+            "submitted_timestamp": "2016-09-18T00:00:01.123456+02:00",
+            "InstallTime": 1474123456,
+            "Notes": ["ignored"],
+        });
+        let imported = map_raw_crash(&raw).unwrap();
+        assert_eq!(imported.product, "Workrave");
+        assert_eq!(imported.version, "1.10.50");
+        assert_eq!(
+            imported.submitted_at,
+            chrono::NaiveDate::from_ymd_opt(2016, 9, 17)
+                .unwrap()
+                .and_hms_micro_opt(22, 0, 1, 123456)
+        );
+        assert_eq!(
+            imported.annotations,
+            BTreeMap::from([
+                ("InstallTime".to_owned(), "1474123456".to_owned()),
+                ("ReleaseChannel".to_owned(), "beta".to_owned()),
+            ])
+        );
+
+        assert_eq!(map_raw_crash(&json!({ "ProductName": "Workrave" })), None);
+    }
+
+    #[test]
+    fn test_list_flat() {
+        let source =
+            std::env::temp_dir().join(format!("guardrail-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("b.json"), "{}").unwrap();
+        std::fs::write(source.join("b.dmp"), "").unwrap();
+        std::fs::write(source.join("a.json"), "{}").unwrap();
+        std::fs::write(source.join("notes.txt"), "").unwrap();
+
+        let crashes = list(&source).unwrap();
+        std::fs::remove_dir_all(&source).unwrap();
+
+        assert_eq!(crashes.len(), 2);
+        assert_eq!(crashes[0].id, "a");
+        assert_eq!(crashes[1].dump, source.join("b.dmp"));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut progress = Progress::default();
+        assert!(progress.is_pending("a"));
+
+        progress.record("a", true);
+        progress.record("b", false);
+        progress.record("c", true);
+        assert!(!progress.is_pending("a"));
+        assert!(progress.is_pending("b"));
+        assert!(!progress.is_pending("c"));
+        assert!(progress.is_pending("d"));
+
+        progress.record("b", true);
+        assert_eq!(
+            progress,
+            Progress {
+                after: Some("c".to_owned()),
+                failed: BTreeSet::new(),
+            }
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use chrono::{Duration, DurationRound, NaiveDateTime};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::data_providers::webhook;
+use crate::entity;
+use crate::model::alert::{AlertCreateDto, AlertRepo};
+use crate::model::webhook::WebhookRepo;
+
+/// Number of hourly buckets used to build the baseline.
+const HISTORY_HOURS: i64 = 7 * 24;
+/// Smoothing factor of the exponentially weighted moving average.
+const ALPHA: f64 = 0.1;
+/// Hours with fewer crashes than this never raise an alert.
+const MIN_COUNT: i64 = 5;
+const WARNING_SCORE: f64 = 3.0;
+const CRITICAL_SCORE: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub severity: Severity,
+    pub observed: i64,
+    pub expected: f64,
+    pub score: f64,
+}
+
+/// Compares the last bucket against an EWMA baseline of the preceding buckets.
+pub fn detect(counts: &[i64]) -> Option<Anomaly> {
+    let (&observed, history) = counts.split_last()?;
+    let (&first, rest) = history.split_first()?;
+
+    let mut mean = first as f64;
+    let mut variance = 0.0;
+    for &count in rest {
+        let diff = count as f64 - mean;
+        let increment = ALPHA * diff;
+        mean += increment;
+        variance = (1.0 - ALPHA) * (variance + diff * increment);
+    }
+
+    if observed < MIN_COUNT {
+        return None;
+    }
+
+    // Poisson noise as a floor, so that a perfectly flat history does not make every
+    // small change infinitely significant.
+    let deviation = variance.max(mean).max(1.0).sqrt();
+    let score = (observed as f64 - mean) / deviation;
+
+    let severity = if score >= CRITICAL_SCORE {
+        Severity::Critical
+    } else if score >= WARNING_SCORE {
+        Severity::Warning
+    } else {
+        return None;
+    };
+
+    Some(Anomaly {
+        severity,
+        observed,
+        expected: mean,
+        score,
+    })
+}
+
+/// Crash counts per product, per version and per environment of a product.
+type Series = HashMap<(Uuid, Option<Uuid>, Option<String>), Vec<i64>>;
+
+fn bucket(
+    crashes: Vec<(Uuid, Uuid, Option<String>, NaiveDateTime)>,
+    start: NaiveDateTime,
+    buckets: usize,
+) -> Series {
+    let mut series: Series = HashMap::new();
+    for (product_id, version_id, environment, created_at) in crashes {
+        let index = (created_at - start).num_hours();
+        if index < 0 || index as usize >= buckets {
+            continue;
+        }
+        let mut keys = vec![
+            (product_id, None, None),
+            (product_id, Some(version_id), None),
+        ];
+        if let Some(environment) = environment {
+            keys.push((product_id, None, Some(environment)));
+        }
+        for key in keys {
+            series.entry(key).or_insert_with(|| vec![0; buckets])[index as usize] += 1;
+        }
+    }
+    series
+}
+
+async fn check(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let now = chrono::Utc::now().naive_utc();
+    let end = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+    let start = end - Duration::hours(HISTORY_HOURS + 1);
+
+    let crashes = entity::prelude::Crash::find()
+        .select_only()
+        .column(entity::crash::Column::ProductId)
+        .column(entity::crash::Column::VersionId)
+        .column(entity::crash::Column::Environment)
+        .column(entity::crash::Column::CreatedAt)
+        .filter(entity::crash::Column::CreatedAt.gte(start))
+        .filter(entity::crash::Column::CreatedAt.lt(end))
+        .into_tuple::<(Uuid, Uuid, Option<String>, NaiveDateTime)>()
+        .all(db)
+        .await?;
+
+    let window_start = end - Duration::hours(1);
+    let series = bucket(crashes, start, HISTORY_HOURS as usize + 1);
+    for ((product_id, version_id, environment), counts) in series {
+        let Some(anomaly) = detect(&counts) else {
+            continue;
+        };
+
+        let scope = match (version_id, &environment) {
+            (Some(_), _) => "version".to_owned(),
+            (None, Some(environment)) => format!("product in {}", environment),
+            (None, None) => "product".to_owned(),
+        };
+        let message = format!(
+            "{} crashes in the hour starting {} for this {}, expected about {:.1} (score {:.1})",
+            anomaly.observed, window_start, scope, anomaly.expected, anomaly.score
+        );
+        let dto = AlertCreateDto {
+            severity: anomaly.severity.as_str().to_owned(),
+            message: message.clone(),
+            observed: anomaly.observed,
+            expected: anomaly.expected,
+            window_start,
+            product_id,
+            version_id,
+            environment: environment.clone(),
+        };
+        // The job runs again in the same hour after a restart, the alert is raised only once.
+        let Some(alert_id) = AlertRepo::raise(db, dto).await? else {
+            continue;
+        };
+        warn!(
+            "crash volume anomaly for product {}: {}",
+            product_id, message
+        );
+        WebhookRepo::notify(
+            db,
+            product_id,
+            webhook::ALERT,
+            serde_json::json!({
+                "alert_id": alert_id,
+                "severity": anomaly.severity.as_str(),
+                "message": message,
+                "observed": anomaly.observed,
+                "expected": anomaly.expected,
+                "window_start": window_start,
+                "version_id": version_id,
+                "environment": environment,
+            }),
+        );
+    }
+    Ok(())
+}
+
+pub async fn run(db: DatabaseConnection) {
+    let period = std::time::Duration::from_secs(60 * 60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "anomaly", period).await {
+            continue;
+        }
+        info!("checking crash volumes");
+        if let Err(e) = check(&db).await {
+            error!("crash volume anomaly detection failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let mut counts = vec![10, 12, 9, 11, 10, 13, 8, 10, 11, 12, 9, 10];
+
+        counts.push(12);
+        assert_eq!(detect(&counts), None);
+
+        counts.pop();
+        counts.push(22);
+        assert_eq!(detect(&counts).unwrap().severity, Severity::Warning);
+
+        counts.pop();
+        counts.push(60);
+        assert_eq!(detect(&counts).unwrap().severity, Severity::Critical);
+
+        assert_eq!(detect(&[0, 0, 0, 0, 3]), None);
+        assert_eq!(detect(&[4]), None);
+    }
+
+    #[test]
+    fn test_bucket() {
+        let start =
+            NaiveDateTime::parse_from_str("2024-08-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let product = Uuid::new_v4();
+        let version = Uuid::new_v4();
+        let staging = Some("staging".to_owned());
+        let crashes = vec![
+            (product, version, None, start + Duration::minutes(5)),
+            (
+                product,
+                version,
+                staging.clone(),
+                start + Duration::minutes(65),
+            ),
+            (product, version, None, start + Duration::minutes(70)),
+            (product, version, None, start + Duration::hours(5)),
+        ];
+
+        let series = bucket(crashes, start, 3);
+        assert_eq!(series[&(product, None, None)], vec![1, 2, 0]);
+        assert_eq!(series[&(product, Some(version), None)], vec![1, 2, 0]);
+        assert_eq!(series[&(product, None, staging)], vec![0, 1, 0]);
+        assert_eq!(series.len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use super::AttachmentProcessor;
+use crate::report::AttachmentFinding;
+
+/// Extensions of log files, which clients do not always upload as text.
+const LOG_EXTENSIONS: [&str; 3] = [".log", ".txt", ".out"];
+
+/// Maximum length of an extracted line, in characters.
+const MAX_LINE_LENGTH: usize = 500;
+
+/// Extracts the last error lines of text and log attachments.
+pub struct LogProcessor {
+    max_lines: usize,
+    patterns: Vec<String>,
+}
+
+impl LogProcessor {
+    pub const NAME: &'static str = "log";
+
+    pub fn new(max_lines: usize, patterns: &[String]) -> Self {
+        Self {
+            max_lines,
+            patterns: patterns
+                .iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    fn is_error(&self, line: &str) -> bool {
+        let line = line.to_lowercase();
+        self.patterns.iter().any(|pattern| line.contains(pattern))
+    }
+}
+
+impl AttachmentProcessor for LogProcessor {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn accepts(&self, mime_type: &str, filename: &str) -> bool {
+        let filename = filename.to_lowercase();
+        let filename = filename.strip_suffix(".zst").unwrap_or(&filename);
+        mime_type.starts_with("text/")
+            || LOG_EXTENSIONS
+                .iter()
+                .any(|extension| filename.ends_with(extension))
+    }
+
+    fn process(&self, attachment: &str, content: &str) -> Option<AttachmentFinding> {
+        let mut lines = VecDeque::with_capacity(self.max_lines);
+        let mut count = 0;
+        for line in content.lines().filter(|line| self.is_error(line)) {
+            count += 1;
+            if self.max_lines == 0 {
+                continue;
+            }
+            if lines.len() == self.max_lines {
+                lines.pop_front();
+            }
+            lines.push_back(line.trim_end().chars().take(MAX_LINE_LENGTH).collect());
+        }
+        if count == 0 {
+            return None;
+        }
+
+        Some(AttachmentFinding {
+            attachment: attachment.to_owned(),
+            processor: self.name().to_owned(),
+            summary: match count {
+                1 => "1 error line".to_owned(),
+                count if count > lines.len() => {
+                    format!("{} error lines, last {} shown", count, lines.len())
+                }
+                count => format!("{} error lines", count),
+            },
+            lines: lines.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(max_lines: usize) -> LogProcessor {
+        LogProcessor::new(max_lines, &["error".to_owned(), "FATAL".to_owned()])
+    }
+
+    #[test]
+    fn test_accepts() {
+        let processor = processor(2);
+        assert!(processor.accepts("text/plain", "attachment"));
+        assert!(processor.accepts("application/octet-stream", "/data/app.log.zst"));
+        assert!(processor.accepts("application/octet-stream", "Console.TXT"));
+        assert!(!processor.accepts("application/x-dmp", "crash.dmp"));
+    }
+
+    #[test]
+    fn test_process() {
+        let processor = processor(2);
+        let content =
+            "starting\nERROR: no config\nloading\nerror: retry failed\nfatal: out of memory\n";
+        let finding = processor.process("log", content).unwrap();
+        assert_eq!(finding.attachment, "log");
+        assert_eq!(finding.processor, LogProcessor::NAME);
+        assert_eq!(finding.summary, "3 error lines, last 2 shown");
+        assert_eq!(
+            finding.lines,
+            vec!["error: retry failed", "fatal: out of memory"]
+        );
+
+        let finding = processor.process("log", "fatal: crashed").unwrap();
+        assert_eq!(finding.summary, "1 error line");
+        assert!(processor.process("log", "all good\n").is_none());
+    }
+}
@@ -0,0 +1,244 @@
+mod log;
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::api::{metrics, ResponseCache};
+use crate::entity;
+use crate::report::AttachmentFinding;
+use crate::settings;
+use crate::utils::compression;
+
+// Attachment processors extract what matters for triage from the attachments of a crash, such
+// as the last error lines of a log file. Their findings are stored in the crash report under
+// `attachments`, next to the findings of the analyzers.
+//
+// Processors run in the background, after the upload, so that large attachments do not delay
+// it. Products enable processors by name in `attachment_processors`; attachments of products
+// without processors are left alone and are processed once a processor is enabled.
+
+pub use log::LogProcessor;
+
+const BATCH_SIZE: u64 = 100;
+
+pub trait AttachmentProcessor: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Returns whether the processor handles attachments of the content type and file name.
+    fn accepts(&self, mime_type: &str, filename: &str) -> bool;
+
+    fn process(&self, attachment: &str, content: &str) -> Option<AttachmentFinding>;
+}
+
+#[derive(Default)]
+pub struct AttachmentProcessors {
+    processors: Vec<Box<dyn AttachmentProcessor>>,
+}
+
+impl fmt::Debug for AttachmentProcessors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.processors.iter().map(|processor| processor.name()))
+            .finish()
+    }
+}
+
+/// Returns the names of the processors enabled in the `attachment_processors` of a product.
+fn enabled_processors(setting: Option<&str>) -> Vec<String> {
+    setting
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+impl AttachmentProcessors {
+    /// Returns all built-in processors.
+    pub fn from_settings(settings: &app::settings::AttachmentProcessing) -> Self {
+        let mut processors = Self::default();
+        processors.register(LogProcessor::new(
+            settings.error_lines,
+            &settings.error_patterns,
+        ));
+        processors
+    }
+
+    pub fn register(&mut self, processor: impl AttachmentProcessor + 'static) {
+        self.processors.push(Box::new(processor));
+    }
+
+    /// Runs the enabled processors that accept the attachment on its content.
+    pub fn run(
+        &self,
+        enabled: &[String],
+        attachment: &entity::attachment::Model,
+        content: &str,
+    ) -> Vec<AttachmentFinding> {
+        self.processors
+            .iter()
+            .filter(|processor| enabled.iter().any(|name| name == processor.name()))
+            .filter(|processor| processor.accepts(&attachment.mime_type, &attachment.filename))
+            .filter_map(|processor| processor.process(&attachment.name, content))
+            .collect()
+    }
+}
+
+/// Adds findings to a crash report, replacing earlier findings of the same processor for the
+/// same attachment.
+fn add_findings(report: &mut Value, findings: Vec<AttachmentFinding>) {
+    let Some(report) = report.as_object_mut() else {
+        return;
+    };
+    let mut all: Vec<AttachmentFinding> = report
+        .get("attachments")
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default();
+    all.retain(|finding| {
+        !findings
+            .iter()
+            .any(|new| new.attachment == finding.attachment && new.processor == finding.processor)
+    });
+    all.extend(findings);
+    report.insert("attachments".to_owned(), serde_json::json!(all));
+}
+
+/// Reads an attachment as text, or returns `None` when it is too large or cannot be read.
+async fn read_text(attachment: &entity::attachment::Model, max_size: u64) -> Option<String> {
+    if attachment.size < 0 || attachment.size as u64 > max_size {
+        return None;
+    }
+    let path = Path::new(&attachment.filename);
+    match compression::read_stored_file(path, attachment.compression.as_deref()).await {
+        Ok(content) => Some(String::from_utf8_lossy(&content).into_owned()),
+        Err(e) => {
+            warn!("failed to read attachment {:?}: {:?}", path, e);
+            None
+        }
+    }
+}
+
+/// Processes the attachments of products with enabled processors that were not processed yet.
+/// Returns the number of processed attachments.
+async fn process_pending(
+    db: &DatabaseConnection,
+    cache: &ResponseCache,
+    processors: &AttachmentProcessors,
+) -> Result<usize, DbErr> {
+    let enabled: HashMap<Uuid, Vec<String>> = entity::prelude::Product::find()
+        .filter(entity::product::Column::AttachmentProcessors.is_not_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|product| {
+            let names = enabled_processors(product.attachment_processors.as_deref());
+            (product.id, names)
+        })
+        .filter(|(_, names)| !names.is_empty())
+        .collect();
+    if enabled.is_empty() {
+        return Ok(0);
+    }
+
+    let max_size = settings().attachment_processing.max_size;
+    let mut processed = 0;
+    loop {
+        let attachments = entity::prelude::Attachment::find()
+            .find_also_related(entity::prelude::Crash)
+            .filter(entity::attachment::Column::ProcessedAt.is_null())
+            .filter(entity::crash::Column::ProductId.is_in(enabled.keys().cloned()))
+            .order_by_asc(entity::attachment::Column::CreatedAt)
+            .limit(BATCH_SIZE)
+            .all(db)
+            .await?;
+        if attachments.is_empty() {
+            return Ok(processed);
+        }
+
+        for (attachment, crash) in attachments {
+            let findings = match (crash.as_ref(), read_text(&attachment, max_size).await) {
+                (Some(crash), Some(content)) => {
+                    processors.run(&enabled[&crash.product_id], &attachment, &content)
+                }
+                _ => vec![],
+            };
+            if let (Some(crash), false) = (crash, findings.is_empty()) {
+                let product_id = crash.product_id;
+                let mut report = crash.report.clone();
+                add_findings(&mut report, findings);
+                let mut crash = crash.into_active_model();
+                crash.report = Set(report);
+                crash.update(db).await?;
+                cache.invalidate_product(product_id);
+            }
+
+            let mut attachment = attachment.into_active_model();
+            attachment.processed_at = Set(Some(chrono::Utc::now().naive_utc()));
+            attachment.update(db).await?;
+            processed += 1;
+        }
+    }
+}
+
+/// Processes new attachments every minute, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection, cache: Arc<ResponseCache>) {
+    let processors = AttachmentProcessors::from_settings(&settings().attachment_processing);
+    let period = std::time::Duration::from_secs(60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "attachment_processing", period).await {
+            continue;
+        }
+        match process_pending(&db, &cache, &processors).await {
+            Ok(0) => (),
+            Ok(processed) => {
+                metrics().record_attachments_processed(processed);
+                info!("processed {} attachments", processed);
+            }
+            Err(e) => error!("processing attachments failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_enabled_processors() {
+        assert_eq!(
+            enabled_processors(Some(" Log, ,other")),
+            vec!["log", "other"]
+        );
+        assert!(enabled_processors(None).is_empty());
+    }
+
+    #[test]
+    fn test_add_findings() {
+        let finding = |attachment: &str, summary: &str| AttachmentFinding {
+            attachment: attachment.to_owned(),
+            processor: LogProcessor::NAME.to_owned(),
+            summary: summary.to_owned(),
+            lines: vec![],
+        };
+        let mut report = json!({ "status": "OK" });
+        add_findings(&mut report, vec![finding("a", "old"), finding("b", "b")]);
+        add_findings(&mut report, vec![finding("a", "new")]);
+
+        let findings: Vec<AttachmentFinding> =
+            serde_json::from_value(report["attachments"].clone()).unwrap();
+        assert_eq!(findings, vec![finding("b", "b"), finding("a", "new")]);
+        assert_eq!(report["status"], "OK");
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::NaiveDateTime;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::data_providers::crash::BuildArtifact;
+use crate::entity;
+use crate::model::build_metadata::{is_finished, parse_metadata, BuildMetadataRepo};
+use crate::model::link_template::{crash_values, render_link, LinkTemplateRepo};
+use crate::settings;
+
+// Link templates with a `metadata_url` refer to the CI build that produced a crash, e.g.
+// `https://ci.example.com/api/builds?commit={commit}&build_id={build_id}`. This job renders the
+// metadata URLs for the crashes reported since its last run, fetches the metadata of builds it
+// has not fetched before, and fetches the metadata of builds that had not finished again once
+// it is older than `ci_builds.refresh`. See `BuildMetadataRepo`.
+
+const PERIOD: Duration = Duration::from_secs(60);
+
+/// Maximum number of crashes looked at per run; later crashes are left for the next run.
+const BATCH_SIZE: u64 = 500;
+
+/// Builds that have not finished after this many days are not fetched again.
+const MAX_REFRESH_DAYS: i64 = 7;
+
+struct CiBuilds {
+    client: reqwest::Client,
+    /// Creation time of the newest crash that was looked at.
+    since: NaiveDateTime,
+}
+
+impl CiBuilds {
+    fn new(settings: &app::settings::CiBuilds) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout))
+            .build()?;
+        Ok(Self {
+            client,
+            since: chrono::Utc::now().naive_utc() - chrono::Duration::days(1),
+        })
+    }
+
+    /// Fetches the metadata of a build.
+    async fn fetch(&self, url: &str) -> Result<(Option<String>, Vec<BuildArtifact>), String> {
+        let mut request = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT, "application/json");
+        if let Some(authorization) = settings()
+            .ci_builds
+            .authorization
+            .iter()
+            .find(|authorization| url.starts_with(&authorization.url_prefix))
+        {
+            request = request.header(reqwest::header::AUTHORIZATION, &authorization.header);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("CI server answered {}", response.status()));
+        }
+        let json: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        Ok(parse_metadata(&json))
+    }
+
+    /// Returns the metadata URLs of the builds of the crashes reported since the last run, by
+    /// link template.
+    async fn new_builds(
+        &mut self,
+        db: &DatabaseConnection,
+    ) -> Result<HashSet<(Uuid, String)>, DbErr> {
+        let templates = LinkTemplateRepo::get_with_metadata_url(db).await?;
+        if templates.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let products: HashSet<Uuid> = templates
+            .iter()
+            .map(|template| template.product_id)
+            .collect();
+        let crashes = entity::prelude::Crash::find()
+            .select_only()
+            .column(entity::crash::Column::Id)
+            .column(entity::crash::Column::ProductId)
+            .column(entity::crash::Column::VersionId)
+            .column(entity::crash::Column::CreatedAt)
+            .filter(entity::crash::Column::CreatedAt.gt(self.since))
+            .filter(entity::crash::Column::ProductId.is_in(products))
+            .order_by_asc(entity::crash::Column::CreatedAt)
+            .limit(BATCH_SIZE)
+            .into_tuple::<(Uuid, Uuid, Uuid, NaiveDateTime)>()
+            .all(db)
+            .await?;
+
+        let mut annotations: HashMap<Uuid, Vec<(String, String)>> = HashMap::new();
+        entity::prelude::Annotation::find()
+            .select_only()
+            .column(entity::annotation::Column::CrashId)
+            .column(entity::annotation::Column::Key)
+            .column(entity::annotation::Column::Value)
+            .filter(
+                entity::annotation::Column::CrashId
+                    .is_in(crashes.iter().map(|(id, ..)| *id).collect::<Vec<_>>()),
+            )
+            .into_tuple::<(Uuid, String, String)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .for_each(|(crash_id, key, value)| {
+                annotations.entry(crash_id).or_default().push((key, value))
+            });
+
+        let mut builds = HashSet::new();
+        for (id, product_id, version_id, created_at) in crashes {
+            self.since = self.since.max(created_at);
+            let values = crash_values(
+                id,
+                product_id,
+                version_id,
+                annotations.remove(&id).unwrap_or_default(),
+            );
+            for template in templates
+                .iter()
+                .filter(|template| template.product_id == product_id)
+            {
+                if let Some(url) = template
+                    .metadata_url
+                    .as_deref()
+                    .and_then(|metadata_url| render_link(metadata_url, &values))
+                {
+                    builds.insert((template.id, url));
+                }
+            }
+        }
+        Ok(builds)
+    }
+
+    /// Returns the metadata URLs of recent builds that had not finished when their metadata was
+    /// fetched, by link template.
+    async fn unfinished_builds(
+        db: &DatabaseConnection,
+        stale_before: NaiveDateTime,
+    ) -> Result<HashSet<(Uuid, String)>, DbErr> {
+        let recent = chrono::Utc::now().naive_utc() - chrono::Duration::days(MAX_REFRESH_DAYS);
+        Ok(entity::prelude::BuildMetadata::find()
+            .filter(entity::build_metadata::Column::FetchedAt.lt(stale_before))
+            .filter(entity::build_metadata::Column::CreatedAt.gt(recent))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|metadata| !is_finished(metadata.status.as_deref()))
+            .map(|metadata| (metadata.link_template_id, metadata.url))
+            .collect())
+    }
+
+    /// Fetches the metadata of new builds and of builds that had not finished. Returns the
+    /// number of fetched builds.
+    async fn refresh(&mut self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let stale_before = chrono::Utc::now().naive_utc()
+            - chrono::Duration::seconds(settings().ci_builds.refresh as i64);
+        let mut builds = self.new_builds(db).await?;
+        builds.extend(Self::unfinished_builds(db, stale_before).await?);
+
+        let mut fetched = 0;
+        for (link_template_id, url) in builds {
+            let metadata = BuildMetadataRepo::get(db, link_template_id, &url).await?;
+            if !BuildMetadataRepo::needs_fetch(metadata.as_ref(), stale_before) {
+                continue;
+            }
+            let result = self.fetch(&url).await;
+            if let Err(e) = &result {
+                warn!("failed to fetch build metadata from {}: {}", url, e);
+            }
+            BuildMetadataRepo::store(db, link_template_id, &url, result).await?;
+            fetched += 1;
+        }
+        Ok(fetched)
+    }
+}
+
+/// Fetches the metadata of CI builds every minute, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let mut builds = match CiBuilds::new(&settings().ci_builds) {
+        Ok(builds) => builds,
+        Err(e) => {
+            error!("CI build metadata not fetched: {:?}", e);
+            return;
+        }
+    };
+
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "ci_builds", PERIOD).await {
+            continue;
+        }
+        match builds.refresh(&db).await {
+            Ok(0) => (),
+            Ok(fetched) => info!("fetched metadata of {} CI builds", fetched),
+            Err(e) => error!("fetching CI build metadata failed: {:?}", e),
+        }
+    }
+}
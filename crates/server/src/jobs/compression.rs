@@ -0,0 +1,184 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::path::Path;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::entity;
+use crate::settings;
+use crate::utils::compression;
+
+const BATCH_SIZE: u64 = 100;
+const LEASE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Compresses attachments that were stored before compression was enabled. Attachments whose
+/// file is missing are skipped. Returns the number of compressed attachments.
+async fn compress_existing(
+    db: &DatabaseConnection,
+    method: &str,
+    level: i32,
+) -> Result<usize, DbErr> {
+    let mut last: Option<Uuid> = None;
+    let mut compressed = 0;
+    loop {
+        let mut query = entity::prelude::Attachment::find()
+            .filter(entity::attachment::Column::Compression.is_null())
+            .order_by_asc(entity::attachment::Column::Id)
+            .limit(BATCH_SIZE);
+        if let Some(last) = last {
+            query = query.filter(entity::attachment::Column::Id.gt(last));
+        }
+        let attachments = query.all(db).await?;
+        let Some(attachment) = attachments.last() else {
+            return Ok(compressed);
+        };
+        last = Some(attachment.id);
+
+        for attachment in attachments {
+            let path = Path::new(&attachment.filename);
+            if !path.is_file() {
+                continue;
+            }
+            let file = if compression::from_path(path).is_some() {
+                path.to_path_buf()
+            } else {
+                match compression::compress_file(path, level).await {
+                    Ok(file) => file,
+                    Err(e) => {
+                        warn!("failed to compress {:?}: {:?}", path, e);
+                        continue;
+                    }
+                }
+            };
+
+            let mut model = attachment.into_active_model();
+            model.filename = Set(file.to_string_lossy().into_owned());
+            model.compression = Set(Some(method.to_owned()));
+            model.update(db).await?;
+            compressed += 1;
+        }
+    }
+}
+
+/// Compresses the attachments stored before compression was enabled. Runs once at startup, on
+/// the replica that gets the lease.
+pub async fn run(db: DatabaseConnection) {
+    let Some(method) = compression::configured() else {
+        return;
+    };
+    if !super::hold_lease(&db, "compression", LEASE).await {
+        return;
+    }
+    match compress_existing(&db, method, settings().storage.compression_level).await {
+        Ok(0) => (),
+        Ok(compressed) => info!("compressed {} stored attachments", compressed),
+        Err(e) => error!("compressing stored attachments failed: {:?}", e),
+    }
+    super::release_lease(&db, "compression").await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::base::Repo;
+    use crate::model::product::ProductCreateDto;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+
+    #[tokio::test]
+    async fn test_compress_existing() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let product_id = Repo::create(&db, ProductCreateDto::named("Workrave"))
+            .await
+            .unwrap();
+        let version_id = Repo::create(
+            &db,
+            entity::version::CreateModel {
+                name: "1.11".to_owned(),
+                hash: "hash".to_owned(),
+                tag: "v1.11".to_owned(),
+                product_id,
+                channel: None,
+                release_date: None,
+            },
+        )
+        .await
+        .unwrap();
+        let crash_id = Repo::create(
+            &db,
+            entity::crash::CreateModel {
+                report: serde_json::json!({}),
+                summary: "summary".to_owned(),
+                version_id,
+                product_id,
+                user_agent: None,
+                sdk: None,
+                promoted: None,
+                minidump_hash: None,
+                platform: None,
+                environment: None,
+                issue_id: None,
+                authenticated: true,
+                legal_hold_at: None,
+                legal_hold_by: None,
+                legal_hold_reason: None,
+                verified_checksum: None,
+                signature: None,
+                processing_status: "done".to_owned(),
+                processing_error: None,
+                received_at: None,
+                clock_skew: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("crash.dmp");
+        std::fs::write(&file, b"MDMP".repeat(256)).unwrap();
+
+        let attachment = |filename: &Path| entity::attachment::CreateModel {
+            name: "minidump".to_owned(),
+            mime_type: "application/x-dmp".to_owned(),
+            size: 1024,
+            filename: filename.to_string_lossy().into_owned(),
+            crash_id,
+            compression: None,
+            processed_at: None,
+        };
+        let stored = Repo::create(&db, attachment(&file)).await.unwrap();
+        let missing = Repo::create(&db, attachment(&dir.join("missing.dmp")))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            compress_existing(&db, compression::ZSTD, 3).await.unwrap(),
+            1
+        );
+
+        let stored = Repo::get_by_id::<entity::attachment::Entity>(&db, stored)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.compression.as_deref(), Some(compression::ZSTD));
+        assert!(stored.filename.ends_with("crash.dmp.zst"));
+        assert!(!file.exists());
+        let content = compression::read_file(Path::new(&stored.filename), Some(compression::ZSTD))
+            .await
+            .unwrap();
+        assert_eq!(content, b"MDMP".repeat(256));
+
+        let missing = Repo::get_by_id::<entity::attachment::Entity>(&db, missing)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(missing.compression, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
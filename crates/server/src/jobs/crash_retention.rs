@@ -0,0 +1,183 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use sea_orm::sea_query::Query;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect,
+};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::entity;
+use crate::model::partition::{month_of, next_month, PartitionRepo};
+use crate::settings;
+use crate::utils::storage_path::remove_empty_parents;
+
+// Crashes are deleted once they are older than `maintenance.crash_retention_days`, unless they
+// are on legal hold. On PostgreSQL, the crash and annotation tables are partitioned by month:
+// months that expired as a whole are dropped at once, unless they contain a crash on legal hold.
+// The remaining expired crashes, e.g. of the month that is only partly expired, are deleted in
+// batches. The attachment files are deleted first in both cases. The age of a crash is based on
+// the time the server received it, see `CrashRepo::create_unique`.
+
+async fn remove_file(path: &Path) {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            warn!("failed to remove {:?}: {:?}", path, e)
+        }
+        _ => (),
+    }
+}
+
+/// Deletes the files of one batch of attachments of the crashes matching `crashes`, and the
+/// attachments. Returns the number of deleted attachments.
+async fn delete_attachments(
+    db: &DatabaseConnection,
+    crashes: Condition,
+    batch_size: u64,
+) -> Result<u64, DbErr> {
+    let attachments = entity::prelude::Attachment::find()
+        .filter(
+            entity::attachment::Column::CrashId.in_subquery(
+                Query::select()
+                    .column(entity::crash::Column::Id)
+                    .from(entity::crash::Entity)
+                    .cond_where(crashes)
+                    .to_owned(),
+            ),
+        )
+        .limit(batch_size)
+        .all(db)
+        .await?;
+    if attachments.is_empty() {
+        return Ok(0);
+    }
+
+    for attachment in &attachments {
+        let path = Path::new(&attachment.filename);
+        remove_file(path).await;
+        remove_empty_parents(path).await;
+    }
+    let result = entity::prelude::Attachment::delete_many()
+        .filter(entity::attachment::Column::Id.is_in(attachments.iter().map(|a| a.id)))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Drops the partitions of the months that ended before `before` and have no crashes on legal
+/// hold, after deleting the attachments of their crashes. Returns the dropped months.
+async fn drop_expired_months(
+    db: &DatabaseConnection,
+    before: NaiveDateTime,
+    batch_size: u64,
+) -> Result<Vec<NaiveDate>, DbErr> {
+    let mut dropped = Vec::new();
+    for month in PartitionRepo::months(db).await? {
+        let end = next_month(month);
+        if end > before.date() {
+            break;
+        }
+        if !PartitionRepo::is_droppable(db, month).await? {
+            info!("keeping partitions of {}, crashes are on legal hold", month);
+            continue;
+        }
+        let crashes = Condition::all()
+            .add(entity::crash::Column::CreatedAt.gte(month.and_time(NaiveTime::MIN)))
+            .add(entity::crash::Column::CreatedAt.lt(end.and_time(NaiveTime::MIN)));
+        while delete_attachments(db, crashes.clone(), batch_size).await? == batch_size {}
+        PartitionRepo::drop_month(db, month).await?;
+        dropped.push(month);
+    }
+    Ok(dropped)
+}
+
+/// Deletes the crashes created before `before` that are not on legal hold, in batches,
+/// including their attachment files. Returns the number of deleted crashes.
+async fn delete_expired_crashes(
+    db: &DatabaseConnection,
+    before: NaiveDateTime,
+    batch_size: u64,
+) -> Result<u64, DbErr> {
+    let mut deleted = 0;
+    loop {
+        let ids: Vec<Uuid> = entity::prelude::Crash::find()
+            .select_only()
+            .column(entity::crash::Column::Id)
+            .filter(entity::crash::Column::CreatedAt.lt(before))
+            .filter(entity::crash::Column::LegalHoldAt.is_null())
+            .limit(batch_size)
+            .into_tuple()
+            .all(db)
+            .await?;
+        if ids.is_empty() {
+            return Ok(deleted);
+        }
+
+        let crashes = Condition::all().add(entity::crash::Column::Id.is_in(ids.clone()));
+        while delete_attachments(db, crashes.clone(), batch_size).await? == batch_size {}
+        // Annotations are removed by the cascade, or by the trigger on PostgreSQL.
+        let result = entity::prelude::Crash::delete_many()
+            .filter(entity::crash::Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+        deleted += result.rows_affected;
+    }
+}
+
+/// Creates the partitions of the current month and the months ahead.
+async fn create_partitions(db: &DatabaseConnection, now: NaiveDateTime) -> Result<(), DbErr> {
+    let mut month = month_of(now);
+    for _ in 0..=settings().maintenance.partition_months_ahead {
+        PartitionRepo::create(db, month).await?;
+        month = next_month(month);
+    }
+    Ok(())
+}
+
+async fn maintain(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let config = &settings().maintenance;
+    let now = chrono::Utc::now().naive_utc();
+    let partitioned = PartitionRepo::is_partitioned(db).await?;
+    if partitioned {
+        create_partitions(db, now).await?;
+    }
+
+    let Some(days) = config.crash_retention_days else {
+        return Ok(());
+    };
+    let before = now - chrono::Duration::days(days);
+    let batch_size = config.crash_batch_size.max(1);
+    if partitioned {
+        for month in drop_expired_months(db, before, batch_size).await? {
+            info!("dropped the crashes of {}", month);
+        }
+    }
+    let deleted = delete_expired_crashes(db, before, batch_size).await?;
+    if deleted > 0 {
+        info!("deleted {} expired crashes", deleted);
+    }
+    Ok(())
+}
+
+/// Maintains the partitions of the crash tables and deletes expired crashes once a day, in the
+/// hour configured by `maintenance.hour`, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let period = Duration::from_secs(60 * 60);
+    let mut interval = tokio::time::interval(period);
+    let mut last_run: Option<NaiveDate> = None;
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        if now.hour() != settings().maintenance.hour || last_run == Some(now.date_naive()) {
+            continue;
+        }
+        if !super::hold_lease(&db, "crash_retention", period).await {
+            continue;
+        }
+        last_run = Some(now.date_naive());
+        if let Err(e) = maintain(&db).await {
+            error!("crash retention failed: {:?}", e);
+        }
+    }
+}
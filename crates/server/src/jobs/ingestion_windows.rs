@@ -0,0 +1,119 @@
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::analyzers::TAG_KEY;
+use crate::api::{MinidumpApi, PROCESSING_QUEUED_TAG};
+use crate::app_state::AppState;
+use crate::entity;
+use crate::entity::sea_orm_active_enums::AnnotationKind;
+use crate::model::crash::STATUS_FAILED;
+use crate::model::product::IngestionSchedule;
+
+// Crashes uploaded while their product is in an ingestion window that queues crashes are stored
+// with the `processing-queued` tag, but their minidump is not processed. Once the window has
+// ended, this job processes them as if they were reprocessed. The windows are evaluated again
+// here, so the crashes of a product stay queued while any of its windows is in effect.
+
+const PERIOD: Duration = Duration::from_secs(60);
+
+const BATCH_SIZE: u64 = 50;
+
+/// Returns queued crashes of a product, oldest first.
+async fn queued(
+    db: &DatabaseConnection,
+    product_id: Uuid,
+) -> Result<Vec<entity::crash::Model>, DbErr> {
+    entity::prelude::Crash::find()
+        .inner_join(entity::prelude::Annotation)
+        .filter(entity::crash::Column::ProductId.eq(product_id))
+        .filter(entity::annotation::Column::Key.eq(TAG_KEY))
+        .filter(entity::annotation::Column::Value.eq(PROCESSING_QUEUED_TAG))
+        .filter(entity::annotation::Column::Kind.eq(AnnotationKind::System))
+        .order_by_asc(entity::crash::Column::CreatedAt)
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await
+}
+
+/// Takes a crash that could not be processed out of the queue, so that it is not tried again
+/// every period, and marks it as failed. It can still be reprocessed from the crash page.
+async fn unqueue(db: &DatabaseConnection, crash_id: Uuid, error: &str) -> Result<(), DbErr> {
+    entity::prelude::Crash::update_many()
+        .col_expr(
+            entity::crash::Column::Summary,
+            Expr::value(format!("Processing failed: {}", error)),
+        )
+        .col_expr(
+            entity::crash::Column::ProcessingStatus,
+            Expr::value(STATUS_FAILED),
+        )
+        .col_expr(
+            entity::crash::Column::ProcessingError,
+            Expr::value(error.to_owned()),
+        )
+        .filter(entity::crash::Column::Id.eq(crash_id))
+        .exec(db)
+        .await?;
+    entity::prelude::Annotation::delete_many()
+        .filter(entity::annotation::Column::CrashId.eq(crash_id))
+        .filter(entity::annotation::Column::Key.eq(TAG_KEY))
+        .filter(entity::annotation::Column::Value.eq(PROCESSING_QUEUED_TAG))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Processes the queued crashes of products outside their ingestion windows while this replica
+/// holds the lease. Returns the number of processed crashes.
+async fn process_queued(state: &AppState) -> Result<usize, DbErr> {
+    let mut processed = 0;
+    let products = entity::prelude::Product::find().all(&state.db).await?;
+    for product in products {
+        let now = chrono::Utc::now().naive_utc();
+        if IngestionSchedule::from(&product).active(now).is_some() {
+            continue;
+        }
+        loop {
+            let crashes = queued(&state.db, product.id).await?;
+            if crashes.is_empty() {
+                break;
+            }
+            for crash in crashes {
+                if !super::hold_lease(&state.db, "ingestion_windows", PERIOD).await {
+                    return Ok(processed);
+                }
+                let crash_id = crash.id;
+                match MinidumpApi::reprocess(state, crash).await {
+                    Ok(()) => processed += 1,
+                    Err(e) => {
+                        warn!("processing queued crash {} failed: {}", crash_id, e);
+                        unqueue(&state.db, crash_id, &e.to_string()).await?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(processed)
+}
+
+/// Processes crashes queued during ingestion windows every minute, on the replica that holds
+/// the lease.
+pub async fn run(state: AppState) {
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&state.db, "ingestion_windows", PERIOD).await {
+            continue;
+        }
+        match process_queued(&state).await {
+            Ok(0) => (),
+            Ok(processed) => info!("processed {} queued crashes", processed),
+            Err(e) => error!("processing queued crashes failed: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::api::IssueCounts;
+
+/// Writes the crashes counted per issue every 10 seconds, which bounds how far the counts in
+/// issue lists lag behind.
+pub async fn run(db: DatabaseConnection, counts: Arc<IssueCounts>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
+    loop {
+        interval.tick().await;
+        match counts.flush(&db).await {
+            Ok(0) => (),
+            Ok(updated) => debug!("counted crashes of {} issues", updated),
+            Err(e) => error!("counting issue crashes failed: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::model::issue::IssueRepo;
+
+// Snoozed issues are opened again once their snooze ends, and issues with auto-resolve are
+// resolved once they have had no crashes for their number of days, see `IssueRepo`.
+
+const PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Wakes snoozed issues and auto-resolves inactive issues every ten minutes, on the replica that
+/// holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "issue_noise", PERIOD).await {
+            continue;
+        }
+        let now = chrono::Utc::now().naive_utc();
+        match IssueRepo::wake_snoozed(&db, now).await {
+            Ok(0) => (),
+            Ok(woken) => info!("opened {} issues after their snooze ended", woken),
+            Err(e) => error!("opening snoozed issues failed: {:?}", e),
+        }
+        match IssueRepo::auto_resolve(&db, now).await {
+            Ok(resolved) if resolved.is_empty() => (),
+            Ok(resolved) => info!(
+                audit = "issue",
+                "issues {:?} resolved automatically after a period without crashes", resolved
+            ),
+            Err(e) => error!("resolving inactive issues failed: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+mod anomaly;
+mod attachments;
+mod ci_builds;
+mod compression;
+mod crash_retention;
+mod ingestion_windows;
+mod issue_counts;
+mod issue_noise;
+mod notifications;
+mod product_cleanup;
+mod promotion;
+mod session_cleanup;
+mod signature_backfill;
+mod staging_cleanup;
+mod symbol_conversion;
+mod symbol_provider;
+mod token_usage;
+mod upload_counts;
+
+use sea_orm::DatabaseConnection;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::error;
+
+use crate::app_state::AppState;
+use crate::model::job_lease::JobLeaseRepo;
+
+// Every replica starts the jobs, but jobs that must not run on several replicas at the same
+// time only do their work while the replica holds the job's lease, see `JobLeaseRepo`. The
+// cleanup of resumable uploads runs on every replica, as each may have its own staging
+// directory, and so does writing the submissions of device keys, as each replica counts its own.
+
+/// Identifies this replica as holder of job leases.
+fn instance() -> &'static str {
+    static INSTANCE: OnceLock<String> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "guardrail".to_owned());
+        format!("{}-{}", host, uuid::Uuid::new_v4())
+    })
+}
+
+/// Returns whether this replica should run the job now, acquiring or renewing its lease. The
+/// lease lasts two periods of the job, so that the replica keeps the job as long as it runs,
+/// and another replica takes over soon after it stops.
+pub(crate) async fn hold_lease(db: &DatabaseConnection, job: &str, period: Duration) -> bool {
+    let duration = chrono::Duration::from_std(period * 2).unwrap_or(chrono::Duration::hours(1));
+    JobLeaseRepo::acquire(db, job, instance(), duration)
+        .await
+        .unwrap_or_else(|e| {
+            error!("failed to acquire lease of job {}: {:?}", job, e);
+            false
+        })
+}
+
+pub(crate) async fn release_lease(db: &DatabaseConnection, job: &str) {
+    if let Err(e) = JobLeaseRepo::release(db, job, instance()).await {
+        error!("failed to release lease of job {}: {:?}", job, e);
+    }
+}
+
+/// Starts the periodic background jobs. Jobs that change data served by the read API
+/// invalidate the affected responses in the cache of `state`.
+pub fn start(state: &AppState) {
+    let db = state.db.clone();
+    let cache = state.cache.clone();
+    tokio::spawn(anomaly::run(db.clone()));
+    tokio::spawn(attachments::run(db.clone(), cache.clone()));
+    tokio::spawn(ci_builds::run(db.clone()));
+    tokio::spawn(compression::run(db.clone()));
+    tokio::spawn(crash_retention::run(db.clone()));
+    tokio::spawn(ingestion_windows::run(state.clone()));
+    tokio::spawn(issue_counts::run(db.clone(), state.issue_counts.clone()));
+    tokio::spawn(issue_noise::run(db.clone()));
+    tokio::spawn(notifications::run(db.clone()));
+    tokio::spawn(product_cleanup::run(db.clone(), cache.clone()));
+    tokio::spawn(promotion::run(db.clone(), cache));
+    tokio::spawn(session_cleanup::run(db.clone()));
+    tokio::spawn(signature_backfill::run(db.clone()));
+    tokio::spawn(staging_cleanup::run());
+    tokio::spawn(symbol_conversion::run(db.clone()));
+    tokio::spawn(symbol_provider::run(db.clone()));
+    tokio::spawn(token_usage::run(db.clone(), state.usage.clone()));
+    tokio::spawn(upload_counts::run(db));
+}
@@ -0,0 +1,230 @@
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::model::notification::{self, Notification, NotificationRepo};
+use crate::settings;
+
+// Notifications are queued in the notification table when a crash with a new signature is
+// reported, see `NotificationRepo::notify`, and sent here. Notifications that cannot be sent are
+// retried with a growing delay, up to `notifications.max_attempts` times.
+
+const PERIOD: Duration = Duration::from_secs(30);
+const TIMEOUT: Duration = Duration::from_secs(10);
+const BATCH_SIZE: u64 = 100;
+
+type Mailer = AsyncSmtpTransport<Tokio1Executor>;
+
+fn mailer(smtp: &app::settings::Smtp) -> Result<Mailer, lettre::transport::smtp::Error> {
+    let builder = match smtp.tls {
+        true => Mailer::relay(&smtp.host)?,
+        false => Mailer::starttls_relay(&smtp.host)?,
+    };
+    let mut builder = builder.port(smtp.port).timeout(Some(TIMEOUT));
+    if !smtp.username.is_empty() {
+        builder = builder.credentials(Credentials::new(
+            smtp.username.clone(),
+            smtp.password.clone(),
+        ));
+    }
+    Ok(builder.build())
+}
+
+/// Returns the address of a page of the web interface.
+fn link(path: &str) -> String {
+    let server = &settings().server;
+    format!(
+        "{}{}{}",
+        server.site.trim_end_matches('/'),
+        server.path_prefix,
+        path
+    )
+}
+
+/// Returns the text of the email of a notification, from the data of the event.
+fn email_body(payload: &Value) -> String {
+    let field = |name: &str| match &payload[name] {
+        Value::String(value) => value.clone(),
+        Value::Null => "-".to_owned(),
+        value => value.to_string(),
+    };
+    let mut body = format!(
+        "A crash with a new signature was reported for {} {}.\n\n\
+         Signature: {}\n\
+         Title: {}\n",
+        field("product"),
+        field("version"),
+        field("signature"),
+        field("title")
+    );
+    if let Some(issue_id) = payload["issue_id"].as_str() {
+        body.push_str(&format!(
+            "Issue: {}\n",
+            link(&format!("/issue?issue={}", issue_id))
+        ));
+    }
+    if let Some(crash_id) = payload["crash_id"].as_str() {
+        body.push_str(&format!(
+            "Crash: {}\n",
+            link(&format!("/crash?crash={}", crash_id))
+        ));
+    }
+    body
+}
+
+struct Sender {
+    client: reqwest::Client,
+    mailer: Option<Mailer>,
+    from: Option<Mailbox>,
+}
+
+impl Sender {
+    fn new(settings: &app::settings::Notifications) -> Self {
+        let mailer = match settings.email_recipients.is_empty() {
+            true => None,
+            false => mailer(&settings.smtp)
+                .inspect_err(|e| error!("email notifications disabled: {:?}", e))
+                .ok(),
+        };
+        let from = settings
+            .smtp
+            .from
+            .parse()
+            .inspect_err(|e| error!("invalid sender of notifications: {:?}", e))
+            .ok();
+        Self {
+            client: reqwest::Client::new(),
+            mailer,
+            from,
+        }
+    }
+
+    async fn send_email(&self, notification: &Notification) -> Result<(), String> {
+        let (Some(mailer), Some(from)) = (&self.mailer, &self.from) else {
+            return Err("email is not configured".to_owned());
+        };
+        let to: Mailbox = notification
+            .recipient
+            .parse()
+            .map_err(|e| format!("invalid recipient: {}", e))?;
+        let email = Message::builder()
+            .from(from.clone())
+            .to(to)
+            .subject(&notification.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email_body(&notification.payload))
+            .map_err(|e| e.to_string())?;
+        mailer
+            .send(email)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn send_webhook(&self, notification: &Notification) -> Result<(), String> {
+        let body = serde_json::json!({
+            "id": notification.id,
+            "event": notification.event,
+            "product_id": notification.product_id,
+            "subject": notification.subject,
+            "timestamp": notification.created_at.and_utc().to_rfc3339(),
+            "data": notification.payload,
+        });
+        let response = self
+            .client
+            .post(&notification.recipient)
+            .timeout(TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "Guardrail")
+            .header("X-Guardrail-Event", &notification.event)
+            .header("X-Guardrail-Delivery", notification.id.to_string())
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(response.status().to_string()),
+        }
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        match notification.channel.as_str() {
+            notification::EMAIL => self.send_email(notification).await,
+            notification::WEBHOOK => self.send_webhook(notification).await,
+            channel => Err(format!("unknown channel {}", channel)),
+        }
+    }
+
+    /// Sends the notifications that are due. Returns the number of sent notifications.
+    async fn send_due(&self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let config = &settings().notifications;
+        let now = chrono::Utc::now().naive_utc();
+        let mut sent = 0;
+        for notification in NotificationRepo::get_due(db, now, BATCH_SIZE).await? {
+            let result = self.send(&notification).await;
+            if let Err(e) = &result {
+                warn!(
+                    "failed to send {} notification to {}: {}",
+                    notification.channel, notification.recipient, e
+                );
+            } else {
+                sent += 1;
+            }
+            NotificationRepo::record_attempt(
+                db,
+                notification,
+                result,
+                config.max_attempts,
+                config.retry_delay,
+            )
+            .await?;
+        }
+        Ok(sent)
+    }
+}
+
+/// Sends queued notifications every 30 seconds, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let sender = Sender::new(&settings().notifications);
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "notifications", PERIOD).await {
+            continue;
+        }
+        match sender.send_due(&db).await {
+            Ok(0) => (),
+            Ok(sent) => info!("sent {} notifications", sent),
+            Err(e) => error!("sending notifications failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_body() {
+        let payload = serde_json::json!({
+            "product": "Workrave",
+            "version": "1.11.0",
+            "signature": "workrave::Timer::tick",
+            "title": "EXCEPTION_ACCESS_VIOLATION_READ",
+        });
+        let body = email_body(&payload);
+        assert!(body.starts_with("A crash with a new signature was reported for Workrave 1.11.0."));
+        assert!(body.contains("Signature: workrave::Timer::tick\n"));
+        assert!(!body.contains("Issue:"));
+        assert_eq!(
+            email_body(&Value::Null).lines().nth(2),
+            Some("Signature: -")
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QuerySelect, Set, TransactionTrait,
+};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::api::ResponseCache;
+use crate::entity;
+use crate::model::crash::CrashRepo;
+use crate::model::product_deletion::{ProductDeletion, ProductDeletionRepo, STATE_DONE};
+use crate::utils::storage_path::remove_empty_parents;
+
+const BATCH_SIZE: u64 = 100;
+
+async fn remove_file(path: &Path) {
+    let result = if path.is_dir() {
+        tokio::fs::remove_dir_all(path).await
+    } else {
+        tokio::fs::remove_file(path).await
+    };
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            warn!("failed to remove {:?}: {:?}", path, e)
+        }
+        _ => (),
+    }
+}
+
+/// Deletes one batch of crashes, including their attachment files. Crashes on legal hold are
+/// kept. Returns the number of deleted crashes.
+async fn delete_crashes(db: &DatabaseConnection, product_id: Uuid) -> Result<u64, DbErr> {
+    let ids: Vec<Uuid> = entity::prelude::Crash::find()
+        .select_only()
+        .column(entity::crash::Column::Id)
+        .filter(entity::crash::Column::ProductId.eq(product_id))
+        .filter(entity::crash::Column::LegalHoldAt.is_null())
+        .limit(BATCH_SIZE)
+        .into_tuple()
+        .all(db)
+        .await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let attachments = entity::prelude::Attachment::find()
+        .filter(entity::attachment::Column::CrashId.is_in(ids.clone()))
+        .all(db)
+        .await?;
+    for attachment in attachments {
+        let path = Path::new(&attachment.filename);
+        remove_file(path).await;
+        remove_empty_parents(path).await;
+    }
+
+    // Annotations and attachments are removed by the cascade, or by the trigger on PostgreSQL.
+    let result = entity::prelude::Crash::delete_many()
+        .filter(entity::crash::Column::Id.is_in(ids))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Deletes one batch of symbols, including their files. Returns the number of deleted symbols.
+async fn delete_symbols(db: &DatabaseConnection, product_id: Uuid) -> Result<u64, DbErr> {
+    let symbols = entity::prelude::Symbols::find()
+        .filter(entity::symbols::Column::ProductId.eq(product_id))
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await?;
+    if symbols.is_empty() {
+        return Ok(0);
+    }
+
+    for symbol in &symbols {
+        let path = Path::new(&symbol.file_location);
+        remove_file(path).await;
+        remove_empty_parents(path).await;
+    }
+
+    let result = entity::prelude::Symbols::delete_many()
+        .filter(entity::symbols::Column::Id.is_in(symbols.iter().map(|s| s.id)))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Deletes one batch of conversions of native debug files, including the uploaded files. Returns
+/// the number of deleted conversions.
+async fn delete_symbol_conversions(
+    db: &DatabaseConnection,
+    product_id: Uuid,
+) -> Result<u64, DbErr> {
+    let conversions = entity::prelude::SymbolConversion::find()
+        .filter(entity::symbol_conversion::Column::ProductId.eq(product_id))
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await?;
+    if conversions.is_empty() {
+        return Ok(0);
+    }
+
+    for conversion in &conversions {
+        let path = Path::new(&conversion.file_location);
+        remove_file(path).await;
+        remove_empty_parents(path).await;
+    }
+
+    let result = entity::prelude::SymbolConversion::delete_many()
+        .filter(entity::symbol_conversion::Column::Id.is_in(conversions.iter().map(|c| c.id)))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Runs a deletion to completion. Progress is stored after every batch, so an interrupted
+/// deletion resumes where it left off.
+async fn process(db: &DatabaseConnection, deletion: ProductDeletion) -> Result<(), DbErr> {
+    info!(
+        "deleting product {} ({})",
+        deletion.product_name, deletion.product_id
+    );
+    let mut crashes_deleted = deletion.crashes_deleted;
+    let mut symbols_deleted = deletion.symbols_deleted;
+    let mut model = deletion.clone().into_active_model();
+
+    loop {
+        let deleted = delete_crashes(db, deletion.product_id).await?;
+        if deleted == 0 {
+            break;
+        }
+        crashes_deleted += deleted as i64;
+        model.crashes_deleted = Set(crashes_deleted);
+        model = model.save(db).await?;
+    }
+
+    while delete_symbol_conversions(db, deletion.product_id).await? > 0 {}
+
+    loop {
+        let deleted = delete_symbols(db, deletion.product_id).await?;
+        if deleted == 0 {
+            break;
+        }
+        symbols_deleted += deleted as i64;
+        model.symbols_deleted = Set(symbols_deleted);
+        model = model.save(db).await?;
+    }
+
+    // The product is kept, and the deletion stays pending, until the legal holds of its
+    // remaining crashes are released.
+    let held =
+        CrashRepo::count_held(db, entity::crash::Column::ProductId, deletion.product_id).await?;
+    if held > 0 {
+        debug!(
+            "product {} has {} crashes on legal hold, not deleted yet",
+            deletion.product_name, held
+        );
+        return Ok(());
+    }
+
+    // Versions, roles and the remaining per-product configuration are removed by the cascade.
+    let txn = db.begin().await?;
+    entity::prelude::Product::delete_by_id(deletion.product_id)
+        .exec(&txn)
+        .await?;
+    model.state = Set(STATE_DONE.to_owned());
+    model.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
+    model.save(&txn).await?;
+    txn.commit().await?;
+
+    info!(
+        "deleted product {}: {} crashes, {} symbols",
+        deletion.product_name, crashes_deleted, symbols_deleted
+    );
+    Ok(())
+}
+
+pub async fn run(db: DatabaseConnection, cache: Arc<ResponseCache>) {
+    let period = std::time::Duration::from_secs(60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "product_cleanup", period).await {
+            continue;
+        }
+        let pending = match ProductDeletionRepo::get_pending(&db).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                error!("failed to get pending product deletions: {:?}", e);
+                continue;
+            }
+        };
+        for deletion in pending {
+            let product_id = deletion.product_id;
+            if let Err(e) = process(&db, deletion).await {
+                error!("product deletion failed: {:?}", e);
+            }
+            cache.invalidate_product(product_id);
+        }
+    }
+}
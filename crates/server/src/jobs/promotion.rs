@@ -0,0 +1,166 @@
+use chrono::NaiveDateTime;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::api::ResponseCache;
+use crate::entity;
+use crate::model::annotation::{promote, promoted_keys};
+use crate::model::job_state::JobStateRepo;
+
+const BATCH_SIZE: u64 = 500;
+
+/// Promotion progress of a product: the configured keys and the creation time and id of the
+/// newest annotation that has been copied to the `promoted` column. Annotations are processed
+/// in (creation time, id) order, so that annotations created at the same time as the last one
+/// of a batch are not skipped. The progress is stored in the database, so that a restart or
+/// another replica continues where the job stopped.
+#[derive(Debug, Serialize, Deserialize)]
+struct Progress {
+    keys: Vec<String>,
+    since: NaiveDateTime,
+    last_id: Uuid,
+}
+
+impl Progress {
+    fn new(keys: Vec<String>) -> Self {
+        Self {
+            keys,
+            since: NaiveDateTime::default(),
+            last_id: Uuid::nil(),
+        }
+    }
+}
+
+fn state_key(product_id: Uuid) -> String {
+    format!("promotion/{}", product_id)
+}
+
+async fn clear(db: &DatabaseConnection, product_id: Uuid) -> Result<(), DbErr> {
+    entity::prelude::Crash::update_many()
+        .col_expr(
+            entity::crash::Column::Promoted,
+            Expr::value(None::<serde_json::Value>),
+        )
+        .filter(entity::crash::Column::ProductId.eq(product_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Recomputes the `promoted` column of crashes that received a promoted annotation since the
+/// last run. Returns `false` once all annotations have been processed.
+async fn promote_batch(
+    db: &DatabaseConnection,
+    product_id: Uuid,
+    progress: &mut Progress,
+) -> Result<bool, DbErr> {
+    let rows: Vec<(Uuid, NaiveDateTime, Uuid)> = entity::prelude::Annotation::find()
+        .select_only()
+        .column(entity::annotation::Column::CrashId)
+        .column(entity::annotation::Column::CreatedAt)
+        .column(entity::annotation::Column::Id)
+        .inner_join(entity::prelude::Crash)
+        .filter(entity::crash::Column::ProductId.eq(product_id))
+        .filter(entity::annotation::Column::Key.is_in(progress.keys.clone()))
+        .filter(
+            Condition::any()
+                .add(entity::annotation::Column::CreatedAt.gt(progress.since))
+                .add(
+                    Condition::all()
+                        .add(entity::annotation::Column::CreatedAt.eq(progress.since))
+                        .add(entity::annotation::Column::Id.gt(progress.last_id)),
+                ),
+        )
+        .order_by_asc(entity::annotation::Column::CreatedAt)
+        .order_by_asc(entity::annotation::Column::Id)
+        .limit(BATCH_SIZE)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let Some((_, since, last_id)) = rows.last().cloned() else {
+        return Ok(false);
+    };
+
+    let crash_ids: BTreeSet<Uuid> = rows.into_iter().map(|(crash_id, _, _)| crash_id).collect();
+    for crash_id in crash_ids {
+        let annotations = entity::prelude::Annotation::find()
+            .filter(entity::annotation::Column::CrashId.eq(crash_id))
+            .filter(entity::annotation::Column::Key.is_in(progress.keys.clone()))
+            .all(db)
+            .await?;
+        entity::prelude::Crash::update_many()
+            .col_expr(
+                entity::crash::Column::Promoted,
+                Expr::value(promote(&progress.keys, &annotations)),
+            )
+            .filter(entity::crash::Column::Id.eq(crash_id))
+            .exec(db)
+            .await?;
+    }
+
+    progress.since = since;
+    progress.last_id = last_id;
+    JobStateRepo::set(db, &state_key(product_id), progress).await?;
+    Ok(true)
+}
+
+async fn check(db: &DatabaseConnection, cache: &ResponseCache) -> Result<(), DbErr> {
+    let products = entity::prelude::Product::find().all(db).await?;
+
+    for product in products {
+        let key = state_key(product.id);
+        let keys = promoted_keys(product.promoted_annotations.as_deref());
+        let stored = JobStateRepo::get::<Progress>(db, &key).await?;
+        let mut progress = match stored {
+            Some(progress) if progress.keys == keys => Some(progress),
+            stored => {
+                // Start over so that crashes uploaded before the change are (re)promoted.
+                if stored.is_some() || !keys.is_empty() {
+                    info!("annotation promotion of product {} changed", product.name);
+                }
+                if stored.is_some() {
+                    clear(db, product.id).await?;
+                    JobStateRepo::remove(db, &key).await?;
+                    cache.invalidate_product(product.id);
+                }
+                if keys.is_empty() {
+                    None
+                } else {
+                    let progress = Progress::new(keys);
+                    JobStateRepo::set(db, &key, &progress).await?;
+                    Some(progress)
+                }
+            }
+        };
+
+        if let Some(product_progress) = progress.as_mut() {
+            if promote_batch(db, product.id, product_progress).await? {
+                while promote_batch(db, product.id, product_progress).await? {}
+                cache.invalidate_product(product.id);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(db: DatabaseConnection, cache: Arc<ResponseCache>) {
+    let period = std::time::Duration::from_secs(60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "promotion", period).await {
+            continue;
+        }
+        if let Err(e) = check(&db, &cache).await {
+            error!("annotation promotion failed: {:?}", e);
+        }
+    }
+}
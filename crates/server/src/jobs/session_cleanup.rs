@@ -0,0 +1,52 @@
+use chrono::{NaiveDate, Timelike};
+use sea_orm::{DatabaseConnection, DbErr};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::api::metrics;
+use crate::model::session::SessionRepo;
+use crate::settings;
+
+/// Deletes stale sessions in batches, see `SessionRepo::delete_stale`. Returns the number of
+/// deleted sessions.
+async fn delete_stale_sessions(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let config = &settings().maintenance;
+    let now = chrono::Utc::now().naive_utc();
+    let abandoned_before = now - chrono::Duration::days(config.abandoned_session_days);
+    let batch_size = config.session_batch_size.max(1);
+
+    let mut deleted = 0;
+    loop {
+        let batch = SessionRepo::delete_stale(db, now, abandoned_before, batch_size).await?;
+        deleted += batch;
+        if batch < batch_size {
+            return Ok(deleted);
+        }
+    }
+}
+
+/// Runs the nightly maintenance once a day, in the hour configured by `maintenance.hour`, on
+/// the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let period = Duration::from_secs(60 * 60);
+    let mut interval = tokio::time::interval(period);
+    let mut last_run: Option<NaiveDate> = None;
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        if now.hour() != settings().maintenance.hour || last_run == Some(now.date_naive()) {
+            continue;
+        }
+        if !super::hold_lease(&db, "session_cleanup", period).await {
+            continue;
+        }
+        last_run = Some(now.date_naive());
+        match delete_stale_sessions(&db).await {
+            Ok(deleted) => {
+                metrics().record_sessions_deleted(deleted);
+                info!("deleted {} stale sessions", deleted);
+            }
+            Err(e) => error!("deleting stale sessions failed: {:?}", e),
+        }
+    }
+}
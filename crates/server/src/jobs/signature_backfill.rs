@@ -0,0 +1,38 @@
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::model::crash::CrashRepo;
+use crate::settings;
+
+// While the `crash_signature` rollout is in the `dual` phase, new crashes store their signature
+// in the `signature` column and this job fills it for the crashes stored before, see
+// `app::rollout`. The crashes are visited once per run of the server; once it reports that all
+// crashes were visited, the rollout can move to the `new` phase.
+
+const PERIOD: Duration = Duration::from_secs(60);
+const BATCH: u64 = 500;
+
+/// Fills the signature of a batch of crashes every minute, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    if !settings().rollout.crash_signature.backfills() {
+        return;
+    }
+    let mut interval = tokio::time::interval(PERIOD);
+    let mut after = None;
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "signature_backfill", PERIOD).await {
+            continue;
+        }
+        match CrashRepo::backfill_signatures(&db, after, BATCH).await {
+            Ok(Some(last)) => after = Some(last),
+            Ok(None) => {
+                info!("backfilled the signatures of all crashes");
+                super::release_lease(&db, "signature_backfill").await;
+                return;
+            }
+            Err(e) => error!("backfilling crash signatures failed: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
+
+use crate::api::{staging_dir, StagedUpload};
+use crate::settings;
+
+async fn remove_file(path: &Path) {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            warn!("failed to remove {:?}: {:?}", path, e)
+        }
+        _ => (),
+    }
+}
+
+async fn is_expired(path: &Path, now: i64) -> bool {
+    match tokio::fs::read(path).await {
+        Ok(metadata) => serde_json::from_slice::<StagedUpload>(&metadata)
+            .map_or(true, |upload| upload.expires_at() < now),
+        Err(_) => true,
+    }
+}
+
+/// Removes resumable uploads that were not finished in time, and data files without upload
+/// parameters that are older than the upload lifetime. Returns the number of removed uploads.
+async fn clean(dir: &Path, now: i64) -> std::io::Result<usize> {
+    let lifetime = Duration::from_secs(settings().ingest.resumable_upload_lifetime);
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if is_expired(&path, now).await {
+                remove_file(&path.with_extension("")).await;
+                remove_file(&path).await;
+                removed += 1;
+            }
+        } else if !path.with_extension("json").exists() {
+            let age = entry
+                .metadata()
+                .await?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age > lifetime {
+                remove_file(&path).await;
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+pub async fn run() {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(15 * 60));
+    loop {
+        interval.tick().await;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64);
+        match clean(&staging_dir(), now).await {
+            Ok(0) => (),
+            Ok(removed) => info!("removed {} expired resumable uploads", removed),
+            Err(e) => error!("staging cleanup failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clean() {
+        let dir = std::env::temp_dir().join(format!("guardrail-staging-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let upload = |created_at| StagedUpload {
+            product: "Workrave".to_owned(),
+            version: "1.11".to_owned(),
+            channel: None,
+            environment: None,
+            user_agent: None,
+            sdk: None,
+            checksum: None,
+            owner: None,
+            created_at,
+        };
+        let now = chrono::Utc::now().timestamp();
+        let lifetime = settings().ingest.resumable_upload_lifetime as i64;
+        std::fs::write(dir.join("active"), b"data").unwrap();
+        std::fs::write(
+            dir.join("active.json"),
+            serde_json::to_vec(&upload(now)).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("expired"), b"data").unwrap();
+        std::fs::write(
+            dir.join("expired.json"),
+            serde_json::to_vec(&upload(now - lifetime - 1)).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("orphan"), b"data").unwrap();
+
+        assert_eq!(clean(&dir, now).await.unwrap(), 1);
+        assert!(dir.join("active").exists());
+        assert!(dir.join("active.json").exists());
+        assert!(!dir.join("expired").exists());
+        assert!(!dir.join("expired.json").exists());
+        // Orphaned data is only removed once it is older than the upload lifetime.
+        assert!(dir.join("orphan").exists());
+
+        assert_eq!(clean(&dir.join("missing"), now).await.unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use sea_orm::{DatabaseConnection, DbErr};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::api::{metrics, SymbolsApi};
+use crate::model::symbol_conversion::{SymbolConversion, SymbolConversionRepo};
+use crate::settings;
+
+// Native debug files, such as PDB files and ELF files with DWARF debug info, are uploaded to
+// `/api/symbols/upload-native` and converted here with Mozilla's dump_syms, which must be
+// installed on the server. The converted symbols are validated and stored like uploaded Breakpad
+// symbols; the native file is kept. Conversions that fail are not retried, the error is stored
+// with the conversion instead.
+
+const PERIOD: Duration = Duration::from_secs(60);
+
+const BATCH_SIZE: u64 = 10;
+
+/// Number of lines of the diagnostics of dump_syms kept in the error of a failed conversion.
+const ERROR_LINES: usize = 10;
+
+/// Returns the last lines of the diagnostics of dump_syms.
+fn error_message(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines[lines.len().saturating_sub(ERROR_LINES)..].join("\n")
+}
+
+/// Runs dump_syms on a native debug file, writing the Breakpad symbols to `symbol_file`.
+async fn dump_syms(
+    settings: &app::settings::SymbolConversion,
+    native_file: &Path,
+    symbol_file: &Path,
+) -> Result<(), String> {
+    let child = Command::new(&settings.dump_syms)
+        .arg("--output")
+        .arg(symbol_file)
+        .arg(native_file)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", settings.dump_syms, e))?;
+
+    let timeout = Duration::from_secs(settings.timeout);
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("conversion took more than {} seconds", settings.timeout))?
+        .map_err(|e| format!("failed to run {}: {}", settings.dump_syms, e))?;
+    if !output.status.success() {
+        let message = error_message(&output.stderr);
+        if message.is_empty() {
+            return Err(format!("dump_syms failed: {}", output.status));
+        }
+        return Err(message);
+    }
+    Ok(())
+}
+
+/// Converts a native debug file and stores its symbols. Returns the id of the symbols.
+async fn convert(db: &DatabaseConnection, conversion: &SymbolConversion) -> Result<Uuid, String> {
+    let symbol_file = SymbolsApi::get_temp_symbols_file()
+        .await
+        .map_err(|e| e.to_string())?;
+    let result = async {
+        dump_syms(
+            &settings().symbol_conversion,
+            Path::new(&conversion.file_location),
+            &symbol_file,
+        )
+        .await?;
+        let data = SymbolsApi::process_symbol_file(&symbol_file)
+            .await
+            .map_err(|e| e.to_string())?;
+        SymbolsApi::store(db, data, conversion.product_id, conversion.version_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&symbol_file).await;
+    }
+    result
+}
+
+/// Runs the pending conversions while this replica holds the lease, which is renewed before
+/// every conversion as a conversion may take longer than the period of the job. Returns the
+/// number of converted files.
+async fn convert_pending(db: &DatabaseConnection, lease: Duration) -> Result<usize, DbErr> {
+    let mut converted = 0;
+    loop {
+        let pending = SymbolConversionRepo::get_pending(db, BATCH_SIZE).await?;
+        if pending.is_empty() {
+            return Ok(converted);
+        }
+        for conversion in pending {
+            if !super::hold_lease(db, "symbol_conversion", lease).await {
+                return Ok(converted);
+            }
+            match convert(db, &conversion).await {
+                Ok(symbols_id) => {
+                    info!(
+                        "converted {} ({}) to symbols {}",
+                        conversion.file_name, conversion.id, symbols_id
+                    );
+                    metrics().record_symbol_conversion(true);
+                    SymbolConversionRepo::converted(db, conversion.id, symbols_id).await?;
+                    converted += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        "conversion of {} ({}) failed: {}",
+                        conversion.file_name, conversion.id, e
+                    );
+                    metrics().record_symbol_conversion(false);
+                    SymbolConversionRepo::failed(db, conversion.id, &e).await?;
+                }
+            }
+        }
+    }
+}
+
+/// Converts uploaded native debug files every minute, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let lease = PERIOD.max(Duration::from_secs(settings().symbol_conversion.timeout));
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "symbol_conversion", lease).await {
+            continue;
+        }
+        match convert_pending(&db, lease).await {
+            Ok(0) => (),
+            Ok(converted) => info!("converted {} native debug files", converted),
+            Err(e) => error!("converting native debug files failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(error_message(b""), "");
+        assert_eq!(
+            error_message(b"\nError: unsupported file format\n  \n"),
+            "Error: unsupported file format"
+        );
+        let stderr: String = (0..15).map(|i| format!("line {}\n", i)).collect();
+        let message = error_message(stderr.as_bytes());
+        assert_eq!(message.lines().count(), ERROR_LINES);
+        assert!(message.starts_with("line 5\n"));
+        assert!(message.ends_with("line 14"));
+    }
+}
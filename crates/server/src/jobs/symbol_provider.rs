@@ -0,0 +1,215 @@
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::api::metrics;
+use crate::entity;
+use crate::model::missing_symbols::{missing_modules, MissingSymbolsRepo};
+use crate::model::symbols::symbols_dir;
+use crate::settings;
+
+// Crashes of modules without symbols, such as system libraries, have unsymbolicated frames.
+// The symbol provider fetches the symbols of these modules from the configured upstream symbol
+// servers and stores them with the uploaded symbols, so that later crashes are symbolicated.
+// Crashes that were processed before can be reprocessed once their symbols have been fetched.
+//
+// Symbols that an upstream server does not have are not requested again until the server
+// restarts.
+
+const PERIOD: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum number of modules remembered as not available upstream.
+const MAX_UNAVAILABLE: usize = 10_000;
+
+/// Name of the Breakpad symbol file of a module, e.g. `xul.sym` for `xul.pdb` and
+/// `libxul.so.sym` for `libxul.so`.
+fn sym_file_name(debug_file: &str) -> String {
+    match debug_file.strip_suffix(".pdb") {
+        Some(stem) => format!("{}.sym", stem),
+        None => format!("{}.sym", debug_file),
+    }
+}
+
+/// Location of a fetched symbol file below `dir`, in the layout the symbol supplier expects.
+fn fetched_file(dir: &Path, debug_file: &str, debug_id: &str) -> PathBuf {
+    dir.join(debug_file)
+        .join(debug_id)
+        .join(sym_file_name(debug_file))
+}
+
+struct SymbolProvider {
+    client: reqwest::Client,
+    urls: Vec<String>,
+    max_size: u64,
+    /// Creation time of the newest crash that was checked for missing symbols.
+    since: NaiveDateTime,
+    unavailable: HashSet<(String, String)>,
+}
+
+impl SymbolProvider {
+    fn new(settings: &app::settings::SymbolServers) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout))
+            .build()?;
+        Ok(Self {
+            client,
+            urls: settings
+                .urls
+                .iter()
+                .map(|url| url.trim_end_matches('/').to_owned())
+                .collect(),
+            max_size: settings.max_size,
+            since: chrono::Utc::now().naive_utc() - chrono::Duration::days(1),
+            unavailable: HashSet::new(),
+        })
+    }
+
+    /// Downloads the symbol file of a module from the first server that has it.
+    async fn download(&self, debug_file: &str, debug_id: &str) -> Option<Vec<u8>> {
+        for url in &self.urls {
+            let url = format!(
+                "{}/{}/{}/{}",
+                url,
+                debug_file,
+                debug_id,
+                sym_file_name(debug_file)
+            );
+            let response = match self.client.get(&url).send().await {
+                Ok(response) if response.status().is_success() => response,
+                Ok(response) => {
+                    debug!("no symbols at {}: {}", url, response.status());
+                    continue;
+                }
+                Err(e) => {
+                    warn!("failed to fetch symbols from {}: {:?}", url, e);
+                    continue;
+                }
+            };
+            if response
+                .content_length()
+                .is_some_and(|length| length > self.max_size)
+            {
+                warn!("symbols at {} exceed the maximum size", url);
+                continue;
+            }
+            match response.bytes().await {
+                // Servers may answer with an HTML page instead of a missing symbol file.
+                Ok(content) if content.starts_with(b"MODULE ") => return Some(content.to_vec()),
+                Ok(_) => debug!("no symbol file at {}", url),
+                Err(e) => warn!("failed to fetch symbols from {}: {:?}", url, e),
+            }
+        }
+        None
+    }
+
+    /// Stores a symbol file, first under a temporary name so that processing never reads a
+    /// partial file.
+    async fn store(path: &Path, content: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let partial = path.with_extension("sym.partial");
+        tokio::fs::write(&partial, content).await?;
+        tokio::fs::rename(&partial, path).await
+    }
+
+    /// Fetches the missing symbols of the crashes reported since the last run. Returns the
+    /// number of fetched symbol files.
+    async fn fetch_missing(&mut self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let crashes: Vec<(serde_json::Value, NaiveDateTime)> = entity::prelude::Crash::find()
+            .select_only()
+            .column(entity::crash::Column::Report)
+            .column(entity::crash::Column::CreatedAt)
+            .filter(entity::crash::Column::CreatedAt.gt(self.since))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let mut modules = HashSet::new();
+        for (report, created_at) in &crashes {
+            self.since = self.since.max(*created_at);
+            modules.extend(missing_modules(report));
+        }
+
+        let dir = symbols_dir();
+        let mut fetched = 0;
+        for module in modules {
+            let (debug_file, debug_id) = &module;
+            let path = fetched_file(&dir, debug_file, debug_id);
+            if path.is_file() || self.unavailable.contains(&module) {
+                continue;
+            }
+            match self.download(debug_file, debug_id).await {
+                Some(content) => match Self::store(&path, &content).await {
+                    Ok(()) => {
+                        info!("fetched symbols of {} {}", debug_file, debug_id);
+                        MissingSymbolsRepo::resolve(db, debug_file, debug_id).await?;
+                        fetched += 1;
+                    }
+                    Err(e) => error!("failed to store symbols {:?}: {:?}", path, e),
+                },
+                None => {
+                    if self.unavailable.len() >= MAX_UNAVAILABLE {
+                        self.unavailable.clear();
+                    }
+                    self.unavailable.insert(module);
+                }
+            }
+        }
+        Ok(fetched)
+    }
+}
+
+/// Fetches missing symbols from the upstream symbol servers every five minutes, on the replica
+/// that holds the lease. Does nothing unless symbol servers are configured.
+pub async fn run(db: DatabaseConnection) {
+    let settings = &settings().symbol_servers;
+    if settings.urls.is_empty() {
+        return;
+    }
+    let mut provider = match SymbolProvider::new(settings) {
+        Ok(provider) => provider,
+        Err(e) => {
+            error!("symbol provider not started: {:?}", e);
+            return;
+        }
+    };
+
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "symbol_provider", PERIOD).await {
+            continue;
+        }
+        match provider.fetch_missing(&db).await {
+            Ok(0) => (),
+            Ok(fetched) => {
+                metrics().record_symbols_fetched(fetched);
+                info!("fetched {} symbol files from upstream servers", fetched);
+            }
+            Err(e) => error!("fetching missing symbols failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetched_file() {
+        assert_eq!(sym_file_name("xul.pdb"), "xul.sym");
+        assert_eq!(sym_file_name("libxul.so"), "libxul.so.sym");
+        assert_eq!(
+            fetched_file(
+                Path::new("symbols"),
+                "libc.so.6",
+                "ABCDEF0123456789ABCDEF01234567890"
+            ),
+            Path::new("symbols/libc.so.6/ABCDEF0123456789ABCDEF01234567890/libc.so.6.sym")
+        );
+    }
+}
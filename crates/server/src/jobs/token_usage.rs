@@ -0,0 +1,18 @@
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::api::TokenUsage;
+
+/// Writes the submissions counted per device key every 30 seconds.
+pub async fn run(db: DatabaseConnection, usage: Arc<TokenUsage>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+    loop {
+        interval.tick().await;
+        match usage.flush(&db).await {
+            Ok(0) => (),
+            Ok(updated) => debug!("recorded submissions of {} device keys", updated),
+            Err(e) => error!("recording device key submissions failed: {:?}", e),
+        }
+    }
+}
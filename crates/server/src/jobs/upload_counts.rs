@@ -0,0 +1,28 @@
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::model::upload_count::UploadCountRepo;
+
+// Uploads are counted per product and day for the daily upload quota, see `UploadQuota`. The
+// counts of past days are no longer needed once the day is over, so they are removed, which
+// resets the counters.
+
+const PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Removes the upload counts of past days every hour, on the replica that holds the lease.
+pub async fn run(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(PERIOD);
+    loop {
+        interval.tick().await;
+        if !super::hold_lease(&db, "upload_counts", PERIOD).await {
+            continue;
+        }
+        let today = chrono::Utc::now().date_naive();
+        match UploadCountRepo::delete_before(&db, today).await {
+            Ok(0) => (),
+            Ok(deleted) => info!("reset {} daily upload counts", deleted),
+            Err(e) => error!("resetting daily upload counts failed: {:?}", e),
+        }
+    }
+}
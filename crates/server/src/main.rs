@@ -1,17 +1,21 @@
+mod analyzers;
 mod api;
 mod app_state;
 mod auth;
+mod bootstrap;
 mod fileserv;
+mod import;
+mod jobs;
 mod session_store;
 mod utils;
 
 use app::auth::layer::AuthLayer;
 use app::auth::AuthSession;
 use axum::body::Body;
-use axum::extract::{DefaultBodyLimit, State};
+use axum::extract::{DefaultBodyLimit, OriginalUri, State};
 use axum::http::Request;
 use axum::response::{IntoResponse, Response};
-use axum::Router;
+use axum::{middleware, Router};
 use axum_server::tls_rustls::RustlsConfig;
 use fileserv::file_and_error_handler;
 use leptos::*;
@@ -26,12 +30,13 @@ use tower_http::trace::TraceLayer;
 use tower_sessions::cookie::SameSite;
 use tower_sessions::{Expiry, SessionManagerLayer};
 use tracing::level_filters::LevelFilter;
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{fmt, EnvFilter, FmtSubscriber};
 use webauthn_rs::prelude::*;
 
 use crate::entity;
+use app::model::query_metrics;
 use app::settings::settings;
 use app::*;
 use app_state::AppState;
@@ -64,12 +69,16 @@ async fn init_logging() {
 
 async fn init_db() -> Result<DatabaseConnection, sea_orm::DbErr> {
     let connect_options = ConnectOptions::new(&settings().database.uri).to_owned();
-    Database::connect(connect_options).await
+    let mut db = Database::connect(connect_options).await?;
+    db.set_metric_callback(query_metrics::observe);
+    Ok(db)
 }
 
 fn create_webauthn() -> Arc<Webauthn> {
     let rp_id = settings().auth.id.as_str();
     let rp_origin = Url::parse(settings().auth.origin.as_str()).expect("Invalid URL");
+    // The origin never includes the path prefix the application is hosted under.
+    let rp_origin = Url::parse(&rp_origin.origin().ascii_serialization()).expect("Invalid URL");
     let builder = WebauthnBuilder::new(rp_id, &rp_origin).expect("Invalid configuration");
     let builder = builder.rp_name(settings().auth.name.as_str());
 
@@ -97,6 +106,13 @@ async fn leptos_routes_handler(
     State(app_state): State<AppState>,
     req: Request<Body>,
 ) -> Response {
+    // Routes are nested under the path prefix; the router in the app expects the full path.
+    let (mut parts, body) = req.into_parts();
+    if let Some(OriginalUri(uri)) = parts.extensions.get::<OriginalUri>().cloned() {
+        parts.uri = uri;
+    }
+    let req = Request::from_parts(parts, body);
+
     let handler = leptos_axum::render_route_with_context(
         app_state.leptos_options.clone(),
         app_state.routes.clone(),
@@ -116,28 +132,84 @@ async fn main() {
 
     info!("Starting server on port {}", settings().server.port);
 
+    let prefix = app::prefix::path_prefix();
+
     let conf = get_configuration(None).await.unwrap();
-    let leptos_options = conf.leptos_options;
+    let mut leptos_options = conf.leptos_options;
+    if !prefix.is_empty() {
+        leptos_options.site_pkg_dir = format!(
+            "{}/{}",
+            prefix.trim_start_matches('/'),
+            leptos_options.site_pkg_dir
+        );
+    }
     let _addr = leptos_options.site_addr;
     let routes = generate_route_list(App);
 
     let db = init_db().await.unwrap();
+    if let Some(path) = &settings().server.bootstrap {
+        let config = bootstrap::load(path).expect("Invalid bootstrap configuration");
+        bootstrap::reconcile(&db, &config)
+            .await
+            .expect("Failed to apply bootstrap configuration");
+    }
     let webauthn = create_webauthn();
     let state = AppState {
         leptos_options: leptos_options.clone(),
         routes: routes.clone(),
         db: db.clone(),
         webauthn,
+        nonces: Default::default(),
+        analyzers: Arc::new(analyzers::Analyzers::from_settings(&settings().analyzers)),
+        cache: Default::default(),
+        uploads: Default::default(),
+        public: Default::default(),
+        diagnostics: Default::default(),
+        usage: Default::default(),
+        issue_counts: Default::default(),
+        crashes: Default::default(),
     };
+    tokio::spawn(api::listen_for_crashes(db.clone(), state.crashes.clone()));
+    // Background jobs and imports change data, a read-only mirror leaves them to the primary.
+    if !settings().server.read_only {
+        jobs::start(&state);
+    }
+
+    if !settings().import.source.is_empty() && !settings().server.read_only {
+        tokio::spawn(import::run(
+            state.clone(),
+            PathBuf::from(&settings().import.source),
+            settings().import.rate,
+        ));
+    }
 
-    let session_store = SeaOrmSessionStore::new(db);
+    let issue_counts = state.issue_counts.clone();
+    let session_store = SeaOrmSessionStore::new(db.clone());
     let session_layer = SessionManagerLayer::new(session_store)
         .with_name("guardrail")
         .with_same_site(SameSite::Lax)
         .with_expiry(Expiry::OnInactivity(Duration::hours(4)))
+        .with_path(if prefix.is_empty() { "/" } else { prefix })
         .with_secure(false);
 
-    let auth_layer = AuthLayer::new();
+    let auth_layer = AuthLayer::new(state.db.clone());
+
+    let mut api_routes = Router::new()
+        .merge(api::meta_routes())
+        .merge(api::export_routes(state.clone()))
+        .merge(api::product_routes(state.clone()))
+        .merge(api::v1_routes(state.clone()))
+        .nest("/api", api::routes().await)
+        .nest("/download", api::download_routes())
+        .nest("/live", api::live_routes())
+        .nest("/ingest", api::signed_routes(state.clone()))
+        .nest("/public", api::public_routes(state.clone()))
+        .nest("/read", api::read_routes(state.clone()));
+    match api::upload_routes() {
+        Some(upload_routes) => api_routes = api_routes.nest("/upload", upload_routes),
+        None => warn!("upload tokens are disabled, set auth.upload_token.secret to enable them"),
+    }
+    let api_routes = api_routes.layer(middleware::from_fn(api::reject_writes));
 
     let routes_all = Router::new()
         .route(
@@ -146,26 +218,104 @@ async fn main() {
         )
         .leptos_routes_with_handler(routes, axum::routing::get(leptos_routes_handler))
         .fallback(file_and_error_handler)
-        .nest("/api", api::routes().await)
-        .nest("/auth", auth::routes().await)
-        .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
+        .merge(api_routes)
+        .nest(
+            "/auth",
+            auth::routes()
+                .await
+                .layer(middleware::from_fn(api::reject_writes)),
+        )
+        .layer(DefaultBodyLimit::max(settings().ingest.max_upload_size))
         .layer(TraceLayer::new_for_http())
         .layer(auth_layer)
         .layer(session_layer)
         .with_state(state);
 
-    //TODO: Make configurable
-    let config = RustlsConfig::from_pem_file(
-        PathBuf::from("dev").join("cert.pem"),
-        PathBuf::from("dev").join("key.pem"),
-    )
-    .await
-    .unwrap();
-
-    let port = settings().server.port;
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    axum_server::bind_rustls(addr, config)
-        .serve(routes_all.into_make_service())
-        .await
-        .unwrap();
+    let routes_all = if prefix.is_empty() {
+        routes_all
+    } else {
+        Router::new().nest(prefix, routes_all)
+    };
+
+    let listeners = settings().server.listeners();
+    let certificate = &settings().server.tls;
+    let config = match listeners.iter().any(|(_, tls)| *tls) {
+        true => Some(
+            RustlsConfig::from_pem_file(
+                PathBuf::from(&certificate.cert),
+                PathBuf::from(&certificate.key),
+            )
+            .await
+            .unwrap(),
+        ),
+        false => None,
+    };
+
+    let handle = axum_server::Handle::new();
+    tokio::spawn(shutdown_on_signal(handle.clone()));
+
+    let servers = listeners.into_iter().map(|(addr, tls)| {
+        let service = routes_all
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>();
+        let config = config.clone().filter(|_| tls);
+        info!(
+            "listening on {} ({})",
+            addr,
+            if tls { "https" } else { "http" }
+        );
+        let handle = handle.clone();
+        async move {
+            match config {
+                Some(config) => {
+                    axum_server::bind_rustls(addr, config)
+                        .handle(handle)
+                        .serve(service)
+                        .await
+                }
+                None => axum_server::bind(addr).handle(handle).serve(service).await,
+            }
+        }
+    });
+    futures::future::try_join_all(servers).await.unwrap();
+
+    // Crashes of existing issues are counted in memory until the next flush, write them before
+    // exiting so that a restart does not lose them.
+    if !settings().server.read_only {
+        match issue_counts.flush(&db).await {
+            Ok(updated) => info!("counted crashes of {} issues before shutdown", updated),
+            Err(e) => warn!("counting issue crashes before shutdown failed: {:?}", e),
+        }
+    }
+}
+
+/// Stops accepting connections on SIGINT or SIGTERM, and lets requests in flight finish.
+async fn shutdown_on_signal(handle: axum_server::Handle) {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for SIGINT: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => (),
+        _ = terminate => (),
+    }
+    info!("shutting down");
+    handle.graceful_shutdown(Some(std::time::Duration::from_secs(30)));
 }
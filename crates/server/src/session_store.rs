@@ -52,8 +52,14 @@ impl SessionStore for SeaOrmSessionStore {
         };
         app::entity::prelude::Session::insert(data)
             .on_conflict(
+                // The expiry moves with every save, which the nightly cleanup of stale
+                // sessions relies on.
                 OnConflict::column(migration::SessionColumns::Id)
-                    .update_columns([migration::SessionColumns::Data])
+                    .update_columns([
+                        migration::SessionColumns::Data,
+                        migration::SessionColumns::ExpiresAt,
+                        migration::SessionColumns::UpdatedAt,
+                    ])
                     .to_owned(),
             )
             .exec(&self.db)
@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::error::UtilsError;
+
+// Archives bundle many files in a single upload, such as the symbol files of a build. Zip and
+// tar archives are supported, recognized by their magic bytes. Compressed tar archives are
+// decompressed before extraction, like other uploads, see `compression::decompress_upload`.
+//
+// Files are extracted under unique names, so the paths in an archive never decide where files
+// are written.
+
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+const TAR_MAGIC: &[u8] = b"ustar";
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// Maximum number of files extracted from an archive.
+pub const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+}
+
+fn format(header: &[u8]) -> Option<Format> {
+    if header.starts_with(&ZIP_MAGIC) {
+        Some(Format::Zip)
+    } else if header
+        .get(TAR_MAGIC_OFFSET..)
+        .is_some_and(|magic| magic.starts_with(TAR_MAGIC))
+    {
+        Some(Format::Tar)
+    } else {
+        None
+    }
+}
+
+/// A file of an archive, with the location it was extracted to, or the reason it was not.
+#[derive(Debug)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub file: Result<PathBuf, String>,
+}
+
+fn too_large(max_size: u64) -> String {
+    format!("file exceeds {} bytes", max_size)
+}
+
+fn extract_entry(reader: impl Read, dir: &Path, max_size: u64) -> Result<PathBuf, String> {
+    let path = dir.join(uuid::Uuid::new_v4().to_string());
+    let written = File::create(&path)
+        .and_then(|mut output| std::io::copy(&mut reader.take(max_size + 1), &mut output));
+    match written {
+        Ok(size) if size <= max_size => Ok(path),
+        Ok(_) => {
+            let _ = std::fs::remove_file(&path);
+            Err(too_large(max_size))
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e.to_string())
+        }
+    }
+}
+
+fn extract_zip(input: File, dir: &Path, max_size: u64) -> Result<Vec<ArchiveEntry>, UtilsError> {
+    let mut archive =
+        zip::ZipArchive::new(input).map_err(|e| UtilsError::InvalidArchive(e.to_string()))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(UtilsError::InvalidArchive(format!(
+            "more than {} files",
+            MAX_ENTRIES
+        )));
+    }
+
+    let mut entries = vec![];
+    for index in 0..archive.len() {
+        let file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(e) => {
+                entries.push(ArchiveEntry {
+                    name: format!("#{}", index),
+                    file: Err(e.to_string()),
+                });
+                continue;
+            }
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_owned();
+        let file = match file.size() > max_size {
+            true => Err(too_large(max_size)),
+            false => extract_entry(file, dir, max_size),
+        };
+        entries.push(ArchiveEntry { name, file });
+    }
+    Ok(entries)
+}
+
+fn extract_tar(input: File, dir: &Path, max_size: u64) -> Result<Vec<ArchiveEntry>, UtilsError> {
+    let invalid = |e: std::io::Error| UtilsError::InvalidArchive(e.to_string());
+    let mut archive = tar::Archive::new(input);
+
+    let mut entries = vec![];
+    // Tar archives have no index, so a corrupt entry ends the extraction.
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        if entries.len() == MAX_ENTRIES {
+            return Err(UtilsError::InvalidArchive(format!(
+                "more than {} files",
+                MAX_ENTRIES
+            )));
+        }
+        let name = entry
+            .path()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file = match entry.size() > max_size {
+            true => Err(too_large(max_size)),
+            false => extract_entry(&mut entry, dir, max_size),
+        };
+        entries.push(ArchiveEntry { name, file });
+    }
+    Ok(entries)
+}
+
+/// Extracts the files of a zip or tar archive into `dir`. Directories and links are skipped,
+/// and files larger than `max_size` are not extracted. Entries are returned in the order of the
+/// archive; an entry that cannot be extracted does not stop the others.
+pub async fn extract(
+    path: &Path,
+    dir: &Path,
+    max_size: u64,
+) -> Result<Vec<ArchiveEntry>, UtilsError> {
+    let path = path.to_path_buf();
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<Vec<ArchiveEntry>, UtilsError> {
+        let mut input = File::open(&path)?;
+        let mut header = vec![];
+        (&mut input)
+            .take((TAR_MAGIC_OFFSET + TAR_MAGIC.len()) as u64)
+            .read_to_end(&mut header)?;
+        input.seek(SeekFrom::Start(0))?;
+
+        match format(&header) {
+            Some(Format::Zip) => extract_zip(input, &dir, max_size),
+            Some(Format::Tar) => extract_tar(input, &dir, max_size),
+            None => Err(UtilsError::InvalidArchive(
+                "not a zip or tar archive".to_owned(),
+            )),
+        }
+    })
+    .await
+    .map_err(|_| UtilsError::Failure)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SYMBOLS: &[u8] = b"MODULE Linux x86_64 ABCDEF0123456789ABCDEF01234567890 app\n";
+
+    fn read_entries(entries: &[ArchiveEntry]) -> Vec<(String, Result<Vec<u8>, String>)> {
+        entries
+            .iter()
+            .map(|entry| {
+                let content = match &entry.file {
+                    Ok(path) => Ok(std::fs::read(path).unwrap()),
+                    Err(e) => Err(e.clone()),
+                };
+                (entry.name.clone(), content)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_extract_zip() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let path = dir.join("symbols.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.add_directory("app/", options).unwrap();
+        writer.start_file("app/app.sym", options).unwrap();
+        writer.write_all(SYMBOLS).unwrap();
+        writer.start_file("large.sym", options).unwrap();
+        writer.write_all(&[b'x'; 1024]).unwrap();
+        writer.finish().unwrap();
+
+        let entries = extract(&path, &dir, 512).await.unwrap();
+        assert_eq!(
+            read_entries(&entries),
+            vec![
+                ("app/app.sym".to_owned(), Ok(SYMBOLS.to_vec())),
+                ("large.sym".to_owned(), Err(too_large(512))),
+            ]
+        );
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_extract_tar() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let path = dir.join("symbols.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(SYMBOLS.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "app/app.sym", SYMBOLS)
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let entries = extract(&path, &dir, 512).await.unwrap();
+        assert_eq!(
+            read_entries(&entries),
+            vec![("app/app.sym".to_owned(), Ok(SYMBOLS.to_vec()))]
+        );
+
+        let plain = dir.join("app.sym");
+        tokio::fs::write(&plain, SYMBOLS).await.unwrap();
+        assert!(matches!(
+            extract(&plain, &dir, 512).await,
+            Err(UtilsError::InvalidArchive(_))
+        ));
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
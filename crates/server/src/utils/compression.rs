@@ -0,0 +1,304 @@
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+use super::error::UtilsError;
+use crate::settings;
+
+// Minidumps compress well, so stored minidumps and attachments are compressed with zstd after
+// they have been written. The compression is stored with the attachment, and compressed files
+// get a `.zst` extension, so that files can also be recognized without the database.
+//
+// Clients may also upload compressed minidumps and symbols. These are recognized by their magic
+// bytes and decompressed before they are processed.
+
+pub const ZSTD: &str = "zstd";
+const ZSTD_EXTENSION: &str = "zst";
+pub const GZIP: &str = "gzip";
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns the configured compression, `None` if stored files are kept as is.
+pub fn configured() -> Option<&'static str> {
+    match settings().storage.compression.as_str() {
+        ZSTD => Some(ZSTD),
+        _ => None,
+    }
+}
+
+/// Returns the compression of a file, based on its extension.
+pub fn from_path(path: &Path) -> Option<&'static str> {
+    path.extension()
+        .is_some_and(|extension| extension == ZSTD_EXTENSION)
+        .then_some(ZSTD)
+}
+
+/// Returns the compression of uploaded content, based on its first bytes.
+pub fn from_magic(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(&ZSTD_MAGIC) {
+        Some(ZSTD)
+    } else if header.starts_with(&GZIP_MAGIC) {
+        Some(GZIP)
+    } else {
+        None
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// Decompresses an uploaded file in place if it is compressed with gzip or zstd. At most
+/// `max_size` bytes are decompressed, so that a small upload cannot fill the disk. Returns the
+/// compression of the upload, `None` if it was not compressed.
+pub async fn decompress_upload(
+    path: &Path,
+    max_size: u64,
+) -> Result<Option<&'static str>, UtilsError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<Option<&'static str>, UtilsError> {
+        let mut input = std::fs::File::open(&path)?;
+        let mut header = vec![];
+        (&mut input)
+            .take(ZSTD_MAGIC.len() as u64)
+            .read_to_end(&mut header)?;
+        let Some(compression) = from_magic(&header) else {
+            return Ok(None);
+        };
+        input.seek(SeekFrom::Start(0))?;
+
+        let mut decoder: Box<dyn Read> = match compression {
+            GZIP => Box::new(flate2::read::MultiGzDecoder::new(input)),
+            _ => Box::new(zstd::stream::read::Decoder::new(input)?),
+        };
+        let tmp = tmp_path(&path);
+        let written = std::fs::File::create(&tmp).and_then(|mut output| {
+            std::io::copy(&mut (&mut decoder).take(max_size + 1), &mut output)
+        });
+        match written {
+            Ok(size) if size <= max_size => {
+                std::fs::rename(&tmp, &path)?;
+                Ok(Some(compression))
+            }
+            Ok(_) => {
+                std::fs::remove_file(&tmp)?;
+                Err(UtilsError::DecompressedTooLarge(max_size))
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp);
+                Err(e.into())
+            }
+        }
+    })
+    .await
+    .map_err(|_| UtilsError::Failure)?
+}
+
+fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ZSTD_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Compresses the file with zstd and removes the original. Returns the path of the compressed
+/// file, which is the original path with a `.zst` extension.
+pub async fn compress_file(path: &Path, level: i32) -> Result<PathBuf, UtilsError> {
+    let source = path.to_path_buf();
+    let target = compressed_path(path);
+    let tmp = tmp_path(&target);
+
+    let compressed = target.clone();
+    tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        let mut input = std::fs::File::open(&source)?;
+        let output = std::fs::File::create(&tmp)?;
+        zstd::stream::copy_encode(&mut input, output, level)?;
+        // Rename first, so that a crash in between leaves a complete file behind.
+        std::fs::rename(&tmp, &compressed)?;
+        std::fs::remove_file(&source)
+    })
+    .await
+    .map_err(|_| UtilsError::Failure)??;
+    Ok(target)
+}
+
+/// Reads a stored file, decompressing it if needed.
+pub async fn read_file(path: &Path, compression: Option<&str>) -> Result<Vec<u8>, UtilsError> {
+    let content = tokio::fs::read(path).await?;
+    match compression {
+        None => Ok(content),
+        Some(ZSTD) => Ok(zstd::stream::decode_all(content.as_slice())?),
+        Some(other) => Err(UtilsError::UnsupportedCompression(other.to_owned())),
+    }
+}
+
+/// Returns the hex encoded SHA-256 of a file, decompressed if needed. The file is hashed while it
+/// is read, so that large minidumps are not held in memory.
+pub async fn hash_file(path: &Path, compression: Option<&str>) -> Result<String, UtilsError> {
+    let path = path.to_path_buf();
+    let compression = compression.map(str::to_owned);
+    tokio::task::spawn_blocking(move || -> Result<String, UtilsError> {
+        let input = std::fs::File::open(&path)?;
+        let mut reader: Box<dyn Read> = match compression.as_deref() {
+            None => Box::new(input),
+            Some(ZSTD) => Box::new(zstd::stream::read::Decoder::new(input)?),
+            Some(other) => return Err(UtilsError::UnsupportedCompression(other.to_owned())),
+        };
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut reader, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(|_| UtilsError::Failure)?
+}
+
+/// Reads a stored file for processing, like `read_file`. Shared storage may not show a file that
+/// was just written by another replica yet, so a file that is not found is read again, see
+/// `storage.read_retries`. Other errors are returned immediately.
+pub async fn read_stored_file(
+    path: &Path,
+    compression: Option<&str>,
+) -> Result<Vec<u8>, UtilsError> {
+    let storage = &settings().storage;
+    read_with_retry(
+        path,
+        compression,
+        storage.read_retries,
+        Duration::from_millis(storage.read_retry_delay),
+    )
+    .await
+}
+
+async fn read_with_retry(
+    path: &Path,
+    compression: Option<&str>,
+    retries: u32,
+    mut delay: Duration,
+) -> Result<Vec<u8>, UtilsError> {
+    for _ in 0..retries {
+        match read_file(path, compression).await {
+            Err(UtilsError::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("{:?} not found, reading again in {:?}", path, delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    read_file(path, compression).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compress_file() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("crash.dmp");
+        let content = b"MDMP".repeat(1024);
+        tokio::fs::write(&path, &content).await.unwrap();
+
+        let compressed = compress_file(&path, 3).await.unwrap();
+        assert_eq!(compressed, dir.join("crash.dmp.zst"));
+        assert!(!path.exists());
+        assert!(std::fs::metadata(&compressed).unwrap().len() < content.len() as u64);
+        assert_eq!(from_path(&compressed), Some(ZSTD));
+        assert_eq!(from_path(&path), None);
+
+        let restored = read_file(&compressed, from_path(&compressed))
+            .await
+            .unwrap();
+        assert_eq!(restored, content);
+        assert!(read_file(&compressed, Some("lz4")).await.is_err());
+        assert_eq!(
+            hash_file(&compressed, from_path(&compressed))
+                .await
+                .unwrap(),
+            hex::encode(Sha256::digest(&content))
+        );
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_retry() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("crash.dmp");
+        let delay = Duration::from_millis(20);
+
+        let missing = read_with_retry(&path, None, 2, delay).await;
+        assert!(
+            matches!(missing, Err(UtilsError::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound)
+        );
+
+        // The file appears while it is read again.
+        let writer = {
+            let path = path.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                tokio::fs::write(&path, b"MDMP").await.unwrap();
+            })
+        };
+        let content = read_with_retry(&path, None, 4, delay).await.unwrap();
+        assert_eq!(content, b"MDMP");
+        writer.await.unwrap();
+
+        // Errors other than a missing file are not retried.
+        let started = std::time::Instant::now();
+        assert!(
+            read_with_retry(&path, Some("lz4"), 4, Duration::from_secs(10))
+                .await
+                .is_err()
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_decompress_upload() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let content = b"MDMP".repeat(1024);
+
+        let plain = dir.join("plain.dmp");
+        tokio::fs::write(&plain, &content).await.unwrap();
+        assert_eq!(decompress_upload(&plain, 1 << 20).await.unwrap(), None);
+        assert_eq!(tokio::fs::read(&plain).await.unwrap(), content);
+
+        let zstd = dir.join("zstd.dmp");
+        let compressed = zstd::stream::encode_all(content.as_slice(), 3).unwrap();
+        tokio::fs::write(&zstd, &compressed).await.unwrap();
+        assert_eq!(decompress_upload(&zstd, 1 << 20).await.unwrap(), Some(ZSTD));
+        assert_eq!(tokio::fs::read(&zstd).await.unwrap(), content);
+
+        let gzip = dir.join("gzip.dmp");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&content).unwrap();
+        tokio::fs::write(&gzip, encoder.finish().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(decompress_upload(&gzip, 1 << 20).await.unwrap(), Some(GZIP));
+        assert_eq!(tokio::fs::read(&gzip).await.unwrap(), content);
+
+        let bomb = dir.join("bomb.dmp");
+        tokio::fs::write(&bomb, &compressed).await.unwrap();
+        assert!(matches!(
+            decompress_upload(&bomb, 1024).await,
+            Err(UtilsError::DecompressedTooLarge(1024))
+        ));
+        assert!(!tmp_path(&bomb).exists());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
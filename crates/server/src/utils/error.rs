@@ -5,6 +5,21 @@ pub enum UtilsError {
     #[error("general failure")]
     Failure,
 
+    #[error("invalid storage template: '{0}'")]
+    InvalidTemplate(String),
+
+    #[error("unsupported compression: '{0}'")]
+    UnsupportedCompression(String),
+
+    #[error("decompressed upload exceeds {0} bytes")]
+    DecompressedTooLarge(u64),
+
+    #[error("upload exceeds {0} bytes")]
+    UploadTooLarge(u64),
+
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+
     #[error("io-error: '{0}'")]
     IOError(#[from] std::io::Error),
 }
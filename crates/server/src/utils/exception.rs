@@ -0,0 +1,43 @@
+use app::report::{ExceptionDetails, Register};
+use minidump::{Minidump, MinidumpException, MinidumpMiscInfo, MinidumpSystemInfo};
+use std::ops::Deref;
+
+// The stackwalk output of minidump-processor describes the crash (its reason and address) but
+// not the raw exception code or the registers at the time of the crash, which are needed to
+// debug crashes without a usable stack. They are read from the exception stream of the minidump
+// and added to the report as `exception`.
+
+/// Returns the details of the exception of the minidump, or `None` if it has no exception
+/// stream. The registers are empty if the minidump has no CPU context for the exception.
+pub fn details<'a, T>(dump: &Minidump<'a, T>) -> Option<ExceptionDetails>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let exception = dump.get_stream::<MinidumpException>().ok()?;
+    let record = &exception.raw.exception_record;
+
+    let registers = dump
+        .get_stream::<MinidumpSystemInfo>()
+        .ok()
+        .and_then(|system_info| {
+            let misc = dump.get_stream::<MinidumpMiscInfo>().ok();
+            let context = exception.context(&system_info, misc.as_ref())?;
+            Some(
+                context
+                    .valid_registers()
+                    .map(|(name, _)| Register {
+                        name: name.to_owned(),
+                        value: context.format_register(name),
+                    })
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+
+    Some(ExceptionDetails {
+        code: Some(format!("{:#x}", record.exception_code)),
+        flags: Some(format!("{:#x}", record.exception_flags)),
+        thread_id: Some(exception.thread_id),
+        registers,
+    })
+}
@@ -1,5 +1,12 @@
+pub mod archive;
+pub mod compression;
 pub mod error;
+pub mod exception;
+pub mod scrub;
+pub mod stackwalk_cache;
+pub mod storage_path;
 pub mod stream_to_file;
+pub mod symbol_check;
 
 // use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
@@ -0,0 +1,211 @@
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::error;
+
+use crate::settings;
+
+// Crashes must not store personal data such as email addresses, or user names in paths. The
+// scrub rules of `minidumps.scrub_rules` are applied to the annotation values of an upload and
+// to the processed report before they are stored, and annotations can be limited to an allow
+// list or excluded by a block list. The provenance of a report is recorded by guardrail itself
+// and is left alone.
+
+struct Rule {
+    pattern: Regex,
+    replacement: String,
+    products: Vec<String>,
+}
+
+pub struct Scrubber {
+    rules: Vec<Rule>,
+    allowed: Vec<String>,
+    blocked: Vec<String>,
+}
+
+impl Scrubber {
+    /// Compiles the rules of the settings. Rules with an invalid pattern are logged and skipped.
+    pub fn new(settings: &app::settings::Minidumps) -> Self {
+        let rules = settings
+            .scrub_rules
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(pattern) => Some(Rule {
+                    pattern,
+                    replacement: rule.replacement.clone(),
+                    products: rule.products.clone(),
+                }),
+                Err(e) => {
+                    error!("ignoring scrub rule {:?}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        let lowercase = |keys: &[String]| -> Vec<String> {
+            keys.iter().map(|key| key.to_lowercase()).collect()
+        };
+        Self {
+            rules,
+            allowed: lowercase(&settings.allowed_annotations),
+            blocked: lowercase(&settings.blocked_annotations),
+        }
+    }
+
+    /// Applies the rules of the product to a text.
+    pub fn scrub<'a>(&self, product: &str, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.rules {
+            if !rule.products.is_empty() && !rule.products.iter().any(|name| name == product) {
+                continue;
+            }
+            let scrubbed = match rule.pattern.replace_all(&text, rule.replacement.as_str()) {
+                Cow::Owned(scrubbed) => scrubbed,
+                Cow::Borrowed(_) => continue,
+            };
+            text = Cow::Owned(scrubbed);
+        }
+        text
+    }
+
+    /// Returns whether an annotation may be stored.
+    pub fn keeps(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        (self.allowed.is_empty() || self.allowed.contains(&key)) && !self.blocked.contains(&key)
+    }
+
+    /// Drops the annotations that may not be stored and scrubs the values of the others.
+    pub fn annotations(
+        &self,
+        product: &str,
+        annotations: BTreeMap<String, String>,
+    ) -> BTreeMap<String, String> {
+        annotations
+            .into_iter()
+            .filter(|(key, _)| self.keeps(key))
+            .map(|(key, value)| {
+                let value = self.scrub(product, &value).into_owned();
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Scrubs the strings of a processed report, except its provenance.
+    pub fn report(&self, product: &str, report: &mut Value) {
+        match report.as_object_mut() {
+            Some(fields) => fields
+                .iter_mut()
+                .filter(|(key, _)| *key != "provenance")
+                .for_each(|(_, value)| self.value(product, value)),
+            None => self.value(product, report),
+        }
+    }
+
+    fn value(&self, product: &str, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                let scrubbed = match self.scrub(product, text) {
+                    Cow::Owned(scrubbed) => scrubbed,
+                    Cow::Borrowed(_) => return,
+                };
+                *text = scrubbed;
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.value(product, item)),
+            Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|field| self.value(product, field)),
+            _ => (),
+        }
+    }
+}
+
+pub fn scrubber() -> &'static Scrubber {
+    static SCRUBBER: OnceLock<Scrubber> = OnceLock::new();
+    SCRUBBER.get_or_init(|| Scrubber::new(&settings().minidumps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app::settings::{Minidumps, ScrubRule};
+    use serde_json::json;
+
+    #[test]
+    fn test_default_rules() {
+        let scrubber = Scrubber::new(&Minidumps::default());
+        assert_eq!(
+            scrubber.scrub("Workrave", "crash reported by jane.doe@example.com"),
+            "crash reported by [email]"
+        );
+        assert_eq!(
+            scrubber.scrub(
+                "Workrave",
+                r"C:\Users\jane\AppData\Local\Workrave\workrave.exe"
+            ),
+            r"C:\Users\[user]\AppData\Local\Workrave\workrave.exe"
+        );
+        assert_eq!(
+            scrubber.scrub("Workrave", "/home/jane/.config/workrave"),
+            "/home/[user]/.config/workrave"
+        );
+        assert!(matches!(
+            scrubber.scrub("Workrave", "/usr/lib/libc.so.6"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_rules_per_product() {
+        let scrubber = Scrubber::new(&Minidumps {
+            scrub_rules: vec![
+                ScrubRule {
+                    pattern: "serial=[0-9]+".to_owned(),
+                    replacement: "serial=[scrubbed]".to_owned(),
+                    products: vec!["Workrave".to_owned()],
+                },
+                ScrubRule {
+                    pattern: "(".to_owned(),
+                    replacement: "".to_owned(),
+                    products: vec![],
+                },
+            ],
+            allowed_annotations: vec![],
+            blocked_annotations: vec!["Email".to_owned()],
+        });
+        assert_eq!(
+            scrubber.scrub("Workrave", "serial=1234"),
+            "serial=[scrubbed]"
+        );
+        assert_eq!(scrubber.scrub("Scroom", "serial=1234"), "serial=1234");
+
+        let annotations = BTreeMap::from([
+            ("email".to_owned(), "jane@example.com".to_owned()),
+            ("options".to_owned(), "serial=42".to_owned()),
+        ]);
+        assert_eq!(
+            scrubber.annotations("Workrave", annotations),
+            BTreeMap::from([("options".to_owned(), "serial=[scrubbed]".to_owned())])
+        );
+
+        let mut report = json!({
+            "modules": [{ "filename": "serial=1" }],
+            "provenance": { "credential": "serial=2" },
+        });
+        scrubber.report("Workrave", &mut report);
+        assert_eq!(report["modules"][0]["filename"], "serial=[scrubbed]");
+        assert_eq!(report["provenance"]["credential"], "serial=2");
+    }
+
+    #[test]
+    fn test_allowed_annotations() {
+        let scrubber = Scrubber::new(&Minidumps {
+            scrub_rules: vec![],
+            allowed_annotations: vec!["Channel".to_owned(), "version".to_owned()],
+            blocked_annotations: vec![],
+        });
+        assert!(scrubber.keeps("channel"));
+        assert!(scrubber.keeps("Version"));
+        assert!(!scrubber.keeps("user"));
+    }
+}
@@ -0,0 +1,108 @@
+use app::build_id::{normalize_debug_file, normalize_debug_id};
+use minidump::{Minidump, MinidumpModuleList, Module};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use super::error::UtilsError;
+use crate::model::symbols::symbols_file;
+use crate::settings;
+
+// Walking the stacks of a minidump is expensive, and gives the same result as long as the same
+// symbols are available, e.g. when a crash is reprocessed or the same minidump is uploaded for
+// another product. The output is cached below `stackwalk-cache/` in the base path, keyed by the
+// hash of the minidump and of the symbol files that are present for its modules, so uploading
+// symbols for any of its modules leads to a new entry.
+
+const CACHE_DIR: &str = "stackwalk-cache";
+
+pub fn cache_dir() -> PathBuf {
+    Path::new(&settings().server.base_path).join(CACHE_DIR)
+}
+
+/// Returns the modules of the minidump that have a symbol file in `dir`, as
+/// `module_id/build_id`, sorted.
+pub fn available_symbols<'a, T>(dump: &Minidump<'a, T>, dir: &Path) -> Vec<String>
+where
+    T: Deref<Target = [u8]> + 'a,
+{
+    let Ok(modules) = dump.get_stream::<MinidumpModuleList>() else {
+        return vec![];
+    };
+    let mut symbols: Vec<String> = modules
+        .iter()
+        .filter_map(|module| {
+            let module_id = normalize_debug_file(&module.debug_file()?);
+            let build_id = normalize_debug_id(&module.debug_identifier()?.breakpad().to_string())?;
+            symbols_file(dir, &module_id, &build_id)
+                .exists()
+                .then(|| format!("{}/{}", module_id, build_id))
+        })
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+/// Returns the cache key of a minidump, given the SHA-256 hash of its content and its
+/// available symbols.
+pub fn key(minidump_hash: &[u8], symbols: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(minidump_hash);
+    for symbol in symbols {
+        hasher.update(b"\n");
+        hasher.update(symbol.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn entry(dir: &Path, key: &str) -> PathBuf {
+    dir.join(&key[..2]).join(format!("{}.json", key))
+}
+
+/// Returns the cached stackwalk output, if any.
+pub async fn get(dir: &Path, key: &str) -> Option<Value> {
+    let content = tokio::fs::read(entry(dir, key)).await.ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Stores the stackwalk output. The entry is written next to its final location and then
+/// renamed, so that it is never read partially.
+pub async fn put(dir: &Path, key: &str, output: &Value) -> Result<(), UtilsError> {
+    let path = entry(dir, key);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension(format!("{}.partial", uuid::Uuid::new_v4()));
+    tokio::fs::write(&partial, output.to_string()).await?;
+    if let Err(e) = tokio::fs::rename(&partial, &path).await {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        let minidump_hash = Sha256::digest(b"MDMP");
+
+        let without_symbols = key(&minidump_hash, &[]);
+        let with_symbols = key(&minidump_hash, &["app.pdb/ABCDEF01234567891".to_owned()]);
+        assert_ne!(without_symbols, with_symbols);
+        assert_eq!(without_symbols, key(&minidump_hash, &[]));
+
+        assert_eq!(get(&dir, &with_symbols).await, None);
+        let output = serde_json::json!({ "status": "OK", "threads": [] });
+        put(&dir, &with_symbols, &output).await.unwrap();
+        assert_eq!(get(&dir, &with_symbols).await, Some(output));
+        assert_eq!(get(&dir, &without_symbols).await, None);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use super::error::UtilsError;
+use crate::settings;
+
+// Files are stored below `server.base_path`, at a location given by a template from the
+// `storage` settings, e.g. `minidumps/{product}/{yyyy}/{mm}/{uuid}`. Values are sanitized so
+// that they cannot add path components, and templates that leave the base path are rejected.
+
+pub struct StoragePath {
+    values: HashMap<&'static str, String>,
+}
+
+impl StoragePath {
+    pub fn new(product: &str, filename: &str) -> Self {
+        Self::at(product, filename, Utc::now().naive_utc())
+    }
+
+    pub fn at(product: &str, filename: &str, now: NaiveDateTime) -> Self {
+        let values = HashMap::from([
+            ("product", sanitize(product)),
+            ("filename", sanitize(filename)),
+            ("uuid", uuid::Uuid::new_v4().to_string()),
+            ("yyyy", now.format("%Y").to_string()),
+            ("mm", now.format("%m").to_string()),
+            ("dd", now.format("%d").to_string()),
+        ]);
+        Self { values }
+    }
+
+    pub fn with_crash(mut self, crash: uuid::Uuid) -> Self {
+        self.values.insert("crash", crash.to_string());
+        self
+    }
+
+    /// Expands the template to a path relative to the base path.
+    pub fn render(&self, template: &str) -> Result<PathBuf, UtilsError> {
+        let invalid = || UtilsError::InvalidTemplate(template.to_owned());
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(invalid)? + start;
+            let value = self
+                .values
+                .get(rest[start + 1..end].trim())
+                .ok_or_else(invalid)?;
+            result.push_str(&rest[..start]);
+            result.push_str(value);
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+
+        let path = PathBuf::from(result);
+        let relative = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !relative || path.as_os_str().is_empty() {
+            return Err(invalid());
+        }
+        Ok(path)
+    }
+
+    /// Returns the full path for the template and creates its directory.
+    pub async fn create(&self, template: &str) -> Result<PathBuf, UtilsError> {
+        let path = Path::new(&settings().server.base_path).join(self.render(template)?);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        Ok(path)
+    }
+}
+
+/// Replaces characters that would add path components.
+fn sanitize(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '\0' => '_',
+            c => c,
+        })
+        .collect();
+    match value.as_str() {
+        "" | "." | ".." => "_".to_owned(),
+        _ => value,
+    }
+}
+
+/// Removes the directories of a deleted file that are now empty, up to the top level directory
+/// below the base path, e.g. `minidumps`.
+pub async fn remove_empty_parents(path: &Path) {
+    let base_path = Path::new(&settings().server.base_path);
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if !current.starts_with(base_path) || current.parent() == Some(base_path) {
+            break;
+        }
+        if tokio::fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let now =
+            NaiveDateTime::parse_from_str("2024-03-07 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let crash = uuid::Uuid::new_v4();
+        let path = StoragePath::at("Workrave", "crash.dmp", now).with_crash(crash);
+
+        assert_eq!(
+            path.render("minidumps/{product}/{yyyy}/{mm}/{dd}/{filename}")
+                .unwrap(),
+            PathBuf::from("minidumps/Workrave/2024/03/07/crash.dmp")
+        );
+        assert_eq!(
+            path.render("attachments/{crash}/{filename}").unwrap(),
+            PathBuf::from(format!("attachments/{}/crash.dmp", crash))
+        );
+        let uuid = path.render("{uuid}").unwrap();
+        assert!(uuid::Uuid::parse_str(uuid.to_str().unwrap()).is_ok());
+
+        assert!(path.render("minidumps/{unknown}").is_err());
+        assert!(path.render("minidumps/{product").is_err());
+        assert!(path.render("../minidumps/{filename}").is_err());
+        assert!(path.render("/minidumps/{filename}").is_err());
+        assert!(path.render("").is_err());
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let now = Utc::now().naive_utc();
+        let path = StoragePath::at("..", "../../etc/passwd", now);
+        assert_eq!(
+            path.render("minidumps/{product}/{filename}").unwrap(),
+            PathBuf::from("minidumps/_/.._.._etc_passwd")
+        );
+    }
+}
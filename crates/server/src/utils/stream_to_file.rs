@@ -2,26 +2,73 @@ use axum::body::Bytes;
 use axum::BoxError;
 use futures::prelude::*;
 use tokio::fs::File;
-use tokio::io::{self, BufWriter};
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio_util::io::StreamReader;
 
 use super::error::UtilsError;
 
-pub async fn stream_to_file<S, E>(path: &std::path::PathBuf, stream: S) -> Result<(), UtilsError>
+/// Writes the stream to a file. Fails when the stream is larger than `max_size` bytes, `None` for
+/// no limit, and removes the file. Returns the size of the file.
+pub async fn stream_to_file<S, E>(
+    path: &std::path::PathBuf,
+    stream: S,
+    max_size: Option<u64>,
+) -> Result<u64, UtilsError>
 where
     S: Stream<Item = Result<Bytes, E>>,
     E: Into<BoxError>,
 {
-    async {
+    let written = async {
         let body_with_io_error = stream.map_err(|err| io::Error::new(io::ErrorKind::Other, err));
         let body_reader = StreamReader::new(body_with_io_error);
         futures::pin_mut!(body_reader);
 
         let mut file = BufWriter::new(File::create(path).await?);
-        tokio::io::copy(&mut body_reader, &mut file).await?;
-
-        Ok::<(), UtilsError>(())
+        let written = match max_size {
+            Some(max_size) => {
+                tokio::io::copy(&mut body_reader.take(max_size + 1), &mut file).await?
+            }
+            None => tokio::io::copy(&mut body_reader, &mut file).await?,
+        };
+        file.flush().await?;
+        Ok::<u64, io::Error>(written)
     }
     .await
-    .map_err(|_err| (UtilsError::Failure))
+    .map_err(|_err| UtilsError::Failure)?;
+
+    match max_size {
+        Some(max_size) if written > max_size => {
+            let _ = tokio::fs::remove_file(path).await;
+            Err(UtilsError::UploadTooLarge(max_size))
+        }
+        _ => Ok(written),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_to_file() {
+        let dir = std::env::temp_dir().join(format!("guardrail-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("attachment.log");
+        let chunks = || {
+            stream::iter(vec![
+                Ok::<_, io::Error>(Bytes::from_static(b"0123456789")),
+                Ok(Bytes::from_static(b"abcdef")),
+            ])
+        };
+
+        assert_eq!(stream_to_file(&path, chunks(), Some(16)).await.unwrap(), 16);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"0123456789abcdef");
+        assert_eq!(stream_to_file(&path, chunks(), None).await.unwrap(), 16);
+
+        let result = stream_to_file(&path, chunks(), Some(15)).await;
+        assert!(matches!(result, Err(UtilsError::UploadTooLarge(15))));
+        assert!(!path.exists());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::io::BufRead;
+
+// Symbol files are only parsed when a minidump is processed, so a file with a valid header but
+// a corrupt body would only break stack walking later on. Uploads are checked for well formed
+// records; the first lines are all checked, after that a sample. Files that look suspicious are
+// quarantined instead of rejected, so that an admin can review them.
+
+/// Number of lines that are all checked.
+const CHECKED_LINES: usize = 10_000;
+/// After the first lines, one in this many lines is checked.
+const SAMPLE_INTERVAL: usize = 100;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SymbolCheck {
+    pub lines: usize,
+    /// Number of malformed lines among the checked ones.
+    pub malformed: usize,
+    pub first_malformed: Option<usize>,
+    /// Number of FUNC records at an address that already has one, ignoring records marked as
+    /// having multiple names.
+    pub duplicate_functions: usize,
+    pub first_duplicate: Option<u64>,
+    /// Number of FUNC and PUBLIC records.
+    pub functions: usize,
+}
+
+impl SymbolCheck {
+    /// Returns why the file should be quarantined, or `None` if it looks sound.
+    pub fn quarantine_reason(&self) -> Option<String> {
+        let mut reasons = vec![];
+        if let Some(line) = self.first_malformed {
+            reasons.push(format!(
+                "{} malformed line(s), first at line {}",
+                self.malformed, line
+            ));
+        }
+        if let Some(address) = self.first_duplicate {
+            reasons.push(format!(
+                "{} duplicate FUNC record(s), first at {:#x}",
+                self.duplicate_functions, address
+            ));
+        }
+        if self.functions == 0 {
+            reasons.push("no FUNC or PUBLIC records".to_owned());
+        }
+        (!reasons.is_empty()).then(|| reasons.join("; "))
+    }
+}
+
+fn is_hex(field: &str) -> bool {
+    !field.is_empty() && field.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_dec(field: &str) -> bool {
+    !field.is_empty() && field.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Skips the optional `m` marker of FUNC and PUBLIC records.
+fn skip_multiple<'a>(fields: &[&'a str]) -> (bool, Vec<&'a str>) {
+    match fields.first() {
+        Some(&"m") => (true, fields[1..].to_vec()),
+        _ => (false, fields.to_vec()),
+    }
+}
+
+/// Checks the syntax of a single record of a Breakpad symbol file.
+fn is_valid_record(line: &str) -> bool {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let Some((&keyword, rest)) = fields.split_first() else {
+        return true;
+    };
+    match keyword {
+        "MODULE" => rest.len() >= 4,
+        "INFO" => true,
+        "FILE" => rest.len() >= 2 && is_dec(rest[0]),
+        "FUNC" => {
+            let (_, rest) = skip_multiple(rest);
+            rest.len() >= 3 && rest[..3].iter().all(|field| is_hex(field))
+        }
+        "PUBLIC" => {
+            let (_, rest) = skip_multiple(rest);
+            rest.len() >= 2 && rest[..2].iter().all(|field| is_hex(field))
+        }
+        "STACK" => match rest.first() {
+            Some(&"WIN") => rest.len() >= 12,
+            Some(&"CFI") => rest.len() >= 2,
+            _ => false,
+        },
+        "INLINE_ORIGIN" => rest.len() >= 2 && is_dec(rest[0]),
+        "INLINE" => {
+            rest.len() >= 5
+                && rest[..3].iter().all(|field| is_dec(field))
+                && rest[3..].iter().all(|field| is_hex(field))
+        }
+        // Line records: address, size, line number and file number.
+        _ => fields.len() == 4 && is_hex(fields[0]) && is_hex(fields[1]) && is_dec(fields[2]),
+    }
+}
+
+/// Checks the records of a symbol file, see `SymbolCheck`.
+pub fn check_symbols<R: BufRead>(reader: R) -> std::io::Result<SymbolCheck> {
+    let mut check = SymbolCheck::default();
+    let mut functions = HashSet::new();
+
+    for (index, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let number = index + 1;
+        check.lines = number;
+
+        let Ok(line) = std::str::from_utf8(&line) else {
+            check.malformed += 1;
+            check.first_malformed.get_or_insert(number);
+            continue;
+        };
+        let line = line.trim_end_matches('\r');
+
+        if line.starts_with("FUNC ") || line.starts_with("PUBLIC ") {
+            check.functions += 1;
+        }
+        if let Some(rest) = line.strip_prefix("FUNC ") {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            let (multiple, fields) = skip_multiple(&fields);
+            let address = fields
+                .first()
+                .and_then(|address| u64::from_str_radix(address, 16).ok());
+            if let (false, Some(address)) = (multiple, address) {
+                if !functions.insert(address) {
+                    check.duplicate_functions += 1;
+                    check.first_duplicate.get_or_insert(address);
+                }
+            }
+        }
+
+        let sampled = index < CHECKED_LINES || index % SAMPLE_INTERVAL == 0;
+        if sampled && !is_valid_record(line) {
+            check.malformed += 1;
+            check.first_malformed.get_or_insert(number);
+        }
+    }
+    Ok(check)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMBOLS: &str = "MODULE windows x86_64 ABCDEF0123456789ABCDEF01234567891 workrave.pdb
+INFO CODE_ID 5F3A2B1C4000 workrave.exe
+FILE 0 c:\\build\\workrave\\timer.cc
+INLINE_ORIGIN 0 Timer::elapsed()
+FUNC 1000 2a 0 Timer::tick()
+INLINE 0 42 0 0 1010 8
+1000 10 41 0
+1010 1a 42 0
+FUNC m 2000 10 0 Timer::reset()
+FUNC m 2000 10 0 Timer::clear()
+PUBLIC 3000 0 main
+STACK CFI INIT 1000 2a .cfa: $rsp 8 + .ra: .cfa -8 + ^
+STACK CFI 1004 .cfa: $rsp 16 +
+STACK WIN 4 1000 2a 4 0 0 0 0 0 1 $T0 $ebp = $eip $T0 4 + ^ =
+";
+
+    #[test]
+    fn test_check_symbols() {
+        let check = check_symbols(SYMBOLS.as_bytes()).unwrap();
+        assert_eq!(check.lines, 14);
+        assert_eq!(check.functions, 4);
+        assert_eq!(check.quarantine_reason(), None);
+
+        let corrupt = format!(
+            "{}FUNC 1000 8 0 Timer::start()\n1000 zz 1 0\n\u{0}\u{0}\u{0}\n",
+            SYMBOLS
+        );
+        let check = check_symbols(corrupt.as_bytes()).unwrap();
+        assert_eq!(check.malformed, 2);
+        assert_eq!(check.first_malformed, Some(16));
+        assert_eq!(check.duplicate_functions, 1);
+        assert_eq!(
+            check.quarantine_reason().unwrap(),
+            "2 malformed line(s), first at line 16; 1 duplicate FUNC record(s), first at 0x1000"
+        );
+
+        let header_only = "MODULE windows x86_64 ABCDEF0123456789ABCDEF01234567891 workrave.pdb\n";
+        let check = check_symbols(header_only.as_bytes()).unwrap();
+        assert_eq!(
+            check.quarantine_reason().as_deref(),
+            Some("no FUNC or PUBLIC records")
+        );
+    }
+}